@@ -0,0 +1,160 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use rlp::RlpStream;
+use serde_json::json;
+use std::str::FromStr;
+use web3::ethabi::{self, Token};
+use web3::signing::{keccak256, Key, SecretKey, SecretKeyRef};
+use web3::types::{Address, U256};
+
+use taz::modules::arbitrage::{self, ArbitrageInputs};
+use taz::modules::execution::{self, FlashloanPlan, SwapOrder};
+use taz::modules::mempool_recorder;
+use taz::modules::sandwich;
+use taz::modules::sequencer_feed;
+use taz::modules::venues::{self, Venue, VenueVersion};
+
+fn address(byte: u8) -> Address {
+    Address::from([byte; 20])
+}
+
+// Minimal big-endian bytes, as RLP encodes integers
+fn be(value: U256) -> Vec<u8> {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    bytes[32 - value.bits().div_ceil(8)..].to_vec()
+}
+
+fn weth() -> Address {
+    Address::from_str("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2").unwrap()
+}
+
+fn usdc() -> Address {
+    Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").unwrap()
+}
+
+// swapExactTokensForTokens through a two-hop path, as a victim would send it
+fn v2_swap_calldata() -> Vec<u8> {
+    let selector = keccak256(b"swapExactTokensForTokens(uint256,uint256,address[],address,uint256)");
+    let args = ethabi::encode(&[
+        Token::Uint(U256::exp10(18)),
+        Token::Uint(U256::from(3_000_000_000u64)),
+        Token::Array(vec![Token::Address(weth()), Token::Address(usdc()), Token::Address(address(7))]),
+        Token::Address(address(9)),
+        Token::Uint(U256::from(1_700_000_000u64)),
+    ]);
+    [selector[..4].to_vec(), args].concat()
+}
+
+// A signed EIP-1559 transaction carrying the swap, as the sequencer feed delivers it
+fn signed_eip1559_tx() -> Vec<u8> {
+    let key = SecretKey::from_str("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap();
+    let input = v2_swap_calldata();
+    let fields = |stream: &mut RlpStream| {
+        stream
+            .append(&42161u64)
+            .append(&7u64)
+            .append(&1_000_000_000u64)
+            .append(&20_000_000_000u64)
+            .append(&300_000u64)
+            .append(&address(1).as_bytes().to_vec())
+            .append(&0u64)
+            .append(&input);
+        stream.begin_list(0);
+    };
+
+    let mut unsigned = RlpStream::new_list(9);
+    fields(&mut unsigned);
+    let signing_hash = keccak256(&[vec![0x02], unsigned.out().to_vec()].concat());
+    let signature = SecretKeyRef::new(&key).sign(&signing_hash, None).unwrap();
+
+    let mut signed = RlpStream::new_list(12);
+    fields(&mut signed);
+    // Typed transactions carry the bare y parity rather than 27/28
+    signed
+        .append(&(signature.v % 27))
+        .append(&be(U256::from_big_endian(signature.r.as_bytes())))
+        .append(&be(U256::from_big_endian(signature.s.as_bytes())));
+    [vec![0x02], signed.out().to_vec()].concat()
+}
+
+fn quote_math(c: &mut Criterion) {
+    let reserve_in = U256::from_dec_str("12000000000000000000000").unwrap();
+    let reserve_out = U256::from_dec_str("36000000000000").unwrap();
+    c.bench_function("quote/v2_amount_out", |b| {
+        b.iter(|| venues::v2_amount_out(black_box(U256::exp10(18)), black_box(reserve_in), black_box(reserve_out), 3000))
+    });
+
+    let sqrt_price_x96 = U256::from_dec_str("1771595571142957166518320255467520").unwrap();
+    c.bench_function("quote/v3_spot_amount_out", |b| {
+        b.iter(|| venues::v3_spot_amount_out(black_box(U256::exp10(18)), black_box(sqrt_price_x96), true, 500))
+    });
+
+    let venue = Venue { dex: String::from("uniswap"), version: VenueVersion::V2, pool: address(3), router: address(4), quoter: None, quoter_v2: false, v2_fee: 3000, router_fee: 0 };
+    let state = json!({ "reserve0": reserve_out.to_string(), "reserve1": reserve_in.to_string() });
+    c.bench_function("quote/from_cached_state", |b| {
+        b.iter(|| venue.quote_from_state(black_box(&state), usdc(), weth(), U256::from(3_000_000_000u64)))
+    });
+}
+
+fn calldata_decoding(c: &mut Criterion) {
+    let calldata = v2_swap_calldata();
+    c.bench_function("decode/victim_pair", |b| b.iter(|| sandwich::victim_pair(black_box(&calldata))));
+    c.bench_function("decode/intent", |b| b.iter(|| mempool_recorder::decode_intent(black_box(&calldata))));
+
+    let raw = signed_eip1559_tx();
+    c.bench_function("decode/sequencer_signed_tx", |b| b.iter(|| sequencer_feed::decode_signed_tx(black_box(&raw))));
+}
+
+fn opportunity_evaluation(c: &mut Criterion) {
+    let inputs = ArbitrageInputs {
+        token_in: weth(),
+        token_out: usdc(),
+        amount_in: U256::exp10(18),
+        buy_venue: String::from("uniswap v3 0.05bps"),
+        quoted_out: U256::from(3_012_000_000u64),
+        buy_factor: Some(0.998),
+        sell_venue: String::from("sushiswap v2"),
+        quoted_back: U256::from_dec_str("1004000000000000000").unwrap(),
+        sell_factor: Some(0.997),
+        gas_fees: U256::from(300_000u64),
+        min_spread_bps: 0.0,
+    };
+    c.bench_function("evaluate/arbitrage_decide", |b| b.iter(|| arbitrage::decide(black_box(&inputs))));
+}
+
+fn bundle_construction(c: &mut Criterion) {
+    c.bench_function("build/approve_and_swap_v2", |b| {
+        b.iter(|| {
+            execution::approve_and_swap_calls(address(4), vec![weth(), usdc()], U256::exp10(18), U256::from(2_990_000_000u64), address(9), U256::from(1_700_000_000u64))
+        })
+    });
+    c.bench_function("build/approve_and_swap_v3", |b| {
+        b.iter(|| {
+            let order = SwapOrder {
+                token_in: weth(),
+                token_out: usdc(),
+                amount_in: U256::exp10(18),
+                amount_out_min: U256::from(2_990_000_000u64),
+                recipient: address(9),
+                deadline: U256::from(1_700_000_000u64),
+            };
+            execution::approve_and_swap_v3_calls(address(5), 500, &order)
+        })
+    });
+    c.bench_function("build/flashloan_plan_legs", |b| {
+        b.iter_batched(
+            || execution::approve_and_swap_calls(address(4), vec![weth(), usdc()], U256::exp10(18), U256::zero(), address(9), U256::from(1_700_000_000u64)).unwrap(),
+            |calls| {
+                let mut plan = FlashloanPlan::new(address(8), weth(), U256::exp10(20), 5);
+                for call in calls {
+                    plan.push(call);
+                }
+                plan
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(hot_paths, quote_math, calldata_decoding, opportunity_evaluation, bundle_construction);
+criterion_main!(hot_paths);
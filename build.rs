@@ -0,0 +1,6 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Generate the gRPC service and messages from proto/taz.proto, with the bundled protoc
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::compile_protos("proto/taz.proto")?;
+    Ok(())
+}
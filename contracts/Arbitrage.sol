@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MIT
 pragma solidity ^0.5.11;
+pragma experimental ABIEncoderV2;
 
 import "@uniswap/v2-periphery/contracts/interfaces/IUniswapV2Router02.sol";
 import "@openzeppelin/contracts/token/ERC20/IERC20.sol";
@@ -12,6 +13,16 @@ contract Arbitrage is Ownable, ReentrancyGuard {
     // Event to log arbitrage execution
     event ArbitrageExecuted(address tokenIn, address tokenOut, uint256 profit);
 
+    // Event to log a batched multi-leg trade
+    event BatchExecuted(address profitToken, uint256 calls, uint256 profit);
+
+    // One leg (approve, swap, ...) of a batched trade
+    struct Call {
+        address to;
+        uint256 value;
+        bytes data;
+    }
+
     constructor(address _uniswapRouter, address _sushiswapRouter) public {
         uniswapRouter = IUniswapV2Router02(_uniswapRouter);
         sushiswapRouter = IUniswapV2Router02(_sushiswapRouter);
@@ -79,6 +90,35 @@ contract Arbitrage is Ownable, ReentrancyGuard {
 
         return true;
     }
+    // Run every leg of a multi-leg trade in this one transaction. Any failing call, or a profitToken
+    // balance that doesn't end at least minProfit above where it started, reverts the whole cycle
+    function executeBatch(Call[] memory calls, address profitToken, uint256 minProfit)
+        public
+        payable
+        onlyOwner
+        nonReentrant
+        returns (uint256)
+    {
+        uint256 balanceBefore = IERC20(profitToken).balanceOf(address(this));
+
+        for (uint256 i = 0; i < calls.length; i++) {
+            (bool success, bytes memory result) = calls[i].to.call.value(calls[i].value)(calls[i].data);
+            if (!success) {
+                // Bubble up the failing leg's revert reason
+                assembly {
+                    revert(add(result, 32), mload(result))
+                }
+            }
+        }
+
+        uint256 balanceAfter = IERC20(profitToken).balanceOf(address(this));
+        require(balanceAfter >= balanceBefore + minProfit, "Batch not profitable");
+
+        uint256 profit = balanceAfter - balanceBefore;
+        emit BatchExecuted(profitToken, calls.length, profit);
+        return profit;
+    }
+
     // Helper function to calculate expected Uniswap price
     function getExpectedPriceFromUniswap(address tokenIn, address tokenOut, uint256 amountIn)
         internal
@@ -9,6 +9,13 @@ import "@openzeppelin/contracts/security/ReentrancyGuard.sol";
 contract FlashLoan is Ownable, ReentrancyGuard {
     ILendingPool public lendingPool;
 
+    // A strategy call executed while the loan is outstanding
+    struct Call {
+        address to;
+        uint256 value;
+        bytes data;
+    }
+
     constructor(address _lendingPool) {
         lendingPool = ILendingPool(_lendingPool);
     }
@@ -49,15 +56,26 @@ contract FlashLoan is Ownable, ReentrancyGuard {
         uint256 premium,
         address initiator,
         bytes calldata params
-    ) external nonReentrant returns (bool) {
-        // Parse the strategy from params
+    ) external returns (bool) {
+        // Only the lending pool may call back, and only for loans this contract initiated
+        require(msg.sender == address(lendingPool), "Caller is not the lending pool");
+        require(initiator == address(this), "Flash loan not initiated by this contract");
+
+        // Parse the strategy from the first word of params
         bytes32 strategy;
         assembly {
-            strategy := calldataload(add(params.offset, 0x20))
+            strategy := calldataload(params.offset)
         }
 
         // Call the appropriate strategy based on the parsed strategy type
-        if (strategy == keccak256("arbitrage")) {
+        if (strategy == keccak256("calls")) {
+            // Execute the bot's planned call sequence; any failing call reverts the whole loan
+            (, Call[] memory calls) = abi.decode(params, (bytes32, Call[]));
+            for (uint256 i = 0; i < calls.length; i++) {
+                (bool success, ) = calls[i].to.call{value: calls[i].value}(calls[i].data);
+                require(success, "Planned call failed");
+            }
+        } else if (strategy == keccak256("arbitrage")) {
             // Execute arbitrage logic
             // Add your arbitrage logic here
         } else if (strategy == keccak256("liquidation")) {
@@ -0,0 +1,45 @@
+#!/bin/bash
+
+# Run the hot-path benchmarks against a committed baseline.
+#   scripts/bench.sh save [name]     run and store the results as benches/baselines/<name>
+#   scripts/bench.sh compare [name]  run and report changes against benches/baselines/<name>
+# The baseline name defaults to "main".
+
+set -e
+
+mode="${1:-compare}"
+name="${2:-main}"
+baseline_dir="benches/baselines/$name"
+criterion_dir="target/criterion"
+
+if ! command -v cargo &> /dev/null; then
+  echo "Cargo could not be found. Please install Rust and Cargo."
+  exit 1
+fi
+
+case "$mode" in
+  save)
+    cargo bench --bench hot_paths -- --save-baseline "$name"
+    rm -rf "$baseline_dir"
+    # Keep only the estimates criterion compares against, not the HTML reports
+    find "$criterion_dir" -path "*/$name/*" -name "*.json" | while read -r file; do
+      relative="${file#$criterion_dir/}"
+      mkdir -p "$baseline_dir/$(dirname "$relative")"
+      cp "$file" "$baseline_dir/$relative"
+    done
+    echo "Baseline saved to $baseline_dir"
+    ;;
+  compare)
+    if [ ! -d "$baseline_dir" ]; then
+      echo "No baseline at $baseline_dir. Run 'scripts/bench.sh save $name' first."
+      exit 1
+    fi
+    mkdir -p "$criterion_dir"
+    cp -r "$baseline_dir"/. "$criterion_dir"/
+    cargo bench --bench hot_paths -- --baseline "$name"
+    ;;
+  *)
+    echo "Usage: scripts/bench.sh [save|compare] [baseline]"
+    exit 1
+    ;;
+esac
@@ -18,13 +18,28 @@ function updateProfit(amount) {
     document.getElementById('profit-amount').innerText = '$' + amount.toFixed(2);
 }
 
+// Build request headers with the operator's API token
+function authHeaders() {
+    const headers = { 'Content-Type': 'application/json' };
+    const token = localStorage.getItem('apiToken');
+    if (token) {
+        headers['Authorization'] = `Bearer ${token}`;
+    }
+    return headers;
+}
+
+// Save the API token entered by the operator
+function saveApiToken() {
+    localStorage.setItem('apiToken', document.getElementById('api-token').value);
+    updateStatus('API token saved');
+    connectLiveFeed();
+}
+
 // Generic function to send requests to the backend
 function runStrategy(strategy, config) {
     fetch(`/run-${strategy}`, {
         method: 'POST',
-        headers: {
-            'Content-Type': 'application/json'
-        },
+        headers: authHeaders(),
         body: JSON.stringify(config)
     })
     .then(response => response.json())
@@ -109,6 +124,45 @@ function runSandwich() {
     runStrategy('sandwich', config);
 }
 
+// Fetch the equity curve and draw it with the current drawdown
+function refreshEquity() {
+    fetch('/api/equity', { headers: authHeaders() })
+        .then(response => response.json())
+        .then(data => {
+            if (!data || !data.points || data.points.length === 0) {
+                return;
+            }
+            const latest = data.points[data.points.length - 1];
+            document.getElementById('equity-usd').innerText = '$' + latest.total_usd.toFixed(2);
+            document.getElementById('equity-eth').innerText = latest.total_eth.toFixed(4);
+            document.getElementById('equity-drawdown').innerText =
+                (data.drawdown * 100).toFixed(2) + '% (max ' + (data.max_drawdown * 100).toFixed(2) + '%)';
+            drawEquityChart(data.points.map(point => point.total_usd));
+        })
+        .catch(error => console.error('Error fetching equity:', error));
+}
+
+function drawEquityChart(values) {
+    const canvas = document.getElementById('equity-chart');
+    const context = canvas.getContext('2d');
+    const min = Math.min(...values);
+    const max = Math.max(...values);
+    const range = max - min || 1;
+
+    context.clearRect(0, 0, canvas.width, canvas.height);
+    context.beginPath();
+    values.forEach((value, index) => {
+        const x = (index / Math.max(values.length - 1, 1)) * canvas.width;
+        const y = canvas.height - ((value - min) / range) * canvas.height;
+        if (index === 0) {
+            context.moveTo(x, y);
+        } else {
+            context.lineTo(x, y);
+        }
+    });
+    context.stroke();
+}
+
 // Run all strategies
 function runMultipleStrategies() {
     runArbitrage();
@@ -119,6 +173,51 @@ function runMultipleStrategies() {
     runSandwich();
 }
 
+// Fetch the projected funding runway
+function refreshRunway() {
+    fetch('/api/runway', { headers: authHeaders() })
+        .then(response => response.json())
+        .then(data => {
+            if (!data) {
+                return;
+            }
+            document.getElementById('runway-days').innerText =
+                data.runway_days === null ? 'no recent burn' : data.runway_days.toFixed(1) + ' days';
+            document.getElementById('runway-burn').innerText = (Number(data.daily_burn_wei) / 1e18).toFixed(4);
+        })
+        .catch(error => console.error('Error fetching runway:', error));
+}
+
+// Stream status and profit over the dashboard WebSocket. Browsers can't set headers on the
+// upgrade, so the token goes in the query string
+let liveFeed = null;
+let liveFeedPoll = null;
+
+function connectLiveFeed() {
+    if (liveFeed) {
+        liveFeed.onclose = null;
+        liveFeed.close();
+        clearInterval(liveFeedPoll);
+    }
+    const token = localStorage.getItem('apiToken');
+    if (!token) {
+        return;
+    }
+    const scheme = location.protocol === 'https:' ? 'wss' : 'ws';
+    const socket = new WebSocket(`${scheme}://${location.host}/ws?token=${encodeURIComponent(token)}`);
+    liveFeed = socket;
+
+    socket.onopen = () => {
+        socket.send('status');
+        liveFeedPoll = setInterval(() => socket.send('status'), 5000);
+    };
+    socket.onmessage = event => updateStatus(event.data);
+    socket.onclose = () => {
+        clearInterval(liveFeedPoll);
+        setTimeout(connectLiveFeed, 10000);
+    };
+}
+
 // Load configurations from local storage on page load
 window.onload = function() {
     const strategies = ['arbitrage', 'flashloan', 'frontrunning', 'hft', 'liquidation', 'sandwich'];
@@ -133,6 +232,12 @@ window.onload = function() {
             });
         }
     });
+
+    refreshRunway();
+    setInterval(refreshRunway, 60000);
+    refreshEquity();
+    setInterval(refreshEquity, 60000);
+    connectLiveFeed();
 };
 
 
@@ -0,0 +1,97 @@
+// Import modules for different strategies
+pub mod modules {
+    pub mod arbitrage;
+    pub mod flashloan;
+    pub mod frontrunning;
+    pub mod liquidation;
+    pub mod sandwich;
+    pub mod hft;
+    pub mod runtime;
+    pub mod soak;
+    pub mod execution;
+    pub mod account_abstraction;
+    pub mod safe;
+    pub mod inventory;
+    pub mod monitoring;
+    pub mod runway;
+    pub mod dashboard;
+    pub mod config_audit;
+    pub mod auth;
+    pub mod control;
+    pub mod ws_metrics;
+    pub mod scan_interval;
+    pub mod pair_discovery;
+    pub mod venues;
+    pub mod slippage;
+    pub mod jit;
+    pub mod gas_golf;
+    pub mod equity;
+    pub mod allocator;
+    pub mod postmortem;
+    pub mod mempool_recorder;
+    pub mod inclusion;
+    pub mod providers;
+    pub mod health_history;
+    pub mod replay;
+    pub mod gas_feed;
+    pub mod code_watch;
+    pub mod sanctions;
+    pub mod coordination;
+    pub mod feed;
+    pub mod events;
+    pub mod grpc_api;
+    pub mod state;
+    pub mod logging;
+    pub mod alerts;
+    pub mod templates;
+    pub mod explorer;
+    pub mod ens;
+    pub mod config_validator;
+    pub mod liquidation_report;
+    pub mod heatmap;
+    pub mod builders;
+    pub mod chains;
+    pub mod sequencer_feed;
+    pub mod fees;
+    pub mod checkpoint;
+    pub mod domain;
+    pub mod approvals;
+    pub mod permit;
+    pub mod pricing;
+    pub mod clustering;
+    pub mod rfq;
+    pub mod signals;
+    pub mod mempool_mode;
+    pub mod actions;
+    pub mod rewards;
+    pub mod config_drift;
+    pub mod simulation;
+    pub mod deadlines;
+    pub mod testnet;
+    pub mod size_buckets;
+    pub mod plan_graph;
+    pub mod spend_caps;
+    pub mod nonce_recovery;
+    pub mod lp_yield;
+    pub mod ramp;
+    pub mod reconcile;
+    pub mod config;
+    pub mod strategy;
+    pub mod signer;
+    pub mod relay;
+    pub mod liquidation_watch;
+    pub mod gas;
+    pub mod victim_blacklist;
+    pub mod alert_rules;
+    pub mod storage;
+    pub mod pair_class;
+    pub mod selector_check;
+    pub mod dex;
+    pub mod receipt_events;
+    pub mod sizing;
+    pub mod cancellation_risk;
+    pub mod path_finder;
+    pub mod funds_at_risk;
+    pub mod pool_state;
+    pub mod multicall;
+}
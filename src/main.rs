@@ -1,153 +1,326 @@
 use serde_json::Value;
-use std::fs;
-use web3::transports::Http;
-use web3::types::U256;
-use web3::Web3;
 use tokio::task;
 use log::{info, error};
 
-// Import modules for different strategies
-mod modules {
-    pub mod arbitrage;
-    pub mod flashloan;
-    pub mod frontrunning;
-    pub mod liquidation;
-    pub mod sandwich;
-    pub mod hft;
-}
+// Strategy and infrastructure modules live in the library crate so benches can reach them
+use taz::modules;
+use taz::modules::config::GlobalConfig;
 
-// Load global config file
-fn load_global_config() -> Value {
-    let config_path = "config/global_config.json";
-    let config_data = fs::read_to_string(config_path)
-        .expect("Unable to read global config file");
-    serde_json::from_str(&config_data).expect("Unable to parse global config file")
-}
+fn main() -> web3::Result<()> {
+    // `taz state export|import ...` moves persisted state between machines without starting the bot
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("state") {
+        modules::logging::init(&Value::Null);
+        return Ok(modules::state::run_state_command(&args[1..])?);
+    }
+    // `taz plans graph [--format dot|json]` prints recent multi-leg trade plans as token/leg graphs
+    if args.first().map(String::as_str) == Some("plans") {
+        modules::logging::init(&Value::Null);
+        return Ok(modules::plan_graph::run_plans_command(&args[1..])?);
+    }
+
+    // Load global configuration; a missing or mistyped field stops startup with the file and field named
+    let settings = GlobalConfig::load()?;
+    let global_config = settings.to_value();
+
+    // Logging with per-module levels that can be changed at runtime
+    modules::logging::init(&global_config);
+
+    // `taz liquidations scan --block N --dry-run` reports what the liquidation strategy would do at a block
+    if args.first().map(String::as_str) == Some("liquidations") {
+        let command = modules::liquidation_report::run_liquidations_command(global_config.clone(), args[1..].to_vec());
+        return Ok(modules::runtime::run(&global_config["runtime"], command)??);
+    }
+
+    // `taz faucet [--wait]` shows where to fund the wallet on a testnet preset and waits for the funds
+    if args.first().map(String::as_str) == Some("faucet") {
+        let command = modules::testnet::run_faucet_command(global_config.clone(), args[1..].to_vec());
+        return Ok(modules::runtime::run(&global_config["runtime"], command)??);
+    }
+
+    // `taz reconcile [--date YYYY-MM-DD]` diffs a day of on-chain history against the internal ledgers
+    if args.first().map(String::as_str) == Some("reconcile") {
+        let command = modules::reconcile::run_reconcile_command(global_config.clone(), args[1..].to_vec());
+        return Ok(modules::runtime::run(&global_config["runtime"], command)??);
+    }
+
+    // `taz run <strategy> [--confirm]` shows what one pass of a strategy puts at risk, then runs it
+    if args.first().map(String::as_str) == Some("run") {
+        let command = modules::funds_at_risk::run_trigger_command(global_config.clone(), args[1..].to_vec());
+        return Ok(modules::runtime::run(&global_config["runtime"], command)??);
+    }
 
-// Load individual strategy config based on global config
-fn load_strategy_config(strategy_name: &str) -> Value {
-    let global_config = load_global_config();
-    let strategy_path = global_config["strategies"][strategy_name]["config_path"]
-        .as_str()
-        .expect("Strategy config path not found");
-    let config_data = fs::read_to_string(strategy_path)
-        .expect("Unable to read strategy config file");
-    serde_json::from_str(&config_data).expect("Unable to parse strategy config file")
+    // Build the runtime from config (worker threads, blocking pool, optional io-uring)
+    modules::runtime::run(&global_config["runtime"], run_bot(settings))?
 }
 
-#[tokio::main]
-async fn main() -> web3::Result<()> {
-    // Load global configuration
-    let global_config = load_global_config();
-    let infura_project_id = global_config["infura_project_id"].as_str().unwrap();
-    let network = global_config["network"].as_str().unwrap();
-    let eth_node_url = format!("https://{}.infura.io/v3/{}", network, infura_project_id);
+async fn run_bot(settings: GlobalConfig) -> web3::Result<()> {
+    let global_config = settings.to_value();
+    let eth_node_url = format!("https://{}.infura.io/v3/{}", settings.network, settings.infura_project_id);
 
-    let transport = Http::new(ð_node_url)?;
-    let web3 = Web3::new(transport);
-    let web3 = std::sync::Arc::new(web3);
+    // Per-strategy RPC endpoints (low-latency local node vs hosted), with health checks enforcing the pins.
+    // Every client built from the pool fails over between its endpoints request by request
+    let providers = std::sync::Arc::new(modules::providers::ProviderPool::from_config(&eth_node_url)?);
+    modules::providers::install(providers.clone());
+    task::spawn(modules::providers::run_provider_health_checks(providers.clone()));
+    let web3 = providers.web3_for("main")?;
+    if let Some((network, _)) = modules::testnet::active_preset() {
+        info!("Running with the {} testnet preset", network);
+    }
 
-    let default_gas_limit = global_config["default_gas_limit"].as_u64().unwrap_or(5000000);
-    let bot_mode = global_config["bot_mode"].as_str().unwrap();
+    // Config fields may name addresses by ENS; resolve them before anything reads an address,
+    // then report invalid configs and keep re-resolving so record changes are noticed
+    modules::ens::resolve_config_names(&web3).await;
+    modules::config_validator::report();
+    if global_config["selector_check_enabled"].as_bool().unwrap_or(false) {
+        modules::selector_check::verify_selectors(&web3).await;
+    }
+    task::spawn(modules::ens::run_ens_revalidation(
+        (*web3).clone(),
+        global_config["ens_revalidate_interval"].as_u64().unwrap_or(3600),
+    ));
+
+    // Trade and PnL store, started before the strategies so their first records are kept
+    if global_config["storage_enabled"].as_bool().unwrap_or(false) {
+        info!("Trade store enabled");
+        task::spawn(modules::storage::run_storage((*web3).clone()));
+    }
+
+    // Pool cache, pool state and borrower index from the last checkpoint, synced forward to the head
+    modules::checkpoint::bootstrap(&web3).await;
+    if global_config["checkpoint_export_enabled"].as_bool().unwrap_or(false) {
+        task::spawn(modules::checkpoint::run_checkpoint_export((*web3).clone()));
+    }
+    // Reserves of cached pools kept in memory from Sync/Swap events, so quotes skip the RPC round trip
+    if global_config["pool_state_sync_enabled"].as_bool().unwrap_or(false) {
+        info!("Pool state sync enabled");
+        task::spawn(modules::pool_state::run_pool_state_sync());
+    }
+
+    // Single writer per wallet: without the lock this instance runs as a read-only observer
+    if !modules::coordination::start().await {
+        info!("Running as observer, transactions will not be submitted");
+    }
+    // Analyst deployments never load a signer; tasks that only exist to send transactions stay off
+    let read_only = modules::signer::read_only();
+    if read_only {
+        info!("Read-only mode: detection, simulation and decisions are recorded, nothing is signed or sent");
+    }
+
+    let bot_mode = settings.bot_mode.as_str();
 
     // Monitoring (if enabled)
     if global_config["monitoring_enabled"].as_bool().unwrap_or(false) {
         info!("Monitoring enabled");
-        task::spawn(async {
-            // Add monitoring logic if necessary
-        });
+        task::spawn(modules::ws_metrics::run_new_heads_watchdog());
+        task::spawn(modules::monitoring::run_balance_checks((*web3).clone()));
+    }
+
+    // Dashboard server (if enabled)
+    if global_config["dashboard_enabled"].as_bool().unwrap_or(false) {
+        info!("Dashboard enabled");
+        task::spawn(modules::dashboard::run_dashboard((*web3).clone(), providers.clone()));
+    }
+
+    // gRPC mirror of the status, control and data endpoints (if enabled)
+    if global_config["grpc_enabled"].as_bool().unwrap_or(false) {
+        info!("gRPC API enabled");
+        task::spawn(modules::grpc_api::run_grpc_api(providers.clone()));
+    }
+
+    // Audit config changes made on disk while the bot is running
+    let watched_configs = global_config["strategies"]
+        .as_object()
+        .map(|strategies| {
+            strategies
+                .values()
+                .filter_map(|strategy| strategy["config_path"].as_str().map(|path| path.to_string()))
+                .chain(std::iter::once(String::from("config/global_config.json")))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    task::spawn(modules::config_audit::watch_config_files(
+        watched_configs,
+        global_config["config_watch_interval"].as_u64().unwrap_or(30),
+    ));
+
+    // Funding runway projection and low-balance alerts
+    if global_config["runway_monitor_enabled"].as_bool().unwrap_or(false) {
+        info!("Runway monitor enabled");
+        task::spawn(modules::runway::run_runway_monitor((*web3).clone()));
+    }
+
+    // Shared inventory view (if enabled) lets strategies trade from tokens the wallet already holds
+    if global_config["inventory_view_enabled"].as_bool().unwrap_or(false) {
+        info!("Inventory view enabled");
+        task::spawn(modules::inventory::run_inventory_view((*web3).clone()));
+    }
+
+    // Liquidations prepared from DEX price shocks and fired once the oracle catches up
+    if global_config["liquidation_watch_enabled"].as_bool().unwrap_or(false) {
+        info!("Liquidation watch enabled");
+        task::spawn(modules::liquidation_watch::run_liquidation_watch((*web3).clone()));
+    }
+
+    // Inventory manager (if enabled) keeps base assets at their target weights
+    if global_config["inventory_manager_enabled"].as_bool().unwrap_or(false) && !read_only {
+        info!("Inventory manager enabled");
+        task::spawn(modules::inventory::run_inventory_manager((*web3).clone()));
+    }
+
+    // Mark-to-market equity curve (ETH + token inventory)
+    if global_config["equity_tracker_enabled"].as_bool().unwrap_or(false) {
+        info!("Equity tracker enabled");
+        task::spawn(modules::equity::run_equity_tracker((*web3).clone()));
+    }
+
+    // Archive sampled pending transactions and their inclusion outcomes for offline research
+    if global_config["mempool_recorder_enabled"].as_bool().unwrap_or(false) {
+        info!("Mempool recorder enabled");
+        task::spawn(modules::mempool_recorder::run_mempool_recorder());
+    }
+
+    // Publish normalized pending transactions to Redis for strategies running on other machines
+    if modules::feed::publish_pending_enabled() {
+        info!("Pending transaction feed publisher enabled");
+        task::spawn(modules::feed::run_pending_publisher());
+    }
+
+    // Shift virtual capital toward strategies with better risk-adjusted returns
+    if global_config["allocator_enabled"].as_bool().unwrap_or(false) {
+        info!("Capital allocator enabled");
+        task::spawn(modules::allocator::run_allocator());
+    }
+
+    // Re-send critical alerts nobody has acknowledged
+    task::spawn(modules::alerts::run_alert_escalation());
+
+    // Operator-defined alert and pause rules over the event bus
+    if global_config["alert_rules_enabled"].as_bool().unwrap_or(false) {
+        info!("Alert rules enabled");
+        task::spawn(modules::alert_rules::run_alert_rules());
+    }
+
+    // Hourly per-pair spread heatmap for the dashboard and CSV export
+    if global_config["heatmap_enabled"].as_bool().unwrap_or(false) {
+        info!("Spread heatmap export enabled");
+        task::spawn(modules::heatmap::run_heatmap_export());
+    }
+
+    // Periodic payout of accrued performance fees to the operator
+    if global_config["fee_transfers_enabled"].as_bool().unwrap_or(false) && !read_only {
+        info!("Performance fee transfers enabled");
+        task::spawn(modules::fees::run_fee_transfers((*web3).clone()));
+    }
+
+    // Revocation of allowances towards routers the bot has stopped using
+    if global_config["approval_sweep_enabled"].as_bool().unwrap_or(false) && !read_only {
+        info!("Stale approval sweep enabled");
+        task::spawn(modules::approvals::run_approval_sweep((*web3).clone()));
+    }
+
+    // Reward claimer (if enabled) collects protocol incentives once they are worth the gas
+    if global_config["reward_claimer_enabled"].as_bool().unwrap_or(false) && !read_only {
+        info!("Reward claimer enabled");
+        task::spawn(modules::rewards::run_reward_claimer((*web3).clone()));
+    }
+
+    // Drift check (if enabled) verifies configured routers, pools and feeds against the chain
+    if global_config["drift_check_enabled"].as_bool().unwrap_or(false) {
+        info!("Config drift check enabled");
+        task::spawn(modules::config_drift::run_drift_check((*web3).clone()));
+    }
+
+    // Stuck-nonce recovery (if enabled) runs once at startup, then replaces or cancels stale transactions
+    if global_config["nonce_recovery_enabled"].as_bool().unwrap_or(false) && !read_only {
+        info!("Stuck nonce recovery enabled");
+        task::spawn(modules::nonce_recovery::run_nonce_recovery((*web3).clone()));
+    }
+
+    // Daily reconciliation (if enabled) diffs the previous day's on-chain history against the ledgers
+    if global_config["reconciliation_enabled"].as_bool().unwrap_or(false) {
+        info!("Daily reconciliation enabled");
+        task::spawn(modules::reconcile::run_reconciliation((*web3).clone()));
+    }
+
+    // Passive LP yield (if enabled) parks idle capital in configured pairs between opportunities
+    if global_config["lp_yield_enabled"].as_bool().unwrap_or(false) && !read_only {
+        info!("Passive LP yield enabled");
+        task::spawn(modules::lp_yield::run_lp_yield((*web3).clone()));
+    }
+
+    // Arbitrum sequencer feed: sequenced transactions before they are in a block, with arbitrage backruns
+    if global_config["sequencer_feed_enabled"].as_bool().unwrap_or(false) {
+        info!("Sequencer feed reader enabled");
+        task::spawn(modules::sequencer_feed::run_sequencer_feed());
+        task::spawn(modules::arbitrage::backrun_sequenced_swaps((*web3).clone()));
+    }
+
+    // Without pending visibility (private mempools, sequencer-only chains) mempool strategies are
+    // skipped and backruns are detected from new blocks, unless the sequencer feed covers them
+    let mempool = modules::mempool_mode::detect(&web3).await;
+    if mempool == modules::mempool_mode::MempoolVisibility::Hidden && !global_config["sequencer_feed_enabled"].as_bool().unwrap_or(false) {
+        info!("Block-driven backruns enabled");
+        task::spawn(modules::arbitrage::backrun_block_swaps((*web3).clone()));
+    }
+
+    // Builder inclusion and payment analysis for our submissions, feeding the tip multiplier
+    if global_config["builder_analysis_enabled"].as_bool().unwrap_or(false) {
+        info!("Builder payment analysis enabled");
+        task::spawn(modules::builders::run_builder_analysis((*web3).clone()));
+    }
+
+    // Pause strategies whose routers, pools or lending contracts get upgraded until an operator acknowledges
+    if global_config["code_watch_enabled"].as_bool().unwrap_or(false) {
+        info!("Contract code watch enabled");
+        task::spawn(modules::code_watch::run_code_watch((*web3).clone()));
+    }
+
+    // Refit per-pool slippage factors from realized executions
+    task::spawn(modules::slippage::run_slippage_calibration());
+
+    // Tag pools where JIT liquidity absorbs large swaps so the sandwich module deprioritizes them
+    task::spawn(modules::jit::run_jit_scanner((*web3).clone()));
+
+    // Daily token pair discovery by volume for the arbitrage scanner
+    if global_config["pair_discovery_enabled"].as_bool().unwrap_or(false) {
+        info!("Pair discovery enabled");
+        task::spawn(modules::pair_discovery::run_pair_discovery((*providers.web3_for("pair_discovery")?).clone()));
+    }
+
+    if modules::mempool_mode::requires_mempool(bot_mode) && !modules::mempool_mode::mempool_available() {
+        error!("The {} strategy needs pending transactions, which this endpoint does not show", bot_mode);
+        return Ok(());
     }
 
     // Run strategies based on bot mode
     match bot_mode {
-        "arbitrage" => {
-            info!("Running Arbitrage Strategy");
-            let arbitrage_config = load_strategy_config("arbitrage");
-            modules::arbitrage::execute_arbitrage_with_retry(web3.clone(), U256::zero(), 3).await.unwrap();
-        }
-        "flashloan" => {
-            info!("Running Flashloan Strategy");
-            let flashloan_config = load_strategy_config("flashloan");
-            let asset_address = flashloan_config["asset_address"].as_str().unwrap().parse().unwrap();
-            modules::flashloan::execute_flashloan(web3.clone(), U256::zero(), asset_address).await.unwrap();
+        "soak" => {
+            info!("Running Soak Test with synthetic mempool traffic");
+            modules::soak::run_soak_test().await;
         }
-        "frontrunning" => {
-            info!("Running Frontrunning Strategy");
-            let frontrunning_config = load_strategy_config("frontrunning");
-            let transactions = modules::frontrunning::fetch_mempool_transactions(web3.clone()).await;
-            // Process the fetched transactions as needed
+        "replay" => {
+            info!("Replaying recorded strategy decisions");
+            modules::replay::run_replay();
         }
-        "liquidation" => {
-            info!("Running Liquidation Strategy");
-            let liquidation_config = load_strategy_config("liquidation");
-            let borrower_address = liquidation_config["borrower_address"].as_str().unwrap().parse().unwrap();
-            let collateral_asset = liquidation_config["collateral_asset"].as_str().unwrap().parse().unwrap();
-            modules::liquidation::execute_liquidation(web3.clone(), borrower_address, U256::zero(), collateral_asset).await.unwrap();
-        }
-        "sandwich" => {
-            info!("Running Sandwich Attack Strategy");
-            let sandwich_config = load_strategy_config("sandwich");
-            modules::sandwich::execute_sandwich_attack_with_retry(web3.clone(), U256::zero(), 3).await.unwrap();
-        }
-        "hft" => {
-            info!("Running HFT Strategy");
-            let hft_config = load_strategy_config("hft");
-            modules::hft::execute_hft(web3.clone()).await.unwrap();
+        "health_history" => {
+            info!("Rebuilding historical borrower health factors from the archive node");
+            let archive_web3 = providers.web3_for("health_history")?;
+            modules::health_history::run_health_history((*archive_web3).clone()).await?;
         }
         "multi" | "all" => {
             info!("Running All Enabled Strategies");
-            let enabled_strategies = global_config["strategies"]
-                .as_object()
-                .unwrap()
-                .iter()
-                .filter(|(_, strategy)| strategy["enabled"].as_bool().unwrap_or(false))
-                .map(|(name, _)| name.to_string())
-                .collect::<Vec<_>>();
-
-            for strategy in enabled_strategies {
-                match strategy.as_str() {
-                    "arbitrage" => {
-                        info!("Running Arbitrage");
-                        let arbitrage_config = load_strategy_config("arbitrage");
-                        modules::arbitrage::execute_arbitrage_with_retry(web3.clone(), U256::zero(), 3).await.unwrap();
-                    }
-                    "flashloan" => {
-                        info!("Running Flashloan");
-                        let flashloan_config = load_strategy_config("flashloan");
-                        let asset_address = flashloan_config["asset_address"].as_str().unwrap().parse().unwrap();
-
-                        modules::flashloan::execute_flashloan(web3.clone(), U256::zero(), asset_address).await.unwrap();
-                    }
-                    "frontrunning" => {
-                        info!("Running Frontrunning");
-                        let frontrunning_config = load_strategy_config("frontrunning");
-                        let transactions = modules::frontrunning::fetch_mempool_transactions(web3.clone()).await;
-                        // Process the fetched transactions as needed
-                    }
-                    "liquidation" => {
-                        info!("Running Liquidation");
-                        let liquidation_config = load_strategy_config("liquidation");
-                        let borrower_address = liquidation_config["borrower_address"].as_str().unwrap().parse().unwrap();
-                        let collateral_asset = liquidation_config["collateral_asset"].as_str().unwrap().parse().unwrap();
-                        modules::liquidation::execute_liquidation(web3.clone(), borrower_address, U256::zero(), collateral_asset).await.unwrap();
-                    }
-                    "sandwich" => {
-                        info!("Running Sandwich Attack");
-                        let sandwich_config = load_strategy_config("sandwich");
-                        modules::sandwich::execute_sandwich_attack_with_retry(web3.clone(), U256::zero(), 3).await.unwrap();
-                    }
-                    "hft" => {
-                        info!("Running HFT");
-                        let hft_config = load_strategy_config("hft");
-                        modules::hft::execute_hft(web3.clone()).await.unwrap();
-                    }
-                    _ => error!("Unknown strategy: {}", strategy),
-                }
-            }
-        }
-        _ => {
-            error!("Invalid bot mode: {}", bot_mode);
+            modules::strategy::run_orchestrator(providers.clone(), modules::strategy::enabled(&settings)).await;
         }
+        // A single strategy runs under the same supervision as in multi mode
+        strategy => match modules::strategy::build(strategy, &settings) {
+            Ok(Some(strategy)) => {
+                info!("Running {} strategy", strategy.name());
+                modules::strategy::run_orchestrator(providers.clone(), vec![strategy]).await;
+            }
+            Ok(None) => error!("Invalid bot mode: {}", bot_mode),
+            Err(e) => error!("Unable to start {}: {}", bot_mode, e),
+        },
     }
 
     Ok(())
@@ -0,0 +1,306 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::str::FromStr;
+use web3::contract::{Contract, Options};
+use web3::ethabi::{self, Token};
+use web3::signing::{keccak256, SecretKey};
+use web3::types::{Address, Bytes, H256, U256};
+use log::{error, info};
+use thiserror::Error;
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::modules::gas_feed;
+use crate::modules::providers::FailoverTransport;
+
+// Placeholder signature accepted by SimpleAccount-style wallets during gas estimation
+const DUMMY_SIGNATURE: &str = "fffffffffffffffffffffffffffffff0000000000000000000000000000000007aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa1c";
+
+// Load the account abstraction configuration
+fn load_account_abstraction_config() -> Value {
+    let config_path = "config/account_abstraction_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read account abstraction config file");
+    serde_json::from_str(&config_data).expect("Unable to parse account abstraction config file")
+}
+
+// ERC-4337 (EntryPoint v0.6) user operation
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    pub sender: Address,
+    pub nonce: U256,
+    pub init_code: Bytes,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub paymaster_and_data: Bytes,
+    pub signature: Bytes,
+}
+
+impl UserOperation {
+    // Hash of the operation as computed by EntryPoint.getUserOpHash
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> H256 {
+        let packed = ethabi::encode(&[
+            Token::Address(self.sender),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.init_code.0).to_vec()),
+            Token::FixedBytes(keccak256(&self.call_data.0).to_vec()),
+            Token::Uint(self.call_gas_limit),
+            Token::Uint(self.verification_gas_limit),
+            Token::Uint(self.pre_verification_gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::FixedBytes(keccak256(&self.paymaster_and_data.0).to_vec()),
+        ]);
+
+        let encoded = ethabi::encode(&[
+            Token::FixedBytes(keccak256(&packed).to_vec()),
+            Token::Address(entry_point),
+            Token::Uint(U256::from(chain_id)),
+        ]);
+
+        H256::from(keccak256(&encoded))
+    }
+}
+
+// Smart-account backend that submits batched calls as UserOperations through a bundler
+pub struct AccountAbstractionBackend {
+    web3: web3::Web3<FailoverTransport>,
+    client: reqwest::Client,
+    bundler_url: String,
+    paymaster_url: Option<String>,
+    entry_point: Address,
+    smart_account: Address,
+    chain_id: u64,
+    owner_key: SecretKey,
+    config: Value,
+}
+
+impl AccountAbstractionBackend {
+    // Initialize the backend from config/account_abstraction_config.json
+    pub fn new(web3: web3::Web3<FailoverTransport>) -> Result<Self, AccountAbstractionError> {
+        let config = load_account_abstraction_config();
+
+        let bundler_url = config["bundler_url"].as_str().ok_or(AccountAbstractionError::MissingConfig("bundler_url"))?.to_string();
+        let entry_point = Address::from_str(config["entry_point"].as_str().ok_or(AccountAbstractionError::MissingConfig("entry_point"))?)
+            .map_err(|_| AccountAbstractionError::MissingConfig("entry_point"))?;
+        let smart_account = Address::from_str(config["smart_account"].as_str().ok_or(AccountAbstractionError::MissingConfig("smart_account"))?)
+            .map_err(|_| AccountAbstractionError::MissingConfig("smart_account"))?;
+        let chain_id = config["chain_id"].as_u64().ok_or(AccountAbstractionError::MissingConfig("chain_id"))?;
+
+        // The owner key never lives in the config file, only the env var name does
+        let key_env = config["owner_key_env"].as_str().unwrap_or("TAZ_AA_OWNER_KEY");
+        let owner_key_hex = std::env::var(key_env).map_err(|_| AccountAbstractionError::MissingConfig("owner_key_env"))?;
+        let owner_key = SecretKey::from_str(owner_key_hex.trim_start_matches("0x"))
+            .map_err(|_| AccountAbstractionError::InvalidOwnerKey)?;
+
+        Ok(AccountAbstractionBackend {
+            web3,
+            client: reqwest::Client::new(),
+            bundler_url,
+            paymaster_url: config["paymaster_url"].as_str().map(|url| url.to_string()),
+            entry_point,
+            smart_account,
+            chain_id,
+            owner_key,
+            config,
+        })
+    }
+
+    // Address that receives swap outputs when this backend is used
+    pub fn smart_account(&self) -> Address {
+        self.smart_account
+    }
+
+    // Encode a list of calls as SimpleAccount.execute / executeBatch calldata
+    pub fn encode_batch(&self, calls: &[(Address, U256, Vec<u8>)]) -> Result<Vec<u8>, AccountAbstractionError> {
+        let account_abi = ethabi::Contract::load(&include_bytes!("abi/simple_account_abi.json")[..])?;
+
+        if calls.len() == 1 {
+            let (to, value, data) = &calls[0];
+            let encoded = account_abi.function("execute")?.encode_input(&[
+                Token::Address(*to),
+                Token::Uint(*value),
+                Token::Bytes(data.clone()),
+            ])?;
+            return Ok(encoded);
+        }
+
+        // executeBatch carries no value, so every batched call must be value-less
+        if calls.iter().any(|(_, value, _)| !value.is_zero()) {
+            return Err(AccountAbstractionError::ValueInBatch);
+        }
+
+        let encoded = account_abi.function("executeBatch")?.encode_input(&[
+            Token::Array(calls.iter().map(|(to, _, _)| Token::Address(*to)).collect()),
+            Token::Array(calls.iter().map(|(_, _, data)| Token::Bytes(data.clone())).collect()),
+        ])?;
+        Ok(encoded)
+    }
+
+    // Fetch the smart account nonce from the EntryPoint
+    async fn get_nonce(&self) -> Result<U256, AccountAbstractionError> {
+        let entry_point = Contract::from_json(
+            self.web3.eth(),
+            self.entry_point,
+            include_bytes!("abi/entry_point_abi.json"),
+        )?;
+
+        let nonce: U256 = entry_point
+            .query("getNonce", (self.smart_account, U256::zero()), None, Options::default(), None)
+            .await?;
+        Ok(nonce)
+    }
+
+    // Send a JSON-RPC request to the bundler or paymaster
+    async fn rpc_request(&self, url: &str, method: &str, params: Value) -> Result<Value, AccountAbstractionError> {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response: Value = self.client.post(url).json(&request).send().await?.json().await?;
+
+        if let Some(rpc_error) = response.get("error") {
+            return Err(AccountAbstractionError::BundlerError(rpc_error.to_string()));
+        }
+        Ok(response["result"].clone())
+    }
+
+    // Build, sign and submit a UserOperation for the given calls, returning the userOpHash
+    pub async fn submit_calls(&self, calls: &[(Address, U256, Vec<u8>)]) -> Result<H256, AccountAbstractionError> {
+        let call_data = self.encode_batch(calls)?;
+        // Consensus fees across gas sources, falling back to the node gas price plus the configured tip
+        let (max_fee_per_gas, priority_fee) = match gas_feed::consensus_estimate(&self.web3).await {
+            Ok(estimate) => (estimate.max_fee, estimate.priority_fee),
+            Err(_) => {
+                let gas_price = self.web3.eth().gas_price().await?;
+                let priority_fee = U256::from(self.config["max_priority_fee_per_gas"].as_u64().unwrap_or(1_500_000_000));
+                (gas_price + priority_fee, priority_fee)
+            }
+        };
+
+        let mut user_op = UserOperation {
+            sender: self.smart_account,
+            nonce: self.get_nonce().await?,
+            init_code: Bytes(Vec::new()),
+            call_data: Bytes(call_data),
+            call_gas_limit: U256::from(self.config["call_gas_limit"].as_u64().unwrap_or(1_000_000)),
+            verification_gas_limit: U256::from(self.config["verification_gas_limit"].as_u64().unwrap_or(150_000)),
+            pre_verification_gas: U256::from(self.config["pre_verification_gas"].as_u64().unwrap_or(60_000)),
+            max_fee_per_gas,
+            max_priority_fee_per_gas: priority_fee,
+            paymaster_and_data: Bytes(hex_to_bytes(self.config["paymaster_and_data"].as_str().unwrap_or("0x"))?),
+            signature: Bytes(hex_to_bytes(DUMMY_SIGNATURE)?),
+        };
+
+        // Sponsored gas: let the paymaster fill in paymasterAndData (and gas limits) before signing
+        if let Some(paymaster_url) = &self.paymaster_url {
+            let sponsored = self
+                .rpc_request(paymaster_url, "pm_sponsorUserOperation", json!([user_op, self.entry_point]))
+                .await?;
+            if let Some(data) = sponsored["paymasterAndData"].as_str() {
+                user_op.paymaster_and_data = Bytes(hex_to_bytes(data)?);
+            }
+            apply_gas_estimate(&mut user_op, &sponsored);
+        } else if self.config["estimate_gas"].as_bool().unwrap_or(true) {
+            let estimate = self
+                .rpc_request(&self.bundler_url, "eth_estimateUserOperationGas", json!([user_op, self.entry_point]))
+                .await?;
+            apply_gas_estimate(&mut user_op, &estimate);
+        }
+
+        let user_op_hash = user_op.hash(self.entry_point, self.chain_id);
+        let signed = self.web3.accounts().sign(user_op_hash.as_bytes(), &self.owner_key);
+        user_op.signature = signed.signature;
+
+        let result = self
+            .rpc_request(&self.bundler_url, "eth_sendUserOperation", json!([user_op, self.entry_point]))
+            .await?;
+        let submitted_hash = result
+            .as_str()
+            .and_then(|hash| H256::from_str(hash).ok())
+            .unwrap_or(user_op_hash);
+
+        info!("UserOperation submitted: {:?} ({} calls)", submitted_hash, calls.len());
+        Ok(submitted_hash)
+    }
+
+    // Poll the bundler until the UserOperation is included or the timeout elapses
+    pub async fn wait_for_receipt(&self, user_op_hash: H256) -> Result<Value, AccountAbstractionError> {
+        let timeout = Duration::from_secs(self.config["receipt_timeout_secs"].as_u64().unwrap_or(120));
+        let poll_interval = Duration::from_millis(self.config["receipt_poll_ms"].as_u64().unwrap_or(2000));
+        let started = Instant::now();
+
+        while started.elapsed() < timeout {
+            let receipt = self
+                .rpc_request(&self.bundler_url, "eth_getUserOperationReceipt", json!([user_op_hash]))
+                .await?;
+            if !receipt.is_null() {
+                if receipt["success"].as_bool() == Some(false) {
+                    error!("UserOperation {:?} reverted: {}", user_op_hash, receipt["reason"]);
+                    return Err(AccountAbstractionError::Reverted(user_op_hash));
+                }
+                info!("UserOperation {:?} included in tx {}", user_op_hash, receipt["receipt"]["transactionHash"]);
+                return Ok(receipt);
+            }
+            sleep(poll_interval).await;
+        }
+
+        Err(AccountAbstractionError::ReceiptTimeout(user_op_hash))
+    }
+}
+
+// Overwrite gas fields with the values returned by the bundler or paymaster
+fn apply_gas_estimate(user_op: &mut UserOperation, estimate: &Value) {
+    let parse = |field: &str| estimate[field].as_str().and_then(|value| U256::from_str(value.trim_start_matches("0x")).ok());
+
+    if let Some(call_gas_limit) = parse("callGasLimit") {
+        user_op.call_gas_limit = call_gas_limit;
+    }
+    if let Some(verification_gas_limit) = parse("verificationGasLimit") {
+        user_op.verification_gas_limit = verification_gas_limit;
+    }
+    if let Some(pre_verification_gas) = parse("preVerificationGas") {
+        user_op.pre_verification_gas = pre_verification_gas;
+    }
+}
+
+// Decode a 0x-prefixed hex string into bytes; malformed paymaster data must not be sent truncated
+fn hex_to_bytes(value: &str) -> Result<Vec<u8>, AccountAbstractionError> {
+    Ok(hex::decode(value.trim_start_matches("0x"))?)
+}
+
+// Custom error type for the account abstraction backend
+#[derive(Error, Debug)]
+pub enum AccountAbstractionError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Contract error: {0}")]
+    ContractError(#[from] web3::contract::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("Invalid hex data: {0}")]
+    HexError(#[from] hex::FromHexError),
+    #[error("Bundler error: {0}")]
+    BundlerError(String),
+    #[error("Missing or invalid account abstraction config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("Invalid smart account owner key")]
+    InvalidOwnerKey,
+    #[error("executeBatch cannot forward ETH value")]
+    ValueInBatch,
+    #[error("UserOperation reverted: {0:?}")]
+    Reverted(H256),
+    #[error("Timed out waiting for UserOperation receipt: {0:?}")]
+    ReceiptTimeout(H256),
+}
+
+// Implement conversion for AccountAbstractionError to Web3 error
+impl From<AccountAbstractionError> for web3::Error {
+    fn from(error: AccountAbstractionError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use web3::ethabi::{self, param_type::Reader, token::{LenientTokenizer, Tokenizer}, ParamType};
+use web3::types::U256;
+use thiserror::Error;
+
+use crate::modules::ens;
+use crate::modules::execution::Call;
+
+// Load the contract action definitions
+fn load_actions_config() -> Value {
+    let config_path = "config/actions_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read actions config file");
+    serde_json::from_str(&config_data).expect("Unable to parse actions config file")
+}
+
+// Where an action runs relative to the plan's swap legs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionPhase {
+    Before,
+    After,
+}
+
+fn before() -> ActionPhase {
+    ActionPhase::Before
+}
+
+// A config-defined call to an arbitrary contract method (claim rewards, unstake, ...). Target, args
+// and value are templates: "{name}" placeholders are filled from the plan context when the plan is
+// turned into calls, e.g. "{recipient}", "{amount_in}" or "{legs.0.token_out}"
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContractAction {
+    pub name: String,
+    pub target: String,
+    // ABI fragment of the method, e.g. "claimRewards(address[],uint256,address)"
+    pub function: String,
+    #[serde(default)]
+    pub args: Vec<Value>,
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default = "before")]
+    pub phase: ActionPhase,
+}
+
+// Actions configured for a strategy, in config order
+pub fn for_strategy(strategy: &str) -> Vec<ContractAction> {
+    let config = load_actions_config();
+    config["actions"]
+        .as_array()
+        .map(|actions| {
+            actions
+                .iter()
+                .filter(|action| {
+                    action["strategies"]
+                        .as_array()
+                        .is_some_and(|strategies| strategies.iter().any(|name| name.as_str() == Some(strategy)))
+                })
+                .filter_map(|action| serde_json::from_value(action.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Split "name(type,...)" (an optional leading "function " is allowed) into the name and param types
+fn parse_fragment(fragment: &str) -> Result<(String, Vec<ParamType>), ActionError> {
+    let fragment = fragment.trim();
+    let fragment = fragment.strip_prefix("function ").unwrap_or(fragment).trim();
+    let (name, params) = fragment
+        .split_once('(')
+        .filter(|(name, params)| !name.is_empty() && params.ends_with(')'))
+        .ok_or_else(|| ActionError::InvalidFragment(fragment.to_string()))?;
+    if params == ")" {
+        return Ok((name.trim().to_string(), Vec::new()));
+    }
+    match Reader::read(&format!("({}", params)) {
+        Ok(ParamType::Tuple(types)) => Ok((name.trim().to_string(), types)),
+        _ => Err(ActionError::InvalidFragment(fragment.to_string())),
+    }
+}
+
+// Fill "{key}" placeholders from the context; an unknown key is an error rather than a silent blank
+fn render(template: &str, context: &BTreeMap<String, String>) -> Result<String, ActionError> {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}').ok_or_else(|| ActionError::InvalidTemplate(template.to_string()))? + start;
+        let key = &rest[start + 1..end];
+        let value = context.get(key).ok_or_else(|| ActionError::UnknownPlaceholder(key.to_string()))?;
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(value);
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+// Render an argument into the lenient tokenizer's text form; JSON arrays become "[a,b,...]"
+fn render_arg(arg: &Value, context: &BTreeMap<String, String>) -> Result<String, ActionError> {
+    match arg {
+        Value::String(template) => render(template, context),
+        Value::Array(items) => {
+            let items = items.iter().map(|item| render_arg(item, context)).collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("[{}]", items.join(",")))
+        }
+        other => Ok(other.to_string()),
+    }
+}
+
+impl ContractAction {
+    // Encode the action into a call with its templates filled from the plan context
+    pub fn call(&self, context: &BTreeMap<String, String>) -> Result<Call, ActionError> {
+        let (name, types) = parse_fragment(&self.function)?;
+        if types.len() != self.args.len() {
+            return Err(ActionError::ArgumentCount { action: self.name.clone(), expected: types.len(), got: self.args.len() });
+        }
+
+        let target = render(&self.target, context)?;
+        let to = ens::parse_address(&target).ok_or(ActionError::InvalidTarget(target))?;
+        let mut tokens = Vec::with_capacity(types.len());
+        for (param, arg) in types.iter().zip(&self.args) {
+            let text = render_arg(arg, context)?;
+            let token = LenientTokenizer::tokenize(param, &text)
+                .map_err(|e| ActionError::InvalidArgument { action: self.name.clone(), arg: text, reason: e.to_string() })?;
+            tokens.push(token);
+        }
+        let value = match &self.value {
+            Some(template) => {
+                let value = render(template, context)?;
+                U256::from_dec_str(&value).map_err(|_| ActionError::InvalidArgument { action: self.name.clone(), arg: value, reason: String::from("value must be a decimal amount of wei") })?
+            }
+            None => U256::zero(),
+        };
+
+        let data = [ethabi::short_signature(&name, &types).to_vec(), ethabi::encode(&tokens)].concat();
+        Ok(Call { to, value, data })
+    }
+}
+
+// Custom error type for contract actions
+#[derive(Error, Debug)]
+pub enum ActionError {
+    #[error("Invalid ABI fragment: {0}")]
+    InvalidFragment(String),
+    #[error("Unterminated placeholder in template: {0}")]
+    InvalidTemplate(String),
+    #[error("Unknown placeholder {{{0}}}")]
+    UnknownPlaceholder(String),
+    #[error("Action target {0} is not an address or known ENS name")]
+    InvalidTarget(String),
+    #[error("Action {action} takes {expected} arguments, {got} configured")]
+    ArgumentCount { action: String, expected: usize, got: usize },
+    #[error("Action {action}: invalid argument {arg}: {reason}")]
+    InvalidArgument { action: String, arg: String, reason: String },
+}
+
+// Implement conversion for ActionError to Web3 error
+impl From<ActionError> for web3::Error {
+    fn from(error: ActionError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,380 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use tokio::sync::broadcast::error::RecvError;
+use log::{info, warn};
+use thiserror::Error;
+use chrono::{DateTime, Utc};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::control;
+use crate::modules::events::{self, BotEvent};
+
+// Load the monitoring configuration (the "alert_rules" section holds the rules)
+fn load_monitoring_config() -> Value {
+    let config_path = "config/monitoring_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read monitoring config file");
+    serde_json::from_str(&config_data).expect("Unable to parse monitoring config file")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+// Two-character operators first, so "<=" isn't read as "<"
+const OPERATORS: [(&str, Operator); 7] = [
+    (" == ", Operator::Eq),
+    (" != ", Operator::Ne),
+    (" <= ", Operator::Le),
+    (" >= ", Operator::Ge),
+    (" < ", Operator::Lt),
+    (" > ", Operator::Gt),
+    (" contains ", Operator::Contains),
+];
+
+// One "field operator value" test against an event
+#[derive(Clone, Debug)]
+struct Comparison {
+    field: String,
+    operator: Operator,
+    value: String,
+    // The value as a number, with "gwei" and "eth" suffixes scaled to wei
+    number: Option<f64>,
+}
+
+fn parse_number(value: &str) -> Option<f64> {
+    let (amount, scale) = match value.rsplit_once(' ') {
+        Some((amount, "gwei")) => (amount, 1e9),
+        Some((amount, "eth")) => (amount, 1e18),
+        _ => (value, 1.0),
+    };
+    amount.trim().parse::<f64>().ok().map(|amount| amount * scale)
+}
+
+fn parse_comparison(text: &str) -> Result<Comparison, AlertRuleError> {
+    let text = format!(" {} ", text.trim());
+    let (symbol, operator) = OPERATORS
+        .iter()
+        .find(|(symbol, _)| text.contains(symbol))
+        .ok_or_else(|| AlertRuleError::InvalidCondition(text.trim().to_string()))?;
+    let (field, value) = text.split_once(symbol).unwrap_or_default();
+    let field = field.trim().to_string();
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+    if field.is_empty() || value.is_empty() {
+        return Err(AlertRuleError::InvalidCondition(text.trim().to_string()));
+    }
+    let number = parse_number(&value);
+    Ok(Comparison { field, operator: *operator, value, number })
+}
+
+// "a AND b OR c" is (a AND b) OR c: a list of alternatives, each a list of comparisons that must all hold
+fn parse_condition(text: &str) -> Result<Vec<Vec<Comparison>>, AlertRuleError> {
+    text.split(" OR ")
+        .map(|alternative| alternative.split(" AND ").map(parse_comparison).collect())
+        .collect()
+}
+
+// Event kind, or a (dotted) path into the payload
+fn field_value(event: &BotEvent, field: &str) -> Option<Value> {
+    if field == "kind" {
+        return Some(json!(event.kind));
+    }
+    event.payload.pointer(&format!("/{}", field.replace('.', "/"))).cloned()
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    value.as_f64().or_else(|| value.as_str().and_then(|text| text.parse::<f64>().ok()))
+}
+
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl Comparison {
+    // A missing field never matches, whatever the operator
+    fn matches(&self, event: &BotEvent) -> bool {
+        let actual = match field_value(event, &self.field) {
+            Some(actual) if !actual.is_null() => actual,
+            _ => return false,
+        };
+        if self.operator == Operator::Contains {
+            return as_text(&actual).contains(&self.value);
+        }
+        if let (Some(actual), Some(expected)) = (as_number(&actual), self.number) {
+            return match self.operator {
+                Operator::Eq => actual == expected,
+                Operator::Ne => actual != expected,
+                Operator::Lt => actual < expected,
+                Operator::Le => actual <= expected,
+                Operator::Gt => actual > expected,
+                Operator::Ge => actual >= expected,
+                Operator::Contains => false,
+            };
+        }
+        match self.operator {
+            Operator::Eq => as_text(&actual).eq_ignore_ascii_case(&self.value),
+            Operator::Ne => !as_text(&actual).eq_ignore_ascii_case(&self.value),
+            // Ordering only makes sense between numbers
+            _ => false,
+        }
+    }
+}
+
+// What a matching rule does
+#[derive(Clone, Debug)]
+enum RuleAction {
+    Alert(Severity),
+    // Strategy name, or a {field} placeholder filled from the event
+    PauseStrategy(String),
+    PauseAll,
+    KillSwitch,
+}
+
+fn parse_action(action: &Value) -> Result<RuleAction, AlertRuleError> {
+    if let Some(severity) = action["alert"].as_str() {
+        return match severity {
+            "info" => Ok(RuleAction::Alert(Severity::Info)),
+            "warn" => Ok(RuleAction::Alert(Severity::Warn)),
+            "critical" => Ok(RuleAction::Alert(Severity::Critical)),
+            other => Err(AlertRuleError::InvalidAction(format!("unknown severity {}", other))),
+        };
+    }
+    if let Some(strategy) = action["pause_strategy"].as_str() {
+        return Ok(RuleAction::PauseStrategy(strategy.to_string()));
+    }
+    if action["pause"].as_bool().unwrap_or(false) {
+        return Ok(RuleAction::PauseAll);
+    }
+    if action["kill_switch"].as_bool().unwrap_or(false) {
+        return Ok(RuleAction::KillSwitch);
+    }
+    Err(AlertRuleError::InvalidAction(action.to_string()))
+}
+
+// An operator-defined rule from the config
+#[derive(Clone, Debug)]
+struct Rule {
+    name: String,
+    // Event kind the rule applies to; every event when absent
+    event: Option<String>,
+    condition: Vec<Vec<Comparison>>,
+    actions: Vec<RuleAction>,
+    // Alert subject, with {field} placeholders filled from the event
+    message: Option<String>,
+}
+
+fn parse_rule(rule: &Value) -> Result<Rule, AlertRuleError> {
+    let name = rule["name"].as_str().ok_or(AlertRuleError::MissingName)?.to_string();
+    let condition = match rule["when"].as_str() {
+        Some(when) => parse_condition(when)?,
+        None => Vec::new(),
+    };
+    let actions = rule["actions"].as_array().map(|actions| actions.iter().map(parse_action).collect()).unwrap_or(Ok(Vec::new()))?;
+    if actions.is_empty() {
+        return Err(AlertRuleError::InvalidAction(format!("rule {} has no actions", name)));
+    }
+    Ok(Rule {
+        name,
+        event: rule["event"].as_str().map(String::from),
+        condition,
+        actions,
+        message: rule["message"].as_str().map(String::from),
+    })
+}
+
+impl Rule {
+    fn matches(&self, event: &BotEvent) -> bool {
+        if self.event.as_ref().is_some_and(|kind| *kind != event.kind) {
+            return false;
+        }
+        // No condition matches every event of the kind
+        self.condition.is_empty() || self.condition.iter().any(|all| all.iter().all(|comparison| comparison.matches(event)))
+    }
+}
+
+// Replace {field} placeholders with the event's values
+fn fill(template: &str, event: &BotEvent) -> String {
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        filled.push_str(&rest[..start]);
+        filled.push_str(&field_value(event, &rest[start + 1..end]).map(|value| as_text(&value)).unwrap_or_default());
+        rest = &rest[end + 1..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+// Rules parsed from the config, reparsed whenever the section changes
+#[derive(Default)]
+struct RuleSet {
+    source: Value,
+    rules: Vec<Rule>,
+}
+
+fn rule_set() -> &'static Mutex<RuleSet> {
+    static RULES: OnceLock<Mutex<RuleSet>> = OnceLock::new();
+    RULES.get_or_init(|| Mutex::new(RuleSet::default()))
+}
+
+// Current rules; an invalid rule is logged once per config change and left out
+fn current_rules(config: &Value) -> Vec<Rule> {
+    let mut set = rule_set().lock().unwrap();
+    if set.source != config["rules"] {
+        set.source = config["rules"].clone();
+        set.rules = config["rules"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|rule| match parse_rule(rule) {
+                Ok(rule) => Some(rule),
+                Err(e) => {
+                    warn!("Skipping alert rule {}: {}", rule["name"].as_str().unwrap_or("(unnamed)"), e);
+                    None
+                }
+            })
+            .collect();
+        info!("Loaded {} alert rules", set.rules.len());
+    }
+    set.rules.clone()
+}
+
+// How often each rule fired, for the dashboard
+#[derive(Clone, Debug, Default, Serialize)]
+struct RuleStats {
+    matches: u64,
+    // Matches inside the cooldown, counted but not acted on
+    suppressed: u64,
+    last_fired: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    last_fired_at: Option<Instant>,
+}
+
+fn stats() -> &'static Mutex<HashMap<String, RuleStats>> {
+    static STATS: OnceLock<Mutex<HashMap<String, RuleStats>>> = OnceLock::new();
+    STATS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Count the match and decide whether to act, once per cooldown_secs per rule
+fn should_fire(rule: &Rule, cooldown_secs: u64) -> bool {
+    let mut stats = stats().lock().unwrap();
+    let entry = stats.entry(rule.name.clone()).or_default();
+    entry.matches += 1;
+    if entry.last_fired_at.is_some_and(|at| at.elapsed().as_secs() < cooldown_secs) {
+        entry.suppressed += 1;
+        return false;
+    }
+    entry.last_fired_at = Some(Instant::now());
+    entry.last_fired = Some(Utc::now());
+    true
+}
+
+fn fire(rule: &Rule, event: &BotEvent) {
+    let subject = match &rule.message {
+        Some(message) => fill(message, event),
+        None => format!("Alert rule {} matched a {} event", rule.name, event.kind),
+    };
+    let operator = format!("alert_rule:{}", rule.name);
+    info!("Alert rule {} matched {}: {}", rule.name, event.kind, subject);
+    for action in &rule.actions {
+        match action {
+            RuleAction::Alert(severity) => {
+                alerts::raise(&operator, *severity, &subject, &event.payload.to_string());
+            }
+            RuleAction::PauseStrategy(strategy) => {
+                let strategy = fill(strategy, event);
+                if !strategy.is_empty() && !control::paused_strategies().contains_key(&strategy) {
+                    control::pause_strategy(&strategy, &format!("alert rule {}: {}", rule.name, subject));
+                }
+            }
+            RuleAction::PauseAll => {
+                if !control::is_paused() {
+                    control::pause(&operator);
+                }
+            }
+            RuleAction::KillSwitch => {
+                if !control::is_kill_switch_engaged() {
+                    control::engage_kill_switch(&operator);
+                }
+            }
+        }
+    }
+}
+
+// Evaluate every event on the bus against the configured rules until the process exits
+pub async fn run_alert_rules() {
+    let mut bus = events::subscribe();
+    loop {
+        let event = match bus.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("Alert rules fell behind the event bus and skipped {} events", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+
+        let config = load_monitoring_config()["alert_rules"].clone();
+        let cooldown_secs = config["cooldown_secs"].as_u64().unwrap_or(60);
+        for rule in current_rules(&config).iter().filter(|rule| rule.matches(&event)) {
+            if should_fire(rule, cooldown_secs) {
+                fire(rule, &event);
+            }
+        }
+    }
+}
+
+// Configured rules with their match counts, for the dashboard
+pub fn snapshot() -> Value {
+    let rules = rule_set().lock().unwrap();
+    let stats = stats().lock().unwrap();
+    let listed: Vec<Value> = rules
+        .rules
+        .iter()
+        .map(|rule| {
+            json!({
+                "name": rule.name,
+                "event": rule.event,
+                "stats": stats.get(&rule.name).cloned().unwrap_or_default(),
+            })
+        })
+        .collect();
+    json!({ "rules": listed, "configured": rules.source })
+}
+
+// Custom error type for alert rules
+#[derive(Error, Debug)]
+pub enum AlertRuleError {
+    #[error("Alert rule without a name")]
+    MissingName,
+    #[error("Invalid condition: {0}")]
+    InvalidCondition(String),
+    #[error("Invalid action: {0}")]
+    InvalidAction(String),
+}
+
+// Implement conversion for AlertRuleError to Web3 error
+impl From<AlertRuleError> for web3::Error {
+    fn from(error: AlertRuleError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,291 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use log::{error, info, warn};
+use thiserror::Error;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use tokio::time::{sleep, Duration};
+
+use crate::modules::monitoring;
+use crate::modules::templates;
+
+// Every raised alert (including suppressed duplicates) is appended here
+const ALERT_LOG_PATH: &str = "data/alerts.jsonl";
+// Acknowledged alerts stay visible on the dashboard for this long
+const RETAIN_ACKNOWLEDGED_HOURS: i64 = 24;
+
+// Load the monitoring configuration (the "alerts" section holds routing and escalation)
+fn load_monitoring_config() -> Value {
+    let config_path = "config/monitoring_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read monitoring config file");
+    serde_json::from_str(&config_data).expect("Unable to parse monitoring config file")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warn,
+    Critical,
+}
+
+impl Severity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+// One alert as shown on the dashboard; repeats of the same key inside the dedup window only bump the count
+#[derive(Clone, Debug, Serialize)]
+pub struct Alert {
+    pub id: u64,
+    pub key: String,
+    pub severity: Severity,
+    pub subject: String,
+    pub body: String,
+    pub first_raised: DateTime<Utc>,
+    pub last_raised: DateTime<Utc>,
+    pub last_notified: DateTime<Utc>,
+    pub occurrences: u64,
+    pub suppressed: u64,
+    pub acknowledged_by: Option<String>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub escalations: u64,
+    pub last_escalated: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+struct AlertRegistry {
+    next_id: u64,
+    alerts: HashMap<String, Alert>,
+}
+
+fn registry() -> &'static Mutex<AlertRegistry> {
+    static REGISTRY: OnceLock<Mutex<AlertRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(AlertRegistry::default()))
+}
+
+fn channels(config: &Value, severity: Severity) -> Vec<String> {
+    let defaults: &[&str] = match severity {
+        Severity::Info => &["dashboard"],
+        Severity::Warn => &["dashboard", "telegram"],
+        Severity::Critical => &["dashboard", "sms", "email"],
+    };
+    config["routing"][severity.as_str()]
+        .as_array()
+        .map(|list| list.iter().filter_map(|channel| channel.as_str().map(String::from)).collect())
+        .unwrap_or_else(|| defaults.iter().map(|channel| channel.to_string()).collect())
+}
+
+// Deliver to each external channel; "dashboard" needs nothing beyond the registry
+fn deliver(channels: &[String], severity: Severity, subject: &str, body: &str) {
+    for channel in channels {
+        let result = match channel.as_str() {
+            "dashboard" => Ok(()),
+            "email" => monitoring::send_email_notification(subject, body),
+            // SMS and Telegram are async; alerts are raised from sync and async code alike
+            "sms" => {
+                let text = format!("{}: {}", subject, body);
+                match tokio::runtime::Handle::try_current() {
+                    Ok(handle) => {
+                        handle.spawn(async move {
+                            if let Err(e) = monitoring::send_sms_notification(&text).await {
+                                error!("Failed to send SMS alert: {}", e);
+                            }
+                        });
+                        Ok(())
+                    }
+                    Err(_) => {
+                        warn!("No runtime to deliver SMS alert: {}", subject);
+                        Ok(())
+                    }
+                }
+            }
+            "telegram" => {
+                let text = format!("[{}] {}\n{}", severity.as_str().to_uppercase(), subject, body);
+                match tokio::runtime::Handle::try_current() {
+                    Ok(handle) => {
+                        handle.spawn(async move {
+                            if let Err(e) = monitoring::send_telegram_notification(&text).await {
+                                error!("Failed to send Telegram alert: {}", e);
+                            }
+                        });
+                        Ok(())
+                    }
+                    Err(_) => {
+                        warn!("No runtime to deliver Telegram alert: {}", subject);
+                        Ok(())
+                    }
+                }
+            }
+            other => {
+                warn!("Unknown alert channel {}", other);
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            error!("Failed to deliver {} alert via {}: {}", severity.as_str(), channel, e);
+        }
+    }
+}
+
+fn log_alert(alert: &Alert, notified: bool) {
+    let _ = fs::create_dir_all("data");
+    let entry = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "id": alert.id,
+        "key": alert.key,
+        "severity": alert.severity,
+        "subject": alert.subject,
+        "notified": notified,
+        "occurrences": alert.occurrences,
+    });
+    match OpenOptions::new().create(true).append(true).open(ALERT_LOG_PATH) {
+        Ok(mut alert_log) => {
+            if let Err(e) = writeln!(alert_log, "{}", entry) {
+                error!("Failed to write alert log entry: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open alert log: {}", e),
+    }
+}
+
+// Raise an alert. Alerts sharing a key are one alert: a repeat inside dedup_window_secs of the last
+// notification is counted but not sent again, and a repeat raises a new notification after that
+pub fn raise(key: &str, severity: Severity, subject: &str, body: &str) {
+    let config = load_monitoring_config()["alerts"].clone();
+    let window = ChronoDuration::seconds(config["dedup_window_secs"].as_i64().unwrap_or(900));
+    let now = Utc::now();
+
+    let (alert, notify) = {
+        let mut registry = registry().lock().unwrap();
+        registry.next_id += 1;
+        let next_id = registry.next_id;
+        let alert = registry.alerts.entry(key.to_string()).or_insert_with(|| Alert {
+            id: next_id,
+            key: key.to_string(),
+            severity,
+            subject: subject.to_string(),
+            body: body.to_string(),
+            first_raised: now,
+            last_raised: now,
+            last_notified: now - window - ChronoDuration::seconds(1),
+            occurrences: 0,
+            suppressed: 0,
+            acknowledged_by: None,
+            acknowledged_at: None,
+            escalations: 0,
+            last_escalated: None,
+        });
+
+        alert.occurrences += 1;
+        alert.last_raised = now;
+        alert.subject = subject.to_string();
+        alert.body = body.to_string();
+        alert.severity = alert.severity.max(severity);
+        let notify = now - alert.last_notified > window;
+        if notify {
+            alert.last_notified = now;
+            // A fresh notification needs a fresh acknowledgement
+            alert.acknowledged_by = None;
+            alert.acknowledged_at = None;
+            alert.escalations = 0;
+            alert.last_escalated = None;
+        } else {
+            alert.suppressed += 1;
+        }
+        (alert.clone(), notify)
+    };
+
+    log_alert(&alert, notify);
+    if notify {
+        info!("Alert {} ({}): {}", alert.id, severity.as_str(), subject);
+        deliver(&channels(&config, severity), severity, subject, body);
+    }
+}
+
+pub fn acknowledge(id: u64, operator: &str) -> Result<Alert, AlertError> {
+    let mut registry = registry().lock().unwrap();
+    let alert = registry.alerts.values_mut().find(|alert| alert.id == id).ok_or(AlertError::UnknownAlert(id))?;
+    alert.acknowledged_by = Some(operator.to_string());
+    alert.acknowledged_at = Some(Utc::now());
+    info!("Alert {} acknowledged by {}", id, operator);
+    Ok(alert.clone())
+}
+
+// Open alerts first, newest first; acknowledged ones drop off after a day
+pub fn list() -> Vec<Alert> {
+    let cutoff = Utc::now() - ChronoDuration::hours(RETAIN_ACKNOWLEDGED_HOURS);
+    let mut registry = registry().lock().unwrap();
+    registry.alerts.retain(|_, alert| alert.acknowledged_at.map(|at| at > cutoff).unwrap_or(true));
+    let mut alerts: Vec<Alert> = registry.alerts.values().cloned().collect();
+    alerts.sort_by(|a, b| a.acknowledged_at.is_some().cmp(&b.acknowledged_at.is_some()).then(b.last_raised.cmp(&a.last_raised)));
+    alerts
+}
+
+pub fn unacknowledged() -> Vec<Alert> {
+    list().into_iter().filter(|alert| alert.acknowledged_at.is_none()).collect()
+}
+
+// Re-send critical alerts nobody acknowledged within escalate_after_minutes through the escalation
+// channels, repeating every escalation_repeat_minutes until acknowledged
+pub async fn run_alert_escalation() {
+    loop {
+        let config = load_monitoring_config()["alerts"].clone();
+        let after = ChronoDuration::minutes(config["escalate_after_minutes"].as_i64().unwrap_or(15));
+        let repeat = ChronoDuration::minutes(config["escalation_repeat_minutes"].as_i64().unwrap_or(30));
+        let escalation_channels: Vec<String> = config["escalation_channels"]
+            .as_array()
+            .map(|list| list.iter().filter_map(|channel| channel.as_str().map(String::from)).collect())
+            .unwrap_or_else(|| vec![String::from("sms"), String::from("email")]);
+        let now = Utc::now();
+
+        let due: Vec<Alert> = {
+            let mut registry = registry().lock().unwrap();
+            registry
+                .alerts
+                .values_mut()
+                .filter(|alert| alert.severity == Severity::Critical && alert.acknowledged_at.is_none())
+                .filter(|alert| match alert.last_escalated {
+                    Some(last) => now - last > repeat,
+                    None => now - alert.last_notified > after,
+                })
+                .map(|alert| {
+                    alert.escalations += 1;
+                    alert.last_escalated = Some(now);
+                    alert.clone()
+                })
+                .collect()
+        };
+
+        for alert in due {
+            warn!("Escalating unacknowledged critical alert {} ({})", alert.id, alert.subject);
+            let (subject, body) = templates::render("escalation", &[
+                ("count", alert.escalations.to_string()),
+                ("subject", alert.subject.clone()),
+                ("since", alert.last_notified.to_rfc3339()),
+                ("occurrences", alert.occurrences.to_string()),
+                ("body", alert.body.clone()),
+                ("id", alert.id.to_string()),
+            ]);
+            deliver(&escalation_channels, Severity::Critical, &subject, &body);
+        }
+
+        sleep(Duration::from_secs(60)).await;
+    }
+}
+
+// Custom error type for alerting
+#[derive(Error, Debug)]
+pub enum AlertError {
+    #[error("No alert with id {0}")]
+    UnknownAlert(u64),
+}
@@ -0,0 +1,389 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use web3::ethabi::ethereum_types::U512;
+use web3::types::{Address, H256, U256};
+use log::{error, info, warn};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use tokio::time::{sleep, Duration};
+
+use crate::modules::config_audit::{self, ConfigChange};
+use crate::modules::domain::OpportunitySource;
+use crate::modules::fees;
+use crate::modules::lp_yield;
+use crate::modules::pricing::{self, PricingError};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::ramp;
+use crate::modules::receipt_events;
+use crate::modules::runway;
+use crate::modules::storage;
+
+// Per-trade strategy results, current allocations and the decision log
+const PNL_LEDGER_PATH: &str = "data/strategy_pnl.jsonl";
+const ALLOCATIONS_PATH: &str = "data/allocations.json";
+const ALLOCATION_EVENTS_PATH: &str = "data/allocation_events.jsonl";
+
+// Load the allocator configuration
+fn load_allocator_config() -> Value {
+    let config_path = "config/allocator_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read allocator config file");
+    serde_json::from_str(&config_data).expect("Unable to parse allocator config file")
+}
+
+// Append a strategy trade result (signed, in wei, whatever token the strategy traded) to the ledger,
+// tagged with the source that surfaced the opportunity
+pub fn record_trade_result(strategy: &str, source: OpportunitySource, pnl_wei: i128) {
+    let _ = fs::create_dir_all("data");
+    let record = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "strategy": strategy,
+        "source": source,
+        "pnl_wei": pnl_wei.to_string(),
+    });
+
+    match OpenOptions::new().create(true).append(true).open(PNL_LEDGER_PATH) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write strategy PnL record: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open strategy PnL ledger: {}", e),
+    }
+
+    // The performance fee is kept in its own ledger, the strategy PnL above stays gross
+    fees::accrue(strategy, pnl_wei);
+    ramp::record_result(strategy, pnl_wei);
+    storage::record_pnl(strategy, source, pnl_wei);
+}
+
+// Amount as a u128, saturating instead of panicking past u128
+fn saturating_u128(amount: U256) -> u128 {
+    if amount > U256::from(u128::MAX) { u128::MAX } else { amount.low_u128() }
+}
+
+// Signed result of receiving one amount for another, saturating instead of panicking past u128
+pub fn signed_difference(received: U256, spent: U256) -> i128 {
+    let clamp = |amount: U256| if amount > U256::from(i128::MAX as u128) { i128::MAX } else { amount.low_u128() as i128 };
+    if received >= spent {
+        clamp(received - spent)
+    } else {
+        -clamp(spent - received)
+    }
+}
+
+// Realized result of a trade once its transactions are mined: the holder's net flow of each token
+// across their receipts, valued in wei at the current rate, goes to the ledger. Unmined or unpriced
+// trades are logged and left out
+pub async fn record_realized(
+    web3: &web3::Web3<FailoverTransport>,
+    strategy: &str,
+    source: OpportunitySource,
+    holder: Address,
+    tokens: &[Address],
+    tx_hashes: &[H256],
+) {
+    let mut flows = vec![(U256::zero(), U256::zero()); tokens.len()];
+    for tx_hash in tx_hashes {
+        let receipt = match receipt_events::wait(web3, *tx_hash).await {
+            Ok(Some(receipt)) => receipt,
+            Ok(None) => {
+                warn!("{} trade {:?} was not mined in time, its result is not recorded", strategy, tx_hash);
+                return;
+            }
+            Err(e) => {
+                warn!("Could not read the receipt of {} trade {:?}: {}", strategy, tx_hash, e);
+                return;
+            }
+        };
+        for (token, (received, spent)) in tokens.iter().zip(flows.iter_mut()) {
+            let (token_in, token_out) = receipt.flows(*token, holder);
+            *received = received.saturating_add(token_in);
+            *spent = spent.saturating_add(token_out);
+        }
+    }
+
+    let (mut received_wei, mut spent_wei) = (U256::zero(), U256::zero());
+    for (token, (received, spent)) in tokens.iter().zip(flows) {
+        match (pricing::token_to_wei(web3, *token, received).await, pricing::token_to_wei(web3, *token, spent).await) {
+            (Ok(received), Ok(spent)) => {
+                received_wei = received_wei.saturating_add(received);
+                spent_wei = spent_wei.saturating_add(spent);
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                warn!("Could not value the {} trade result in wei: {}", strategy, e);
+                return;
+            }
+        }
+    }
+    record_trade_result(strategy, source, signed_difference(received_wei, spent_wei));
+}
+
+// Result of receiving one amount of token for another, valued in wei at the current rate
+pub async fn pnl_in_wei(web3: &web3::Web3<FailoverTransport>, token: Address, received: U256, spent: U256) -> Result<i128, PricingError> {
+    let received = pricing::token_to_wei(web3, token, received).await?;
+    let spent = pricing::token_to_wei(web3, token, spent).await?;
+    Ok(signed_difference(received, spent))
+}
+
+// Largest amount of token the strategy may trade, the allocation converted from wei at the current
+// rate. Without an allocation, or a rate to convert it at, the amount is left as it is
+pub async fn cap_to_allocation(web3: &web3::Web3<FailoverTransport>, strategy: &str, token: Address, amount: U256) -> U256 {
+    let allocated = allocation_for(strategy);
+    if allocated.is_zero() {
+        return amount;
+    }
+    match pricing::wei_to_token(web3, token, allocated).await {
+        Ok(allocated) => amount.min(allocated),
+        Err(e) => {
+            warn!("Could not convert the {} allocation into {:?}: {}", strategy, token, e);
+            amount
+        }
+    }
+}
+
+// A strategy is about to take an opportunity needing amount_wei of capital. High-value ones get
+// capital parked in passive LP positions pulled back to the wallet
+pub fn request_capital(strategy: &str, amount_wei: U256, profit_wei: U256) {
+    lp_yield::request_withdrawal(strategy, amount_wei, profit_wei);
+}
+
+// Trade results per strategy inside the trailing window
+fn results_by_strategy(window: ChronoDuration) -> HashMap<String, Vec<f64>> {
+    results_by(window, "strategy")
+}
+
+// Trade results inside the trailing window, grouped by a ledger field. Results recorded before
+// sources were tagged count as "untagged"
+fn results_by(window: ChronoDuration, field: &str) -> HashMap<String, Vec<f64>> {
+    let cutoff = Utc::now() - window;
+    let mut results: HashMap<String, Vec<f64>> = HashMap::new();
+
+    let contents = fs::read_to_string(PNL_LEDGER_PATH).unwrap_or_default();
+    for line in contents.lines() {
+        let record: Value = match serde_json::from_str(line) {
+            Ok(record) => record,
+            Err(_) => continue,
+        };
+        let recent = record["timestamp"]
+            .as_str()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc) >= cutoff)
+            .unwrap_or(false);
+        if !recent {
+            continue;
+        }
+        let pnl = record["pnl_wei"].as_str().and_then(|pnl| pnl.parse::<f64>().ok()).unwrap_or(0.0);
+        let key = record[field].as_str().unwrap_or(if field == "source" { "untagged" } else { "unknown" }).to_string();
+        results.entry(key).or_default().push(pnl);
+    }
+    results
+}
+
+// Most recent trade results, newest first, optionally for one strategy
+pub fn recent_trade_results(limit: usize, strategy: Option<&str>) -> Vec<Value> {
+    let contents = fs::read_to_string(PNL_LEDGER_PATH).unwrap_or_default();
+    contents
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|record| strategy.map(|strategy| record["strategy"] == strategy).unwrap_or(true))
+        .take(limit)
+        .collect()
+}
+
+// Summed trade results per strategy inside the trailing window
+pub fn trailing_pnl(window: ChronoDuration) -> HashMap<String, f64> {
+    results_by_strategy(window)
+        .into_iter()
+        .map(|(strategy, results)| (strategy, results.iter().sum()))
+        .collect()
+}
+
+// PnL per opportunity source inside the trailing window: trades, wins, total and mean per trade
+pub fn pnl_by_source(window: ChronoDuration) -> Value {
+    let sources: HashMap<String, Value> = results_by(window, "source")
+        .into_iter()
+        .map(|(source, results)| {
+            let total: f64 = results.iter().sum();
+            (source, json!({
+                "trades": results.len(),
+                "wins": results.iter().filter(|pnl| **pnl > 0.0).count(),
+                "total_pnl_wei": total,
+                "mean_pnl_wei": total / results.len().max(1) as f64,
+            }))
+        })
+        .collect();
+    json!(sources)
+}
+
+// Risk-adjusted return: mean net result per trade over its standard deviation, after the strategy's gas burn
+pub fn risk_adjusted_scores(window: ChronoDuration, gas_log_path: &str) -> HashMap<String, f64> {
+    let gas_burn = runway::burn_by_strategy(gas_log_path, window);
+
+    results_by_strategy(window)
+        .into_iter()
+        .map(|(strategy, results)| {
+            let count = results.len() as f64;
+            let gas_per_trade = gas_burn.get(&strategy).map(|burn| saturating_u128(*burn) as f64).unwrap_or(0.0) / count;
+            let net: Vec<f64> = results.iter().map(|pnl| pnl - gas_per_trade).collect();
+            let mean = net.iter().sum::<f64>() / count;
+            let variance = net.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / count;
+            let score = if variance > 0.0 { mean / variance.sqrt() } else if mean > 0.0 { 1.0 } else { 0.0 };
+            (strategy, score)
+        })
+        .collect()
+}
+
+// Current weights, defaulting to an even split over the configured strategies
+pub fn current_allocations() -> HashMap<String, f64> {
+    if let Some(saved) = fs::read_to_string(ALLOCATIONS_PATH).ok().and_then(|data| serde_json::from_str(&data).ok()) {
+        return saved;
+    }
+    let config = load_allocator_config();
+    let strategies: Vec<String> = config["strategies"].as_object().map(|s| s.keys().cloned().collect()).unwrap_or_default();
+    let even = 1.0 / strategies.len().max(1) as f64;
+    strategies.into_iter().map(|strategy| (strategy, even)).collect()
+}
+
+// Virtual capital the strategy may deploy right now, never more than its ramp stage allows
+pub fn allocation_for(strategy: &str) -> U256 {
+    let config = load_allocator_config();
+    let total = config["total_capital_wei"]
+        .as_str()
+        .and_then(|total| U256::from_dec_str(total).ok())
+        .unwrap_or_default();
+    let weight = current_allocations().get(strategy).copied().unwrap_or(0.0);
+    // Weight applied in parts per million in integer math, so totals past u128 neither panic nor lose precision
+    let weight_ppm = U512::from((weight.clamp(0.0, 1.0) * 1_000_000.0).round() as u64);
+    let allocated = U256::try_from(U512::from(total) * weight_ppm / U512::from(1_000_000u64)).unwrap_or(total);
+    match ramp::max_size(strategy) {
+        Some(limit) if allocated.is_zero() => limit,
+        Some(limit) => allocated.min(limit),
+        None => allocated,
+    }
+}
+
+// Clamp weights to their bounds and renormalize, repeating until bounds and sum agree
+fn clamp_to_bounds(weights: &mut HashMap<String, f64>, bounds: &HashMap<String, (f64, f64)>) {
+    for _ in 0..10 {
+        for (strategy, weight) in weights.iter_mut() {
+            let (min, max) = bounds.get(strategy).copied().unwrap_or((0.0, 1.0));
+            *weight = weight.clamp(min, max);
+        }
+        let sum: f64 = weights.values().sum();
+        if sum <= 0.0 || (sum - 1.0).abs() < 1e-9 {
+            return;
+        }
+        for weight in weights.values_mut() {
+            *weight /= sum;
+        }
+    }
+}
+
+// Move allocations toward score-proportional targets, at most max_shift per strategy per round
+pub fn reallocate() -> HashMap<String, f64> {
+    let config = load_allocator_config();
+    let window = ChronoDuration::hours(config["window_hours"].as_i64().unwrap_or(72));
+    let max_shift = config["max_shift_per_round"].as_f64().unwrap_or(0.05);
+    let gas_log_path = config["gas_log_path"].as_str().unwrap_or("data/gas_spend.jsonl");
+
+    let bounds: HashMap<String, (f64, f64)> = config["strategies"]
+        .as_object()
+        .map(|strategies| {
+            strategies
+                .iter()
+                .map(|(name, limits)| (name.clone(), (limits["min"].as_f64().unwrap_or(0.0), limits["max"].as_f64().unwrap_or(1.0))))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let current = current_allocations();
+    let scores = risk_adjusted_scores(window, gas_log_path);
+    let positive_total: f64 = bounds.keys().map(|strategy| scores.get(strategy).copied().unwrap_or(0.0).max(0.0)).sum();
+    if positive_total <= 0.0 {
+        info!("Allocator: no strategy has a positive risk-adjusted return, keeping allocations");
+        return current;
+    }
+
+    let mut next: HashMap<String, f64> = bounds
+        .keys()
+        .map(|strategy| {
+            let target = scores.get(strategy).copied().unwrap_or(0.0).max(0.0) / positive_total;
+            let from = current.get(strategy).copied().unwrap_or(0.0);
+            (strategy.clone(), from + (target - from).clamp(-max_shift, max_shift))
+        })
+        .collect();
+    clamp_to_bounds(&mut next, &bounds);
+
+    record_reallocation(&current, &next, &scores);
+    next
+}
+
+// Persist the new weights and log the decision as an auditable event
+fn record_reallocation(previous: &HashMap<String, f64>, next: &HashMap<String, f64>, scores: &HashMap<String, f64>) {
+    let changes: Vec<ConfigChange> = next
+        .iter()
+        .filter(|(strategy, weight)| (previous.get(*strategy).copied().unwrap_or(0.0) - **weight).abs() > 1e-4)
+        .map(|(strategy, weight)| ConfigChange {
+            path: strategy.clone(),
+            old_value: previous.get(strategy).map(|old| json!(old)),
+            new_value: Some(json!(weight)),
+        })
+        .collect();
+    if changes.is_empty() {
+        return;
+    }
+
+    let _ = fs::create_dir_all("data");
+    if let Err(e) = fs::write(ALLOCATIONS_PATH, json!(next).to_string()) {
+        error!("Failed to persist allocations: {}", e);
+        return;
+    }
+
+    let event = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "previous": previous,
+        "allocations": next,
+        "scores": scores,
+    });
+    match OpenOptions::new().create(true).append(true).open(ALLOCATION_EVENTS_PATH) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", event) {
+                error!("Failed to write allocation event: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open allocation event log: {}", e),
+    }
+    config_audit::record_audit(ALLOCATIONS_PATH, "allocator", "allocator", &changes);
+
+    for change in &changes {
+        info!("Allocator: {}", change.describe());
+    }
+}
+
+// Allocations and recent decisions for the dashboard
+pub fn allocation_report() -> Value {
+    let events: Vec<Value> = fs::read_to_string(ALLOCATION_EVENTS_PATH)
+        .unwrap_or_default()
+        .lines()
+        .rev()
+        .take(50)
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    json!({
+        "allocations": current_allocations(),
+        "events": events,
+    })
+}
+
+// Periodic reallocation
+pub async fn run_allocator() {
+    let interval = load_allocator_config()["rebalance_interval_secs"].as_u64().unwrap_or(21_600);
+
+    loop {
+        reallocate();
+        sleep(Duration::from_secs(interval)).await;
+    }
+}
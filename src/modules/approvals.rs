@@ -0,0 +1,276 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use web3::contract::{Contract, Options};
+use web3::types::{Address, H256, U256};
+use log::{error, info, warn};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::ens;
+use crate::modules::events;
+use crate::modules::execution::{self, Call, ExecutionBackend, ExecutionError};
+use crate::modules::multicall::{self, MulticallError};
+use crate::modules::providers::FailoverTransport;
+
+// Load the approval sizing and revocation configuration
+fn load_approvals_config() -> Value {
+    let config_path = "config/approvals_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read approvals config file");
+    serde_json::from_str(&config_data).expect("Unable to parse approvals config file")
+}
+
+fn ledger_path(config: &Value) -> String {
+    config["ledger_path"].as_str().unwrap_or("data/approvals.jsonl").to_string()
+}
+
+fn read_ledger(config: &Value) -> Vec<Value> {
+    fs::read_to_string(ledger_path(config))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .collect()
+}
+
+fn append(config: &Value, record: &Value) {
+    let path = ledger_path(config);
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write approval ledger: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open approval ledger: {}", e),
+    }
+}
+
+// Rolling cap configured for a token, if any
+fn rolling_cap(config: &Value, token: Address) -> Option<U256> {
+    config["rolling_caps"].as_object()?.iter().find_map(|(key, cap)| {
+        (ens::parse_address(key)? == token).then(|| cap.as_str().and_then(|cap| U256::from_dec_str(cap).ok())).flatten()
+    })
+}
+
+// Size the approvals in a batch. "per_trade" keeps each approval at the amount the trade needs;
+// "rolling_cap" tops the allowance up to the token's cap so later trades reuse it until it runs down.
+// Zero-amount approvals are revocations and pass through untouched
+pub fn size_approvals(calls: Vec<Call>) -> Vec<Call> {
+    let config = load_approvals_config();
+    if config["mode"].as_str().unwrap_or("per_trade") != "rolling_cap" {
+        return calls;
+    }
+    calls
+        .into_iter()
+        .map(|call| match execution::decode_approve(&call) {
+            Some((spender, amount)) if !amount.is_zero() => match rolling_cap(&config, call.to) {
+                Some(cap) if cap > amount => execution::approve_call(call.to, spender, cap).unwrap_or(call),
+                _ => call,
+            },
+            _ => call,
+        })
+        .collect()
+}
+
+// Record the approvals granted and the routers used by a submitted batch
+pub fn record_usage(owner: Address, calls: &[Call], tx_hash: H256) {
+    let config = load_approvals_config();
+    let timestamp = Utc::now().to_rfc3339();
+    let mut spenders = Vec::new();
+    for call in calls {
+        match execution::decode_approve(call) {
+            Some((_, amount)) if amount.is_zero() => {}
+            Some((spender, amount)) => append(&config, &json!({
+                "kind": "approval",
+                "timestamp": timestamp,
+                "owner": owner,
+                "token": call.to,
+                "spender": spender,
+                "amount": amount.to_string(),
+                "tx_hash": tx_hash,
+            })),
+            None => spenders.push(call.to),
+        }
+    }
+    if !spenders.is_empty() {
+        append(&config, &json!({ "kind": "use", "timestamp": timestamp, "owner": owner, "spenders": spenders, "tx_hash": tx_hash }));
+    }
+}
+
+// An allowance granted by the bot and not revoked since, with the last time its spender was used
+#[derive(Clone, Debug)]
+pub struct OutstandingApproval {
+    pub owner: Address,
+    pub token: Address,
+    pub spender: Address,
+    pub last_used: DateTime<Utc>,
+}
+
+fn parse_timestamp(record: &Value) -> Option<DateTime<Utc>> {
+    record["timestamp"].as_str().and_then(|ts| DateTime::parse_from_rfc3339(ts).ok()).map(|ts| ts.with_timezone(&Utc))
+}
+
+fn parse_address(record: &Value, key: &str) -> Option<Address> {
+    serde_json::from_value(record[key].clone()).ok()
+}
+
+fn outstanding(ledger: &[Value]) -> Vec<OutstandingApproval> {
+    let mut approvals: HashMap<(Address, Address, Address), DateTime<Utc>> = HashMap::new();
+    let mut last_use: HashMap<(Address, Address), DateTime<Utc>> = HashMap::new();
+    for record in ledger {
+        let (owner, timestamp) = match (parse_address(record, "owner"), parse_timestamp(record)) {
+            (Some(owner), Some(timestamp)) => (owner, timestamp),
+            _ => continue,
+        };
+        match record["kind"].as_str() {
+            Some("approval") => {
+                if let (Some(token), Some(spender)) = (parse_address(record, "token"), parse_address(record, "spender")) {
+                    approvals.insert((owner, token, spender), timestamp);
+                }
+            }
+            Some("use") => {
+                let spenders: Vec<Address> = serde_json::from_value(record["spenders"].clone()).unwrap_or_default();
+                for spender in spenders {
+                    last_use.insert((owner, spender), timestamp);
+                }
+            }
+            Some("revoke") => {
+                if let (Some(token), Some(spender)) = (parse_address(record, "token"), parse_address(record, "spender")) {
+                    approvals.remove(&(owner, token, spender));
+                }
+            }
+            _ => {}
+        }
+    }
+    approvals
+        .into_iter()
+        .map(|((owner, token, spender), approved_at)| OutstandingApproval {
+            owner,
+            token,
+            spender,
+            last_used: last_use.get(&(owner, spender)).copied().unwrap_or(approved_at).max(approved_at),
+        })
+        .collect()
+}
+
+// Revoke every allowance the backend still holds towards routers not used within stale_after_days
+pub async fn revoke_stale_approvals(web3: &web3::Web3<FailoverTransport>) -> Result<usize, ApprovalsError> {
+    let config = load_approvals_config();
+    let stale_after = ChronoDuration::days(config["stale_after_days"].as_i64().unwrap_or(14));
+    let backend = ExecutionBackend::from_config(web3)?;
+    let owner = backend.recipient();
+    let cutoff = Utc::now() - stale_after;
+
+    let stale: Vec<OutstandingApproval> = outstanding(&read_ledger(&config))
+        .into_iter()
+        .filter(|approval| approval.owner == owner && approval.last_used <= cutoff)
+        .collect();
+    // Every stale allowance in one multicall; one that didn't come back is read on its own
+    let queries: Vec<(Address, Address, Address)> = stale.iter().map(|approval| (approval.token, owner, approval.spender)).collect();
+    let allowances = multicall::allowances(web3, &queries).await?;
+
+    let mut revoked = 0;
+    for (approval, batched) in stale.into_iter().zip(allowances) {
+        let allowance: U256 = match batched {
+            Some(allowance) => allowance,
+            None => {
+                let erc20 = Contract::from_json(web3.eth(), approval.token, include_bytes!("abi/erc20_abi.json"))?;
+                erc20.query("allowance", (owner, approval.spender), None, Options::default(), None).await?
+            }
+        };
+
+        let mut record = json!({
+            "kind": "revoke",
+            "timestamp": Utc::now().to_rfc3339(),
+            "owner": owner,
+            "token": approval.token,
+            "spender": approval.spender,
+            "allowance": allowance.to_string(),
+            "last_used": approval.last_used.to_rfc3339(),
+        });
+        // Already spent down to zero: close it in the ledger without sending anything
+        if allowance.is_zero() {
+            append(&config, &record);
+            continue;
+        }
+
+        let revoke = execution::approve_call(approval.token, approval.spender, U256::zero())?;
+        match backend.execute_calls(web3, "approvals", vec![revoke]).await {
+            Ok(tx_hash) => {
+                record["tx_hash"] = json!(tx_hash);
+                append(&config, &record);
+                events::emit("approval_revoked", record.clone());
+                info!("Revoked stale allowance of {:?} to {:?}: {:?}", approval.token, approval.spender, tx_hash);
+                revoked += 1;
+            }
+            Err(e) => {
+                record["kind"] = json!("revoke_failed");
+                record["error"] = json!(e.to_string());
+                append(&config, &record);
+                warn!("Failed to revoke allowance of {:?} to {:?}: {}", approval.token, approval.spender, e);
+            }
+        }
+    }
+    Ok(revoked)
+}
+
+// Sweep stale allowances on the configured interval
+pub async fn run_approval_sweep(web3: web3::Web3<FailoverTransport>) {
+    loop {
+        let config = load_approvals_config();
+        match revoke_stale_approvals(&web3).await {
+            Ok(0) => {}
+            Ok(revoked) => info!("Approval sweep revoked {} stale allowances", revoked),
+            Err(e) => warn!("Approval sweep failed: {}", e),
+        }
+        sleep(Duration::from_secs(config["sweep_interval_secs"].as_u64().unwrap_or(86400))).await;
+    }
+}
+
+// Sizing mode, outstanding allowances and the latest ledger entries for the dashboard
+pub fn summary(limit: usize) -> Value {
+    let config = load_approvals_config();
+    let ledger = read_ledger(&config);
+    let approvals: Vec<Value> = outstanding(&ledger)
+        .iter()
+        .map(|approval| json!({
+            "owner": approval.owner,
+            "token": approval.token,
+            "spender": approval.spender,
+            "last_used": approval.last_used.to_rfc3339(),
+        }))
+        .collect();
+    json!({
+        "mode": config["mode"].as_str().unwrap_or("per_trade"),
+        "stale_after_days": config["stale_after_days"],
+        "outstanding": approvals,
+        "recent": ledger.iter().rev().take(limit).collect::<Vec<_>>(),
+    })
+}
+
+// Custom error type for approval management
+#[derive(Error, Debug)]
+pub enum ApprovalsError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Contract error: {0}")]
+    ContractError(#[from] web3::contract::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Multicall error: {0}")]
+    MulticallError(#[from] MulticallError),
+}
+
+// Implement conversion for ApprovalsError to Web3 error
+impl From<ApprovalsError> for web3::Error {
+    fn from(error: ApprovalsError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
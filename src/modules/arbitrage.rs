@@ -1,26 +1,56 @@
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::fs;
-use web3::types::{U256, Address};
-use web3::contract::Options;
-use web3::contract::Contract;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use web3::types::{U256, Address, BlockId, BlockNumber};
+use web3::contract::{Contract, Options};
 use log::{error, info};
 use thiserror::Error;
 use tokio::time::{sleep, Duration};
-use web3::transports::Http;
-use std::str::FromStr;
 use tokio::task::spawn;
 use futures::future::join_all;
+use async_trait::async_trait;
+use web3::types::H256;
+use web3::Transport;
+use chrono::Utc;
+use crate::modules::actions;
+use crate::modules::allocator;
+use crate::modules::config::{ArbitrageConfig, StrategyConfig};
+use crate::modules::deadlines::{DeadlineMissed, DecisionBudget};
+use crate::modules::domain::{self, ExecutionResult, ExecutionStatus, OpportunitySource, QuoteWatermark, TradeLeg, TradePlan};
+use crate::modules::gas::{self, Urgency};
+use crate::modules::gas_feed;
+use crate::modules::ens;
+use crate::modules::execution::{ExecutionBackend, ExecutionError, ExecutorBatch};
+use crate::modules::heatmap;
+use crate::modules::inventory;
+use crate::modules::postmortem;
+use crate::modules::pricing::{self, PricingError};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::replay;
+use crate::modules::rfq::{self, RfqError, RfqLegPlan};
+use crate::modules::sandwich;
+use crate::modules::sequencer_feed;
+use crate::modules::signer::{self, SignerError};
+use crate::modules::sizing;
+use crate::modules::pair_class;
+use crate::modules::pair_discovery;
+use crate::modules::path_finder::{self, PathFinderError};
+use crate::modules::receipt_events;
+use crate::modules::scan_interval::AdaptiveInterval;
+use crate::modules::slippage;
+use crate::modules::strategy::{Opportunity, Strategy, StrategyError};
+use crate::modules::testnet;
+use crate::modules::venues::{self, Venue, VenueError, VenueVersion};
+
+// Fresh quotes to take after plans are refused as stale before giving up on an opportunity
+const MAX_REQUOTES: u32 = 3;
 
 // Load arbitrage config
 fn load_arbitrage_config() -> Value {
     let config_path = "config/arbitrage_config.json";
     let config_data = std::fs::read_to_string(config_path).expect("Unable to read arbitrage config file");
-    serde_json::from_str(&config_data).expect("Unable to parse arbitrage config file")
-}
-
-// Convert string to Address
-fn str_to_address(address: &str) -> Address {
-    Address::from_str(address).unwrap()
+    testnet::apply(config_path, serde_json::from_str(&config_data).expect("Unable to parse arbitrage config file"))
 }
 
 // Dynamic loan calculation for arbitrage opportunities
@@ -30,167 +60,569 @@ pub fn calculate_dynamic_loan_amount(expected_profit: U256, gas_fee: U256, slipp
     U256::from(max_loan_amount).saturating_sub(gas_fee)
 }
 
-// Profitability Tracking
+// Profitability Tracking: gas_fees must already be in the profit token (see pricing::gas_cost_in_token)
 pub fn is_profitable(profit: U256, gas_fees: U256) -> bool {
     profit > gas_fees
 }
 
+// Everything the round-trip decision depends on, recorded so the decision can be replayed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArbitrageInputs {
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: U256,
+    pub buy_venue: String,
+    pub quoted_out: U256,
+    pub buy_factor: Option<f64>,
+    pub sell_venue: String,
+    pub quoted_back: U256,
+    pub sell_factor: Option<f64>,
+    pub gas_fees: U256,
+    // Spread the pair's class requires; decisions recorded before pair classes had none
+    #[serde(default)]
+    pub min_spread_bps: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArbitrageDecision {
+    pub amount_out: U256,
+    pub amount_back: U256,
+    pub profit: U256,
+    pub execute: bool,
+}
+
+// Pure round-trip decision: slippage-corrected outputs, profit and whether it clears both the pair
+// class's spread threshold and gas
+pub fn decide(inputs: &ArbitrageInputs) -> ArbitrageDecision {
+    let amount_out = slippage::apply_factor(inputs.quoted_out, inputs.buy_factor);
+    let amount_back = slippage::apply_factor(inputs.quoted_back, inputs.sell_factor);
+    let profit = amount_back.saturating_sub(inputs.amount_in);
+    let clears_spread = pair_class::spread_bps(inputs.amount_in, amount_back) >= inputs.min_spread_bps;
+    ArbitrageDecision { amount_out, amount_back, profit, execute: amount_back > inputs.amount_in && clears_spread && is_profitable(profit, inputs.gas_fees) }
+}
+
 // Scan DEX prices and identify arbitrage opportunities
 pub async fn scan_for_opportunities(
-    web3: web3::Web3<Http>,
+    web3: web3::Web3<FailoverTransport>,
     token_pairs: Vec<(Address, Address)>,
     check_interval: u64
 ) {
+    let mut interval = AdaptiveInterval::from_config("Arbitrage", &load_arbitrage_config(), check_interval);
+
     loop {
         let mut tasks = vec![];
 
-        for (token_in, token_out) in token_pairs.iter().cloned() {
+        // Discovered pairs take over from the configured list once discovery has run
+        let discovered = pair_discovery::active_pairs();
+        let pairs = if discovered.is_empty() { token_pairs.clone() } else { discovered };
+
+        for (token_in, token_out) in pairs {
             let web3_clone = web3.clone();
             tasks.push(spawn(async move {
-                if let Err(e) = check_arbitrage_opportunity(&web3_clone, token_in, token_out).await {
-                    error!("Error checking arbitrage opportunity: {:?}", e);
+                match check_arbitrage_opportunity(&web3_clone, token_in, token_out, OpportunitySource::ScheduledScan).await {
+                    Ok(found) => found,
+                    Err(e) => {
+                        error!("Error checking arbitrage opportunity: {:?}", e);
+                        false
+                    }
                 }
             }));
         }
 
-        join_all(tasks).await;
-        sleep(Duration::from_secs(check_interval)).await;
+        let found_any = join_all(tasks).await.into_iter().any(|found| found.unwrap_or(false));
+        sleep(interval.record(found_any)).await;
     }
 }
 
-// Check arbitrage opportunity between two tokens
+// Check arbitrage opportunity between two tokens, returning whether a profitable one was found. A
+// plan refused for stale quotes is priced again from fresh quotes, never resubmitted as it was
 pub async fn check_arbitrage_opportunity(
-    web3: &web3::Web3<Http>,
+    web3: &web3::Web3<FailoverTransport>,
     token_in: Address,
     token_out: Address,
-) -> Result<(), ArbitrageError> {
+    source: OpportunitySource,
+) -> Result<bool, ArbitrageError> {
+    for attempt in 1..=MAX_REQUOTES {
+        match quote_and_execute(web3, token_in, token_out, source).await {
+            Err(ArbitrageError::ExecutionError(ExecutionError::StaleQuotes { quoted_block, head })) => {
+                info!("Arbitrage quotes from block {} stale at {}, re-quoting ({}/{})", quoted_block, head, attempt, MAX_REQUOTES);
+            }
+            result => return result,
+        }
+    }
+    Ok(false)
+}
+
+async fn quote_and_execute(
+    web3: &web3::Web3<FailoverTransport>,
+    token_in: Address,
+    token_out: Address,
+    source: OpportunitySource,
+) -> Result<bool, ArbitrageError> {
     let config = load_arbitrage_config();
-    
-    let uniswap_router_contract = Contract::from_json(
-        web3.eth(),
-        str_to_address(&config["uniswap_router_address"].as_str().unwrap()),
-        include_bytes!("../abi/uniswap_router_abi.json")
-    ).expect("Invalid Uniswap router ABI");
+    // Stable and correlated pairs trade bigger sizes on thinner spreads than volatile ones
+    let pair = pair_class::params_for(&config, token_in, token_out);
+    // Never size beyond the capital the allocator currently gives arbitrage
+    let amount_in = allocator::cap_to_allocation(web3, "arbitrage", token_in, pair.quote_amount).await;
+
+    // Plans carry the block their quotes were read at; recorded decisions are keyed by it too
+    let quoted_at = QuoteWatermark::at_head(web3).await?;
+    let block = replay::recording_enabled().then_some(quoted_at.block);
+    // Quoting and gas pricing share the strategy's per-block decision budget
+    let budget = DecisionBudget::start("arbitrage", None);
+
+    // Best venue (V2 pair or any V3 fee tier, on any DEX) to buy token_out, then the best one to sell it back
+    let (buy_venue, quoted_out) = match budget.stage("buy_quote", venues::best_quote(web3, token_in, token_out, amount_in)).await?? {
+        Some(quote) => quote,
+        None => return Ok(false),
+    };
+    // RFQ makers can beat the AMMs on the buy leg; a quote covering only part of the leg is topped
+    // up from the best AMM venue
+    let rfq_plan = if rfq::enabled() {
+        let taker = ExecutionBackend::from_config(web3)?.recipient();
+        match budget.stage("rfq_quote", rfq::plan_leg(web3, token_in, token_out, amount_in, taker)).await? {
+            Ok(plan) => plan.filter(|plan| plan.total_out() > quoted_out),
+            Err(e) => {
+                error!("RFQ planning for {:?} -> {:?} failed: {}", token_in, token_out, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // Theoretical AMM output corrected by the pool's calibrated realized/expected factor; firm RFQ
+    // quotes don't slip, and their AMM remainder is re-quoted when it is routed
+    let (buy_label, quoted_out, buy_factor) = match &rfq_plan {
+        Some(plan) => (plan.label(), plan.total_out(), None),
+        None => (buy_venue.label(), quoted_out, slippage::factor(buy_venue.pool)),
+    };
+    let sell_quote = venues::best_quote(web3, token_out, token_in, slippage::apply_factor(quoted_out, buy_factor));
+    let (sell_venue, quoted_back) = match budget.stage("sell_quote", sell_quote).await?? {
+        Some(quote) => quote,
+        None => return Ok(false),
+    };
 
-    let sushiswap_router_contract = Contract::from_json(
-        web3.eth(),
-        str_to_address(&config["sushiswap_router_address"].as_str().unwrap()),
-        include_bytes!("../abi/sushiswap_router_abi.json")
-    ).expect("Invalid Sushiswap router ABI");
+    // Gas priced in token_in, the token the round trip's profit is measured in
+    let gas_units = U256::from(config["estimated_gas_units"].as_u64().unwrap_or(350_000));
+    let urgency = Urgency::for_strategy("arbitrage");
+    let gas_fees = budget.stage("gas_pricing", pricing::gas_cost_in_token(web3, token_in, gas_units, urgency)).await??;
 
-    let price_uniswap = get_token_price(web3, &uniswap_router_contract, token_in, token_out).await?;
-    let price_sushiswap = get_token_price(web3, &sushiswap_router_contract, token_in, token_out).await?;
+    // The configured size is a cap: the AMM round trip is sized from both pools' reserves and the
+    // sized amount quoted again. Curve pools and failed reads keep the full size
+    let (mut amount_in, mut quoted_out, mut quoted_back) = (amount_in, quoted_out, quoted_back);
+    if rfq_plan.is_none() && sizing::enabled() {
+        let sized = sizing::size_arbitrage(web3, &buy_venue, &sell_venue, token_in, token_out, amount_in, gas_fees);
+        match budget.stage("sizing", sized).await? {
+            Ok(Some(sized)) if sized.amount_in < amount_in => {
+                amount_in = sized.amount_in;
+                quoted_out = buy_venue.quote(web3, token_in, token_out, amount_in).await?;
+                quoted_back = sell_venue.quote(web3, token_out, token_in, slippage::apply_factor(quoted_out, buy_factor)).await?;
+            }
+            Ok(_) => {}
+            Err(e) => error!("Could not size {} -> {} from reserves: {}", buy_label, sell_venue.label(), e),
+        }
+    }
+
+    let inputs = ArbitrageInputs {
+        token_in,
+        token_out,
+        amount_in,
+        buy_venue: buy_label,
+        quoted_out,
+        buy_factor,
+        sell_venue: sell_venue.label(),
+        quoted_back,
+        sell_factor: slippage::factor(sell_venue.pool),
+        gas_fees,
+        min_spread_bps: pair.min_spread_bps,
+    };
+    let decision = decide(&inputs);
+    budget.finish();
+    heatmap::record_spread(token_in, token_out, &inputs.buy_venue, &inputs.sell_venue, amount_in, decision.amount_back);
+    if let Some(block) = block {
+        replay::record_decision("arbitrage", block, serde_json::json!(inputs), serde_json::json!(decision));
+    }
+
+    let ArbitrageDecision { amount_out, amount_back, profit, execute } = decision;
+
+    // Gas is paid at the inclusion block's base fee; skip trades a plausible rise would turn into a loss
+    if execute {
+        match gas::suggest_fees(web3, urgency).await {
+            Ok(fees) => {
+                let profit_wei = pricing::token_to_wei(web3, token_in, profit).await?;
+                let sensitivity = gas_feed::fee_sensitivity(profit_wei, gas_units, fees.priority_fee, fees.base_fee);
+                if let Some(flips_at) = sensitivity.flips_at {
+                    info!(
+                        "Declining arbitrage {} -> {}: profit {:?} turns negative at a base fee of {} gwei",
+                        inputs.buy_venue,
+                        inputs.sell_venue,
+                        profit,
+                        flips_at.as_u128() as f64 / 1e9
+                    );
+                    return Ok(false);
+                }
+            }
+            Err(e) => error!("No gas estimate for the base fee sensitivity check: {}", e),
+        }
+    }
+    if execute {
+        info!(
+            "Profitable {} arbitrage opportunity found: buy on {}, sell on {}, Profit: {:?}, Gas: {:?}",
+            pair.class.as_str(),
+            inputs.buy_venue,
+            inputs.sell_venue,
+            profit,
+            gas_fees
+        );
+        // Read-only instances stop at the decision, which is already recorded
+        if signer::read_only() {
+            return Ok(true);
+        }
+        // High-value trades pull capital back from passive LP positions
+        if let (Ok(amount_wei), Ok(profit_wei)) = (pricing::token_to_wei(web3, token_in, amount_in).await, pricing::token_to_wei(web3, token_in, profit).await) {
+            allocator::request_capital("arbitrage", amount_wei, profit_wei);
+        }
 
-    if price_uniswap > price_sushiswap {
-        let profit = price_uniswap - price_sushiswap;
-        let gas_fees = U256::from(300000); // Example gas fees
-        if is_profitable(profit, gas_fees) {
-            info!("Profitable arbitrage opportunity found: Profit: {:?}, Gas: {:?}", profit, gas_fees);
-            execute_multi_leg_arbitrage(web3, profit).await?;
+        // Buy and sell legs run in one operation: batched by the backend, or by the executor contract
+        let backend = ExecutionBackend::from_config(web3)?;
+        if let Some(rfq_plan) = rfq_plan {
+            execute_rfq_arbitrage(web3, &backend, &rfq_plan, sell_venue, pair.slippage_tolerance, source).await?;
+        } else {
+            let plan = TradePlan {
+                strategy: String::from("arbitrage"),
+                source,
+                legs: vec![
+                    TradeLeg { venue: buy_venue, token_in, token_out, amount_in, expected_out: amount_out },
+                    TradeLeg { venue: sell_venue, token_in: token_out, token_out: token_in, amount_in: amount_out, expected_out: amount_back },
+                ],
+                expected_profit: profit,
+                gas_fees,
+                actions: actions::for_strategy("arbitrage"),
+                quoted_at: Some(quoted_at),
+                slippage_tolerance: Some(pair.slippage_tolerance),
+            };
+            execute_batched_arbitrage(web3, &backend, &plan).await?;
         }
+        return Ok(true);
     }
 
-    Ok(())
+    Ok(false)
 }
 
-// Multi-leg arbitrage logic (A -> B -> C -> A)
-pub async fn execute_multi_leg_arbitrage(
-    web3: &web3::Web3<Http>,
-    loaned_amount: U256
-) -> Result<(), ArbitrageError> {
-    // Implementation of execute_multi_leg_arbitrage function
-    unimplemented!("execute_multi_leg_arbitrage function not implemented")
+// Backrun swaps as the sequencer orders them: each sequenced router swap moves its pool before the
+// block exists, so re-check that pair straight away instead of waiting for the next scan
+pub async fn backrun_sequenced_swaps(web3: web3::Web3<FailoverTransport>) {
+    let mut sequenced = sequencer_feed::subscribe();
+    loop {
+        let pending = match sequenced.recv().await {
+            Ok(pending) => pending,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                error!("Sequencer backruns fell behind, skipped {} transactions", skipped);
+                continue;
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        };
+        if let Some((token_in, token_out)) = sandwich::victim_pair(&pending.input.0) {
+            let web3 = web3.clone();
+            spawn(async move {
+                if let Err(e) = check_arbitrage_opportunity(&web3, token_out, token_in, OpportunitySource::SequencerFeed).await {
+                    error!("Backrun check after {:?} failed: {:?}", pending.hash, e);
+                }
+            });
+        }
+    }
 }
 
-async fn get_token_price(
-    web3: &web3::Web3<Http>,
-    router_contract: &Contract<Http>,
-    token_in: Address,
-    token_out: Address,
-) -> Result<U256, ArbitrageError> {
-    // Implementation of get_token_price function
-    // This is a placeholder and should be replaced with actual implementation
-    unimplemented!("get_token_price function not implemented")
-}    let config = load_arbitrage_config();
-    let token_a: Address = config["arbitrage_token_a"].as_str().unwrap().parse().expect("Invalid address");
-    let token_b: Address = config["arbitrage_token_b"].as_str().unwrap().parse().expect("Invalid address");
-    let token_c: Address = config["arbitrage_token_c"].as_str().unwrap().parse().expect("Invalid address");
-
-    let uniswap_router_contract = Contract::from_json(
-        web3.eth(),
-        str_to_address(&config["uniswap_router_address"].as_str().unwrap()),
-        include_bytes!("../abi/uniswap_router_abi.json")
-    ).expect("Invalid Uniswap router ABI");
-    let sushiswap_router_contract = Contract::from_json(
-        web3.eth(),
-        str_to_address(&config["sushiswap_router_address"].as_str().unwrap()),
-        include_bytes!("../abi/sushiswap_router_abi.json")
-    ).expect("Invalid Sushiswap router ABI");
-    pub fn estimate_gas_fees() -> U256 {
-        U256::from(300000) // Example gas fees for arbitrage trades
-    }
-    // Multi-leg arbitrage (A -> B -> C -> A)
-    let leg_1_profit = perform_trade(web3, &uniswap_router_contract, token_a, token_b, loaned_amount).await?;
-    if is_profitable(leg_1_profit, gas_fees) {
-        let leg_2_profit = perform_trade(web3, &sushiswap_router_contract, token_b, token_c, leg_1_profit).await?;
-        if is_profitable(leg_2_profit, gas_fees) {
-            let final_profit = perform_trade(web3, &uniswap_router_contract, token_c, token_a, leg_2_profit).await?;
-            if is_profitable(final_profit, gas_fees) {
-                info!("Arbitrage completed successfully with a profit.");
-            } else {
-                error!("Final leg of arbitrage was not profitable.");
+// Block-driven backruns for endpoints without pending visibility: once a block lands, re-check
+// every pair its router swaps moved. Slower than the mempool or sequencer feed, but it still
+// catches the dislocations a block leaves behind instead of waiting on pending transactions
+pub async fn backrun_block_swaps(web3: web3::Web3<FailoverTransport>) {
+    let mut last_block = 0u64;
+    loop {
+        sleep(Duration::from_secs(1)).await;
+        let head = match web3.eth().block_number().await {
+            Ok(head) => head.as_u64(),
+            Err(e) => {
+                error!("Block backruns could not read the head block: {}", e);
+                continue;
             }
-        } else {
-            error!("Second leg of arbitrage was not profitable.");
-            return Ok(());
+        };
+        if head <= last_block {
+            continue;
         }
-    } else {
-        error!("First leg of arbitrage was not profitable.");
-        return Ok(());
-    }        error!("First leg of arbitrage was not profitable.");
-    
+        // Only the newest block matters after a gap; older dislocations have been taken
+        last_block = head;
 
+        let block = match web3.eth().block_with_txs(BlockId::Number(BlockNumber::Number(head.into()))).await {
+            Ok(Some(block)) => block,
+            Ok(None) => continue,
+            Err(e) => {
+                error!("Block backruns could not fetch block {}: {}", head, e);
+                continue;
+            }
+        };
+        let mut pairs: Vec<(Address, Address)> = block
+            .transactions
+            .iter()
+            .filter_map(|transaction| sandwich::victim_pair(&transaction.input.0))
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+        for (token_in, token_out) in pairs {
+            let web3 = web3.clone();
+            spawn(async move {
+                if let Err(e) = check_arbitrage_opportunity(&web3, token_out, token_in, OpportunitySource::BlockEvent).await {
+                    error!("Backrun check after block {} failed: {:?}", head, e);
+                }
+            });
+        }
+    }
+}
 
-    Ok(())
+// ERC20 decimals per token; they never change, so each token is read once
+fn decimals_cache() -> &'static Mutex<HashMap<Address, u8>> {
+    static DECIMALS: OnceLock<Mutex<HashMap<Address, u8>>> = OnceLock::new();
+    DECIMALS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+async fn token_decimals<T: Transport>(web3: &web3::Web3<T>, token: Address) -> Result<u8, ArbitrageError> {
+    if let Some(decimals) = decimals_cache().lock().unwrap().get(&token) {
+        return Ok(*decimals);
+    }
+    let erc20 = Contract::from_json(web3.eth(), token, include_bytes!("abi/erc20_abi.json"))?;
+    let decimals: u8 = erc20.query("decimals", (), None, Options::default(), None).await?;
+    decimals_cache().lock().unwrap().insert(token, decimals);
+    Ok(decimals)
+}
 
-// Execute individual trades
-pub async fn perform_trade(
-    web3: &web3::Web3<Http>,
-    router_contract: &Contract<Http>,
+// Price of token_in in token_out as an 18-decimal fixed-point number (1e18 = 1.0), from
+// getAmountsOut for price_probe_amount whole tokens on the V2 router that pays most. Both amounts
+// are scaled by their token's decimals, so a 6-decimal stablecoin prices like an 18-decimal token
+pub async fn get_token_price(
+    web3: &web3::Web3<FailoverTransport>,
     token_in: Address,
     token_out: Address,
-    amount_in: U256
 ) -> Result<U256, ArbitrageError> {
-    let gas_fees: U256 = U256::from(300000); // Example gas fees
-    let trade_params = (vec![token_in, token_out], amount_in, 1u64);
+    let probe_tokens = load_arbitrage_config()["price_probe_amount"].as_f64().unwrap_or(1.0);
+    let routers: Vec<Address> = venues::venues_for(web3, token_in, token_out)
+        .await?
+        .into_iter()
+        .filter(|venue| venue.version == VenueVersion::V2)
+        .map(|venue| venue.router)
+        .collect();
+    price_from_routers(web3, &routers, token_in, token_out, probe_tokens).await
+}
 
-    let result = router_contract
-        .call("swapExactTokensForTokens", trade_params, "YOUR_ADDRESS".parse().unwrap(), Options::default())
-        .await;
+// Probe every router with getAmountsOut for probe_tokens whole tokens; the best output sets the price
+async fn price_from_routers<T: Transport>(
+    web3: &web3::Web3<T>,
+    routers: &[Address],
+    token_in: Address,
+    token_out: Address,
+    probe_tokens: f64,
+) -> Result<U256, ArbitrageError> {
+    let decimals_in = token_decimals(web3, token_in).await? as usize;
+    let decimals_out = token_decimals(web3, token_out).await? as usize;
+    // Probe in millionths of a token so fractional probes work for any decimals
+    let probe = U256::from((probe_tokens * 1e6) as u128).saturating_mul(U256::exp10(decimals_in)) / U256::from(1_000_000u64);
+    if probe.is_zero() {
+        return Err(ArbitrageError::InvalidConfig(String::from("price_probe_amount")));
+    }
 
-    match result {
-        Ok(output_amount) => {
-            info!("Trade executed: {:?}", output_amount);
-            Ok(U256::from(output_amount))
+    let mut best = U256::zero();
+    for router in routers {
+        let router_contract = Contract::from_json(web3.eth(), *router, include_bytes!("abi/uniswap_router_abi.json"))?;
+        let amounts: Vec<U256> = match router_contract
+            .query("getAmountsOut", (probe, vec![token_in, token_out]), None, Options::default(), None)
+            .await
+        {
+            Ok(amounts) => amounts,
+            Err(e) => {
+                error!("getAmountsOut failed on router {:?}: {}", router, e);
+                continue;
+            }
+        };
+        best = best.max(amounts.last().copied().unwrap_or_default());
+    }
+    if best.is_zero() {
+        return Err(ArbitrageError::NoPrice(token_in, token_out));
+    }
+
+    // price = (out / 10^decimals_out) / (probe / 10^decimals_in), scaled by 1e18
+    Ok(best.saturating_mul(U256::exp10(18 + decimals_in)) / probe.saturating_mul(U256::exp10(decimals_out)))
+}
+
+// Multi-leg arbitrage: the path finder searches the token universe for the most profitable cycle
+// (optionally from one start token) and the cycle runs as one batch
+pub async fn execute_multi_leg_arbitrage(
+    web3: &web3::Web3<FailoverTransport>,
+    start_token: Option<Address>
+) -> Result<(), ArbitrageError> {
+    let config = load_arbitrage_config();
+    let quoted_at = QuoteWatermark::at_head(web3).await?;
+    let routes = path_finder::find_routes(web3).await?;
+    let route = match routes.into_iter().find(|route| start_token.is_none_or(|token| route.start_token() == token)) {
+        Some(route) => route,
+        None => {
+            info!("No profitable multi-leg cycle from {:?}", start_token);
+            return Ok(());
         }
+    };
+
+    info!("Profitable multi-leg arbitrage via {}: Profit: {:?}, Gas: {:?}", route.label(), route.profit, route.gas_fees);
+    let plan = TradePlan {
+        strategy: String::from("arbitrage"),
+        source: OpportunitySource::ScheduledScan,
+        legs: route.legs,
+        expected_profit: route.amount_back - route.amount_in,
+        gas_fees: route.gas_fees,
+        actions: actions::for_strategy("arbitrage"),
+        quoted_at: Some(quoted_at),
+        slippage_tolerance: config["slippage_tolerance"].as_f64(),
+    };
+    let backend = ExecutionBackend::from_config(web3)?;
+    execute_batched_arbitrage(web3, &backend, &plan).await?;
+    Ok(())
+}
+
+// Executor contract that runs batched legs for a plain wallet
+fn executor_address() -> Option<Address> {
+    load_arbitrage_config()["arbitrage_contract_address"].as_str().and_then(ens::parse_address)
+}
+
+// Execute every leg (approve + swap) in a single batch: smart accounts and Safes run the legs
+// atomically themselves, a plain wallet hands them to the executor contract as one executeBatch
+// call, so the cycle lands whole or not at all
+pub async fn execute_batched_arbitrage(
+    web3: &web3::Web3<FailoverTransport>,
+    backend: &ExecutionBackend,
+    plan: &TradePlan
+) -> Result<H256, ArbitrageError> {
+    let deadline = U256::from(Utc::now().timestamp() + 600);
+    let (holder, calls) = if backend.is_atomic() {
+        (backend.recipient(), plan.calls(backend.recipient(), deadline)?)
+    } else {
+        // The executor holds the funds and receives every leg's output
+        let executor = executor_address().ok_or(ArbitrageError::NoExecutor)?;
+        let profit_token = plan.legs.first().map(|leg| leg.token_in).unwrap_or_default();
+        let batch = ExecutorBatch::new(executor, profit_token, plan.gas_fees, plan.calls(executor, deadline)?);
+        (executor, vec![batch.prepare(web3, backend).await?])
+    };
+
+    let submission = match backend.execute_plan(web3, plan, calls.clone()).await {
+        Ok(submission) => submission,
         Err(e) => {
-            error!("Failed to execute trade: {:?}", e);
-            Err(ArbitrageError::ContractError(e))
+            let mut result = ExecutionResult::new(plan, ExecutionStatus::Failed, None);
+            result.error = Some(e.to_string());
+            result.record();
+            return Err(e.into());
+        }
+    };
+    info!("Batched arbitrage submitted: {:?}", submission);
+
+    // The decoded receipt goes into the execution record once the transaction is mined; a reverted
+    // round trip also gets a post-mortem bundle with the pools it touched. A Safe proposal has no
+    // transaction until the owners execute it
+    let receipt = if backend.is_proposal() {
+        None
+    } else {
+        match receipt_events::wait(web3, submission).await {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                error!("Could not read the receipt of {:?}: {}", submission, e);
+                None
+            }
+        }
+    };
+    if let Some(receipt) = receipt.as_ref().filter(|receipt| !receipt.success) {
+        postmortem::capture(web3, "arbitrage", "transaction reverted", backend.recipient(), &calls, Some(submission), &plan.pools()).await;
+        let mut result = ExecutionResult::new(plan, ExecutionStatus::Reverted, Some(submission));
+        result.receipt = Some(receipt.clone());
+        result.record();
+        return Err(ArbitrageError::Reverted(submission));
+    }
+
+    // Feed expected vs each leg's decoded output back into the slippage model
+    let mut result = ExecutionResult::new(plan, ExecutionStatus::Submitted, Some(submission));
+    if let Some(receipt) = &receipt {
+        for leg in &plan.legs {
+            let realized = receipt.received(leg.token_out, leg.venue.pool, holder);
+            slippage::record_execution(leg.venue.pool, &leg.venue.label(), leg.expected_out, realized);
+            result.realized_out = Some(realized);
         }
     }
+    result.receipt = receipt;
+
+    // Round-trip result (last leg out minus first leg in) goes to the inventory in token_in and to the
+    // strategy PnL ledger in wei
+    if let (Some(realized), Some(first)) = (result.realized_out, plan.legs.first()) {
+        inventory::record_change(&plan.strategy, first.token_in, allocator::signed_difference(realized, plan.amount_in()));
+        match allocator::pnl_in_wei(web3, first.token_in, realized, plan.amount_in()).await {
+            Ok(pnl_wei) => {
+                allocator::record_trade_result("arbitrage", plan.source, pnl_wei);
+                result.pnl_wei = Some(pnl_wei);
+            }
+            Err(e) => error!("Could not value the arbitrage result of {:?} in wei: {}", submission, e),
+        }
+    }
+    result.record();
+    Ok(submission)
+}
+
+// Buy leg against an RFQ maker, sell leg on the AMM. The buy leg settles first so the sell leg can be
+// sized to what the maker, and the fallback route for anything it left unfilled, actually delivered
+pub async fn execute_rfq_arbitrage(
+    web3: &web3::Web3<FailoverTransport>,
+    backend: &ExecutionBackend,
+    plan: &RfqLegPlan,
+    sell_venue: Venue,
+    slippage_tolerance: f64,
+    source: OpportunitySource
+) -> Result<H256, ArbitrageError> {
+    let quote = &plan.quote;
+    let bought = rfq::execute_leg(web3, backend, "arbitrage", plan, slippage_tolerance).await?;
+    info!(
+        "RFQ buy leg via {}: {} filled by the maker, {} routed to the AMMs, {} received",
+        quote.provider, bought.filled_in, bought.fallback_in, bought.received
+    );
+    if bought.received.is_zero() {
+        return Err(ArbitrageError::NothingReceived(bought.rfq_submission));
+    }
+
+    // The sell leg is re-quoted for what was actually received and held to the pair's slippage
+    // tolerance, like the AMM round trip's legs
+    let expected_out = slippage::adjust(sell_venue.pool, sell_venue.quote(web3, quote.token_out, quote.token_in, bought.received).await?);
+    let deadline = U256::from(Utc::now().timestamp() + 600);
+    let min_out = domain::min_out(expected_out, Some(slippage_tolerance));
+    let calls = sell_venue.swap_calls(quote.token_out, quote.token_in, bought.received, min_out, backend.recipient(), deadline)?;
+    let submission = backend.execute_calls(web3, "arbitrage", calls).await?;
+    info!("RFQ arbitrage sell leg submitted on {}: {:?}", sell_venue.label(), submission);
+
+    // Round-trip result (sell leg out minus the buy leg's full input) goes to the strategy PnL ledger
+    // once the sell leg is mined
+    if backend.is_proposal() {
+        return Ok(submission);
+    }
+    if let Some(realized) = slippage::realized_output(web3, submission, quote.token_in, sell_venue.pool, backend.recipient()).await {
+        match allocator::pnl_in_wei(web3, quote.token_in, realized, plan.amount_in).await {
+            Ok(pnl_wei) => allocator::record_trade_result("arbitrage", source, pnl_wei),
+            Err(e) => error!("Could not value the RFQ arbitrage result of {:?} in wei: {}", submission, e),
+        }
+    }
+    Ok(submission)
 }
 
 // Retry logic for arbitrage trades
 pub async fn execute_arbitrage_with_retry(
-    web3: &web3::Web3<Http>,
-    loaned_amount: U256,
+    web3: &web3::Web3<FailoverTransport>,
+    start_token: Option<Address>,
     max_retries: u8
 ) -> Result<(), ArbitrageError> {
     let mut attempts = 0;
     let mut delay = 1;
 
     while attempts < max_retries {
-        let result = execute_multi_leg_arbitrage(web3, loaned_amount).await;
+        let result = execute_multi_leg_arbitrage(web3, start_token).await;
         match result {
             Ok(_) => return Ok(()),
             Err(e) => {
@@ -205,6 +637,72 @@ pub async fn execute_arbitrage_with_retry(
     Err(ArbitrageError::RetriesExceeded)
 }
 
+// Arbitrage under the orchestrator: the configured token cycle, or the discovered pairs once
+// discovery has run
+pub struct ArbitrageStrategy {
+    settings: ArbitrageConfig,
+}
+
+impl ArbitrageStrategy {
+    pub fn new(settings: ArbitrageConfig) -> Self {
+        ArbitrageStrategy { settings }
+    }
+
+    fn token_pairs(&self) -> Vec<(Address, Address)> {
+        let discovered = pair_discovery::active_pairs();
+        if !discovered.is_empty() {
+            return discovered;
+        }
+        let settings = &self.settings;
+        vec![
+            (settings.arbitrage_token_a, settings.arbitrage_token_b),
+            (settings.arbitrage_token_b, settings.arbitrage_token_c),
+            (settings.arbitrage_token_c, settings.arbitrage_token_a),
+        ]
+    }
+}
+
+#[async_trait]
+impl Strategy for ArbitrageStrategy {
+    fn name(&self) -> &'static str {
+        "arbitrage"
+    }
+
+    fn config(&self) -> Value {
+        self.settings.to_value()
+    }
+
+    // Quoting and trading share one decision budget, so a profitable pair is traded during the check
+    // itself and comes back already executed
+    async fn scan(&self, web3: &web3::Web3<FailoverTransport>) -> Result<Vec<Opportunity>, StrategyError> {
+        let pairs = self.token_pairs();
+        let checks = pairs
+            .iter()
+            .map(|&(token_in, token_out)| check_arbitrage_opportunity(web3, token_in, token_out, OpportunitySource::ScheduledScan));
+        let mut traded = Vec::new();
+        for ((token_in, _), result) in pairs.iter().zip(join_all(checks).await) {
+            match result {
+                Ok(true) => traded.push(Opportunity { asset: Some(*token_in), executed: true, ..Default::default() }),
+                Ok(false) => {}
+                Err(e) => error!("Error checking arbitrage opportunity: {:?}", e),
+            }
+        }
+
+        // Cycles across the wider token universe are handed to execute, from the best route's start token
+        if path_finder::enabled() {
+            match path_finder::find_routes(web3).await {
+                Ok(routes) => traded.extend(routes.first().map(|route| Opportunity { asset: Some(route.start_token()), amount: route.amount_in, ..Default::default() })),
+                Err(e) => error!("Error searching for multi-leg cycles: {:?}", e),
+            }
+        }
+        Ok(traded)
+    }
+
+    async fn execute(&self, web3: &web3::Web3<FailoverTransport>, opportunity: Opportunity) -> Result<(), StrategyError> {
+        Ok(execute_arbitrage_with_retry(web3, opportunity.asset, 3).await?)
+    }
+}
+
 // Custom error type for arbitrage
 #[derive(Error, Debug)]
 pub enum ArbitrageError {
@@ -212,8 +710,34 @@ pub enum ArbitrageError {
     Web3Error(#[from] web3::Error),
     #[error("Contract error: {0}")]
     ContractError(#[from] web3::contract::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Venue error: {0}")]
+    VenueError(#[from] VenueError),
+    #[error("Decision deadline: {0}")]
+    DeadlineMissed(#[from] DeadlineMissed),
+    #[error("Pricing error: {0}")]
+    PricingError(#[from] PricingError),
+    #[error("RFQ error: {0}")]
+    RfqError(#[from] RfqError),
+    #[error("Signer error: {0}")]
+    SignerError(#[from] SignerError),
+    #[error("Path finder error: {0}")]
+    PathFinderError(#[from] PathFinderError),
+    #[error("RFQ buy leg delivered nothing: {0:?}")]
+    NothingReceived(H256),
+    #[error("Arbitrage transaction reverted: {0:?}")]
+    Reverted(H256),
     #[error("Retries exceeded for arbitrage")]
     RetriesExceeded,
+    #[error("arbitrage_contract_address must be configured to batch legs from a plain wallet")]
+    NoExecutor,
+    #[error("{0} is missing or invalid in the arbitrage config")]
+    InvalidConfig(String),
+    #[error("No V2 router prices {0:?} in {1:?}")]
+    NoPrice(Address, Address),
 }
 
 // Implement conversion for ArbitrageError to Web3 error
@@ -223,3 +747,166 @@ impl From<ArbitrageError> for web3::Error {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+    use web3::ethabi::{self, Token};
+    use web3::types::Bytes;
+    use jsonrpc_core as rpc;
+    use web3::{helpers, RequestId};
+    use crate::modules::multicall;
+
+    const GET_AMOUNTS_OUT: &str = "getAmountsOut(uint256,address[])";
+
+    type Answers = HashMap<(Address, Vec<u8>), Vec<u8>>;
+    type CallLog = Vec<(Address, Vec<u8>)>;
+
+    // Answers eth_call by target and selector and records every call; calls without an answer revert
+    #[derive(Clone, Debug, Default)]
+    struct MockTransport {
+        answers: Arc<Mutex<Answers>>,
+        calls: Arc<Mutex<CallLog>>,
+    }
+
+    impl MockTransport {
+        fn answer(&self, target: Address, signature: &str, output: Vec<Token>) {
+            self.answers.lock().unwrap().insert((target, multicall::selector(signature).to_vec()), ethabi::encode(&output));
+        }
+
+        // Calldata of every call made to target with the function's selector
+        fn calls_to(&self, target: Address, signature: &str) -> Vec<Vec<u8>> {
+            let selector = multicall::selector(signature);
+            self.calls
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(to, data)| *to == target && data.starts_with(&selector))
+                .map(|(_, data)| data.clone())
+                .collect()
+        }
+    }
+
+    impl Transport for MockTransport {
+        type Out = futures::future::Ready<web3::error::Result<Value>>;
+
+        fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, rpc::Call) {
+            (0, helpers::build_request(0, method, params))
+        }
+
+        fn send(&self, _id: RequestId, request: rpc::Call) -> Self::Out {
+            let call = match request {
+                rpc::Call::MethodCall(rpc::MethodCall { params: rpc::Params::Array(params), .. }) => params.into_iter().next().unwrap_or_default(),
+                _ => Value::Null,
+            };
+            let target: Address = serde_json::from_value(call["to"].clone()).unwrap_or_default();
+            let data: Bytes = serde_json::from_value(call["data"].clone()).unwrap_or_default();
+            self.calls.lock().unwrap().push((target, data.0.clone()));
+            let key = (target, data.0.get(..4).unwrap_or_default().to_vec());
+            futures::future::ready(match self.answers.lock().unwrap().get(&key) {
+                Some(output) => Ok(json!(Bytes(output.clone()))),
+                None => Err(web3::Error::Rpc(rpc::Error {
+                    code: rpc::ErrorCode::ServerError(3),
+                    message: String::from("execution reverted"),
+                    data: None,
+                })),
+            })
+        }
+    }
+
+    fn amounts(amount_in: U256, amount_out: U256) -> Vec<Token> {
+        vec![Token::Array(vec![Token::Uint(amount_in), Token::Uint(amount_out)])]
+    }
+
+    fn decimals(transport: &MockTransport, token: Address, decimals: u8) {
+        transport.answer(token, "decimals()", vec![Token::Uint(decimals.into())]);
+    }
+
+    // Tokens and routers get their own addresses per test, since the decimals cache is process-wide
+    fn address(n: u64) -> Address {
+        Address::from_low_u64_be(n)
+    }
+
+    #[tokio::test]
+    async fn price_comes_from_the_best_router_scaled_by_decimals() {
+        let (weth, usdc, router_a, router_b) = (address(0x101), address(0x102), address(0x103), address(0x104));
+        let transport = MockTransport::default();
+        decimals(&transport, weth, 18);
+        decimals(&transport, usdc, 6);
+        transport.answer(router_a, GET_AMOUNTS_OUT, amounts(U256::exp10(18), U256::from(2_990_000_000u64)));
+        transport.answer(router_b, GET_AMOUNTS_OUT, amounts(U256::exp10(18), U256::from(3_000_000_000u64)));
+        let web3 = web3::Web3::new(transport);
+
+        let price = price_from_routers(&web3, &[router_a, router_b], weth, usdc, 1.0).await.unwrap();
+        assert_eq!(price, U256::from(3000) * U256::exp10(18));
+    }
+
+    #[tokio::test]
+    async fn fractional_probe_is_sized_in_the_input_decimals() {
+        let (usdc, weth, router) = (address(0x201), address(0x202), address(0x203));
+        let transport = MockTransport::default();
+        decimals(&transport, usdc, 6);
+        decimals(&transport, weth, 18);
+        // 0.5 USDC buys 0.00015 WETH
+        transport.answer(router, GET_AMOUNTS_OUT, amounts(U256::from(500_000u64), U256::from(150_000_000_000_000u64)));
+        let web3 = web3::Web3::new(transport.clone());
+
+        let price = price_from_routers(&web3, &[router], usdc, weth, 0.5).await.unwrap();
+        assert_eq!(price, U256::from(300_000_000_000_000u64));
+
+        let probes = transport.calls_to(router, GET_AMOUNTS_OUT);
+        assert_eq!(probes.len(), 1);
+        let args = ethabi::decode(&[ethabi::ParamType::Uint(256), ethabi::ParamType::Array(Box::new(ethabi::ParamType::Address))], &probes[0][4..]).unwrap();
+        assert_eq!(args[0], Token::Uint(U256::from(500_000u64)));
+        assert_eq!(args[1], Token::Array(vec![Token::Address(usdc), Token::Address(weth)]));
+    }
+
+    #[tokio::test]
+    async fn decimals_are_read_once_per_token() {
+        let (dai, usdt, router) = (address(0x301), address(0x302), address(0x303));
+        let transport = MockTransport::default();
+        decimals(&transport, dai, 18);
+        decimals(&transport, usdt, 6);
+        transport.answer(router, GET_AMOUNTS_OUT, amounts(U256::exp10(18), U256::from(1_000_000u64)));
+        let web3 = web3::Web3::new(transport.clone());
+
+        for _ in 0..3 {
+            let price = price_from_routers(&web3, &[router], dai, usdt, 1.0).await.unwrap();
+            assert_eq!(price, U256::exp10(18));
+        }
+        assert_eq!(transport.calls_to(dai, "decimals()").len(), 1);
+        assert_eq!(transport.calls_to(usdt, "decimals()").len(), 1);
+        assert_eq!(transport.calls_to(router, GET_AMOUNTS_OUT).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn no_liquidity_is_an_error() {
+        let (token_in, token_out, empty_router, reverting_router) = (address(0x401), address(0x402), address(0x403), address(0x404));
+        let transport = MockTransport::default();
+        decimals(&transport, token_in, 18);
+        decimals(&transport, token_out, 18);
+        // One router has an empty pair, the other has no pair at all and reverts
+        transport.answer(empty_router, GET_AMOUNTS_OUT, amounts(U256::exp10(18), U256::zero()));
+        let web3 = web3::Web3::new(transport);
+
+        let result = price_from_routers(&web3, &[empty_router, reverting_router], token_in, token_out, 1.0).await;
+        assert!(matches!(result, Err(ArbitrageError::NoPrice(from, to)) if from == token_in && to == token_out));
+
+        let result = price_from_routers(&web3, &[], token_in, token_out, 1.0).await;
+        assert!(matches!(result, Err(ArbitrageError::NoPrice(_, _))));
+    }
+
+    #[tokio::test]
+    async fn zero_probe_is_a_config_error() {
+        let (token_in, token_out, router) = (address(0x501), address(0x502), address(0x503));
+        let transport = MockTransport::default();
+        decimals(&transport, token_in, 18);
+        decimals(&transport, token_out, 18);
+        let web3 = web3::Web3::new(transport);
+
+        let result = price_from_routers(&web3, &[router], token_in, token_out, 0.0).await;
+        assert!(matches!(result, Err(ArbitrageError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,152 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use warp::http::StatusCode;
+use warp::{Filter, Rejection, Reply};
+use web3::signing::keccak256;
+use log::warn;
+
+// Roles ordered by privilege: each role can do everything the ones below it can
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Operator,
+    Admin,
+}
+
+impl Role {
+    fn from_str(role: &str) -> Option<Role> {
+        match role {
+            "viewer" => Some(Role::Viewer),
+            "operator" => Some(Role::Operator),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+// An authenticated caller of the dashboard/control API
+#[derive(Clone, Debug)]
+pub struct OperatorIdentity {
+    pub name: String,
+    pub role: Role,
+}
+
+// Configured API tokens (stored as keccak256 hashes, never in plain text)
+#[derive(Clone)]
+pub struct AuthConfig {
+    operators: Vec<(String, String, Role)>,
+}
+
+impl AuthConfig {
+    // Load operators from the "operators" section of the dashboard config
+    pub fn from_config(config: &Value) -> AuthConfig {
+        let operators = config["operators"]
+            .as_array()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|entry| {
+                        let name = entry["name"].as_str()?.to_string();
+                        let token_hash = entry["token_hash"].as_str()?.trim_start_matches("0x").to_lowercase();
+                        let role = Role::from_str(entry["role"].as_str()?)?;
+                        Some((name, token_hash, role))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        AuthConfig { operators }
+    }
+
+    // Look up the operator owning a bearer token
+    pub fn authenticate(&self, token: &str) -> Option<OperatorIdentity> {
+        let token_hash = to_hex(&keccak256(token.as_bytes()));
+        self.operators
+            .iter()
+            .find(|(_, hash, _)| *hash == token_hash)
+            .map(|(name, _, role)| OperatorIdentity { name: name.clone(), role: *role })
+    }
+}
+
+// Rejections produced by the auth filter
+#[derive(Debug)]
+pub enum AuthRejection {
+    MissingToken,
+    InvalidToken,
+    Forbidden(Role),
+}
+
+impl warp::reject::Reject for AuthRejection {}
+
+// Filter that authenticates the bearer token and requires at least the given role
+pub fn require_role(
+    auth: Arc<AuthConfig>,
+    required: Role,
+) -> impl Filter<Extract = (OperatorIdentity,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization").and_then(move |header: Option<String>| {
+        let auth = auth.clone();
+        async move {
+            let token = header.as_deref().and_then(|value| value.strip_prefix("Bearer "));
+            check_token(&auth, token, required)
+        }
+    })
+}
+
+// Same as require_role, but also accepts the token as a ?token= query parameter, since
+// browsers can't set headers on a WebSocket upgrade
+pub fn require_role_or_query(
+    auth: Arc<AuthConfig>,
+    required: Role,
+) -> impl Filter<Extract = (OperatorIdentity,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::query::<HashMap<String, String>>())
+        .and_then(move |header: Option<String>, query: HashMap<String, String>| {
+            let auth = auth.clone();
+            async move {
+                let token = header
+                    .as_deref()
+                    .and_then(|value| value.strip_prefix("Bearer "))
+                    .or_else(|| query.get("token").map(String::as_str));
+                check_token(&auth, token, required)
+            }
+        })
+}
+
+fn check_token(auth: &AuthConfig, token: Option<&str>, required: Role) -> Result<OperatorIdentity, Rejection> {
+    let token = token.ok_or_else(|| warp::reject::custom(AuthRejection::MissingToken))?;
+
+    let identity = auth
+        .authenticate(token)
+        .ok_or_else(|| warp::reject::custom(AuthRejection::InvalidToken))?;
+
+    if identity.role < required {
+        warn!("{} ({:?}) denied access requiring {:?}", identity.name, identity.role, required);
+        return Err(warp::reject::custom(AuthRejection::Forbidden(required)));
+    }
+    Ok(identity)
+}
+
+// Same as require_role for handlers that don't need the caller's identity
+pub fn restrict_to(auth: Arc<AuthConfig>, required: Role) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    require_role(auth, required).map(|_| ()).untuple_one()
+}
+
+// Turn auth rejections into 401/403 responses
+pub async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Rejection> {
+    match rejection.find::<AuthRejection>() {
+        Some(AuthRejection::MissingToken) | Some(AuthRejection::InvalidToken) => Ok(warp::reply::with_status(
+            warp::reply::json(&"Missing or invalid API token"),
+            StatusCode::UNAUTHORIZED,
+        )),
+        Some(AuthRejection::Forbidden(required)) => Ok(warp::reply::with_status(
+            warp::reply::json(&format!("Requires {:?} role", required)),
+            StatusCode::FORBIDDEN,
+        )),
+        None => Err(rejection),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
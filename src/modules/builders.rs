@@ -0,0 +1,339 @@
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use web3::types::{Address, Block, BlockId, BlockNumber, H256, U256};
+use web3::Transport;
+use log::{error, info, warn};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::events;
+use crate::modules::providers::FailoverTransport;
+
+// Load the builder analysis configuration
+fn load_builders_config() -> Value {
+    let config_path = "config/builders_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read builders config file");
+    serde_json::from_str(&config_data).expect("Unable to parse builders config file")
+}
+
+fn records_path(config: &Value) -> String {
+    config["records_path"].as_str().unwrap_or("data/builder_payments.jsonl").to_string()
+}
+
+// A submission waiting to land, with the block it was sent at
+struct PendingSubmission {
+    tx_hash: H256,
+    submitted_block: u64,
+}
+
+// Priority-fee multiplier applied by the gas feed, adjusted toward the target inclusion rate
+fn tip_multiplier_state() -> &'static Mutex<f64> {
+    static MULTIPLIER: OnceLock<Mutex<f64>> = OnceLock::new();
+    MULTIPLIER.get_or_init(|| Mutex::new(1.0))
+}
+
+pub fn tip_multiplier() -> f64 {
+    *tip_multiplier_state().lock().unwrap()
+}
+
+// Builders sign their blocks in extraData ("beaverbuild.org", "Titan (titanbuilder.xyz)"); fall back
+// to the fee recipient when it is not printable
+fn builder_name(block: &Block<H256>) -> String {
+    let extra = String::from_utf8_lossy(&block.extra_data.0).trim_matches(char::from(0)).trim().to_string();
+    if !extra.is_empty() && extra.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+        extra
+    } else {
+        format!("{:?}", block.author)
+    }
+}
+
+fn append_record(config: &Value, record: Value) {
+    let path = records_path(config);
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write builder record: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open builder records {}: {}", path, e),
+    }
+}
+
+// Direct ETH transfers to the fee recipient inside our transaction, from the call trace
+async fn coinbase_transfer(web3: &web3::Web3<FailoverTransport>, tx_hash: H256, fee_recipient: Address) -> U256 {
+    let params = vec![json!(tx_hash), json!({ "tracer": "callTracer" })];
+    let trace = match web3.transport().execute("debug_traceTransaction", params).await {
+        Ok(trace) => trace,
+        Err(e) => {
+            warn!("Could not trace {:?} for coinbase payment: {}", tx_hash, e);
+            return U256::zero();
+        }
+    };
+
+    fn sum_to(call: &Value, recipient: &str, total: &mut U256) {
+        if call["to"].as_str().map(|to| to.eq_ignore_ascii_case(recipient)).unwrap_or(false) {
+            if let Some(value) = call["value"].as_str().and_then(|value| U256::from_str_radix(value.trim_start_matches("0x"), 16).ok()) {
+                *total += value;
+            }
+        }
+        for child in call["calls"].as_array().into_iter().flatten() {
+            sum_to(child, recipient, total);
+        }
+    }
+    let mut total = U256::zero();
+    sum_to(&trace, &format!("{:?}", fee_recipient), &mut total);
+    total
+}
+
+// Check pending submissions against a new block: every builder block seen while we were pending is
+// an opportunity, and an inclusion records what we paid (priority fee plus direct coinbase transfer)
+async fn process_block(web3: &web3::Web3<FailoverTransport>, config: &Value, block: &Block<H256>, pending: &mut Vec<PendingSubmission>) {
+    let number = block.number.map(|number| number.as_u64()).unwrap_or_default();
+    let builder = builder_name(block);
+    let base_fee = block.base_fee_per_gas.unwrap_or_default();
+    let max_pending_blocks = config["max_pending_blocks"].as_u64().unwrap_or(25);
+    let (mut waiting, mut included) = (0u64, 0u64);
+
+    let mut still_pending = Vec::new();
+    for submission in pending.drain(..) {
+        let receipt = match web3.eth().transaction_receipt(submission.tx_hash).await {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                warn!("Receipt lookup for {:?} failed: {}", submission.tx_hash, e);
+                still_pending.push(submission);
+                continue;
+            }
+        };
+        match receipt {
+            Some(receipt) if receipt.block_number.map(|n| n.as_u64()) == Some(number) => {
+                waiting += 1;
+                included += 1;
+                let gas_used = receipt.gas_used.unwrap_or_default();
+                let priority_fee = receipt.effective_gas_price.unwrap_or_default().saturating_sub(base_fee) * gas_used;
+                let coinbase = coinbase_transfer(web3, submission.tx_hash, block.author).await;
+                append_record(config, json!({
+                    "kind": "inclusion",
+                    "timestamp": Utc::now().to_rfc3339(),
+                    "tx_hash": submission.tx_hash,
+                    "block": number,
+                    "builder": builder,
+                    "fee_recipient": block.author,
+                    "blocks_waited": number.saturating_sub(submission.submitted_block),
+                    "gas_used": gas_used.to_string(),
+                    "priority_fee_wei": priority_fee.to_string(),
+                    "coinbase_transfer_wei": coinbase.to_string(),
+                    "total_payment_wei": (priority_fee + coinbase).to_string(),
+                }));
+            }
+            // Landed in a block we did not see (polling gap); it no longer counts against anyone
+            Some(_) => {}
+            None if number.saturating_sub(submission.submitted_block) >= max_pending_blocks => {
+                waiting += 1;
+                append_record(config, json!({
+                    "kind": "expired",
+                    "timestamp": Utc::now().to_rfc3339(),
+                    "tx_hash": submission.tx_hash,
+                    "submitted_block": submission.submitted_block,
+                }));
+            }
+            None => {
+                waiting += 1;
+                still_pending.push(submission);
+            }
+        }
+    }
+    *pending = still_pending;
+
+    if waiting > 0 {
+        append_record(config, json!({
+            "kind": "block",
+            "timestamp": Utc::now().to_rfc3339(),
+            "block": number,
+            "builder": builder,
+            "pending": waiting,
+            "included": included,
+        }));
+    }
+}
+
+// Per-builder inclusion rate and cost over the trailing window
+pub fn builder_stats(window: ChronoDuration) -> Value {
+    let config = load_builders_config();
+    let cutoff = Utc::now() - window;
+    let contents = fs::read_to_string(records_path(&config)).unwrap_or_default();
+
+    #[derive(Default)]
+    struct Stats {
+        blocks_while_pending: u64,
+        inclusions: u64,
+        total_payment: f64,
+        coinbase_payment: f64,
+        blocks_waited: u64,
+    }
+    let mut builders: BTreeMap<String, Stats> = BTreeMap::new();
+    let (mut opportunities, mut inclusions) = (0u64, 0u64);
+    for record in contents.lines().filter_map(|line| serde_json::from_str::<Value>(line).ok()) {
+        let recent = record["timestamp"]
+            .as_str()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc) >= cutoff)
+            .unwrap_or(false);
+        if !recent {
+            continue;
+        }
+        let wei = |field: &str| record[field].as_str().and_then(|value| value.parse::<f64>().ok()).unwrap_or(0.0);
+        let builder = record["builder"].as_str().unwrap_or("unknown").to_string();
+        match record["kind"].as_str() {
+            Some("block") => {
+                let stats = builders.entry(builder).or_default();
+                stats.blocks_while_pending += 1;
+                opportunities += 1;
+                if record["included"].as_u64().unwrap_or(0) > 0 {
+                    inclusions += 1;
+                }
+            }
+            Some("inclusion") => {
+                let stats = builders.entry(builder).or_default();
+                stats.inclusions += 1;
+                stats.total_payment += wei("total_payment_wei");
+                stats.coinbase_payment += wei("coinbase_transfer_wei");
+                stats.blocks_waited += record["blocks_waited"].as_u64().unwrap_or(0);
+            }
+            _ => {}
+        }
+    }
+
+    let rows: Vec<Value> = builders
+        .iter()
+        .map(|(builder, stats)| {
+            let per_inclusion = |total: f64| if stats.inclusions > 0 { Some(total / stats.inclusions as f64) } else { None };
+            json!({
+                "builder": builder,
+                "blocks_while_pending": stats.blocks_while_pending,
+                "inclusions": stats.inclusions,
+                "inclusion_rate": if stats.blocks_while_pending > 0 { Some(stats.inclusions as f64 / stats.blocks_while_pending as f64) } else { None },
+                "avg_payment_wei": per_inclusion(stats.total_payment),
+                "avg_coinbase_transfer_wei": per_inclusion(stats.coinbase_payment),
+                "avg_blocks_waited": per_inclusion(stats.blocks_waited as f64),
+            })
+        })
+        .collect();
+    json!({
+        "window_hours": window.num_hours(),
+        "inclusion_rate": if opportunities > 0 { Some(inclusions as f64 / opportunities as f64) } else { None },
+        "tip_multiplier": tip_multiplier(),
+        "builders": rows,
+    })
+}
+
+// Builders ordered by inclusion rate (ties broken by cheaper inclusions), for choosing where to send
+pub fn builder_ranking() -> Vec<String> {
+    let config = load_builders_config();
+    let stats = builder_stats(ChronoDuration::hours(config["window_hours"].as_i64().unwrap_or(168)));
+    let mut rows: Vec<Value> = stats["builders"].as_array().cloned().unwrap_or_default();
+    rows.sort_by(|a, b| {
+        let rate = |row: &Value| row["inclusion_rate"].as_f64().unwrap_or(0.0);
+        let cost = |row: &Value| row["avg_payment_wei"].as_f64().unwrap_or(f64::MAX);
+        rate(b).partial_cmp(&rate(a)).unwrap_or(std::cmp::Ordering::Equal).then(cost(a).partial_cmp(&cost(b)).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    rows.iter().filter_map(|row| row["builder"].as_str().map(String::from)).collect()
+}
+
+// Nudge the tip multiplier toward the target inclusion rate: up when we wait too often, down when
+// we land comfortably above target and could pay less
+fn retune_tips(config: &Value) {
+    let stats = builder_stats(ChronoDuration::hours(config["tuning_window_hours"].as_i64().unwrap_or(24)));
+    let rate = match stats["inclusion_rate"].as_f64() {
+        Some(rate) => rate,
+        None => return,
+    };
+    let target = config["target_inclusion_rate"].as_f64().unwrap_or(0.5);
+    let step = config["tip_step"].as_f64().unwrap_or(0.1);
+    let min = config["min_tip_multiplier"].as_f64().unwrap_or(0.5);
+    let max = config["max_tip_multiplier"].as_f64().unwrap_or(3.0);
+
+    let mut multiplier = tip_multiplier_state().lock().unwrap();
+    let previous = *multiplier;
+    if rate < target {
+        *multiplier = (*multiplier + step).min(max);
+    } else if rate > target + config["tip_margin"].as_f64().unwrap_or(0.2) {
+        *multiplier = (*multiplier - step).max(min);
+    }
+    if (*multiplier - previous).abs() > f64::EPSILON {
+        info!("Inclusion rate {:.2} vs target {:.2}: tip multiplier {:.2} -> {:.2}", rate, target, previous, *multiplier);
+    }
+}
+
+// Follow our submissions from the event bus and attribute each new block to its builder
+pub async fn run_builder_analysis(web3: web3::Web3<FailoverTransport>) {
+    let mut submissions = events::subscribe();
+    let mut pending: Vec<PendingSubmission> = Vec::new();
+    let mut last_block = 0u64;
+    let mut blocks_since_retune = 0u64;
+
+    loop {
+        let config = load_builders_config();
+        let head = match web3.eth().block_number().await {
+            Ok(head) => head.as_u64(),
+            Err(e) => {
+                warn!("Builder analysis could not read the head block: {}", e);
+                sleep(Duration::from_secs(3)).await;
+                continue;
+            }
+        };
+
+        loop {
+            match submissions.try_recv() {
+                Ok(event) if event.kind == "execution_submitted" => {
+                    if let Some(tx_hash) = event.payload["tx_hash"].as_str().and_then(|hash| hash.parse::<H256>().ok()) {
+                        pending.push(PendingSubmission { tx_hash, submitted_block: head });
+                    }
+                }
+                Ok(_) | Err(TryRecvError::Lagged(_)) => {}
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+            }
+        }
+
+        if head > last_block && !pending.is_empty() {
+            let from = if last_block == 0 { head } else { last_block + 1 };
+            for number in from..=head {
+                match web3.eth().block(BlockId::Number(BlockNumber::Number(number.into()))).await {
+                    Ok(Some(block)) => process_block(&web3, &config, &block, &mut pending).await,
+                    Ok(None) => {}
+                    Err(e) => warn!("Builder analysis could not fetch block {}: {}", number, e),
+                }
+                blocks_since_retune += 1;
+            }
+        }
+        last_block = head;
+
+        if blocks_since_retune >= config["retune_every_blocks"].as_u64().unwrap_or(300) {
+            retune_tips(&config);
+            blocks_since_retune = 0;
+        }
+        sleep(Duration::from_secs(config["poll_interval_secs"].as_u64().unwrap_or(3))).await;
+    }
+}
+
+// Inclusions per builder per day, for charting how our flow spreads across builders over time
+pub fn inclusions_by_day() -> Value {
+    let config = load_builders_config();
+    let contents = fs::read_to_string(records_path(&config)).unwrap_or_default();
+    let mut days: BTreeMap<String, HashMap<String, u64>> = BTreeMap::new();
+    for record in contents.lines().filter_map(|line| serde_json::from_str::<Value>(line).ok()) {
+        if record["kind"].as_str() != Some("inclusion") {
+            continue;
+        }
+        let day = record["timestamp"].as_str().map(|ts| ts.chars().take(10).collect::<String>()).unwrap_or_default();
+        *days.entry(day).or_default().entry(record["builder"].as_str().unwrap_or("unknown").to_string()).or_default() += 1;
+    }
+    json!(days)
+}
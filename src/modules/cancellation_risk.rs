@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use web3::types::{Address, Transaction, U256};
+use web3::Transport;
+
+use crate::modules::inclusion;
+
+// Senders whose replacement history is kept; the least recently seen is evicted past this
+const MAX_SENDERS: usize = 10_000;
+
+// Load the sandwich configuration (cancellation thresholds live there)
+fn load_sandwich_config() -> Value {
+    let config_path = "config/sandwich_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read sandwich config file");
+    serde_json::from_str(&config_data).expect("Unable to parse sandwich config file")
+}
+
+// Pending transactions resolved by the mempool recorder and how many of them were replaced
+#[derive(Clone, Copy, Debug, Default)]
+struct ReplacementCounts {
+    observed: u64,
+    replaced: u64,
+}
+
+impl ReplacementCounts {
+    fn record(&mut self, replaced: bool) {
+        self.observed += 1;
+        self.replaced += replaced as u64;
+    }
+
+    // Replacement rate pulled towards the prior until there are weight samples behind it
+    fn rate(&self, prior: f64, weight: f64) -> f64 {
+        (self.replaced as f64 + prior * weight) / (self.observed as f64 + weight)
+    }
+}
+
+// Counts per fee decile at first sight
+fn fee_history() -> &'static Mutex<HashMap<usize, ReplacementCounts>> {
+    static HISTORY: OnceLock<Mutex<HashMap<usize, ReplacementCounts>>> = OnceLock::new();
+    HISTORY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Counts per sender, with when the sender was last seen
+fn sender_history() -> &'static Mutex<HashMap<Address, (Instant, ReplacementCounts)>> {
+    static HISTORY: OnceLock<Mutex<HashMap<Address, (Instant, ReplacementCounts)>>> = OnceLock::new();
+    HISTORY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn decile(percentile: f64) -> usize {
+    ((percentile * 10.0) as usize).min(9)
+}
+
+// Record whether a pending transaction was included or replaced by the same sender and nonce,
+// keyed by its sender and fee decile at first sight
+pub fn record_outcome(sender: Option<Address>, gas_price_percentile: f64, replaced: bool) {
+    fee_history().lock().unwrap().entry(decile(gas_price_percentile)).or_default().record(replaced);
+    let sender = match sender {
+        Some(sender) => sender,
+        None => return,
+    };
+    let mut senders = sender_history().lock().unwrap();
+    if senders.len() >= MAX_SENDERS && !senders.contains_key(&sender) {
+        let oldest = senders.iter().min_by_key(|(_, (seen, _))| *seen).map(|(address, _)| *address);
+        if let Some(oldest) = oldest {
+            senders.remove(&oldest);
+        }
+    }
+    let entry = senders.entry(sender).or_insert((Instant::now(), ReplacementCounts::default()));
+    entry.0 = Instant::now();
+    entry.1.record(replaced);
+}
+
+// What the replacement estimate for one victim was built from
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CancellationRisk {
+    pub gas_price_percentile: f64,
+    pub fee_samples: u64,
+    pub sender_samples: u64,
+    // Chance the victim is replaced or cancelled before the bundle lands (0.0 - 1.0)
+    pub probability: f64,
+}
+
+// Estimate from the victim's fee level and its sender's history: the fee decile's replacement rate
+// (low payers get sped up or cancelled more) is the prior for the sender's own rate, and both lean on
+// the configured prior until they have prior_weight samples
+pub fn estimate_with(sender: Option<Address>, gas_price_percentile: f64, config: &Value) -> CancellationRisk {
+    let prior = config["cancellation_prior"].as_f64().unwrap_or(0.05);
+    let weight = config["cancellation_prior_weight"].as_f64().unwrap_or(20.0);
+    let fee_counts = fee_history().lock().unwrap().get(&decile(gas_price_percentile)).copied().unwrap_or_default();
+    let sender_counts = sender
+        .and_then(|sender| sender_history().lock().unwrap().get(&sender).map(|(_, counts)| *counts))
+        .unwrap_or_default();
+    let fee_rate = fee_counts.rate(prior, weight);
+    CancellationRisk {
+        gas_price_percentile,
+        fee_samples: fee_counts.observed,
+        sender_samples: sender_counts.observed,
+        probability: sender_counts.rate(fee_rate, weight),
+    }
+}
+
+// Estimate for a pending victim at its current fee percentile
+pub async fn estimate<T: Transport>(web3: &web3::Web3<T>, victim: &Transaction) -> Result<CancellationRisk, web3::Error> {
+    let gas_price_percentile = inclusion::gas_price_percentile(web3, victim).await?;
+    Ok(estimate_with(victim.from, gas_price_percentile, &load_sandwich_config()))
+}
+
+// Everything the bundle decision depends on, recorded so the decision can be replayed. Amounts are in wei
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RaceInputs {
+    pub risk: CancellationRisk,
+    pub expected_profit: U256,
+    pub gas_cost: U256,
+    // Tip the victim's size bucket would pay
+    pub tip: U256,
+    pub max_probability: f64,
+    pub min_expected_profit: U256,
+}
+
+impl RaceInputs {
+    pub fn new(risk: CancellationRisk, expected_profit: U256, gas_cost: U256, tip: U256) -> Self {
+        let config = load_sandwich_config();
+        RaceInputs {
+            risk,
+            expected_profit,
+            gas_cost,
+            tip,
+            max_probability: config["cancellation_max_probability"].as_f64().unwrap_or(0.5),
+            min_expected_profit: config["cancellation_min_expected_profit"]
+                .as_str()
+                .and_then(|amount| U256::from_dec_str(amount).ok())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RaceDecision {
+    pub submit: bool,
+    // Tip scaled by the chance the victim is still there when the bundle lands
+    pub tip: U256,
+    pub expected_value: U256,
+    pub reason: String,
+}
+
+fn scale(amount: U256, factor: f64) -> U256 {
+    U256::from((amount.as_u128() as f64 * factor.clamp(0.0, 1.0)) as u128)
+}
+
+// Pure bundle decision: a replaced victim invalidates the bundle, so the tip is only worth paying
+// in proportion to the victim surviving, and a bundle whose risk-weighted profit after gas and the
+// scaled tip is marginal isn't sent
+pub fn decide(inputs: &RaceInputs) -> RaceDecision {
+    let survival = 1.0 - inputs.risk.probability.clamp(0.0, 1.0);
+    let tip = scale(inputs.tip, survival);
+    let net = inputs.expected_profit.saturating_sub(inputs.gas_cost).saturating_sub(tip);
+    let expected_value = scale(net, survival);
+    let (submit, reason) = if inputs.risk.probability > inputs.max_probability {
+        (false, "victim_likely_replaced")
+    } else if expected_value.is_zero() || expected_value < inputs.min_expected_profit {
+        (false, "marginal_after_risk")
+    } else {
+        (true, "submit")
+    };
+    RaceDecision { submit, tip, expected_value, reason: reason.to_string() }
+}
+
+// Replacement rates per fee decile, for the dashboard
+pub fn snapshot() -> Value {
+    let config = load_sandwich_config();
+    let prior = config["cancellation_prior"].as_f64().unwrap_or(0.05);
+    let weight = config["cancellation_prior_weight"].as_f64().unwrap_or(20.0);
+    let history = fee_history().lock().unwrap();
+    let deciles: Vec<Value> = (0..10)
+        .map(|decile| {
+            let counts = history.get(&decile).copied().unwrap_or_default();
+            json!({
+                "fee_level": format!("p{}-p{}", decile * 10, decile * 10 + 10),
+                "observed": counts.observed,
+                "replaced": counts.replaced,
+                "rate": counts.rate(prior, weight),
+            })
+        })
+        .collect();
+    json!({ "deciles": deciles, "senders_tracked": sender_history().lock().unwrap().len() })
+}
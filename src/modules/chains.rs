@@ -0,0 +1,387 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use web3::ethabi::{self, ParamType};
+use web3::signing::{keccak256, Key, SecretKey, SecretKeyRef};
+use web3::types::{Address, BlockId, BlockNumber, Bytes, CallRequest, TransactionRequest, H256, U256};
+use web3::Transport;
+use futures::future::{select_ok, FutureExt};
+use log::{info, warn};
+use chrono::Utc;
+use thiserror::Error;
+
+use crate::modules::ens;
+use crate::modules::providers::{self, FailoverTransport};
+use crate::modules::signer::{self, SignerError};
+use crate::modules::testnet;
+
+// Load the chain profiles
+fn load_chains_config() -> Value {
+    let config_path = "config/chains_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read chains config file");
+    testnet::apply(config_path, serde_json::from_str(&config_data).expect("Unable to parse chains config file"))
+}
+
+// How a transaction gets ordered on a chain, which decides how we send it
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubmissionStrategy {
+    // Mainnet-style public mempool: ordering is bought with the priority fee
+    PublicMempool,
+    // Arbitrum first-come-first-served: tips don't buy ordering, so send with no tip to every
+    // sequencer endpoint at once and let the fastest path win
+    FcfsRace { endpoints: Vec<String> },
+    // Arbitrum Timeboost: submit on the express lane while we control the round, otherwise race FCFS
+    ExpressLane { endpoints: Vec<String>, auction_contract: Address, controller_key_env: String },
+    // OP-stack sequencers order by priority fee; sending straight to the sequencer skips gossip
+    SequencerPriority { endpoints: Vec<String> },
+}
+
+// When a block stops being able to reorg out on a chain
+#[derive(Clone, Debug, PartialEq)]
+pub enum FinalityRule {
+    // Final once this many blocks are built on top of it
+    Depth(u64),
+    // Final once the node's safe head reaches it: L2 blocks whose batch is posted to L1
+    Safe,
+    // Final once the node's finalized head reaches it: proof-of-stake checkpoint finality
+    Finalized,
+}
+
+// Confirmation semantics of a chain: how many blocks make a transaction count as landed (L2 soft
+// confirmations come from the sequencer straight away) and when it is final
+#[derive(Clone, Debug, PartialEq)]
+pub struct Finality {
+    pub confirmations: u64,
+    pub rule: FinalityRule,
+}
+
+impl Default for Finality {
+    fn default() -> Self {
+        Finality { confirmations: 1, rule: FinalityRule::Finalized }
+    }
+}
+
+impl Finality {
+    fn from_config(chain: &Value) -> Result<Self, ChainError> {
+        let finality = &chain["finality"];
+        if finality.is_null() {
+            return Ok(Finality::default());
+        }
+        let rule = match finality["final"].as_str().unwrap_or("finalized") {
+            "finalized" => FinalityRule::Finalized,
+            "safe" => FinalityRule::Safe,
+            "depth" => FinalityRule::Depth(finality["final_depth"].as_u64().ok_or(ChainError::MissingConfig("finality.final_depth"))?),
+            other => return Err(ChainError::UnknownFinality(other.to_string())),
+        };
+        Ok(Finality { confirmations: finality["confirmations"].as_u64().unwrap_or(1).max(1), rule })
+    }
+
+    // Whether a block has its soft confirmations at this head; the block itself is the first
+    pub fn is_confirmed(&self, block: u64, head: u64) -> bool {
+        head.saturating_add(1) >= block.saturating_add(self.confirmations)
+    }
+
+    // Highest block that is final right now
+    pub async fn final_block(&self, web3: &web3::Web3<FailoverTransport>) -> Result<u64, ChainError> {
+        let tag = match self.rule {
+            FinalityRule::Depth(depth) => return Ok(web3.eth().block_number().await?.as_u64().saturating_sub(depth)),
+            FinalityRule::Safe => BlockNumber::Safe,
+            FinalityRule::Finalized => BlockNumber::Finalized,
+        };
+        web3.eth()
+            .block(BlockId::Number(tag))
+            .await?
+            .and_then(|block| block.number)
+            .map(|number| number.as_u64())
+            .ok_or(ChainError::NoFinalBlock)
+    }
+}
+
+// The chain we run on and how we submit there
+#[derive(Clone, Debug)]
+pub struct ChainProfile {
+    pub name: String,
+    pub chain_id: u64,
+    pub strategy: SubmissionStrategy,
+    // Sequencer feed for pre-confirmation data, where the chain has one
+    pub feed_url: Option<String>,
+    pub finality: Finality,
+}
+
+// Profile for the chain selected by "chain" in config/chains_config.json
+pub fn active_chain() -> Result<ChainProfile, ChainError> {
+    let config = load_chains_config();
+    let name = config["chain"].as_str().unwrap_or("ethereum").to_string();
+    let chain = &config["chains"][&name];
+    if !chain.is_object() {
+        return Err(ChainError::UnknownChain(name));
+    }
+
+    let endpoints: Vec<String> = chain["sequencer_endpoints"]
+        .as_array()
+        .map(|list| list.iter().filter_map(|url| url.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    let strategy = match chain["submission"].as_str().unwrap_or("public_mempool") {
+        "public_mempool" => SubmissionStrategy::PublicMempool,
+        "fcfs" => SubmissionStrategy::FcfsRace { endpoints },
+        "express_lane" => SubmissionStrategy::ExpressLane {
+            endpoints,
+            auction_contract: chain["timeboost"]["auction_contract"]
+                .as_str()
+                .and_then(ens::parse_address)
+                .ok_or(ChainError::MissingConfig("timeboost.auction_contract"))?,
+            controller_key_env: chain["timeboost"]["controller_key_env"].as_str().unwrap_or("TAZ_EXPRESS_LANE_KEY").to_string(),
+        },
+        "sequencer_priority" => SubmissionStrategy::SequencerPriority { endpoints },
+        other => return Err(ChainError::UnknownStrategy(other.to_string())),
+    };
+
+    Ok(ChainProfile {
+        chain_id: chain["chain_id"].as_u64().ok_or(ChainError::MissingConfig("chain_id"))?,
+        feed_url: chain["feed_url"].as_str().map(String::from),
+        finality: Finality::from_config(chain)?,
+        name,
+        strategy,
+    })
+}
+
+// Finality of the active chain; mainnet defaults when the chain config can't be read
+pub fn finality() -> Finality {
+    match active_chain() {
+        Ok(chain) => chain.finality,
+        Err(e) => {
+            warn!("Using default finality: {}", e);
+            Finality::default()
+        }
+    }
+}
+
+// Latest race result per endpoint, so the dashboard can show which sequencer path is fastest
+fn race_results() -> &'static Mutex<Vec<Value>> {
+    static RESULTS: OnceLock<Mutex<Vec<Value>>> = OnceLock::new();
+    RESULTS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+// Express lane sequence numbers restart every round
+fn express_lane_sequence() -> &'static Mutex<(u64, u64)> {
+    static SEQUENCE: OnceLock<Mutex<(u64, u64)>> = OnceLock::new();
+    SEQUENCE.get_or_init(|| Mutex::new((0, 0)))
+}
+
+// Broadcast a signed transaction to every endpoint and our own node at once; the first acceptance wins
+async fn race_raw(web3: &web3::Web3<FailoverTransport>, endpoints: &[String], raw: Bytes) -> Result<H256, ChainError> {
+    let node = web3.clone();
+    let node_raw = raw.clone();
+    let mut sends = vec![async move { node.eth().send_raw_transaction(node_raw).await.map(|hash| (String::from("node"), hash)) }.boxed()];
+    for url in endpoints {
+        let client = web3::Web3::new(FailoverTransport::http(url)?);
+        let url = url.clone();
+        let raw = raw.clone();
+        sends.push(async move { client.eth().send_raw_transaction(raw).await.map(|hash| (url, hash)) }.boxed());
+    }
+
+    let started = Instant::now();
+    let ((winner, tx_hash), _) = select_ok(sends).await?;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    info!("Raced {:?} to {} endpoints, first accepted by {} in {}ms", tx_hash, endpoints.len() + 1, winner, latency_ms);
+
+    let mut results = race_results().lock().unwrap();
+    results.push(json!({ "timestamp": Utc::now().to_rfc3339(), "tx_hash": tx_hash, "winner": winner, "latency_ms": latency_ms }));
+    let overflow = results.len().saturating_sub(100);
+    results.drain(..overflow);
+    Ok(tx_hash)
+}
+
+// Current Timeboost round from the auction contract's round timing
+async fn current_round(web3: &web3::Web3<FailoverTransport>, auction_contract: Address) -> Result<u64, ChainError> {
+    let request = CallRequest {
+        to: Some(auction_contract),
+        data: Some(Bytes(keccak256(b"roundTimingInfo()")[..4].to_vec())),
+        ..Default::default()
+    };
+    let output = web3.eth().call(request, None).await?;
+    let fields = ethabi::decode(&[ParamType::Int(64), ParamType::Uint(64), ParamType::Uint(64), ParamType::Uint(64)], &output.0)?;
+    let offset = fields[0].clone().into_int().unwrap_or_default().low_u64();
+    let duration = fields[1].clone().into_uint().unwrap_or_default().low_u64().max(1);
+    Ok((Utc::now().timestamp() as u64).saturating_sub(offset) / duration)
+}
+
+// Express lane controller for a round, from the two most recently resolved rounds
+async fn express_lane_controller(web3: &web3::Web3<FailoverTransport>, auction_contract: Address, round: u64) -> Result<Option<Address>, ChainError> {
+    let request = CallRequest {
+        to: Some(auction_contract),
+        data: Some(Bytes(keccak256(b"resolvedRounds()")[..4].to_vec())),
+        ..Default::default()
+    };
+    let output = web3.eth().call(request, None).await?;
+    let round_type = ParamType::Tuple(vec![ParamType::Address, ParamType::Uint(64)]);
+    let rounds = ethabi::decode(&[round_type.clone(), round_type], &output.0)?;
+    Ok(rounds.into_iter().filter_map(|resolved| resolved.into_tuple()).find_map(|fields| {
+        let controller = fields[0].clone().into_address()?;
+        let resolved_round = fields[1].clone().into_uint()?.low_u64();
+        (resolved_round == round).then_some(controller)
+    }))
+}
+
+// Submit on the express lane: the controller signs chain id, auction contract, round, sequence
+// number and the raw transaction under the TIMEBOOST_BID domain
+async fn submit_express_lane(
+    chain_id: u64,
+    endpoint: &str,
+    auction_contract: Address,
+    controller_key: &SecretKey,
+    round: u64,
+    raw: Bytes,
+) -> Result<H256, ChainError> {
+    let sequence = {
+        let mut state = express_lane_sequence().lock().unwrap();
+        if state.0 != round {
+            *state = (round, 0);
+        }
+        state.1 += 1;
+        state.1 - 1
+    };
+
+    let mut payload = keccak256(b"TIMEBOOST_BID").to_vec();
+    let mut chain_id_word = [0u8; 32];
+    U256::from(chain_id).to_big_endian(&mut chain_id_word);
+    payload.extend_from_slice(&chain_id_word);
+    payload.extend_from_slice(auction_contract.as_bytes());
+    payload.extend_from_slice(&round.to_be_bytes());
+    payload.extend_from_slice(&sequence.to_be_bytes());
+    payload.extend_from_slice(&raw.0);
+    let prefixed = [format!("\x19Ethereum Signed Message:\n{}", payload.len()).into_bytes(), payload].concat();
+    let signature = SecretKeyRef::new(controller_key)
+        .sign(&keccak256(&prefixed), None)
+        .map_err(|_| ChainError::SigningFailed)?;
+    let mut signature_bytes = Vec::with_capacity(65);
+    signature_bytes.extend_from_slice(signature.r.as_bytes());
+    signature_bytes.extend_from_slice(signature.s.as_bytes());
+    signature_bytes.push(signature.v as u8);
+
+    let submission = json!({
+        "chainId": format!("{:#x}", chain_id),
+        "round": format!("{:#x}", round),
+        "auctionContractAddress": auction_contract,
+        "sequenceNumber": format!("{:#x}", sequence),
+        "transaction": raw,
+        "options": null,
+        "signature": Bytes(signature_bytes),
+    });
+    let client = web3::Web3::new(FailoverTransport::http(endpoint)?);
+    client.transport().execute("timeboost_sendExpressLaneTransaction", vec![submission]).await?;
+
+    // The express lane returns nothing; the hash is that of the raw transaction we sent
+    let tx_hash = H256::from(keccak256(&raw.0));
+    info!("Sent {:?} on the Timeboost express lane (round {}, sequence {})", tx_hash, round, sequence);
+    Ok(tx_hash)
+}
+
+// Send a transaction from the wallet the way the active chain orders them
+pub async fn submit(web3: &web3::Web3<FailoverTransport>, strategy: &str, mut request: TransactionRequest) -> Result<H256, ChainError> {
+    let chain = active_chain()?;
+    match chain.strategy {
+        SubmissionStrategy::PublicMempool => match providers::fastest_submission_client() {
+            // Signed locally (or by the wallet's node without a key), broadcast through the
+            // lowest-latency endpoint
+            Some(client) => {
+                let raw = signer::sign_request(web3, strategy, request).await?;
+                Ok(client.eth().send_raw_transaction(raw).await?)
+            }
+            None => {
+                let raw = signer::sign_request(web3, strategy, request).await?;
+                Ok(web3.eth().send_raw_transaction(raw).await?)
+            }
+        },
+        SubmissionStrategy::SequencerPriority { endpoints } => {
+            let raw = signer::sign_request(web3, strategy, request).await?;
+            race_raw(web3, &endpoints, raw).await
+        }
+        SubmissionStrategy::FcfsRace { endpoints } => {
+            // Arrival order is all that counts, a tip is wasted
+            request.max_priority_fee_per_gas = Some(U256::zero());
+            let raw = signer::sign_request(web3, strategy, request).await?;
+            race_raw(web3, &endpoints, raw).await
+        }
+        SubmissionStrategy::ExpressLane { endpoints, auction_contract, controller_key_env } => {
+            request.max_priority_fee_per_gas = Some(U256::zero());
+            let raw = signer::sign_request(web3, strategy, request).await?;
+
+            let controller_key = std::env::var(&controller_key_env)
+                .ok()
+                .and_then(|key| SecretKey::from_str(key.trim_start_matches("0x")).ok());
+            if let (Some(controller_key), Some(endpoint)) = (controller_key, endpoints.first()) {
+                let round = current_round(web3, auction_contract).await?;
+                let controller = express_lane_controller(web3, auction_contract, round).await?;
+                if controller == Some(SecretKeyRef::new(&controller_key).address()) {
+                    match submit_express_lane(chain.chain_id, endpoint, auction_contract, &controller_key, round, raw.clone()).await {
+                        Ok(tx_hash) => return Ok(tx_hash),
+                        Err(e) => warn!("Express lane submission failed, racing FCFS instead: {}", e),
+                    }
+                }
+            }
+            race_raw(web3, &endpoints, raw).await
+        }
+    }
+}
+
+// Active chain and recent sequencer race results for the dashboard
+pub fn snapshot() -> Value {
+    let chain = match active_chain() {
+        Ok(chain) => chain,
+        Err(e) => return json!({ "error": e.to_string() }),
+    };
+    let strategy = match &chain.strategy {
+        SubmissionStrategy::PublicMempool => "public_mempool",
+        SubmissionStrategy::FcfsRace { .. } => "fcfs",
+        SubmissionStrategy::ExpressLane { .. } => "express_lane",
+        SubmissionStrategy::SequencerPriority { .. } => "sequencer_priority",
+    };
+    let finality = match chain.finality.rule {
+        FinalityRule::Depth(depth) => format!("{} blocks deep", depth),
+        FinalityRule::Safe => String::from("safe head"),
+        FinalityRule::Finalized => String::from("finalized head"),
+    };
+    json!({
+        "chain": chain.name,
+        "chain_id": chain.chain_id,
+        "submission": strategy,
+        "confirmations": chain.finality.confirmations,
+        "finality": finality,
+        "feed_url": chain.feed_url,
+        "races": *race_results().lock().unwrap(),
+    })
+}
+
+// Custom error type for chain-specific submission
+#[derive(Error, Debug)]
+pub enum ChainError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Signer error: {0}")]
+    SignerError(#[from] SignerError),
+    #[error("Unknown chain: {0}")]
+    UnknownChain(String),
+    #[error("Unknown submission strategy: {0}")]
+    UnknownStrategy(String),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("Failed to sign the express lane submission")]
+    SigningFailed,
+    #[error("Unknown finality rule: {0}")]
+    UnknownFinality(String),
+    #[error("The node did not return a safe or finalized block")]
+    NoFinalBlock,
+}
+
+// Implement conversion for ChainError to Web3 error
+impl From<ChainError> for web3::Error {
+    fn from(error: ChainError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
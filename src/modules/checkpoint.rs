@@ -0,0 +1,340 @@
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use web3::ethabi::{self, ParamType};
+use web3::signing::keccak256;
+use web3::types::{Address, BlockId, BlockNumber, Bytes, CallRequest, FilterBuilder, H256};
+use log::{error, info, warn};
+use chrono::Utc;
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::ens;
+use crate::modules::health_history::{self, HistoryError};
+use crate::modules::pool_state;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::venues::{self, Venue, VenueVersion};
+
+const CHECKPOINT_VERSION: u64 = 1;
+
+// Aave V3 events that change an account's position, with the topic holding the account
+const AAVE_ACCOUNT_EVENTS: [(&str, usize); 5] = [
+    ("Borrow(address,address,address,uint256,uint8,uint256,uint16)", 2),
+    ("Supply(address,address,address,uint256,uint16)", 2),
+    ("Withdraw(address,address,address,uint256)", 2),
+    ("Repay(address,address,address,uint256,bool)", 2),
+    ("LiquidationCall(address,address,address,uint256,uint256,address,bool)", 3),
+];
+
+// Load the checkpoint configuration
+fn load_checkpoint_config() -> Value {
+    let config_path = "config/checkpoint_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read checkpoint config file");
+    serde_json::from_str(&config_data).expect("Unable to parse checkpoint config file")
+}
+
+// Load configuration for liquidation (pool and comptroller addresses live there)
+fn load_liquidation_config() -> Value {
+    let config_path = "config/liquidation_config.json";
+    let config_data = fs::read_to_string(config_path).expect("Unable to read liquidation config file");
+    serde_json::from_str(&config_data).expect("Unable to parse liquidation config file")
+}
+
+// Lending accounts we follow, with their latest health snapshot
+fn borrower_index() -> &'static Mutex<BTreeMap<Address, Value>> {
+    static INDEX: OnceLock<Mutex<BTreeMap<Address, Value>>> = OnceLock::new();
+    INDEX.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+pub fn known_borrowers() -> Vec<Address> {
+    borrower_index().lock().unwrap().keys().copied().collect()
+}
+
+async fn call_at(web3: &web3::Web3<FailoverTransport>, to: Address, signature: &str, block: u64) -> Result<Vec<u8>, CheckpointError> {
+    let request = CallRequest {
+        to: Some(to),
+        data: Some(Bytes(keccak256(signature.as_bytes())[..4].to_vec())),
+        ..Default::default()
+    };
+    Ok(web3.eth().call(request, Some(BlockId::Number(BlockNumber::Number(block.into())))).await?.0)
+}
+
+// Reserves (V2) or price, liquidity and tick (V3) of one pool at the block. Curve pools are always
+// quoted over RPC, so only the block is kept for them
+async fn read_pool_state(web3: &web3::Web3<FailoverTransport>, venue: &Venue, block: u64) -> Result<Value, CheckpointError> {
+    match venue.version {
+        VenueVersion::V2 => {
+            let output = call_at(web3, venue.pool, "getReserves()", block).await?;
+            let fields = ethabi::decode(&[ParamType::Uint(112), ParamType::Uint(112), ParamType::Uint(32)], &output)?;
+            Ok(json!({
+                "block": block,
+                "reserve0": fields[0].clone().into_uint().unwrap_or_default().to_string(),
+                "reserve1": fields[1].clone().into_uint().unwrap_or_default().to_string(),
+            }))
+        }
+        VenueVersion::V3 { .. } => {
+            let slot0 = call_at(web3, venue.pool, "slot0()", block).await?;
+            let fields = ethabi::decode(&[ParamType::Uint(160), ParamType::Int(24)], &slot0[..64.min(slot0.len())])?;
+            let liquidity = call_at(web3, venue.pool, "liquidity()", block).await?;
+            let liquidity = ethabi::decode(&[ParamType::Uint(128)], &liquidity)?;
+            Ok(json!({
+                "block": block,
+                "sqrt_price_x96": fields[0].clone().into_uint().unwrap_or_default().to_string(),
+                "tick": fields[1].clone().into_int().map(|tick| tick.low_u32() as i32),
+                "liquidity": liquidity[0].clone().into_uint().unwrap_or_default().to_string(),
+            }))
+        }
+        VenueVersion::Curve { .. } => Ok(json!({ "block": block })),
+    }
+}
+
+fn venue_to_json(venue: &Venue) -> Value {
+    json!({
+        "dex": venue.dex,
+        "fee": match venue.version { VenueVersion::V2 => None, VenueVersion::V3 { fee } | VenueVersion::Curve { fee, .. } => Some(fee) },
+        "curve_indices": match venue.version { VenueVersion::Curve { low_index, high_index, .. } => Some([low_index, high_index]), _ => None },
+        "pool": venue.pool,
+        "router": venue.router,
+        "quoter": venue.quoter,
+        "quoter_v2": venue.quoter_v2,
+        "v2_fee": venue.v2_fee,
+        "router_fee": venue.router_fee,
+    })
+}
+
+fn venue_from_json(entry: &Value) -> Option<Venue> {
+    let address = |key: &str| entry[key].as_str().and_then(ens::parse_address);
+    Some(Venue {
+        dex: entry["dex"].as_str()?.to_string(),
+        version: match (entry["fee"].as_u64(), entry["curve_indices"].as_array()) {
+            (Some(fee), Some(indices)) => VenueVersion::Curve {
+                fee: fee as u32,
+                low_index: indices.first()?.as_u64()? as u8,
+                high_index: indices.get(1)?.as_u64()? as u8,
+            },
+            (Some(fee), None) => VenueVersion::V3 { fee: fee as u32 },
+            (None, _) => VenueVersion::V2,
+        },
+        pool: address("pool")?,
+        router: address("router")?,
+        quoter: address("quoter"),
+        quoter_v2: entry["quoter_v2"].as_bool().unwrap_or(false),
+        v2_fee: entry["v2_fee"].as_u64().map_or(venues::DEFAULT_V2_FEE, |fee| fee as u32),
+        router_fee: entry["router_fee"].as_u64().unwrap_or(0) as u32,
+    })
+}
+
+// Write the pool cache with every pool's state and every followed account's position, all pinned to
+// the current head, so the next start can skip rebuilding them from scratch
+pub async fn export_checkpoint(web3: &web3::Web3<FailoverTransport>, path: &str) -> Result<Value, CheckpointError> {
+    let block = web3.eth().block_number().await?.as_u64();
+    let liquidation_config = load_liquidation_config();
+    let aave_pool = liquidation_config["aave_pool_address"].as_str().and_then(ens::parse_address);
+    let comptroller = liquidation_config["compound_comptroller_address"].as_str().and_then(ens::parse_address);
+
+    let mut pairs = Vec::new();
+    for ((token_a, token_b), pair_venues) in venues::cached_pairs() {
+        let mut entries = Vec::new();
+        for venue in &pair_venues {
+            let mut entry = venue_to_json(venue);
+            match read_pool_state(web3, venue, block).await {
+                Ok(state) => entry["state"] = state,
+                Err(e) => warn!("Could not read state of pool {:?} for the checkpoint: {}", venue.pool, e),
+            }
+            entries.push(entry);
+        }
+        pairs.push(json!({ "token_a": token_a, "token_b": token_b, "venues": entries }));
+    }
+
+    let mut borrowers: BTreeSet<Address> = known_borrowers().into_iter().collect();
+    borrowers.extend(
+        liquidation_config["scan_borrowers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|value| value.as_str().and_then(ens::parse_address)),
+    );
+    let mut accounts = Vec::new();
+    for borrower in borrowers {
+        match health_history::health_at(web3, aave_pool, comptroller, borrower, block).await {
+            Ok(snapshot) => accounts.push(snapshot.to_json()),
+            Err(e) => warn!("Could not read position of {:?} for the checkpoint: {}", borrower, e),
+        }
+    }
+
+    let (pairs_count, accounts_count) = (pairs.len(), accounts.len());
+    let checkpoint = json!({
+        "version": CHECKPOINT_VERSION,
+        "created_at": Utc::now().to_rfc3339(),
+        "block": block,
+        "pairs": pairs,
+        "borrowers": accounts,
+    });
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write next to the target and rename, so a crash never leaves a half-written checkpoint
+    let staging = format!("{}.tmp", path);
+    fs::write(&staging, serde_json::to_vec(&checkpoint)?)?;
+    fs::rename(&staging, path)?;
+    info!("Checkpoint at block {} written to {} ({} pairs, {} accounts)", block, path, pairs_count, accounts_count);
+    Ok(checkpoint)
+}
+
+// Replay pool and lending events from the block after the checkpoint to the head. Pool state is
+// updated from the events themselves; accounts touched by an Aave event are re-read at the head
+pub async fn sync_forward(web3: &web3::Web3<FailoverTransport>, from_block: u64) -> Result<u64, CheckpointError> {
+    let config = load_checkpoint_config();
+    let chunk = config["sync_chunk_blocks"].as_u64().unwrap_or(2000).max(1);
+    let head = web3.eth().block_number().await?.as_u64();
+    let liquidation_config = load_liquidation_config();
+    let aave_pool = liquidation_config["aave_pool_address"].as_str().and_then(ens::parse_address);
+    let comptroller = liquidation_config["compound_comptroller_address"].as_str().and_then(ens::parse_address);
+
+    let pools: Vec<Address> = venues::cached_pairs().into_iter().flat_map(|(_, venues)| venues.into_iter().map(|venue| venue.pool)).collect();
+    let account_topics: Vec<(H256, usize)> = AAVE_ACCOUNT_EVENTS
+        .iter()
+        .map(|(signature, topic)| (H256::from(keccak256(signature.as_bytes())), *topic))
+        .collect();
+
+    let mut touched: BTreeSet<Address> = BTreeSet::new();
+    let mut start = from_block;
+    while start <= head {
+        let end = (start + chunk - 1).min(head);
+        if !pools.is_empty() {
+            let filter = FilterBuilder::default()
+                .address(pools.clone())
+                .topics(Some(pool_state::event_topics()), None, None, None)
+                .from_block(BlockNumber::Number(start.into()))
+                .to_block(BlockNumber::Number(end.into()))
+                .build();
+            for log in web3.eth().logs(filter).await? {
+                pool_state::apply_log(&log);
+            }
+        }
+        if let Some(pool) = aave_pool {
+            let filter = FilterBuilder::default()
+                .address(vec![pool])
+                .topics(Some(account_topics.iter().map(|(topic, _)| *topic).collect()), None, None, None)
+                .from_block(BlockNumber::Number(start.into()))
+                .to_block(BlockNumber::Number(end.into()))
+                .build();
+            for log in web3.eth().logs(filter).await? {
+                let account_topic = account_topics.iter().find(|(topic, _)| log.topics.first() == Some(topic)).map(|(_, index)| *index);
+                if let Some(account) = account_topic.and_then(|index| log.topics.get(index)) {
+                    touched.insert(Address::from_slice(&account.as_bytes()[12..]));
+                }
+            }
+        }
+        start = end + 1;
+    }
+
+    // New borrowers join the index; existing accounts only need a refresh if something happened to them
+    for account in &touched {
+        match health_history::health_at(web3, aave_pool, comptroller, *account, head).await {
+            Ok(snapshot) => {
+                borrower_index().lock().unwrap().insert(*account, snapshot.to_json());
+            }
+            Err(HistoryError::NotArchive(_)) => {}
+            Err(e) => warn!("Could not refresh position of {:?}: {}", account, e),
+        }
+    }
+    info!("Synced checkpoint forward from block {} to {} ({} accounts touched)", from_block, head, touched.len());
+    Ok(head)
+}
+
+// Seed the pool cache, pool states and borrower index from a checkpoint, then catch up to the head
+pub async fn import_checkpoint(web3: &web3::Web3<FailoverTransport>, path: &str) -> Result<u64, CheckpointError> {
+    let config = load_checkpoint_config();
+    let checkpoint: Value = serde_json::from_slice(&fs::read(path)?)?;
+    let version = checkpoint["version"].as_u64().unwrap_or(0);
+    if version != CHECKPOINT_VERSION {
+        return Err(CheckpointError::UnsupportedVersion(version));
+    }
+    let block = checkpoint["block"].as_u64().ok_or(CheckpointError::Malformed("block"))?;
+    let head = web3.eth().block_number().await?.as_u64();
+    let max_age = config["max_age_blocks"].as_u64().unwrap_or(200_000);
+    if head.saturating_sub(block) > max_age {
+        return Err(CheckpointError::TooOld(block, head));
+    }
+
+    for pair in checkpoint["pairs"].as_array().into_iter().flatten() {
+        let token = |key: &str| pair[key].as_str().and_then(ens::parse_address);
+        let (token_a, token_b) = match (token("token_a"), token("token_b")) {
+            (Some(token_a), Some(token_b)) => (token_a, token_b),
+            _ => continue,
+        };
+        let entries = pair["venues"].as_array().cloned().unwrap_or_default();
+        for entry in &entries {
+            if let (Some(pool), false) = (entry["pool"].as_str().and_then(ens::parse_address), entry["state"].is_null()) {
+                pool_state::insert(pool, entry["state"].clone());
+            }
+        }
+        venues::seed_cache(token_a, token_b, entries.iter().filter_map(venue_from_json).collect());
+    }
+
+    {
+        let mut index = borrower_index().lock().unwrap();
+        for account in checkpoint["borrowers"].as_array().into_iter().flatten() {
+            if let Some(borrower) = account["borrower"].as_str().and_then(ens::parse_address) {
+                index.insert(borrower, account.clone());
+            }
+        }
+    }
+    info!("Loaded checkpoint from block {} ({} blocks behind the head)", block, head.saturating_sub(block));
+
+    sync_forward(web3, block + 1).await
+}
+
+// Restore from the configured checkpoint if there is one; a missing or unusable checkpoint just
+// means the caches are rebuilt from RPC as before
+pub async fn bootstrap(web3: &web3::Web3<FailoverTransport>) {
+    let config = load_checkpoint_config();
+    let path = config["path"].as_str().unwrap_or("data/checkpoint.json");
+    if !config["import_on_start"].as_bool().unwrap_or(true) || !std::path::Path::new(path).exists() {
+        return;
+    }
+    if let Err(e) = import_checkpoint(web3, path).await {
+        warn!("Checkpoint {} not used, rebuilding state from RPC: {}", path, e);
+    }
+}
+
+// Refresh the checkpoint on the export interval
+pub async fn run_checkpoint_export(web3: web3::Web3<FailoverTransport>) {
+    loop {
+        let config = load_checkpoint_config();
+        sleep(Duration::from_secs(config["export_interval_secs"].as_u64().unwrap_or(3600))).await;
+        let path = config["path"].as_str().unwrap_or("data/checkpoint.json");
+        if let Err(e) = export_checkpoint(&web3, path).await {
+            error!("Failed to write checkpoint {}: {}", path, e);
+        }
+    }
+}
+
+// Custom error type for checkpoints
+#[derive(Error, Debug)]
+pub enum CheckpointError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+    #[error("Unsupported checkpoint version {0}")]
+    UnsupportedVersion(u64),
+    #[error("Checkpoint is missing {0}")]
+    Malformed(&'static str),
+    #[error("Checkpoint at block {0} is too far behind the head ({1})")]
+    TooOld(u64, u64),
+}
+
+// Implement conversion for CheckpointError to Web3 error
+impl From<CheckpointError> for web3::Error {
+    fn from(error: CheckpointError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
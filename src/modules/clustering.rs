@@ -0,0 +1,144 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use web3::types::{Address, Transaction, H256, U256};
+
+use crate::modules::ens;
+use crate::modules::pool_state;
+use crate::modules::sandwich;
+use crate::modules::venues;
+
+// Load sandwich config (clustering settings live there)
+fn load_sandwich_config() -> Value {
+    let config_path = "config/sandwich_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read sandwich config file");
+    serde_json::from_str(&config_data).expect("Unable to parse sandwich config file")
+}
+
+// Pending swaps from one sender on one pair within the clustering window, treated as one split order
+#[derive(Clone, Debug)]
+pub struct SwapCluster {
+    pub sender: Address,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub hashes: Vec<H256>,
+    pub total_amount_in: U256,
+    // ETH-equivalent size: the value of ETH-in swaps plus the amount of WETH-in swaps
+    pub total_value: U256,
+    pub first_seen: Instant,
+    pub last_seen: Instant,
+}
+
+impl SwapCluster {
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    // The slice that lands last; a backrun has to follow it
+    pub fn latest(&self) -> Option<H256> {
+        self.hashes.last().copied()
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "sender": self.sender,
+            "token_in": self.token_in,
+            "token_out": self.token_out,
+            "hashes": self.hashes,
+            "total_amount_in": self.total_amount_in.to_string(),
+            "total_value": self.total_value.to_string(),
+            "span_ms": self.last_seen.duration_since(self.first_seen).as_millis() as u64,
+            "aggregate_impact": aggregate_impact(self),
+        })
+    }
+}
+
+// Open clusters keyed by (sender, token_in, token_out). The pair stands in for the pool: the
+// router picks the pool, and slices of one order route through the same one
+type Clusters = HashMap<(Address, Address, Address), SwapCluster>;
+
+fn clusters() -> &'static Mutex<Clusters> {
+    static CLUSTERS: OnceLock<Mutex<Clusters>> = OnceLock::new();
+    CLUSTERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Add a pending swap to its sender's cluster for the pair, dropping clusters that have been quiet
+// for longer than the window. Returns the cluster the swap now belongs to, None for non-swaps
+pub fn observe(transaction: &Transaction) -> Option<SwapCluster> {
+    let config = load_sandwich_config();
+    if !config["cluster_enabled"].as_bool().unwrap_or(true) {
+        return None;
+    }
+    let window = Duration::from_millis(config["cluster_window_ms"].as_u64().unwrap_or(3000));
+    let weth = config["weth_address"].as_str().and_then(ens::parse_address);
+
+    let swap = sandwich::victim_swap(&transaction.input.0, transaction.value)?;
+    let value = if !transaction.value.is_zero() {
+        transaction.value
+    } else if Some(swap.token_in) == weth {
+        swap.amount_in
+    } else {
+        U256::zero()
+    };
+
+    let now = Instant::now();
+    let mut clusters = clusters().lock().unwrap();
+    clusters.retain(|_, cluster| now.duration_since(cluster.last_seen) <= window);
+
+    let sender = transaction.from?;
+    let cluster = clusters.entry((sender, swap.token_in, swap.token_out)).or_insert_with(|| SwapCluster {
+        sender,
+        token_in: swap.token_in,
+        token_out: swap.token_out,
+        hashes: Vec::new(),
+        total_amount_in: U256::zero(),
+        total_value: U256::zero(),
+        first_seen: now,
+        last_seen: now,
+    });
+    // The same pending transaction can arrive from more than one source
+    if !cluster.hashes.contains(&transaction.hash) {
+        cluster.hashes.push(transaction.hash);
+        cluster.total_amount_in = cluster.total_amount_in.saturating_add(swap.amount_in);
+        cluster.total_value = cluster.total_value.saturating_add(value);
+        cluster.last_seen = now;
+    }
+    Some(cluster.clone())
+}
+
+// Price impact of the whole cluster on the deepest cached pool for the pair, from checkpointed
+// or event-synced pool state: one minus the output for the total relative to the marginal rate of a small slice.
+// None when no V2 pool for the pair has cached state (V3 state only gives a spot price)
+pub fn aggregate_impact(cluster: &SwapCluster) -> Option<f64> {
+    let slice = cluster.total_amount_in / U256::from(1000);
+    if slice.is_zero() {
+        return None;
+    }
+    venues::cached_pairs()
+        .into_iter()
+        .filter(|((a, b), _)| (*a, *b) == (cluster.token_in, cluster.token_out) || (*b, *a) == (cluster.token_in, cluster.token_out))
+        .flat_map(|(_, venues)| venues)
+        .filter(|venue| matches!(venue.version, venues::VenueVersion::V2))
+        .filter_map(|venue| {
+            let state = pool_state::get(venue.pool)?;
+            let full = venue.quote_from_state(&state, cluster.token_in, cluster.token_out, cluster.total_amount_in)?;
+            let marginal = venue.quote_from_state(&state, cluster.token_in, cluster.token_out, slice)?;
+            (!marginal.is_zero()).then_some((full, marginal))
+        })
+        .max_by_key(|(full, _)| *full)
+        .map(|(full, marginal)| 1.0 - full.as_u128() as f64 / (marginal.as_u128() as f64 * 1000.0))
+}
+
+// Open clusters with more than one slice, for the dashboard
+pub fn open_clusters() -> Value {
+    let clusters = clusters().lock().unwrap();
+    let open: Vec<Value> = clusters.values().filter(|cluster| cluster.len() > 1).map(|cluster| cluster.to_json()).collect();
+    json!(open)
+}
@@ -0,0 +1,312 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use web3::signing::keccak256;
+use web3::types::{Address, H256, U256};
+use log::{error, info, warn};
+use thiserror::Error;
+use chrono::Utc;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::config_audit::{self, ConfigChange};
+use crate::modules::control;
+use crate::modules::ens;
+use crate::modules::events;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::templates;
+
+// EIP-1967 proxy slots: keccak256("eip1967.proxy.implementation") - 1 and keccak256("eip1967.proxy.beacon") - 1
+const IMPLEMENTATION_SLOT: &str = "360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+const BEACON_SLOT: &str = "a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+
+// Load the code watch configuration
+fn load_code_watch_config() -> Value {
+    let config_path = "config/code_watch_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read code watch config file");
+    serde_json::from_str(&config_data).expect("Unable to parse code watch config file")
+}
+
+// What a tracked contract looks like on-chain: its own code plus whatever a proxy points at
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CodeFingerprint {
+    pub code_hash: H256,
+    pub implementation: Address,
+    pub beacon: Address,
+    pub implementation_code_hash: Option<H256>,
+}
+
+// A detected change that still needs an operator to acknowledge it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingUpgrade {
+    pub name: String,
+    pub strategies: Vec<String>,
+    pub previous: CodeFingerprint,
+    pub current: CodeFingerprint,
+    pub detected_at: String,
+}
+
+// Known-good fingerprints and unacknowledged changes, persisted so a restart keeps strategies blocked
+#[derive(Default, Serialize, Deserialize)]
+struct CodeWatchState {
+    baselines: HashMap<String, CodeFingerprint>,
+    pending: HashMap<String, PendingUpgrade>,
+}
+
+struct TrackedContract {
+    name: String,
+    address: Address,
+    strategies: Vec<String>,
+}
+
+fn state() -> &'static Mutex<CodeWatchState> {
+    static STATE: OnceLock<Mutex<CodeWatchState>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(load_state(&state_path(&load_code_watch_config()))))
+}
+
+fn state_path(config: &Value) -> String {
+    config["state_path"].as_str().unwrap_or("data/code_hashes.json").to_string()
+}
+
+fn load_state(path: &str) -> CodeWatchState {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(path: &str, state: &CodeWatchState) {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match serde_json::to_string_pretty(state) {
+        Ok(contents) => {
+            if let Err(e) = fs::write(path, contents) {
+                error!("Failed to write code watch state {}: {}", path, e);
+            }
+        }
+        Err(e) => error!("Failed to serialize code watch state: {}", e),
+    }
+}
+
+fn key(address: Address) -> String {
+    format!("{:?}", address)
+}
+
+fn tracked_contracts(config: &Value) -> Vec<TrackedContract> {
+    config["contracts"]
+        .as_array()
+        .map(|contracts| {
+            contracts
+                .iter()
+                .filter_map(|contract| {
+                    let address = contract["address"].as_str().and_then(ens::parse_address);
+                    if address.is_none() {
+                        warn!("Skipping tracked contract with invalid address: {}", contract);
+                    }
+                    Some(TrackedContract {
+                        name: contract["name"].as_str().unwrap_or("unnamed").to_string(),
+                        address: address?,
+                        strategies: contract["strategies"]
+                            .as_array()
+                            .map(|list| list.iter().filter_map(|s| s.as_str().map(String::from)).collect())
+                            .unwrap_or_default(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn slot_address(value: H256) -> Address {
+    Address::from_slice(&value.as_bytes()[12..])
+}
+
+async fn code_hash(web3: &web3::Web3<FailoverTransport>, address: Address) -> Result<H256, CodeWatchError> {
+    let code = web3.eth().code(address, None).await?;
+    if code.0.is_empty() {
+        return Err(CodeWatchError::NoCode(address));
+    }
+    Ok(H256::from(keccak256(&code.0)))
+}
+
+// Hash the contract's code and read the EIP-1967 implementation/beacon slots
+pub async fn fingerprint(web3: &web3::Web3<FailoverTransport>, address: Address) -> Result<CodeFingerprint, CodeWatchError> {
+    let own_hash = code_hash(web3, address).await?;
+    let implementation = slot_address(web3.eth().storage(address, U256::from_str(IMPLEMENTATION_SLOT).unwrap(), None).await?);
+    let beacon = slot_address(web3.eth().storage(address, U256::from_str(BEACON_SLOT).unwrap(), None).await?);
+
+    // A swapped implementation at the same address is caught by the slot; this catches a redeployed one
+    let implementation_code_hash = if implementation.is_zero() {
+        None
+    } else {
+        Some(code_hash(web3, implementation).await?)
+    };
+
+    Ok(CodeFingerprint { code_hash: own_hash, implementation, beacon, implementation_code_hash })
+}
+
+fn describe_change(previous: &CodeFingerprint, current: &CodeFingerprint) -> Vec<String> {
+    let mut changes = Vec::new();
+    if previous.code_hash != current.code_hash {
+        changes.push(format!("code hash {:?} -> {:?}", previous.code_hash, current.code_hash));
+    }
+    if previous.implementation != current.implementation {
+        changes.push(format!("implementation {:?} -> {:?}", previous.implementation, current.implementation));
+    }
+    if previous.beacon != current.beacon {
+        changes.push(format!("beacon {:?} -> {:?}", previous.beacon, current.beacon));
+    }
+    if previous.implementation_code_hash != current.implementation_code_hash {
+        changes.push(format!("implementation code hash {:?} -> {:?}", previous.implementation_code_hash, current.implementation_code_hash));
+    }
+    changes
+}
+
+// Fingerprint every tracked contract once; the first sighting becomes the baseline, any later
+// difference pauses the contract's strategies until acknowledged
+pub async fn check_contracts(web3: &web3::Web3<FailoverTransport>) {
+    let config = load_code_watch_config();
+    let path = state_path(&config);
+
+    for contract in tracked_contracts(&config) {
+        let current = match fingerprint(web3, contract.address).await {
+            Ok(current) => current,
+            Err(e) => {
+                warn!("Code watch could not fingerprint {} ({:?}): {}", contract.name, contract.address, e);
+                continue;
+            }
+        };
+
+        let mut state = state().lock().unwrap();
+        let address_key = key(contract.address);
+        let previous = match state.baselines.get(&address_key) {
+            Some(previous) => previous.clone(),
+            None => {
+                info!("Code watch baseline recorded for {} ({:?})", contract.name, contract.address);
+                state.baselines.insert(address_key, current);
+                save_state(&path, &state);
+                continue;
+            }
+        };
+        if previous == current || state.pending.get(&address_key).map(|pending| pending.current == current).unwrap_or(false) {
+            continue;
+        }
+
+        let changes = describe_change(&previous, &current);
+        state.pending.insert(address_key, PendingUpgrade {
+            name: contract.name.clone(),
+            strategies: contract.strategies.clone(),
+            previous,
+            current,
+            detected_at: Utc::now().to_rfc3339(),
+        });
+        save_state(&path, &state);
+        drop(state);
+
+        let reason = format!("unacknowledged code change on {} ({:?})", contract.name, contract.address);
+        for strategy in &contract.strategies {
+            control::pause_strategy(strategy, &reason);
+        }
+        error!("Contract {} ({:?}) changed: {}", contract.name, contract.address, changes.join("; "));
+        events::emit("code_change", json!({
+            "contract": contract.name,
+            "address": contract.address,
+            "changes": changes,
+            "paused_strategies": contract.strategies,
+        }));
+
+        if config["alert"].as_bool().unwrap_or(true) {
+            let (subject, body) = templates::render("code_change", &[
+                ("contract", contract.name.clone()),
+                ("address", templates::address_link(contract.address)),
+                ("changes", changes.join("\n")),
+                ("strategies", contract.strategies.join(", ")),
+            ]);
+            alerts::raise(&format!("code_change:{}", key(contract.address)), Severity::Critical, &subject, &body);
+        }
+    }
+}
+
+// Whether any of the calls targets a contract with an unacknowledged change
+pub fn unacknowledged_target(targets: impl IntoIterator<Item = Address>) -> Option<Address> {
+    let state = state().lock().unwrap();
+    targets.into_iter().find(|target| state.pending.contains_key(&key(*target)))
+}
+
+// Accept a contract's new code as the baseline and resume strategies with nothing else pending
+pub fn acknowledge(address: Address, operator: &str) -> Result<PendingUpgrade, CodeWatchError> {
+    let path = state_path(&load_code_watch_config());
+    let mut state = state().lock().unwrap();
+    let address_key = key(address);
+    let upgrade = state.pending.remove(&address_key).ok_or(CodeWatchError::NothingPending(address))?;
+    state.baselines.insert(address_key.clone(), upgrade.current.clone());
+    save_state(&path, &state);
+
+    for strategy in &upgrade.strategies {
+        let still_blocked = state.pending.values().any(|pending| pending.strategies.contains(strategy));
+        if !still_blocked {
+            control::resume_strategy(strategy, operator);
+        }
+    }
+    drop(state);
+
+    let changes = vec![ConfigChange {
+        path: address_key,
+        old_value: Some(json!(upgrade.previous)),
+        new_value: Some(json!(upgrade.current)),
+    }];
+    config_audit::record_audit("code_watch", operator, "code_watch_ack", &changes);
+    warn!("Code change on {} ({:?}) acknowledged by {}", upgrade.name, address, operator);
+    Ok(upgrade)
+}
+
+// Pending changes and paused strategies for the dashboard
+pub fn snapshot() -> Value {
+    let state = state().lock().unwrap();
+    json!({
+        "tracked": state.baselines.len(),
+        "pending": state.pending,
+        "paused_strategies": control::paused_strategies(),
+    })
+}
+
+// Re-pause strategies for changes left unacknowledged before a restart, then poll on the configured interval
+pub async fn run_code_watch(web3: web3::Web3<FailoverTransport>) {
+    let config = load_code_watch_config();
+    let interval = config["check_interval_secs"].as_u64().unwrap_or(60);
+
+    let pending: Vec<PendingUpgrade> = state().lock().unwrap().pending.values().cloned().collect();
+    for upgrade in pending {
+        for strategy in &upgrade.strategies {
+            control::pause_strategy(strategy, &format!("unacknowledged code change on {}", upgrade.name));
+        }
+    }
+
+    loop {
+        check_contracts(&web3).await;
+        sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+// Custom error type for the code watch
+#[derive(Error, Debug)]
+pub enum CodeWatchError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("No code at {0:?}")]
+    NoCode(Address),
+    #[error("No unacknowledged code change for {0:?}")]
+    NothingPending(Address),
+}
+
+// Implement conversion for CodeWatchError to Web3 error
+impl From<CodeWatchError> for web3::Error {
+    fn from(error: CodeWatchError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
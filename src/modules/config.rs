@@ -0,0 +1,382 @@
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use web3::types::{Address, U256};
+use thiserror::Error;
+
+use crate::modules::ens;
+use crate::modules::testnet;
+
+pub const GLOBAL_CONFIG_PATH: &str = "config/global_config.json";
+
+// Config files carry `//` comments after their fields; drop them (outside of strings) so the rest
+// is plain JSON
+fn strip_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    for line in text.lines() {
+        let (mut in_string, mut escaped, mut end) = (false, false, line.len());
+        let bytes = line.as_bytes();
+        for (index, &byte) in bytes.iter().enumerate() {
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if byte == b'\\' {
+                    escaped = true;
+                } else if byte == b'"' {
+                    in_string = false;
+                }
+            } else if byte == b'"' {
+                in_string = true;
+            } else if byte == b'/' && bytes.get(index + 1) == Some(&b'/') {
+                end = index;
+                break;
+            }
+        }
+        stripped.push_str(&line[..end]);
+        stripped.push('\n');
+    }
+    stripped
+}
+
+// A config file as JSON with its comments removed, as written on disk
+pub fn read_value(path: &str) -> Result<Value, ConfigError> {
+    let data = fs::read_to_string(path).map_err(|e| ConfigError::Unreadable { file: path.to_string(), reason: e.to_string() })?;
+    serde_json::from_str(&strip_comments(&data)).map_err(|e| ConfigError::Invalid { file: path.to_string(), field: None, reason: e.to_string() })
+}
+
+// A config file as JSON, comments removed and the active testnet preset applied
+pub fn load_value(path: &str) -> Result<Value, ConfigError> {
+    Ok(testnet::apply(path, read_value(path)?))
+}
+
+// The top-level field a deserialization error is about: serde names missing fields itself; for a
+// field of the wrong type, it is the one whose removal makes the error go away or turn into
+// "missing field"
+fn failing_field<T: DeserializeOwned>(value: &Value, error: &serde_json::Error) -> Option<String> {
+    let message = error.to_string();
+    if let Some(rest) = message.strip_prefix("missing field `") {
+        return rest.split('`').next().map(String::from);
+    }
+    let fields = value.as_object()?;
+    fields.keys().find_map(|key| {
+        let mut without = fields.clone();
+        without.remove(key);
+        match serde_json::from_value::<T>(Value::Object(without)) {
+            Ok(_) => Some(key.clone()),
+            Err(e) if e.to_string().starts_with(&format!("missing field `{}`", key)) => Some(key.clone()),
+            Err(_) => None,
+        }
+    })
+}
+
+// Deserialize a typed view of an already loaded config, naming the file and field on failure
+pub fn from_value<T: DeserializeOwned>(path: &str, value: &Value) -> Result<T, ConfigError> {
+    serde_json::from_value(value.clone()).map_err(|e| ConfigError::Invalid {
+        file: path.to_string(),
+        field: failing_field::<T>(value, &e),
+        reason: e.to_string(),
+    })
+}
+
+// Load and deserialize a config file into its typed struct
+pub fn load<T: DeserializeOwned>(path: &str) -> Result<T, ConfigError> {
+    from_value(path, &load_value(path)?)
+}
+
+// Address fields accept anything ens::parse_address does: hex, or an ENS name resolved at startup
+fn address<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    ens::parse_address(&text).ok_or_else(|| D::Error::custom(format!("`{}` is neither a valid address nor a resolved ENS name", text)))
+}
+
+fn optional_address<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Address>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(text) => ens::parse_address(&text).map(Some).ok_or_else(|| D::Error::custom(format!("`{}` is neither a valid address nor a resolved ENS name", text))),
+        None => Ok(None),
+    }
+}
+
+// Wei amounts are written either as JSON numbers or as decimal strings (for amounts past u64)
+fn wei<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Number(number) => number.as_u64().map(U256::from).ok_or_else(|| D::Error::custom(format!("{} is not a whole amount of wei", number))),
+        Value::String(text) => U256::from_dec_str(&text).map_err(|_| D::Error::custom(format!("`{}` is not a decimal amount of wei", text))),
+        other => Err(D::Error::custom(format!("expected an amount of wei, found {}", other))),
+    }
+}
+
+fn serialize_wei<S: serde::Serializer>(amount: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&amount.to_string())
+}
+
+// A strategy's config file with its typed fields; everything else stays reachable through `extra`
+pub trait StrategyConfig: DeserializeOwned + Serialize {
+    const NAME: &'static str;
+
+    // The same config as a Value, for code that still reads fields by key
+    fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+}
+
+// Where a strategy's config lives and whether it runs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StrategyEntry {
+    #[serde(default)]
+    pub enabled: bool,
+    pub config_path: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GlobalConfig {
+    pub network: String,
+    pub infura_project_id: String,
+    pub bot_mode: String,
+    // Ceiling for the per-transaction gas limits derived from simulation
+    #[serde(default = "default_gas_limit")]
+    pub default_gas_limit: u64,
+    // May be an ENS name, which is only resolved once the bot is connected
+    pub wallet_address: String,
+    #[serde(default)]
+    pub strategies: BTreeMap<String, StrategyEntry>,
+    // Feature flags and module settings still read by key
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+fn default_gas_limit() -> u64 {
+    5_000_000
+}
+
+impl GlobalConfig {
+    pub fn load() -> Result<Self, ConfigError> {
+        load(GLOBAL_CONFIG_PATH)
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    // Typed config of a strategy, read from the path the global config gives it
+    pub fn strategy<T: StrategyConfig>(&self) -> Result<T, ConfigError> {
+        let default_path = format!("config/{}_config.json", T::NAME);
+        let path = self.strategies.get(T::NAME).map(|entry| entry.config_path.clone()).unwrap_or(default_path);
+        load(&path)
+    }
+
+    // Strategies switched on in the global config
+    pub fn enabled_strategies(&self) -> Vec<String> {
+        self.strategies.iter().filter(|(_, entry)| entry.enabled).map(|(name, _)| name.clone()).collect()
+    }
+
+    // Typed load of every enabled strategy's config, keeping the failures
+    pub fn validate_strategies(&self) -> Vec<ConfigError> {
+        self.enabled_strategies()
+            .iter()
+            .filter_map(|name| match name.as_str() {
+                ArbitrageConfig::NAME => self.strategy::<ArbitrageConfig>().err(),
+                FlashloanConfig::NAME => self.strategy::<FlashloanConfig>().err(),
+                FrontrunningConfig::NAME => self.strategy::<FrontrunningConfig>().err(),
+                LiquidationConfig::NAME => self.strategy::<LiquidationConfig>().err(),
+                SandwichConfig::NAME => self.strategy::<SandwichConfig>().err(),
+                HftConfig::NAME => self.strategy::<HftConfig>().err(),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArbitrageConfig {
+    #[serde(deserialize_with = "address")]
+    pub uniswap_router_address: Address,
+    #[serde(deserialize_with = "address")]
+    pub sushiswap_router_address: Address,
+    #[serde(deserialize_with = "address")]
+    pub arbitrage_token_a: Address,
+    #[serde(deserialize_with = "address")]
+    pub arbitrage_token_b: Address,
+    #[serde(deserialize_with = "address")]
+    pub arbitrage_token_c: Address,
+    pub max_gas_limit: u64,
+    pub min_profit_margin: f64,
+    pub slippage_tolerance: f64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl StrategyConfig for ArbitrageConfig {
+    const NAME: &'static str = "arbitrage";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FlashloanConfig {
+    #[serde(deserialize_with = "address")]
+    pub flashloan_contract_address: Address,
+    #[serde(deserialize_with = "address")]
+    pub lending_pool_address: Address,
+    // The borrowed asset; WETH when no other asset is configured
+    #[serde(default, deserialize_with = "optional_address")]
+    pub asset_address: Option<Address>,
+    #[serde(default, deserialize_with = "optional_address")]
+    pub weth_address: Option<Address>,
+    #[serde(default, deserialize_with = "optional_address")]
+    pub swap_router_a: Option<Address>,
+    #[serde(default, deserialize_with = "optional_address")]
+    pub swap_router_b: Option<Address>,
+    #[serde(default, deserialize_with = "optional_address")]
+    pub intermediate_token: Option<Address>,
+    #[serde(default = "default_slippage")]
+    pub slippage_tolerance: f64,
+    #[serde(default = "default_flashloan_premium_bps")]
+    pub flashloan_premium_bps: u64,
+    // Trade from held inventory instead of borrowing when there is enough of the asset
+    #[serde(default = "default_use_inventory")]
+    pub use_inventory: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+fn default_slippage() -> f64 {
+    0.005
+}
+
+fn default_flashloan_premium_bps() -> u64 {
+    9
+}
+
+fn default_use_inventory() -> bool {
+    true
+}
+
+impl FlashloanConfig {
+    pub fn asset(&self) -> Option<Address> {
+        self.asset_address.or(self.weth_address)
+    }
+}
+
+impl StrategyConfig for FlashloanConfig {
+    const NAME: &'static str = "flashloan";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FrontrunningConfig {
+    #[serde(deserialize_with = "address")]
+    pub uniswap_router_address: Address,
+    #[serde(deserialize_with = "address")]
+    pub token_in: Address,
+    #[serde(deserialize_with = "address")]
+    pub token_out: Address,
+    pub gas_limit: u64,
+    #[serde(deserialize_with = "wei", serialize_with = "serialize_wei")]
+    pub min_transaction_size: U256,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl StrategyConfig for FrontrunningConfig {
+    const NAME: &'static str = "frontrunning";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LiquidationConfig {
+    #[serde(deserialize_with = "address")]
+    pub aave_pool_address: Address,
+    // Compound isn't deployed everywhere; testnet presets remove these
+    #[serde(default, deserialize_with = "optional_address")]
+    pub compound_comptroller_address: Option<Address>,
+    #[serde(default, deserialize_with = "optional_address")]
+    pub ctoken_collateral_address: Option<Address>,
+    #[serde(deserialize_with = "address")]
+    pub exit_router_address: Address,
+    // Position liquidated when the strategy is run directly rather than from the scanner
+    #[serde(default, deserialize_with = "optional_address")]
+    pub borrower_address: Option<Address>,
+    #[serde(default, deserialize_with = "optional_address")]
+    pub collateral_asset: Option<Address>,
+    pub max_gas_limit: u64,
+    #[serde(default = "default_slippage")]
+    pub slippage_tolerance: f64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl StrategyConfig for LiquidationConfig {
+    const NAME: &'static str = "liquidation";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SandwichConfig {
+    #[serde(deserialize_with = "address")]
+    pub sandwich_contract_address: Address,
+    #[serde(deserialize_with = "address")]
+    pub uniswap_router_address: Address,
+    #[serde(deserialize_with = "address")]
+    pub sushiswap_router_address: Address,
+    pub max_gas_limit: u64,
+    pub min_profit_margin: f64,
+    pub slippage_tolerance: f64,
+    // Smallest pending swap worth sandwiching
+    #[serde(default = "default_min_victim_value", deserialize_with = "wei", serialize_with = "serialize_wei")]
+    pub min_victim_value: U256,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+fn default_min_victim_value() -> U256 {
+    U256::exp10(18)
+}
+
+impl StrategyConfig for SandwichConfig {
+    const NAME: &'static str = "sandwich";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HftConfig {
+    #[serde(deserialize_with = "address")]
+    pub asset: Address,
+    // Token the asset is priced and sold in, across every configured DEX
+    #[serde(deserialize_with = "address")]
+    pub quote_token: Address,
+    // Label of the flash loan module the trades run through, for the logs
+    #[serde(default = "default_hft_module")]
+    pub module: String,
+    // Aave pool the trades' flash loans are taken from, and the HFT contract that receives them
+    #[serde(default, deserialize_with = "optional_address")]
+    pub aave_pool_address: Option<Address>,
+    #[serde(default, deserialize_with = "optional_address")]
+    pub hft_contract_address: Option<Address>,
+    #[serde(deserialize_with = "wei", serialize_with = "serialize_wei")]
+    pub expected_profit: U256,
+    #[serde(deserialize_with = "wei", serialize_with = "serialize_wei")]
+    pub gas_fee: U256,
+    pub slippage_tolerance: f64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+fn default_hft_module() -> String {
+    String::from("flashloan")
+}
+
+impl StrategyConfig for HftConfig {
+    const NAME: &'static str = "hft";
+}
+
+// Custom error type for typed configuration
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Unable to read {file}: {reason}")]
+    Unreadable { file: String, reason: String },
+    #[error("Invalid config {file}{}: {reason}", field.as_ref().map(|field| format!(" (field `{}`)", field)).unwrap_or_default())]
+    Invalid { file: String, field: Option<String>, reason: String },
+}
+
+// Implement conversion for ConfigError to Web3 error
+impl From<ConfigError> for web3::Error {
+    fn from(error: ConfigError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,220 @@
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+use log::{error, info};
+use thiserror::Error;
+use chrono::Utc;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::templates;
+
+// Where audit entries are appended
+const AUDIT_LOG_PATH: &str = "data/config_audit.jsonl";
+
+// Hash of the contents each dashboard edit wrote, so the file watcher doesn't audit the bot's own writes
+fn own_writes() -> &'static Mutex<HashMap<String, u64>> {
+    static OWN_WRITES: OnceLock<Mutex<HashMap<String, u64>>> = OnceLock::new();
+    OWN_WRITES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn content_hash(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+// A single changed field between two versions of a config file
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigChange {
+    pub path: String,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+}
+
+impl ConfigChange {
+    pub fn describe(&self) -> String {
+        match (&self.old_value, &self.new_value) {
+            (None, Some(new_value)) => format!("+ {} = {}", self.path, new_value),
+            (Some(old_value), None) => format!("- {} (was {})", self.path, old_value),
+            (Some(old_value), Some(new_value)) => format!("~ {}: {} -> {}", self.path, old_value, new_value),
+            (None, None) => format!("? {}", self.path),
+        }
+    }
+}
+
+// Recursively diff two JSON documents into dotted-path changes
+pub fn diff_configs(old: &Value, new: &Value) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    diff_values("", old, new, &mut changes);
+    changes
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, changes: &mut Vec<ConfigChange>) {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            for (key, old_value) in old_map {
+                let child = join_path(path, key);
+                match new_map.get(key) {
+                    Some(new_value) => diff_values(&child, old_value, new_value, changes),
+                    None => changes.push(ConfigChange { path: child, old_value: Some(old_value.clone()), new_value: None }),
+                }
+            }
+            for (key, new_value) in new_map {
+                if !old_map.contains_key(key) {
+                    changes.push(ConfigChange { path: join_path(path, key), old_value: None, new_value: Some(new_value.clone()) });
+                }
+            }
+        }
+        _ if old != new => changes.push(ConfigChange {
+            path: path.to_string(),
+            old_value: Some(old.clone()),
+            new_value: Some(new.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn join_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+// Append an audit entry (who/when/what) for a config change
+pub fn record_audit(file: &str, operator: &str, source: &str, changes: &[ConfigChange]) {
+    if changes.is_empty() {
+        return;
+    }
+    let _ = fs::create_dir_all("data");
+
+    let entry = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "file": file,
+        "operator": operator,
+        "source": source,
+        "changes": changes.iter().map(|change| json!({
+            "path": change.path,
+            "old": change.old_value,
+            "new": change.new_value,
+        })).collect::<Vec<_>>(),
+    });
+
+    match OpenOptions::new().create(true).append(true).open(AUDIT_LOG_PATH) {
+        Ok(mut audit_log) => {
+            if let Err(e) = writeln!(audit_log, "{}", entry) {
+                error!("Failed to write config audit entry: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open config audit log: {}", e),
+    }
+}
+
+// Read the most recent audit entries, newest first
+pub fn read_audit_log(limit: usize) -> Vec<Value> {
+    let contents = fs::read_to_string(AUDIT_LOG_PATH).unwrap_or_default();
+    contents
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
+}
+
+// Notify operators of a reload, including the diff in the message body
+fn notify_reload(file: &str, operator: &str, changes: &[ConfigChange]) {
+    let diff = changes.iter().map(|change| change.describe()).collect::<Vec<_>>().join("\n");
+    info!("Config {} reloaded by {}:\n{}", file, operator, diff);
+
+    let (subject, body) = templates::render("config_reload", &[
+        ("file", file.to_string()),
+        ("operator", operator.to_string()),
+        ("time", Utc::now().to_rfc3339()),
+        ("diff", diff.clone()),
+    ]);
+    alerts::raise(&format!("config_reload:{}:{}", file, diff), Severity::Warn, &subject, &body);
+}
+
+// Apply an edit made through the dashboard: write the file, audit and notify
+pub fn apply_config_edit(file: &str, new_config: &Value, operator: &str) -> Result<Vec<ConfigChange>, ConfigAuditError> {
+    let old_config: Value = serde_json::from_str(&fs::read_to_string(file)?)?;
+    let changes = diff_configs(&old_config, new_config);
+    if changes.is_empty() {
+        return Ok(changes);
+    }
+
+    let contents = serde_json::to_string_pretty(new_config)?;
+    own_writes().lock().unwrap().insert(file.to_string(), content_hash(&contents));
+    fs::write(file, contents)?;
+    record_audit(file, operator, "dashboard", &changes);
+    notify_reload(file, operator, &changes);
+    Ok(changes)
+}
+
+// Watch config files for changes made outside the bot and audit each reload
+pub async fn watch_config_files(files: Vec<String>, check_interval: u64) {
+    let mut snapshots: HashMap<String, (Option<SystemTime>, Value)> = HashMap::new();
+    for file in &files {
+        let modified = fs::metadata(file).and_then(|meta| meta.modified()).ok();
+        let contents = fs::read_to_string(file).ok().and_then(|data| serde_json::from_str(&data).ok()).unwrap_or(Value::Null);
+        snapshots.insert(file.clone(), (modified, contents));
+    }
+
+    // Host edits carry no operator identity, so use the account running the bot
+    let operator = std::env::var("TAZ_OPERATOR")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| String::from("unknown"));
+
+    loop {
+        sleep(Duration::from_secs(check_interval)).await;
+
+        for file in &files {
+            let modified = fs::metadata(file).and_then(|meta| meta.modified()).ok();
+            let (last_modified, last_contents) = match snapshots.get(file) {
+                Some(snapshot) => snapshot.clone(),
+                None => continue,
+            };
+            if modified == last_modified {
+                continue;
+            }
+
+            let data = fs::read_to_string(file).unwrap_or_default();
+            let new_contents: Value = match serde_json::from_str(&data) {
+                Ok(contents) => contents,
+                Err(_) => {
+                    error!("Config {} changed but could not be parsed, keeping previous version", file);
+                    continue;
+                }
+            };
+
+            // A dashboard edit was already audited and notified when it was applied
+            let own_write = own_writes().lock().unwrap().get(file) == Some(&content_hash(&data));
+            if own_write {
+                own_writes().lock().unwrap().remove(file);
+            }
+
+            let changes = diff_configs(&last_contents, &new_contents);
+            if !changes.is_empty() && !own_write {
+                record_audit(file, &operator, "file", &changes);
+                notify_reload(file, &operator, &changes);
+            }
+            snapshots.insert(file.clone(), (modified, new_contents));
+        }
+    }
+}
+
+// Custom error type for config auditing
+#[derive(Error, Debug)]
+pub enum ConfigAuditError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+}
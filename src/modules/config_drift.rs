@@ -0,0 +1,203 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use web3::ethabi::{self, ParamType, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, CallRequest, U256};
+use log::{info, warn};
+use chrono::Utc;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::ens;
+use crate::modules::events;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::templates;
+use crate::modules::venues::{self, VenueVersion};
+
+// Load the drift check configuration (extra pools and price feeds to verify)
+fn load_drift_config() -> Value {
+    let config_path = "config/drift_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read drift config file");
+    serde_json::from_str(&config_data).expect("Unable to parse drift config file")
+}
+
+fn load_config(file: &str) -> Value {
+    let config_data = fs::read_to_string(format!("config/{}", file)).unwrap_or_default();
+    serde_json::from_str(&config_data).unwrap_or(Value::Null)
+}
+
+// One on-chain fact a configured address should still satisfy
+struct Expectation {
+    subject: String,
+    target: Address,
+    signature: &'static str,
+    output: ParamType,
+    expected: Token,
+}
+
+// A configured address that no longer behaves as the config assumes
+#[derive(Clone, Debug, Serialize)]
+pub struct DriftIssue {
+    pub subject: String,
+    pub target: Address,
+    pub check: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+fn last_report() -> &'static Mutex<(Option<String>, Vec<DriftIssue>)> {
+    static REPORT: OnceLock<Mutex<(Option<String>, Vec<DriftIssue>)>> = OnceLock::new();
+    REPORT.get_or_init(|| Mutex::new((None, Vec::new())))
+}
+
+fn address_at(config: &Value, field: &str) -> Option<Address> {
+    config[field].as_str().and_then(ens::parse_address)
+}
+
+fn expect_address(subject: String, target: Address, signature: &'static str, expected: Address) -> Expectation {
+    Expectation { subject, target, signature, output: ParamType::Address, expected: Token::Address(expected) }
+}
+
+fn expect_uint(subject: String, target: Address, signature: &'static str, expected: U256) -> Expectation {
+    Expectation { subject, target, signature, output: ParamType::Uint(256), expected: Token::Uint(expected) }
+}
+
+// Everything to verify: router and quoter factories, cached and configured pool tokens and
+// factories, price feed decimals and the Aave oracle's base unit
+fn expectations() -> Vec<Expectation> {
+    let mut checks = Vec::new();
+
+    let venues_config = load_config("venues_config.json");
+    for dex in venues_config["dexes"].as_array().cloned().unwrap_or_default() {
+        let name = dex["name"].as_str().unwrap_or("dex").to_string();
+        if let (Some(router), Some(factory)) = (address_at(&dex, "v2_router"), address_at(&dex, "v2_factory")) {
+            checks.push(expect_address(format!("{} v2 router", name), router, "factory()", factory));
+        }
+        if let Some(factory) = address_at(&dex, "v3_factory") {
+            if let Some(router) = address_at(&dex, "v3_router") {
+                checks.push(expect_address(format!("{} v3 router", name), router, "factory()", factory));
+            }
+            if let Some(quoter) = address_at(&dex, "v3_quoter") {
+                checks.push(expect_address(format!("{} v3 quoter", name), quoter, "factory()", factory));
+            }
+        }
+
+        // Pools the venue cache found for this DEX must still trade the pair it filed them under.
+        // Curve pools have no token0/token1 or factory and are checked by the registry lookup itself
+        for ((token_a, token_b), venues) in venues::cached_pairs() {
+            let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+            for venue in venues.iter().filter(|venue| venue.dex == name) {
+                let factory_field = match venue.version {
+                    VenueVersion::V2 => "v2_factory",
+                    VenueVersion::V3 { .. } => "v3_factory",
+                    VenueVersion::Curve { .. } => continue,
+                };
+                let subject = format!("{} pool {:?}", venue.label(), venue.pool);
+                checks.push(expect_address(subject.clone(), venue.pool, "token0()", token0));
+                checks.push(expect_address(subject.clone(), venue.pool, "token1()", token1));
+                if let Some(factory) = address_at(&dex, factory_field) {
+                    checks.push(expect_address(subject, venue.pool, "factory()", factory));
+                }
+            }
+        }
+    }
+
+    let drift_config = load_drift_config();
+    for pool in drift_config["pools"].as_array().cloned().unwrap_or_default() {
+        let name = pool["name"].as_str().unwrap_or("pool").to_string();
+        let address = match address_at(&pool, "address") {
+            Some(address) => address,
+            None => continue,
+        };
+        for (field, signature) in [("token0", "token0()"), ("token1", "token1()"), ("factory", "factory()")] {
+            if let Some(expected) = address_at(&pool, field) {
+                checks.push(expect_address(name.clone(), address, signature, expected));
+            }
+        }
+    }
+    for feed in drift_config["feeds"].as_array().cloned().unwrap_or_default() {
+        let name = feed["name"].as_str().unwrap_or("feed").to_string();
+        if let (Some(address), Some(decimals)) = (address_at(&feed, "address"), feed["decimals"].as_u64()) {
+            checks.push(expect_uint(name, address, "decimals()", U256::from(decimals)));
+        }
+    }
+
+    let liquidation_config = load_config("liquidation_config.json");
+    if let (Some(oracle), Some(decimals)) = (address_at(&liquidation_config, "aave_oracle_address"), liquidation_config["aave_base_decimals"].as_u64()) {
+        checks.push(expect_uint(String::from("aave oracle"), oracle, "BASE_CURRENCY_UNIT()", U256::exp10(decimals as usize)));
+    }
+    checks
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Address(address) => format!("{:?}", address),
+        Token::Uint(value) => value.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+// Run every expectation against the chain. A call that reverts or returns garbage counts as drift:
+// it usually means the address is no longer the contract the config thinks it is
+pub async fn check_drift(web3: &web3::Web3<FailoverTransport>) -> Vec<DriftIssue> {
+    let mut issues = Vec::new();
+    for check in expectations() {
+        let request = CallRequest {
+            to: Some(check.target),
+            data: Some(Bytes(keccak256(check.signature.as_bytes())[..4].to_vec())),
+            ..Default::default()
+        };
+        let actual = match web3.eth().call(request, None).await {
+            Ok(output) => match ethabi::decode(std::slice::from_ref(&check.output), &output.0) {
+                Ok(tokens) if tokens.first() == Some(&check.expected) => continue,
+                Ok(tokens) => tokens.first().map(describe).unwrap_or_default(),
+                Err(_) => format!("undecodable output 0x{}", output.0.iter().map(|byte| format!("{:02x}", byte)).collect::<String>()),
+            },
+            Err(e) => format!("call failed: {}", e),
+        };
+        issues.push(DriftIssue {
+            subject: check.subject,
+            target: check.target,
+            check: check.signature.to_string(),
+            expected: describe(&check.expected),
+            actual,
+        });
+    }
+    issues
+}
+
+// Check on the configured interval, alerting once per drifted field
+pub async fn run_drift_check(web3: web3::Web3<FailoverTransport>) {
+    loop {
+        let config = load_drift_config();
+        let issues = check_drift(&web3).await;
+        if issues.is_empty() {
+            info!("Config drift check passed");
+        }
+        for issue in &issues {
+            warn!("Config drift: {} {}: expected {}, got {}", issue.subject, issue.check, issue.expected, issue.actual);
+            let (subject, body) = templates::render("config_drift", &[
+                ("subject", issue.subject.clone()),
+                ("address", format!("{:?}", issue.target)),
+                ("check", issue.check.clone()),
+                ("expected", issue.expected.clone()),
+                ("actual", issue.actual.clone()),
+            ]);
+            alerts::raise(&format!("config_drift:{:?}:{}", issue.target, issue.check), Severity::Warn, &subject, &body);
+        }
+        if !issues.is_empty() {
+            events::emit("config_drift", json!(issues));
+        }
+        *last_report().lock().unwrap() = (Some(Utc::now().to_rfc3339()), issues);
+        sleep(Duration::from_secs(config["check_interval_secs"].as_u64().unwrap_or(3600))).await;
+    }
+}
+
+// Latest drift report for the dashboard
+pub fn snapshot() -> Value {
+    let report = last_report().lock().unwrap();
+    json!({ "checked_at": report.0, "issues": report.1 })
+}
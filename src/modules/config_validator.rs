@@ -0,0 +1,119 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use log::{error, info};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::config::{ConfigError, GlobalConfig};
+use crate::modules::ens;
+
+const CONFIG_DIR: &str = "config";
+
+// A problem in one config field, reported at startup and on the dashboard
+#[derive(Clone, Debug, Serialize)]
+pub struct ValidationIssue {
+    pub file: String,
+    pub path: String,
+    pub message: String,
+}
+
+// Address fields are named *_address or address, or hold a 0x value of address length
+fn looks_like_address(key: &str, value: &str) -> bool {
+    key == "address" || key.ends_with("_address") || (value.starts_with("0x") && value.len() == 42)
+}
+
+fn check_addresses(file: &str, path: &str, key: &str, value: &Value, issues: &mut Vec<ValidationIssue>) {
+    match value {
+        Value::String(text)
+            if looks_like_address(key, text) && !ens::is_ens_name(text) && ens::parse_address(text).is_none() =>
+        {
+            issues.push(ValidationIssue {
+                file: file.to_string(),
+                path: path.to_string(),
+                message: format!("{} is neither a valid address nor an ENS name", text),
+            });
+        }
+        Value::Object(map) => {
+            for (child_key, child) in map {
+                let child_path = if path.is_empty() { child_key.clone() } else { format!("{}.{}", path, child_key) };
+                check_addresses(file, &child_path, child_key, child, issues);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                check_addresses(file, &format!("{}[{}]", path, index), key, child, issues);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Check every config file: it parses, its address fields are valid, and its ENS names resolved
+pub fn validate_configs() -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut files: Vec<_> = fs::read_dir(CONFIG_DIR)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    files.retain(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"));
+    files.sort();
+
+    for path in files {
+        let file = path.to_string_lossy().to_string();
+        let config = match fs::read_to_string(&path).map(|data| serde_json::from_str::<Value>(&data)) {
+            Ok(Ok(config)) => config,
+            Ok(Err(e)) => {
+                issues.push(ValidationIssue { file, path: String::new(), message: format!("invalid JSON: {}", e) });
+                continue;
+            }
+            Err(e) => {
+                issues.push(ValidationIssue { file, path: String::new(), message: format!("unreadable: {}", e) });
+                continue;
+            }
+        };
+        check_addresses(&file, "", "", &config, &mut issues);
+    }
+
+    // Enabled strategies must also load into their typed configs
+    let typed_errors = match GlobalConfig::load() {
+        Ok(global) => global.validate_strategies(),
+        Err(e) => vec![e],
+    };
+    for error in typed_errors {
+        let (file, path, message) = match error {
+            ConfigError::Unreadable { file, reason } => (file, String::new(), reason),
+            ConfigError::Invalid { file, field, reason } => (file, field.unwrap_or_default(), reason),
+        };
+        issues.push(ValidationIssue { file, path, message });
+    }
+
+    for (name, resolution) in ens::failures() {
+        for field in &resolution.used_by {
+            let (file, path) = field.split_once(':').unwrap_or((field.as_str(), ""));
+            issues.push(ValidationIssue {
+                file: file.to_string(),
+                path: path.to_string(),
+                message: format!("ENS name {} failed to resolve: {}", name, resolution.error.clone().unwrap_or_default()),
+            });
+        }
+    }
+    issues
+}
+
+// Log every issue and raise one alert summarizing them
+pub fn report() -> Vec<ValidationIssue> {
+    let issues = validate_configs();
+    if issues.is_empty() {
+        info!("Config validation passed");
+        return issues;
+    }
+    for issue in &issues {
+        error!("Config issue in {} {}: {}", issue.file, issue.path, issue.message);
+    }
+    let body = issues
+        .iter()
+        .map(|issue| format!("{} {}: {}", issue.file, issue.path, issue.message))
+        .collect::<Vec<_>>()
+        .join("\n");
+    alerts::raise("config_validation", Severity::Warn, &format!("{} config issues", issues.len()), &body);
+    issues
+}
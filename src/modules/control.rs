@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use serde_json::json;
+use log::{info, warn};
+
+use crate::modules::events;
+
+// Global run-state flags shared by strategies and the control API
+static PAUSED: AtomicBool = AtomicBool::new(false);
+static KILL_SWITCH: AtomicBool = AtomicBool::new(false);
+
+// Individually paused strategies and why
+fn paused_strategies_registry() -> &'static Mutex<HashMap<String, String>> {
+    static PAUSED_STRATEGIES: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    PAUSED_STRATEGIES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Whether strategies should skip new executions
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst) || KILL_SWITCH.load(Ordering::SeqCst)
+}
+
+pub fn pause(operator: &str) {
+    PAUSED.store(true, Ordering::SeqCst);
+    info!("Strategies paused by {}", operator);
+    events::emit("paused", json!({ "operator": operator }));
+}
+
+pub fn resume(operator: &str) {
+    PAUSED.store(false, Ordering::SeqCst);
+    events::emit("resumed", json!({ "operator": operator }));
+    if KILL_SWITCH.load(Ordering::SeqCst) {
+        warn!("Resume requested by {} but the kill switch is still engaged", operator);
+    } else {
+        info!("Strategies resumed by {}", operator);
+    }
+}
+
+// The kill switch blocks execution until an admin explicitly disables it
+pub fn is_kill_switch_engaged() -> bool {
+    KILL_SWITCH.load(Ordering::SeqCst)
+}
+
+pub fn engage_kill_switch(operator: &str) {
+    KILL_SWITCH.store(true, Ordering::SeqCst);
+    warn!("Kill switch engaged by {}", operator);
+    events::emit("kill_switch_engaged", json!({ "operator": operator }));
+}
+
+pub fn disable_kill_switch(operator: &str) {
+    KILL_SWITCH.store(false, Ordering::SeqCst);
+    warn!("Kill switch disabled by {}", operator);
+    events::emit("kill_switch_disabled", json!({ "operator": operator }));
+}
+
+// Per-strategy pause, on top of the global flags
+pub fn is_strategy_paused(strategy: &str) -> bool {
+    is_paused() || paused_strategies_registry().lock().unwrap().contains_key(strategy)
+}
+
+pub fn pause_strategy(strategy: &str, reason: &str) {
+    paused_strategies_registry().lock().unwrap().insert(strategy.to_string(), reason.to_string());
+    warn!("Strategy {} paused: {}", strategy, reason);
+    events::emit("strategy_paused", json!({ "strategy": strategy, "reason": reason }));
+}
+
+pub fn resume_strategy(strategy: &str, operator: &str) {
+    if paused_strategies_registry().lock().unwrap().remove(strategy).is_some() {
+        info!("Strategy {} resumed by {}", strategy, operator);
+        events::emit("strategy_resumed", json!({ "strategy": strategy, "operator": operator }));
+    }
+}
+
+pub fn paused_strategies() -> HashMap<String, String> {
+    paused_strategies_registry().lock().unwrap().clone()
+}
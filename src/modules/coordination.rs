@@ -0,0 +1,289 @@
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use sqlx::{Connection, PgConnection};
+use web3::signing::keccak256;
+use log::{error, info, warn};
+use thiserror::Error;
+use chrono::{DateTime, Utc};
+use tokio::time::{sleep, Duration};
+
+// Whether this instance currently holds the wallet lock and may submit transactions
+static WRITER: AtomicBool = AtomicBool::new(false);
+
+// Load the coordination configuration
+fn load_coordination_config() -> Value {
+    let config_path = "config/coordination_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read coordination config file");
+    serde_json::from_str(&config_data).expect("Unable to parse coordination config file")
+}
+
+// Load global config (the execution wallet names the lock)
+fn load_global_config() -> Value {
+    let config_path = "config/global_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read global config file");
+    serde_json::from_str(&config_data).expect("Unable to parse global config file")
+}
+
+// Only the lock holder submits transactions; observers keep scanning and serving the dashboard.
+// With coordination disabled every instance is a writer, as before
+pub fn is_writer() -> bool {
+    WRITER.load(Ordering::SeqCst)
+}
+
+fn set_writer(writer: bool, lock_name: &str) {
+    let was_writer = WRITER.swap(writer, Ordering::SeqCst);
+    match (was_writer, writer) {
+        (false, true) => info!("Acquired wallet lock {}, running as writer", lock_name),
+        (true, false) => error!("Lost wallet lock {}, switching to observer mode", lock_name),
+        _ => {}
+    }
+}
+
+// Identifies this process in lock files and logs
+fn instance_id() -> &'static str {
+    static INSTANCE_ID: OnceLock<String> = OnceLock::new();
+    INSTANCE_ID.get_or_init(|| {
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| String::from("localhost"));
+        format!("{}:{}:{}", host, std::process::id(), Utc::now().timestamp_millis())
+    })
+}
+
+// Lock held by this instance: a lease file, or a dedicated Postgres session holding an advisory lock
+enum WalletLock {
+    File { path: String, ttl: chrono::Duration },
+    Postgres { connection: Box<PgConnection> },
+}
+
+fn lock_name(config: &Value) -> String {
+    config["lock_name"]
+        .as_str()
+        .map(String::from)
+        .or_else(|| load_global_config()["wallet_address"].as_str().map(|wallet| wallet.to_lowercase()))
+        .unwrap_or_else(|| String::from("default"))
+}
+
+// Advisory lock keys are 64-bit; derive one from the lock name
+fn advisory_key(lock_name: &str) -> i64 {
+    let hash = keccak256(format!("taz-bot:{}", lock_name).as_bytes());
+    i64::from_be_bytes(hash[..8].try_into().unwrap())
+}
+
+fn read_lease(path: &str) -> Option<Value> {
+    fs::read_to_string(path).ok().and_then(|contents| serde_json::from_str(&contents).ok())
+}
+
+fn write_lease(path: &str, create_new: bool) -> std::io::Result<()> {
+    let lease = json!({
+        "instance": instance_id(),
+        "pid": std::process::id(),
+        "heartbeat_at": Utc::now().to_rfc3339(),
+    });
+    let mut file = if create_new {
+        OpenOptions::new().write(true).create_new(true).open(path)?
+    } else {
+        OpenOptions::new().write(true).truncate(true).open(path)?
+    };
+    writeln!(file, "{}", lease)
+}
+
+fn lease_expired(lease: &Value, ttl: chrono::Duration) -> bool {
+    lease["heartbeat_at"]
+        .as_str()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|ts| Utc::now() - ts.with_timezone(&Utc) > ttl)
+        .unwrap_or(true)
+}
+
+// Create the lease file atomically; a lease whose holder stopped heartbeating is taken over
+fn try_file_lock(path: &str, ttl: chrono::Duration) -> Result<bool, CoordinationError> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match write_lease(path, true) {
+        Ok(()) => return Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    match read_lease(path) {
+        Some(lease) if lease["instance"] == instance_id() => Ok(true),
+        Some(lease) if !lease_expired(&lease, ttl) => Ok(false),
+        stale => {
+            warn!("Taking over stale wallet lock {} from {}", path, stale.map(|lease| lease["instance"].to_string()).unwrap_or_default());
+            let _ = fs::remove_file(path);
+            // Another observer may race for the same stale lease; only one create_new wins
+            match write_lease(path, true) {
+                Ok(()) => Ok(true),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+                Err(e) => Err(e.into()),
+            }
+        }
+    }
+}
+
+async fn try_postgres_lock(url: &str, key: i64) -> Result<Option<PgConnection>, CoordinationError> {
+    let mut connection = PgConnection::connect(url).await?;
+    let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)").bind(key).fetch_one(&mut connection).await?;
+    Ok(if acquired { Some(connection) } else { None })
+}
+
+async fn try_acquire(config: &Value, name: &str) -> Result<Option<WalletLock>, CoordinationError> {
+    let ttl = chrono::Duration::seconds(config["lease_ttl_secs"].as_i64().unwrap_or(30));
+    match config["backend"].as_str().unwrap_or("file") {
+        "file" => {
+            let lock_dir = config["lock_dir"].as_str().unwrap_or("data/locks");
+            let path = format!("{}/{}.lock", lock_dir, name);
+            Ok(if try_file_lock(&path, ttl)? { Some(WalletLock::File { path, ttl }) } else { None })
+        }
+        "postgres" => {
+            let url = config["postgres_url"].as_str().ok_or(CoordinationError::MissingConfig("postgres_url"))?;
+            Ok(try_postgres_lock(url, advisory_key(name)).await?.map(|connection| WalletLock::Postgres { connection: Box::new(connection) }))
+        }
+        other => Err(CoordinationError::UnknownBackend(other.to_string())),
+    }
+}
+
+// Renew the lease; false once another instance owns the lock or the lock session is gone
+async fn heartbeat(lock: &mut WalletLock) -> bool {
+    match lock {
+        WalletLock::File { path, ttl } => match read_lease(path) {
+            Some(lease) if lease["instance"] == instance_id() => write_lease(path, false).is_ok(),
+            None => try_file_lock(path, *ttl).unwrap_or(false),
+            Some(_) => false,
+        },
+        // The advisory lock lives as long as the session, so a live session means it is still held
+        WalletLock::Postgres { connection } => connection.ping().await.is_ok(),
+    }
+}
+
+fn release(lock: WalletLock) {
+    if let WalletLock::File { path, .. } = lock {
+        if read_lease(&path).map(|lease| lease["instance"] == instance_id()).unwrap_or(false) {
+            let _ = fs::remove_file(&path);
+        }
+    }
+    // A Postgres advisory lock is released when its session closes
+}
+
+// Try to become the single writer for the wallet. Returns whether this instance is the writer;
+// observers keep retrying in the background when takeover is enabled
+pub async fn start() -> bool {
+    start_with(load_coordination_config()).await
+}
+
+pub async fn start_with(config: Value) -> bool {
+    let name = lock_name(&config);
+    if !config["enabled"].as_bool().unwrap_or(false) {
+        set_writer(true, &name);
+        return true;
+    }
+    if config["role"].as_str() == Some("observer") {
+        info!("Configured as observer for wallet lock {}, transactions will not be submitted", name);
+        return false;
+    }
+
+    let lock = match try_acquire(&config, &name).await {
+        Ok(lock) => lock,
+        Err(e) => {
+            error!("Failed to acquire wallet lock {}: {}", name, e);
+            None
+        }
+    };
+    let writer = lock.is_some();
+    if writer {
+        set_writer(true, &name);
+    } else {
+        warn!("Wallet lock {} is held by another instance, running as observer", name);
+    }
+    tokio::spawn(maintain_lock(config, name, lock));
+    writer
+}
+
+// Heartbeat the held lock, demoting to observer if it is lost; while observing, retry acquiring it
+async fn maintain_lock(config: Value, name: String, mut lock: Option<WalletLock>) {
+    let interval = Duration::from_secs(config["heartbeat_secs"].as_u64().unwrap_or(10));
+    let takeover = config["takeover"].as_bool().unwrap_or(true);
+
+    loop {
+        sleep(interval).await;
+        match lock.as_mut() {
+            Some(held) => {
+                if heartbeat(held).await {
+                    continue;
+                }
+                set_writer(false, &name);
+                if let Some(lost) = lock.take() {
+                    release(lost);
+                }
+            }
+            None if takeover => match try_acquire(&config, &name).await {
+                Ok(Some(acquired)) => {
+                    lock = Some(acquired);
+                    set_writer(true, &name);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Wallet lock {} retry failed: {}", name, e),
+            },
+            None => {}
+        }
+    }
+}
+
+// Instance role for the dashboard
+pub fn snapshot() -> Value {
+    let config = load_coordination_config();
+    json!({
+        "enabled": config["enabled"].as_bool().unwrap_or(false),
+        "backend": config["backend"].as_str().unwrap_or("file"),
+        "lock": lock_name(&config),
+        "instance": instance_id(),
+        "role": if is_writer() { "writer" } else { "observer" },
+    })
+}
+
+// Custom error type for instance coordination
+#[derive(Error, Debug)]
+pub enum CoordinationError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("Unknown coordination backend: {0}")]
+    UnknownBackend(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::signer;
+
+    #[tokio::test]
+    async fn only_the_lock_holder_passes_the_send_gate() {
+        let lock_dir = std::env::temp_dir().join(format!("taz-locks-{}", std::process::id()));
+        let lock_dir = lock_dir.to_str().unwrap();
+        let config = |lock_name: &str| {
+            json!({ "enabled": true, "backend": "file", "lock_dir": lock_dir, "lock_name": lock_name, "takeover": false })
+        };
+
+        // A live lease from another instance keeps this one an observer
+        fs::create_dir_all(lock_dir).unwrap();
+        let lease = json!({ "instance": "other:1:0", "pid": 1, "heartbeat_at": Utc::now().to_rfc3339() });
+        fs::write(format!("{}/held.lock", lock_dir), lease.to_string()).unwrap();
+        assert!(!start_with(config("held")).await);
+        assert!(matches!(signer::ensure_may_send(None), Err(signer::SignerError::ObserverMode)));
+
+        // A free lock makes this instance the writer, so sends get past the gate
+        assert!(start_with(config("free")).await);
+        assert!(is_writer());
+        assert!(signer::ensure_may_send(None).is_ok());
+
+        let _ = fs::remove_dir_all(lock_dir);
+    }
+}
@@ -1,21 +1,66 @@
-use warp::Filter;
-use serde_json::Value;
+use warp::{Filter, Reply};
+use serde_json::{json, Value};
 use std::fs;
-use tokio::time::{sleep, Duration};
+use std::collections::HashMap;
 use std::convert::Infallible;
 use std::sync::{Arc, Mutex};
-use tokio::task;
 use warp::ws::{Message, WebSocket};
 use warp::hyper::StatusCode;
-use futures_util::{StreamExt, SinkExt};
+use futures::{StreamExt, SinkExt};
 
-// Structure to hold the configuration, current status, and profit tracking
-struct DashboardState {
-    config: Value,
-    refresh_interval: u64,
-    status: Arc<Mutex<String>>,
-    profit: Arc<Mutex<f64>>,  // Added for profit tracking
-}
+use std::str::FromStr;
+use web3::types::{Address, U256};
+
+use crate::modules::alerts;
+use crate::modules::approvals;
+use crate::modules::allocator;
+use crate::modules::auth::{self, AuthConfig, OperatorIdentity, Role};
+use crate::modules::alert_rules;
+use crate::modules::cancellation_risk;
+use crate::modules::code_watch;
+use crate::modules::config_audit;
+use crate::modules::config_drift;
+use crate::modules::config_validator;
+use crate::modules::control;
+use crate::modules::coordination;
+use crate::modules::deadlines;
+use crate::modules::domain;
+use crate::modules::ens;
+use crate::modules::equity;
+use crate::modules::execution::{self, ExecutionBackend};
+use crate::modules::gas;
+use crate::modules::gas_feed;
+use crate::modules::heatmap;
+use crate::modules::builders;
+use crate::modules::chains;
+use crate::modules::clustering;
+use crate::modules::fees;
+use crate::modules::funds_at_risk;
+use crate::modules::jit;
+use crate::modules::logging;
+use crate::modules::lp_yield;
+use crate::modules::inclusion;
+use crate::modules::inventory;
+use crate::modules::liquidation_watch;
+use crate::modules::nonce_recovery;
+use crate::modules::pair_discovery;
+use crate::modules::path_finder;
+use crate::modules::pool_state;
+use crate::modules::providers::{self, FailoverTransport, ProviderPool};
+use crate::modules::ramp;
+use crate::modules::reconcile;
+use crate::modules::rewards;
+use crate::modules::selector_check;
+use crate::modules::signals;
+use crate::modules::signer;
+use crate::modules::simulation;
+use crate::modules::slippage;
+use crate::modules::spend_caps;
+use crate::modules::storage;
+use crate::modules::strategy;
+use crate::modules::venues;
+use crate::modules::victim_blacklist;
+use crate::modules::ws_metrics;
 
 // Load dashboard configuration from file
 fn load_dashboard_config() -> Value {
@@ -31,46 +76,99 @@ async fn serve_static_file(file_path: &str) -> Result<impl warp::Reply, Infallib
     Ok(warp::reply::html(content))
 }
 
-// POST handler to trigger bot strategies
-async fn run_strategy(strategy: &str, state: Arc<Mutex<String>>, profit: Arc<Mutex<f64>>) -> Result<impl warp::Reply, Infallible> {
-    let mut status = state.lock().unwrap();
-    *status = format!("Running {} strategy", strategy);
+// Manual triggers pass ?confirm=true once the operator has seen the funds at risk
+fn confirmed(query: &HashMap<String, String>) -> bool {
+    query.get("confirm").is_some_and(|value| value == "true")
+}
+
+// POST handler to trigger one scan-and-execute pass of a strategy. What the run puts at risk is
+// computed first; above manual_confirm_above_wei it is returned with 409 until the request confirms it
+async fn run_strategy(
+    strategy: &'static str,
+    query: HashMap<String, String>,
+    operator: OperatorIdentity,
+    web3: web3::Web3<FailoverTransport>,
+    state: Arc<Mutex<String>>,
+) -> Result<impl warp::Reply, Infallible> {
+    let (runner, risk) = match funds_at_risk::estimate_strategy(&web3, strategy).await {
+        Ok(estimate) => estimate,
+        Err(e) => return Ok(warp::reply::with_status(warp::reply::json(&json!({ "error": e.to_string() })), StatusCode::BAD_REQUEST)),
+    };
+    if risk.requires_confirmation && !confirmed(&query) {
+        let reply = json!({ "confirmation_required": true, "funds_at_risk": risk });
+        return Ok(warp::reply::with_status(warp::reply::json(&reply), StatusCode::CONFLICT));
+    }
 
-    // Simulate running the strategy (replace with real logic)
-    sleep(Duration::from_secs(3)).await;
-    
-    // Simulate profit calculation for demo (replace with actual logic)
-    let mut profit_value = profit.lock().unwrap();
-    *profit_value += 100.0;
+    log::info!("Manual {} run triggered by {} with {} wei at risk", strategy, operator.name, risk.total_wei);
+    *state.lock().unwrap() = format!("Running {} strategy", strategy);
+    let result = strategy::run_once(runner.as_ref(), &web3).await;
+    *state.lock().unwrap() = format!("{} strategy completed", strategy);
 
-    *status = format!("{} strategy completed", strategy);
-    Ok(warp::reply::json(&format!("{} strategy executed successfully. Current profit: {}", strategy, *profit_value)))
+    // Realized profit recorded by the strategies, not a simulated figure
+    let profit_value = storage::realized_pnl_eth().await.unwrap_or(0.0);
+    match result {
+        Ok(found) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "strategy": strategy, "opportunities": found, "funds_at_risk": risk, "profit": profit_value })),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(warp::reply::json(&json!({ "error": e.to_string(), "funds_at_risk": risk })), StatusCode::INTERNAL_SERVER_ERROR)),
+    }
 }
 
-// POST handler to trigger multiple strategies
-async fn run_multiple_strategies(state: Arc<Mutex<String>>, profit: Arc<Mutex<f64>>) -> Result<impl warp::Reply, Infallible> {
-    let mut status = state.lock().unwrap();
-    *status = String::from("Running multiple strategies");
+// POST handler to trigger multiple strategies; the threshold applies to their combined funds at risk
+async fn run_multiple_strategies(
+    query: HashMap<String, String>,
+    operator: OperatorIdentity,
+    web3: web3::Web3<FailoverTransport>,
+    state: Arc<Mutex<String>>,
+) -> Result<impl warp::Reply, Infallible> {
+    let mut runners = Vec::new();
+    for strategy in ["arbitrage", "flashloan"] {
+        match funds_at_risk::estimate_strategy(&web3, strategy).await {
+            Ok(estimate) => runners.push(estimate),
+            Err(e) => return Ok(warp::reply::with_status(warp::reply::json(&json!({ "error": e.to_string() })), StatusCode::BAD_REQUEST)),
+        }
+    }
+    let total_wei = runners.iter().fold(U256::zero(), |total, (_, risk)| total.saturating_add(risk.total_wei));
+    let risks: Vec<_> = runners.iter().map(|(_, risk)| risk.clone()).collect();
+    if total_wei > funds_at_risk::confirm_above() && !confirmed(&query) {
+        let reply = json!({ "confirmation_required": true, "total_wei": total_wei, "funds_at_risk": risks });
+        return Ok(warp::reply::with_status(warp::reply::json(&reply), StatusCode::CONFLICT));
+    }
 
-    // Simulate running two strategies in parallel (arbitrage and flashloan)
-    let arbitrage_task = task::spawn(run_strategy("arbitrage", state.clone(), profit.clone()));
-    let flashloan_task = task::spawn(run_strategy("flashloan", state.clone(), profit.clone()));
+    log::info!("Manual arbitrage and flashloan runs triggered by {} with {} wei at risk", operator.name, total_wei);
+    *state.lock().unwrap() = String::from("Running multiple strategies");
+    let runs = runners.iter().map(|(runner, _)| strategy::run_once(runner.as_ref(), &web3));
+    let results: Vec<Value> = futures::future::join_all(runs)
+        .await
+        .into_iter()
+        .map(|result| match result {
+            Ok(found) => json!({ "opportunities": found }),
+            Err(e) => json!({ "error": e.to_string() }),
+        })
+        .collect();
+    *state.lock().unwrap() = String::from("Multiple strategies completed");
 
-    // Wait for both tasks to complete
-    let _ = tokio::join!(arbitrage_task, flashloan_task);
+    let profit_value = storage::realized_pnl_eth().await.unwrap_or(0.0);
+    let reply = json!({ "results": results, "total_wei": total_wei, "funds_at_risk": risks, "profit": profit_value });
+    Ok(warp::reply::with_status(warp::reply::json(&reply), StatusCode::OK))
+}
 
-    *status = String::from("Multiple strategies completed");
-    Ok(warp::reply::json(&format!("Multiple strategies executed successfully. Current profit: {}", *profit.lock().unwrap())))
+async fn funds_at_risk_handler(strategy: String, web3: web3::Web3<FailoverTransport>) -> Result<impl warp::Reply, Infallible> {
+    match funds_at_risk::estimate_strategy(&web3, &strategy).await {
+        Ok((_, risk)) => Ok(warp::reply::with_status(warp::reply::json(&risk), StatusCode::OK)),
+        Err(e) => Ok(warp::reply::with_status(warp::reply::json(&json!({ "error": e.to_string() })), StatusCode::BAD_REQUEST)),
+    }
 }
 
 // Real-time WebSocket monitoring for updates (e.g., flashloan status, profit)
-async fn handle_websocket(ws: WebSocket, state: Arc<Mutex<String>>, profit: Arc<Mutex<f64>>) {
+async fn handle_websocket(ws: WebSocket, state: Arc<Mutex<String>>) {
     let (mut tx, mut rx) = ws.split();
 
     while let Some(result) = rx.next().await {
         if result.is_ok() {
             let status = state.lock().unwrap().clone();
-            let profit_value = *profit.lock().unwrap();
+            let profit_value = storage::realized_pnl_eth().await.unwrap_or(0.0);
             let message = format!("Status: {}, Profit: {}", status, profit_value);
 
             if tx.send(Message::text(message)).await.is_err() {
@@ -85,25 +183,260 @@ async fn dashboard_handler() -> Result<impl warp::Reply, Infallible> {
     serve_static_file("static/dashboard.html").await
 }
 
+// Per-strategy opportunities, transactions, realized PnL and gas from the trade store
+async fn storage_summary_handler() -> Result<impl warp::Reply, Infallible> {
+    match storage::summary().await {
+        Ok(summary) => Ok(warp::reply::with_status(warp::reply::json(&summary), StatusCode::OK)),
+        Err(e) => Ok(warp::reply::with_status(warp::reply::json(&json!({ "error": e.to_string() })), StatusCode::SERVICE_UNAVAILABLE)),
+    }
+}
+
+// Decoded receipt events of one recorded transaction
+async fn receipt_events_handler(tx_hash: String) -> Result<impl warp::Reply, Infallible> {
+    match storage::receipt_events(&tx_hash).await {
+        Ok(events) => Ok(warp::reply::with_status(warp::reply::json(&events), StatusCode::OK)),
+        Err(e) => Ok(warp::reply::with_status(warp::reply::json(&json!({ "error": e.to_string() })), StatusCode::SERVICE_UNAVAILABLE)),
+    }
+}
+
+// Serve the latest runway projection written by the runway monitor
+async fn runway_handler() -> Result<impl warp::Reply, Infallible> {
+    let projection = fs::read_to_string("data/runway.json")
+        .ok()
+        .and_then(|data| serde_json::from_str::<Value>(&data).ok())
+        .unwrap_or(Value::Null);
+    Ok(warp::reply::json(&projection))
+}
+
+// POST handler to edit a config file from the dashboard (diffed and audited)
+async fn edit_config_handler(name: String, operator: OperatorIdentity, new_config: Value) -> Result<impl warp::Reply, Infallible> {
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Ok(warp::reply::with_status(warp::reply::json(&"Invalid config name"), StatusCode::BAD_REQUEST));
+    }
+
+    let file = format!("config/{}_config.json", name);
+    match config_audit::apply_config_edit(&file, &new_config, &operator.name) {
+        Ok(changes) => {
+            let diff: Vec<String> = changes.iter().map(|change| change.describe()).collect();
+            Ok(warp::reply::with_status(warp::reply::json(&diff), StatusCode::OK))
+        }
+        Err(e) => Ok(warp::reply::with_status(warp::reply::json(&e.to_string()), StatusCode::INTERNAL_SERVER_ERROR)),
+    }
+}
+
+// POST handler to promote pending discovered pairs to the scanner
+async fn approve_pairs_handler(operator: OperatorIdentity) -> Result<impl warp::Reply, Infallible> {
+    match pair_discovery::approve_pending_pairs(&operator.name) {
+        Ok(count) => Ok(warp::reply::with_status(warp::reply::json(&format!("Approved {} pairs", count)), StatusCode::OK)),
+        Err(e) => Ok(warp::reply::with_status(warp::reply::json(&e.to_string()), StatusCode::BAD_REQUEST)),
+    }
+}
+
+// POST handler to change one module's log level at runtime: {"module": "arbitrage", "level": "debug"};
+// a null level drops the override
+async fn log_level_handler(operator: OperatorIdentity, body: Value) -> Result<impl warp::Reply, Infallible> {
+    let module = match body["module"].as_str() {
+        Some(module) if module.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':') => module,
+        _ => return Ok(warp::reply::with_status(warp::reply::json(&"Invalid module"), StatusCode::BAD_REQUEST)),
+    };
+
+    match logging::set_level(module, body["level"].as_str(), &operator.name) {
+        Ok(()) => Ok(warp::reply::with_status(warp::reply::json(&logging::snapshot()), StatusCode::OK)),
+        Err(e) => Ok(warp::reply::with_status(warp::reply::json(&e.to_string()), StatusCode::BAD_REQUEST)),
+    }
+}
+
+// GET handler for the most recent config audit entries
+async fn config_audit_handler() -> Result<impl warp::Reply, Infallible> {
+    Ok(warp::reply::json(&config_audit::read_audit_log(100)))
+}
+
+// POST handler for pause/resume and kill switch actions
+async fn control_handler(action: String, operator: OperatorIdentity) -> Result<impl warp::Reply, Infallible> {
+    match action.as_str() {
+        "pause" => control::pause(&operator.name),
+        "resume" => control::resume(&operator.name),
+        "kill" => control::engage_kill_switch(&operator.name),
+        // Only admins may lift the kill switch
+        "unkill" if operator.role == Role::Admin => control::disable_kill_switch(&operator.name),
+        "unkill" => {
+            return Ok(warp::reply::with_status(warp::reply::json(&"Requires Admin role"), StatusCode::FORBIDDEN));
+        }
+        _ => {
+            return Ok(warp::reply::with_status(warp::reply::json(&"Unknown control action"), StatusCode::BAD_REQUEST));
+        }
+    }
+
+    let state = serde_json::json!({
+        "paused": control::is_paused(),
+        "kill_switch": control::is_kill_switch_engaged(),
+    });
+    Ok(warp::reply::with_status(warp::reply::json(&state), StatusCode::OK))
+}
+
+// Compact view for phones: equity, 24h PnL, per-strategy state and alerts still waiting on an operator
+pub fn build_summary(provider_pool: &ProviderPool) -> Value {
+    let day = chrono::Duration::hours(24);
+    let pnl = allocator::trailing_pnl(day);
+    let paused = control::paused_strategies();
+
+    let global_config: Value = fs::read_to_string("config/global_config.json")
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or(Value::Null);
+    let strategies: Vec<Value> = global_config["strategies"]
+        .as_object()
+        .map(|strategies| {
+            strategies
+                .iter()
+                .map(|(name, strategy)| {
+                    let state = if !strategy["enabled"].as_bool().unwrap_or(false) {
+                        "disabled"
+                    } else if control::is_strategy_paused(name) {
+                        "paused"
+                    } else {
+                        "running"
+                    };
+                    json!({
+                        "name": name,
+                        "state": state,
+                        "pause_reason": paused.get(name),
+                        "pnl_24h_wei": pnl.get(name).copied().unwrap_or(0.0),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut alerts = Vec::new();
+    if control::is_kill_switch_engaged() {
+        alerts.push(json!({ "kind": "kill_switch", "message": "Kill switch engaged" }));
+    }
+    if let Some(pending) = code_watch::snapshot()["pending"].as_object() {
+        for (address, upgrade) in pending {
+            alerts.push(json!({
+                "kind": "code_change",
+                "message": format!("{} ({}) changed, acknowledgement required", upgrade["name"].as_str().unwrap_or("contract"), address),
+                "since": upgrade["detected_at"],
+            }));
+        }
+    }
+    for alert in alerts::unacknowledged() {
+        alerts.push(json!({
+            "kind": "alert",
+            "id": alert.id,
+            "severity": alert.severity,
+            "message": alert.subject,
+            "occurrences": alert.occurrences,
+            "since": alert.first_raised.to_rfc3339(),
+        }));
+    }
+    if let Some(endpoints) = providers::snapshot(provider_pool)["endpoints"].as_object() {
+        for (name, health) in endpoints {
+            if health["checked"].as_bool().unwrap_or(false) && !health["healthy"].as_bool().unwrap_or(true) {
+                alerts.push(json!({ "kind": "rpc_unhealthy", "message": format!("RPC endpoint {} unhealthy", name) }));
+            }
+        }
+    }
+
+    json!({
+        "equity": equity::latest_with_change(day),
+        "pnl_24h_wei": pnl.values().sum::<f64>(),
+        "paused": control::is_paused(),
+        "writer": coordination::is_writer(),
+        "read_only": signer::read_only(),
+        "strategies": strategies,
+        "alerts": alerts,
+    })
+}
+
+// Web app manifest so the summary page can be added to a phone's home screen
+async fn manifest_handler() -> Result<impl warp::Reply, Infallible> {
+    let manifest = json!({
+        "name": "TAZ-BOT Summary",
+        "short_name": "TAZ-BOT",
+        "start_url": "/summary",
+        "display": "standalone",
+        "background_color": "#111111",
+        "theme_color": "#111111",
+    });
+    Ok(warp::reply::with_header(warp::reply::json(&manifest), "content-type", "application/manifest+json"))
+}
+
+// POST handler acknowledging a tracked contract's code change so its strategies can resume (admin only)
+async fn code_watch_ack_handler(contract: String, operator: OperatorIdentity) -> Result<impl warp::Reply, Infallible> {
+    let contract = match Address::from_str(&contract) {
+        Ok(contract) => contract,
+        Err(_) => return Ok(warp::reply::with_status(warp::reply::json(&"Invalid contract address"), StatusCode::BAD_REQUEST)),
+    };
+
+    match code_watch::acknowledge(contract, &operator.name) {
+        Ok(upgrade) => Ok(warp::reply::with_status(warp::reply::json(&upgrade), StatusCode::OK)),
+        Err(e) => Ok(warp::reply::with_status(warp::reply::json(&e.to_string()), StatusCode::BAD_REQUEST)),
+    }
+}
+
+// POST handler acknowledging an alert, which stops its escalation
+async fn alert_ack_handler(id: u64, operator: OperatorIdentity) -> Result<impl warp::Reply, Infallible> {
+    match alerts::acknowledge(id, &operator.name) {
+        Ok(alert) => Ok(warp::reply::with_status(warp::reply::json(&alert), StatusCode::OK)),
+        Err(e) => Ok(warp::reply::with_status(warp::reply::json(&e.to_string()), StatusCode::NOT_FOUND)),
+    }
+}
+
+// POST handler to sweep a token's profits to the configured cold wallet (admin only)
+async fn sweep_handler(token: String, operator: OperatorIdentity, web3: web3::Web3<FailoverTransport>) -> Result<impl warp::Reply, Infallible> {
+    let token = match Address::from_str(&token) {
+        Ok(token) => token,
+        Err(_) => return Ok(warp::reply::with_status(warp::reply::json(&"Invalid token address"), StatusCode::BAD_REQUEST)),
+    };
+
+    let result = match ExecutionBackend::from_config(&web3) {
+        Ok(backend) => execution::sweep_profits(&web3, &backend, token).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(submission) => {
+            log::info!("Profit sweep of {:?} triggered by {}", token, operator.name);
+            Ok(warp::reply::with_status(warp::reply::json(&submission), StatusCode::OK))
+        }
+        Err(e) => Ok(warp::reply::with_status(warp::reply::json(&e.to_string()), StatusCode::INTERNAL_SERVER_ERROR)),
+    }
+}
+
 // Run the Warp server and handle routes
-#[tokio::main]
-async fn main() {
+pub async fn run_dashboard(web3: web3::Web3<FailoverTransport>, provider_pool: Arc<ProviderPool>) {
     let config = load_dashboard_config();
-    let refresh_interval = config["refresh_interval"].as_u64().unwrap_or(60);
+    let auth = Arc::new(AuthConfig::from_config(&config));
+    let web3_filter = warp::any().map(move || web3.clone());
     let state = Arc::new(Mutex::new(String::from("Ready")));
-    let profit = Arc::new(Mutex::new(0.0)); // Initialize profit tracking
 
     let state_filter = warp::any().map(move || state.clone());
-    let profit_filter = warp::any().map(move || profit.clone());
 
     // WebSocket route
     let websocket_route = warp::path("ws")
+        .and(auth::require_role_or_query(auth.clone(), Role::Viewer).map(|_| ()).untuple_one())
         .and(warp::ws())
         .and(state_filter.clone())
-        .and(profit_filter.clone())
-        .map(|ws: warp::ws::Ws, state, profit| {
-            ws.on_upgrade(move |socket| handle_websocket(socket, state, profit))
+        .map(|ws: warp::ws::Ws, state| {
+            ws.on_upgrade(move |socket| handle_websocket(socket, state))
+        });
+
+    // External strategy signals: a websocket stream, and a webhook for senders like TradingView
+    let signal_stream = warp::path!("api" / "signals" / "ws")
+        .and(auth::require_role(auth.clone(), Role::Operator))
+        .and(warp::ws())
+        .map(|operator: OperatorIdentity, ws: warp::ws::Ws| {
+            ws.on_upgrade(move |socket| signals::handle_signal_socket(socket, operator))
         });
+    let signal_auth = auth.clone();
+    let signal_webhook = warp::path!("api" / "signals")
+        .and(warp::post())
+        .and(warp::any().map(move || signal_auth.clone()))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(signals::handle_signal_webhook);
 
     // Route to serve the dashboard HTML
     let dashboard = warp::path("dashboard")
@@ -119,25 +452,392 @@ async fn main() {
         .and(warp::get())
         .and_then(|| serve_static_file("static/dashboard.js"));
 
-    // Route to handle POST requests for bot strategies
+    // Route to handle POST requests for bot strategies; runs above the confirmation threshold need ?confirm=true
     let run_arbitrage = warp::path("run-arbitrage")
         .and(warp::post())
+        .map(|| "arbitrage")
+        .and(warp::query::<HashMap<String, String>>())
+        .and(auth::require_role(auth.clone(), Role::Operator))
+        .and(web3_filter.clone())
         .and(state_filter.clone())
-        .and(profit_filter.clone())
         .and_then(run_strategy);
 
     let run_flashloan = warp::path("run-flashloan")
         .and(warp::post())
+        .map(|| "flashloan")
+        .and(warp::query::<HashMap<String, String>>())
+        .and(auth::require_role(auth.clone(), Role::Operator))
+        .and(web3_filter.clone())
         .and(state_filter.clone())
-        .and(profit_filter.clone())
         .and_then(run_strategy);
 
     let run_multiple = warp::path("run-multiple")
         .and(warp::post())
+        .and(warp::query::<HashMap<String, String>>())
+        .and(auth::require_role(auth.clone(), Role::Operator))
+        .and(web3_filter.clone())
         .and(state_filter.clone())
-        .and(profit_filter.clone())
         .and_then(run_multiple_strategies);
 
+    // What a manual run of a strategy would put at risk, shown before triggering it
+    let funds_at_risk_route = warp::path!("api" / "funds-at-risk" / String)
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .and(web3_filter.clone())
+        .and_then(funds_at_risk_handler);
+
+    // Funding runway projection
+    let runway = warp::path!("api" / "runway")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .and_then(runway_handler);
+
+    // Recorded trades and PnL per strategy
+    let storage_route = warp::path!("api" / "storage" / "summary")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .and_then(storage_summary_handler);
+
+    // Swaps, transfers, liquidations and flash loans decoded from a transaction's receipt
+    let receipt_events_route = warp::path!("api" / "storage" / "transactions" / String / "events")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .and_then(receipt_events_handler);
+
+    // Marked equity curve with drawdown
+    let equity_route = warp::path!("api" / "equity")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&equity::equity_curve()));
+
+    // Whether this instance holds the wallet lock or is observing
+    let instance_route = warp::path!("api" / "instance")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&coordination::snapshot()));
+
+    // Compact summary for the mobile page, plus the page itself and its manifest
+    let summary_pool = provider_pool.clone();
+    let summary_route = warp::path!("api" / "summary")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(move || warp::reply::json(&build_summary(&summary_pool)));
+
+    let summary_page = warp::path("summary")
+        .and(warp::get())
+        .and_then(|| serve_static_file("static/summary.html"));
+
+    let manifest = warp::path("manifest.webmanifest")
+        .and(warp::get())
+        .and_then(manifest_handler);
+
+    // RPC endpoint health and which endpoint each pinned consumer is using
+    let providers_route = warp::path!("api" / "providers")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(move || warp::reply::json(&providers::snapshot(&provider_pool)));
+
+    // Decision latency and opportunities missed on their per-block deadline
+    let deadlines_route = warp::path!("api" / "deadlines")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&deadlines::snapshot()));
+
+    // Daily ledger-vs-chain reconciliation results
+    let reconciliation_route = warp::path!("api" / "reconciliation")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&reconcile::recent_summaries(30)));
+
+    // Ramp stage, streak and size limit per strategy being ramped up
+    let ramp_route = warp::path!("api" / "ramp")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&ramp::snapshot()));
+
+    // Wallet balances every strategy trades from, with reservations and recent changes
+    let inventory_route = warp::path!("api" / "inventory")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&inventory::view()));
+
+    // Liquidations prepared ahead of the oracle, with the price each one fires at
+    let prepared_liquidations_route = warp::path!("api" / "liquidations" / "prepared")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&liquidation_watch::view()));
+
+    // Passive LP positions with their value and impermanent loss
+    let lp_route = warp::path!("api" / "lp")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&lp_yield::snapshot()));
+
+    // Stuck transaction replacements, cancels and nonce gap fills
+    let nonces_route = warp::path!("api" / "nonces")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&nonce_recovery::recent_actions(100)));
+
+    // Value and gas signed per strategy against its hard spending cap
+    let spend_caps_route = warp::path!("api" / "spend-caps")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&spend_caps::snapshot()));
+
+    // Simulation pool load, reverts and results discarded after their deadline
+    let simulation_route = warp::path!("api" / "simulation")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&simulation::snapshot()));
+
+    // Per-endpoint round-trip latency and the endpoint transactions are sent through
+    let provider_latency = warp::path!("api" / "providers" / "latency")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&providers::latency_metrics()));
+
+    // Latest consensus gas estimate with per-source quotes and rejected outliers
+    let gas_route = warp::path!("api" / "gas")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&gas_feed::snapshot()));
+
+    // Tracked base fees, the predicted next one and the fees suggested at each urgency
+    let gas_fees_route = warp::path!("api" / "gas" / "fees")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&gas::snapshot()));
+
+    // Recent per-trade strategy results, newest first
+    let trades_route = warp::path!("api" / "trades")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&allocator::recent_trade_results(100, None)));
+
+    // PnL per opportunity source (mempool, sequencer feed, block events, signals, scans) over the last week
+    let pnl_sources_route = warp::path!("api" / "pnl" / "sources")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&allocator::pnl_by_source(chrono::Duration::hours(168))));
+
+    // Strategy capital allocations and reallocation decisions
+    let allocations_route = warp::path!("api" / "allocations")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&allocator::allocation_report()));
+
+    // WebSocket subscription health (events/sec, lag, reconnects)
+    let ws_metrics_route = warp::path!("api" / "ws-metrics")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&ws_metrics::snapshot()));
+
+    // Mempool first-seen to inclusion time per venue and fee decile
+    let inclusion_latency_route = warp::path!("api" / "inclusion-latency")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&inclusion::latency_snapshot()));
+
+    // Victim replacement rates per fee decile, from the mempool recorder's outcomes
+    let cancellation_risk_route = warp::path!("api" / "cancellation-risk")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&cancellation_risk::snapshot()));
+
+    // Event-synced pool state: whether quotes come from memory and how many pools are followed
+    let pool_state_route = warp::path!("api" / "pool-state")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&pool_state::snapshot()));
+
+    // Multi-leg cycles from the path finder's last search, best first
+    let routes_route = warp::path!("api" / "arbitrage" / "routes")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&path_finder::snapshot()));
+
+    // Discovered token pairs; approving a pending set (manual discovery mode) is admin only
+    let pairs_route = warp::path!("api" / "pairs")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&pair_discovery::pair_sets()));
+
+    // Venues (V2 pairs and V3 fee tiers) currently in the pool cache
+    let venues_route = warp::path!("api" / "venues")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&venues::cached_venues()));
+
+    // Calibrated per-pool slippage factors (realized / expected output)
+    let slippage_route = warp::path!("api" / "slippage")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&slippage::factors_snapshot()));
+
+    // Victim patterns skipped because their sandwiches keep reverting
+    let victim_blacklist_route = warp::path!("api" / "sandwich" / "blacklist")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&victim_blacklist::snapshot()));
+
+    // Pools tagged for JIT liquidity
+    let jit_route = warp::path!("api" / "jit")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&jit::snapshot()));
+
+    let approve_pairs = warp::path!("api" / "pairs" / "approve")
+        .and(warp::post())
+        .and(auth::require_role(auth.clone(), Role::Admin))
+        .and_then(approve_pairs_handler);
+
+    // Config edits and audit trail
+    let edit_config = warp::path!("api" / "config" / String)
+        .and(warp::post())
+        .and(auth::require_role(auth.clone(), Role::Admin))
+        .and(warp::body::json())
+        .and_then(edit_config_handler);
+
+    let config_audit_log = warp::path!("api" / "config" / "audit")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .and_then(config_audit_handler);
+
+    // Config problems (bad addresses, unresolvable ENS names); re-run on each request
+    let config_validation = warp::path!("api" / "config" / "validation")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&config_validator::validate_configs()));
+
+    let ens_route = warp::path!("api" / "ens")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&ens::snapshot()));
+
+    // Per-module log levels, changeable at runtime by operators
+    let log_levels = warp::path!("api" / "log-levels")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&logging::snapshot()));
+
+    let set_log_level = warp::path!("api" / "log-levels")
+        .and(warp::post())
+        .and(auth::require_role(auth.clone(), Role::Operator))
+        .and(warp::body::json())
+        .and_then(log_level_handler);
+
+    // Spread heatmap per pair, route and hour, as JSON or CSV
+    let heatmap_route = warp::path!("api" / "heatmap")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&heatmap::latest_heatmap()));
+
+    let heatmap_csv = warp::path!("api" / "heatmap.csv")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::with_header(heatmap::to_csv(&heatmap::latest_heatmap()), "content-type", "text/csv"));
+
+    // Inclusion rate and cost per builder over the last week, plus daily inclusions
+    let builders_route = warp::path!("api" / "builders")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| {
+            let mut stats = builders::builder_stats(chrono::Duration::hours(168));
+            stats["ranking"] = json!(builders::builder_ranking());
+            stats["inclusions_by_day"] = builders::inclusions_by_day();
+            warp::reply::json(&stats)
+        });
+
+    // Active chain, its submission strategy and recent sequencer races
+    let chain_route = warp::path!("api" / "chain")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&chains::snapshot()));
+
+    // Performance fee accrual, payouts and the latest fee ledger entries
+    let fees_route = warp::path!("api" / "fees")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&fees::summary(50)));
+
+    // Latest execution results, each with the plan that was sent
+    let executions_route = warp::path!("api" / "executions")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&domain::recent_executions(100)));
+
+    // Approval sizing mode, allowances still outstanding and the approval ledger
+    let approvals_route = warp::path!("api" / "approvals")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&approvals::summary(50)));
+
+    // Configured addresses that no longer behave as the config expects
+    let drift_route = warp::path!("api" / "config" / "drift")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&config_drift::snapshot()));
+
+    // Reward claims and skipped checks
+    let rewards_route = warp::path!("api" / "rewards")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&rewards::recent_claims(100)));
+
+    // Split orders currently being tracked in the mempool
+    let clusters_route = warp::path!("api" / "clusters")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&clustering::open_clusters()));
+
+    // Pause/resume and kill switch (operators; disabling the kill switch is admin only)
+    let control_route = warp::path!("api" / "control" / String)
+        .and(warp::post())
+        .and(auth::require_role(auth.clone(), Role::Operator))
+        .and_then(control_handler);
+
+    // Deduplicated alerts; acknowledging a critical one stops its escalation
+    let alerts_route = warp::path!("api" / "alerts")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&alerts::list()));
+
+    // Operator-defined alert rules and how often each has matched
+    let alert_rules_route = warp::path!("api" / "alert-rules")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&alert_rules::snapshot()));
+
+    let alert_ack = warp::path!("api" / "alerts" / u64 / "ack")
+        .and(warp::post())
+        .and(auth::require_role(auth.clone(), Role::Operator))
+        .and_then(alert_ack_handler);
+
+    // Tracked contract code changes; acknowledging one re-enables trading through it, so admin only
+    let code_watch_route = warp::path!("api" / "code-watch")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&code_watch::snapshot()));
+
+    let code_watch_ack = warp::path!("api" / "code-watch" / "ack" / String)
+        .and(warp::post())
+        .and(auth::require_role(auth.clone(), Role::Admin))
+        .and_then(code_watch_ack_handler);
+
+    // Contracts checked at startup for the functions the bot calls on them
+    let selector_check_route = warp::path!("api" / "selector-check")
+        .and(warp::get())
+        .and(auth::restrict_to(auth.clone(), Role::Viewer))
+        .map(|| warp::reply::json(&selector_check::snapshot()));
+
+    // Profit sweeps move funds, so they are admin only
+    let sweep = warp::path!("api" / "sweep" / String)
+        .and(warp::post())
+        .and(auth::require_role(auth.clone(), Role::Admin))
+        .and(web3_filter.clone())
+        .and_then(sweep_handler);
+
     // Run Warp server
     let routes = websocket_route
         .or(dashboard)
@@ -145,7 +845,82 @@ async fn main() {
         .or(js)
         .or(run_arbitrage)
         .or(run_flashloan)
-        .or(run_multiple);
+        .or(run_multiple)
+        .or(funds_at_risk_route)
+        .or(runway)
+        .or(storage_route)
+        .or(receipt_events_route)
+        .or(equity_route)
+        .or(instance_route)
+        // Collapsed into plain responses every few routes so the filter type stays within the
+        // compiler's recursion limit
+        .map(Reply::into_response)
+        .boxed()
+        .or(summary_route)
+        .or(summary_page)
+        .or(manifest)
+        .or(allocations_route)
+        .or(trades_route)
+        .or(pnl_sources_route)
+        .or(providers_route)
+        .or(provider_latency)
+        .or(simulation_route)
+        .or(deadlines_route)
+        .or(spend_caps_route)
+        .or(nonces_route)
+        .map(Reply::into_response)
+        .boxed()
+        .or(lp_route)
+        .or(ramp_route)
+        .or(inventory_route)
+        .or(prepared_liquidations_route)
+        .or(reconciliation_route)
+        .or(gas_route)
+        .or(gas_fees_route)
+        .or(ws_metrics_route)
+        .or(inclusion_latency_route)
+        .or(cancellation_risk_route)
+        .or(routes_route)
+        .or(pool_state_route)
+        .map(Reply::into_response)
+        .boxed()
+        .or(pairs_route)
+        .or(approve_pairs)
+        .or(venues_route)
+        .or(slippage_route)
+        .or(heatmap_route)
+        .or(heatmap_csv)
+        .or(builders_route)
+        .or(chain_route)
+        .or(fees_route)
+        .or(executions_route)
+        .or(approvals_route)
+        .or(clusters_route)
+        .map(Reply::into_response)
+        .boxed()
+        .or(rewards_route)
+        .or(drift_route)
+        .or(signal_stream)
+        .or(signal_webhook)
+        .or(jit_route)
+        .or(victim_blacklist_route)
+        .or(config_audit_log)
+        .or(config_validation)
+        .or(ens_route)
+        .or(edit_config)
+        .or(log_levels)
+        .or(set_log_level)
+        .map(Reply::into_response)
+        .boxed()
+        .or(control_route)
+        .or(alerts_route)
+        .or(alert_rules_route)
+        .or(alert_ack)
+        .or(code_watch_route)
+        .or(code_watch_ack)
+        .or(selector_check_route)
+        .or(sweep)
+        .recover(auth::handle_rejection);
 
     warp::serve(routes)
         .run(([127, 0, 0, 1], config["port"].as_u64().unwrap_or(8080) as u16))
@@ -0,0 +1,140 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::future::Future;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use web3::types::H256;
+use log::{error, warn};
+use chrono::Utc;
+use thiserror::Error;
+use tokio::time::{timeout_at, Duration, Instant};
+
+use crate::modules::events;
+
+// Opportunities dropped because their decision ran out of time
+const MISSED_PATH: &str = "data/missed_opportunities.jsonl";
+
+// Load the per-strategy decision budgets
+fn load_deadlines_config() -> Value {
+    let config_path = "config/deadlines_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read deadlines config file");
+    serde_json::from_str(&config_data).expect("Unable to parse deadlines config file")
+}
+
+// Decision latency and deadline misses per strategy
+#[derive(Default)]
+struct DecisionMetrics {
+    decided: u64,
+    missed: u64,
+    missed_by_stage: HashMap<String, u64>,
+    last_decision_ms: u64,
+    max_decision_ms: u64,
+}
+
+fn metrics() -> &'static Mutex<HashMap<String, DecisionMetrics>> {
+    static METRICS: OnceLock<Mutex<HashMap<String, DecisionMetrics>>> = OnceLock::new();
+    METRICS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// The time a strategy has to decide on one opportunity, counted from when it was seen. Quoting and
+// simulation run as stages inside it; the first stage to overrun ends the decision
+pub struct DecisionBudget {
+    strategy: String,
+    target: Option<H256>,
+    budget: Duration,
+    started: Instant,
+}
+
+impl DecisionBudget {
+    pub fn start(strategy: &str, target: Option<H256>) -> Self {
+        let config = load_deadlines_config();
+        let budget_ms = config["budgets_ms"][strategy].as_u64().or(config["default_budget_ms"].as_u64()).unwrap_or(1000);
+        DecisionBudget { strategy: strategy.to_string(), target, budget: Duration::from_millis(budget_ms), started: Instant::now() }
+    }
+
+    pub fn deadline(&self) -> Instant {
+        self.started + self.budget
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.deadline().saturating_duration_since(Instant::now())
+    }
+
+    // Run one stage of the decision, abandoning it (and recording the miss) once the budget is spent
+    pub async fn stage<F: Future>(&self, stage: &str, future: F) -> Result<F::Output, DeadlineMissed> {
+        match timeout_at(self.deadline(), future).await {
+            Ok(output) => Ok(output),
+            Err(_) => Err(self.missed(stage)),
+        }
+    }
+
+    // The decision finished in time (whatever it decided)
+    pub fn finish(&self) {
+        let elapsed_ms = self.started.elapsed().as_millis() as u64;
+        let mut metrics = metrics().lock().unwrap();
+        let entry = metrics.entry(self.strategy.clone()).or_default();
+        entry.decided += 1;
+        entry.last_decision_ms = elapsed_ms;
+        entry.max_decision_ms = entry.max_decision_ms.max(elapsed_ms);
+    }
+
+    fn missed(&self, stage: &str) -> DeadlineMissed {
+        let budget_ms = self.budget.as_millis() as u64;
+        warn!("{} missed its {}ms decision deadline in {} ({:?})", self.strategy, budget_ms, stage, self.target);
+        {
+            let mut metrics = metrics().lock().unwrap();
+            let entry = metrics.entry(self.strategy.clone()).or_default();
+            entry.missed += 1;
+            *entry.missed_by_stage.entry(stage.to_string()).or_default() += 1;
+        }
+
+        let record = json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "strategy": self.strategy,
+            "target": self.target,
+            "reason": "missed — deadline",
+            "stage": stage,
+            "budget_ms": budget_ms,
+        });
+        let _ = fs::create_dir_all("data");
+        match OpenOptions::new().create(true).append(true).open(MISSED_PATH) {
+            Ok(mut file) => {
+                if let Err(e) = writeln!(file, "{}", record) {
+                    error!("Failed to write missed opportunity: {}", e);
+                }
+            }
+            Err(e) => error!("Failed to open missed opportunity log: {}", e),
+        }
+        events::emit("opportunity_missed", record);
+
+        DeadlineMissed { strategy: self.strategy.clone(), stage: stage.to_string(), budget_ms }
+    }
+}
+
+// Decision latency and misses per strategy for the dashboard
+pub fn snapshot() -> Value {
+    let metrics = metrics().lock().unwrap();
+    let strategies: HashMap<&String, Value> = metrics
+        .iter()
+        .map(|(strategy, entry)| {
+            (strategy, json!({
+                "decided": entry.decided,
+                "missed_deadline": entry.missed,
+                "missed_by_stage": entry.missed_by_stage,
+                "last_decision_ms": entry.last_decision_ms,
+                "max_decision_ms": entry.max_decision_ms,
+            }))
+        })
+        .collect();
+    json!(strategies)
+}
+
+#[derive(Error, Debug)]
+#[error("{strategy} missed its {budget_ms}ms decision deadline in {stage}")]
+pub struct DeadlineMissed {
+    pub strategy: String,
+    pub stage: String,
+    pub budget_ms: u64,
+}
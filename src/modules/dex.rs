@@ -0,0 +1,366 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use web3::contract::{Contract, Options};
+use web3::ethabi::{self, ParamType, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, CallRequest, U256};
+use log::{debug, info, warn};
+
+use crate::modules::ens;
+use crate::modules::execution::{self, Call, ExecutionError, SwapOrder};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::venues::{self, less_fee, Venue, VenueError, VenueVersion, DEFAULT_V2_FEE};
+
+// An exchange the bot can find pools on, quote and trade through. Adapters are built from the
+// dexes in config/venues_config.json, so plugging in a new exchange takes an implementation here
+// plus a config entry
+#[async_trait]
+pub trait Dex: Send + Sync {
+    // DEX name from the config; a DEX with V2 and V3 routers has an adapter for each
+    fn name(&self) -> &str;
+
+    // Pools this adapter has for the pair, one venue each
+    async fn pools(&self, web3: &web3::Web3<FailoverTransport>, token_a: Address, token_b: Address) -> Result<Vec<Venue>, VenueError>;
+
+    // Amount of token_out received for amount_in on one of this adapter's venues, net of the router fee
+    async fn quote(&self, web3: &web3::Web3<FailoverTransport>, venue: &Venue, token_in: Address, token_out: Address, amount_in: U256) -> Result<U256, VenueError>;
+
+    // Approve + swap calls trading amount_in on the venue
+    fn build_swap_tx(&self, venue: &Venue, order: &SwapOrder) -> Result<Vec<Call>, ExecutionError>;
+}
+
+fn config_address(dex: &Value, key: &str) -> Option<Address> {
+    dex[key].as_str().and_then(ens::parse_address)
+}
+
+// Router fee configured for a DEX's V2 or V3 router, none by default
+fn router_fee(dex: &Value, key: &str) -> u32 {
+    dex[key].as_u64().unwrap_or(0) as u32
+}
+
+// Selector plus ABI-encoded arguments for a call by signature
+fn encode_call(signature: &str, args: &[Token]) -> Vec<u8> {
+    [keccak256(signature.as_bytes())[..4].to_vec(), ethabi::encode(args)].concat()
+}
+
+async fn call(web3: &web3::Web3<FailoverTransport>, to: Address, data: Vec<u8>) -> Result<Vec<u8>, VenueError> {
+    let request = CallRequest { to: Some(to), data: Some(Bytes(data)), ..Default::default() };
+    Ok(web3.eth().call(request, None).await?.0)
+}
+
+// Uniswap V2 and its forks (Sushiswap and the rest), which only differ in addresses and pair fee
+pub struct UniswapV2 {
+    name: String,
+    config: Value,
+}
+
+// Fee a V2 fork's pair charges, implied by its router's quote for a small trade against the pair's
+// reserves: out = in * g * r_out / (r_in + in * g) solved for g = 1 - fee. Rounded to 10 units
+// (0.001%) to absorb the router's integer rounding; None when the pair is empty or the result is
+// not a plausible fee
+async fn detect_v2_fee(web3: &web3::Web3<FailoverTransport>, pool: Address, router: Address, token_a: Address, token_b: Address) -> Result<Option<u32>, VenueError> {
+    let output = call(web3, pool, encode_call("getReserves()", &[])).await?;
+    let reserves = ethabi::decode(&[ParamType::Uint(112), ParamType::Uint(112), ParamType::Uint(32)], &output)?;
+    let (reserve0, reserve1) = (reserves[0].clone().into_uint().unwrap_or_default(), reserves[1].clone().into_uint().unwrap_or_default());
+    // Quote token_a -> token_b; the pair orders its reserves by token address
+    let (reserve_in, reserve_out) = if token_a < token_b { (reserve0, reserve1) } else { (reserve1, reserve0) };
+    let amount_in = reserve_in / U256::from(1000);
+    if amount_in.is_zero() || reserve_out.is_zero() {
+        return Ok(None);
+    }
+
+    let router = Contract::from_json(web3.eth(), router, include_bytes!("abi/uniswap_router_abi.json"))?;
+    let amounts: Vec<U256> = router
+        .query("getAmountsOut", (amount_in, vec![token_a, token_b]), None, Options::default(), None)
+        .await?;
+    let amount_out = amounts.last().copied().unwrap_or_default();
+    if amount_out.is_zero() || amount_out >= reserve_out {
+        return Ok(None);
+    }
+    let kept = amount_out.saturating_mul(reserve_in).saturating_mul(U256::from(1_000_000)) / amount_in.saturating_mul(reserve_out - amount_out);
+    let fee = 1_000_000u64.saturating_sub(kept.min(U256::from(1_000_000)).as_u64());
+    let fee = ((fee + 5) / 10 * 10) as u32;
+    // Nothing we trade on charges more than 10%
+    Ok((fee <= 100_000).then_some(fee))
+}
+
+#[async_trait]
+impl Dex for UniswapV2 {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn pools(&self, web3: &web3::Web3<FailoverTransport>, token_a: Address, token_b: Address) -> Result<Vec<Venue>, VenueError> {
+        let (factory, router) = match (config_address(&self.config, "v2_factory"), config_address(&self.config, "v2_router")) {
+            (Some(factory), Some(router)) => (factory, router),
+            _ => return Ok(Vec::new()),
+        };
+        let factory = Contract::from_json(web3.eth(), factory, include_bytes!("abi/uniswap_v2_factory_abi.json"))?;
+        let pool: Address = factory.query("getPair", (token_a, token_b), None, Options::default(), None).await?;
+        if pool.is_zero() {
+            return Ok(Vec::new());
+        }
+
+        // A configured fee wins; otherwise detect it, falling back to Uniswap's 0.3%
+        let v2_fee = match self.config["v2_fee"].as_u64() {
+            Some(fee) => fee as u32,
+            None if self.config["detect_v2_fee"].as_bool().unwrap_or(true) => match detect_v2_fee(web3, pool, router, token_a, token_b).await {
+                Ok(Some(fee)) => fee,
+                Ok(None) => DEFAULT_V2_FEE,
+                Err(e) => {
+                    warn!("Could not detect the {} v2 fee for {:?}: {}", self.name, pool, e);
+                    DEFAULT_V2_FEE
+                }
+            },
+            None => DEFAULT_V2_FEE,
+        };
+        if v2_fee != DEFAULT_V2_FEE {
+            info!("{} v2 pair {:?} charges {}bps", self.name, pool, v2_fee as f64 / 100.0);
+        }
+        Ok(vec![Venue {
+            dex: self.name.clone(),
+            version: VenueVersion::V2,
+            pool,
+            router,
+            quoter: None,
+            quoter_v2: false,
+            v2_fee,
+            router_fee: router_fee(&self.config, "v2_router_fee"),
+        }])
+    }
+
+    // The router prices in the pair fee, so only the router fee is taken off
+    async fn quote(&self, web3: &web3::Web3<FailoverTransport>, venue: &Venue, token_in: Address, token_out: Address, amount_in: U256) -> Result<U256, VenueError> {
+        let router = Contract::from_json(web3.eth(), venue.router, include_bytes!("abi/uniswap_router_abi.json"))?;
+        let amounts: Vec<U256> = router
+            .query("getAmountsOut", (amount_in, vec![token_in, token_out]), None, Options::default(), None)
+            .await?;
+        Ok(less_fee(amounts.last().copied().unwrap_or_default(), venue.router_fee))
+    }
+
+    fn build_swap_tx(&self, venue: &Venue, order: &SwapOrder) -> Result<Vec<Call>, ExecutionError> {
+        execution::approve_and_swap_calls(venue.router, vec![order.token_in, order.token_out], order.amount_in, order.amount_out_min, order.recipient, order.deadline)
+    }
+}
+
+// Uniswap V3 pools, one venue per fee tier
+pub struct UniswapV3 {
+    name: String,
+    config: Value,
+}
+
+// V3 fee tiers to look up for a pair: a pair_fee_tiers entry naming both tokens wins over the
+// DEX's fee_tiers, so thin tiers can be skipped for pairs that only trade on one or two
+fn fee_tiers_for(dex: &Value, token_a: Address, token_b: Address) -> Vec<u32> {
+    let tiers = |value: &Value| -> Option<Vec<u32>> {
+        value.as_array().map(|tiers| tiers.iter().filter_map(|tier| tier.as_u64()).map(|tier| tier as u32).collect())
+    };
+    let pair_tiers = dex["pair_fee_tiers"].as_array().and_then(|pairs| {
+        pairs.iter().find_map(|pair| {
+            let tokens: Vec<Address> = pair["tokens"].as_array()?.iter().filter_map(|token| token.as_str().and_then(ens::parse_address)).collect();
+            (tokens.contains(&token_a) && tokens.contains(&token_b)).then(|| tiers(&pair["fee_tiers"])).flatten()
+        })
+    });
+    pair_tiers.or_else(|| tiers(&dex["fee_tiers"])).unwrap_or_else(|| vec![100, 500, 3000, 10000])
+}
+
+// QuoterV2 quoteExactInputSingle: amount out and initialized ticks crossed. Its params struct
+// doesn't fit the contract query helpers, so the call is encoded directly
+async fn quote_v3_v2(
+    web3: &web3::Web3<FailoverTransport>,
+    quoter: Address,
+    token_in: Address,
+    token_out: Address,
+    fee: u32,
+    amount_in: U256,
+) -> Result<(U256, u64), VenueError> {
+    let quoter_abi = ethabi::Contract::load(&include_bytes!("abi/uniswap_v3_quoter_v2_abi.json")[..])?;
+    let function = quoter_abi.function("quoteExactInputSingle")?;
+    let data = function.encode_input(&[Token::Tuple(vec![
+        Token::Address(token_in),
+        Token::Address(token_out),
+        Token::Uint(amount_in),
+        Token::Uint(U256::from(fee)),
+        Token::Uint(U256::zero()),
+    ])])?;
+    let output = function.decode_output(&call(web3, quoter, data).await?)?;
+    let amount_out = output.first().cloned().and_then(Token::into_uint).unwrap_or_default();
+    let ticks_crossed = output.get(2).cloned().and_then(Token::into_uint).map_or(0, |ticks| ticks.low_u64());
+    Ok((amount_out, ticks_crossed))
+}
+
+#[async_trait]
+impl Dex for UniswapV3 {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn pools(&self, web3: &web3::Web3<FailoverTransport>, token_a: Address, token_b: Address) -> Result<Vec<Venue>, VenueError> {
+        let (factory, router) = match (config_address(&self.config, "v3_factory"), config_address(&self.config, "v3_router")) {
+            (Some(factory), Some(router)) => (factory, router),
+            _ => return Ok(Vec::new()),
+        };
+        let factory = Contract::from_json(web3.eth(), factory, include_bytes!("abi/uniswap_v3_factory_abi.json"))?;
+
+        let mut venues = Vec::new();
+        for fee in fee_tiers_for(&self.config, token_a, token_b) {
+            let pool: Address = factory
+                .query("getPool", (token_a, token_b, U256::from(fee)), None, Options::default(), None)
+                .await?;
+            if !pool.is_zero() {
+                venues.push(Venue {
+                    dex: self.name.clone(),
+                    version: VenueVersion::V3 { fee },
+                    pool,
+                    router,
+                    quoter: config_address(&self.config, "v3_quoter"),
+                    quoter_v2: self.config["v3_quoter_v2"].as_bool().unwrap_or(false),
+                    v2_fee: DEFAULT_V2_FEE,
+                    router_fee: router_fee(&self.config, "v3_router_fee"),
+                });
+            }
+        }
+        Ok(venues)
+    }
+
+    // The quoter prices in the fee tier, so only the router fee is taken off
+    async fn quote(&self, web3: &web3::Web3<FailoverTransport>, venue: &Venue, token_in: Address, token_out: Address, amount_in: U256) -> Result<U256, VenueError> {
+        let fee = match venue.version {
+            VenueVersion::V3 { fee } => fee,
+            _ => return Err(VenueError::WrongAdapter(venue.label())),
+        };
+        let quoter_address = venue.quoter.ok_or(VenueError::MissingQuoter(venue.dex.clone()))?;
+        if venue.quoter_v2 {
+            let (amount_out, ticks_crossed) = quote_v3_v2(web3, quoter_address, token_in, token_out, fee, amount_in).await?;
+            debug!("{} quote for {:?} crossed {} initialized ticks", venue.label(), amount_in, ticks_crossed);
+            return Ok(less_fee(amount_out, venue.router_fee));
+        }
+        let quoter = Contract::from_json(web3.eth(), quoter_address, include_bytes!("abi/uniswap_v3_quoter_abi.json"))?;
+        let amount_out: U256 = quoter
+            .query(
+                "quoteExactInputSingle",
+                (token_in, token_out, U256::from(fee), amount_in, U256::zero()),
+                None,
+                Options::default(),
+                None,
+            )
+            .await?;
+        Ok(less_fee(amount_out, venue.router_fee))
+    }
+
+    fn build_swap_tx(&self, venue: &Venue, order: &SwapOrder) -> Result<Vec<Call>, ExecutionError> {
+        let fee = match venue.version {
+            VenueVersion::V3 { fee } => fee,
+            _ => return Err(ExecutionError::UnsupportedVenue(venue.label())),
+        };
+        execution::approve_and_swap_v3_calls(venue.router, fee, order)
+    }
+}
+
+// Curve pools found through the registry. The pool is its own router: get_dy quotes, exchange swaps
+pub struct Curve {
+    name: String,
+    config: Value,
+}
+
+// Coin indices for a direction, from the venue's indices of the lower and higher token address
+fn curve_indices(venue: &Venue, token_in: Address, token_out: Address) -> Option<(u8, u8)> {
+    match venue.version {
+        VenueVersion::Curve { low_index, high_index, .. } if token_in < token_out => Some((low_index, high_index)),
+        VenueVersion::Curve { low_index, high_index, .. } => Some((high_index, low_index)),
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl Dex for Curve {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn pools(&self, web3: &web3::Web3<FailoverTransport>, token_a: Address, token_b: Address) -> Result<Vec<Venue>, VenueError> {
+        let registry = match config_address(&self.config, "curve_registry") {
+            Some(registry) => registry,
+            None => return Ok(Vec::new()),
+        };
+        let output = call(web3, registry, encode_call("find_pool_for_coins(address,address)", &[Token::Address(token_a), Token::Address(token_b)])).await?;
+        let pool = ethabi::decode(&[ParamType::Address], &output)?[0].clone().into_address().unwrap_or_default();
+        if pool.is_zero() {
+            return Ok(Vec::new());
+        }
+
+        let (low, high) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        let output = call(web3, registry, encode_call("get_coin_indices(address,address,address)", &[Token::Address(pool), Token::Address(low), Token::Address(high)])).await?;
+        let indices = ethabi::decode(&[ParamType::Int(128), ParamType::Int(128), ParamType::Bool], &output)?;
+        // Metapool swaps through the base pool's underlying coins take different calls; not traded
+        if indices[2].clone().into_bool().unwrap_or(false) {
+            debug!("Skipping {} pool {:?}: only trades the pair as underlying coins", self.name, pool);
+            return Ok(Vec::new());
+        }
+        let index = |token: &Token| token.clone().into_int().map_or(0, |index| index.low_u32() as u8);
+
+        // Curve fees are in 1e10ths; venues keep hundredths of a bip
+        let output = call(web3, pool, encode_call("fee()", &[])).await?;
+        let fee = ethabi::decode(&[ParamType::Uint(256)], &output)?[0].clone().into_uint().unwrap_or_default() / U256::from(10_000);
+
+        Ok(vec![Venue {
+            dex: self.name.clone(),
+            version: VenueVersion::Curve { fee: fee.low_u32(), low_index: index(&indices[0]), high_index: index(&indices[1]) },
+            pool,
+            router: pool,
+            quoter: None,
+            quoter_v2: false,
+            v2_fee: DEFAULT_V2_FEE,
+            router_fee: 0,
+        }])
+    }
+
+    // get_dy is net of the pool fee
+    async fn quote(&self, web3: &web3::Web3<FailoverTransport>, venue: &Venue, token_in: Address, token_out: Address, amount_in: U256) -> Result<U256, VenueError> {
+        let (i, j) = curve_indices(venue, token_in, token_out).ok_or_else(|| VenueError::WrongAdapter(venue.label()))?;
+        let data = encode_call("get_dy(int128,int128,uint256)", &[Token::Int(U256::from(i)), Token::Int(U256::from(j)), Token::Uint(amount_in)]);
+        let output = call(web3, venue.pool, data).await?;
+        Ok(ethabi::decode(&[ParamType::Uint(256)], &output)?[0].clone().into_uint().unwrap_or_default())
+    }
+
+    // exchange pays msg.sender, so the output lands with whoever runs the calls (the wallet, the
+    // executor contract or the smart account) whatever recipient is asked for
+    fn build_swap_tx(&self, venue: &Venue, order: &SwapOrder) -> Result<Vec<Call>, ExecutionError> {
+        let (i, j) = curve_indices(venue, order.token_in, order.token_out).ok_or_else(|| ExecutionError::UnsupportedVenue(venue.label()))?;
+        let data = encode_call(
+            "exchange(int128,int128,uint256,uint256)",
+            &[Token::Int(U256::from(i)), Token::Int(U256::from(j)), Token::Uint(order.amount_in), Token::Uint(order.amount_out_min)],
+        );
+        Ok(vec![execution::approve_call(order.token_in, venue.pool, order.amount_in)?, Call { to: venue.pool, value: U256::zero(), data }])
+    }
+}
+
+// Adapters for every configured DEX: one per router kind it has addresses for
+pub fn configured() -> Vec<Box<dyn Dex>> {
+    let mut adapters: Vec<Box<dyn Dex>> = Vec::new();
+    for dex in venues::dex_configs() {
+        let name = dex["name"].as_str().unwrap_or("unknown").to_string();
+        if dex.get("v2_factory").is_some() {
+            adapters.push(Box::new(UniswapV2 { name: name.clone(), config: dex.clone() }));
+        }
+        if dex.get("v3_factory").is_some() {
+            adapters.push(Box::new(UniswapV3 { name: name.clone(), config: dex.clone() }));
+        }
+        if dex.get("curve_registry").is_some() {
+            adapters.push(Box::new(Curve { name, config: dex.clone() }));
+        }
+    }
+    adapters
+}
+
+// Adapter that quotes and trades a venue. Quotes and swaps only need the venue, so venues restored
+// from a checkpoint still trade after their DEX leaves the config
+pub fn for_venue(venue: &Venue) -> Box<dyn Dex> {
+    let name = venue.dex.clone();
+    let config = venues::dex_configs().into_iter().find(|dex| dex["name"].as_str() == Some(venue.dex.as_str())).unwrap_or(Value::Null);
+    match venue.version {
+        VenueVersion::V2 => Box::new(UniswapV2 { name, config }),
+        VenueVersion::V3 { .. } => Box::new(UniswapV3 { name, config }),
+        VenueVersion::Curve { .. } => Box::new(Curve { name, config }),
+    }
+}
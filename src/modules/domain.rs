@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use web3::ethabi::ethereum_types::U512;
+use web3::types::{Address, BlockId, BlockNumber, H256, U256};
+use log::error;
+use chrono::Utc;
+
+use crate::modules::actions::{ActionPhase, ContractAction};
+use crate::modules::events;
+use crate::modules::execution::{Call, ExecutionError};
+use crate::modules::plan_graph;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::ramp;
+use crate::modules::receipt_events::DecodedReceipt;
+use crate::modules::venues::Venue;
+
+// Every execution outcome, in submission order
+const EXECUTIONS_PATH: &str = "data/executions.jsonl";
+
+// What surfaced an opportunity, so PnL can be attributed to the data feed that paid for it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpportunitySource {
+    PublicMempool,
+    MevShareHint,
+    SequencerFeed,
+    BlockEvent,
+    ExternalSignal,
+    #[default]
+    ScheduledScan,
+}
+
+impl OpportunitySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpportunitySource::PublicMempool => "public_mempool",
+            OpportunitySource::MevShareHint => "mev_share_hint",
+            OpportunitySource::SequencerFeed => "sequencer_feed",
+            OpportunitySource::BlockEvent => "block_event",
+            OpportunitySource::ExternalSignal => "external_signal",
+            OpportunitySource::ScheduledScan => "scheduled_scan",
+        }
+    }
+}
+
+// One swap on one venue, with the output it was quoted for
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TradeLeg {
+    pub venue: Venue,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: U256,
+    pub expected_out: U256,
+}
+
+// A detected opportunity, handed from detection to execution (possibly on another machine)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Opportunity {
+    pub strategy: String,
+    pub target_hash: Option<H256>,
+    pub block: Option<u64>,
+    pub details: Value,
+    pub detected_at_ms: i64,
+    // Route and expected profit, when the detecting strategy already priced one
+    #[serde(default)]
+    pub legs: Vec<TradeLeg>,
+    #[serde(default)]
+    pub expected_profit: Option<U256>,
+    #[serde(default)]
+    pub source: OpportunitySource,
+}
+
+// Block the quotes behind a plan were read at, so a plan that sat through retries or slow checks
+// can be recognised as stale before it is broadcast
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QuoteWatermark {
+    pub block: u64,
+    pub timestamp: u64,
+}
+
+impl QuoteWatermark {
+    // The head block, read just before quoting; quotes read after it are at least this fresh
+    pub async fn at_head(web3: &web3::Web3<FailoverTransport>) -> Result<Self, web3::Error> {
+        let head = web3
+            .eth()
+            .block(BlockId::Number(BlockNumber::Latest))
+            .await?
+            .ok_or_else(|| web3::Error::InvalidResponse(String::from("no latest block")))?;
+        Ok(QuoteWatermark {
+            block: head.number.map(|number| number.as_u64()).unwrap_or_default(),
+            timestamp: head.timestamp.as_u64(),
+        })
+    }
+}
+
+// What will be sent for an opportunity: the legs in order, the sizing and the expected result
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TradePlan {
+    pub strategy: String,
+    #[serde(default)]
+    pub source: OpportunitySource,
+    pub legs: Vec<TradeLeg>,
+    pub expected_profit: U256,
+    pub gas_fees: U256,
+    // Contract calls run before or after the legs (claims, unstakes, ...)
+    #[serde(default)]
+    pub actions: Vec<ContractAction>,
+    // When the legs were quoted; plans without one are never refused as stale
+    #[serde(default)]
+    pub quoted_at: Option<QuoteWatermark>,
+    // Each leg's minimum output is its expected output less this share; without one, legs accept any output
+    #[serde(default)]
+    pub slippage_tolerance: Option<f64>,
+}
+
+impl TradePlan {
+    pub fn amount_in(&self) -> U256 {
+        self.legs.first().map(|leg| leg.amount_in).unwrap_or_default()
+    }
+
+    pub fn expected_out(&self) -> U256 {
+        self.legs.last().map(|leg| leg.expected_out).unwrap_or_default()
+    }
+
+    pub fn pools(&self) -> Vec<Address> {
+        self.legs.iter().map(|leg| leg.venue.pool).collect()
+    }
+
+    // Values action templates can refer to
+    pub fn context(&self, recipient: Address, deadline: U256) -> BTreeMap<String, String> {
+        let mut context = BTreeMap::new();
+        context.insert(String::from("strategy"), self.strategy.clone());
+        context.insert(String::from("recipient"), format!("{:?}", recipient));
+        context.insert(String::from("deadline"), deadline.to_string());
+        context.insert(String::from("amount_in"), self.amount_in().to_string());
+        context.insert(String::from("expected_out"), self.expected_out().to_string());
+        context.insert(String::from("expected_profit"), self.expected_profit.to_string());
+        if let (Some(first), Some(last)) = (self.legs.first(), self.legs.last()) {
+            context.insert(String::from("token_in"), format!("{:?}", first.token_in));
+            context.insert(String::from("token_out"), format!("{:?}", last.token_out));
+        }
+        for (index, leg) in self.legs.iter().enumerate() {
+            context.insert(format!("legs.{}.token_in", index), format!("{:?}", leg.token_in));
+            context.insert(format!("legs.{}.token_out", index), format!("{:?}", leg.token_out));
+            context.insert(format!("legs.{}.amount_in", index), leg.amount_in.to_string());
+            context.insert(format!("legs.{}.expected_out", index), leg.expected_out.to_string());
+            context.insert(format!("legs.{}.pool", index), format!("{:?}", leg.venue.pool));
+        }
+        context
+    }
+
+    // Before-actions, approve + swap calls for every leg paying out to the recipient, then after-actions
+    pub fn calls(&self, recipient: Address, deadline: U256) -> Result<Vec<Call>, ExecutionError> {
+        let context = self.context(recipient, deadline);
+        let action_calls = |phase: ActionPhase| -> Result<Vec<Call>, ExecutionError> {
+            self.actions.iter().filter(|action| action.phase == phase).map(|action| Ok(action.call(&context)?)).collect()
+        };
+
+        let mut calls = action_calls(ActionPhase::Before)?;
+        for leg in &self.legs {
+            let min_out = min_out(leg.expected_out, self.slippage_tolerance);
+            calls.extend(leg.venue.swap_calls(leg.token_in, leg.token_out, leg.amount_in, min_out, recipient, deadline)?);
+        }
+        calls.extend(action_calls(ActionPhase::After)?);
+        Ok(calls)
+    }
+}
+
+// Least a swap may return: its expected output less the slippage tolerance, never zero. Without a
+// tolerance any output is accepted
+pub fn min_out(expected_out: U256, slippage_tolerance: Option<f64>) -> U256 {
+    match slippage_tolerance {
+        Some(tolerance) => {
+            let kept_bps = U512::from(((1.0 - tolerance.clamp(0.0, 1.0)) * 10_000.0) as u64);
+            U256::try_from(U512::from(expected_out) * kept_bps / U512::from(10_000u64))
+                .unwrap_or(U256::MAX)
+                .max(U256::one())
+        }
+        None => U256::one(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionStatus {
+    // Submitted and not known to have reverted
+    Submitted,
+    Reverted,
+    Failed,
+}
+
+// How executing a plan went
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecutionResult {
+    pub timestamp: String,
+    pub strategy: String,
+    pub status: ExecutionStatus,
+    pub submission: Option<H256>,
+    pub plan: TradePlan,
+    pub realized_out: Option<U256>,
+    // Realized output less the input, when the output could be read back
+    pub pnl_wei: Option<i128>,
+    pub error: Option<String>,
+    // Token/leg graph of multi-leg plans, when plan_graph_format is set
+    #[serde(default)]
+    pub plan_graph: Option<Value>,
+    // Swaps, transfers, liquidations and flash loans decoded from the receipt, with the gas paid
+    #[serde(default)]
+    pub receipt: Option<DecodedReceipt>,
+}
+
+impl ExecutionResult {
+    pub fn new(plan: &TradePlan, status: ExecutionStatus, submission: Option<H256>) -> Self {
+        ExecutionResult {
+            timestamp: Utc::now().to_rfc3339(),
+            strategy: plan.strategy.clone(),
+            status,
+            submission,
+            plan: plan.clone(),
+            realized_out: None,
+            pnl_wei: None,
+            error: None,
+            plan_graph: plan_graph::for_record(plan),
+            receipt: None,
+        }
+    }
+
+    // Append to the executions log and publish as an execution_result event
+    pub fn record(&self) {
+        let record = json!(self);
+        let _ = fs::create_dir_all("data");
+        match OpenOptions::new().create(true).append(true).open(EXECUTIONS_PATH) {
+            Ok(mut file) => {
+                if let Err(e) = writeln!(file, "{}", record) {
+                    error!("Failed to write execution result: {}", e);
+                }
+            }
+            Err(e) => error!("Failed to open execution log: {}", e),
+        }
+        events::emit("execution_result", record);
+        if self.status == ExecutionStatus::Reverted {
+            ramp::record_revert(&self.strategy);
+        }
+    }
+}
+
+// Most recent execution results, newest first
+pub fn recent_executions(limit: usize) -> Vec<ExecutionResult> {
+    fs::read_to_string(EXECUTIONS_PATH)
+        .unwrap_or_default()
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
+}
@@ -0,0 +1,209 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, CallRequest, H256};
+use log::{error, info, warn};
+use thiserror::Error;
+use chrono::Utc;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::providers::FailoverTransport;
+
+// ENS registry, deployed at the same address on mainnet and the public testnets
+const ENS_REGISTRY: &str = "00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+const CONFIG_DIR: &str = "config";
+
+// Where each ENS name in the configs currently points, or why it could not be resolved
+#[derive(Clone, Debug, Serialize)]
+pub struct Resolution {
+    pub address: Option<Address>,
+    pub error: Option<String>,
+    pub resolved_at: String,
+    // Config fields the name appears in, as "file:path"
+    pub used_by: Vec<String>,
+}
+
+fn resolutions() -> &'static Mutex<BTreeMap<String, Resolution>> {
+    static RESOLUTIONS: OnceLock<Mutex<BTreeMap<String, Resolution>>> = OnceLock::new();
+    RESOLUTIONS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+// Config values like "my-cold-wallet.eth"; paths and URLs that happen to end in .eth are not names
+pub fn is_ens_name(value: &str) -> bool {
+    let value = value.trim();
+    value.to_lowercase().ends_with(".eth") && value.len() > 4 && !value.contains(['/', ':', ' '])
+}
+
+// EIP-137 namehash
+pub fn namehash(name: &str) -> H256 {
+    let mut node = [0u8; 32];
+    for label in name.trim().to_lowercase().rsplit('.') {
+        let label_hash = keccak256(label.as_bytes());
+        node = keccak256(&[node, label_hash].concat());
+    }
+    H256::from(node)
+}
+
+// An address config field: a hex address, or an ENS name already resolved at startup
+pub fn parse_address(value: &str) -> Option<Address> {
+    if is_ens_name(value) {
+        return resolutions().lock().unwrap().get(&value.trim().to_lowercase()).and_then(|resolution| resolution.address);
+    }
+    Address::from_str(value.trim()).ok()
+}
+
+async fn call_for_address(web3: &web3::Web3<FailoverTransport>, to: Address, signature: &str, node: H256) -> Result<Address, EnsError> {
+    let data = [keccak256(signature.as_bytes())[..4].to_vec(), node.as_bytes().to_vec()].concat();
+    let request = CallRequest { to: Some(to), data: Some(Bytes(data)), ..Default::default() };
+    let output = web3.eth().call(request, None).await?;
+    if output.0.len() < 32 {
+        return Err(EnsError::InvalidResponse(signature.to_string()));
+    }
+    Ok(Address::from_slice(&output.0[12..32]))
+}
+
+// Registry -> resolver -> addr(node)
+pub async fn resolve(web3: &web3::Web3<FailoverTransport>, name: &str) -> Result<Address, EnsError> {
+    let node = namehash(name);
+    let registry = Address::from_str(ENS_REGISTRY).unwrap();
+    let resolver = call_for_address(web3, registry, "resolver(bytes32)", node).await?;
+    if resolver.is_zero() {
+        return Err(EnsError::NoResolver(name.to_string()));
+    }
+    let address = call_for_address(web3, resolver, "addr(bytes32)", node).await?;
+    if address.is_zero() {
+        return Err(EnsError::NoAddress(name.to_string()));
+    }
+    Ok(address)
+}
+
+fn collect_names(file: &str, path: &str, value: &Value, names: &mut BTreeMap<String, Vec<String>>) {
+    match value {
+        Value::String(text) if is_ens_name(text) => {
+            names.entry(text.trim().to_lowercase()).or_default().push(format!("{}:{}", file, path));
+        }
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                collect_names(file, &child_path, child, names);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                collect_names(file, &format!("{}[{}]", path, index), child, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Every ENS name used anywhere in config/, with the fields that use it
+fn config_names() -> BTreeMap<String, Vec<String>> {
+    let mut names = BTreeMap::new();
+    let entries = match fs::read_dir(CONFIG_DIR) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Failed to list config directory: {}", e);
+            return names;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let file = path.to_string_lossy().to_string();
+        if let Some(config) = fs::read_to_string(&path).ok().and_then(|data| serde_json::from_str::<Value>(&data).ok()) {
+            collect_names(&file, "", &config, &mut names);
+        }
+    }
+    names
+}
+
+// Resolve every ENS name in the configs. Alerts when a name fails or starts pointing somewhere new,
+// since a changed record on e.g. the sweep destination redirects funds
+pub async fn resolve_config_names(web3: &web3::Web3<FailoverTransport>) {
+    for (name, used_by) in config_names() {
+        let result = resolve(web3, &name).await;
+        let previous = resolutions().lock().unwrap().get(&name).and_then(|resolution| resolution.address);
+
+        match &result {
+            Ok(address) if previous.is_some() && previous != Some(*address) => {
+                warn!("ENS name {} now resolves to {:?} (was {:?})", name, address, previous);
+                alerts::raise(
+                    &format!("ens_changed:{}", name),
+                    Severity::Critical,
+                    &format!("ENS name {} changed", name),
+                    &format!("{} now resolves to {:?} (was {:?}). Used by: {}", name, address, previous.unwrap_or_default(), used_by.join(", ")),
+                );
+            }
+            Ok(address) => info!("ENS name {} resolves to {:?}", name, address),
+            Err(e) => {
+                error!("Failed to resolve ENS name {}: {}", name, e);
+                alerts::raise(
+                    &format!("ens_failed:{}", name),
+                    Severity::Warn,
+                    &format!("ENS name {} did not resolve", name),
+                    &format!("{}: {}. Used by: {}", name, e, used_by.join(", ")),
+                );
+            }
+        }
+
+        // Keep the last good address through a transient RPC failure
+        let resolution = Resolution {
+            address: result.as_ref().ok().copied().or(previous),
+            error: result.err().map(|e| e.to_string()),
+            resolved_at: Utc::now().to_rfc3339(),
+            used_by,
+        };
+        resolutions().lock().unwrap().insert(name, resolution);
+    }
+}
+
+// Names that currently have no usable address, for the config validator
+pub fn failures() -> Vec<(String, Resolution)> {
+    resolutions()
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, resolution)| resolution.error.is_some())
+        .map(|(name, resolution)| (name.clone(), resolution.clone()))
+        .collect()
+}
+
+pub fn snapshot() -> Value {
+    json!(*resolutions().lock().unwrap())
+}
+
+// Re-resolve on the configured interval so record changes are picked up (and alerted on)
+pub async fn run_ens_revalidation(web3: web3::Web3<FailoverTransport>, interval_secs: u64) {
+    loop {
+        sleep(Duration::from_secs(interval_secs)).await;
+        resolve_config_names(&web3).await;
+    }
+}
+
+// Custom error type for ENS resolution
+#[derive(Error, Debug)]
+pub enum EnsError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("{0} has no resolver")]
+    NoResolver(String),
+    #[error("{0} has no address record")]
+    NoAddress(String),
+    #[error("Invalid response from {0}")]
+    InvalidResponse(String),
+}
+
+// Implement conversion for EnsError to Web3 error
+impl From<EnsError> for web3::Error {
+    fn from(error: EnsError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
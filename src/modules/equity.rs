@@ -0,0 +1,221 @@
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use web3::contract::Contract;
+use web3::types::U256;
+use log::{error, info};
+use thiserror::Error;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use tokio::time::{sleep, Duration};
+
+use crate::modules::ens;
+use crate::modules::execution::{ExecutionBackend, ExecutionError};
+use crate::modules::inventory::{self, InventoryError};
+use crate::modules::providers::FailoverTransport;
+
+// Load the equity tracking configuration
+fn load_equity_config() -> Value {
+    let config_path = "config/equity_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read equity config file");
+    serde_json::from_str(&config_data).expect("Unable to parse equity config file")
+}
+
+// Load the inventory configuration (router, quote token and tracked tokens are shared)
+fn load_inventory_config() -> Value {
+    let config_path = "config/inventory_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read inventory config file");
+    serde_json::from_str(&config_data).expect("Unable to parse inventory config file")
+}
+
+// One point on the equity curve
+#[derive(Clone, Debug)]
+pub struct EquityPoint {
+    pub block: u64,
+    pub eth_balance: U256,
+    pub eth_usd: f64,
+    pub total_usd: f64,
+    pub total_eth: f64,
+}
+
+impl EquityPoint {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "block": self.block,
+            "eth_balance_wei": self.eth_balance.to_string(),
+            "eth_usd": self.eth_usd,
+            "total_usd": self.total_usd,
+            "total_eth": self.total_eth,
+        })
+    }
+}
+
+// Mark ETH and token inventory held by the execution backend in USD and ETH
+pub async fn sample_equity(web3: &web3::Web3<FailoverTransport>) -> Result<EquityPoint, EquityError> {
+    let config = load_equity_config();
+    let inventory_config = load_inventory_config();
+    let quote_decimals = config["quote_decimals"].as_u64().unwrap_or(6) as i32;
+    let weth = config["weth_address"]
+        .as_str()
+        .and_then(ens::parse_address)
+        .ok_or(EquityError::MissingConfig("weth_address"))?;
+    let quote_token = inventory_config["quote_token"]
+        .as_str()
+        .and_then(ens::parse_address)
+        .ok_or(EquityError::MissingConfig("quote_token"))?;
+    let router_address = inventory_config["router_address"]
+        .as_str()
+        .and_then(ens::parse_address)
+        .ok_or(EquityError::MissingConfig("router_address"))?;
+
+    let holder = ExecutionBackend::from_config(web3)?.recipient();
+    let block = web3.eth().block_number().await?.as_u64();
+    let eth_balance = web3.eth().balance(holder, None).await?;
+
+    // ETH/USD mark from the same router the inventory is valued with
+    let router = Contract::from_json(web3.eth(), router_address, include_bytes!("abi/uniswap_router_abi.json"))?;
+    let one_eth_in_quote = inventory::value_in_quote(&router, weth, quote_token, U256::exp10(18)).await?;
+    let quote_unit = 10f64.powi(quote_decimals);
+    let eth_usd = one_eth_in_quote.as_u128() as f64 / quote_unit;
+
+    let holdings = inventory::snapshot_holdings(web3, &inventory_config, holder).await?;
+    let tokens_usd: f64 = holdings.iter().map(|holding| holding.value.as_u128() as f64 / quote_unit).sum();
+    let eth_usd_value = eth_balance.as_u128() as f64 / 1e18 * eth_usd;
+    let total_usd = tokens_usd + eth_usd_value;
+    let total_eth = if eth_usd > 0.0 { total_usd / eth_usd } else { 0.0 };
+
+    Ok(EquityPoint { block, eth_balance, eth_usd, total_usd, total_eth })
+}
+
+fn series_path(config: &Value) -> String {
+    config["series_path"].as_str().unwrap_or("data/equity.jsonl").to_string()
+}
+
+// Append a point to the persisted equity series
+fn record_point(config: &Value, point: &EquityPoint) {
+    let path = series_path(config);
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", point.to_json()) {
+                error!("Failed to write equity point: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open equity series {}: {}", path, e),
+    }
+}
+
+// Most recent points plus current and maximum drawdown from the running peak
+pub fn equity_curve() -> Value {
+    let config = load_equity_config();
+    let limit = config["dashboard_points"].as_u64().unwrap_or(1440) as usize;
+    let contents = fs::read_to_string(series_path(&config)).unwrap_or_default();
+    let points: Vec<Value> = contents.lines().filter_map(|line| serde_json::from_str(line).ok()).collect();
+
+    let (mut peak, mut max_drawdown, mut drawdown) = (0.0f64, 0.0f64, 0.0f64);
+    for point in &points {
+        let total = point["total_usd"].as_f64().unwrap_or(0.0);
+        peak = peak.max(total);
+        drawdown = if peak > 0.0 { (peak - total) / peak } else { 0.0 };
+        max_drawdown = max_drawdown.max(drawdown);
+    }
+
+    let recent = points[points.len().saturating_sub(limit)..].to_vec();
+    json!({
+        "points": recent,
+        "peak_usd": peak,
+        "drawdown": drawdown,
+        "max_drawdown": max_drawdown,
+    })
+}
+
+// Latest equity and its change over the trailing window, for the compact summary
+pub fn latest_with_change(window: ChronoDuration) -> Value {
+    let config = load_equity_config();
+    let contents = fs::read_to_string(series_path(&config)).unwrap_or_default();
+    let points: Vec<Value> = contents.lines().filter_map(|line| serde_json::from_str(line).ok()).collect();
+    let latest = match points.last() {
+        Some(latest) => latest,
+        None => return Value::Null,
+    };
+
+    // Oldest point still inside the window is the baseline
+    let cutoff = Utc::now() - window;
+    let baseline = points.iter().find(|point| {
+        point["timestamp"]
+            .as_str()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc) >= cutoff)
+            .unwrap_or(false)
+    });
+    let total_usd = latest["total_usd"].as_f64().unwrap_or(0.0);
+    json!({
+        "total_usd": total_usd,
+        "total_eth": latest["total_eth"],
+        "timestamp": latest["timestamp"],
+        "change_usd": baseline.map(|point| total_usd - point["total_usd"].as_f64().unwrap_or(0.0)),
+    })
+}
+
+// ETH/USD from the most recent equity sample
+pub fn latest_eth_usd() -> Option<f64> {
+    let config = load_equity_config();
+    let contents = fs::read_to_string(series_path(&config)).ok()?;
+    let latest: Value = serde_json::from_str(contents.lines().last()?).ok()?;
+    latest["eth_usd"].as_f64().filter(|eth_usd| *eth_usd > 0.0)
+}
+
+// Sample equity every minute, or on every new block when per_block is set
+pub async fn run_equity_tracker(web3: web3::Web3<FailoverTransport>) {
+    let config = load_equity_config();
+    let per_block = config["per_block"].as_bool().unwrap_or(false);
+    let sample_interval = config["sample_interval_secs"].as_u64().unwrap_or(60);
+    let mut last_block = 0u64;
+
+    loop {
+        let due = if per_block {
+            match web3.eth().block_number().await {
+                Ok(block) if block.as_u64() > last_block => {
+                    last_block = block.as_u64();
+                    true
+                }
+                _ => false,
+            }
+        } else {
+            true
+        };
+
+        if due {
+            match sample_equity(&web3).await {
+                Ok(point) => {
+                    info!("Equity at block {}: ${:.2} ({:.4} ETH)", point.block, point.total_usd, point.total_eth);
+                    record_point(&config, &point);
+                }
+                Err(e) => error!("Failed to sample equity: {}", e),
+            }
+        }
+
+        let delay = if per_block { 3 } else { sample_interval };
+        sleep(Duration::from_secs(delay)).await;
+    }
+}
+
+// Custom error type for equity tracking
+#[derive(Error, Debug)]
+pub enum EquityError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Inventory error: {0}")]
+    InventoryError(#[from] InventoryError),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+}
@@ -0,0 +1,34 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+use chrono::Utc;
+
+// Slow subscribers past this many buffered events skip ahead instead of holding the bus back
+const EVENT_BUFFER: usize = 1024;
+
+// Something an external tool may want to react to: control actions, submissions, code changes
+#[derive(Clone, Debug, Serialize)]
+pub struct BotEvent {
+    pub timestamp: String,
+    pub kind: String,
+    pub payload: Value,
+}
+
+fn bus() -> &'static broadcast::Sender<BotEvent> {
+    static BUS: OnceLock<broadcast::Sender<BotEvent>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(EVENT_BUFFER).0)
+}
+
+// Publish an event to live subscribers; dropped when nobody is listening
+pub fn emit(kind: &str, payload: Value) {
+    let _ = bus().send(BotEvent {
+        timestamp: Utc::now().to_rfc3339(),
+        kind: kind.to_string(),
+        payload,
+    });
+}
+
+pub fn subscribe() -> broadcast::Receiver<BotEvent> {
+    bus().subscribe()
+}
@@ -0,0 +1,624 @@
+use serde_json::{json, Value};
+use std::fs;
+use web3::ethabi::{self, Token};
+use web3::types::{Address, Bytes, CallRequest, TransactionRequest, H256, U256, U64};
+use log::{info, warn};
+use thiserror::Error;
+
+use crate::modules::account_abstraction::{AccountAbstractionBackend, AccountAbstractionError};
+use crate::modules::actions::ActionError;
+use crate::modules::approvals;
+use crate::modules::chains::{self, ChainError};
+use crate::modules::code_watch;
+use crate::modules::control;
+use crate::modules::domain::{QuoteWatermark, TradePlan};
+use crate::modules::coordination;
+use crate::modules::ens;
+use crate::modules::events;
+use crate::modules::explorer;
+use crate::modules::gas::{self, Urgency};
+use crate::modules::gas_golf;
+use crate::modules::multicall;
+use crate::modules::nonce_recovery;
+use crate::modules::postmortem;
+use crate::modules::receipt_events::{self, ReceiptEventsError};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::safe::{SafeBackend, SafeError};
+use crate::modules::signer::{self, SignerError};
+use crate::modules::simulation::{self, SimulationError, SimulationRequest};
+use crate::modules::spend_caps::{self, SpendCapError};
+use crate::modules::storage;
+
+// Load global config (execution backend selection lives there)
+fn load_global_config() -> Value {
+    let config_path = "config/global_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read global config file");
+    serde_json::from_str(&config_data).expect("Unable to parse global config file")
+}
+
+// A single contract call to be executed by a backend
+#[derive(Clone, Debug)]
+pub struct Call {
+    pub to: Address,
+    pub value: U256,
+    pub data: Vec<u8>,
+}
+
+// Backends that can carry strategy calls on-chain
+pub enum ExecutionBackend {
+    // Plain transactions from the wallet, one per call, signed with the local key (or by the node without one)
+    Direct { wallet: Address },
+    // ERC-4337 UserOperations from a smart account, all calls in one operation
+    AccountAbstraction(AccountAbstractionBackend),
+    // Gnosis Safe transactions, proposed to the transaction service or executed with threshold signatures
+    Safe(SafeBackend),
+}
+
+impl ExecutionBackend {
+    // Select the backend named by "execution_backend" in the global config
+    pub fn from_config(web3: &web3::Web3<FailoverTransport>) -> Result<Self, ExecutionError> {
+        let config = load_global_config();
+        // The smart account and Safe backends load owner keys, which a read-only instance never does
+        let backend = config["execution_backend"].as_str().unwrap_or("direct");
+        if signer::read_only() && backend != "direct" {
+            return Err(ExecutionError::ReadOnly);
+        }
+        match backend {
+            "account_abstraction" => Ok(ExecutionBackend::AccountAbstraction(AccountAbstractionBackend::new(web3.clone())?)),
+            "safe" => Ok(ExecutionBackend::Safe(SafeBackend::new(web3.clone())?)),
+            "direct" => {
+                let wallet = config["wallet_address"]
+                    .as_str()
+                    .and_then(ens::parse_address)
+                    .ok_or(ExecutionError::MissingWallet)?;
+                Ok(ExecutionBackend::Direct { wallet })
+            }
+            other => Err(ExecutionError::UnknownBackend(other.to_string())),
+        }
+    }
+
+    // Address that holds funds and receives trade outputs for this backend
+    pub fn recipient(&self) -> Address {
+        match self {
+            ExecutionBackend::Direct { wallet } => *wallet,
+            ExecutionBackend::AccountAbstraction(backend) => backend.smart_account(),
+            ExecutionBackend::Safe(backend) => backend.safe_address(),
+        }
+    }
+
+    // Whether all calls passed to execute_calls land atomically. A Safe proposal only queues the
+    // batch for the owners, so nothing has run when it returns
+    pub fn is_atomic(&self) -> bool {
+        match self {
+            ExecutionBackend::Direct { .. } => false,
+            ExecutionBackend::AccountAbstraction(_) => true,
+            ExecutionBackend::Safe(backend) => backend.executes(),
+        }
+    }
+
+    // Whether execute_calls only proposes the batch (a Safe in propose mode): the hash it returns
+    // identifies the proposal, and no receipt exists until the owners execute it
+    pub fn is_proposal(&self) -> bool {
+        matches!(self, ExecutionBackend::Safe(backend) if !backend.executes())
+    }
+
+    // Execute the calls on behalf of a strategy and return the hash identifying the submission
+    pub async fn execute_calls(&self, web3: &web3::Web3<FailoverTransport>, strategy: &str, calls: Vec<Call>) -> Result<H256, ExecutionError> {
+        if calls.is_empty() {
+            return Err(ExecutionError::EmptyBatch);
+        }
+        if control::is_paused() {
+            return Err(ExecutionError::Paused);
+        }
+        if signer::read_only() {
+            return Err(ExecutionError::ReadOnly);
+        }
+        // Only the instance holding the wallet lock sends, so two instances never race on nonces
+        if !coordination::is_writer() {
+            return Err(ExecutionError::ObserverMode);
+        }
+        // Never trade through a contract whose code changed until an operator has reviewed it
+        if let Some(target) = code_watch::unacknowledged_target(calls.iter().map(|call| call.to)) {
+            return Err(ExecutionError::UnacknowledgedUpgrade(target));
+        }
+
+        let calls = gas_golf::optimize_calls(web3, self.recipient(), calls).await;
+        if calls.is_empty() {
+            return Err(ExecutionError::EmptyBatch);
+        }
+        // Approvals that are still needed are sized per trade or topped up to the rolling cap
+        let calls = approvals::size_approvals(calls);
+
+        let submitted = self.submit(web3, strategy, calls.clone()).await;
+        let targets: Vec<Address> = calls.iter().map(|call| call.to).collect();
+        match &submitted {
+            Ok(tx_hash) => {
+                let mut methods = Vec::with_capacity(calls.len());
+                for call in &calls {
+                    methods.push(explorer::describe_call(call.to, &call.data).await);
+                }
+                events::emit("execution_submitted", json!({ "tx_hash": tx_hash, "targets": targets, "methods": methods }));
+                approvals::record_usage(self.recipient(), &calls, *tx_hash);
+            }
+            Err(e) => {
+                events::emit("execution_failed", json!({ "error": e.to_string(), "targets": targets }));
+                // Outages, refused signatures and caps say nothing about the trade itself
+                if e.is_revert() {
+                    postmortem::capture(web3, strategy, &e.to_string(), self.recipient(), &calls, None, &[]).await;
+                }
+            }
+        }
+        submitted
+    }
+
+    // Execute the calls carrying out a plan, refusing to broadcast once its quotes are more than
+    // max_quote_age_blocks behind the head; the caller re-quotes instead of trading on stale prices
+    pub async fn execute_plan(&self, web3: &web3::Web3<FailoverTransport>, plan: &TradePlan, calls: Vec<Call>) -> Result<H256, ExecutionError> {
+        if let Some(quoted_at) = plan.quoted_at {
+            ensure_fresh(web3, &plan.strategy, quoted_at).await?;
+        }
+        self.execute_calls(web3, &plan.strategy, calls).await
+    }
+
+    // Hand the optimized calls to the selected backend. Direct transactions are charged to the
+    // strategy's spending cap by the signer and Safe batches by the Safe backend, with the gas it
+    // estimates; smart-account batches are charged here, at the last step before signing
+    async fn submit(&self, web3: &web3::Web3<FailoverTransport>, strategy: &str, calls: Vec<Call>) -> Result<H256, ExecutionError> {
+        match self {
+            ExecutionBackend::Direct { wallet } => {
+                // Fees at the strategy's urgency; without fee history the node fills in its own
+                let fees = match gas::suggest_fees(web3, Urgency::for_strategy(strategy)).await {
+                    Ok(fees) => Some(fees),
+                    Err(e) => {
+                        warn!("No fee suggestion, using node defaults: {}", e);
+                        None
+                    }
+                };
+                let mut last_hash = H256::zero();
+                let legs = calls.len();
+                for (index, call) in calls.into_iter().enumerate() {
+                    // Later calls depend on earlier ones (the swap spends what the approve allowed),
+                    // so each leg waits for the previous one to be mined before it is simulated
+                    if index > 0 {
+                        await_leg(web3, last_hash).await?;
+                    }
+                    // The gas limit is pinned to the simulated usage plus a margin, so the signed
+                    // transaction can't burn more than the cap is charged for when it is signed
+                    let estimate = CallRequest {
+                        from: Some(*wallet),
+                        to: Some(call.to),
+                        value: Some(call.value),
+                        data: Some(Bytes(call.data.clone())),
+                        ..Default::default()
+                    };
+                    simulation::presend_check(web3, strategy, estimate.clone()).await?;
+                    let gas = simulation::gas_limit(web3, estimate).await?;
+                    let request = TransactionRequest {
+                        from: *wallet,
+                        to: Some(call.to),
+                        gas: Some(gas),
+                        value: Some(call.value),
+                        data: Some(Bytes(call.data)),
+                        max_fee_per_gas: fees.as_ref().map(|fees| fees.max_fee),
+                        max_priority_fee_per_gas: fees.as_ref().map(|fees| fees.priority_fee),
+                        ..Default::default()
+                    };
+                    // Mempool, sequencer race or express lane, depending on the active chain
+                    last_hash = chains::submit(web3, strategy, request).await?;
+                    nonce_recovery::track(*wallet, last_hash);
+                    storage::record_transaction(strategy, last_hash, "direct");
+                    info!("Direct transaction sent: {:?} ({}/{})", last_hash, index + 1, legs);
+                }
+                Ok(last_hash)
+            }
+            // Smart-account gas is bounded by the bundler or relayer, so only value is capped here
+            ExecutionBackend::AccountAbstraction(backend) => {
+                spend_caps::authorize(strategy, calls.iter().fold(U256::zero(), |total, call| total.saturating_add(call.value)), U256::zero())?;
+                let batch: Vec<(Address, U256, Vec<u8>)> = calls
+                    .into_iter()
+                    .map(|call| (call.to, call.value, call.data))
+                    .collect();
+                let user_op_hash = backend.submit_calls(&batch).await?;
+                let receipt = backend.wait_for_receipt(user_op_hash).await?;
+                // Receipts and logs belong to the bundle transaction that carried the UserOperation
+                serde_json::from_value::<H256>(receipt["receipt"]["transactionHash"].clone())
+                    .map_err(|_| AccountAbstractionError::BundlerError(format!("receipt for {:?} has no transactionHash", user_op_hash)).into())
+            }
+            ExecutionBackend::Safe(backend) => {
+                let batch: Vec<(Address, U256, Vec<u8>)> = calls
+                    .into_iter()
+                    .map(|call| (call.to, call.value, call.data))
+                    .collect();
+                let tx_hash = backend.submit_calls(strategy, &batch).await?;
+                // Proposals return a Safe transaction hash, not an on-chain one
+                if backend.executes() {
+                    storage::record_transaction(strategy, tx_hash, "safe");
+                }
+                Ok(tx_hash)
+            }
+        }
+    }
+}
+
+// Wait for a leg of a direct batch to be mined; the rest of the batch is abandoned if it reverted
+// or wasn't mined within receipt_timeout_secs
+async fn await_leg(web3: &web3::Web3<FailoverTransport>, tx_hash: H256) -> Result<(), ExecutionError> {
+    match receipt_events::wait_for_receipt(web3, tx_hash).await? {
+        Some(receipt) if receipt.status == Some(U64::from(1)) => Ok(()),
+        Some(_) => Err(ExecutionError::LegReverted(tx_hash)),
+        None => Err(ExecutionError::LegNotMined(tx_hash)),
+    }
+}
+
+// Fail with StaleQuotes when the head has moved more than max_quote_age_blocks past the quotes
+async fn ensure_fresh(web3: &web3::Web3<FailoverTransport>, strategy: &str, quoted_at: QuoteWatermark) -> Result<(), ExecutionError> {
+    let max_age = load_global_config()["max_quote_age_blocks"].as_u64().unwrap_or(2);
+    let head = web3.eth().block_number().await?.as_u64();
+    let age = head.saturating_sub(quoted_at.block);
+    if age > max_age {
+        warn!("Refusing {} plan quoted at block {}: head is {} ({} blocks old)", strategy, quoted_at.block, head, age);
+        events::emit("stale_plan_refused", json!({ "strategy": strategy, "quoted_block": quoted_at.block, "quoted_timestamp": quoted_at.timestamp, "head": head }));
+        return Err(ExecutionError::StaleQuotes { quoted_block: quoted_at.block, head });
+    }
+    Ok(())
+}
+
+// Build an ERC20 approve call
+pub fn approve_call(token: Address, spender: Address, amount: U256) -> Result<Call, ExecutionError> {
+    let erc20 = ethabi::Contract::load(&include_bytes!("abi/erc20_abi.json")[..])?;
+    let data = erc20
+        .function("approve")?
+        .encode_input(&[Token::Address(spender), Token::Uint(amount)])?;
+    Ok(Call { to: token, value: U256::zero(), data })
+}
+
+// Decode an ERC20 approve into (spender, amount)
+pub fn decode_approve(call: &Call) -> Option<(Address, U256)> {
+    if call.data.len() < 4 || call.data[..4] != multicall::selector("approve(address,uint256)") {
+        return None;
+    }
+    let tokens = ethabi::decode(&[ethabi::ParamType::Address, ethabi::ParamType::Uint(256)], &call.data[4..]).ok()?;
+    Some((tokens[0].clone().into_address()?, tokens[1].clone().into_uint()?))
+}
+
+// Build approve + swapExactTokensForTokens calls against a V2-style router
+pub fn approve_and_swap_calls(
+    router: Address,
+    path: Vec<Address>,
+    amount_in: U256,
+    amount_out_min: U256,
+    recipient: Address,
+    deadline: U256,
+) -> Result<Vec<Call>, ExecutionError> {
+    let router_abi = ethabi::Contract::load(&include_bytes!("abi/uniswap_v2_router_abi.json")[..])?;
+    let swap_data = router_abi.function("swapExactTokensForTokens")?.encode_input(&[
+        Token::Uint(amount_in),
+        Token::Uint(amount_out_min),
+        Token::Array(path.iter().map(|token| Token::Address(*token)).collect()),
+        Token::Address(recipient),
+        Token::Uint(deadline),
+    ])?;
+
+    Ok(vec![
+        approve_call(path[0], router, amount_in)?,
+        Call { to: router, value: U256::zero(), data: swap_data },
+    ])
+}
+
+// One exact-input swap: what goes in, the least that must come out, and who receives it by when
+#[derive(Clone, Copy, Debug)]
+pub struct SwapOrder {
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: U256,
+    pub amount_out_min: U256,
+    pub recipient: Address,
+    pub deadline: U256,
+}
+
+// Build an exactInputSingle call against a Uniswap V3 swap router
+pub fn swap_v3_call(router: Address, fee: u32, order: &SwapOrder) -> Result<Call, ExecutionError> {
+    let router_abi = ethabi::Contract::load(&include_bytes!("abi/uniswap_v3_router_abi.json")[..])?;
+    let swap_data = router_abi.function("exactInputSingle")?.encode_input(&[Token::Tuple(vec![
+        Token::Address(order.token_in),
+        Token::Address(order.token_out),
+        Token::Uint(U256::from(fee)),
+        Token::Address(order.recipient),
+        Token::Uint(order.deadline),
+        Token::Uint(order.amount_in),
+        Token::Uint(order.amount_out_min),
+        Token::Uint(U256::zero()),
+    ])])?;
+    Ok(Call { to: router, value: U256::zero(), data: swap_data })
+}
+
+// Build approve + exactInputSingle calls against a Uniswap V3 swap router
+pub fn approve_and_swap_v3_calls(router: Address, fee: u32, order: &SwapOrder) -> Result<Vec<Call>, ExecutionError> {
+    Ok(vec![
+        approve_call(order.token_in, router, order.amount_in)?,
+        swap_v3_call(router, fee, order)?,
+    ])
+}
+
+// Sweep the full token balance held by the backend to the configured profit address
+pub async fn sweep_profits(
+    web3: &web3::Web3<FailoverTransport>,
+    backend: &ExecutionBackend,
+    token: Address,
+) -> Result<Option<H256>, ExecutionError> {
+    let config = load_global_config();
+    let destination = config["profit_sweep_address"]
+        .as_str()
+        .and_then(ens::parse_address)
+        .ok_or(ExecutionError::MissingSweepAddress)?;
+
+    let erc20 = web3::contract::Contract::from_json(web3.eth(), token, include_bytes!("abi/erc20_abi.json"))?;
+    let balance: U256 = erc20
+        .query("balanceOf", backend.recipient(), None, web3::contract::Options::default(), None)
+        .await?;
+    if balance.is_zero() {
+        return Ok(None);
+    }
+
+    let erc20_abi = ethabi::Contract::load(&include_bytes!("abi/erc20_abi.json")[..])?;
+    let data = erc20_abi
+        .function("transfer")?
+        .encode_input(&[Token::Address(destination), Token::Uint(balance)])?;
+
+    let submission = backend
+        .execute_calls(web3, "profit_sweep", vec![Call { to: token, value: U256::zero(), data }])
+        .await?;
+    info!("Swept {} of token {:?} to {:?}: {:?}", balance, token, destination, submission);
+    Ok(Some(submission))
+}
+
+// Phase one of a flashloan strategy: the loan, the trades run inside the callback and the repayment
+// are collected and validated together, nothing is sent until the plan is committed
+pub struct FlashloanPlan {
+    pub receiver: Address,
+    pub asset: Address,
+    pub amount: U256,
+    pub premium: U256,
+    pub calls: Vec<Call>,
+}
+
+// A validated plan encoded as the single call that runs the whole sequence in one transaction
+pub struct AtomicPayload {
+    pub call: Call,
+    pub legs: usize,
+    pub repayment: U256,
+}
+
+impl FlashloanPlan {
+    pub fn new(receiver: Address, asset: Address, amount: U256, premium_bps: u64) -> Self {
+        let premium = amount * U256::from(premium_bps) / U256::from(10_000u64);
+        FlashloanPlan { receiver, asset, amount, premium, calls: Vec::new() }
+    }
+
+    // Add a trade executed by the receiver contract while the loan is outstanding
+    pub fn push(&mut self, call: Call) {
+        self.calls.push(call);
+    }
+
+    // Validate every leg and encode the sequence, refusing anything that can't run atomically
+    pub async fn prepare(mut self, web3: &web3::Web3<FailoverTransport>, backend: &ExecutionBackend) -> Result<AtomicPayload, ExecutionError> {
+        // The receiver contract runs the trades, so allowances are checked against it
+        self.calls = gas_golf::optimize_calls(web3, self.receiver, self.calls).await;
+        if self.calls.is_empty() {
+            return Err(ExecutionError::EmptyBatch);
+        }
+        if self.calls.iter().any(|call| call.to == self.receiver) {
+            return Err(ExecutionError::NotAtomic(String::from("strategy calls may not re-enter the flashloan receiver")));
+        }
+        if !self.calls.iter().all(|call| call.value.is_zero()) {
+            return Err(ExecutionError::NotAtomic(String::from("strategy calls inside the loan callback cannot carry ETH")));
+        }
+
+        // The trades only run atomically with the loan if the receiver contract executes them in its callback
+        let code = web3.eth().code(self.receiver, None).await?;
+        if code.0.is_empty() {
+            return Err(ExecutionError::NotAtomic(format!("flashloan receiver {:?} has no contract code", self.receiver)));
+        }
+
+        // Callback params: the "calls" strategy tag followed by the (to, value, data) sequence
+        let params = ethabi::encode(&[
+            Token::FixedBytes(web3::signing::keccak256(b"calls").to_vec()),
+            Token::Array(
+                self.calls
+                    .iter()
+                    .map(|call| Token::Tuple(vec![Token::Address(call.to), Token::Uint(call.value), Token::Bytes(call.data.clone())]))
+                    .collect(),
+            ),
+        ]);
+
+        let receiver_abi = ethabi::Contract::load(&include_bytes!("abi/flashloan_abi.json")[..])?;
+        let data = receiver_abi.function("executeFlashLoan")?.encode_input(&[
+            Token::Address(self.asset),
+            Token::Uint(self.amount),
+            Token::Bytes(params),
+        ])?;
+        let call = Call { to: self.receiver, value: U256::zero(), data };
+
+        // Simulate the full loan -> trades -> repay sequence; a revert means the plan would lose or fail
+        let simulation = web3::types::CallRequest {
+            from: Some(backend.recipient()),
+            to: Some(call.to),
+            data: Some(Bytes(call.data.clone())),
+            ..Default::default()
+        };
+        match simulation::simulate(web3, SimulationRequest::Call(simulation), simulation::default_deadline()).await {
+            Ok(_) => {}
+            Err(SimulationError::Reverted(reason)) => {
+                postmortem::capture(web3, "flashloan", &reason, backend.recipient(), &[call], None, &[]).await;
+                return Err(ExecutionError::SimulationFailed(reason));
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(AtomicPayload { call, legs: self.calls.len(), repayment: self.amount + self.premium })
+    }
+}
+
+// A multi-leg trade run by the strategy's executor contract: every call goes into one executeBatch
+// call, so any failing leg, or a profit token balance that doesn't grow by min_profit, reverts the
+// whole cycle instead of leaving funds stranded halfway
+pub struct ExecutorBatch {
+    pub executor: Address,
+    pub profit_token: Address,
+    pub min_profit: U256,
+    pub calls: Vec<Call>,
+}
+
+impl ExecutorBatch {
+    pub fn new(executor: Address, profit_token: Address, min_profit: U256, calls: Vec<Call>) -> Self {
+        ExecutorBatch { executor, profit_token, min_profit, calls }
+    }
+
+    // Validate the legs and encode them as the single executor call, refusing anything that can't
+    // run atomically
+    pub async fn prepare(mut self, web3: &web3::Web3<FailoverTransport>, backend: &ExecutionBackend) -> Result<Call, ExecutionError> {
+        // The executor holds the funds and runs the swaps, so allowances are checked against it
+        self.calls = gas_golf::optimize_calls(web3, self.executor, self.calls).await;
+        if self.calls.is_empty() {
+            return Err(ExecutionError::EmptyBatch);
+        }
+        if self.calls.iter().any(|call| call.to == self.executor) {
+            return Err(ExecutionError::NotAtomic(String::from("batched calls may not re-enter the executor")));
+        }
+        let code = web3.eth().code(self.executor, None).await?;
+        if code.0.is_empty() {
+            return Err(ExecutionError::NotAtomic(format!("executor {:?} has no contract code", self.executor)));
+        }
+
+        let executor_abi = ethabi::Contract::load(&include_bytes!("abi/arbitrage_abi.json")[..])?;
+        let data = executor_abi.function("executeBatch")?.encode_input(&[
+            Token::Array(
+                self.calls
+                    .iter()
+                    .map(|call| Token::Tuple(vec![Token::Address(call.to), Token::Uint(call.value), Token::Bytes(call.data.clone())]))
+                    .collect(),
+            ),
+            Token::Address(self.profit_token),
+            Token::Uint(self.min_profit),
+        ])?;
+        let value = self.calls.iter().fold(U256::zero(), |total, call| total.saturating_add(call.value));
+        let call = Call { to: self.executor, value, data };
+
+        // Simulate the whole cycle; a revert means a leg would fail or the cycle would lose
+        let simulation = web3::types::CallRequest {
+            from: Some(backend.recipient()),
+            to: Some(call.to),
+            value: Some(call.value),
+            data: Some(Bytes(call.data.clone())),
+            ..Default::default()
+        };
+        match simulation::simulate(web3, SimulationRequest::Call(simulation), simulation::default_deadline()).await {
+            Ok(_) => {}
+            Err(SimulationError::Reverted(reason)) => {
+                postmortem::capture(web3, "executor_batch", &reason, backend.recipient(), &[call], None, &[]).await;
+                return Err(ExecutionError::SimulationFailed(reason));
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        info!("Executor batch with {} calls prepared for {:?}", self.calls.len(), self.executor);
+        Ok(call)
+    }
+}
+
+impl AtomicPayload {
+    // Phase two: send the validated sequence as one transaction
+    pub async fn commit(self, web3: &web3::Web3<FailoverTransport>, backend: &ExecutionBackend, strategy: &str) -> Result<H256, ExecutionError> {
+        info!("Committing flashloan plan with {} legs, repayment {}", self.legs, self.repayment);
+        backend.execute_calls(web3, strategy, vec![self.call]).await
+    }
+}
+
+// Custom error type for execution backends
+#[derive(Error, Debug)]
+pub enum ExecutionError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Contract error: {0}")]
+    ContractError(#[from] web3::contract::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Safe error: {0}")]
+    SafeError(#[from] SafeError),
+    #[error("Account abstraction error: {0}")]
+    AccountAbstractionError(#[from] AccountAbstractionError),
+    #[error("Chain submission error: {0}")]
+    ChainError(#[from] ChainError),
+    #[error("Unknown execution backend: {0}")]
+    UnknownBackend(String),
+    #[error("wallet_address is required for the direct execution backend")]
+    MissingWallet,
+    #[error("profit_sweep_address is not configured")]
+    MissingSweepAddress,
+    #[error("No calls to execute")]
+    EmptyBatch,
+    #[error("Execution is paused or the kill switch is engaged")]
+    Paused,
+    #[error("Call targets {0:?}, whose code changed and has not been acknowledged")]
+    UnacknowledgedUpgrade(Address),
+    #[error("This instance is an observer; another instance holds the wallet lock")]
+    ObserverMode,
+    #[error("This instance is read-only and never signs or sends transactions")]
+    ReadOnly,
+    #[error("Venue {0} can't be traded by this adapter")]
+    UnsupportedVenue(String),
+    #[error("Plan cannot be executed atomically: {0}")]
+    NotAtomic(String),
+    #[error("Plan simulation reverted: {0}")]
+    SimulationFailed(String),
+    #[error("Plan was quoted at block {quoted_block}, too far behind head {head}")]
+    StaleQuotes { quoted_block: u64, head: u64 },
+    #[error("Contract action error: {0}")]
+    ActionError(#[from] ActionError),
+    #[error("Simulation error: {0}")]
+    SimulationError(#[from] SimulationError),
+    #[error("Spending cap: {0}")]
+    SpendCapError(#[from] SpendCapError),
+    #[error("Receipt error: {0}")]
+    ReceiptError(#[from] ReceiptEventsError),
+    #[error("Batch leg {0:?} reverted; the remaining calls were not sent")]
+    LegReverted(H256),
+    #[error("Batch leg {0:?} was not mined in time; the remaining calls were not sent")]
+    LegNotMined(H256),
+}
+
+impl ExecutionError {
+    // The calls revert: a simulation or the node refused them, or a sent leg reverted on-chain.
+    // Outages, refused signatures and caps say nothing about the trade and are not reverts
+    pub fn is_revert(&self) -> bool {
+        match self {
+            ExecutionError::SimulationFailed(_) | ExecutionError::LegReverted(_) => true,
+            ExecutionError::SimulationError(e) => e.is_revert(),
+            ExecutionError::Web3Error(e) => simulation::is_revert_error(e),
+            ExecutionError::ContractError(web3::contract::Error::Api(e)) => simulation::is_revert_error(e),
+            ExecutionError::ChainError(ChainError::Web3Error(e)) => simulation::is_revert_error(e),
+            ExecutionError::ChainError(ChainError::SignerError(e)) => signer_reverted(e),
+            ExecutionError::SafeError(SafeError::SimulationError(e)) => e.is_revert(),
+            ExecutionError::SafeError(SafeError::Web3Error(e)) => simulation::is_revert_error(e),
+            ExecutionError::AccountAbstractionError(AccountAbstractionError::Reverted(_)) => true,
+            ExecutionError::AccountAbstractionError(AccountAbstractionError::Web3Error(e)) => simulation::is_revert_error(e),
+            _ => false,
+        }
+    }
+}
+
+fn signer_reverted(error: &SignerError) -> bool {
+    match error {
+        SignerError::SimulationError(e) => e.is_revert(),
+        SignerError::Web3Error(e) => simulation::is_revert_error(e),
+        _ => false,
+    }
+}
+
+// Implement conversion for ExecutionError to Web3 error
+impl From<ExecutionError> for web3::Error {
+    fn from(error: ExecutionError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,251 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use web3::ethabi;
+use web3::types::Address;
+use log::{info, warn};
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::mempool_recorder;
+
+// Load the block explorer configuration
+fn load_explorer_config() -> Value {
+    let config_path = "config/explorer_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read explorer config file");
+    serde_json::from_str(&config_data).expect("Unable to parse explorer config file")
+}
+
+// Verification results and parsed ABIs; None records a contract the explorer has no source for
+#[derive(Default)]
+struct ExplorerCache {
+    verified: HashMap<Address, bool>,
+    abis: HashMap<Address, Option<ethabi::Contract>>,
+}
+
+fn cache() -> &'static Mutex<ExplorerCache> {
+    static CACHE: OnceLock<Mutex<ExplorerCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(ExplorerCache::default()))
+}
+
+// Etherscan-compatible API client for the configured chain
+pub struct ExplorerClient {
+    api_url: String,
+    api_key: String,
+    abi_cache_dir: PathBuf,
+    min_interval: Duration,
+    client: reqwest::Client,
+}
+
+impl ExplorerClient {
+    pub fn from_config() -> Result<Self, ExplorerError> {
+        let config = load_explorer_config();
+        let chain_id = config["chain_id"].as_u64().unwrap_or(1);
+        let chain = &config["chains"][chain_id.to_string()];
+        let api_url = chain["api_url"].as_str().ok_or(ExplorerError::UnsupportedChain(chain_id))?;
+        let timeout = Duration::from_millis(config["timeout_ms"].as_u64().unwrap_or(5000));
+
+        Ok(ExplorerClient {
+            api_url: api_url.to_string(),
+            api_key: chain["api_key"].as_str().unwrap_or_default().to_string(),
+            abi_cache_dir: PathBuf::from(config["abi_cache_dir"].as_str().unwrap_or("data/abis")),
+            min_interval: Duration::from_millis(config["min_request_interval_ms"].as_u64().unwrap_or(250)),
+            client: reqwest::Client::builder().timeout(timeout).build()?,
+        })
+    }
+
+    // Space requests out to stay under the explorer's rate limit
+    async fn throttle(&self) {
+        static LAST_REQUEST: OnceLock<Mutex<Option<Instant>>> = OnceLock::new();
+        let wait = {
+            let mut last = LAST_REQUEST.get_or_init(|| Mutex::new(None)).lock().unwrap();
+            let now = Instant::now();
+            let next = last.map(|last| last + self.min_interval).filter(|next| *next > now).unwrap_or(now);
+            *last = Some(next);
+            next - now
+        };
+        sleep(wait).await;
+    }
+
+    async fn contract_query(&self, action: &str, address: Address) -> Result<Value, ExplorerError> {
+        self.throttle().await;
+        let address = format!("{:?}", address);
+        let response: Value = self
+            .client
+            .get(&self.api_url)
+            .query(&[("module", "contract"), ("action", action), ("address", &address), ("apikey", &self.api_key)])
+            .send()
+            .await?
+            .json()
+            .await?;
+        if response["status"].as_str() != Some("1") {
+            let result = response["result"].as_str().unwrap_or_default();
+            // Unverified contracts are an answer, not a failure
+            if result.contains("not verified") {
+                return Ok(Value::Null);
+            }
+            return Err(ExplorerError::ApiError(format!("{} {}", response["message"], result)));
+        }
+        Ok(response["result"].clone())
+    }
+
+    async fn query(&self, params: &[(&str, &str)]) -> Result<Value, ExplorerError> {
+        self.throttle().await;
+        let response: Value = self
+            .client
+            .get(&self.api_url)
+            .query(params)
+            .query(&[("apikey", &self.api_key)])
+            .send()
+            .await?
+            .json()
+            .await?;
+        if response["status"].as_str() != Some("1") {
+            let message = response["message"].as_str().unwrap_or_default();
+            // An account without activity in the range is an empty list, not a failure
+            if message.starts_with("No transactions found") || message.starts_with("No records found") {
+                return Ok(Value::Array(Vec::new()));
+            }
+            return Err(ExplorerError::ApiError(format!("{} {}", message, response["result"])));
+        }
+        Ok(response["result"].clone())
+    }
+
+    // Last block mined at or before the unix timestamp
+    pub async fn block_at(&self, timestamp: i64) -> Result<u64, ExplorerError> {
+        let timestamp = timestamp.to_string();
+        let result = self.query(&[("module", "block"), ("action", "getblocknobytime"), ("timestamp", &timestamp), ("closest", "before")]).await?;
+        result.as_str().and_then(|block| block.parse().ok()).ok_or_else(|| ExplorerError::ApiError(format!("unexpected block number {}", result)))
+    }
+
+    // Normal transactions to or from the address in the block range, oldest first
+    pub async fn account_transactions(&self, address: Address, start_block: u64, end_block: u64) -> Result<Vec<Value>, ExplorerError> {
+        self.account_list("txlist", address, start_block, end_block).await
+    }
+
+    // ERC20 transfers to or from the address in the block range, oldest first
+    pub async fn token_transfers(&self, address: Address, start_block: u64, end_block: u64) -> Result<Vec<Value>, ExplorerError> {
+        self.account_list("tokentx", address, start_block, end_block).await
+    }
+
+    async fn account_list(&self, action: &str, address: Address, start_block: u64, end_block: u64) -> Result<Vec<Value>, ExplorerError> {
+        let (address, start_block, end_block) = (format!("{:?}", address), start_block.to_string(), end_block.to_string());
+        let result = self
+            .query(&[
+                ("module", "account"),
+                ("action", action),
+                ("address", &address),
+                ("startblock", &start_block),
+                ("endblock", &end_block),
+                ("sort", "asc"),
+            ])
+            .await?;
+        Ok(result.as_array().cloned().unwrap_or_default())
+    }
+
+    // Whether the explorer has verified source code for the contract
+    pub async fn source_verified(&self, address: Address) -> Result<bool, ExplorerError> {
+        if let Some(verified) = cache().lock().unwrap().verified.get(&address) {
+            return Ok(*verified);
+        }
+        let result = self.contract_query("getsourcecode", address).await?;
+        let verified = result[0]["SourceCode"].as_str().map(|source| !source.is_empty()).unwrap_or(false);
+        cache().lock().unwrap().verified.insert(address, verified);
+        Ok(verified)
+    }
+
+    fn abi_cache_path(&self, address: Address) -> PathBuf {
+        self.abi_cache_dir.join(format!("{:?}.json", address))
+    }
+
+    // ABI for a contract we have no local ABI file for, cached in memory and on disk
+    pub async fn fetch_abi(&self, address: Address) -> Result<Option<ethabi::Contract>, ExplorerError> {
+        if let Some(abi) = cache().lock().unwrap().abis.get(&address) {
+            return Ok(abi.clone());
+        }
+
+        let path = self.abi_cache_path(address);
+        let abi_json = match fs::read_to_string(&path) {
+            Ok(abi_json) => Some(abi_json),
+            Err(_) => {
+                let result = self.contract_query("getabi", address).await?;
+                let abi_json = result.as_str().map(String::from);
+                if let Some(abi_json) = &abi_json {
+                    let _ = fs::create_dir_all(&self.abi_cache_dir);
+                    if let Err(e) = fs::write(&path, abi_json) {
+                        warn!("Failed to cache ABI for {:?}: {}", address, e);
+                    }
+                    info!("Fetched ABI for {:?} from the explorer", address);
+                }
+                abi_json
+            }
+        };
+
+        let abi = match abi_json {
+            Some(abi_json) => Some(ethabi::Contract::load(abi_json.as_bytes())?),
+            None => None,
+        };
+        cache().lock().unwrap().abis.insert(address, abi.clone());
+        Ok(abi)
+    }
+
+    // Name of the function a call's selector hits, from the explorer ABI of the target
+    pub async fn method_name(&self, address: Address, input: &[u8]) -> Result<Option<String>, ExplorerError> {
+        if input.len() < 4 {
+            return Ok(None);
+        }
+        let abi = match self.fetch_abi(address).await? {
+            Some(abi) => abi,
+            None => return Ok(None),
+        };
+        Ok(abi
+            .functions()
+            .find(|function| function.short_signature()[..] == input[..4])
+            .map(|function| function.name.clone()))
+    }
+}
+
+// Human-readable method for a call: the local method registry first, then the explorer ABI
+pub async fn describe_call(to: Address, input: &[u8]) -> Option<String> {
+    if let Some(method) = mempool_recorder::known_method(input) {
+        return Some(method.to_string());
+    }
+    let client = match ExplorerClient::from_config() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Explorer client unavailable: {}", e);
+            return None;
+        }
+    };
+    match client.method_name(to, input).await {
+        Ok(method) => method,
+        Err(e) => {
+            warn!("Explorer lookup for {:?} failed: {}", to, e);
+            None
+        }
+    }
+}
+
+// Custom error type for the explorer client
+#[derive(Error, Debug)]
+pub enum ExplorerError {
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] ethabi::Error),
+    #[error("Explorer API error: {0}")]
+    ApiError(String),
+    #[error("No explorer configured for chain {0}")]
+    UnsupportedChain(u64),
+}
+
+// Implement conversion for ExplorerError to Web3 error
+impl From<ExplorerError> for web3::Error {
+    fn from(error: ExplorerError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,295 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use redis::aio::MultiplexedConnection;
+use redis::streams::{StreamMaxlen, StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+use web3::transports::WebSocket;
+use web3::types::{Address, Bytes, Transaction, TransactionId, H256, U256};
+use web3::futures::StreamExt;
+use log::{error, info, warn};
+use thiserror::Error;
+use chrono::Utc;
+use tokio::time::{sleep, timeout, Duration};
+
+use crate::modules::providers;
+use crate::modules::ws_metrics;
+
+// Opportunities on the feed use the shared domain type
+pub use crate::modules::domain::Opportunity;
+
+// Load the shared feed configuration
+fn load_feed_config() -> Value {
+    let config_path = "config/feed_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read feed config file");
+    serde_json::from_str(&config_data).expect("Unable to parse feed config file")
+}
+
+// Whether strategies on this machine read pending transactions from Redis instead of their own subscription
+pub fn consume_pending_enabled() -> bool {
+    let config = load_feed_config();
+    config["enabled"].as_bool().unwrap_or(false) && config["consume_pending"].as_bool().unwrap_or(false)
+}
+
+pub fn publish_pending_enabled() -> bool {
+    let config = load_feed_config();
+    config["enabled"].as_bool().unwrap_or(false) && config["publish_pending"].as_bool().unwrap_or(false)
+}
+
+// A pending transaction as published to the feed, independent of which node saw it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingTx {
+    pub hash: H256,
+    pub from: Option<Address>,
+    pub to: Option<Address>,
+    pub nonce: U256,
+    pub value: U256,
+    pub gas: U256,
+    pub gas_price: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub input: Bytes,
+    pub first_seen_ms: i64,
+    pub source: String,
+}
+
+impl PendingTx {
+    pub fn from_transaction(tx: &Transaction, source: &str) -> Self {
+        PendingTx {
+            hash: tx.hash,
+            from: tx.from,
+            to: tx.to,
+            nonce: tx.nonce,
+            value: tx.value,
+            gas: tx.gas,
+            gas_price: tx.gas_price,
+            max_fee_per_gas: tx.max_fee_per_gas,
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+            input: tx.input.clone(),
+            first_seen_ms: Utc::now().timestamp_millis(),
+            source: source.to_string(),
+        }
+    }
+
+    // Rebuild the web3 transaction the strategies already know how to evaluate
+    pub fn to_transaction(&self) -> Transaction {
+        Transaction {
+            hash: self.hash,
+            from: self.from,
+            to: self.to,
+            nonce: self.nonce,
+            value: self.value,
+            gas: self.gas,
+            gas_price: self.gas_price,
+            max_fee_per_gas: self.max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+            input: self.input.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+async fn connect(config: &Value) -> Result<MultiplexedConnection, FeedError> {
+    let url = config["redis_url"].as_str().unwrap_or("redis://127.0.0.1/");
+    let client = redis::Client::open(url)?;
+    Ok(client.get_multiplexed_async_connection().await?)
+}
+
+fn stream_name(config: &Value, key: &str, default: &str) -> String {
+    config[key].as_str().unwrap_or(default).to_string()
+}
+
+// Appends events to the Redis streams, trimmed to roughly max_len entries
+pub struct FeedPublisher {
+    connection: MultiplexedConnection,
+    pending_stream: String,
+    opportunity_stream: String,
+    max_len: usize,
+}
+
+impl FeedPublisher {
+    pub async fn connect() -> Result<Self, FeedError> {
+        let config = load_feed_config();
+        Ok(FeedPublisher {
+            connection: connect(&config).await?,
+            pending_stream: stream_name(&config, "pending_stream", "taz:pending"),
+            opportunity_stream: stream_name(&config, "opportunity_stream", "taz:opportunities"),
+            max_len: config["max_len"].as_u64().unwrap_or(100_000) as usize,
+        })
+    }
+
+    async fn publish<T: Serialize>(&mut self, stream: String, event: &T) -> Result<(), FeedError> {
+        let payload = serde_json::to_string(event)?;
+        let _: String = self
+            .connection
+            .xadd_maxlen(stream, StreamMaxlen::Approx(self.max_len), "*", &[("payload", payload)])
+            .await?;
+        Ok(())
+    }
+
+    pub async fn publish_pending(&mut self, pending: &PendingTx) -> Result<(), FeedError> {
+        self.publish(self.pending_stream.clone(), pending).await
+    }
+
+    pub async fn publish_opportunity(&mut self, opportunity: &Opportunity) -> Result<(), FeedError> {
+        self.publish(self.opportunity_stream.clone(), opportunity).await
+    }
+}
+
+// Publish an opportunity when the feed is enabled; failures are logged, never fatal to detection
+pub async fn publish_opportunity(opportunity: Opportunity) {
+    let config = load_feed_config();
+    if !config["enabled"].as_bool().unwrap_or(false) || !config["publish_opportunities"].as_bool().unwrap_or(false) {
+        return;
+    }
+    let result = async {
+        let mut publisher = FeedPublisher::connect().await?;
+        publisher.publish_opportunity(&opportunity).await
+    };
+    if let Err(e) = result.await {
+        warn!("Failed to publish {} opportunity: {}", opportunity.strategy, e);
+    }
+}
+
+// Reads one stream through a consumer group, so several machines share the work and each event
+// is handled once; entries are acknowledged as soon as they are handed out
+pub struct FeedConsumer {
+    connection: MultiplexedConnection,
+    stream: String,
+    group: String,
+    consumer: String,
+    block_ms: usize,
+    batch_size: usize,
+}
+
+impl FeedConsumer {
+    async fn new(stream_key: &str, default_stream: &str, group: &str) -> Result<Self, FeedError> {
+        let config = load_feed_config();
+        let mut connection = connect(&config).await?;
+        let stream = stream_name(&config, stream_key, default_stream);
+
+        // Start new groups at the tail: stale opportunities are worse than none
+        let created: Result<(), redis::RedisError> = connection.xgroup_create_mkstream(&stream, group, "$").await;
+        if let Err(e) = created {
+            if e.code() != Some("BUSYGROUP") {
+                return Err(e.into());
+            }
+        }
+
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| String::from("localhost"));
+        Ok(FeedConsumer {
+            connection,
+            stream,
+            group: group.to_string(),
+            consumer: format!("{}-{}", host, std::process::id()),
+            block_ms: config["block_ms"].as_u64().unwrap_or(1000) as usize,
+            batch_size: config["batch_size"].as_u64().unwrap_or(100) as usize,
+        })
+    }
+
+    pub async fn pending(group: &str) -> Result<Self, FeedError> {
+        Self::new("pending_stream", "taz:pending", group).await
+    }
+
+    pub async fn opportunities(group: &str) -> Result<Self, FeedError> {
+        Self::new("opportunity_stream", "taz:opportunities", group).await
+    }
+
+    // Next batch of payloads, waiting up to block_ms; empty when nothing arrived
+    async fn next_payloads(&mut self) -> Result<Vec<String>, FeedError> {
+        let options = StreamReadOptions::default()
+            .group(&self.group, &self.consumer)
+            .block(self.block_ms)
+            .count(self.batch_size);
+        let reply: Option<StreamReadReply> = self.connection.xread_options(&[&self.stream], &[">"], &options).await?;
+
+        let entries: Vec<_> = reply.into_iter().flat_map(|reply| reply.keys).flat_map(|key| key.ids).collect();
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids: Vec<&str> = entries.iter().map(|entry| entry.id.as_str()).collect();
+        let _: u64 = self.connection.xack(&self.stream, &self.group, &ids).await?;
+        Ok(entries.iter().filter_map(|entry| entry.get::<String>("payload")).collect())
+    }
+
+    pub async fn next_pending(&mut self) -> Result<Vec<PendingTx>, FeedError> {
+        let payloads = self.next_payloads().await?;
+        Ok(payloads.iter().filter_map(|payload| serde_json::from_str(payload).ok()).collect())
+    }
+
+    pub async fn next_opportunities(&mut self) -> Result<Vec<Opportunity>, FeedError> {
+        let payloads = self.next_payloads().await?;
+        Ok(payloads.iter().filter_map(|payload| serde_json::from_str(payload).ok()).collect())
+    }
+}
+
+// Subscribe to the local node's pending transactions and publish each one, normalized, to the feed
+pub async fn run_pending_publisher() {
+    let config = load_feed_config();
+    let configured_url = config["websocket_url"].as_str().expect("WebSocket URL not found").to_string();
+    let source = config["source_name"].as_str().unwrap_or("local").to_string();
+    let max_silence = Duration::from_secs(config["max_silence_secs"].as_u64().unwrap_or(30));
+    let stream_name = "feed_publisher";
+
+    loop {
+        let mut publisher = match FeedPublisher::connect().await {
+            Ok(publisher) => publisher,
+            Err(e) => {
+                error!("Feed publisher failed to connect to Redis: {}", e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        let websocket_url = providers::websocket_url_for(stream_name, &configured_url);
+        let transport = match WebSocket::new(&websocket_url).await {
+            Ok(transport) => transport,
+            Err(e) => {
+                error!("Feed publisher failed to connect to {}: {}", websocket_url, e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        let web3 = web3::Web3::new(transport);
+        let mut pending = match web3.eth_subscribe().subscribe_new_pending_transactions().await {
+            Ok(pending) => pending,
+            Err(e) => {
+                error!("Feed publisher failed to subscribe: {}", e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        info!("Publishing pending transactions to {}", publisher.pending_stream);
+
+        loop {
+            let hash = match timeout(max_silence, pending.next()).await {
+                Ok(Some(Ok(hash))) => hash,
+                Ok(Some(Err(e))) => {
+                    error!("Feed publisher subscription error: {}", e);
+                    continue;
+                }
+                Ok(None) | Err(_) => {
+                    ws_metrics::record_reconnect(stream_name);
+                    break;
+                }
+            };
+            ws_metrics::record_event(stream_name, None);
+
+            if let Ok(Some(tx)) = web3.eth().transaction(TransactionId::Hash(hash)).await {
+                if let Err(e) = publisher.publish_pending(&PendingTx::from_transaction(&tx, &source)).await {
+                    error!("Failed to publish pending transaction {:?}: {}", hash, e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// Custom error type for the shared feed
+#[derive(Error, Debug)]
+pub enum FeedError {
+    #[error("Redis error: {0}")]
+    RedisError(#[from] redis::RedisError),
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
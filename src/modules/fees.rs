@@ -0,0 +1,225 @@
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use web3::ethabi::{self, Token};
+use web3::types::{Address, U256};
+use log::{error, info, warn};
+use chrono::Utc;
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::ens;
+use crate::modules::events;
+use crate::modules::execution::{Call, ExecutionBackend, ExecutionError};
+use crate::modules::providers::FailoverTransport;
+
+// Serializes ledger appends so accrual always sees the previous high-water mark
+static LEDGER_LOCK: Mutex<()> = Mutex::new(());
+
+// Load the performance fee configuration
+fn load_fees_config() -> Value {
+    let config_path = "config/fees_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read fees config file");
+    serde_json::from_str(&config_data).expect("Unable to parse fees config file")
+}
+
+fn ledger_path(config: &Value) -> String {
+    config["ledger_path"].as_str().unwrap_or("data/fee_ledger.jsonl").to_string()
+}
+
+fn read_ledger(config: &Value) -> Vec<Value> {
+    fs::read_to_string(ledger_path(config))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .collect()
+}
+
+fn append(config: &Value, record: &Value) -> Result<(), FeeError> {
+    let path = ledger_path(config);
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", record)?;
+    Ok(())
+}
+
+fn parse_i128(value: &Value) -> i128 {
+    value.as_str().and_then(|value| value.parse().ok()).unwrap_or(0)
+}
+
+// Totals derived from the fee ledger
+#[derive(Debug, Default)]
+pub struct FeeBalance {
+    pub cumulative_pnl: i128,
+    pub high_water_mark: i128,
+    pub accrued: i128,
+    pub transferred: i128,
+}
+
+impl FeeBalance {
+    // Fees accrued but not yet paid out to the operator
+    pub fn outstanding(&self) -> i128 {
+        self.accrued - self.transferred
+    }
+}
+
+fn balance(ledger: &[Value]) -> FeeBalance {
+    let mut balance = FeeBalance::default();
+    for record in ledger {
+        match record["kind"].as_str() {
+            Some("accrual") => {
+                balance.cumulative_pnl = parse_i128(&record["cumulative_pnl_wei"]);
+                balance.high_water_mark = parse_i128(&record["high_water_mark_wei"]);
+                balance.accrued += parse_i128(&record["fee_wei"]);
+            }
+            Some("transfer") => balance.transferred += parse_i128(&record["amount_wei"]),
+            _ => {}
+        }
+    }
+    balance
+}
+
+// Accrue the performance fee on a realized trade result. With a high-water mark the fee is only
+// charged on cumulative profit above the previous peak, so losses must be made back first
+pub fn accrue(strategy: &str, pnl_wei: i128) {
+    let config = load_fees_config();
+    if !config["enabled"].as_bool().unwrap_or(false) {
+        return;
+    }
+    let fee_bps = config["performance_fee_bps"].as_u64().unwrap_or(0) as i128;
+    let use_high_water_mark = config["high_water_mark"].as_bool().unwrap_or(true);
+
+    let _guard = LEDGER_LOCK.lock().unwrap();
+    let previous = balance(&read_ledger(&config));
+    let cumulative_pnl = previous.cumulative_pnl + pnl_wei;
+    let chargeable = if use_high_water_mark {
+        (cumulative_pnl - previous.high_water_mark).max(0)
+    } else {
+        pnl_wei.max(0)
+    };
+    let fee = chargeable * fee_bps / 10_000;
+    let high_water_mark = previous.high_water_mark.max(cumulative_pnl);
+
+    let record = json!({
+        "kind": "accrual",
+        "timestamp": Utc::now().to_rfc3339(),
+        "strategy": strategy,
+        "pnl_wei": pnl_wei.to_string(),
+        "fee_bps": fee_bps,
+        "fee_wei": fee.to_string(),
+        "cumulative_pnl_wei": cumulative_pnl.to_string(),
+        "high_water_mark_wei": high_water_mark.to_string(),
+    });
+    if let Err(e) = append(&config, &record) {
+        error!("Failed to record fee accrual: {}", e);
+    }
+}
+
+// Transfer the outstanding fee to the operator address in the fee token, recording the attempt
+pub async fn transfer_outstanding(web3: &web3::Web3<FailoverTransport>) -> Result<Option<U256>, FeeError> {
+    let config = load_fees_config();
+    let operator = config["operator_address"]
+        .as_str()
+        .and_then(ens::parse_address)
+        .ok_or(FeeError::MissingConfig("operator_address"))?;
+    let token: Address = config["fee_token"]
+        .as_str()
+        .and_then(ens::parse_address)
+        .ok_or(FeeError::MissingConfig("fee_token"))?;
+    let min_transfer = config["min_transfer_wei"].as_str().and_then(|value| value.parse::<i128>().ok()).unwrap_or(0);
+
+    let outstanding = balance(&read_ledger(&config)).outstanding();
+    if outstanding <= 0 || outstanding < min_transfer {
+        return Ok(None);
+    }
+    let amount = U256::from(outstanding as u128);
+
+    let erc20 = ethabi::Contract::load(&include_bytes!("abi/erc20_abi.json")[..])?;
+    let data = erc20.function("transfer")?.encode_input(&[Token::Address(operator), Token::Uint(amount)])?;
+    let backend = ExecutionBackend::from_config(web3)?;
+    let submitted = backend.execute_calls(web3, "fees", vec![Call { to: token, value: U256::zero(), data }]).await;
+
+    let _guard = LEDGER_LOCK.lock().unwrap();
+    match submitted {
+        Ok(tx_hash) => {
+            append(&config, &json!({
+                "kind": "transfer",
+                "timestamp": Utc::now().to_rfc3339(),
+                "operator": operator,
+                "token": token,
+                "amount_wei": amount.to_string(),
+                "tx_hash": tx_hash,
+            }))?;
+            events::emit("fee_transferred", json!({ "operator": operator, "token": token, "amount_wei": amount.to_string(), "tx_hash": tx_hash }));
+            info!("Transferred {} of accrued performance fee to {:?}: {:?}", amount, operator, tx_hash);
+            Ok(Some(amount))
+        }
+        Err(e) => {
+            append(&config, &json!({
+                "kind": "transfer_failed",
+                "timestamp": Utc::now().to_rfc3339(),
+                "operator": operator,
+                "token": token,
+                "amount_wei": amount.to_string(),
+                "error": e.to_string(),
+            }))?;
+            Err(e.into())
+        }
+    }
+}
+
+// Pay out accrued fees on the transfer interval
+pub async fn run_fee_transfers(web3: web3::Web3<FailoverTransport>) {
+    loop {
+        let config = load_fees_config();
+        if config["enabled"].as_bool().unwrap_or(false) {
+            if let Err(e) = transfer_outstanding(&web3).await {
+                warn!("Performance fee transfer failed: {}", e);
+            }
+        }
+        sleep(Duration::from_secs(config["transfer_interval_secs"].as_u64().unwrap_or(86400))).await;
+    }
+}
+
+// Fee balance and the most recent ledger entries for the dashboard
+pub fn summary(limit: usize) -> Value {
+    let config = load_fees_config();
+    let ledger = read_ledger(&config);
+    let totals = balance(&ledger);
+    json!({
+        "enabled": config["enabled"].as_bool().unwrap_or(false),
+        "performance_fee_bps": config["performance_fee_bps"],
+        "high_water_mark": config["high_water_mark"].as_bool().unwrap_or(true),
+        "operator_address": config["operator_address"],
+        "cumulative_pnl_wei": totals.cumulative_pnl.to_string(),
+        "high_water_mark_wei": totals.high_water_mark.to_string(),
+        "accrued_wei": totals.accrued.to_string(),
+        "transferred_wei": totals.transferred.to_string(),
+        "outstanding_wei": totals.outstanding().to_string(),
+        "recent": ledger.iter().rev().take(limit).collect::<Vec<_>>(),
+    })
+}
+
+// Custom error type for performance fee accounting
+#[derive(Error, Debug)]
+pub enum FeeError {
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+}
+
+// Implement conversion for FeeError to Web3 error
+impl From<FeeError> for web3::Error {
+    fn from(error: FeeError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -1,25 +1,22 @@
 use serde_json::Value;
-use std::fs;
 use web3::types::{U256, Address};
 use web3::contract::Contract;
 use log::{error, info};
 use thiserror::Error;
 use tokio::time::{sleep, Duration};
-use web3::transports::Http;
 use web3::contract::Options;
-use std::str::FromStr;
-
-// Load flashloan config
-fn load_flashloan_config() -> Value {
-    let config_path = "config/flashloan_config.json";
-    let config_data = std::fs::read_to_string(config_path).expect("Unable to read flashloan config file");
-    serde_json::from_str(&config_data).expect("Unable to parse flashloan config file")
-}
-
-// Convert string to Address
-fn str_to_address(address: &str) -> Address {
-    Address::from_str(address).unwrap()
-}
+use chrono::Utc;
+use async_trait::async_trait;
+use crate::modules::allocator;
+use crate::modules::config::{FlashloanConfig, StrategyConfig};
+use crate::modules::domain::{self, OpportunitySource};
+use crate::modules::execution::{self, Call, ExecutionBackend, ExecutionError, FlashloanPlan};
+use crate::modules::gas::Urgency;
+use crate::modules::inventory;
+use crate::modules::pricing::{self, PricingError};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::scan_interval::AdaptiveInterval;
+use crate::modules::strategy::{Opportunity, Strategy, StrategyError};
 
 // Dynamic loan calculation for flashloan opportunities
 pub fn calculate_dynamic_loan_amount(expected_profit: U256, gas_fee: U256, slippage: f64) -> U256 {
@@ -28,78 +25,165 @@ pub fn calculate_dynamic_loan_amount(expected_profit: U256, gas_fee: U256, slipp
     U256::from(max_loan_amount).saturating_sub(gas_fee)
 }
 
-// Profitability Tracking for flashloans
+// Profitability Tracking for flashloans (gas_fees in the borrowed asset, see pricing::gas_cost_in_token)
 pub fn is_profitable(profit: U256, gas_fees: U256) -> bool {
     profit > gas_fees
 }
 
 // Monitor liquidity pools for flashloan opportunities
 pub async fn scan_for_flashloan_opportunities(
-    web3: &web3::Web3<Http>,
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &FlashloanConfig,
     lending_pool: Address,
     check_interval: u64,
 ) {
+    let mut interval = AdaptiveInterval::from_config("Flashloan", &settings.to_value(), check_interval);
+
     loop {
-        // Retrieve liquidity data from the pool
-        if let Ok(available_liquidity) = get_liquidity_data(web3, lending_pool).await {
-            if available_liquidity > U256::from(1000000000000000000u64) {  // Example: 1 ETH liquidity
-                info!("Flashloan opportunity detected with sufficient liquidity");
+        let found = match find_flashloan(web3, settings, lending_pool).await {
+            Ok(Some((loan_amount, gas_fees))) => {
                 // Trigger the flashloan execution if profitable
-                let loan_amount = calculate_dynamic_loan_amount(U256::from(1000000000000000000u64), U256::from(300000), 0.01);
-                if is_profitable(loan_amount, U256::from(300000)) {
-                    if let Err(e) = execute_flashloan(web3, loan_amount, lending_pool).await {
+                if is_profitable(loan_amount, gas_fees) {
+                    if let Err(e) = execute_flashloan(web3, settings, loan_amount, lending_pool).await {
                         error!("Failed to execute flashloan: {:?}", e);
                     }
                 }
+                true
             }
-        }
+            Ok(None) => false,
+            Err(e) => {
+                error!("Unable to price flashloan gas in the borrowed asset: {}", e);
+                true
+            }
+        };
+
+        // Wait before next scan, sooner while opportunities keep appearing
+        sleep(interval.record(found)).await;
+    }
+}
 
-        // Wait before next scan
-        sleep(Duration::from_secs(check_interval)).await;
+// Loan size and its gas cost (in the borrowed asset) when the pool has enough liquidity for one
+async fn find_flashloan(
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &FlashloanConfig,
+    lending_pool: Address,
+) -> Result<Option<(U256, U256)>, FlashloanError> {
+    // Retrieve liquidity data from the pool
+    let available_liquidity = match get_liquidity_data(web3, lending_pool).await {
+        Ok(available_liquidity) => available_liquidity,
+        Err(_) => return Ok(None),
+    };
+    if available_liquidity <= U256::from(1000000000000000000u64) {  // Example: 1 ETH liquidity
+        return Ok(None);
     }
+    info!("Flashloan opportunity detected with sufficient liquidity");
+
+    // Gas is paid in ETH but the loan is denominated in the borrowed asset
+    let gas_units = U256::from(settings.extra.get("estimated_gas_units").and_then(|units| units.as_u64()).unwrap_or(450_000));
+    let gas_fees = match settings.asset() {
+        Some(asset) => pricing::gas_cost_in_token(web3, asset, gas_units, Urgency::for_strategy("flashloan")).await?,
+        None => return Err(FlashloanError::PricingError(PricingError::MissingConfig("asset_address"))),
+    };
+    let loan_amount = calculate_dynamic_loan_amount(U256::from(1000000000000000000u64), gas_fees, 0.01);
+    Ok(Some((loan_amount, gas_fees)))
 }
 
-// Execute the flashloan
+// Execute the flashloan: loan, trades and repayment are planned together and committed as one transaction
 pub async fn execute_flashloan(
-    web3: &web3::Web3<Http>,
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &FlashloanConfig,
     loaned_amount: U256,
     lending_pool: Address,
 ) -> Result<(), FlashloanError> {
-    let config = load_flashloan_config();
-    let asset: Address = config["asset_address"].as_str().unwrap().parse().expect("Invalid address");
-
-    let flashloan_contract = Contract::from_json(
-        web3.eth(),
-        lending_pool,
-        include_bytes!("../abi/aave_flashloan_abi.json")
-    ).expect("Invalid Aave flashloan ABI");
+    let asset = settings.asset().ok_or(FlashloanError::MissingAsset)?;
+    let receiver = settings.flashloan_contract_address;
+    let loaned_amount = allocator::cap_to_allocation(web3, "flashloan", asset, loaned_amount).await;
 
-    let loan_params = (
-        vec![asset],
-        vec![loaned_amount],
-        vec![0u8],  // Changed from u64 to u8
-        asset,
-        Vec::<u8>::new(),  // Changed from vec![].as_slice() to Vec::<u8>::new()
-        0u16
-    );
-
-    let result = flashloan_contract
-        .call("flashLoan", loan_params, Address::from_str("YOUR_ADDRESS").unwrap(), Options::default())
-        .await;
-
-    match result {
-        Ok(_) => {
-            info!("Flashloan executed successfully for amount: {:?}", loaned_amount);
-            Ok(())
+    let backend = ExecutionBackend::from_config(web3)?;
+    // Inventory the wallet already holds covers the trade without the loan premium, on backends
+    // that run both legs in one operation
+    if settings.use_inventory && backend.is_atomic() {
+        if let Some(_reservation) = inventory::reserve("flashloan", asset, loaned_amount) {
+            let holder = backend.recipient();
+            let calls = build_round_trip_calls(web3, settings, holder, asset, loaned_amount, 0).await?;
+            let submission = backend.execute_calls(web3, "flashloan", calls).await?;
+            info!("Round trip executed from inventory for amount: {:?} ({:?})", loaned_amount, submission);
+            if !backend.is_proposal() {
+                allocator::record_realized(web3, "flashloan", OpportunitySource::ScheduledScan, holder, &[asset], &[submission]).await;
+            }
+            return Ok(());
         }
+    }
+
+    let mut plan = FlashloanPlan::new(receiver, asset, loaned_amount, settings.flashloan_premium_bps);
+    for call in build_round_trip_calls(web3, settings, receiver, asset, loaned_amount, settings.flashloan_premium_bps).await? {
+        plan.push(call);
+    }
+
+    // Anything that can't be expressed as a single atomic payload is refused here, before any funds move
+    let payload = match plan.prepare(web3, &backend).await {
+        Ok(payload) => payload,
         Err(e) => {
-            error!("Flashloan execution failed: {:?}", e);
-            Err(FlashloanError::ExecutionFailed(e))
+            error!("Refusing flashloan from pool {:?}: {}", lending_pool, e);
+            return Err(FlashloanError::ExecutionError(e));
         }
+    };
+
+    let submission = payload.commit(web3, &backend, "flashloan").await?;
+    info!("Flashloan executed successfully for amount: {:?} ({:?})", loaned_amount, submission);
+    // What the receiver keeps of the asset after repaying the loan and premium
+    if !backend.is_proposal() {
+        allocator::record_realized(web3, "flashloan", OpportunitySource::ScheduledScan, receiver, &[asset], &[submission]).await;
     }
-}// Retry logic for flashloan execution
+    Ok(())
+}
+
+// Trades run inside the loan callback (or from inventory): asset -> intermediate on router A, then
+// back on router B
+async fn build_round_trip_calls(
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &FlashloanConfig,
+    receiver: Address,
+    asset: Address,
+    amount: U256,
+    premium_bps: u64,
+) -> Result<Vec<Call>, FlashloanError> {
+    let (router_a, router_b, intermediate) = match (settings.swap_router_a, settings.swap_router_b, settings.intermediate_token) {
+        (Some(a), Some(b), Some(token)) => (a, b, token),
+        _ => return Err(FlashloanError::MissingStrategy),
+    };
+    let slippage = settings.slippage_tolerance;
+    let deadline = U256::from(Utc::now().timestamp() as u64 + 120);
+
+    // Quote the first leg so the second leg's input is known when the plan is built
+    let router_contract = Contract::from_json(web3.eth(), router_a, include_bytes!("abi/uniswap_router_abi.json"))?;
+    let amounts_out: Vec<U256> = router_contract
+        .query("getAmountsOut", (amount, vec![asset, intermediate]), None, Options::default(), None)
+        .await?;
+    let quoted = amounts_out.last().copied().unwrap_or_default();
+    let first_leg_min = domain::min_out(quoted, Some(slippage));
+
+    // The second leg sells the whole quoted output, so nothing is left behind in the intermediate
+    // token, and is quoted for it with its own slippage bound. The legs run as one atomic plan: a
+    // first leg that returns less than quoted reverts the whole round trip
+    let router_b_contract = Contract::from_json(web3.eth(), router_b, include_bytes!("abi/uniswap_router_abi.json"))?;
+    let amounts_back: Vec<U256> = router_b_contract
+        .query("getAmountsOut", (quoted, vec![intermediate, asset]), None, Options::default(), None)
+        .await?;
+    let second_leg_min = domain::min_out(amounts_back.last().copied().unwrap_or_default(), Some(slippage));
+
+    // The second leg must also return at least the amount put in plus any loan premium, or the callback can't repay
+    let repay_min = amount + amount * U256::from(premium_bps) / U256::from(10_000u64);
+
+    let mut calls = execution::approve_and_swap_calls(router_a, vec![asset, intermediate], amount, first_leg_min, receiver, deadline)?;
+    calls.extend(execution::approve_and_swap_calls(router_b, vec![intermediate, asset], quoted, second_leg_min.max(repay_min), receiver, deadline)?);
+    Ok(calls)
+}
+
+// Retry logic for flashloan execution
 pub async fn execute_flashloan_with_retry(
-    web3: &web3::Web3<Http>,
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &FlashloanConfig,
     loaned_amount: U256,
     lending_pool: Address,
     max_retries: u8
@@ -108,7 +192,7 @@ pub async fn execute_flashloan_with_retry(
     let mut delay = 1;
 
     while attempts < max_retries {
-        let result = execute_flashloan(web3, loaned_amount, lending_pool).await;
+        let result = execute_flashloan(web3, settings, loaned_amount, lending_pool).await;
         match result {
             Ok(_) => return Ok(()),
             Err(e) => {
@@ -123,15 +207,48 @@ pub async fn execute_flashloan_with_retry(
     Err(FlashloanError::RetriesExceeded)
 }
 
+// Flash loans from the configured lending pool under the orchestrator
+pub struct FlashloanStrategy {
+    settings: FlashloanConfig,
+}
+
+impl FlashloanStrategy {
+    pub fn new(settings: FlashloanConfig) -> Self {
+        FlashloanStrategy { settings }
+    }
+}
+
+#[async_trait]
+impl Strategy for FlashloanStrategy {
+    fn name(&self) -> &'static str {
+        "flashloan"
+    }
+
+    fn config(&self) -> Value {
+        self.settings.to_value()
+    }
+
+    async fn scan(&self, web3: &web3::Web3<FailoverTransport>) -> Result<Vec<Opportunity>, StrategyError> {
+        match find_flashloan(web3, &self.settings, self.settings.lending_pool_address).await? {
+            Some((loan_amount, gas_fees)) if is_profitable(loan_amount, gas_fees) => Ok(vec![Opportunity::sized(loan_amount)]),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    async fn execute(&self, web3: &web3::Web3<FailoverTransport>, opportunity: Opportunity) -> Result<(), StrategyError> {
+        Ok(execute_flashloan_with_retry(web3, &self.settings, opportunity.amount, self.settings.lending_pool_address, 3).await?)
+    }
+}
+
 // Get liquidity data from the lending pool
 pub async fn get_liquidity_data(
-    web3: &web3::Web3<Http>,
+    web3: &web3::Web3<FailoverTransport>,
     lending_pool: Address
 ) -> Result<U256, FlashloanError> {
     let flashloan_contract = Contract::from_json(
         web3.eth(),
         lending_pool,
-        include_bytes!("../abi/aave_flashloan_abi.json")
+        include_bytes!("abi/aave_flashloan_abi.json")
     ).expect("Invalid Aave flashloan ABI");
 
     let result: U256 = flashloan_contract
@@ -149,10 +266,20 @@ pub enum FlashloanError {
     Web3Error(#[from] web3::Error),
     #[error("Contract error: {0}")]
     ContractError(#[from] web3::contract::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
     #[error("Execution failed")]
     ExecutionFailed(web3::contract::Error),
     #[error("Retries exceeded for flashloan")]
     RetriesExceeded,
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("swap_router_a, swap_router_b and intermediate_token must be configured for the flashloan strategy")]
+    MissingStrategy,
+    #[error("asset_address or weth_address must be configured for the flashloan strategy")]
+    MissingAsset,
+    #[error("Pricing error: {0}")]
+    PricingError(#[from] PricingError),
 }
 
 
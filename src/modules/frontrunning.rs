@@ -1,68 +1,122 @@
 use serde_json::Value;
-use std::fs;
-use web3::types::{U256, Transaction, Address};
+use web3::types::{BlockId, BlockNumber, TransactionId, U256, Transaction};
 use web3::contract::Contract;
 use log::{error, info};
 use thiserror::Error;
 use tokio::time::{sleep, Duration};
-use web3::transports::Http;
 use web3::contract::Options;
-use std::str::FromStr;
 use web3::ethabi::ethereum_types::H256;
-
-// Load frontrunning config
-fn load_frontrunning_config() -> Value {
-    let config_path = "config/frontrunning_config.json";
-    let config_data = std::fs::read_to_string(config_path).expect("Unable to read frontrunning config file");
-    serde_json::from_str(&config_data).expect("Unable to parse frontrunning config file")
-}
-
-// Convert string to Address
-fn str_to_address(address: &str) -> Address {
-    Address::from_str(address).unwrap()
-}
+use async_trait::async_trait;
+use crate::modules::allocator;
+use crate::modules::config::{FrontrunningConfig, StrategyConfig};
+use crate::modules::domain::OpportunitySource;
+use crate::modules::gas::{self, Urgency};
+use crate::modules::pricing;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::relay::{self, Bundle, BundleOutcome, RelayClient, RelayError};
+use crate::modules::sanctions;
+use crate::modules::scan_interval::AdaptiveInterval;
+use crate::modules::signer::{SignerError, TxSender};
+use crate::modules::size_buckets;
+use crate::modules::storage;
+use crate::modules::strategy::{Opportunity, Strategy, StrategyError};
 
 // Monitor the mempool for large transactions
 pub async fn monitor_mempool(
-    web3: &web3::Web3<Http>,
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &FrontrunningConfig,
     threshold_amount: U256,
     gas_fee_limit: U256,
     check_interval: u64
 ) {
+    let mut interval = AdaptiveInterval::from_config("Frontrunning", &settings.to_value(), check_interval);
+
     loop {
-        let pending_transactions = fetch_mempool_transactions(web3).await;
-
-        for transaction in pending_transactions {
-            let tx_value = U256::from(transaction.value);
-            
-            // Filter transactions above the threshold
-            if tx_value > threshold_amount {
-                let potential_profit = calculate_potential_profit(tx_value, gas_fee_limit);
-                
-                if is_profitable(potential_profit, gas_fee_limit) {
-                    info!("Profitable frontrunning opportunity detected: {:?}", transaction.hash);
-                    if let Err(e) = execute_frontrunning(web3, transaction).await {
-                        error!("Frontrunning execution failed: {:?}", e);
-                    }
+        let targets = find_targets(web3, settings, threshold_amount, gas_fee_limit).await;
+        let found = !targets.is_empty();
+
+        for target in targets {
+            if let Some(transaction) = target.transaction {
+                if let Err(e) = execute_frontrunning(web3, settings, transaction, target.amount, target.tip_per_gas).await {
+                    error!("Frontrunning execution failed: {:?}", e);
                 }
             }
         }
 
-        sleep(Duration::from_secs(check_interval)).await;
+        sleep(interval.record(found)).await;
+    }
+}
+
+// Pending transactions above the threshold worth front-running, each with its front-run size and tip
+async fn find_targets(
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &FrontrunningConfig,
+    threshold_amount: U256,
+    gas_fee_limit: U256,
+) -> Vec<Opportunity> {
+    let config = settings.to_value();
+    let gas_units = U256::from(config["estimated_gas_units"].as_u64().unwrap_or(250_000));
+    let buckets = size_buckets::from_config(&config);
+    let mut targets = Vec::new();
+
+    for transaction in fetch_mempool_transactions(web3).await {
+        let tx_value = transaction.value;
+
+        // Filter transactions above the threshold
+        if tx_value <= threshold_amount {
+            continue;
+        }
+        // Size, tip and profit threshold follow the target's size bucket when buckets are configured
+        let bucket = size_buckets::bucket_for(&buckets, tx_value);
+        if !buckets.is_empty() && bucket.is_none() {
+            continue;
+        }
+        let front_run_size = bucket.map_or(tx_value, |bucket| bucket.front_run_size(tx_value));
+        let potential_profit = calculate_potential_profit(front_run_size, gas_fee_limit);
+
+        // Profit here is in ETH, so the gas units are priced in wei at the current fees
+        let gas_cost = match pricing::gas_cost_wei(web3, gas_units, Urgency::for_strategy("frontrunning")).await {
+            Ok(gas_cost) => gas_cost,
+            Err(e) => {
+                error!("Unable to price frontrunning gas: {}", e);
+                continue;
+            }
+        };
+        let profitable = match bucket {
+            Some(bucket) => bucket.is_profitable(potential_profit, gas_cost, front_run_size),
+            None => is_profitable(potential_profit, gas_cost),
+        };
+        if !profitable {
+            continue;
+        }
+        let counterparties = [transaction.from, transaction.to].into_iter().flatten().collect::<Vec<_>>();
+        if let Some(screening) = sanctions::first_flagged(&counterparties, "frontrunning_target").await {
+            info!("Skipping target {:?}: counterparty {:?} is flagged ({})", transaction.hash, screening.address, screening.source);
+            continue;
+        }
+        info!("Profitable frontrunning opportunity detected: {:?}", transaction.hash);
+        // The bucket's tip is paid as extra priority fee spread over the gas used
+        let tip_per_gas = bucket.map_or(U256::zero(), |bucket| bucket.tip(potential_profit) / gas_units);
+        targets.push(Opportunity {
+            amount: front_run_size,
+            target: transaction.from,
+            transaction: Some(transaction),
+            tip_per_gas,
+            ..Default::default()
+        });
     }
+    targets
 }
 
 // Fetch pending transactions from the mempool
 pub async fn fetch_mempool_transactions(
-    web3: &web3::Web3<Http>
+    web3: &web3::Web3<FailoverTransport>
 ) -> Vec<Transaction> {
     let mut pending_txs = Vec::new();
-    if let Ok(block) = web3.eth().block(BlockId::Pending).await {
-        if let Some(block) = block {
-            for tx_hash in block.transactions {
-                if let Ok(Some(tx)) = web3.eth().transaction(tx_hash).await {
-                    pending_txs.push(tx);
-                }
+    if let Ok(Some(block)) = web3.eth().block(BlockId::Number(BlockNumber::Pending)).await {
+        for tx_hash in block.transactions {
+            if let Ok(Some(tx)) = web3.eth().transaction(TransactionId::Hash(tx_hash)).await {
+                pending_txs.push(tx);
             }
         }
     }
@@ -73,65 +127,94 @@ pub fn calculate_potential_profit(
     transaction_value: U256,
     gas_fee_limit: U256
 ) -> U256 {
-    let slippage_factor = 0.01;  // Example: 1% slippage
-    let potential_profit = transaction_value - (transaction_value * U256::from_f64(slippage_factor).unwrap());
+    // Example: 1% slippage
+    let potential_profit = transaction_value - transaction_value / 100;
     potential_profit.saturating_sub(gas_fee_limit)
 }
 
-// Check if the transaction is profitable based on gas fees and slippage
+// Check if the transaction is profitable based on gas fees (in wei, like the profit) and slippage
 pub fn is_profitable(profit: U256, gas_fees: U256) -> bool {
     profit > gas_fees
 }
 
 // Execute the frontrunning transaction
 pub async fn execute_frontrunning(
-    web3: &web3::Web3<Http>,
-    target_transaction: Transaction
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &FrontrunningConfig,
+    target_transaction: Transaction,
+    amount_in: U256,
+    tip_per_gas: U256
 ) -> Result<(), FrontrunningError> {
-    let config = load_frontrunning_config();
-    let token_in: Address = config["token_in"].as_str().unwrap().parse().expect("Invalid address");
-    let token_out: Address = config["token_out"].as_str().unwrap().parse().expect("Invalid address");
+    let (token_in, token_out) = (settings.token_in, settings.token_out);
+    let amount_in = allocator::cap_to_allocation(web3, "frontrunning", token_in, amount_in).await;
 
     let uniswap_router_contract = Contract::from_json(
         web3.eth(),
-        str_to_address(&config["uniswap_router_address"].as_str().unwrap()),
-        include_bytes!("../abi/uniswap_router_abi.json")
+        settings.uniswap_router_address,
+        include_bytes!("abi/uniswap_router_abi.json")
     ).expect("Invalid Uniswap router ABI");
 
-    let gas_price = U256::from(20000000000u64); // Example gas price (20 Gwei)
+    // Fees at the strategy's urgency, 20 Gwei if there is no fee history; the tip goes on both so
+    // it buys ordering for legacy and EIP-1559 transactions alike
+    let (max_fee, priority_fee) = match gas::suggest_fees(web3, Urgency::for_strategy("frontrunning")).await {
+        Ok(fees) => (fees.max_fee, fees.priority_fee),
+        Err(_) => (U256::from(20000000000u64), U256::zero()),
+    };
 
     let tx_hash = target_transaction.hash;
-    let trade_params = (vec![token_in, token_out], target_transaction.value, 1u64);
+    let trade_params = (vec![token_in, token_out], amount_in, 1u64);
+
+    let sender = TxSender::from_config(web3).await?;
+    let request = sender.contract_request(&uniswap_router_contract, "swapExactTokensForTokens", trade_params, Options::with(|opt| {
+        opt.gas_price = Some(max_fee + tip_per_gas);
+        opt.max_priority_fee_per_gas = Some(priority_fee + tip_per_gas);
+    }))?;
+
+    // Through the private relay the front-run and its target land together in one bundle or not
+    // at all, and the front-run is never visible in the public mempool
+    if relay::enabled() {
+        let bundle = Bundle::around(web3, &sender, "frontrunning", vec![request], tx_hash, Vec::new()).await?;
+        return match RelayClient::from_config()?.submit(web3, "frontrunning", &bundle).await? {
+            BundleOutcome::Included { block, tx_hashes } => {
+                info!("Frontrunning bundle for {:?} included in block {}", tx_hash, block);
+                allocator::record_realized(web3, "frontrunning", OpportunitySource::PublicMempool, sender.address(), &[token_in, token_out], &tx_hashes).await;
+                Ok(())
+            }
+            BundleOutcome::NotIncluded { .. } => Err(FrontrunningError::BundleNotIncluded(tx_hash)),
+        };
+    }
 
-    let result = uniswap_router_contract
-        .call("swapExactTokensForTokens", trade_params, "YOUR_ADDRESS".parse().unwrap(), Options::with(|opt| {
-            opt.gas_price = Some(gas_price);
-        }))
-        .await;
+    let result = sender.send("frontrunning", request).await;
 
     match result {
-        Ok(_) => {
+        Ok(sent) => {
+            storage::record_transaction("frontrunning", sent, "signed");
             info!("Frontrunning transaction executed successfully: {:?}", tx_hash);
+            // The bought token is marked to market at the current rate
+            allocator::record_realized(web3, "frontrunning", OpportunitySource::PublicMempool, sender.address(), &[token_in, token_out], &[sent]).await;
             Ok(())
         }
         Err(e) => {
             error!("Failed to execute frontrunning transaction: {:?}", e);
-            Err(FrontrunningError::ContractError(e))
+            Err(FrontrunningError::SignerError(e))
         }
     }
 }
 
 // Retry logic for frontrunning trades
 pub async fn execute_frontrunning_with_retry(
-    web3: &web3::Web3<Http>,
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &FrontrunningConfig,
     target_transaction: Transaction,
+    amount_in: U256,
+    tip_per_gas: U256,
     max_retries: u8
 ) -> Result<(), FrontrunningError> {
     let mut attempts = 0;
     let mut delay = 1;
 
     while attempts < max_retries {
-        let result = execute_frontrunning(web3, target_transaction).await;
+        let result = execute_frontrunning(web3, settings, target_transaction.clone(), amount_in, tip_per_gas).await;
         match result {
             Ok(_) => return Ok(()),
             Err(e) => {
@@ -146,6 +229,37 @@ pub async fn execute_frontrunning_with_retry(
     Err(FrontrunningError::RetriesExceeded)
 }
 
+// Front-running pending transactions under the orchestrator
+pub struct FrontrunningStrategy {
+    settings: FrontrunningConfig,
+}
+
+impl FrontrunningStrategy {
+    pub fn new(settings: FrontrunningConfig) -> Self {
+        FrontrunningStrategy { settings }
+    }
+}
+
+#[async_trait]
+impl Strategy for FrontrunningStrategy {
+    fn name(&self) -> &'static str {
+        "frontrunning"
+    }
+
+    fn config(&self) -> Value {
+        self.settings.to_value()
+    }
+
+    async fn scan(&self, web3: &web3::Web3<FailoverTransport>) -> Result<Vec<Opportunity>, StrategyError> {
+        Ok(find_targets(web3, &self.settings, self.settings.min_transaction_size, U256::from(self.settings.gas_limit)).await)
+    }
+
+    async fn execute(&self, web3: &web3::Web3<FailoverTransport>, opportunity: Opportunity) -> Result<(), StrategyError> {
+        let transaction = opportunity.transaction.ok_or(StrategyError::IncompleteOpportunity("pending transaction"))?;
+        Ok(execute_frontrunning_with_retry(web3, &self.settings, transaction, opportunity.amount, opportunity.tip_per_gas, 3).await?)
+    }
+}
+
 // Custom error type for frontrunning
 #[derive(Error, Debug)]
 pub enum FrontrunningError {
@@ -153,6 +267,12 @@ pub enum FrontrunningError {
     Web3Error(#[from] web3::Error),
     #[error("Contract error: {0}")]
     ContractError(#[from] web3::contract::Error),
+    #[error("Signer error: {0}")]
+    SignerError(#[from] SignerError),
+    #[error("Relay error: {0}")]
+    RelayError(#[from] RelayError),
+    #[error("Bundle front-running {0:?} was not included")]
+    BundleNotIncluded(H256),
     #[error("Retries exceeded for frontrunning")]
     RetriesExceeded,
 }
@@ -0,0 +1,209 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::sync::Arc;
+use web3::types::{Address, U256};
+use log::info;
+use thiserror::Error;
+
+use crate::modules::allocator;
+use crate::modules::arbitrage;
+use crate::modules::config::{ConfigError, GlobalConfig};
+use crate::modules::coordination;
+use crate::modules::ens;
+use crate::modules::gas::Urgency;
+use crate::modules::inventory;
+use crate::modules::path_finder;
+use crate::modules::pricing::{self, PricingError};
+use crate::modules::providers::{FailoverTransport, ProviderError, ProviderPool};
+use crate::modules::size_buckets;
+use crate::modules::strategy::{self, Strategy, StrategyError};
+
+// Load the global configuration (the manual trigger threshold lives there)
+fn load_global_config() -> Value {
+    let config_path = "config/global_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read global config file");
+    serde_json::from_str(&config_data).expect("Unable to parse global config file")
+}
+
+// Manual triggers whose funds at risk exceed this (wei) need explicit confirmation
+pub fn confirm_above() -> U256 {
+    load_global_config()["manual_confirm_above_wei"]
+        .as_str()
+        .and_then(|amount| U256::from_dec_str(amount).ok())
+        .unwrap_or_else(|| U256::exp10(18))
+}
+
+// The most one run of a strategy can put at stake, in wei: the flash loan it may take, the held
+// inventory it may spend and the gas it may burn at its gas limit
+#[derive(Clone, Debug, Serialize)]
+pub struct FundsAtRisk {
+    pub strategy: String,
+    // Token the position is taken in; None when the config sizes it in ETH
+    pub asset: Option<Address>,
+    pub size: U256,
+    pub flash_loan_wei: U256,
+    pub inventory_wei: U256,
+    pub gas_wei: U256,
+    pub total_wei: U256,
+    pub confirm_above_wei: U256,
+    pub requires_confirmation: bool,
+}
+
+fn amount(value: &Value) -> Option<U256> {
+    value.as_u64().map(U256::from).or_else(|| value.as_str().and_then(|amount| U256::from_dec_str(amount).ok()))
+}
+
+// Positions a strategy's config allows one pass to take: (asset, size in the asset, whether it is
+// borrowed). The estimate counts the largest in wei; strategies without a configured size only risk gas
+fn positions(name: &str, config: &Value) -> Vec<(Option<Address>, U256, bool)> {
+    let address = |key: &str| config[key].as_str().and_then(ens::parse_address);
+    match name {
+        // Borrowed, unless the wallet holds enough to trade from inventory
+        "flashloan" => {
+            let asset = address("asset_address").or_else(|| address("weth_address"));
+            match (asset, amount(&config["flashloan_amount"])) {
+                (Some(asset), Some(size)) => {
+                    let from_inventory = config["use_inventory"].as_bool().unwrap_or(true) && inventory::available(asset) >= size;
+                    vec![(Some(asset), size, !from_inventory)]
+                }
+                _ => Vec::new(),
+            }
+        }
+        // With the path finder on, a pass may run a cycle from any start token at its start amount
+        "arbitrage" => {
+            if path_finder::enabled() {
+                let start_amounts: Vec<(Option<Address>, U256, bool)> =
+                    path_finder::start_amounts().into_iter().map(|(token, size)| (Some(token), size, false)).collect();
+                if !start_amounts.is_empty() {
+                    return start_amounts;
+                }
+            }
+            amount(&config["quote_amount"]).map(|size| (address("arbitrage_token_a"), size, false)).into_iter().collect()
+        }
+        // The largest bucket's front-run, in ETH
+        "sandwich" | "frontrunning" => {
+            size_buckets::from_config(config).iter().map(|bucket| bucket.max_front_run).max().map(|largest| (None, largest, false)).into_iter().collect()
+        }
+        // The flash loan each trade takes, sized like execute_hft does before the allocator caps it
+        "hft" => match (address("asset"), amount(&config["expected_profit"]), amount(&config["gas_fee"])) {
+            (Some(asset), Some(expected_profit), Some(gas_fee)) => {
+                let slippage = config["slippage_tolerance"].as_f64().unwrap_or(0.0);
+                vec![(Some(asset), arbitrage::calculate_dynamic_loan_amount(expected_profit, gas_fee, slippage), true)]
+            }
+            _ => Vec::new(),
+        },
+        // The debt repaid is flash loaned and depends on the borrower; the allocator caps it at the
+        // strategy's allocation (in wei)
+        "liquidation" => vec![(None, allocator::allocation_for("liquidation"), true)],
+        _ => Vec::new(),
+    }
+}
+
+// Funds at risk for one run of a strategy, from its config at current gas prices
+pub async fn estimate(web3: &web3::Web3<FailoverTransport>, strategy: &dyn Strategy) -> Result<FundsAtRisk, FundsAtRiskError> {
+    let name = strategy.name();
+    let config = strategy.config();
+    let gas_units = config["max_gas_limit"].as_u64().or_else(|| config["estimated_gas_units"].as_u64()).unwrap_or(500_000);
+    let gas_wei = pricing::gas_cost_wei(web3, U256::from(gas_units), Urgency::for_strategy(name)).await?;
+
+    let (mut asset, mut size, mut borrowed, mut size_wei) = (None, U256::zero(), false, U256::zero());
+    for (position_asset, position_size, position_borrowed) in positions(name, &config) {
+        let position_wei = match position_asset {
+            Some(token) if !position_size.is_zero() => pricing::token_to_wei(web3, token, position_size).await?,
+            _ => position_size,
+        };
+        if position_wei >= size_wei {
+            (asset, size, borrowed, size_wei) = (position_asset, position_size, position_borrowed, position_wei);
+        }
+    }
+    let (flash_loan_wei, inventory_wei) = if borrowed { (size_wei, U256::zero()) } else { (U256::zero(), size_wei) };
+    let total_wei = flash_loan_wei.saturating_add(inventory_wei).saturating_add(gas_wei);
+    let confirm_above_wei = confirm_above();
+    Ok(FundsAtRisk {
+        strategy: name.to_string(),
+        asset,
+        size,
+        flash_loan_wei,
+        inventory_wei,
+        gas_wei,
+        total_wei,
+        confirm_above_wei,
+        requires_confirmation: total_wei > confirm_above_wei,
+    })
+}
+
+// Build a strategy by name from the global config and estimate what a manual run of it risks
+pub async fn estimate_strategy(web3: &web3::Web3<FailoverTransport>, name: &str) -> Result<(Arc<dyn Strategy>, FundsAtRisk), FundsAtRiskError> {
+    let strategy = strategy::build(name, &GlobalConfig::load()?)?.ok_or_else(|| FundsAtRiskError::UnknownStrategy(name.to_string()))?;
+    let risk = estimate(web3, strategy.as_ref()).await?;
+    Ok((strategy, risk))
+}
+
+fn eth(wei: U256) -> String {
+    format!("{:.4} ETH", wei.as_u128() as f64 / 1e18)
+}
+
+// `taz run <strategy> [--confirm]`: show what one scan-and-execute pass of a strategy puts at risk and
+// run it, refusing without --confirm when that is above manual_confirm_above_wei
+pub async fn run_trigger_command(global_config: Value, args: Vec<String>) -> Result<(), FundsAtRiskError> {
+    let name = args.first().filter(|name| !name.starts_with("--")).ok_or(FundsAtRiskError::Usage)?;
+    let confirmed = args.iter().any(|arg| arg == "--confirm");
+
+    let default_url = format!(
+        "https://{}.infura.io/v3/{}",
+        global_config["network"].as_str().unwrap_or("mainnet"),
+        global_config["infura_project_id"].as_str().unwrap_or_default()
+    );
+    let web3 = Arc::new(ProviderPool::from_config(&default_url)?).web3_for(name)?;
+    ens::resolve_config_names(&web3).await;
+
+    let (strategy, risk) = estimate_strategy(&web3, name).await?;
+    println!("Funds at risk for one {} run:", name);
+    println!("  flash loan  {}", eth(risk.flash_loan_wei));
+    println!("  inventory   {}", eth(risk.inventory_wei));
+    println!("  gas         {}", eth(risk.gas_wei));
+    println!("  total       {} (confirmation above {})", eth(risk.total_wei), eth(risk.confirm_above_wei));
+    if risk.requires_confirmation && !confirmed {
+        return Err(FundsAtRiskError::ConfirmationRequired(risk.total_wei, risk.confirm_above_wei));
+    }
+
+    // A manual run sends like the bot does, so it has to take the wallet lock first
+    if !coordination::start().await {
+        return Err(FundsAtRiskError::ObserverMode);
+    }
+
+    info!("Manual {} run from the CLI with {} at risk", name, eth(risk.total_wei));
+    let found = strategy::run_once(strategy.as_ref(), &web3).await?;
+    println!("{} run finished: {} opportunities", name, found);
+    Ok(())
+}
+
+// Custom error type for funds-at-risk checks on manual triggers
+#[derive(Error, Debug)]
+pub enum FundsAtRiskError {
+    #[error("Pricing error: {0}")]
+    PricingError(#[from] PricingError),
+    #[error("Config error: {0}")]
+    ConfigError(#[from] ConfigError),
+    #[error("Provider error: {0}")]
+    ProviderError(#[from] ProviderError),
+    #[error("Strategy error: {0}")]
+    StrategyError(#[from] StrategyError),
+    #[error("Unknown strategy: {0}")]
+    UnknownStrategy(String),
+    #[error("{0} wei at risk is above the {1} wei confirmation threshold; pass --confirm to run anyway")]
+    ConfirmationRequired(U256, U256),
+    #[error("Another instance holds the wallet lock; stop it or run this from that instance")]
+    ObserverMode,
+    #[error("Usage: taz run <strategy> [--confirm]")]
+    Usage,
+}
+
+// Implement conversion for FundsAtRiskError to Web3 error
+impl From<FundsAtRiskError> for web3::Error {
+    fn from(error: FundsAtRiskError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,242 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use web3::types::{BlockNumber, U256};
+use thiserror::Error;
+
+use crate::modules::builders;
+use crate::modules::events;
+use crate::modules::providers::FailoverTransport;
+
+const GWEI: f64 = 1e9;
+
+// Load the gas pricing configuration
+fn load_gas_config() -> Value {
+    let config_path = "config/gas_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read gas config file");
+    serde_json::from_str(&config_data).expect("Unable to parse gas config file")
+}
+
+// How soon a transaction has to land
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Urgency {
+    // Can wait several blocks for a cheap slot
+    Low,
+    Normal,
+    High,
+    // Worthless unless it lands in the next block
+    Immediate,
+}
+
+const URGENCIES: [Urgency; 4] = [Urgency::Low, Urgency::Normal, Urgency::High, Urgency::Immediate];
+
+impl Urgency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Urgency::Low => "low",
+            Urgency::Normal => "normal",
+            Urgency::High => "high",
+            Urgency::Immediate => "immediate",
+        }
+    }
+
+    // Urgency of a strategy's transactions from strategy_urgency, normal when it isn't listed
+    pub fn for_strategy(strategy: &str) -> Self {
+        let config = load_gas_config();
+        let configured = config["strategy_urgency"][strategy].as_str().unwrap_or("normal");
+        URGENCIES.into_iter().find(|urgency| urgency.as_str() == configured).unwrap_or(Urgency::Normal)
+    }
+
+    fn index(&self) -> usize {
+        URGENCIES.iter().position(|urgency| urgency == self).unwrap_or(1)
+    }
+
+    // Priority fee percentile paid by recent transactions that this urgency matches
+    fn percentile(&self, config: &Value) -> f64 {
+        let default = [10.0, 50.0, 75.0, 95.0][self.index()];
+        config["priority_percentiles"][self.as_str()].as_f64().unwrap_or(default)
+    }
+
+    // Blocks of maximum base fee increases the max fee covers, so the transaction stays valid while
+    // it waits for inclusion
+    fn headroom_blocks(&self, config: &Value) -> i32 {
+        let default = [6, 3, 2, 1][self.index()];
+        config["headroom_blocks"][self.as_str()].as_i64().unwrap_or(default) as i32
+    }
+}
+
+// Recent blocks from eth_feeHistory, oldest first
+#[derive(Clone, Debug)]
+struct FeeHistory {
+    newest_block: u64,
+    base_fees: Vec<U256>,
+    gas_used_ratios: Vec<f64>,
+    // Per block, the priority fee at each urgency's percentile
+    rewards: Vec<Vec<U256>>,
+}
+
+fn history() -> &'static Mutex<Option<(Instant, FeeHistory)>> {
+    static HISTORY: OnceLock<Mutex<Option<(Instant, FeeHistory)>>> = OnceLock::new();
+    HISTORY.get_or_init(|| Mutex::new(None))
+}
+
+// Fee history over history_blocks, refetched once it is older than refresh_ms
+async fn fee_history(web3: &web3::Web3<FailoverTransport>, config: &Value) -> Result<FeeHistory, GasError> {
+    let refresh = Duration::from_millis(config["refresh_ms"].as_u64().unwrap_or(1000));
+    if let Some((at, history)) = history().lock().unwrap().as_ref() {
+        if at.elapsed() < refresh {
+            return Ok(history.clone());
+        }
+    }
+
+    let fetched = fetch_fee_history(web3, config, BlockNumber::Latest).await?;
+    *history().lock().unwrap() = Some((Instant::now(), fetched.clone()));
+    // Next block's fees in wei, for rules and subscribers watching gas spikes
+    let normal = fetched.suggestion(config, Urgency::Normal);
+    events::emit("gas_fees", json!({
+        "block": normal.block,
+        "base_fee": normal.base_fee.low_u64(),
+        "priority_fee": normal.priority_fee.low_u64(),
+        "gas_price": normal.base_fee.saturating_add(normal.priority_fee).low_u64(),
+    }));
+    Ok(fetched)
+}
+
+// history_blocks of eth_feeHistory ending at the newest block
+async fn fetch_fee_history(web3: &web3::Web3<FailoverTransport>, config: &Value, newest: BlockNumber) -> Result<FeeHistory, GasError> {
+    let blocks = config["history_blocks"].as_u64().unwrap_or(20).max(1);
+    let percentiles: Vec<f64> = URGENCIES.iter().map(|urgency| urgency.percentile(config)).collect();
+    let raw = web3.eth().fee_history(U256::from(blocks), newest, Some(percentiles)).await?;
+
+    // base_fee_per_gas carries one entry past the newest block; keep only the blocks that exist
+    let mut base_fees = raw.base_fee_per_gas;
+    base_fees.truncate(raw.gas_used_ratio.len());
+    if base_fees.is_empty() {
+        return Err(GasError::EmptyHistory);
+    }
+    let oldest_block = match raw.oldest_block {
+        BlockNumber::Number(number) => number.as_u64(),
+        _ => 0,
+    };
+    let newest_block = oldest_block + base_fees.len() as u64 - 1;
+    Ok(FeeHistory {
+        newest_block,
+        base_fees,
+        gas_used_ratios: raw.gas_used_ratio,
+        rewards: raw.reward.unwrap_or_default(),
+    })
+}
+
+// EIP-1559: the base fee moves by up to 1/8 towards keeping blocks half full
+fn next_base_fee(base_fee: U256, gas_used_ratio: f64) -> U256 {
+    let base = base_fee.as_u128() as f64;
+    U256::from((base * (1.0 + (gas_used_ratio - 0.5) / 0.5 / 8.0)).max(0.0) as u128)
+}
+
+impl FeeHistory {
+    // Next block's base fee, from the newest block's base fee and how full it was
+    fn predicted_base_fee(&self) -> U256 {
+        let newest = self.base_fees.len() - 1;
+        next_base_fee(self.base_fees[newest], self.gas_used_ratios.get(newest).copied().unwrap_or(0.5))
+    }
+
+    // Median over the window of the priority fee paid at the urgency's percentile, so a single
+    // block's spike doesn't set the tip
+    fn priority_fee(&self, urgency: Urgency) -> Option<U256> {
+        let mut fees: Vec<U256> = self.rewards.iter().filter_map(|block| block.get(urgency.index()).copied()).collect();
+        if fees.is_empty() {
+            return None;
+        }
+        fees.sort();
+        Some(fees[fees.len() / 2])
+    }
+
+    fn suggestion(&self, config: &Value, urgency: Urgency) -> FeeSuggestion {
+        let base_fee = self.predicted_base_fee();
+        let floor = U256::from((config["min_priority_fee_gwei"].as_f64().unwrap_or(0.01) * GWEI) as u128);
+        let percentile_fee = self.priority_fee(urgency).unwrap_or(floor).max(floor);
+        // Scaled by the multiplier tuned from our inclusion rate across builders
+        let priority_fee = U256::from((percentile_fee.as_u128() as f64 * builders::tip_multiplier()) as u128);
+        let headroom = 1.125f64.powi(urgency.headroom_blocks(config));
+        let max_fee = U256::from((base_fee.as_u128() as f64 * headroom) as u128) + priority_fee;
+        FeeSuggestion { urgency, block: self.newest_block + 1, base_fee, priority_fee, max_fee }
+    }
+}
+
+// Fees for a transaction aimed at the next block
+#[derive(Clone, Copy, Debug)]
+pub struct FeeSuggestion {
+    pub urgency: Urgency,
+    pub block: u64,
+    // Predicted base fee of that block
+    pub base_fee: U256,
+    pub priority_fee: U256,
+    // Covers the base fee rising for the urgency's headroom blocks, plus the priority fee
+    pub max_fee: U256,
+}
+
+impl FeeSuggestion {
+    // Expected cost of gas_units when included at the predicted base fee; profitability checks
+    // compare against this, while max_fee only bounds what can be charged
+    pub fn cost(&self, gas_units: U256) -> U256 {
+        gas_units.saturating_mul(self.base_fee.saturating_add(self.priority_fee))
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "urgency": self.urgency.as_str(),
+            "block": self.block,
+            "base_fee_gwei": self.base_fee.as_u128() as f64 / GWEI,
+            "priority_fee_gwei": self.priority_fee.as_u128() as f64 / GWEI,
+            "max_fee_gwei": self.max_fee.as_u128() as f64 / GWEI,
+        })
+    }
+}
+
+// Base fee, priority fee and max fee for a transaction of the given urgency
+pub async fn suggest_fees(web3: &web3::Web3<FailoverTransport>, urgency: Urgency) -> Result<FeeSuggestion, GasError> {
+    let config = load_gas_config();
+    let history = fee_history(web3, &config).await?;
+    Ok(history.suggestion(&config, urgency))
+}
+
+// The fees the bot would have chosen for the block after a historical one, for backtests. Uses the
+// current builder tip multiplier, which has no history of its own
+pub async fn suggest_fees_at(web3: &web3::Web3<FailoverTransport>, urgency: Urgency, block: u64) -> Result<FeeSuggestion, GasError> {
+    let config = load_gas_config();
+    let history = fetch_fee_history(web3, &config, BlockNumber::Number(block.into())).await?;
+    Ok(history.suggestion(&config, urgency))
+}
+
+// Tracked base fees and the current suggestion for every urgency, for the dashboard
+pub fn snapshot() -> Value {
+    let config = load_gas_config();
+    match history().lock().unwrap().as_ref() {
+        Some((at, history)) => json!({
+            "newest_block": history.newest_block,
+            "base_fees_gwei": history.base_fees.iter().map(|fee| fee.as_u128() as f64 / GWEI).collect::<Vec<_>>(),
+            "gas_used_ratios": history.gas_used_ratios,
+            "suggestions": URGENCIES.iter().map(|urgency| history.suggestion(&config, *urgency).to_json()).collect::<Vec<_>>(),
+            "age_ms": at.elapsed().as_millis() as u64,
+        }),
+        None => json!(null),
+    }
+}
+
+// Custom error type for gas pricing
+#[derive(Error, Debug)]
+pub enum GasError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("eth_feeHistory returned no blocks")]
+    EmptyHistory,
+}
+
+// Implement conversion for GasError to Web3 error
+impl From<GasError> for web3::Error {
+    fn from(error: GasError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
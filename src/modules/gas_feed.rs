@@ -0,0 +1,298 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use web3::types::{BlockId, BlockNumber, U256};
+use log::warn;
+use thiserror::Error;
+
+use crate::modules::builders;
+use crate::modules::providers::FailoverTransport;
+
+const GWEI: f64 = 1e9;
+
+// Load the gas feed configuration
+fn load_gas_feed_config() -> Value {
+    let config_path = "config/gas_feed_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read gas feed config file");
+    serde_json::from_str(&config_data).expect("Unable to parse gas feed config file")
+}
+
+// One source's view of the next block's base fee and the priority fee needed to get in
+#[derive(Clone, Debug)]
+pub struct SourceQuote {
+    pub source: String,
+    pub base_fee: U256,
+    pub priority_fee: U256,
+}
+
+// Consensus over the sources that agreed, plus which sources were dropped as outliers
+#[derive(Clone, Debug)]
+pub struct GasEstimate {
+    pub base_fee: U256,
+    pub priority_fee: U256,
+    pub max_fee: U256,
+    pub quotes: Vec<SourceQuote>,
+    pub rejected: Vec<String>,
+}
+
+impl GasEstimate {
+    pub fn to_json(&self) -> Value {
+        let quotes: Vec<Value> = self
+            .quotes
+            .iter()
+            .map(|quote| json!({
+                "source": quote.source,
+                "base_fee_gwei": quote.base_fee.as_u128() as f64 / GWEI,
+                "priority_fee_gwei": quote.priority_fee.as_u128() as f64 / GWEI,
+            }))
+            .collect();
+        json!({
+            "base_fee_gwei": self.base_fee.as_u128() as f64 / GWEI,
+            "priority_fee_gwei": self.priority_fee.as_u128() as f64 / GWEI,
+            "max_fee_gwei": self.max_fee.as_u128() as f64 / GWEI,
+            "quotes": quotes,
+            "rejected": self.rejected,
+        })
+    }
+}
+
+fn cache() -> &'static Mutex<Option<(Instant, GasEstimate)>> {
+    static CACHE: OnceLock<Mutex<Option<(Instant, GasEstimate)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+fn gwei(value: f64) -> U256 {
+    U256::from((value * GWEI) as u128)
+}
+
+// Next base fee and the configured reward percentile from eth_feeHistory
+async fn node_fee_history(web3: &web3::Web3<FailoverTransport>, config: &Value) -> Result<SourceQuote, GasFeedError> {
+    let blocks = config["fee_history_blocks"].as_u64().unwrap_or(10);
+    let percentile = config["priority_percentile"].as_f64().unwrap_or(50.0);
+    let history = web3.eth().fee_history(U256::from(blocks), BlockNumber::Latest, Some(vec![percentile])).await?;
+
+    // The last base fee entry is the one for the block after the newest
+    let base_fee = history.base_fee_per_gas.last().copied().ok_or(GasFeedError::EmptySource("node_fee_history"))?;
+    let mut rewards: Vec<U256> = history.reward.unwrap_or_default().into_iter().filter_map(|block| block.first().copied()).collect();
+    if rewards.is_empty() {
+        return Err(GasFeedError::EmptySource("node_fee_history"));
+    }
+    rewards.sort();
+    Ok(SourceQuote { source: String::from("node_fee_history"), base_fee, priority_fee: rewards[rewards.len() / 2] })
+}
+
+// Blocknative's next-block estimate at the configured confidence
+async fn blocknative(config: &Value) -> Result<SourceQuote, GasFeedError> {
+    let api_key = config["blocknative_api_key"].as_str().ok_or(GasFeedError::MissingConfig("blocknative_api_key"))?;
+    let url = config["blocknative_url"].as_str().unwrap_or("https://api.blocknative.com/gasprices/blockprices");
+    let confidence = config["blocknative_confidence"].as_u64().unwrap_or(90);
+
+    let response: Value = reqwest::Client::new()
+        .get(url)
+        .query(&[("confidenceLevels", confidence.to_string())])
+        .header("Authorization", api_key)
+        .timeout(Duration::from_millis(config["source_timeout_ms"].as_u64().unwrap_or(1500)))
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    let block = &response["blockPrices"][0];
+    let base_fee = block["baseFeePerGas"].as_f64().ok_or(GasFeedError::EmptySource("blocknative"))?;
+    let priority_fee = block["estimatedPrices"][0]["maxPriorityFeePerGas"].as_f64().ok_or(GasFeedError::EmptySource("blocknative"))?;
+    Ok(SourceQuote { source: String::from("blocknative"), base_fee: gwei(base_fee), priority_fee: gwei(priority_fee) })
+}
+
+// Priority fees actually paid by transactions in the latest block
+async fn included_transactions(web3: &web3::Web3<FailoverTransport>, config: &Value) -> Result<SourceQuote, GasFeedError> {
+    let percentile = config["included_tx_percentile"].as_f64().unwrap_or(0.5);
+    let block = web3
+        .eth()
+        .block_with_txs(BlockId::Number(BlockNumber::Latest))
+        .await?
+        .ok_or(GasFeedError::EmptySource("included_transactions"))?;
+    let block_base_fee = block.base_fee_per_gas.unwrap_or_default();
+
+    let mut tips: Vec<U256> = block
+        .transactions
+        .iter()
+        .map(|tx| match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+            (Some(max_fee), Some(max_tip)) => max_tip.min(max_fee.saturating_sub(block_base_fee)),
+            _ => tx.gas_price.unwrap_or_default().saturating_sub(block_base_fee),
+        })
+        .collect();
+    if tips.is_empty() {
+        return Err(GasFeedError::EmptySource("included_transactions"));
+    }
+    tips.sort();
+    let index = ((tips.len() - 1) as f64 * percentile.clamp(0.0, 1.0)) as usize;
+
+    // Worst-case next base fee from a block that was as full as this one
+    let gas_limit = block.gas_limit.max(U256::one());
+    let target = gas_limit / 2;
+    let next_base_fee = if block.gas_used > target {
+        block_base_fee + block_base_fee * (block.gas_used - target) / target / 8
+    } else {
+        block_base_fee - block_base_fee * (target - block.gas_used) / target / 8
+    };
+    Ok(SourceQuote { source: String::from("included_transactions"), base_fee: next_base_fee, priority_fee: tips[index] })
+}
+
+fn median(values: &mut [U256]) -> U256 {
+    values.sort();
+    values[values.len() / 2]
+}
+
+// Drop quotes whose base or priority fee strays more than max_deviation from the median of all quotes;
+// with only two disagreeing quotes the trusted source wins
+fn reject_outliers(quotes: Vec<SourceQuote>, max_deviation: f64, trusted_source: &str) -> (Vec<SourceQuote>, Vec<String>) {
+    if quotes.len() < 2 {
+        return (quotes, Vec::new());
+    }
+    let base_median = median(&mut quotes.iter().map(|quote| quote.base_fee).collect::<Vec<_>>());
+    let tip_median = median(&mut quotes.iter().map(|quote| quote.priority_fee).collect::<Vec<_>>());
+    let deviates = |value: U256, center: U256| {
+        let (value, center) = (value.as_u128() as f64, center.as_u128() as f64);
+        center > 0.0 && (value - center).abs() / center > max_deviation
+    };
+
+    if quotes.len() == 2 {
+        let disagree = deviates(quotes[0].base_fee, quotes[1].base_fee) || deviates(quotes[0].priority_fee, quotes[1].priority_fee);
+        if !disagree {
+            return (quotes, Vec::new());
+        }
+        let (kept, rejected): (Vec<SourceQuote>, Vec<SourceQuote>) = quotes.into_iter().partition(|quote| quote.source == trusted_source);
+        return (kept, rejected.into_iter().map(|quote| quote.source).collect());
+    }
+
+    let (kept, rejected): (Vec<SourceQuote>, Vec<SourceQuote>) = quotes
+        .into_iter()
+        .partition(|quote| !deviates(quote.base_fee, base_median) && !deviates(quote.priority_fee, tip_median));
+    (kept, rejected.into_iter().map(|quote| quote.source).collect())
+}
+
+// Consensus estimate across node fee history, Blocknative and included transactions, cached briefly
+pub async fn consensus_estimate(web3: &web3::Web3<FailoverTransport>) -> Result<GasEstimate, GasFeedError> {
+    let config = load_gas_feed_config();
+    let ttl = Duration::from_millis(config["cache_ttl_ms"].as_u64().unwrap_or(3000));
+    if let Some((at, estimate)) = cache().lock().unwrap().as_ref() {
+        if at.elapsed() < ttl {
+            return Ok(estimate.clone());
+        }
+    }
+
+    let (node, blocknative_quote, included) = tokio::join!(
+        node_fee_history(web3, &config),
+        blocknative(&config),
+        included_transactions(web3, &config),
+    );
+    let mut quotes = Vec::new();
+    for result in [node, blocknative_quote, included] {
+        match result {
+            Ok(quote) => quotes.push(quote),
+            Err(e) => warn!("Gas source unavailable: {}", e),
+        }
+    }
+    if quotes.is_empty() {
+        return Err(GasFeedError::NoSources);
+    }
+
+    let max_deviation = config["max_deviation"].as_f64().unwrap_or(0.5);
+    let trusted_source = config["trusted_source"].as_str().unwrap_or("node_fee_history");
+    let (kept, rejected) = reject_outliers(quotes.clone(), max_deviation, trusted_source);
+    if !rejected.is_empty() {
+        warn!("Gas feed rejected outlier sources: {:?}", rejected);
+    }
+    if kept.is_empty() {
+        return Err(GasFeedError::NoSources);
+    }
+
+    let base_fee = median(&mut kept.iter().map(|quote| quote.base_fee).collect::<Vec<_>>());
+    let priority_fee = median(&mut kept.iter().map(|quote| quote.priority_fee).collect::<Vec<_>>());
+    // Scaled by the multiplier tuned from our inclusion rate across builders
+    let priority_fee = U256::from((priority_fee.as_u128() as f64 * builders::tip_multiplier()) as u128);
+    let base_fee_multiplier = config["base_fee_multiplier"].as_u64().unwrap_or(2);
+    let estimate = GasEstimate {
+        base_fee,
+        priority_fee,
+        max_fee: base_fee * U256::from(base_fee_multiplier) + priority_fee,
+        quotes,
+        rejected,
+    };
+
+    *cache().lock().unwrap() = Some((Instant::now(), estimate.clone()));
+    Ok(estimate)
+}
+
+// Base fees the inclusion block can plausibly have: each block moves the base fee by at most 12.5%,
+// so over sensitivity_blocks_ahead blocks it spans a known band around the current one
+pub fn base_fee_scenarios(base_fee: U256) -> Vec<U256> {
+    let config = load_gas_feed_config();
+    let blocks_ahead = config["sensitivity_blocks_ahead"].as_u64().unwrap_or(2) as i32;
+    let steps = config["sensitivity_steps"].as_u64().unwrap_or(4).max(1);
+    let base = base_fee.as_u128() as f64;
+    let (low, high) = (base * 0.875f64.powi(blocks_ahead), base * 1.125f64.powi(blocks_ahead));
+    (0..=steps).map(|step| U256::from((low + (high - low) * step as f64 / steps as f64) as u128)).collect()
+}
+
+// Net profit of a planned trade at each scenario base fee
+#[derive(Clone, Debug)]
+pub struct FeeSensitivity {
+    pub scenarios: Vec<(U256, i128)>,
+    // Lowest scenario base fee at which the trade no longer makes money
+    pub flips_at: Option<U256>,
+}
+
+impl FeeSensitivity {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "scenarios": self.scenarios.iter().map(|(base_fee, net)| json!({
+                "base_fee_gwei": base_fee.as_u128() as f64 / GWEI,
+                "net_profit_wei": net.to_string(),
+            })).collect::<Vec<_>>(),
+            "flips_at_gwei": self.flips_at.map(|base_fee| base_fee.as_u128() as f64 / GWEI),
+        })
+    }
+}
+
+// Re-price a planned trade's gas across the scenario base fees
+pub fn fee_sensitivity(gross_profit: U256, gas_used: U256, priority_fee: U256, base_fee: U256) -> FeeSensitivity {
+    let scenarios: Vec<(U256, i128)> = base_fee_scenarios(base_fee)
+        .into_iter()
+        .map(|scenario| {
+            let gas_cost = gas_used.saturating_mul(scenario + priority_fee);
+            (scenario, gross_profit.as_u128() as i128 - gas_cost.as_u128() as i128)
+        })
+        .collect();
+    let flips_at = scenarios.iter().find(|(_, net)| *net <= 0).map(|(scenario, _)| *scenario);
+    FeeSensitivity { scenarios, flips_at }
+}
+
+// Last consensus estimate for the dashboard
+pub fn snapshot() -> Value {
+    match cache().lock().unwrap().as_ref() {
+        Some((at, estimate)) => {
+            let mut value = estimate.to_json();
+            value["age_ms"] = json!(at.elapsed().as_millis() as u64);
+            value
+        }
+        None => json!(null),
+    }
+}
+
+// Custom error type for the gas feed
+#[derive(Error, Debug)]
+pub enum GasFeedError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("Gas source {0} returned no data")]
+    EmptySource(&'static str),
+    #[error("No gas source produced an estimate")]
+    NoSources,
+}
@@ -0,0 +1,335 @@
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use web3::contract::{Contract, Options};
+use web3::ethabi::{self, Token};
+use web3::types::{Address, U256};
+use web3::Transport;
+use log::{info, warn};
+
+use crate::modules::config;
+use crate::modules::execution::{self, Call};
+use crate::modules::multicall;
+use crate::modules::providers::FailoverTransport;
+
+// Rough per-operation costs used to log the effect of each optimization
+const APPROVE_GAS: u64 = 46_000;
+const SWAP_HOP_GAS: u64 = 60_000;
+
+// Load global config (gas golf toggles live there, next to comments)
+fn load_global_config() -> Value {
+    config::load_value(config::GLOBAL_CONFIG_PATH).expect("Unable to load global config file")
+}
+
+// Estimated gas saved by an optimizer pass, split by source
+#[derive(Clone, Debug, Default)]
+pub struct GasReport {
+    pub approvals_removed: usize,
+    pub hops_removed: usize,
+    pub calldata_gas_before: u64,
+    pub calldata_gas_after: u64,
+}
+
+impl GasReport {
+    pub fn estimated_savings(&self) -> i64 {
+        (self.approvals_removed as u64 * APPROVE_GAS + self.hops_removed as u64 * SWAP_HOP_GAS) as i64
+            + self.calldata_gas_before as i64
+            - self.calldata_gas_after as i64
+    }
+}
+
+// Intrinsic calldata cost: 4 gas per zero byte, 16 per non-zero byte
+pub fn calldata_gas(calls: &[Call]) -> u64 {
+    calls
+        .iter()
+        .flat_map(|call| call.data.iter())
+        .map(|byte| if *byte == 0 { 4 } else { 16 })
+        .sum()
+}
+
+// Merge repeated approvals of the same token/spender and drop those already covered by the on-chain allowance
+async fn remove_redundant_approvals<T: Transport>(web3: &web3::Web3<T>, owner: Address, calls: Vec<Call>, report: &mut GasReport) -> Vec<Call> {
+    // A zero approve resets the allowance (tokens like USDT need it before a new approval), so it starts a
+    // new segment for its (token, spender) pair and nonzero approvals are only merged within a segment
+    let mut segments: HashMap<(Address, Address), usize> = HashMap::new();
+    let mut keys: Vec<Option<(Address, Address, usize)>> = Vec::with_capacity(calls.len());
+    let mut needed: HashMap<(Address, Address, usize), U256> = HashMap::new();
+    for call in &calls {
+        let key = match execution::decode_approve(call) {
+            Some((spender, amount)) => {
+                let segment = segments.entry((call.to, spender)).or_insert(0);
+                if amount.is_zero() {
+                    *segment += 1;
+                    None
+                } else {
+                    let key = (call.to, spender, *segment);
+                    let total = needed.entry(key).or_insert_with(U256::zero);
+                    *total = total.saturating_add(amount);
+                    Some(key)
+                }
+            }
+            None => None,
+        };
+        keys.push(key);
+    }
+
+    // Every allowance in one multicall; one that can't be read counts as zero and keeps its approval.
+    // Only approvals before any reset can be covered by the current allowance
+    let pairs: Vec<((Address, Address, usize), U256)> =
+        needed.iter().filter(|((_, _, segment), _)| *segment == 0).map(|(key, amount)| (*key, *amount)).collect();
+    let queries: Vec<(Address, Address, Address)> = pairs.iter().map(|((token, spender, _), _)| (*token, owner, *spender)).collect();
+    let allowances = match multicall::allowances(web3, &queries).await {
+        Ok(allowances) => allowances,
+        Err(e) => {
+            warn!("Unable to read allowances through multicall: {}", e);
+            vec![None; queries.len()]
+        }
+    };
+    let mut covered: HashMap<(Address, Address, usize), bool> = HashMap::new();
+    for ((key, amount), allowance) in pairs.into_iter().zip(allowances) {
+        covered.insert(key, allowance.unwrap_or_default() >= amount);
+    }
+
+    let mut emitted: HashSet<(Address, Address, usize)> = HashSet::new();
+    let mut optimized = Vec::with_capacity(calls.len());
+    for (call, key) in calls.into_iter().zip(keys) {
+        // Zero approvals and other calls are kept as they are
+        if let Some(key) = key {
+            if covered.get(&key).copied().unwrap_or(false) || emitted.contains(&key) {
+                report.approvals_removed += 1;
+                continue;
+            }
+            // One approval for the segment's combined amount replaces the repeated ones
+            let (_, spender, _) = key;
+            let encoded = ethabi::encode(&[Token::Address(spender), Token::Uint(needed[&key])]);
+            let data = [multicall::selector("approve(address,uint256)").to_vec(), encoded].concat();
+            emitted.insert(key);
+            optimized.push(Call { to: call.to, value: call.value, data });
+            continue;
+        }
+        optimized.push(call);
+    }
+    optimized
+}
+
+// Replace multi-hop V2 swaps with the direct pair when it quotes at least as much
+async fn shorten_swap_paths<T: Transport>(web3: &web3::Web3<T>, calls: Vec<Call>, report: &mut GasReport) -> Vec<Call> {
+    let swap_selector = multicall::selector("swapExactTokensForTokens(uint256,uint256,address[],address,uint256)");
+    let params = [
+        ethabi::ParamType::Uint(256),
+        ethabi::ParamType::Uint(256),
+        ethabi::ParamType::Array(Box::new(ethabi::ParamType::Address)),
+        ethabi::ParamType::Address,
+        ethabi::ParamType::Uint(256),
+    ];
+
+    let mut optimized = Vec::with_capacity(calls.len());
+    for call in calls {
+        let decoded = if call.data.len() >= 4 && call.data[..4] == swap_selector {
+            ethabi::decode(&params, &call.data[4..]).ok()
+        } else {
+            None
+        };
+        let mut tokens = match decoded {
+            Some(tokens) => tokens,
+            None => {
+                optimized.push(call);
+                continue;
+            }
+        };
+
+        let path: Vec<Address> = tokens[2].clone().into_array().unwrap_or_default().into_iter().filter_map(|t| t.into_address()).collect();
+        let amount_in = tokens[0].clone().into_uint().unwrap_or_default();
+        if path.len() <= 2 {
+            optimized.push(call);
+            continue;
+        }
+
+        let direct = vec![path[0], path[path.len() - 1]];
+        let quotes = match Contract::from_json(web3.eth(), call.to, include_bytes!("abi/uniswap_router_abi.json")) {
+            Ok(router) => {
+                let multi_hop: Option<Vec<U256>> = router.query("getAmountsOut", (amount_in, path.clone()), None, Options::default(), None).await.ok();
+                let direct_hop: Option<Vec<U256>> = router.query("getAmountsOut", (amount_in, direct.clone()), None, Options::default(), None).await.ok();
+                multi_hop.zip(direct_hop)
+            }
+            Err(_) => None,
+        };
+
+        match quotes {
+            Some((multi_hop, direct_hop)) if direct_hop.last() >= multi_hop.last() => {
+                report.hops_removed += path.len() - 2;
+                tokens[2] = Token::Array(direct.into_iter().map(Token::Address).collect());
+                let data = [swap_selector.to_vec(), ethabi::encode(&tokens)].concat();
+                optimized.push(Call { to: call.to, value: call.value, data });
+            }
+            _ => optimized.push(call),
+        }
+    }
+    optimized
+}
+
+// Optimizer pass over a built plan: same effect, cheapest equivalent encoding. Off unless gas_golf_enabled is set,
+// since it rewrites calldata that was already simulated
+pub async fn optimize_calls(web3: &web3::Web3<FailoverTransport>, owner: Address, calls: Vec<Call>) -> Vec<Call> {
+    let config = load_global_config();
+    if !config["gas_golf_enabled"].as_bool().unwrap_or(false) {
+        return calls;
+    }
+
+    let mut report = GasReport { calldata_gas_before: calldata_gas(&calls), ..Default::default() };
+    let original_len = calls.len();
+
+    let mut optimized = remove_redundant_approvals(web3, owner, calls, &mut report).await;
+    if config["gas_golf_shorten_paths"].as_bool().unwrap_or(true) {
+        optimized = shorten_swap_paths(web3, optimized, &mut report).await;
+    }
+    report.calldata_gas_after = calldata_gas(&optimized);
+
+    if optimized.is_empty() {
+        warn!("Gas golf removed every call from the plan (all approvals already in place)");
+    }
+    if report.estimated_savings() != 0 {
+        info!(
+            "Gas golf: {} -> {} calls, {} approvals and {} hops removed, calldata {} -> {} gas, ~{} gas saved",
+            original_len,
+            optimized.len(),
+            report.approvals_removed,
+            report.hops_removed,
+            report.calldata_gas_before,
+            report.calldata_gas_after,
+            report.estimated_savings()
+        );
+    }
+    optimized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core as rpc;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use web3::types::Bytes;
+    use web3::{helpers, RequestId};
+
+    const GET_AMOUNTS_OUT: &str = "getAmountsOut(uint256,address[])";
+    const SWAP: &str = "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)";
+
+    type Answers = HashMap<(Address, Vec<u8>), Vec<u8>>;
+
+    // Answers eth_call by target and selector; calls without an answer (allowance reads included) revert
+    #[derive(Clone, Debug, Default)]
+    struct MockTransport {
+        answers: Arc<Mutex<Answers>>,
+    }
+
+    impl MockTransport {
+        fn answer(&self, target: Address, signature: &str, output: Vec<Token>) {
+            self.answers.lock().unwrap().insert((target, multicall::selector(signature).to_vec()), ethabi::encode(&output));
+        }
+    }
+
+    impl Transport for MockTransport {
+        type Out = futures::future::Ready<web3::error::Result<Value>>;
+
+        fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, rpc::Call) {
+            (0, helpers::build_request(0, method, params))
+        }
+
+        fn send(&self, _id: RequestId, request: rpc::Call) -> Self::Out {
+            let call = match request {
+                rpc::Call::MethodCall(rpc::MethodCall { params: rpc::Params::Array(params), .. }) => params.into_iter().next().unwrap_or_default(),
+                _ => Value::Null,
+            };
+            let target: Address = serde_json::from_value(call["to"].clone()).unwrap_or_default();
+            let data: Bytes = serde_json::from_value(call["data"].clone()).unwrap_or_default();
+            let key = (target, data.0.get(..4).unwrap_or_default().to_vec());
+            futures::future::ready(match self.answers.lock().unwrap().get(&key) {
+                Some(output) => Ok(json!(Bytes(output.clone()))),
+                None => Err(web3::Error::Rpc(rpc::Error {
+                    code: rpc::ErrorCode::ServerError(3),
+                    message: String::from("execution reverted"),
+                    data: None,
+                })),
+            })
+        }
+    }
+
+    fn address(n: u64) -> Address {
+        Address::from_low_u64_be(n)
+    }
+
+    fn approve(token: Address, spender: Address, amount: u64) -> Call {
+        execution::approve_call(token, spender, U256::from(amount)).unwrap()
+    }
+
+    fn swap(router: Address, path: &[Address], amount_in: u64, amount_out_min: u64) -> Call {
+        let tokens = [
+            Token::Uint(amount_in.into()),
+            Token::Uint(amount_out_min.into()),
+            Token::Array(path.iter().copied().map(Token::Address).collect()),
+            Token::Address(address(0x99)),
+            Token::Uint(1_700_000_000u64.into()),
+        ];
+        Call { to: router, value: U256::zero(), data: [multicall::selector(SWAP).to_vec(), ethabi::encode(&tokens)].concat() }
+    }
+
+    #[tokio::test]
+    async fn approvals_merge_only_within_a_reset() {
+        let (token, spender) = (address(0x101), address(0x102));
+        let web3 = web3::Web3::new(MockTransport::default());
+        let calls = vec![
+            approve(token, spender, 100),
+            approve(token, spender, 20),
+            approve(token, spender, 0),
+            approve(token, spender, 50),
+            approve(token, spender, 30),
+        ];
+
+        let mut report = GasReport::default();
+        let optimized = remove_redundant_approvals(&web3, address(0x100), calls, &mut report).await;
+        let expected = [approve(token, spender, 120), approve(token, spender, 0), approve(token, spender, 80)];
+        assert_eq!(optimized.iter().map(|call| &call.data).collect::<Vec<_>>(), expected.iter().map(|call| &call.data).collect::<Vec<_>>());
+        assert_eq!(report.approvals_removed, 2);
+    }
+
+    #[tokio::test]
+    async fn approvals_to_distinct_spenders_stay_separate() {
+        let (token, spender_a, spender_b) = (address(0x201), address(0x202), address(0x203));
+        let web3 = web3::Web3::new(MockTransport::default());
+        let calls = vec![approve(token, spender_a, 100), approve(token, spender_b, 100), approve(token, spender_a, 50)];
+
+        let mut report = GasReport::default();
+        let optimized = remove_redundant_approvals(&web3, address(0x200), calls, &mut report).await;
+        let decoded: Vec<(Address, Option<(Address, U256)>)> = optimized.iter().map(|call| (call.to, execution::decode_approve(call))).collect();
+        assert_eq!(decoded, vec![(token, Some((spender_a, U256::from(150)))), (token, Some((spender_b, U256::from(100))))]);
+        assert_eq!(report.approvals_removed, 1);
+    }
+
+    #[tokio::test]
+    async fn shortened_path_keeps_amounts_and_output() {
+        let (router, weth, usdc, dai) = (address(0x301), address(0x302), address(0x303), address(0x304));
+        let transport = MockTransport::default();
+        // The direct pair quotes the same output as the route through USDC
+        transport.answer(router, GET_AMOUNTS_OUT, vec![Token::Array(vec![Token::Uint(1000.into()), Token::Uint(2990.into())])]);
+        let web3 = web3::Web3::new(transport);
+
+        let mut report = GasReport::default();
+        let optimized = shorten_swap_paths(&web3, vec![swap(router, &[weth, usdc, dai], 1000, 2900)], &mut report).await;
+        assert_eq!(optimized.len(), 1);
+        assert_eq!(optimized[0].to, router);
+        assert_eq!(optimized[0].data, swap(router, &[weth, dai], 1000, 2900).data);
+        assert_eq!(report.hops_removed, 1);
+    }
+
+    #[tokio::test]
+    async fn path_is_kept_when_the_router_cannot_quote() {
+        let (router, weth, usdc, dai) = (address(0x401), address(0x402), address(0x403), address(0x404));
+        let web3 = web3::Web3::new(MockTransport::default());
+        let call = swap(router, &[weth, usdc, dai], 1000, 2900);
+
+        let mut report = GasReport::default();
+        let optimized = shorten_swap_paths(&web3, vec![call.clone()], &mut report).await;
+        assert_eq!(optimized[0].data, call.data);
+        assert_eq!(report.hops_removed, 0);
+    }
+}
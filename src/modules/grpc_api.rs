@@ -0,0 +1,225 @@
+use serde_json::Value;
+use std::fs;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+use log::{error, info, warn};
+use thiserror::Error;
+
+use crate::modules::allocator;
+use crate::modules::auth::{AuthConfig, OperatorIdentity, Role};
+use crate::modules::control;
+use crate::modules::coordination;
+use crate::modules::dashboard;
+use crate::modules::events;
+use crate::modules::providers::ProviderPool;
+
+pub mod proto {
+    tonic::include_proto!("taz");
+}
+
+use proto::taz_bot_server::{TazBot, TazBotServer};
+use proto::{
+    ControlRequest, Event, StatusReply, StatusRequest, StrategyState, StreamEventsRequest, Trade, TradeHistoryReply,
+    TradeHistoryRequest,
+};
+
+// The gRPC API shares operators and the "grpc" section with the dashboard config
+fn load_dashboard_config() -> Value {
+    let config_path = "config/dashboard_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read dashboard config file");
+    serde_json::from_str(&config_data).expect("Unable to parse dashboard config file")
+}
+
+struct TazBotService {
+    auth: Arc<AuthConfig>,
+    provider_pool: Arc<ProviderPool>,
+}
+
+impl TazBotService {
+    // Same bearer tokens and roles as the HTTP API, read from the "authorization" metadata
+    fn authorize<T>(&self, request: &Request<T>, required: Role) -> Result<OperatorIdentity, AuthDenied> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AuthDenied::Unauthenticated)?;
+        let identity = self.auth.authenticate(token).ok_or(AuthDenied::Unauthenticated)?;
+        if identity.role < required {
+            warn!("{} ({:?}) denied gRPC access requiring {:?}", identity.name, identity.role, required);
+            return Err(AuthDenied::Forbidden(required));
+        }
+        Ok(identity)
+    }
+
+    fn status(&self) -> StatusReply {
+        let summary = dashboard::build_summary(&self.provider_pool);
+        let strategies = summary["strategies"]
+            .as_array()
+            .map(|strategies| {
+                strategies
+                    .iter()
+                    .map(|strategy| StrategyState {
+                        name: strategy["name"].as_str().unwrap_or_default().to_string(),
+                        state: strategy["state"].as_str().unwrap_or_default().to_string(),
+                        pause_reason: strategy["pause_reason"].as_str().unwrap_or_default().to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        StatusReply {
+            paused: control::is_paused(),
+            kill_switch: control::is_kill_switch_engaged(),
+            writer: coordination::is_writer(),
+            strategies,
+            summary_json: summary.to_string(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl TazBot for TazBotService {
+    async fn get_status(&self, request: Request<StatusRequest>) -> Result<Response<StatusReply>, Status> {
+        self.authorize(&request, Role::Viewer)?;
+        Ok(Response::new(self.status()))
+    }
+
+    async fn control(&self, request: Request<ControlRequest>) -> Result<Response<StatusReply>, Status> {
+        let operator = self.authorize(&request, Role::Operator)?;
+        let ControlRequest { action, strategy } = request.into_inner();
+        match action.as_str() {
+            "pause" => control::pause(&operator.name),
+            "resume" => control::resume(&operator.name),
+            "kill" => control::engage_kill_switch(&operator.name),
+            // Only admins may lift the kill switch
+            "unkill" if operator.role == Role::Admin => control::disable_kill_switch(&operator.name),
+            "unkill" => return Err(Status::permission_denied("Requires Admin role")),
+            "pause_strategy" | "resume_strategy" if strategy.is_empty() => {
+                return Err(Status::invalid_argument("strategy is required"));
+            }
+            "pause_strategy" => control::pause_strategy(&strategy, &format!("paused by {}", operator.name)),
+            "resume_strategy" => control::resume_strategy(&strategy, &operator.name),
+            _ => return Err(Status::invalid_argument("Unknown control action")),
+        }
+        Ok(Response::new(self.status()))
+    }
+
+    async fn trade_history(&self, request: Request<TradeHistoryRequest>) -> Result<Response<TradeHistoryReply>, Status> {
+        self.authorize(&request, Role::Viewer)?;
+        let TradeHistoryRequest { limit, strategy } = request.into_inner();
+        let limit = if limit == 0 { 100 } else { limit as usize };
+        let strategy = if strategy.is_empty() { None } else { Some(strategy.as_str()) };
+
+        let trades = allocator::recent_trade_results(limit, strategy)
+            .into_iter()
+            .map(|record| Trade {
+                timestamp: record["timestamp"].as_str().unwrap_or_default().to_string(),
+                strategy: record["strategy"].as_str().unwrap_or_default().to_string(),
+                pnl_wei: record["pnl_wei"].as_str().unwrap_or_default().to_string(),
+            })
+            .collect();
+        Ok(Response::new(TradeHistoryReply { trades }))
+    }
+
+    type StreamEventsStream = Pin<Box<dyn Stream<Item = Result<Event, Status>> + Send>>;
+
+    async fn stream_events(&self, request: Request<StreamEventsRequest>) -> Result<Response<Self::StreamEventsStream>, Status> {
+        self.authorize(&request, Role::Viewer)?;
+        let kinds = request.into_inner().kinds;
+
+        // Lagging subscribers skip the events they missed rather than ending the stream
+        let stream = BroadcastStream::new(events::subscribe()).filter_map(move |event| {
+            let event = event.ok()?;
+            if !kinds.is_empty() && !kinds.contains(&event.kind) {
+                return None;
+            }
+            Some(Ok(Event {
+                timestamp: event.timestamp,
+                kind: event.kind,
+                payload_json: event.payload.to_string(),
+            }))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+// mTLS when a client CA is configured: only clients with a certificate signed by it can connect
+fn tls_config(config: &Value) -> Result<Option<ServerTlsConfig>, GrpcError> {
+    let (cert_path, key_path) = match (config["cert_path"].as_str(), config["key_path"].as_str()) {
+        (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+        _ => return Ok(None),
+    };
+    let identity = Identity::from_pem(fs::read(cert_path)?, fs::read(key_path)?);
+    let mut tls = ServerTlsConfig::new().identity(identity);
+    if let Some(client_ca_path) = config["client_ca_path"].as_str() {
+        tls = tls.client_ca_root(Certificate::from_pem(fs::read(client_ca_path)?));
+    }
+    Ok(Some(tls))
+}
+
+pub async fn run_grpc_api(provider_pool: Arc<ProviderPool>) {
+    let config = load_dashboard_config();
+    let grpc_config = &config["grpc"];
+    let port = grpc_config["port"].as_u64().unwrap_or(50051) as u16;
+    let bind = grpc_config["bind"].as_str().unwrap_or("127.0.0.1");
+    let address = match format!("{}:{}", bind, port).parse() {
+        Ok(address) => address,
+        Err(e) => {
+            error!("Invalid gRPC bind address {}:{}: {}", bind, port, e);
+            return;
+        }
+    };
+
+    let service = TazBotService { auth: Arc::new(AuthConfig::from_config(&config)), provider_pool };
+    let mut builder = Server::builder();
+    match tls_config(&grpc_config["tls"]) {
+        Ok(Some(tls)) => {
+            builder = match builder.tls_config(tls) {
+                Ok(builder) => builder,
+                Err(e) => {
+                    error!("Invalid gRPC TLS config: {}", e);
+                    return;
+                }
+            };
+        }
+        Ok(None) => warn!("gRPC API running without TLS"),
+        Err(e) => {
+            error!("Failed to load gRPC TLS material: {}", e);
+            return;
+        }
+    }
+
+    info!("gRPC API listening on {}", address);
+    if let Err(e) = builder.add_service(TazBotServer::new(service)).serve(address).await {
+        error!("gRPC API stopped: {}", e);
+    }
+}
+
+// Custom error type for the gRPC API
+#[derive(Error, Debug)]
+pub enum GrpcError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+// Why a call was refused, turned into a gRPC status at the handler boundary
+#[derive(Debug)]
+pub enum AuthDenied {
+    Unauthenticated,
+    Forbidden(Role),
+}
+
+impl From<AuthDenied> for Status {
+    fn from(denied: AuthDenied) -> Self {
+        match denied {
+            AuthDenied::Unauthenticated => Status::unauthenticated("Missing or invalid API token"),
+            AuthDenied::Forbidden(required) => Status::permission_denied(format!("Requires {:?} role", required)),
+        }
+    }
+}
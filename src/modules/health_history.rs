@@ -0,0 +1,314 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use web3::ethabi::{self, ParamType, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, BlockId, BlockNumber, Bytes, CallRequest, U256};
+use log::{error, info, warn};
+use thiserror::Error;
+
+use crate::modules::ens;
+use crate::modules::gas::{self, Urgency};
+use crate::modules::liquidation_report;
+use crate::modules::providers::FailoverTransport;
+
+// Health factors are 18-decimal fixed point on Aave
+const WAD: f64 = 1e18;
+
+// Load configuration for liquidation (history settings live there)
+fn load_liquidation_config() -> Value {
+    let config_path = "config/liquidation_config.json";
+    let config_data = fs::read_to_string(config_path).expect("Unable to read liquidation config file");
+    serde_json::from_str(&config_data).expect("Unable to parse liquidation config file")
+}
+
+// A borrower's position on Aave and Compound as of one historical block
+#[derive(Clone, Debug)]
+pub struct HealthSnapshot {
+    pub borrower: Address,
+    pub block: u64,
+    pub timestamp: u64,
+    pub aave_health_factor: Option<f64>,
+    pub aave_collateral_base: Option<U256>,
+    pub aave_debt_base: Option<U256>,
+    pub compound_liquidity: Option<U256>,
+    pub compound_shortfall: Option<U256>,
+}
+
+impl HealthSnapshot {
+    // Liquidatable on either protocol at this block
+    pub fn is_liquidatable(&self) -> bool {
+        self.aave_health_factor.map(|hf| hf < 1.0).unwrap_or(false)
+            || self.compound_shortfall.map(|shortfall| !shortfall.is_zero()).unwrap_or(false)
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "borrower": self.borrower,
+            "block": self.block,
+            "timestamp": self.timestamp,
+            "aave_health_factor": self.aave_health_factor,
+            "aave_collateral_base": self.aave_collateral_base.map(|value| value.to_string()),
+            "aave_debt_base": self.aave_debt_base.map(|value| value.to_string()),
+            "compound_liquidity": self.compound_liquidity.map(|value| value.to_string()),
+            "compound_shortfall": self.compound_shortfall.map(|value| value.to_string()),
+            "liquidatable": self.is_liquidatable(),
+        })
+    }
+}
+
+// eth_call pinned to a historical block; a pruned node answers with a missing-state error
+async fn call_at(web3: &web3::Web3<FailoverTransport>, to: Address, signature: &str, args: &[Token], block: u64) -> Result<Vec<u8>, HistoryError> {
+    let data = [keccak256(signature.as_bytes())[..4].to_vec(), ethabi::encode(args)].concat();
+    let request = CallRequest { to: Some(to), data: Some(Bytes(data)), ..Default::default() };
+    match web3.eth().call(request, Some(BlockId::Number(BlockNumber::Number(block.into())))).await {
+        Ok(output) => Ok(output.0),
+        Err(e) if e.to_string().contains("missing trie node") || e.to_string().contains("state is not available") => {
+            Err(HistoryError::NotArchive(block))
+        }
+        Err(e) => Err(HistoryError::Web3Error(e)),
+    }
+}
+
+// Rebuild one borrower's health at a historical block via archive eth_call
+pub async fn health_at(
+    web3: &web3::Web3<FailoverTransport>,
+    aave_pool: Option<Address>,
+    comptroller: Option<Address>,
+    borrower: Address,
+    block: u64,
+) -> Result<HealthSnapshot, HistoryError> {
+    let timestamp = web3
+        .eth()
+        .block(BlockId::Number(BlockNumber::Number(block.into())))
+        .await?
+        .map(|header| header.timestamp.as_u64())
+        .unwrap_or(0);
+    let mut snapshot = HealthSnapshot {
+        borrower,
+        block,
+        timestamp,
+        aave_health_factor: None,
+        aave_collateral_base: None,
+        aave_debt_base: None,
+        compound_liquidity: None,
+        compound_shortfall: None,
+    };
+
+    if let Some(pool) = aave_pool {
+        let output = call_at(web3, pool, "getUserAccountData(address)", &[Token::Address(borrower)], block).await?;
+        let fields = ethabi::decode(&vec![ParamType::Uint(256); 6], &output)?;
+        let debt = fields[1].clone().into_uint().unwrap_or_default();
+        snapshot.aave_collateral_base = fields[0].clone().into_uint();
+        snapshot.aave_debt_base = Some(debt);
+        // No debt reports a max-uint health factor; keep it out of the numbers
+        snapshot.aave_health_factor = if debt.is_zero() {
+            None
+        } else {
+            fields[5].clone().into_uint().map(|hf| hf.as_u128() as f64 / WAD)
+        };
+    }
+
+    if let Some(comptroller) = comptroller {
+        let output = call_at(web3, comptroller, "getAccountLiquidity(address)", &[Token::Address(borrower)], block).await?;
+        let fields = ethabi::decode(&vec![ParamType::Uint(256); 3], &output)?;
+        if fields[0].clone().into_uint().unwrap_or_default().is_zero() {
+            snapshot.compound_liquidity = fields[1].clone().into_uint();
+            snapshot.compound_shortfall = fields[2].clone().into_uint();
+        }
+    }
+
+    Ok(snapshot)
+}
+
+// Health of every borrower every step_blocks over [from_block, to_block], appended to the output file
+pub async fn reconstruct(
+    web3: &web3::Web3<FailoverTransport>,
+    borrowers: &[Address],
+    from_block: u64,
+    to_block: u64,
+    step_blocks: u64,
+    output_path: &str,
+) -> Result<Vec<HealthSnapshot>, HistoryError> {
+    let config = load_liquidation_config();
+    let address = |key: &str| config[key].as_str().and_then(ens::parse_address);
+    let aave_pool = address("aave_pool_address");
+    let comptroller = address("compound_comptroller_address");
+    if aave_pool.is_none() && comptroller.is_none() {
+        return Err(HistoryError::MissingConfig("aave_pool_address or compound_comptroller_address"));
+    }
+
+    if let Some(parent) = std::path::Path::new(output_path).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let mut output = OpenOptions::new().create(true).append(true).open(output_path)?;
+
+    let mut snapshots = Vec::new();
+    let mut block = from_block;
+    while block <= to_block {
+        for borrower in borrowers {
+            match health_at(web3, aave_pool, comptroller, *borrower, block).await {
+                Ok(snapshot) => {
+                    writeln!(output, "{}", snapshot.to_json())?;
+                    snapshots.push(snapshot);
+                }
+                Err(HistoryError::NotArchive(block)) => return Err(HistoryError::NotArchive(block)),
+                Err(e) => warn!("Failed to rebuild health of {:?} at block {}: {}", borrower, block, e),
+            }
+        }
+        info!("Rebuilt health factors at block {} ({} borrowers)", block, borrowers.len());
+        block += step_blocks.max(1);
+    }
+
+    Ok(snapshots)
+}
+
+// Replay the scanner's near-liquidation threshold over reconstructed history: how early each borrower
+// would have been flagged before becoming liquidatable, and which ones it would have missed
+pub fn evaluate_scanner(snapshots: &[HealthSnapshot], alert_health_factor: f64) -> Value {
+    let mut by_borrower: HashMap<Address, Vec<&HealthSnapshot>> = HashMap::new();
+    for snapshot in snapshots {
+        by_borrower.entry(snapshot.borrower).or_default().push(snapshot);
+    }
+
+    let mut results = Vec::new();
+    let (mut liquidatable, mut flagged_in_time) = (0, 0);
+    for (borrower, mut history) in by_borrower {
+        history.sort_by_key(|snapshot| snapshot.block);
+        let first_liquidatable = history.iter().find(|snapshot| snapshot.is_liquidatable()).map(|snapshot| snapshot.block);
+        let first_flagged = history
+            .iter()
+            .find(|snapshot| snapshot.is_liquidatable() || snapshot.aave_health_factor.map(|hf| hf < alert_health_factor).unwrap_or(false))
+            .map(|snapshot| snapshot.block);
+        let lowest_health_factor = history.iter().filter_map(|snapshot| snapshot.aave_health_factor).fold(f64::INFINITY, f64::min);
+
+        if let Some(liquidatable_at) = first_liquidatable {
+            liquidatable += 1;
+            if first_flagged.map(|flagged| flagged < liquidatable_at).unwrap_or(false) {
+                flagged_in_time += 1;
+            }
+        }
+        results.push(json!({
+            "borrower": borrower,
+            "first_flagged_block": first_flagged,
+            "first_liquidatable_block": first_liquidatable,
+            "lead_blocks": first_flagged.zip(first_liquidatable).map(|(flagged, at)| at as i64 - flagged as i64),
+            "lowest_health_factor": if lowest_health_factor.is_finite() { Some(lowest_health_factor) } else { None },
+        }));
+    }
+
+    json!({
+        "alert_health_factor": alert_health_factor,
+        "borrowers": results.len(),
+        "liquidatable": liquidatable,
+        "flagged_before_liquidatable": flagged_in_time,
+        "results": results,
+    })
+}
+
+// Replay the bidding policy at each borrower's first liquidatable snapshot: whether the bot would have
+// bid, with the same plan and profit checks as `liquidations scan` at that block, and the tip and max
+// fee the gas module would have set at the liquidation urgency for the next block
+pub async fn evaluate_bids(web3: &web3::Web3<FailoverTransport>, snapshots: &[HealthSnapshot]) -> Value {
+    let config = load_liquidation_config();
+    let base_decimals = config["aave_base_decimals"].as_i64().unwrap_or(8) as i32;
+    let urgency = Urgency::for_strategy("liquidation");
+
+    let mut first_liquidatable: HashMap<Address, &HealthSnapshot> = HashMap::new();
+    for snapshot in snapshots.iter().filter(|snapshot| snapshot.is_liquidatable()) {
+        let first = first_liquidatable.entry(snapshot.borrower).or_insert(snapshot);
+        if snapshot.block < first.block {
+            *first = snapshot;
+        }
+    }
+
+    let mut results = Vec::new();
+    let mut bids = 0;
+    for (borrower, snapshot) in first_liquidatable {
+        let (gas_cost, max_base_fee_ratio) = match liquidation_report::gas_at(web3, &config, snapshot.block, base_decimals).await {
+            Ok(gas) => gas,
+            Err(e) => {
+                warn!("Could not price liquidation gas at block {}: {}", snapshot.block, e);
+                (None, 1.0)
+            }
+        };
+        let (plan, excluded) = liquidation_report::bid_decision(snapshot, &config, gas_cost, max_base_fee_ratio, base_decimals);
+        let fees = match gas::suggest_fees_at(web3, urgency, snapshot.block).await {
+            Ok(fees) => Some(fees),
+            Err(e) => {
+                warn!("Could not replay the liquidation tip at block {}: {}", snapshot.block, e);
+                None
+            }
+        };
+        if excluded.is_none() {
+            bids += 1;
+        }
+        results.push(json!({
+            "borrower": borrower,
+            "block": snapshot.block,
+            "bid": excluded.is_none(),
+            "excluded": excluded,
+            "plan": plan,
+            "priority_fee_wei": fees.map(|fees| fees.priority_fee.to_string()),
+            "max_fee_wei": fees.map(|fees| fees.max_fee.to_string()),
+        }));
+    }
+
+    json!({
+        "urgency": urgency.as_str(),
+        "liquidatable": results.len(),
+        "bids": bids,
+        "results": results,
+    })
+}
+
+// Rebuild the configured borrowers over the configured range and write the scanner and bidding
+// evaluation next to it
+pub async fn run_health_history(web3: web3::Web3<FailoverTransport>) -> Result<Value, HistoryError> {
+    let config = load_liquidation_config();
+    let borrowers: Vec<Address> = config["history_borrowers"]
+        .as_array()
+        .map(|list| list.iter().filter_map(|value| value.as_str().and_then(ens::parse_address)).collect())
+        .unwrap_or_default();
+    let from_block = config["history_from_block"].as_u64().ok_or(HistoryError::MissingConfig("history_from_block"))?;
+    let to_block = config["history_to_block"].as_u64().ok_or(HistoryError::MissingConfig("history_to_block"))?;
+    let step_blocks = config["history_step_blocks"].as_u64().unwrap_or(50);
+    let alert_health_factor = config["alert_health_factor"].as_f64().unwrap_or(1.05);
+    let output_path = format!("data/health_history/{}-{}.jsonl", from_block, to_block);
+
+    let snapshots = reconstruct(&web3, &borrowers, from_block, to_block, step_blocks, &output_path).await?;
+    let mut evaluation = evaluate_scanner(&snapshots, alert_health_factor);
+    evaluation["bidding"] = evaluate_bids(&web3, &snapshots).await;
+    let evaluation_path = format!("data/health_history/{}-{}-evaluation.json", from_block, to_block);
+    if let Err(e) = fs::write(&evaluation_path, serde_json::to_string_pretty(&evaluation).unwrap_or_default()) {
+        error!("Failed to write scanner evaluation {}: {}", evaluation_path, e);
+    }
+    info!(
+        "Health history {}-{}: {} of {} liquidatable borrowers flagged in time, {} bid on",
+        from_block, to_block, evaluation["flagged_before_liquidatable"], evaluation["liquidatable"], evaluation["bidding"]["bids"]
+    );
+    Ok(evaluation)
+}
+
+// Custom error type for historical health reconstruction
+#[derive(Error, Debug)]
+pub enum HistoryError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Node has no state for block {0}; an archive endpoint is required")]
+    NotArchive(u64),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+}
+
+// Implement conversion for HistoryError to Web3 error
+impl From<HistoryError> for web3::Error {
+    fn from(error: HistoryError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
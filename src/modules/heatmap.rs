@@ -0,0 +1,169 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use web3::types::{Address, U256};
+use log::{error, info};
+use chrono::{DateTime, Duration as ChronoDuration, Timelike, Utc};
+use tokio::time::{sleep, Duration};
+
+use crate::modules::templates;
+
+// Load the heatmap configuration
+fn load_heatmap_config() -> Value {
+    let config_path = "config/heatmap_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read heatmap config file");
+    serde_json::from_str(&config_data).expect("Unable to parse heatmap config file")
+}
+
+fn spreads_path(config: &Value) -> String {
+    config["spreads_path"].as_str().unwrap_or("data/spreads.jsonl").to_string()
+}
+
+fn output_path(config: &Value) -> String {
+    config["output_path"].as_str().unwrap_or("data/heatmap.json").to_string()
+}
+
+fn pair_label(token_in: Address, token_out: Address) -> String {
+    let name = |token: Address| templates::token_symbol(token).unwrap_or_else(|| format!("{:?}", token));
+    format!("{}/{}", name(token_in), name(token_out))
+}
+
+// Record a quoted round trip whose spread clears min_spread_bps, profitable after gas or not
+pub fn record_spread(token_in: Address, token_out: Address, buy_venue: &str, sell_venue: &str, amount_in: U256, amount_back: U256) {
+    if amount_in.is_zero() {
+        return;
+    }
+    let config = load_heatmap_config();
+    let amount_in_f64 = amount_in.to_string().parse::<f64>().unwrap_or(0.0);
+    let amount_back_f64 = amount_back.to_string().parse::<f64>().unwrap_or(0.0);
+    let spread_bps = (amount_back_f64 - amount_in_f64) / amount_in_f64 * 10_000.0;
+    if spread_bps < config["min_spread_bps"].as_f64().unwrap_or(5.0) {
+        return;
+    }
+
+    let path = spreads_path(&config);
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let record = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "pair": pair_label(token_in, token_out),
+        "route": format!("{} -> {}", buy_venue, sell_venue),
+        "spread_bps": spread_bps,
+        "size": amount_in.to_string(),
+    });
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write spread observation: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open spread log {}: {}", path, e),
+    }
+}
+
+#[derive(Default)]
+struct Cell {
+    count: u64,
+    total_size: f64,
+    total_spread_bps: f64,
+    max_spread_bps: f64,
+}
+
+// Count and size of spreads above threshold per pair, route and hour over the trailing window
+pub fn build_heatmap() -> Value {
+    let config = load_heatmap_config();
+    let window_hours = config["window_hours"].as_i64().unwrap_or(168);
+    let cutoff = Utc::now() - ChronoDuration::hours(window_hours);
+    let contents = fs::read_to_string(spreads_path(&config)).unwrap_or_default();
+
+    let mut cells: BTreeMap<(String, String, String), Cell> = BTreeMap::new();
+    for record in contents.lines().filter_map(|line| serde_json::from_str::<Value>(line).ok()) {
+        let timestamp = match record["timestamp"].as_str().and_then(|ts| DateTime::parse_from_rfc3339(ts).ok()) {
+            Some(timestamp) => timestamp.with_timezone(&Utc),
+            None => continue,
+        };
+        if timestamp < cutoff {
+            continue;
+        }
+        let hour = timestamp.with_minute(0).and_then(|ts| ts.with_second(0)).and_then(|ts| ts.with_nanosecond(0)).unwrap_or(timestamp);
+        let key = (
+            record["pair"].as_str().unwrap_or("?").to_string(),
+            record["route"].as_str().unwrap_or("?").to_string(),
+            hour.to_rfc3339(),
+        );
+        let spread_bps = record["spread_bps"].as_f64().unwrap_or(0.0);
+        let cell = cells.entry(key).or_default();
+        cell.count += 1;
+        cell.total_size += record["size"].as_str().and_then(|size| size.parse::<f64>().ok()).unwrap_or(0.0);
+        cell.total_spread_bps += spread_bps;
+        cell.max_spread_bps = cell.max_spread_bps.max(spread_bps);
+    }
+
+    let rows: Vec<Value> = cells
+        .into_iter()
+        .map(|((pair, route, hour), cell)| json!({
+            "pair": pair,
+            "route": route,
+            "hour": hour,
+            "count": cell.count,
+            "total_size": cell.total_size,
+            "avg_spread_bps": cell.total_spread_bps / cell.count as f64,
+            "max_spread_bps": cell.max_spread_bps,
+        }))
+        .collect();
+    json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "window_hours": window_hours,
+        "min_spread_bps": config["min_spread_bps"],
+        "cells": rows,
+    })
+}
+
+// Latest exported heatmap, or a fresh one if the job has not run yet
+pub fn latest_heatmap() -> Value {
+    let config = load_heatmap_config();
+    fs::read_to_string(output_path(&config))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_else(build_heatmap)
+}
+
+// One row per pair, route and hour
+pub fn to_csv(heatmap: &Value) -> String {
+    let mut csv = String::from("pair,route,hour,count,total_size,avg_spread_bps,max_spread_bps\n");
+    for cell in heatmap["cells"].as_array().into_iter().flatten() {
+        csv.push_str(&format!(
+            "\"{}\",\"{}\",{},{},{},{:.2},{:.2}\n",
+            cell["pair"].as_str().unwrap_or_default(),
+            cell["route"].as_str().unwrap_or_default(),
+            cell["hour"].as_str().unwrap_or_default(),
+            cell["count"],
+            cell["total_size"],
+            cell["avg_spread_bps"].as_f64().unwrap_or(0.0),
+            cell["max_spread_bps"].as_f64().unwrap_or(0.0),
+        ));
+    }
+    csv
+}
+
+// Rebuild the heatmap on the export interval, writing JSON (and CSV when csv_path is set)
+pub async fn run_heatmap_export() {
+    loop {
+        let config = load_heatmap_config();
+        let heatmap = build_heatmap();
+        let path = output_path(&config);
+        match fs::write(&path, heatmap.to_string()) {
+            Ok(()) => info!("Spread heatmap exported to {} ({} cells)", path, heatmap["cells"].as_array().map(|cells| cells.len()).unwrap_or(0)),
+            Err(e) => error!("Failed to write spread heatmap {}: {}", path, e),
+        }
+        if let Some(csv_path) = config["csv_path"].as_str() {
+            if let Err(e) = fs::write(csv_path, to_csv(&heatmap)) {
+                error!("Failed to write spread heatmap CSV {}: {}", csv_path, e);
+            }
+        }
+        sleep(Duration::from_secs(config["export_interval_secs"].as_u64().unwrap_or(3600))).await;
+    }
+}
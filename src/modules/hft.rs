@@ -1,100 +1,191 @@
-use serde_json::Value;
-use std::fs;
-use web3::types::{U256, Address};
+use serde_json::{json, Value};
+use web3::types::{U256, U64, Address, Bytes, TransactionRequest, H256};
 use web3::contract::Options;
 use web3::contract::Contract;
 use log::{error, info};
 use tokio::task;
 use thiserror::Error;
-use tokio::time::{sleep, Duration};
+use tokio::time::sleep;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-
-// Load the HFT configuration
-fn load_hft_config() -> Value {
-    let config_path = "config/hft_config.json";
-    let config_data = fs::read_to_string(config_path)
-        .expect("Unable to read HFT config file");
-    let config: Value = serde_json::from_str(&config_data)
-        .expect("Unable to parse HFT config file");
-    config
-}
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use chrono::Utc;
+use async_trait::async_trait;
+use crate::modules::allocator;
+use crate::modules::arbitrage::{self, ArbitrageError};
+use crate::modules::config::{HftConfig, StrategyConfig};
+use crate::modules::domain::{self, OpportunitySource};
+use crate::modules::execution::ExecutionError;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::receipt_events::{self, ReceiptEventsError};
+use crate::modules::scan_interval::AdaptiveInterval;
+use crate::modules::signals::{self, SignalAction, SignalEvent};
+use crate::modules::slippage;
+use crate::modules::signer::{SignerError, TxSender};
+use crate::modules::strategy::{Opportunity, Strategy, StrategyError};
+use crate::modules::venues::{self, VenueError};
 
 // Continuous Monitoring: Monitor price movements on DEXs
 pub async fn monitor_price_movements(
-    web3: Arc<web3::Web3<web3::transports::Http>>,
-    config: &Value,
+    web3: Arc<web3::Web3<FailoverTransport>>,
+    settings: &HftConfig,
     check_interval: u64
 ) -> Result<(), HFTError> {
-    let asset: Address = config["asset"].as_str().unwrap().parse().expect("Invalid asset address");
-    let uniswap_router_contract = Contract::from_json(
-        web3.eth(),
-        str_to_address(config["uniswap_router_address"].as_str().unwrap()),
-        include_bytes!("abi/uniswap_router_abi.json"),
-    )?;
+    let asset = settings.asset;
+    let mut interval = AdaptiveInterval::from_config("HFT", &settings.to_value(), check_interval);
+
+    let mut sources = signal_sources(asset);
 
     loop {
-        let price = get_asset_price(web3.clone(), uniswap_router_contract.clone(), asset).await?;
+        let price = get_asset_price(web3.clone(), asset, settings.quote_token).await?;
         info!("Current price: {:?}", price);
 
         // Logic to determine if this is a short-term trading opportunity
-        if should_trade(price) {
-            info!("Trading opportunity detected!");
-            execute_hft(web3.clone()).await?;
+        let found = poll_signals(&mut sources, price);
+        if found {
+            execute_hft(web3.clone(), settings).await?;
         }
 
-        // Monitor at intervals
-        sleep(Duration::from_secs(check_interval)).await;
+        // Monitor at intervals, tightened while trades keep triggering
+        sleep(interval.record(found)).await;
+    }
+}
+
+// A source of trading signals, polled once per HFT cycle until it has nothing more to report
+pub trait Signal: Send {
+    fn name(&self) -> &str;
+    fn poll(&mut self, price: U256) -> Option<SignalEvent>;
+}
+
+// The built-in price threshold, plus anything pushed through the external signal endpoint
+fn signal_sources(asset: Address) -> Vec<Box<dyn Signal>> {
+    let mut sources: Vec<Box<dyn Signal>> = vec![Box::new(PriceThreshold)];
+    if signals::enabled() {
+        sources.push(Box::new(ExternalSignals::new(asset)));
+    }
+    sources
+}
+
+// Drain every source at this price; true when any of them calls for a trade
+fn poll_signals(sources: &mut [Box<dyn Signal>], price: U256) -> bool {
+    let mut found = false;
+    for source in sources.iter_mut() {
+        while let Some(signal) = source.poll(price) {
+            match signal.action {
+                SignalAction::Buy | SignalAction::Opportunity => {
+                    info!("Trading opportunity detected by {} ({})", source.name(), signal.source);
+                    found = true;
+                }
+                // The HFT leg only opens positions through the flash loan; nothing to sell into
+                SignalAction::Sell => info!("Ignoring sell signal from {}", signal.source),
+            }
+        }
     }
+    found
 }
 
-// Get asset price from Uniswap or another DEX
+// Fires once per cycle while the price is below the target
+pub struct PriceThreshold;
+
+impl Signal for PriceThreshold {
+    fn name(&self) -> &str {
+        "price_threshold"
+    }
+
+    fn poll(&mut self, price: U256) -> Option<SignalEvent> {
+        if !should_trade(price) {
+            return None;
+        }
+        let now = Utc::now().timestamp_millis();
+        Some(SignalEvent {
+            source: self.name().to_string(),
+            action: SignalAction::Buy,
+            asset: None,
+            strength: 1.0,
+            details: json!({ "price": price.to_string() }),
+            received_at_ms: now,
+            expires_at_ms: now,
+        })
+    }
+}
+
+// Signals pushed by external systems for this asset (or for no asset in particular). Expired
+// signals are dropped, and a lagging receiver just skips what it missed
+pub struct ExternalSignals {
+    receiver: broadcast::Receiver<SignalEvent>,
+    asset: Address,
+}
+
+impl ExternalSignals {
+    pub fn new(asset: Address) -> Self {
+        ExternalSignals { receiver: signals::subscribe(), asset }
+    }
+}
+
+impl Signal for ExternalSignals {
+    fn name(&self) -> &str {
+        "external"
+    }
+
+    fn poll(&mut self, _price: U256) -> Option<SignalEvent> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(signal) if signal.is_expired() => continue,
+                Ok(signal) if signal.asset.is_none_or(|asset| asset == self.asset) => return Some(signal),
+                Ok(_) => continue,
+                Err(TryRecvError::Lagged(skipped)) => info!("External signal consumer skipped {} signals", skipped),
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+// Asset price in the quote token as an 18-decimal fixed-point number, from the V2 routers'
+// getAmountsOut scaled by both tokens' decimals (see arbitrage::get_token_price)
 pub async fn get_asset_price(
-    web3: Arc<web3::Web3<web3::transports::Http>>,
-    uniswap_router_contract: Contract<web3::transports::Http>,
-    asset: Address
+    web3: Arc<web3::Web3<FailoverTransport>>,
+    asset: Address,
+    quote_token: Address
 ) -> Result<U256, HFTError> {
-    let price: U256 = uniswap_router_contract
-        .query("getAmountsOut", (U256::from(1u64), vec![asset]), None, Options::default(), None)
-        .await
-        .map_err(HFTError::ContractError)?;
-
-    Ok(price)
+    Ok(arbitrage::get_token_price(&web3, asset, quote_token).await?)
 }
 
 // Logic to determine if a trade should be executed based on price movement
 fn should_trade(current_price: U256) -> bool {
     // Example: Simple logic, you could improve with technical indicators or thresholds
-    let target_price = U256::from(3000); // Example target price
+    let target_price = U256::from(3000).saturating_mul(U256::exp10(18)); // Example target price, 3000 quote tokens per asset token
     current_price < target_price
 }
 
 // Quick Execution: Execute HFT logic with flash loans (with parallel execution)
 pub async fn execute_hft(
-    web3: Arc<web3::Web3<web3::transports::Http>>
+    web3: Arc<web3::Web3<FailoverTransport>>,
+    settings: &HftConfig,
 ) -> Result<(), HFTError> {
-    // Load HFT configuration
-    let config = load_hft_config();
-    let asset: Address = config["asset"].as_str().unwrap().parse().expect("Invalid address");
-    let module: String = config["module"].as_str().unwrap().to_string();
-    let expected_profit = U256::from_dec_str(config["expected_profit"].as_str().unwrap()).expect("Invalid profit amount");
-    let gas_fee = U256::from_dec_str(config["gas_fee"].as_str().unwrap()).expect("Invalid gas fee");
-    let slippage: f64 = config["slippage"].as_f64().expect("Invalid slippage");
+    let asset = settings.asset;
+    let quote_token = settings.quote_token;
+    let module = settings.module.clone();
+    let slippage_tolerance = settings.slippage_tolerance;
+    let (pool, receiver) = match (settings.aave_pool_address, settings.hft_contract_address) {
+        (Some(pool), Some(receiver)) => (pool, receiver),
+        _ => return Err(HFTError::MissingFlashloanPool),
+    };
 
     // Calculate dynamic loan amount
-    let flashloan_amount = calculate_dynamic_loan_amount(expected_profit, gas_fee, slippage);
+    let flashloan_amount = arbitrage::calculate_dynamic_loan_amount(settings.expected_profit, settings.gas_fee, settings.slippage_tolerance);
+    let flashloan_amount = allocator::cap_to_allocation(&web3, "hft", asset, flashloan_amount).await;
 
     let web3_clone = web3.clone();
     task::spawn(async move {
         info!("Starting HFT module: {} with flash loan amount: {}", module, flashloan_amount);
 
         // Execute flash loan for HFT
-        match request_flash_loan(&web3_clone, asset, flashloan_amount).await {
+        match request_flash_loan(&web3_clone, pool, receiver, asset, flashloan_amount).await {
             Ok(_) => {
                 info!("Flash loan successful for HFT module");
 
                 // ** HFT Strategy: Executing a Trade based on market conditions **
-                match execute_trade(web3_clone.clone(), asset).await {
+                match execute_trade(web3_clone.clone(), asset, quote_token, flashloan_amount, slippage_tolerance).await {
                     Ok(_) => info!("HFT strategy executed successfully"),
                     Err(e) => error!("HFT strategy execution failed: {}", e),
                 }
@@ -103,70 +194,133 @@ pub async fn execute_hft(
         }
     })
     .await
-    .map_err(|e| HFTError::JoinError(e))?;
+    .map_err(HFTError::JoinError)?;
 
     Ok(())
 }
 
-// HFT Trading Logic: Execute the actual trade after flash loan is received
+// HFT Trading Logic: Execute the actual trade after flash loan is received, on whichever configured
+// DEX quotes the asset best. The swap must return the venue's quote less the slippage tolerance
 pub async fn execute_trade(
-    web3: Arc<web3::Web3<web3::transports::Http>>,
-    asset: Address
+    web3: Arc<web3::Web3<FailoverTransport>>,
+    asset: Address,
+    quote_token: Address,
+    amount_in: U256,
+    slippage_tolerance: f64,
 ) -> Result<(), HFTError> {
-    let uniswap_router_contract = Contract::from_json(
-        web3.eth(),
-        "UNISWAP_ROUTER_ADDRESS".parse().unwrap(),
-        include_bytes!("abi/uniswap_router_abi.json"),
-    )?;
-
-    let amount_in: U256 = U256::from(1000);  // Example amount
-    let gas_limit = U256::from(300000);  // Example gas limit
-    let path = vec![asset, "0xTOKEN_B_ADDRESS".parse().unwrap()];  // Example trade path
+    let (venue, quoted) = venues::best_quote(&web3, asset, quote_token, amount_in)
+        .await?
+        .ok_or(HFTError::NoVenue(asset, quote_token))?;
+    let min_out = domain::min_out(slippage::adjust(venue.pool, quoted), Some(slippage_tolerance));
 
-    let result = uniswap_router_contract
-        .call("swapExactTokensForTokens", (amount_in, U256::from(1), path, "YOUR_ADDRESS".parse().unwrap(), U256::from(3000000000u64)), Address::zero(), Options::default())
-        .await;
+    let sender = TxSender::from_config(&web3).await?;
+    let deadline = U256::from(Utc::now().timestamp() + 600);
+    let calls = venue
+        .swap_calls(asset, quote_token, amount_in, min_out, sender.address(), deadline)?;
 
-    match result {
-        Ok(tx) => {
-            info!("HFT trade executed successfully: {:?}", tx);
-            Ok(())
+    let mut sent: Vec<H256> = Vec::new();
+    for call in calls {
+        // The swap spends the approval, so it is only simulated (and its gas estimated) once the
+        // approval is mined
+        if let Some(previous) = sent.last().copied() {
+            match receipt_events::wait_for_receipt(&web3, previous).await? {
+                Some(receipt) if receipt.status == Some(U64::from(1)) => {}
+                _ => return Err(HFTError::LegFailed(previous)),
+            }
         }
-        Err(e) => {
-            error!("HFT trade execution failed: {:?}", e);
-            Err(HFTError::ContractError(e))
+        // Gas is left to the sender, which estimates it from a simulation of the call
+        let request = TransactionRequest {
+            from: sender.address(),
+            to: Some(call.to),
+            value: Some(call.value),
+            data: Some(Bytes(call.data)),
+            ..Default::default()
+        };
+        match sender.send("hft", request).await {
+            Ok(tx) => {
+                info!("HFT trade on {} executed successfully: {:?}", venue.label(), tx);
+                sent.push(tx);
+            }
+            Err(e) => {
+                error!("HFT trade execution failed: {:?}", e);
+                return Err(HFTError::SignerError(e));
+            }
         }
     }
+    allocator::record_realized(&web3, "hft", OpportunitySource::ScheduledScan, sender.address(), &[asset, quote_token], &sent).await;
+    Ok(())
 }
 
-// Flash Loan Execution Logic
+// Flash Loan Execution Logic: borrow from the Aave pool into the receiving HFT contract
 pub async fn request_flash_loan(
-    web3: &web3::Web3<web3::transports::Http>,
+    web3: &web3::Web3<FailoverTransport>,
+    pool: Address,
+    receiver: Address,
     asset: Address,
     amount: U256
 ) -> Result<(), HFTError> {
     let aave_flashloan_contract = Contract::from_json(
         web3.eth(),
-        "AAVE_FLASHLOAN_CONTRACT_ADDRESS".parse().unwrap(),
+        pool,
         include_bytes!("abi/aave_flashloan_abi.json"),
     )?;
 
+    let sender = TxSender::from_config(web3).await?;
+    // receiver, assets, amounts, interest rate modes (0: repay in the same transaction), on behalf of,
+    // callback params, referral code
     let params = (
+        receiver,
         vec![asset],
         vec![amount],
-        vec![0],
-        "YOUR_ADDRESS".parse().unwrap(),
-        vec![0u8],
+        vec![U256::zero()],
+        sender.address(),
+        Vec::<u8>::new(),
+        0u16,
     );
 
-    let tx = aave_flashloan_contract
-        .call("flashLoan", params, Address::zero(), Options::default())
+    let tx = sender
+        .call("hft", &aave_flashloan_contract, "flashLoan", params, Options::default())
         .await?;
 
     info!("Flash loan executed: {:?}", tx);
     Ok(())
 }
 
+// Signal-driven HFT under the orchestrator; the signal sources live as long as the strategy so
+// external signals aren't lost between scans
+pub struct HftStrategy {
+    settings: HftConfig,
+    sources: Mutex<Vec<Box<dyn Signal>>>,
+}
+
+impl HftStrategy {
+    pub fn new(settings: HftConfig) -> Self {
+        let sources = Mutex::new(signal_sources(settings.asset));
+        HftStrategy { settings, sources }
+    }
+}
+
+#[async_trait]
+impl Strategy for HftStrategy {
+    fn name(&self) -> &'static str {
+        "hft"
+    }
+
+    fn config(&self) -> Value {
+        self.settings.to_value()
+    }
+
+    async fn scan(&self, web3: &web3::Web3<FailoverTransport>) -> Result<Vec<Opportunity>, StrategyError> {
+        let price = get_asset_price(Arc::new(web3.clone()), self.settings.asset, self.settings.quote_token).await?;
+        let found = poll_signals(&mut self.sources.lock().await, price);
+        Ok(if found { vec![Opportunity { asset: Some(self.settings.asset), ..Default::default() }] } else { Vec::new() })
+    }
+
+    async fn execute(&self, web3: &web3::Web3<FailoverTransport>, _opportunity: Opportunity) -> Result<(), StrategyError> {
+        Ok(execute_hft(Arc::new(web3.clone()), &self.settings).await?)
+    }
+}
+
 // Custom error type for HFT
 #[derive(Error, Debug)]
 pub enum HFTError {
@@ -174,8 +328,26 @@ pub enum HFTError {
     Web3Error(#[from] web3::Error),
     #[error("Contract error: {0}")]
     ContractError(#[from] web3::contract::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Signer error: {0}")]
+    SignerError(#[from] SignerError),
     #[error("Join error: {0}")]
     JoinError(#[from] tokio::task::JoinError),
+    #[error("Venue error: {0}")]
+    VenueError(#[from] VenueError),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Arbitrage error: {0}")]
+    ArbitrageError(#[from] ArbitrageError),
+    #[error("No configured venue quotes {0:?} -> {1:?}")]
+    NoVenue(Address, Address),
+    #[error("aave_pool_address and hft_contract_address must be configured for HFT flash loans")]
+    MissingFlashloanPool,
+    #[error("Receipt error: {0}")]
+    ReceiptError(#[from] ReceiptEventsError),
+    #[error("Trade leg {0:?} reverted or was not mined; the rest of the trade was not sent")]
+    LegFailed(H256),
 }
 
 // Implement conversion for HFTError to Web3 error
@@ -185,8 +357,3 @@ impl From<HFTError> for web3::Error {
     }
 }
 
-// Helper function to convert string to Address
-fn str_to_address(address: &str) -> Address {
-    Address::from_str(address).unwrap()
-}
-
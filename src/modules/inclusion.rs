@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use web3::types::{Address, BlockId, BlockNumber, Transaction, U256};
+use web3::Transport;
+use tokio::time::Duration;
+
+use crate::modules::venues;
+
+// Recent blocks' fee samples and per-decile inclusion latencies kept in memory
+const MAX_LATENCY_SAMPLES: usize = 500;
+const FEE_REFRESH: Duration = Duration::from_secs(12);
+
+// Upper bounds (ms) of the first-seen-to-inclusion histogram buckets; slower inclusions land in a
+// final overflow bucket
+const LATENCY_BUCKETS_MS: [i64; 10] = [250, 500, 1000, 2000, 4000, 8000, 12000, 24000, 36000, 60000];
+
+// Load the sandwich configuration (inclusion thresholds live there)
+fn load_sandwich_config() -> Value {
+    let config_path = "config/sandwich_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read sandwich config file");
+    serde_json::from_str(&config_data).expect("Unable to parse sandwich config file")
+}
+
+// Inputs for predicting whether a pending transaction lands in the next block
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InclusionFeatures {
+    // Share of recently included transactions paying a lower priority fee (0.0 - 1.0)
+    pub gas_price_percentile: f64,
+    // Pending nonce minus the sender's confirmed nonce; above zero means earlier txs must land first
+    pub nonce_gap: u64,
+    // Whether the max fee still covers the worst-case next base fee (+12.5%)
+    pub covers_next_base_fee: bool,
+    // Median blocks-to-inclusion of past transactions in the same fee percentile decile
+    pub median_latency_blocks: Option<f64>,
+    pub latency_samples: usize,
+}
+
+// Priority fees paid in recent blocks plus the latest base fee
+#[derive(Default)]
+struct FeeDistribution {
+    priority_fees: Vec<U256>,
+    base_fee: U256,
+    refreshed: Option<Instant>,
+}
+
+fn fee_distribution() -> &'static Mutex<FeeDistribution> {
+    static DISTRIBUTION: OnceLock<Mutex<FeeDistribution>> = OnceLock::new();
+    DISTRIBUTION.get_or_init(|| Mutex::new(FeeDistribution::default()))
+}
+
+fn latency_history() -> &'static Mutex<HashMap<usize, VecDeque<u64>>> {
+    static HISTORY: OnceLock<Mutex<HashMap<usize, VecDeque<u64>>>> = OnceLock::new();
+    HISTORY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Time from first sight in the mempool to inclusion for one venue and fee decile
+#[derive(Clone, Debug, Default)]
+struct LatencyHistogram {
+    // One count per bucket in LATENCY_BUCKETS_MS plus the overflow bucket
+    buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    count: u64,
+    sum_ms: i64,
+    max_ms: i64,
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, pending_ms: i64) {
+        let bucket = LATENCY_BUCKETS_MS.iter().position(|bound| pending_ms <= *bound).unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum_ms += pending_ms;
+        self.max_ms = self.max_ms.max(pending_ms);
+    }
+
+    // Upper bound of the bucket holding the given quantile, the max for the overflow bucket
+    fn quantile_ms(&self, quantile: f64) -> Option<i64> {
+        if self.count == 0 {
+            return None;
+        }
+        let target = (self.count as f64 * quantile).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return Some(LATENCY_BUCKETS_MS.get(bucket).copied().unwrap_or(self.max_ms).min(self.max_ms));
+            }
+        }
+        Some(self.max_ms)
+    }
+
+    fn to_json(&self) -> Value {
+        let mut buckets: Vec<Value> = LATENCY_BUCKETS_MS
+            .iter()
+            .zip(self.buckets.iter())
+            .map(|(bound, count)| json!({ "le_ms": bound, "count": count }))
+            .collect();
+        buckets.push(json!({ "le_ms": null, "count": self.buckets[LATENCY_BUCKETS_MS.len()] }));
+        json!({
+            "count": self.count,
+            "mean_ms": self.sum_ms as f64 / self.count.max(1) as f64,
+            "p50_ms": self.quantile_ms(0.5),
+            "p90_ms": self.quantile_ms(0.9),
+            "max_ms": self.max_ms,
+            "buckets": buckets,
+        })
+    }
+}
+
+// Histograms keyed by venue label and fee decile
+fn latency_histograms() -> &'static Mutex<HashMap<(String, usize), LatencyHistogram>> {
+    static HISTOGRAMS: OnceLock<Mutex<HashMap<(String, usize), LatencyHistogram>>> = OnceLock::new();
+    HISTOGRAMS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Tip a transaction offers above the given base fee
+fn priority_fee(tx: &Transaction, base_fee: U256) -> U256 {
+    match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+        (Some(max_fee), Some(max_tip)) => max_tip.min(max_fee.saturating_sub(base_fee)),
+        _ => tx.gas_price.unwrap_or_default().saturating_sub(base_fee),
+    }
+}
+
+fn max_fee(tx: &Transaction) -> U256 {
+    tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default()
+}
+
+// Reload the fee sample from the last few blocks when it is older than a block time
+async fn refresh_fee_distribution<T: Transport>(web3: &web3::Web3<T>, sample_blocks: u64) -> Result<(), web3::Error> {
+    let fresh = fee_distribution().lock().unwrap().refreshed.map(|at| at.elapsed() < FEE_REFRESH).unwrap_or(false);
+    if fresh {
+        return Ok(());
+    }
+
+    let latest = web3.eth().block_number().await?.as_u64();
+    let mut priority_fees = Vec::new();
+    let mut base_fee = U256::zero();
+    for number in latest.saturating_sub(sample_blocks.saturating_sub(1))..=latest {
+        if let Some(block) = web3.eth().block_with_txs(BlockId::Number(BlockNumber::Number(number.into()))).await? {
+            let block_base_fee = block.base_fee_per_gas.unwrap_or_default();
+            priority_fees.extend(block.transactions.iter().map(|tx| priority_fee(tx, block_base_fee)));
+            base_fee = block_base_fee;
+        }
+    }
+    priority_fees.sort();
+
+    let mut distribution = fee_distribution().lock().unwrap();
+    distribution.priority_fees = priority_fees;
+    distribution.base_fee = base_fee;
+    distribution.refreshed = Some(Instant::now());
+    Ok(())
+}
+
+// Percentile of a priority fee within the recent sample
+fn fee_percentile(fee: U256) -> f64 {
+    let distribution = fee_distribution().lock().unwrap();
+    if distribution.priority_fees.is_empty() {
+        return 0.0;
+    }
+    let below = distribution.priority_fees.partition_point(|sample| *sample < fee);
+    below as f64 / distribution.priority_fees.len() as f64
+}
+
+fn decile(percentile: f64) -> usize {
+    ((percentile * 10.0) as usize).min(9)
+}
+
+// Record how many blocks an observed transaction waited, keyed by its fee decile at first sight
+pub fn record_inclusion_latency(gas_price_percentile: f64, blocks_pending: u64) {
+    let mut history = latency_history().lock().unwrap();
+    let samples = history.entry(decile(gas_price_percentile)).or_default();
+    samples.push_back(blocks_pending);
+    if samples.len() > MAX_LATENCY_SAMPLES {
+        samples.pop_front();
+    }
+}
+
+// Record how long an observed transaction took from our first sight of it to inclusion, keyed by
+// the venue it called (its router, "other" for anything else) and its fee decile at first sight
+pub fn record_pending_time(to: Option<Address>, gas_price_percentile: f64, pending_ms: i64) {
+    let venue = to.and_then(venues::router_label).unwrap_or_else(|| "other".to_string());
+    let mut histograms = latency_histograms().lock().unwrap();
+    histograms.entry((venue, decile(gas_price_percentile))).or_default().record(pending_ms.max(0));
+}
+
+// First-seen-to-inclusion histograms per venue and fee decile, for the dashboard
+pub fn latency_snapshot() -> Value {
+    let histograms = latency_histograms().lock().unwrap();
+    let mut venues: HashMap<&String, HashMap<String, Value>> = HashMap::new();
+    for ((venue, decile), histogram) in histograms.iter() {
+        let fee_level = format!("p{}-p{}", decile * 10, decile * 10 + 10);
+        venues.entry(venue).or_default().insert(fee_level, histogram.to_json());
+    }
+    json!(venues)
+}
+
+fn median_latency(gas_price_percentile: f64) -> (Option<f64>, usize) {
+    let history = latency_history().lock().unwrap();
+    let mut samples: Vec<u64> = history.get(&decile(gas_price_percentile)).map(|samples| samples.iter().copied().collect()).unwrap_or_default();
+    if samples.is_empty() {
+        return (None, 0);
+    }
+    samples.sort();
+    (Some(samples[samples.len() / 2] as f64), samples.len())
+}
+
+// Fee percentile of a transaction against the current sample, refreshing the sample if stale
+pub async fn gas_price_percentile<T: Transport>(web3: &web3::Web3<T>, tx: &Transaction) -> Result<f64, web3::Error> {
+    let sample_blocks = load_sandwich_config()["inclusion_fee_sample_blocks"].as_u64().unwrap_or(3);
+    refresh_fee_distribution(web3, sample_blocks).await?;
+    let base_fee = fee_distribution().lock().unwrap().base_fee;
+    Ok(fee_percentile(priority_fee(tx, base_fee)))
+}
+
+// Compute the inclusion features for a pending transaction
+pub async fn extract_features<T: Transport>(web3: &web3::Web3<T>, tx: &Transaction) -> Result<InclusionFeatures, web3::Error> {
+    let gas_price_percentile = gas_price_percentile(web3, tx).await?;
+    let base_fee = fee_distribution().lock().unwrap().base_fee;
+
+    let nonce_gap = match tx.from {
+        Some(sender) => {
+            let confirmed = web3.eth().transaction_count(sender, Some(BlockNumber::Latest)).await?;
+            tx.nonce.saturating_sub(confirmed).low_u64()
+        }
+        None => 0,
+    };
+
+    let next_base_fee = base_fee + base_fee / 8;
+    let (median_latency_blocks, latency_samples) = median_latency(gas_price_percentile);
+
+    Ok(InclusionFeatures {
+        gas_price_percentile,
+        nonce_gap,
+        covers_next_base_fee: max_fee(tx) >= next_base_fee,
+        median_latency_blocks,
+        latency_samples,
+    })
+}
+
+// Thresholds the next-block heuristic applies to the features
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InclusionThresholds {
+    pub min_percentile: f64,
+    pub max_latency_blocks: f64,
+    pub min_samples: usize,
+}
+
+impl InclusionThresholds {
+    pub fn from_config() -> Self {
+        let config = load_sandwich_config();
+        InclusionThresholds {
+            min_percentile: config["inclusion_min_percentile"].as_f64().unwrap_or(0.5),
+            max_latency_blocks: config["inclusion_max_latency_blocks"].as_f64().unwrap_or(1.0),
+            min_samples: config["inclusion_min_samples"].as_u64().unwrap_or(20) as usize,
+        }
+    }
+}
+
+// Heuristic: the transaction is expected in the next block only if nothing blocks it and it pays like
+// transactions that usually land immediately
+pub fn likely_next_block(features: &InclusionFeatures) -> bool {
+    likely_next_block_with(features, &InclusionThresholds::from_config())
+}
+
+pub fn likely_next_block_with(features: &InclusionFeatures, thresholds: &InclusionThresholds) -> bool {
+    if features.nonce_gap > 0 || !features.covers_next_base_fee {
+        return false;
+    }
+    match features.median_latency_blocks {
+        // Enough history for this fee level: trust it over the raw percentile
+        Some(latency) if features.latency_samples >= thresholds.min_samples => latency <= thresholds.max_latency_blocks,
+        _ => features.gas_price_percentile >= thresholds.min_percentile,
+    }
+}
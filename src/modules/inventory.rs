@@ -0,0 +1,403 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use web3::contract::{Contract, Options};
+use web3::types::{Address, H256, U256};
+use log::{error, info, warn};
+use thiserror::Error;
+use chrono::Utc;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::ens;
+use crate::modules::execution::{self, ExecutionBackend, ExecutionError};
+use crate::modules::multicall::{self, MulticallError};
+use crate::modules::providers::FailoverTransport;
+
+// Load the inventory configuration
+fn load_inventory_config() -> Value {
+    let config_path = "config/inventory_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read inventory config file");
+    serde_json::from_str(&config_data).expect("Unable to parse inventory config file")
+}
+
+// A held token with its balance and value in the quote token
+#[derive(Clone, Debug)]
+pub struct Holding {
+    pub token: Address,
+    pub symbol: String,
+    pub balance: U256,
+    pub value: U256,
+    pub target_weight: Option<f64>,
+}
+
+// A trade needed to bring the portfolio back to its targets
+#[derive(Clone, Debug)]
+pub struct RebalanceTrade {
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: U256,
+    pub value: U256,
+    pub reason: String,
+}
+
+// Read a token balance for the holder
+pub async fn get_token_balance(web3: &web3::Web3<FailoverTransport>, token: Address, holder: Address) -> Result<U256, InventoryError> {
+    let erc20 = Contract::from_json(web3.eth(), token, include_bytes!("abi/erc20_abi.json"))?;
+    let balance: U256 = erc20.query("balanceOf", holder, None, Options::default(), None).await?;
+    Ok(balance)
+}
+
+// Value an amount of token in the quote token via the router's getAmountsOut
+pub async fn value_in_quote(
+    router: &Contract<FailoverTransport>,
+    token: Address,
+    quote_token: Address,
+    amount: U256,
+) -> Result<U256, InventoryError> {
+    if token == quote_token || amount.is_zero() {
+        return Ok(amount);
+    }
+
+    let amounts: Vec<U256> = router
+        .query("getAmountsOut", (amount, vec![token, quote_token]), None, Options::default(), None)
+        .await?;
+    Ok(amounts.last().cloned().unwrap_or_default())
+}
+
+// Snapshot balances and quote-token values for all target and tracked tokens
+pub async fn snapshot_holdings(
+    web3: &web3::Web3<FailoverTransport>,
+    config: &Value,
+    holder: Address,
+) -> Result<Vec<Holding>, InventoryError> {
+    let quote_token = parse_address(config, "quote_token")?;
+    let router = Contract::from_json(web3.eth(), parse_address(config, "router_address")?, include_bytes!("abi/uniswap_router_abi.json"))?;
+
+    let mut holdings = Vec::new();
+    let targets = config["targets"].as_array().cloned().unwrap_or_default();
+    let tracked = config["tracked_tokens"].as_array().cloned().unwrap_or_default();
+
+    let entries: Vec<&Value> = targets.iter().chain(tracked.iter()).collect();
+    let tokens = entries
+        .iter()
+        .map(|entry| ens::parse_address(entry["token"].as_str().unwrap_or_default()).ok_or_else(|| InventoryError::InvalidAddress(entry["token"].to_string())))
+        .collect::<Result<Vec<Address>, InventoryError>>()?;
+    // Every balance in one multicall; a token whose balanceOf didn't come back is read on its own
+    let queries: Vec<(Address, Address)> = tokens.iter().map(|token| (*token, holder)).collect();
+    let balances = multicall::token_balances(web3, &queries).await?;
+
+    for ((entry, token), batched) in entries.into_iter().zip(tokens).zip(balances) {
+        let balance = match batched {
+            Some(balance) => balance,
+            None => get_token_balance(web3, token, holder).await?,
+        };
+        let value = match value_in_quote(&router, token, quote_token, balance).await {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Unable to value {}: {}", entry["symbol"], e);
+                U256::zero()
+            }
+        };
+
+        holdings.push(Holding {
+            token,
+            symbol: entry["symbol"].as_str().unwrap_or("?").to_string(),
+            balance,
+            value,
+            target_weight: entry["target_weight"].as_f64(),
+        });
+    }
+
+    Ok(holdings)
+}
+
+// Wallet balances every strategy sees: the latest snapshot, adjusted by trades since, less what
+// strategies have reserved for trades in flight
+#[derive(Default)]
+struct InventoryView {
+    balances: HashMap<Address, U256>,
+    reserved: HashMap<Address, U256>,
+    // Strategy and signed amount of the last change to each token since the snapshot
+    changes: HashMap<Address, (String, i128)>,
+    refreshed_at: Option<String>,
+}
+
+fn inventory_view() -> &'static Mutex<InventoryView> {
+    static VIEW: OnceLock<Mutex<InventoryView>> = OnceLock::new();
+    VIEW.get_or_init(|| Mutex::new(InventoryView::default()))
+}
+
+// Replace the shared view's balances with a fresh snapshot
+fn publish(holdings: &[Holding]) {
+    let mut view = inventory_view().lock().unwrap();
+    view.balances = holdings.iter().map(|holding| (holding.token, holding.balance)).collect();
+    view.changes.clear();
+    view.refreshed_at = Some(Utc::now().to_rfc3339());
+}
+
+// Snapshot the execution wallet's tracked tokens into the shared view
+pub async fn refresh_view(web3: &web3::Web3<FailoverTransport>) -> Result<(), InventoryError> {
+    let config = load_inventory_config();
+    let backend = ExecutionBackend::from_config(web3)?;
+    let holdings = snapshot_holdings(web3, &config, backend.recipient()).await?;
+    publish(&holdings);
+    Ok(())
+}
+
+// Amount of a token held and not reserved by another strategy
+pub fn available(token: Address) -> U256 {
+    let view = inventory_view().lock().unwrap();
+    let balance = view.balances.get(&token).copied().unwrap_or_default();
+    balance.saturating_sub(view.reserved.get(&token).copied().unwrap_or_default())
+}
+
+// Inventory set aside for one trade; released when dropped
+pub struct Reservation {
+    token: Address,
+    amount: U256,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let mut view = inventory_view().lock().unwrap();
+        if let Some(reserved) = view.reserved.get_mut(&self.token) {
+            *reserved = reserved.saturating_sub(self.amount);
+        }
+    }
+}
+
+// Set aside held inventory for a trade so no other strategy spends it at the same time; None when
+// there isn't enough available
+pub fn reserve(strategy: &str, token: Address, amount: U256) -> Option<Reservation> {
+    let mut view = inventory_view().lock().unwrap();
+    let balance = view.balances.get(&token).copied().unwrap_or_default();
+    let reserved = view.reserved.entry(token).or_default();
+    if amount.is_zero() || balance.saturating_sub(*reserved) < amount {
+        return None;
+    }
+    *reserved += amount;
+    info!("{} reserved {} of {:?} from inventory", strategy, amount, token);
+    Some(Reservation { token, amount })
+}
+
+// Apply a strategy's trade result to the view so others see it before the next snapshot
+pub fn record_change(strategy: &str, token: Address, delta: i128) {
+    let mut view = inventory_view().lock().unwrap();
+    let balance = view.balances.entry(token).or_default();
+    let magnitude = U256::from(delta.unsigned_abs());
+    *balance = if delta >= 0 { balance.saturating_add(magnitude) } else { balance.saturating_sub(magnitude) };
+    view.changes.insert(token, (strategy.to_string(), delta));
+}
+
+// Balance, reservations and last change per token, for the dashboard
+pub fn view() -> Value {
+    let view = inventory_view().lock().unwrap();
+    let tokens: HashMap<String, Value> = view
+        .balances
+        .iter()
+        .map(|(token, balance)| {
+            let reserved = view.reserved.get(token).copied().unwrap_or_default();
+            let change = view.changes.get(token).map(|(strategy, delta)| json!({ "strategy": strategy, "delta": delta.to_string() }));
+            (format!("{:?}", token), json!({
+                "balance": balance.to_string(),
+                "reserved": reserved.to_string(),
+                "available": balance.saturating_sub(reserved).to_string(),
+                "last_change": change,
+            }))
+        })
+        .collect();
+    json!({ "refreshed_at": view.refreshed_at, "tokens": tokens })
+}
+
+// Keep the shared view current between the inventory manager's drift checks
+pub async fn run_inventory_view(web3: web3::Web3<FailoverTransport>) {
+    let refresh_interval = load_inventory_config()["view_refresh_secs"].as_u64().unwrap_or(30);
+    loop {
+        if let Err(e) = refresh_view(&web3).await {
+            error!("Inventory view refresh failed: {}", e);
+        }
+        sleep(Duration::from_secs(refresh_interval)).await;
+    }
+}
+
+// Work out the trades that bring base-asset weights back inside the drift threshold
+pub fn plan_rebalance(holdings: &[Holding], drift_threshold: f64, dust_value: U256) -> Vec<RebalanceTrade> {
+    let total: U256 = holdings.iter().fold(U256::zero(), |acc, holding| acc + holding.value);
+    if total.is_zero() {
+        return Vec::new();
+    }
+    let total_f64 = total.as_u128() as f64;
+
+    // Deviation of each base asset from its target, in quote-token value (positive = overweight)
+    let mut deviations: Vec<(&Holding, f64)> = holdings
+        .iter()
+        .filter_map(|holding| {
+            holding
+                .target_weight
+                .map(|target| (holding, holding.value.as_u128() as f64 - target * total_f64))
+        })
+        .collect();
+
+    let most_underweight = match deviations
+        .iter()
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+    {
+        Some((holding, _)) => holding.token,
+        None => return Vec::new(),
+    };
+
+    let mut trades = Vec::new();
+
+    // Stray tokens accumulated by strategies are sold into the most underweight base asset
+    for holding in holdings.iter().filter(|holding| holding.target_weight.is_none()) {
+        if holding.value > dust_value {
+            trades.push(RebalanceTrade {
+                token_in: holding.token,
+                token_out: most_underweight,
+                amount_in: holding.balance,
+                value: holding.value,
+                reason: format!("sell stray {}", holding.symbol),
+            });
+        }
+    }
+
+    // Overweight base assets sell their excess into the most underweight one
+    deviations.retain(|(holding, deviation)| {
+        holding.token != most_underweight && *deviation / total_f64 > drift_threshold
+    });
+    for (holding, excess_value) in deviations {
+        let fraction = (excess_value / holding.value.as_u128().max(1) as f64).min(1.0);
+        let amount_in = U256::from((holding.balance.as_u128() as f64 * fraction) as u128);
+        trades.push(RebalanceTrade {
+            token_in: holding.token,
+            token_out: most_underweight,
+            amount_in,
+            value: U256::from(excess_value as u128),
+            reason: format!("{} overweight by {:.2}%", holding.symbol, excess_value / total_f64 * 100.0),
+        });
+    }
+
+    trades
+}
+
+// Append a rebalance record to the rebalance log (kept apart from strategy PnL)
+fn record_rebalance(config: &Value, trade: &RebalanceTrade, submission: Option<H256>) {
+    let log_path = config["rebalance_log_path"].as_str().unwrap_or("data/rebalances.jsonl");
+    if let Some(parent) = std::path::Path::new(log_path).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    let record = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "token_in": trade.token_in,
+        "token_out": trade.token_out,
+        "amount_in": trade.amount_in.to_string(),
+        "quote_value": trade.value.to_string(),
+        "reason": trade.reason,
+        "submission": submission,
+    });
+
+    match OpenOptions::new().create(true).append(true).open(log_path) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write rebalance record: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open rebalance log {}: {}", log_path, e),
+    }
+}
+
+// Run one drift check and rebalance if needed
+pub async fn check_and_rebalance(web3: &web3::Web3<FailoverTransport>) -> Result<Vec<RebalanceTrade>, InventoryError> {
+    let config = load_inventory_config();
+    let backend = ExecutionBackend::from_config(web3)?;
+    let holdings = snapshot_holdings(web3, &config, backend.recipient()).await?;
+    publish(&holdings);
+
+    let drift_threshold = config["drift_threshold"].as_f64().unwrap_or(0.05);
+    let dust_value = U256::from(config["dust_value"].as_u64().unwrap_or(0));
+    let trades = plan_rebalance(&holdings, drift_threshold, dust_value);
+
+    if trades.is_empty() {
+        info!("Inventory within drift threshold ({} holdings)", holdings.len());
+        return Ok(trades);
+    }
+
+    if !config["auto_rebalance"].as_bool().unwrap_or(false) {
+        for trade in &trades {
+            info!("Rebalance needed (manual mode): {}", trade.reason);
+        }
+        return Ok(trades);
+    }
+
+    let router = parse_address(&config, "router_address")?;
+    let slippage = config["slippage_tolerance"].as_f64().unwrap_or(0.005);
+    let deadline = U256::from(Utc::now().timestamp() + 600);
+
+    for trade in &trades {
+        let quote_router = Contract::from_json(web3.eth(), router, include_bytes!("abi/uniswap_router_abi.json"))?;
+        let expected_out = value_in_quote(&quote_router, trade.token_in, trade.token_out, trade.amount_in).await?;
+        let min_out = U256::from((expected_out.as_u128() as f64 * (1.0 - slippage)) as u128);
+
+        let calls = execution::approve_and_swap_calls(
+            router,
+            vec![trade.token_in, trade.token_out],
+            trade.amount_in,
+            min_out,
+            backend.recipient(),
+            deadline,
+        )?;
+
+        match backend.execute_calls(web3, "inventory", calls).await {
+            Ok(submission) => {
+                info!("Rebalance executed ({}): {:?}", trade.reason, submission);
+                record_rebalance(&config, trade, Some(submission));
+            }
+            Err(e) => {
+                error!("Rebalance failed ({}): {}", trade.reason, e);
+                record_rebalance(&config, trade, None);
+            }
+        }
+    }
+
+    Ok(trades)
+}
+
+// Periodic drift checks
+pub async fn run_inventory_manager(web3: web3::Web3<FailoverTransport>) {
+    let check_interval = load_inventory_config()["check_interval_secs"].as_u64().unwrap_or(3600);
+    loop {
+        if let Err(e) = check_and_rebalance(&web3).await {
+            error!("Inventory check failed: {}", e);
+        }
+        sleep(Duration::from_secs(check_interval)).await;
+    }
+}
+
+// Parse a required address field from the inventory config
+fn parse_address(config: &Value, field: &str) -> Result<Address, InventoryError> {
+    config[field]
+        .as_str()
+        .and_then(ens::parse_address)
+        .ok_or_else(|| InventoryError::InvalidAddress(field.to_string()))
+}
+
+// Custom error type for the inventory manager
+#[derive(Error, Debug)]
+pub enum InventoryError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Contract error: {0}")]
+    ContractError(#[from] web3::contract::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Multicall error: {0}")]
+    MulticallError(#[from] MulticallError),
+    #[error("Invalid address in inventory config: {0}")]
+    InvalidAddress(String),
+}
@@ -0,0 +1,199 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use web3::signing::keccak256;
+use web3::types::{Address, BlockNumber, FilterBuilder, Log, H256, U256};
+use log::{error, info, warn};
+use tokio::time::{sleep, Duration};
+
+use crate::modules::providers::FailoverTransport;
+use crate::modules::venues;
+
+// Load the sandwich configuration (JIT thresholds live there)
+fn load_sandwich_config() -> Value {
+    let config_path = "config/sandwich_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read sandwich config file");
+    serde_json::from_str(&config_data).expect("Unable to parse sandwich config file")
+}
+
+// How often large swaps on a pool were bracketed by JIT liquidity in the last scan
+#[derive(Clone, Debug, Default)]
+pub struct JitStats {
+    pub token_a: Address,
+    pub token_b: Address,
+    pub large_swaps: u64,
+    pub jit_swaps: u64,
+    pub tagged: bool,
+}
+
+impl JitStats {
+    pub fn jit_rate(&self) -> f64 {
+        if self.large_swaps == 0 {
+            0.0
+        } else {
+            self.jit_swaps as f64 / self.large_swaps as f64
+        }
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<Address, JitStats>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<Address, JitStats>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn topic(signature: &str) -> H256 {
+    H256::from(keccak256(signature.as_bytes()))
+}
+
+// Absolute value of a two's complement int256 word
+fn abs_int256(word: &[u8]) -> U256 {
+    let value = U256::from_big_endian(word);
+    if value.bit(255) {
+        (!value).overflowing_add(U256::one()).0
+    } else {
+        value
+    }
+}
+
+// Count large swaps and those absorbed by JIT liquidity: a Mint earlier in the same block and a Burn
+// later in the same block, by the same owner, in transactions other than the swap's
+pub fn count_jit_swaps(logs: &[Log]) -> (u64, u64) {
+    let mint = topic("Mint(address,address,int24,int24,uint128,uint256,uint256)");
+    let burn = topic("Burn(address,int24,int24,uint128,uint256,uint256)");
+    let swap = topic("Swap(address,address,int256,int256,uint160,uint128,int24)");
+
+    // Swap size is the larger absolute token amount; "large" means above the window median
+    let mut sizes: Vec<U256> = logs
+        .iter()
+        .filter(|log| log.topics.first() == Some(&swap) && log.data.0.len() >= 64)
+        .map(|log| abs_int256(&log.data.0[0..32]).max(abs_int256(&log.data.0[32..64])))
+        .collect();
+    if sizes.is_empty() {
+        return (0, 0);
+    }
+    sizes.sort();
+    let median = sizes[sizes.len() / 2];
+
+    let mut by_block: HashMap<u64, Vec<&Log>> = HashMap::new();
+    for log in logs {
+        if let Some(block) = log.block_number {
+            by_block.entry(block.as_u64()).or_default().push(log);
+        }
+    }
+
+    let (mut large_swaps, mut jit_swaps) = (0, 0);
+    for block_logs in by_block.values_mut() {
+        block_logs.sort_by_key(|log| log.log_index.unwrap_or_default());
+
+        for (position, log) in block_logs.iter().enumerate() {
+            if log.topics.first() != Some(&swap) || log.data.0.len() < 64 {
+                continue;
+            }
+            let size = abs_int256(&log.data.0[0..32]).max(abs_int256(&log.data.0[32..64]));
+            if size < median {
+                continue;
+            }
+            large_swaps += 1;
+
+            let mut minted_before = block_logs[..position]
+                .iter()
+                .filter(|earlier| earlier.topics.first() == Some(&mint) && earlier.transaction_hash != log.transaction_hash)
+                .filter_map(|earlier| earlier.topics.get(1).copied());
+            let bracketed = minted_before.any(|owner| {
+                block_logs[position + 1..].iter().any(|later| {
+                    later.topics.first() == Some(&burn)
+                        && later.topics.get(1) == Some(&owner)
+                        && later.transaction_hash != log.transaction_hash
+                })
+            });
+            if bracketed {
+                jit_swaps += 1;
+            }
+        }
+    }
+
+    (large_swaps, jit_swaps)
+}
+
+// Scan a V3 pool's recent Mint/Burn/Swap events and update its JIT tag
+pub async fn analyze_pool(web3: &web3::Web3<FailoverTransport>, pool: Address, token_a: Address, token_b: Address) -> Result<JitStats, web3::Error> {
+    let config = load_sandwich_config();
+    let lookback = config["jit_lookback_blocks"].as_u64().unwrap_or(5000);
+    let rate_threshold = config["jit_rate_threshold"].as_f64().unwrap_or(0.2);
+    let min_large_swaps = config["jit_min_large_swaps"].as_u64().unwrap_or(5);
+
+    let latest = web3.eth().block_number().await?.as_u64();
+    let filter = FilterBuilder::default()
+        .address(vec![pool])
+        .from_block(BlockNumber::Number(latest.saturating_sub(lookback).into()))
+        .to_block(BlockNumber::Number(latest.into()))
+        .topics(
+            Some(vec![
+                topic("Mint(address,address,int24,int24,uint128,uint256,uint256)"),
+                topic("Burn(address,int24,int24,uint128,uint256,uint256)"),
+                topic("Swap(address,address,int256,int256,uint160,uint128,int24)"),
+            ]),
+            None,
+            None,
+            None,
+        )
+        .build();
+    let logs = web3.eth().logs(filter).await?;
+
+    let (large_swaps, jit_swaps) = count_jit_swaps(&logs);
+    let mut stats = JitStats { token_a, token_b, large_swaps, jit_swaps, tagged: false };
+    stats.tagged = large_swaps >= min_large_swaps && stats.jit_rate() >= rate_threshold;
+
+    let previously_tagged = registry().lock().unwrap().get(&pool).map(|existing| existing.tagged).unwrap_or(false);
+    if stats.tagged && !previously_tagged {
+        warn!("Pool {:?} tagged as JIT-heavy: {}/{} large swaps absorbed", pool, jit_swaps, large_swaps);
+    } else if !stats.tagged && previously_tagged {
+        info!("Pool {:?} no longer JIT-heavy ({}/{} large swaps)", pool, jit_swaps, large_swaps);
+    }
+    registry().lock().unwrap().insert(pool, stats.clone());
+    Ok(stats)
+}
+
+// Whether any pool for this pair is tagged as JIT-heavy
+pub fn is_jit_pair(token_a: Address, token_b: Address) -> bool {
+    registry().lock().unwrap().values().any(|stats| {
+        stats.tagged
+            && ((stats.token_a == token_a && stats.token_b == token_b) || (stats.token_a == token_b && stats.token_b == token_a))
+    })
+}
+
+// JIT stats per pool for the dashboard
+pub fn snapshot() -> Value {
+    let pools: HashMap<String, Value> = registry()
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(pool, stats)| {
+            (format!("{:?}", pool), json!({
+                "token_a": stats.token_a,
+                "token_b": stats.token_b,
+                "large_swaps": stats.large_swaps,
+                "jit_swaps": stats.jit_swaps,
+                "jit_rate": stats.jit_rate(),
+                "tagged": stats.tagged,
+            }))
+        })
+        .collect();
+    json!(pools)
+}
+
+// Periodically rescan every V3 pool in the venue cache
+pub async fn run_jit_scanner(web3: web3::Web3<FailoverTransport>) {
+    let scan_interval = load_sandwich_config()["jit_scan_interval_secs"].as_u64().unwrap_or(900);
+
+    loop {
+        for (pool, token_a, token_b) in venues::cached_v3_pools() {
+            if let Err(e) = analyze_pool(&web3, pool, token_a, token_b).await {
+                error!("JIT analysis failed for pool {:?}: {}", pool, e);
+            }
+        }
+        sleep(Duration::from_secs(scan_interval)).await;
+    }
+}
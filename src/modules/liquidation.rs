@@ -1,22 +1,24 @@
 use web3::types::{H160, U256};
 use web3::contract::{Contract, Options};
-use web3::transports::Http;
 use serde_json::Value;
 use thiserror::Error;
-use std::fs;
 use tokio::time::{sleep, Duration};
-use log::{info, error};
+use log::{info, error, warn};
+use chrono::Utc;
+use async_trait::async_trait;
+use crate::modules::config::{LiquidationConfig, StrategyConfig};
+use crate::modules::domain;
+use crate::modules::ens;
+use crate::modules::execution::{self, Call, ExecutionBackend, ExecutionError, SwapOrder};
+use crate::modules::permit;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::sanctions;
+use crate::modules::signer::TxSender;
+use crate::modules::strategy::{Opportunity, Strategy, StrategyError};
 
 // Chainlink AggregatorV3Interface ABI (to fetch price from Chainlink price feed)
 const CHAINLINK_AGGREGATOR_ABI: &[u8] = include_bytes!("abi/chainlink_aggregator_abi.json");
 
-// Load configuration for liquidation
-fn load_liquidation_config() -> Value {
-    let config_path = "config/liquidation_config.json";
-    let config_data = fs::read_to_string(config_path).expect("Unable to read liquidation config file");
-    serde_json::from_str(&config_data).expect("Unable to parse liquidation config file")
-}
-
 // Custom error type for liquidation
 #[derive(Error, Debug)]
 pub enum LiquidationError {
@@ -28,8 +30,12 @@ pub enum LiquidationError {
     ABIError(#[from] web3::ethabi::Error),
     #[error("Join error: {0}")]
     JoinError(#[from] tokio::task::JoinError),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
     #[error("Retries exceeded for liquidation execution")]
     RetriesExceeded,
+    #[error("Borrower {0:?} is flagged by sanctions screening")]
+    SanctionedCounterparty(H160),
 }
 
 // Implement conversion for LiquidationError to Web3 error
@@ -40,41 +46,45 @@ impl From<LiquidationError> for web3::Error {
 }
 
 // Liquidation struct to hold both Aave and Compound settings
-struct Liquidation<'a> {
-    aave_pool: Contract<&'a Http>,
-    compound_comptroller: Contract<&'a Http>,
-    ctoken_collateral: Contract<&'a Http>,
+pub struct Liquidation<'a> {
+    web3: &'a web3::Web3<FailoverTransport>,
+    aave_pool: Contract<FailoverTransport>,
+    // Compound isn't deployed on every network (testnets); without it only Aave is tracked
+    compound_comptroller: Option<Contract<FailoverTransport>>,
 }
 
 impl<'a> Liquidation<'a> {
     // Initialize Liquidation struct with Aave and Compound contracts
-    pub fn new(web3: &'a web3::Web3<Http>, config: &Value) -> Result<Self, LiquidationError> {
-        let aave_pool_address: H160 = config["aave_pool"].as_str().unwrap().parse().expect("Invalid address");
-        let compound_comptroller_address: H160 = config["compound_comptroller"].as_str().unwrap().parse().expect("Invalid address");
-        let ctoken_collateral_address: H160 = config["ctoken_collateral"].as_str().unwrap().parse().expect("Invalid address");
-
-        let aave_pool = Contract::from_json(web3.eth(), aave_pool_address, include_bytes!("abi/aave_pool_abi.json"))?;
-        let compound_comptroller = Contract::from_json(web3.eth(), compound_comptroller_address, include_bytes!("abi/compound_comptroller_abi.json"))?;
-        let ctoken_collateral = Contract::from_json(web3.eth(), ctoken_collateral_address, include_bytes!("abi/ctoken_abi.json"))?;
+    pub fn new(web3: &'a web3::Web3<FailoverTransport>, settings: &LiquidationConfig) -> Result<Self, LiquidationError> {
+        let aave_pool = Contract::from_json(web3.eth(), settings.aave_pool_address, include_bytes!("abi/aave_pool_abi.json"))?;
+        let compound_comptroller = match settings.compound_comptroller_address {
+            Some(address) => Some(Contract::from_json(web3.eth(), address, include_bytes!("abi/compound_comptroller_abi.json"))?),
+            None => None,
+        };
 
-        Ok(Liquidation { aave_pool, compound_comptroller, ctoken_collateral })
+        Ok(Liquidation { web3, aave_pool, compound_comptroller })
     }
 
     // Track debt ratios across multiple protocols and check if the account is near liquidation
     pub async fn track_debt_ratios(&self, borrower_address: H160) -> Result<bool, LiquidationError> {
-        // Fetch health factor from Aave
-        let health_factor: U256 = self.aave_pool
-            .query("getHealthFactor", borrower_address, None, Options::default(), None)
+        // Fetch health factor from Aave (the last field of getUserAccountData)
+        let (_, _, _, _, _, health_factor): (U256, U256, U256, U256, U256, U256) = self.aave_pool
+            .query("getUserAccountData", borrower_address, None, Options::default(), None)
             .await
             .map_err(LiquidationError::ContractError)?;
 
         // Fetch the liquidity ratio from Compound (as an example, you would need the specific Compound method)
-        let liquidity_ratio: U256 = self.compound_comptroller
-            .query("getAccountLiquidity", borrower_address, None, Options::default(), None)
-            .await
-            .map_err(LiquidationError::ContractError)?;
+        let liquidity_ratio: Option<U256> = match &self.compound_comptroller {
+            Some(comptroller) => Some(
+                comptroller
+                    .query("getAccountLiquidity", borrower_address, None, Options::default(), None)
+                    .await
+                    .map_err(LiquidationError::ContractError)?,
+            ),
+            None => None,
+        };
 
-        let is_near_liquidation = health_factor < U256::from(1_000_000_000_000_000_000u128) || liquidity_ratio.is_zero();
+        let is_near_liquidation = health_factor < U256::from(1_000_000_000_000_000_000u128) || liquidity_ratio.is_some_and(|ratio| ratio.is_zero());
 
         Ok(is_near_liquidation)
     }
@@ -82,7 +92,7 @@ impl<'a> Liquidation<'a> {
     // Function to calculate profit from liquidating a borrower
     pub async fn calculate_liquidation_profit(
         &self,
-        collateral_asset: H160,
+        _collateral_asset: H160,
         debt_covered: U256,
         price_feed_address: H160
     ) -> Result<U256, LiquidationError> {
@@ -108,6 +118,8 @@ impl<'a> Liquidation<'a> {
             let result = self.execute_liquidation(borrower_address, debt_covered, collateral_asset).await;
             match result {
                 Ok(_) => return Ok(()),
+                // Retrying won't change the screening outcome
+                Err(e @ LiquidationError::SanctionedCounterparty(_)) => return Err(e),
                 Err(e) => {
                     error!("Liquidation failed: {}, attempt {}/{}", e, attempts + 1, max_retries);
                     attempts += 1;
@@ -125,15 +137,21 @@ impl<'a> Liquidation<'a> {
         &self,
         borrower_address: H160,
         debt_covered: U256,
-        collateral_asset: H160
+        _collateral_asset: H160
     ) -> Result<(), LiquidationError> {
-        let flashloan_result = self.request_flashloan(debt_covered).await?;
-        if flashloan_result.is_ok() {
-            info!("Executing liquidation for borrower: {:?}", borrower_address);
-            Ok(())
-        } else {
-            error!("Failed to request flashloan for liquidation");
-            Err(LiquidationError::ContractError(flashloan_result.unwrap_err()))
+        if let Some(screening) = sanctions::first_flagged(&[borrower_address], "liquidation_borrower").await {
+            info!("Skipping liquidation of {:?}: borrower is flagged ({})", borrower_address, screening.source);
+            return Err(LiquidationError::SanctionedCounterparty(borrower_address));
+        }
+        match self.request_flashloan(debt_covered).await {
+            Ok(()) => {
+                info!("Executing liquidation for borrower: {:?}", borrower_address);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to request flashloan for liquidation");
+                Err(e)
+            }
         }
     }
 
@@ -164,10 +182,172 @@ impl<'a> Liquidation<'a> {
 
     // Function to get asset price from Chainlink price feed
     async fn get_asset_price(&self, price_feed_address: H160) -> Result<U256, LiquidationError> {
-        let chainlink_contract = Contract::from_json(self.aave_pool.web3().eth(), price_feed_address, CHAINLINK_AGGREGATOR_ABI)?;
+        let chainlink_contract = Contract::from_json(self.web3.eth(), price_feed_address, CHAINLINK_AGGREGATOR_ABI)?;
         let price: U256 = chainlink_contract.query("latestAnswer", (), None, Options::default(), None).await?;
         Ok(price)
     }
 }
 
+// Liquidate one borrower with the configured Aave and Compound contracts
+pub async fn execute_liquidation(
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &LiquidationConfig,
+    borrower_address: H160,
+    debt_covered: U256,
+    collateral_asset: H160
+) -> Result<(), LiquidationError> {
+    Liquidation::new(web3, settings)?
+        .execute_liquidation_with_retry(borrower_address, debt_covered, collateral_asset, 3)
+        .await
+}
+
+// Liquidations of the configured borrower and scan_borrowers under the orchestrator
+pub struct LiquidationStrategy {
+    settings: LiquidationConfig,
+}
+
+impl LiquidationStrategy {
+    pub fn new(settings: LiquidationConfig) -> Self {
+        LiquidationStrategy { settings }
+    }
+
+    fn watched_borrowers(&self) -> Vec<H160> {
+        let listed = self.settings.extra.get("scan_borrowers").and_then(|borrowers| borrowers.as_array()).cloned().unwrap_or_default();
+        self.settings
+            .borrower_address
+            .into_iter()
+            .chain(listed.iter().filter_map(|borrower| borrower.as_str().and_then(ens::parse_address)))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Strategy for LiquidationStrategy {
+    fn name(&self) -> &'static str {
+        "liquidation"
+    }
+
+    fn config(&self) -> Value {
+        self.settings.to_value()
+    }
+
+    async fn scan(&self, web3: &web3::Web3<FailoverTransport>) -> Result<Vec<Opportunity>, StrategyError> {
+        let liquidation = Liquidation::new(web3, &self.settings)?;
+        let mut opportunities = Vec::new();
+        for borrower in self.watched_borrowers() {
+            if liquidation.track_debt_ratios(borrower).await? {
+                opportunities.push(Opportunity { target: Some(borrower), asset: self.settings.collateral_asset, ..Default::default() });
+            }
+        }
+        Ok(opportunities)
+    }
+
+    async fn execute(&self, web3: &web3::Web3<FailoverTransport>, opportunity: Opportunity) -> Result<(), StrategyError> {
+        let borrower = opportunity.target.ok_or(StrategyError::IncompleteOpportunity("borrower"))?;
+        let collateral_asset = opportunity.asset.ok_or(StrategyError::IncompleteOpportunity("collateral asset"))?;
+        Ok(execute_liquidation(web3, &self.settings, borrower, opportunity.amount, collateral_asset).await?)
+    }
+}
+
+// Swap seized collateral back into the debt asset (approve + swap batched when the backend supports it)
+pub async fn exit_seized_collateral(
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &LiquidationConfig,
+    collateral_asset: H160,
+    debt_asset: H160,
+    amount: U256
+) -> Result<(), LiquidationError> {
+    let exit_router = settings.exit_router_address;
+
+    let backend = ExecutionBackend::from_config(web3)?;
+    let deadline = U256::from(Utc::now().timestamp() + 600);
+
+    // A plain wallet sends approve and swap as two transactions; when the collateral supports
+    // permit, sign it off-chain and redeem it inside the swap so the exit is one transaction
+    if let ExecutionBackend::Direct { wallet } = &backend {
+        if let Some(call) = permit_exit_call(web3, settings, *wallet, collateral_asset, debt_asset, amount, deadline).await {
+            let submission = backend.execute_calls(web3, "liquidation", vec![call]).await?;
+            info!("Liquidation exit submitted with permit: {:?} ({:?} collateral)", submission, amount);
+            return Ok(());
+        }
+    }
 
+    // The exit is held to the router's quote less the slippage tolerance
+    let router = Contract::from_json(web3.eth(), exit_router, include_bytes!("abi/uniswap_router_abi.json"))?;
+    let amounts_out: Vec<U256> = router
+        .query("getAmountsOut", (amount, vec![collateral_asset, debt_asset]), None, Options::default(), None)
+        .await?;
+    let min_out = domain::min_out(amounts_out.last().copied().unwrap_or_default(), Some(settings.slippage_tolerance));
+
+    let calls = execution::approve_and_swap_calls(
+        exit_router,
+        vec![collateral_asset, debt_asset],
+        amount,
+        min_out,
+        backend.recipient(),
+        deadline,
+    )?;
+
+    let submission = backend.execute_calls(web3, "liquidation", calls).await?;
+    info!("Liquidation exit submitted: {:?} ({:?} collateral)", submission, amount);
+    Ok(())
+}
+
+// Permit + exactInputSingle through the permit exit router, or None when permit exits are off, the
+// collateral has no EIP-2612 permit, the fee tier can't be quoted or the signing key could not sign one
+async fn permit_exit_call(
+    web3: &web3::Web3<FailoverTransport>,
+    settings: &LiquidationConfig,
+    wallet: H160,
+    collateral_asset: H160,
+    debt_asset: H160,
+    amount: U256,
+    deadline: U256,
+) -> Option<Call> {
+    let config = settings.to_value();
+    if !config["permit_exits_enabled"].as_bool().unwrap_or(false) {
+        return None;
+    }
+    let router = config["permit_exit_router_address"].as_str().and_then(ens::parse_address)?;
+    let fee = config["permit_exit_fee"].as_u64().unwrap_or(3000) as u32;
+    let domain = permit::permit_domain(web3, collateral_asset).await?;
+
+    // The swap is held to the fee tier's quote less the slippage tolerance
+    let quoter_address = config["permit_exit_quoter_address"].as_str().and_then(ens::parse_address)?;
+    let quoter = Contract::from_json(web3.eth(), quoter_address, include_bytes!("abi/uniswap_v3_quoter_abi.json")).ok()?;
+    let quoted: U256 = match quoter
+        .query("quoteExactInputSingle", (collateral_asset, debt_asset, U256::from(fee), amount, U256::zero()), None, Options::default(), None)
+        .await
+    {
+        Ok(quoted) => quoted,
+        Err(e) => {
+            warn!("No permit exit quote for {:?}, exiting with an approval: {}", collateral_asset, e);
+            return None;
+        }
+    };
+
+    let sender = match TxSender::from_config(web3).await {
+        Ok(sender) => sender,
+        Err(e) => {
+            warn!("No signing key for a permit on {:?}, exiting with an approval: {}", collateral_asset, e);
+            return None;
+        }
+    };
+    let signature = match permit::sign_permit(web3, &sender, &domain, router, amount, deadline).await {
+        Ok(signature) => signature,
+        Err(e) => {
+            warn!("Permit signing failed for {:?}, exiting with an approval: {}", collateral_asset, e);
+            return None;
+        }
+    };
+    let order = SwapOrder {
+        token_in: collateral_asset,
+        token_out: debt_asset,
+        amount_in: amount,
+        amount_out_min: domain::min_out(quoted, Some(settings.slippage_tolerance)),
+        recipient: wallet,
+        deadline,
+    };
+    let swap = execution::swap_v3_call(router, fee, &order).ok()?;
+    Some(permit::permit_and_swap_call(collateral_asset, amount, deadline, signature, swap))
+}
@@ -0,0 +1,278 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::fs;
+use std::sync::Arc;
+use web3::ethabi::{self, ParamType, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, BlockId, BlockNumber, Bytes, CallRequest, FilterBuilder, H256, U256};
+use log::{info, warn};
+use thiserror::Error;
+
+use crate::modules::checkpoint;
+use crate::modules::ens;
+use crate::modules::gas_feed;
+use crate::modules::health_history::{self, HealthSnapshot, HistoryError};
+use crate::modules::providers::{FailoverTransport, ProviderError, ProviderPool};
+use crate::modules::sanctions;
+
+// Aave V3 Borrow(address indexed reserve, address user, address indexed onBehalfOf, uint256, uint8, uint256, uint16 indexed)
+const BORROW_EVENT: &str = "Borrow(address,address,address,uint256,uint8,uint256,uint16)";
+// Aave lets half the debt be repaid per liquidation, all of it below this health factor
+pub const FULL_CLOSE_HEALTH_FACTOR: f64 = 0.95;
+
+// Load configuration for liquidation (the report shares the scanner settings)
+fn load_liquidation_config() -> Value {
+    let config_path = "config/liquidation_config.json";
+    let config_data = fs::read_to_string(config_path).expect("Unable to read liquidation config file");
+    serde_json::from_str(&config_data).expect("Unable to parse liquidation config file")
+}
+
+// The liquidation the bot would attempt for one account, in Aave base currency units
+#[derive(Clone, Debug, Serialize)]
+pub struct LiquidationPlan {
+    pub close_factor: f64,
+    pub debt_to_cover: f64,
+    pub collateral_bonus: f64,
+    pub flashloan_fee: f64,
+    pub gas_cost: Option<f64>,
+    pub net_profit: f64,
+    // Net profit at the highest base fee the inclusion block can plausibly have
+    pub worst_case_net_profit: Option<f64>,
+}
+
+// One at-risk account: its health, the plan (if any) and why it would be skipped (if it would be)
+#[derive(Clone, Debug, Serialize)]
+pub struct ReportEntry {
+    pub borrower: Address,
+    pub health_factor: Option<f64>,
+    pub compound_shortfall: Option<String>,
+    pub plan: Option<LiquidationPlan>,
+    pub excluded: Option<String>,
+}
+
+fn base_units(value: U256, decimals: i32) -> f64 {
+    value.to_string().parse::<f64>().unwrap_or(0.0) / 10f64.powi(decimals)
+}
+
+// Borrowers to check: the configured list plus everyone who borrowed on Aave in the lookback window
+pub async fn candidate_borrowers(web3: &web3::Web3<FailoverTransport>, config: &Value, aave_pool: Option<Address>, block: u64) -> Vec<Address> {
+    let mut borrowers: BTreeSet<Address> = config["scan_borrowers"]
+        .as_array()
+        .into_iter()
+        .chain(config["history_borrowers"].as_array())
+        .flatten()
+        .filter_map(|value| value.as_str().and_then(ens::parse_address))
+        .collect();
+    // Accounts already followed through the checkpoint's borrower index
+    borrowers.extend(checkpoint::known_borrowers());
+
+    if let Some(pool) = aave_pool {
+        let lookback = config["scan_lookback_blocks"].as_u64().unwrap_or(50_000);
+        let filter = FilterBuilder::default()
+            .address(vec![pool])
+            .topics(Some(vec![H256::from(keccak256(BORROW_EVENT.as_bytes()))]), None, None, None)
+            .from_block(BlockNumber::Number(block.saturating_sub(lookback).into()))
+            .to_block(BlockNumber::Number(block.into()))
+            .build();
+        match web3.eth().logs(filter).await {
+            Ok(logs) => {
+                // onBehalfOf carries the debt, so it is the account to check
+                borrowers.extend(logs.iter().filter_map(|log| log.topics.get(2)).map(|topic| Address::from_slice(&topic.as_bytes()[12..])));
+            }
+            Err(e) => warn!("Could not read Aave borrow events: {}", e),
+        }
+    }
+    borrowers.into_iter().collect()
+}
+
+// ETH price in Aave base currency at the block, to put gas on the same scale as profit
+async fn eth_price_base(web3: &web3::Web3<FailoverTransport>, config: &Value, block: u64, base_decimals: i32) -> Option<f64> {
+    let oracle = config["aave_oracle_address"].as_str().and_then(ens::parse_address)?;
+    let weth = config["weth_address"].as_str().and_then(ens::parse_address)?;
+    let data = [keccak256(b"getAssetPrice(address)")[..4].to_vec(), ethabi::encode(&[Token::Address(weth)])].concat();
+    let request = CallRequest { to: Some(oracle), data: Some(Bytes(data)), ..Default::default() };
+    let output = web3.eth().call(request, Some(BlockId::Number(BlockNumber::Number(block.into())))).await.ok()?;
+    let price = ethabi::decode(&[ParamType::Uint(256)], &output.0).ok()?.first()?.clone().into_uint()?;
+    Some(base_units(price, base_decimals))
+}
+
+fn plan_for(snapshot: &HealthSnapshot, config: &Value, gas_cost: Option<f64>, max_base_fee_ratio: f64, base_decimals: i32) -> Option<LiquidationPlan> {
+    let health_factor = snapshot.aave_health_factor?;
+    let debt = base_units(snapshot.aave_debt_base?, base_decimals);
+    let close_factor = if health_factor < FULL_CLOSE_HEALTH_FACTOR { 1.0 } else { 0.5 };
+    let debt_to_cover = debt * close_factor;
+    let collateral_bonus = debt_to_cover * config["liquidation_bonus_bps"].as_f64().unwrap_or(500.0) / 10_000.0;
+    let flashloan_fee = debt_to_cover * config["flashloan_premium_bps"].as_f64().unwrap_or(5.0) / 10_000.0;
+    let net_profit = collateral_bonus - flashloan_fee - gas_cost.unwrap_or(0.0);
+    let worst_case_net_profit = gas_cost.map(|gas_cost| collateral_bonus - flashloan_fee - gas_cost * max_base_fee_ratio);
+    Some(LiquidationPlan { close_factor, debt_to_cover, collateral_bonus, flashloan_fee, gas_cost, net_profit, worst_case_net_profit })
+}
+
+// Gas for the configured liquidation gas limit at the block's base fee, in Aave base currency (None
+// without an ETH price), and how far the base fee can rise before inclusion as a multiple of the block's
+pub async fn gas_at(web3: &web3::Web3<FailoverTransport>, config: &Value, block: u64, base_decimals: i32) -> Result<(Option<f64>, f64), web3::Error> {
+    let base_fee = web3
+        .eth()
+        .block(BlockId::Number(BlockNumber::Number(block.into())))
+        .await?
+        .and_then(|header| header.base_fee_per_gas)
+        .unwrap_or_default();
+    let gas_eth = base_units(base_fee * U256::from(config["max_gas_limit"].as_u64().unwrap_or(3_000_000)), 18);
+    let gas_cost = eth_price_base(web3, config, block, base_decimals).await.map(|eth_price| gas_eth * eth_price);
+    let max_base_fee_ratio = gas_feed::base_fee_scenarios(base_fee)
+        .last()
+        .filter(|_| !base_fee.is_zero())
+        .map(|high| high.as_u128() as f64 / base_fee.as_u128() as f64)
+        .unwrap_or(1.0);
+    Ok((gas_cost, max_base_fee_ratio))
+}
+
+// Whether the bot would bid on a liquidatable account: the plan it would build, and the reason it
+// would pass on it (no Aave position, unknown gas, or too little profit now or after a base fee rise)
+pub fn bid_decision(snapshot: &HealthSnapshot, config: &Value, gas_cost: Option<f64>, max_base_fee_ratio: f64, base_decimals: i32) -> (Option<LiquidationPlan>, Option<String>) {
+    let min_net_profit = config["min_net_profit_base"].as_f64().unwrap_or(0.0);
+    let plan = plan_for(snapshot, config, gas_cost, max_base_fee_ratio, base_decimals);
+    let excluded = match &plan {
+        None => Some(String::from("Compound shortfall only; Compound liquidations are not planned")),
+        Some(_) if gas_cost.is_none() => Some(String::from("gas cost unknown (set aave_oracle_address and weth_address)")),
+        Some(plan) if plan.net_profit < min_net_profit => {
+            Some(format!("net profit {:.2} below minimum {:.2}", plan.net_profit, min_net_profit))
+        }
+        Some(plan) if plan.worst_case_net_profit.map(|worst| worst < min_net_profit).unwrap_or(false) => Some(format!(
+            "net profit {:.2} drops to {:.2} if the base fee rises {:.1}% before inclusion",
+            plan.net_profit,
+            plan.worst_case_net_profit.unwrap_or_default(),
+            (max_base_fee_ratio - 1.0) * 100.0
+        )),
+        Some(_) => None,
+    };
+    (plan, excluded)
+}
+
+// Every at-risk account at the block with the plan the bot would build and the reason any would be
+// skipped. Nothing is sent: the report only reads state pinned to the block
+pub async fn scan_block(web3: &web3::Web3<FailoverTransport>, block: u64) -> Result<Vec<ReportEntry>, LiquidationReportError> {
+    let config = load_liquidation_config();
+    let aave_pool = config["aave_pool_address"].as_str().and_then(ens::parse_address);
+    let comptroller = config["compound_comptroller_address"].as_str().and_then(ens::parse_address);
+    if aave_pool.is_none() && comptroller.is_none() {
+        return Err(LiquidationReportError::MissingConfig("aave_pool_address or compound_comptroller_address"));
+    }
+    let alert_health_factor = config["alert_health_factor"].as_f64().unwrap_or(1.05);
+    let base_decimals = config["aave_base_decimals"].as_i64().unwrap_or(8) as i32;
+    let (gas_cost, max_base_fee_ratio) = gas_at(web3, &config, block, base_decimals).await?;
+
+    let borrowers = candidate_borrowers(web3, &config, aave_pool, block).await;
+    info!("Checking {} borrowers at block {}", borrowers.len(), block);
+
+    let mut entries = Vec::new();
+    for borrower in borrowers {
+        let snapshot = match health_history::health_at(web3, aave_pool, comptroller, borrower, block).await {
+            Ok(snapshot) => snapshot,
+            Err(HistoryError::NotArchive(block)) => return Err(HistoryError::NotArchive(block).into()),
+            Err(e) => {
+                warn!("Failed to read health of {:?} at block {}: {}", borrower, block, e);
+                continue;
+            }
+        };
+        let at_risk = snapshot.is_liquidatable() || snapshot.aave_health_factor.map(|hf| hf < alert_health_factor).unwrap_or(false);
+        if !at_risk {
+            continue;
+        }
+
+        let (plan, bid_excluded) = bid_decision(&snapshot, &config, gas_cost, max_base_fee_ratio, base_decimals);
+        let excluded = if sanctions::first_flagged(&[borrower], "liquidation_report").await.is_some() {
+            Some(String::from("borrower flagged by sanctions screening"))
+        } else if !snapshot.is_liquidatable() {
+            Some(format!("not liquidatable yet (health factor above 1.0, alert at {})", alert_health_factor))
+        } else {
+            bid_excluded
+        };
+
+        entries.push(ReportEntry {
+            borrower,
+            health_factor: snapshot.aave_health_factor,
+            compound_shortfall: snapshot.compound_shortfall.filter(|shortfall| !shortfall.is_zero()).map(|shortfall| shortfall.to_string()),
+            plan,
+            excluded,
+        });
+    }
+
+    // Most urgent first
+    entries.sort_by(|a, b| {
+        a.health_factor.unwrap_or(f64::MAX).partial_cmp(&b.health_factor.unwrap_or(f64::MAX)).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(entries)
+}
+
+fn print_table(block: u64, entries: &[ReportEntry]) {
+    println!("At-risk accounts at block {}: {}", block, entries.len());
+    println!("{:<44} {:>8} {:>6} {:>14} {:>12}  status", "borrower", "hf", "close", "debt covered", "net profit");
+    for entry in entries {
+        let health_factor = entry.health_factor.map(|hf| format!("{:.4}", hf)).unwrap_or_else(|| String::from("-"));
+        let (close, covered, net) = match &entry.plan {
+            Some(plan) => (format!("{:.0}%", plan.close_factor * 100.0), format!("{:.2}", plan.debt_to_cover), format!("{:.2}", plan.net_profit)),
+            None => (String::from("-"), String::from("-"), String::from("-")),
+        };
+        let status = entry.excluded.clone().unwrap_or_else(|| String::from("would liquidate"));
+        println!("{:<44} {:>8} {:>6} {:>14} {:>12}  {}", format!("{:?}", entry.borrower), health_factor, close, covered, net, status);
+    }
+}
+
+// `taz liquidations scan --block N --dry-run [--json <path>]`
+pub async fn run_liquidations_command(global_config: Value, args: Vec<String>) -> Result<(), LiquidationReportError> {
+    let value_of = |name: &str| args.iter().position(|arg| arg == name).and_then(|index| args.get(index + 1)).cloned();
+    if args.first().map(String::as_str) != Some("scan") {
+        return Err(LiquidationReportError::Usage);
+    }
+    // Only dry runs exist; the flag keeps the command explicit about not sending anything
+    if !args.iter().any(|arg| arg == "--dry-run") {
+        return Err(LiquidationReportError::Usage);
+    }
+    let block = value_of("--block").and_then(|block| block.parse::<u64>().ok()).ok_or(LiquidationReportError::Usage)?;
+
+    let default_url = format!(
+        "https://{}.infura.io/v3/{}",
+        global_config["network"].as_str().unwrap_or("mainnet"),
+        global_config["infura_project_id"].as_str().unwrap_or_default()
+    );
+    let web3 = Arc::new(ProviderPool::from_config(&default_url)?).web3_for("health_history")?;
+    ens::resolve_config_names(&web3).await;
+
+    let entries = scan_block(&web3, block).await?;
+    print_table(block, &entries);
+
+    if let Some(path) = value_of("--json") {
+        let report = json!({ "block": block, "accounts": entries });
+        fs::write(&path, serde_json::to_string_pretty(&report)?)?;
+        println!("Report written to {}", path);
+    }
+    Ok(())
+}
+
+// Custom error type for the liquidation dry-run report
+#[derive(Error, Debug)]
+pub enum LiquidationReportError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("History error: {0}")]
+    HistoryError(#[from] HistoryError),
+    #[error("Provider error: {0}")]
+    ProviderError(#[from] ProviderError),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("usage: taz liquidations scan --block <N> --dry-run [--json <path>]")]
+    Usage,
+}
+
+// Implement conversion for LiquidationReportError to Web3 error
+impl From<LiquidationReportError> for web3::Error {
+    fn from(error: LiquidationReportError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,442 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use web3::ethabi::{self, ParamType, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, BlockId, BlockNumber, Bytes, CallRequest, TransactionRequest, H256, U256};
+use log::{error, info, warn};
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::allocator;
+use crate::modules::domain::OpportunitySource;
+use crate::modules::ens;
+use crate::modules::events;
+use crate::modules::execution::{self, Call, ExecutionBackend, ExecutionError};
+use crate::modules::gas::{self, Urgency};
+use crate::modules::inventory;
+use crate::modules::liquidation_report::{self, FULL_CLOSE_HEALTH_FACTOR};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::relay::{self, Bundle, BundleOutcome, RelayClient, RelayError};
+use crate::modules::sandwich;
+use crate::modules::sanctions;
+use crate::modules::signer::{SignerError, TxSender};
+use crate::modules::venues::{self, VenueError};
+
+// Health factors are 18-decimal fixed point on Aave
+const WAD: f64 = 1e18;
+
+// Load configuration for liquidation (the watcher shares the scanner settings)
+fn load_liquidation_config() -> Value {
+    let config_path = "config/liquidation_config.json";
+    let config_data = fs::read_to_string(config_path).expect("Unable to read liquidation config file");
+    serde_json::from_str(&config_data).expect("Unable to parse liquidation config file")
+}
+
+// A collateral token whose DEX price dropped well below the oracle price, in Aave base units
+#[derive(Clone, Copy, Debug)]
+struct PriceShock {
+    token: Address,
+    decimals: i32,
+    oracle_price: f64,
+    dex_price: f64,
+}
+
+// A liquidation built while the oracle still lags a DEX price move, waiting for the oracle to cross
+#[derive(Clone, Debug, Serialize)]
+pub struct PreparedLiquidation {
+    pub borrower: Address,
+    pub collateral_asset: Address,
+    pub debt_asset: Address,
+    pub debt_to_cover: U256,
+    // At the oracle price when prepared
+    pub health_factor: f64,
+    // Repriced at the DEX price
+    pub dex_health_factor: f64,
+    // Oracle price of the collateral, in base units, at which the position becomes liquidatable
+    pub trigger_price: f64,
+    pub prepared_block: u64,
+    #[serde(skip)]
+    pub calls: Vec<Call>,
+}
+
+// Prepared liquidations keyed by borrower; a newer shock replaces an older preparation
+fn prepared() -> &'static Mutex<HashMap<Address, PreparedLiquidation>> {
+    static PREPARED: OnceLock<Mutex<HashMap<Address, PreparedLiquidation>>> = OnceLock::new();
+    PREPARED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Liquidations waiting on the oracle, for the dashboard
+pub fn view() -> Value {
+    let prepared: Vec<PreparedLiquidation> = prepared().lock().unwrap().values().cloned().collect();
+    json!({ "prepared": prepared })
+}
+
+fn base_units(value: U256, decimals: i32) -> f64 {
+    value.to_string().parse::<f64>().unwrap_or(0.0) / 10f64.powi(decimals)
+}
+
+fn config_address(config: &Value, key: &'static str) -> Result<Address, LiquidationWatchError> {
+    config[key].as_str().and_then(ens::parse_address).ok_or(LiquidationWatchError::MissingConfig(key))
+}
+
+async fn call(web3: &web3::Web3<FailoverTransport>, to: Address, signature: &str, args: &[Token]) -> Result<Vec<u8>, LiquidationWatchError> {
+    let data = [keccak256(signature.as_bytes())[..4].to_vec(), ethabi::encode(args)].concat();
+    let request = CallRequest { to: Some(to), data: Some(Bytes(data)), ..Default::default() };
+    Ok(web3.eth().call(request, Some(BlockId::Number(BlockNumber::Latest))).await?.0)
+}
+
+fn encode_call(to: Address, signature: &str, args: &[Token]) -> Call {
+    let data = [keccak256(signature.as_bytes())[..4].to_vec(), ethabi::encode(args)].concat();
+    Call { to, value: U256::zero(), data }
+}
+
+// Oracle price in base units; None for tokens the oracle doesn't list
+async fn oracle_price(web3: &web3::Web3<FailoverTransport>, oracle: Address, asset: Address, base_decimals: i32) -> Option<f64> {
+    let output = call(web3, oracle, "getAssetPrice(address)", &[Token::Address(asset)]).await.ok()?;
+    let price = ethabi::decode(&[ParamType::Uint(256)], &output).ok()?.first()?.clone().into_uint()?;
+    Some(base_units(price, base_decimals)).filter(|price| *price > 0.0)
+}
+
+async fn token_decimals(web3: &web3::Web3<FailoverTransport>, token: Address) -> Option<i32> {
+    let output = call(web3, token, "decimals()", &[]).await.ok()?;
+    let decimals = ethabi::decode(&[ParamType::Uint(8)], &output).ok()?.first()?.clone().into_uint()?;
+    Some(decimals.as_u32() as i32)
+}
+
+// Compare what one whole token sells for on the best venue against the oracle; a shock when the
+// DEX price sits at least shock_threshold_pct below it
+async fn price_shock(web3: &web3::Web3<FailoverTransport>, config: &Value, oracle: Address, token: Address) -> Result<Option<PriceShock>, LiquidationWatchError> {
+    let base_decimals = config["aave_base_decimals"].as_i64().unwrap_or(8) as i32;
+    let weth = config_address(config, "weth_address")?;
+    let (listed_price, weth_price, decimals) = match (
+        oracle_price(web3, oracle, token, base_decimals).await,
+        oracle_price(web3, oracle, weth, base_decimals).await,
+        token_decimals(web3, token).await,
+    ) {
+        (Some(listed_price), Some(weth_price), Some(decimals)) => (listed_price, weth_price, decimals),
+        _ => return Ok(None),
+    };
+    let weth_out = match venues::best_quote(web3, token, weth, U256::exp10(decimals as usize)).await? {
+        Some((_, weth_out)) => weth_out,
+        None => return Ok(None),
+    };
+    let dex_price = base_units(weth_out, 18) * weth_price;
+    let threshold = config["shock_threshold_pct"].as_f64().unwrap_or(3.0) / 100.0;
+    if (listed_price - dex_price) / listed_price < threshold {
+        return Ok(None);
+    }
+    Ok(Some(PriceShock { token, decimals, oracle_price: listed_price, dex_price }))
+}
+
+// The borrower's (aToken balance, usage as collateral, stable + variable debt) in one reserve
+async fn user_reserve(web3: &web3::Web3<FailoverTransport>, data_provider: Address, asset: Address, borrower: Address) -> Result<(U256, bool, U256), LiquidationWatchError> {
+    let output = call(web3, data_provider, "getUserReserveData(address,address)", &[Token::Address(asset), Token::Address(borrower)]).await?;
+    let mut types = vec![ParamType::Uint(256); 7];
+    types.push(ParamType::Uint(40));
+    types.push(ParamType::Bool);
+    let fields = ethabi::decode(&types, &output)?;
+    let uint = |index: usize| fields[index].clone().into_uint().unwrap_or_default();
+    Ok((uint(0), fields[8].clone().into_bool().unwrap_or(false), uint(1).saturating_add(uint(2))))
+}
+
+// Re-check one borrower at the shocked DEX price. Health is linear in the collateral price, so the
+// account's liquidation threshold gives both the repriced health factor and the oracle price at
+// which it falls below 1.0. None when the position stays healthy at the DEX price
+async fn prepare(
+    web3: &web3::Web3<FailoverTransport>,
+    config: &Value,
+    shock: &PriceShock,
+    borrower: Address,
+    block: u64,
+) -> Result<Option<PreparedLiquidation>, LiquidationWatchError> {
+    let pool = config_address(config, "aave_pool_address")?;
+    let data_provider = config_address(config, "aave_data_provider_address")?;
+    let base_decimals = config["aave_base_decimals"].as_i64().unwrap_or(8) as i32;
+
+    let (balance, as_collateral, _) = user_reserve(web3, data_provider, shock.token, borrower).await?;
+    if balance.is_zero() || !as_collateral {
+        return Ok(None);
+    }
+    let output = call(web3, pool, "getUserAccountData(address)", &[Token::Address(borrower)]).await?;
+    let fields = ethabi::decode(&vec![ParamType::Uint(256); 6], &output)?;
+    let uint = |index: usize| fields[index].clone().into_uint().unwrap_or_default();
+    let debt_base = base_units(uint(1), base_decimals);
+    if debt_base <= 0.0 {
+        return Ok(None);
+    }
+    let health_factor = base_units(uint(5), 0) / WAD;
+    let liquidation_threshold = base_units(uint(3), 0) / 10_000.0;
+
+    // Health factor lost per base unit the collateral price falls
+    let exposure = base_units(balance, shock.decimals) * liquidation_threshold / debt_base;
+    let dex_health_factor = health_factor - (shock.oracle_price - shock.dex_price) * exposure;
+    if exposure <= 0.0 || dex_health_factor >= config["shock_prepare_health_factor"].as_f64().unwrap_or(1.0) {
+        return Ok(None);
+    }
+    let trigger_price = shock.oracle_price - (health_factor - 1.0) / exposure;
+
+    // Repay the largest debt among the assets we keep inventory in
+    let mut largest: Option<(Address, U256)> = None;
+    for debt_asset in config["shock_debt_assets"].as_array().into_iter().flatten().filter_map(|asset| asset.as_str().and_then(ens::parse_address)) {
+        let (_, _, debt) = user_reserve(web3, data_provider, debt_asset, borrower).await?;
+        if !debt.is_zero() && largest.is_none_or(|(_, most)| debt > most) {
+            largest = Some((debt_asset, debt));
+        }
+    }
+    let (debt_asset, debt) = match largest {
+        Some(largest) => largest,
+        None => return Ok(None),
+    };
+    let debt_to_cover = if dex_health_factor < FULL_CLOSE_HEALTH_FACTOR { debt } else { debt / 2 };
+    // Aave accepts a partial repayment, so the allocator's limit just shrinks the liquidation
+    let debt_to_cover = allocator::cap_to_allocation(web3, "liquidation", debt_asset, debt_to_cover).await;
+
+    let calls = vec![
+        execution::approve_call(debt_asset, pool, debt_to_cover)?,
+        encode_call(pool, "liquidationCall(address,address,address,uint256,bool)", &[
+            Token::Address(shock.token),
+            Token::Address(debt_asset),
+            Token::Address(borrower),
+            Token::Uint(debt_to_cover),
+            Token::Bool(false),
+        ]),
+    ];
+    Ok(Some(PreparedLiquidation {
+        borrower,
+        collateral_asset: shock.token,
+        debt_asset,
+        debt_to_cover,
+        health_factor,
+        dex_health_factor,
+        trigger_price,
+        prepared_block: block,
+        calls,
+    }))
+}
+
+// Send a prepared liquidation from held debt-asset inventory: as a bundle through the private relay
+// when it is enabled and the wallet sends directly, through the execution backend otherwise
+async fn submit(web3: &web3::Web3<FailoverTransport>, config: &Value, liquidation: &PreparedLiquidation) -> Result<(), LiquidationWatchError> {
+    let _reservation = inventory::reserve("liquidation", liquidation.debt_asset, liquidation.debt_to_cover)
+        .ok_or(LiquidationWatchError::NoInventory(liquidation.debt_asset))?;
+    let backend = ExecutionBackend::from_config(web3)?;
+
+    if relay::enabled() && !backend.is_atomic() {
+        let sender = TxSender::from_config(web3).await?;
+        // liquidationCall can't be estimated before the approval lands, so each leg gets the ceiling
+        let leg_gas = U256::from(config["max_gas_limit"].as_u64().unwrap_or(3_000_000));
+        let fees = gas::suggest_fees(web3, Urgency::for_strategy("liquidation")).await.ok();
+        let requests = liquidation
+            .calls
+            .iter()
+            .map(|call| TransactionRequest {
+                from: sender.address(),
+                to: Some(call.to),
+                gas: Some(leg_gas),
+                value: Some(call.value),
+                data: Some(Bytes(call.data.clone())),
+                max_fee_per_gas: fees.map(|fees| fees.max_fee),
+                max_priority_fee_per_gas: fees.map(|fees| fees.priority_fee),
+                ..Default::default()
+            })
+            .collect();
+        let bundle = Bundle::of(&sender, "liquidation", requests).await?;
+        return match RelayClient::from_config()?.submit(web3, "liquidation", &bundle).await? {
+            BundleOutcome::Included { block, tx_hashes } => {
+                info!("Prepared liquidation of {:?} included in block {}", liquidation.borrower, block);
+                record_result(web3, liquidation, sender.address(), &tx_hashes).await;
+                Ok(())
+            }
+            BundleOutcome::NotIncluded { .. } => Err(LiquidationWatchError::BundleNotIncluded(liquidation.borrower)),
+        };
+    }
+
+    let submission = backend.execute_calls(web3, "liquidation", liquidation.calls.clone()).await?;
+    info!("Prepared liquidation of {:?} submitted: {:?}", liquidation.borrower, submission);
+    if !backend.is_proposal() {
+        record_result(web3, liquidation, backend.recipient(), &[submission]).await;
+    }
+    Ok(())
+}
+
+// Seized collateral less the debt repaid, both valued in wei
+async fn record_result(web3: &web3::Web3<FailoverTransport>, liquidation: &PreparedLiquidation, holder: Address, tx_hashes: &[H256]) {
+    let tokens = [liquidation.collateral_asset, liquidation.debt_asset];
+    allocator::record_realized(web3, "liquidation", OpportunitySource::BlockEvent, holder, &tokens, tx_hashes).await;
+}
+
+// Tokens sold by the block's router swaps: their pools moved down, ahead of the oracle
+async fn sold_tokens(web3: &web3::Web3<FailoverTransport>, block: u64) -> Result<Vec<Address>, LiquidationWatchError> {
+    let block = match web3.eth().block_with_txs(BlockId::Number(BlockNumber::Number(block.into()))).await? {
+        Some(block) => block,
+        None => return Ok(Vec::new()),
+    };
+    let sold: BTreeSet<Address> = block
+        .transactions
+        .iter()
+        .filter_map(|transaction| sandwich::victim_swap(&transaction.input.0, transaction.value))
+        .map(|swap| swap.token_in)
+        .collect();
+    Ok(sold.into_iter().collect())
+}
+
+// Prepare liquidations for borrowers exposed to any collateral the block's swaps shocked
+async fn prepare_for_block(web3: &web3::Web3<FailoverTransport>, config: &Value, block: u64) -> Result<(), LiquidationWatchError> {
+    let pool = config_address(config, "aave_pool_address")?;
+    let oracle = config_address(config, "aave_oracle_address")?;
+
+    let mut borrowers: Option<Vec<Address>> = None;
+    for token in sold_tokens(web3, block).await? {
+        let shock = match price_shock(web3, config, oracle, token).await? {
+            Some(shock) => shock,
+            None => continue,
+        };
+        info!(
+            "Price shock on {:?}: DEX {:.4} vs oracle {:.4} at block {}",
+            token, shock.dex_price, shock.oracle_price, block
+        );
+        events::emit("price_shock", json!({ "token": token, "dex_price": shock.dex_price, "oracle_price": shock.oracle_price, "block": block }));
+
+        if borrowers.is_none() {
+            borrowers = Some(liquidation_report::candidate_borrowers(web3, config, Some(pool), block).await);
+        }
+        for borrower in borrowers.iter().flatten() {
+            if sanctions::first_flagged(&[*borrower], "liquidation_watch").await.is_some() {
+                continue;
+            }
+            let liquidation = match prepare(web3, config, &shock, *borrower, block).await {
+                Ok(Some(liquidation)) => liquidation,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to re-check {:?} after the {:?} shock: {}", borrower, token, e);
+                    continue;
+                }
+            };
+            info!(
+                "Prepared liquidation of {:?}: health factor {:.4} at the DEX price, fires at oracle price {:.4}",
+                borrower, liquidation.dex_health_factor, liquidation.trigger_price
+            );
+            events::emit("liquidation_prepared", json!({
+                "borrower": borrower,
+                "collateral_asset": token,
+                "debt_asset": liquidation.debt_asset,
+                "dex_health_factor": liquidation.dex_health_factor,
+                "trigger_price": liquidation.trigger_price,
+            }));
+            prepared().lock().unwrap().insert(*borrower, liquidation);
+        }
+    }
+    Ok(())
+}
+
+// Submit every prepared liquidation whose collateral's oracle price has reached its trigger, once
+// the pool confirms the account is liquidatable; drop preparations older than prepared_ttl_blocks
+async fn fire_ready(web3: &web3::Web3<FailoverTransport>, config: &Value, block: u64) -> Result<(), LiquidationWatchError> {
+    let pool = config_address(config, "aave_pool_address")?;
+    let oracle = config_address(config, "aave_oracle_address")?;
+    let base_decimals = config["aave_base_decimals"].as_i64().unwrap_or(8) as i32;
+    let ttl = config["prepared_ttl_blocks"].as_u64().unwrap_or(50);
+
+    let waiting: Vec<PreparedLiquidation> = {
+        let mut prepared = prepared().lock().unwrap();
+        prepared.retain(|_, liquidation| block.saturating_sub(liquidation.prepared_block) <= ttl);
+        prepared.values().cloned().collect()
+    };
+
+    let mut prices: HashMap<Address, Option<f64>> = HashMap::new();
+    for liquidation in waiting {
+        let price = match prices.get(&liquidation.collateral_asset) {
+            Some(price) => *price,
+            None => {
+                let price = oracle_price(web3, oracle, liquidation.collateral_asset, base_decimals).await;
+                prices.insert(liquidation.collateral_asset, price);
+                price
+            }
+        };
+        if price.is_none_or(|price| price > liquidation.trigger_price) {
+            continue;
+        }
+
+        // The trigger is linear in the oracle price; the pool has the final word
+        let output = call(web3, pool, "getUserAccountData(address)", &[Token::Address(liquidation.borrower)]).await?;
+        let health_factor = ethabi::decode(&vec![ParamType::Uint(256); 6], &output)?[5].clone().into_uint().unwrap_or(U256::MAX);
+        if health_factor >= U256::exp10(18) {
+            continue;
+        }
+
+        prepared().lock().unwrap().remove(&liquidation.borrower);
+        info!("Oracle crossed for {:?}, submitting the prepared liquidation", liquidation.borrower);
+        let submitted = submit(web3, config, &liquidation).await;
+        events::emit("prepared_liquidation_fired", json!({
+            "borrower": liquidation.borrower,
+            "blocks_prepared": block.saturating_sub(liquidation.prepared_block),
+            "error": submitted.as_ref().err().map(|e| e.to_string()),
+        }));
+        if let Err(e) = submitted {
+            error!("Prepared liquidation of {:?} failed: {}", liquidation.borrower, e);
+        }
+    }
+    Ok(())
+}
+
+// Block-driven: after each block, look for swaps that pushed a collateral's DEX price below its
+// oracle price and prepare liquidations for the borrowers that price would sink, then fire any
+// prepared liquidation whose oracle has caught up
+pub async fn run_liquidation_watch(web3: web3::Web3<FailoverTransport>) {
+    let mut last_block = 0u64;
+    loop {
+        sleep(Duration::from_secs(1)).await;
+        let head = match web3.eth().block_number().await {
+            Ok(head) => head.as_u64(),
+            Err(e) => {
+                error!("Liquidation watch could not read the head block: {}", e);
+                continue;
+            }
+        };
+        if head <= last_block {
+            continue;
+        }
+        last_block = head;
+
+        let config = load_liquidation_config();
+        if let Err(e) = prepare_for_block(&web3, &config, head).await {
+            error!("Liquidation watch failed at block {}: {}", head, e);
+        }
+        if let Err(e) = fire_ready(&web3, &config, head).await {
+            error!("Prepared liquidations could not be checked at block {}: {}", head, e);
+        }
+    }
+}
+
+// Custom error type for the shock-driven liquidation watch
+#[derive(Error, Debug)]
+pub enum LiquidationWatchError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Signer error: {0}")]
+    SignerError(#[from] SignerError),
+    #[error("Relay error: {0}")]
+    RelayError(#[from] RelayError),
+    #[error("Venue error: {0}")]
+    VenueError(#[from] VenueError),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("Not enough {0:?} inventory to cover the debt")]
+    NoInventory(Address),
+    #[error("Liquidation bundle for {0:?} was not included")]
+    BundleNotIncluded(Address),
+}
+
+// Implement conversion for LiquidationWatchError to Web3 error
+impl From<LiquidationWatchError> for web3::Error {
+    fn from(error: LiquidationWatchError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,137 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{fmt, reload, Registry};
+use log::info;
+use thiserror::Error;
+
+// Module names without a path are resolved inside this crate's modules
+const MODULE_PREFIX: &str = "taz::modules::";
+
+// Default level plus per-module overrides, keyed as the operator wrote them
+struct LogLevels {
+    default: String,
+    modules: BTreeMap<String, String>,
+}
+
+fn levels() -> &'static Mutex<LogLevels> {
+    static LEVELS: OnceLock<Mutex<LogLevels>> = OnceLock::new();
+    LEVELS.get_or_init(|| Mutex::new(LogLevels { default: String::from("info"), modules: BTreeMap::new() }))
+}
+
+fn reload_handle() -> &'static OnceLock<reload::Handle<EnvFilter, Registry>> {
+    static HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+    &HANDLE
+}
+
+fn target(module: &str) -> String {
+    if module.contains("::") {
+        module.to_string()
+    } else {
+        format!("{}{}", MODULE_PREFIX, module)
+    }
+}
+
+fn validate_level(level: &str) -> Result<(), LoggingError> {
+    level.parse::<LevelFilter>().map(|_| ()).map_err(|_| LoggingError::InvalidLevel(level.to_string()))
+}
+
+fn build_filter(levels: &LogLevels) -> Result<EnvFilter, LoggingError> {
+    let mut filter = EnvFilter::try_new(&levels.default).map_err(|e| LoggingError::InvalidDirective(e.to_string()))?;
+    for (module, level) in &levels.modules {
+        let directive = format!("{}={}", target(module), level);
+        filter = filter.add_directive(directive.parse().map_err(|_| LoggingError::InvalidDirective(directive.clone()))?);
+    }
+    Ok(filter)
+}
+
+// Install the subscriber from "log_level" and "log_levels" in the global config. Records from the
+// log macros are bridged into tracing at full verbosity so the reloadable filter alone decides
+pub fn init(global_config: &Value) {
+    let mut current = levels().lock().unwrap();
+    current.default = global_config["log_level"].as_str().unwrap_or("info").to_string();
+    if let Some(modules) = global_config["log_levels"].as_object() {
+        for (module, level) in modules {
+            if let Some(level) = level.as_str() {
+                current.modules.insert(module.clone(), level.to_string());
+            }
+        }
+    }
+
+    let filter = build_filter(&current).unwrap_or_else(|e| {
+        eprintln!("Invalid log levels in global config ({}), falling back to info", e);
+        EnvFilter::new("info")
+    });
+    let (filter, handle) = reload::Layer::new(filter);
+    let subscriber = Registry::default().with(filter).with(fmt::layer());
+
+    if tracing_log::LogTracer::init().is_ok() && tracing::subscriber::set_global_default(subscriber).is_ok() {
+        let _ = reload_handle().set(handle);
+    }
+}
+
+fn apply(levels: &LogLevels) -> Result<(), LoggingError> {
+    let filter = build_filter(levels)?;
+    reload_handle()
+        .get()
+        .ok_or(LoggingError::NotInitialized)?
+        .reload(filter)
+        .map_err(|e| LoggingError::ReloadFailed(e.to_string()))
+}
+
+// Change one module's level at runtime ("default" changes the base level); None drops the override
+pub fn set_level(module: &str, level: Option<&str>, operator: &str) -> Result<(), LoggingError> {
+    if let Some(level) = level {
+        validate_level(level)?;
+    }
+    let mut current = levels().lock().unwrap();
+    let previous_default = current.default.clone();
+    let previous_module = current.modules.get(module).cloned();
+
+    match (module, level) {
+        ("default", Some(level)) => current.default = level.to_string(),
+        ("default", None) => current.default = String::from("info"),
+        (module, Some(level)) => {
+            current.modules.insert(module.to_string(), level.to_string());
+        }
+        (module, None) => {
+            current.modules.remove(module);
+        }
+    }
+
+    // Keep the previous levels if the new filter is rejected
+    if let Err(e) = apply(&current) {
+        current.default = previous_default;
+        match previous_module {
+            Some(level) => current.modules.insert(module.to_string(), level),
+            None => current.modules.remove(module),
+        };
+        return Err(e);
+    }
+    info!("Log level for {} set to {} by {}", module, level.unwrap_or("inherited"), operator);
+    Ok(())
+}
+
+// Current default and per-module levels for the dashboard
+pub fn snapshot() -> Value {
+    let current = levels().lock().unwrap();
+    json!({
+        "default": current.default,
+        "modules": current.modules,
+    })
+}
+
+// Custom error type for runtime log level changes
+#[derive(Error, Debug)]
+pub enum LoggingError {
+    #[error("Invalid log level: {0}")]
+    InvalidLevel(String),
+    #[error("Invalid log directive: {0}")]
+    InvalidDirective(String),
+    #[error("Logging has not been initialized")]
+    NotInitialized,
+    #[error("Failed to reload log filter: {0}")]
+    ReloadFailed(String),
+}
@@ -0,0 +1,411 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use web3::ethabi::{self, ParamType, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, CallRequest, U256};
+use log::{error, info, warn};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use thiserror::Error;
+use tokio::sync::Notify;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::ens;
+use crate::modules::events;
+use crate::modules::execution::{self, Call, ExecutionBackend, ExecutionError};
+use crate::modules::inventory::{self, InventoryError};
+use crate::modules::pricing::{self, PricingError};
+use crate::modules::providers::FailoverTransport;
+
+// Open positions (what went in, so impermanent loss can be measured) and every deposit and withdrawal
+const POSITIONS_PATH: &str = "data/lp_positions.json";
+const LP_LEDGER_PATH: &str = "data/lp_yield.jsonl";
+
+// Load the passive LP configuration
+fn load_lp_yield_config() -> Value {
+    let config_path = "config/lp_yield_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read LP yield config file");
+    serde_json::from_str(&config_data).expect("Unable to parse LP yield config file")
+}
+
+// A configured V2-style pair to provide liquidity to
+#[derive(Clone, Debug)]
+struct PositionConfig {
+    name: String,
+    pair: Address,
+    router: Address,
+    token_a: Address,
+    token_b: Address,
+    // Most of token_a to deposit; token_b is matched at the pair's ratio
+    max_amount_a: U256,
+}
+
+fn parse_position(entry: &Value) -> Option<PositionConfig> {
+    let address = |key: &str| entry[key].as_str().and_then(ens::parse_address);
+    Some(PositionConfig {
+        name: entry["name"].as_str()?.to_string(),
+        pair: address("pair")?,
+        router: address("router")?,
+        token_a: address("token_a")?,
+        token_b: address("token_b")?,
+        max_amount_a: entry["max_amount_a"].as_str().and_then(|amount| U256::from_dec_str(amount).ok())?,
+    })
+}
+
+fn positions(config: &Value) -> Vec<PositionConfig> {
+    config["positions"].as_array().map(|positions| positions.iter().filter_map(parse_position).collect()).unwrap_or_default()
+}
+
+// What went into an open position
+fn entries() -> &'static Mutex<HashMap<String, Value>> {
+    static ENTRIES: OnceLock<Mutex<HashMap<String, Value>>> = OnceLock::new();
+    ENTRIES.get_or_init(|| {
+        let saved = fs::read_to_string(POSITIONS_PATH).ok().and_then(|data| serde_json::from_str(&data).ok());
+        Mutex::new(saved.unwrap_or_default())
+    })
+}
+
+fn save_entries(entries: &HashMap<String, Value>) {
+    let _ = fs::create_dir_all("data");
+    if let Err(e) = fs::write(POSITIONS_PATH, json!(entries).to_string()) {
+        error!("Failed to persist LP positions: {}", e);
+    }
+}
+
+// Latest valuation per position for the dashboard
+fn valuations() -> &'static Mutex<HashMap<String, Value>> {
+    static VALUATIONS: OnceLock<Mutex<HashMap<String, Value>>> = OnceLock::new();
+    VALUATIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Capital a strategy asked to have back, and when the last recall happened
+struct Recall {
+    pending: Option<(String, U256)>,
+    last: Option<DateTime<Utc>>,
+}
+
+fn recall() -> &'static Mutex<Recall> {
+    static RECALL: OnceLock<Mutex<Recall>> = OnceLock::new();
+    RECALL.get_or_init(|| Mutex::new(Recall { pending: None, last: None }))
+}
+
+fn wake() -> &'static Notify {
+    static WAKE: OnceLock<Notify> = OnceLock::new();
+    WAKE.get_or_init(Notify::new)
+}
+
+fn record(action: &str, position: &str, details: Value) {
+    let record = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "action": action,
+        "position": position,
+        "details": details,
+    });
+    let _ = fs::create_dir_all("data");
+    match OpenOptions::new().create(true).append(true).open(LP_LEDGER_PATH) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write LP record: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open LP ledger: {}", e),
+    }
+    events::emit("lp_yield", record);
+}
+
+// The allocator needs capital for an opportunity. Only high-value ones (expected profit of at least
+// recall_min_profit_wei) pull liquidity back; the LP loop is woken to withdraw right away
+pub fn request_withdrawal(strategy: &str, amount_wei: U256, profit_wei: U256) {
+    let config = load_lp_yield_config();
+    let min_profit = config["recall_min_profit_wei"].as_str().and_then(|amount| U256::from_dec_str(amount).ok()).unwrap_or(U256::MAX);
+    if profit_wei < min_profit || entries().lock().unwrap().is_empty() {
+        return;
+    }
+    let mut recall = recall().lock().unwrap();
+    let requested = recall.pending.as_ref().map(|(_, amount)| *amount).unwrap_or_default();
+    recall.pending = Some((strategy.to_string(), requested.saturating_add(amount_wei)));
+    drop(recall);
+    info!("{} needs {} wei for a high-value opportunity, recalling LP capital", strategy, amount_wei);
+    wake().notify_one();
+}
+
+async fn call(web3: &web3::Web3<FailoverTransport>, to: Address, signature: &str, args: &[Token], outputs: &[ParamType]) -> Result<Vec<Token>, LpYieldError> {
+    let data = [keccak256(signature.as_bytes())[..4].to_vec(), ethabi::encode(args)].concat();
+    let request = CallRequest { to: Some(to), data: Some(Bytes(data)), ..Default::default() };
+    let output = web3.eth().call(request, None).await?;
+    Ok(ethabi::decode(outputs, &output.0)?)
+}
+
+// Reserves in (token_a, token_b) order, total LP supply and the LP tokens we hold
+async fn pair_state(web3: &web3::Web3<FailoverTransport>, position: &PositionConfig, holder: Address) -> Result<(U256, U256, U256, U256), LpYieldError> {
+    let uint = |token: &Token| token.clone().into_uint().unwrap_or_default();
+    let reserves = call(web3, position.pair, "getReserves()", &[], &[ParamType::Uint(112), ParamType::Uint(112), ParamType::Uint(32)]).await?;
+    let supply = call(web3, position.pair, "totalSupply()", &[], &[ParamType::Uint(256)]).await?;
+    let held = inventory::get_token_balance(web3, position.pair, holder).await?;
+    // The pair orders its reserves by token address
+    let (reserve_a, reserve_b) = if position.token_a < position.token_b {
+        (uint(&reserves[0]), uint(&reserves[1]))
+    } else {
+        (uint(&reserves[1]), uint(&reserves[0]))
+    };
+    Ok((reserve_a, reserve_b, uint(&supply[0]), held))
+}
+
+fn share(amount: U256, held: U256, supply: U256) -> U256 {
+    if supply.is_zero() {
+        return U256::zero();
+    }
+    amount.full_mul(held).checked_div(supply.into()).and_then(|share| U256::try_from(share).ok()).unwrap_or_default()
+}
+
+fn amount_field(entry: &Value, field: &str) -> U256 {
+    entry[field].as_str().and_then(|amount| U256::from_dec_str(amount).ok()).unwrap_or_default()
+}
+
+// Value of the position against simply holding what went in, both at today's prices from the
+// pricing module. Impermanent loss is net of the fees earned, since those sit in the reserves too
+async fn valuation(web3: &web3::Web3<FailoverTransport>, position: &PositionConfig, entry: &Value, holder: Address) -> Result<Value, LpYieldError> {
+    let (reserve_a, reserve_b, supply, held) = pair_state(web3, position, holder).await?;
+    let (amount_a, amount_b) = (share(reserve_a, held, supply), share(reserve_b, held, supply));
+    let value_wei = pricing::token_to_wei(web3, position.token_a, amount_a).await?.saturating_add(pricing::token_to_wei(web3, position.token_b, amount_b).await?);
+    let hold_wei = pricing::token_to_wei(web3, position.token_a, amount_field(entry, "deposited_a"))
+        .await?
+        .saturating_add(pricing::token_to_wei(web3, position.token_b, amount_field(entry, "deposited_b")).await?);
+    let impermanent_loss = if hold_wei.is_zero() { 0.0 } else { 1.0 - value_wei.as_u128() as f64 / hold_wei.as_u128() as f64 };
+    Ok(json!({
+        "lp_tokens": held.to_string(),
+        "amount_a": amount_a.to_string(),
+        "amount_b": amount_b.to_string(),
+        "value_wei": value_wei.to_string(),
+        "hold_value_wei": hold_wei.to_string(),
+        "impermanent_loss": impermanent_loss,
+        "entered_at": entry["entered_at"],
+    }))
+}
+
+fn deadline() -> U256 {
+    U256::from(Utc::now().timestamp() + 600)
+}
+
+fn less_slippage(amount: U256, slippage: f64) -> U256 {
+    U256::from((amount.as_u128() as f64 * (1.0 - slippage)) as u128)
+}
+
+// Deposit idle balances into the pair. Only what is above the idle reserve kept in the wallet for
+// active strategies is used, up to the position's max_amount_a, matched at the current pool ratio
+async fn deploy(web3: &web3::Web3<FailoverTransport>, backend: &ExecutionBackend, position: &PositionConfig, config: &Value) -> Result<(), LpYieldError> {
+    let holder = backend.recipient();
+    let keep = config["idle_reserve_pct"].as_f64().unwrap_or(0.5).clamp(0.0, 1.0);
+    let free = |balance: U256| U256::from((balance.as_u128() as f64 * (1.0 - keep)) as u128);
+    let free_a = free(inventory::get_token_balance(web3, position.token_a, holder).await?);
+    let free_b = free(inventory::get_token_balance(web3, position.token_b, holder).await?);
+
+    let (reserve_a, reserve_b, _, _) = pair_state(web3, position, holder).await?;
+    if reserve_a.is_zero() || reserve_b.is_zero() {
+        return Ok(());
+    }
+    let mut amount_a = free_a.min(position.max_amount_a);
+    let mut amount_b = amount_a.saturating_mul(reserve_b) / reserve_a;
+    if amount_b > free_b {
+        amount_b = free_b;
+        amount_a = amount_b.saturating_mul(reserve_a) / reserve_b;
+    }
+    let min_deposit = config["min_deposit_a"].as_str().and_then(|amount| U256::from_dec_str(amount).ok()).unwrap_or_default();
+    if amount_a.is_zero() || amount_b.is_zero() || amount_a < min_deposit {
+        return Ok(());
+    }
+
+    let slippage = config["slippage_tolerance"].as_f64().unwrap_or(0.005);
+    let add = [
+        ethabi::short_signature("addLiquidity", &[ParamType::Address, ParamType::Address, ParamType::Uint(256), ParamType::Uint(256), ParamType::Uint(256), ParamType::Uint(256), ParamType::Address, ParamType::Uint(256)]).to_vec(),
+        ethabi::encode(&[
+            Token::Address(position.token_a),
+            Token::Address(position.token_b),
+            Token::Uint(amount_a),
+            Token::Uint(amount_b),
+            Token::Uint(less_slippage(amount_a, slippage)),
+            Token::Uint(less_slippage(amount_b, slippage)),
+            Token::Address(holder),
+            Token::Uint(deadline()),
+        ]),
+    ]
+    .concat();
+    let calls = vec![
+        execution::approve_call(position.token_a, position.router, amount_a)?,
+        execution::approve_call(position.token_b, position.router, amount_b)?,
+        Call { to: position.router, value: U256::zero(), data: add },
+    ];
+    let submission = backend.execute_calls(web3, "lp_yield", calls).await?;
+
+    // A top-up adds to what is already in
+    let mut entries = entries().lock().unwrap();
+    let previous = entries.get(&position.name).cloned().unwrap_or(Value::Null);
+    let entry = json!({
+        "deposited_a": amount_field(&previous, "deposited_a").saturating_add(amount_a).to_string(),
+        "deposited_b": amount_field(&previous, "deposited_b").saturating_add(amount_b).to_string(),
+        "entered_at": previous["entered_at"].as_str().map(String::from).unwrap_or_else(|| Utc::now().to_rfc3339()),
+    });
+    entries.insert(position.name.clone(), entry);
+    save_entries(&entries);
+    drop(entries);
+    info!("Deployed {} / {} of idle capital into {}: {:?}", amount_a, amount_b, position.name, submission);
+    record("deposit", &position.name, json!({ "amount_a": amount_a.to_string(), "amount_b": amount_b.to_string(), "submission": submission }));
+    Ok(())
+}
+
+// Remove all of our liquidity from the pair
+async fn withdraw(web3: &web3::Web3<FailoverTransport>, backend: &ExecutionBackend, position: &PositionConfig, config: &Value, reason: &str) -> Result<U256, LpYieldError> {
+    let holder = backend.recipient();
+    let (reserve_a, reserve_b, supply, held) = pair_state(web3, position, holder).await?;
+    if held.is_zero() {
+        let mut entries = entries().lock().unwrap();
+        entries.remove(&position.name);
+        save_entries(&entries);
+        return Ok(U256::zero());
+    }
+    let (amount_a, amount_b) = (share(reserve_a, held, supply), share(reserve_b, held, supply));
+    let slippage = config["slippage_tolerance"].as_f64().unwrap_or(0.005);
+    let remove = [
+        ethabi::short_signature("removeLiquidity", &[ParamType::Address, ParamType::Address, ParamType::Uint(256), ParamType::Uint(256), ParamType::Uint(256), ParamType::Address, ParamType::Uint(256)]).to_vec(),
+        ethabi::encode(&[
+            Token::Address(position.token_a),
+            Token::Address(position.token_b),
+            Token::Uint(held),
+            Token::Uint(less_slippage(amount_a, slippage)),
+            Token::Uint(less_slippage(amount_b, slippage)),
+            Token::Address(holder),
+            Token::Uint(deadline()),
+        ]),
+    ]
+    .concat();
+    let calls = vec![
+        execution::approve_call(position.pair, position.router, held)?,
+        Call { to: position.router, value: U256::zero(), data: remove },
+    ];
+    let submission = backend.execute_calls(web3, "lp_yield", calls).await?;
+    let value_wei = pricing::token_to_wei(web3, position.token_a, amount_a).await?.saturating_add(pricing::token_to_wei(web3, position.token_b, amount_b).await?);
+
+    let mut entries = entries().lock().unwrap();
+    entries.remove(&position.name);
+    save_entries(&entries);
+    drop(entries);
+    valuations().lock().unwrap().remove(&position.name);
+    info!("Withdrew {} from {} ({}): {:?}", value_wei, position.name, reason, submission);
+    record("withdraw", &position.name, json!({ "reason": reason, "amount_a": amount_a.to_string(), "amount_b": amount_b.to_string(), "value_wei": value_wei.to_string(), "submission": submission }));
+    Ok(value_wei)
+}
+
+// One pass: answer a pending recall first, then exit positions past the impermanent loss limit and
+// put idle capital to work once no recall has happened for redeploy_cooldown_secs
+pub async fn check_positions(web3: &web3::Web3<FailoverTransport>) -> Result<(), LpYieldError> {
+    let config = load_lp_yield_config();
+    let backend = ExecutionBackend::from_config(web3)?;
+    let holder = backend.recipient();
+    let positions = positions(&config);
+
+    let pending = recall().lock().unwrap().pending.take();
+    if let Some((strategy, needed)) = pending {
+        // Largest positions first, until the request is covered
+        let mut open: Vec<(PositionConfig, U256)> = Vec::new();
+        for position in positions.iter().filter(|position| entries().lock().unwrap().contains_key(&position.name)) {
+            let entry = entries().lock().unwrap().get(&position.name).cloned().unwrap_or(Value::Null);
+            let value = match valuation(web3, position, &entry, holder).await {
+                Ok(valuation) => amount_field(&valuation, "value_wei"),
+                Err(_) => U256::zero(),
+            };
+            open.push((position.clone(), value));
+        }
+        open.sort_by_key(|entry| std::cmp::Reverse(entry.1));
+        let mut released = U256::zero();
+        for (position, _) in open {
+            if released >= needed {
+                break;
+            }
+            match withdraw(web3, &backend, &position, &config, &format!("recalled for {}", strategy)).await {
+                Ok(value) => released = released.saturating_add(value),
+                Err(e) => error!("Recalling {} failed: {}", position.name, e),
+            }
+        }
+        recall().lock().unwrap().last = Some(Utc::now());
+        return Ok(());
+    }
+
+    let max_loss = config["max_impermanent_loss"].as_f64().unwrap_or(0.02);
+    let cooldown = ChronoDuration::seconds(config["redeploy_cooldown_secs"].as_i64().unwrap_or(900));
+    let cooling_down = recall().lock().unwrap().last.is_some_and(|last| Utc::now() - last < cooldown);
+    for position in &positions {
+        let entry = entries().lock().unwrap().get(&position.name).cloned();
+        match entry {
+            Some(entry) => {
+                let valuation = valuation(web3, position, &entry, holder).await?;
+                let loss = valuation["impermanent_loss"].as_f64().unwrap_or(0.0);
+                valuations().lock().unwrap().insert(position.name.clone(), valuation);
+                if loss > max_loss {
+                    warn!("Impermanent loss on {} is {:.2}%, above the {:.2}% limit", position.name, loss * 100.0, max_loss * 100.0);
+                    withdraw(web3, &backend, position, &config, "impermanent loss limit").await?;
+                }
+            }
+            None if !cooling_down => deploy(web3, &backend, position, &config).await?,
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+// Periodic checks, woken early by a capital recall
+pub async fn run_lp_yield(web3: web3::Web3<FailoverTransport>) {
+    let interval = load_lp_yield_config()["check_interval_secs"].as_u64().unwrap_or(300);
+    loop {
+        if let Err(e) = check_positions(&web3).await {
+            error!("LP yield check failed: {}", e);
+        }
+        tokio::select! {
+            _ = sleep(Duration::from_secs(interval)) => {}
+            _ = wake().notified() => {}
+        }
+    }
+}
+
+// Open positions with their latest valuation, for the dashboard
+pub fn snapshot() -> Value {
+    let entries = entries().lock().unwrap().clone();
+    let valuations = valuations().lock().unwrap();
+    let positions: HashMap<String, Value> = entries
+        .into_iter()
+        .map(|(name, entry)| {
+            let valuation = valuations.get(&name).cloned().unwrap_or(Value::Null);
+            (name, json!({ "entry": entry, "valuation": valuation }))
+        })
+        .collect();
+    let recall = recall().lock().unwrap();
+    json!({
+        "positions": positions,
+        "pending_recall": recall.pending.as_ref().map(|(strategy, amount)| json!({ "strategy": strategy, "amount_wei": amount.to_string() })),
+        "last_recall": recall.last.map(|last| last.to_rfc3339()),
+    })
+}
+
+// Custom error type for passive LP positions
+#[derive(Error, Debug)]
+pub enum LpYieldError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Inventory error: {0}")]
+    InventoryError(#[from] InventoryError),
+    #[error("Pricing error: {0}")]
+    PricingError(#[from] PricingError),
+}
+
+// Implement conversion for LpYieldError to Web3 error
+impl From<LpYieldError> for web3::Error {
+    fn from(error: LpYieldError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,108 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use tokio::time::{sleep, Duration, Instant};
+use log::{info, warn};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::events;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::templates;
+use crate::modules::testnet;
+
+// Load the chain profiles (pending visibility is a property of the chain and its endpoint)
+fn load_chains_config() -> Value {
+    let config_path = "config/chains_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read chains config file");
+    testnet::apply(config_path, serde_json::from_str(&config_data).expect("Unable to parse chains config file"))
+}
+
+// Whether our endpoint shows other senders' pending transactions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MempoolVisibility {
+    Visible,
+    // Private mempool or sequencer-only ordering: nothing to frontrun or sandwich before a block
+    Hidden,
+    // Not probed yet
+    Unknown,
+}
+
+// Strategies that only work from pending transactions
+const MEMPOOL_STRATEGIES: [&str; 2] = ["frontrunning", "sandwich"];
+
+fn visibility_state() -> &'static Mutex<MempoolVisibility> {
+    static VISIBILITY: OnceLock<Mutex<MempoolVisibility>> = OnceLock::new();
+    VISIBILITY.get_or_init(|| Mutex::new(MempoolVisibility::Unknown))
+}
+
+pub fn visibility() -> MempoolVisibility {
+    *visibility_state().lock().unwrap()
+}
+
+// Until a probe says otherwise the mempool is assumed visible, which is the pre-existing behavior
+pub fn mempool_available() -> bool {
+    visibility() != MempoolVisibility::Hidden
+}
+
+pub fn requires_mempool(strategy: &str) -> bool {
+    MEMPOOL_STRATEGIES.contains(&strategy)
+}
+
+// Count pending transaction hashes seen through a pending filter over the probe window. Endpoints
+// that don't support pending filters count as hidden
+async fn probe(web3: &web3::Web3<FailoverTransport>, window: Duration, min_pending: usize) -> MempoolVisibility {
+    let filter = match web3.eth_filter().create_pending_transactions_filter().await {
+        Ok(filter) => filter,
+        Err(e) => {
+            warn!("Endpoint rejected a pending transaction filter: {}", e);
+            return MempoolVisibility::Hidden;
+        }
+    };
+
+    let started = Instant::now();
+    let mut seen = 0;
+    while started.elapsed() < window && seen < min_pending {
+        sleep(Duration::from_secs(1)).await;
+        match filter.poll().await {
+            Ok(Some(hashes)) => seen += hashes.len(),
+            Ok(None) => {}
+            Err(e) => {
+                warn!("Pending transaction filter failed: {}", e);
+                break;
+            }
+        }
+    }
+    info!("Mempool probe saw {} pending transactions in {}s", seen, started.elapsed().as_secs());
+    if seen >= min_pending { MempoolVisibility::Visible } else { MempoolVisibility::Hidden }
+}
+
+// Decide once at startup whether mempool strategies can run here. The chain profile's
+// "pending_visibility" is "visible", "hidden" or "auto" (probe the endpoint)
+pub async fn detect(web3: &web3::Web3<FailoverTransport>) -> MempoolVisibility {
+    let config = load_chains_config();
+    let chain_name = config["chain"].as_str().unwrap_or("ethereum").to_string();
+    let chain = &config["chains"][&chain_name];
+
+    let detected = match chain["pending_visibility"].as_str().unwrap_or("auto") {
+        "visible" => MempoolVisibility::Visible,
+        "hidden" => MempoolVisibility::Hidden,
+        _ => {
+            let window = Duration::from_secs(config["mempool_probe_secs"].as_u64().unwrap_or(20));
+            let min_pending = config["mempool_probe_min_pending"].as_u64().unwrap_or(5) as usize;
+            probe(web3, window, min_pending).await
+        }
+    };
+    *visibility_state().lock().unwrap() = detected;
+
+    if detected == MempoolVisibility::Hidden {
+        let strategies = MEMPOOL_STRATEGIES.join(", ");
+        warn!("No pending transaction visibility on {}; disabling {} and detecting from blocks", chain_name, strategies);
+        let (subject, body) = templates::render("mempool_hidden", &[("chain", chain_name.clone()), ("strategies", strategies)]);
+        alerts::raise(&format!("mempool_hidden:{}", chain_name), Severity::Warn, &subject, &body);
+    }
+    events::emit("mempool_visibility", json!({ "chain": chain_name, "visibility": detected }));
+    detected
+}
@@ -0,0 +1,366 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use web3::futures::StreamExt;
+use web3::signing::keccak256;
+use web3::transports::WebSocket;
+use web3::types::{Address, BlockId, BlockNumber, Transaction, TransactionId, H256, U256};
+use log::{error, info, warn};
+use chrono::Utc;
+use tokio::time::{sleep, timeout, Duration};
+
+use crate::modules::cancellation_risk;
+use crate::modules::inclusion;
+use crate::modules::providers;
+use crate::modules::sandwich;
+use crate::modules::ws_metrics;
+
+// Load the mempool recorder configuration
+fn load_mempool_recorder_config() -> Value {
+    let config_path = "config/mempool_recorder_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read mempool recorder config file");
+    serde_json::from_str(&config_data).expect("Unable to parse mempool recorder config file")
+}
+
+// Router, token and liquidity methods worth naming in the archive
+const KNOWN_METHODS: &[(&str, &str)] = &[
+    ("swapExactETHForTokens(uint256,address[],address,uint256)", "swap"),
+    ("swapExactTokensForTokens(uint256,uint256,address[],address,uint256)", "swap"),
+    ("swapExactTokensForETH(uint256,uint256,address[],address,uint256)", "swap"),
+    ("swapTokensForExactTokens(uint256,uint256,address[],address,uint256)", "swap"),
+    ("swapETHForExactTokens(uint256,address[],address,uint256)", "swap"),
+    ("exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))", "swap"),
+    ("exactInput((bytes,address,uint256,uint256,uint256))", "swap"),
+    ("execute(bytes,bytes[],uint256)", "swap"),
+    ("multicall(uint256,bytes[])", "multicall"),
+    ("multicall(bytes[])", "multicall"),
+    ("addLiquidity(address,address,uint256,uint256,uint256,uint256,address,uint256)", "liquidity"),
+    ("addLiquidityETH(address,uint256,uint256,uint256,address,uint256)", "liquidity"),
+    ("removeLiquidity(address,address,uint256,uint256,uint256,address,uint256)", "liquidity"),
+    ("removeLiquidityETH(address,uint256,uint256,uint256,address,uint256)", "liquidity"),
+    ("approve(address,uint256)", "approve"),
+    ("transfer(address,uint256)", "transfer"),
+    ("transferFrom(address,address,uint256)", "transfer"),
+];
+
+// Method name for calldata hitting one of the known router, token and liquidity methods
+pub fn known_method(input: &[u8]) -> Option<&'static str> {
+    if input.len() < 4 {
+        return None;
+    }
+    KNOWN_METHODS
+        .iter()
+        .find(|(signature, _)| input[..4] == keccak256(signature.as_bytes())[..4])
+        .map(|(signature, _)| signature.split('(').next().unwrap_or(signature))
+}
+
+// Classify a pending transaction by its calldata: method, kind and swapped pair when decodable
+pub fn decode_intent(input: &[u8]) -> Value {
+    if input.is_empty() {
+        return json!({ "kind": "eth_transfer" });
+    }
+    if input.len() < 4 {
+        return json!({ "kind": "unknown" });
+    }
+
+    let method = KNOWN_METHODS
+        .iter()
+        .find(|(signature, _)| input[..4] == keccak256(signature.as_bytes())[..4]);
+    match method {
+        Some((signature, kind)) => {
+            let pair = sandwich::victim_pair(input).map(|(token_in, token_out)| json!([token_in, token_out]));
+            json!({
+                "kind": kind,
+                "method": signature.split('(').next().unwrap_or(signature),
+                "pair": pair,
+            })
+        }
+        None => json!({ "kind": "unknown", "selector": format!("0x{:02x}{:02x}{:02x}{:02x}", input[0], input[1], input[2], input[3]) }),
+    }
+}
+
+// Deterministic sampling so a given hash is kept or dropped the same way across restarts
+fn sampled(hash: H256, sample_rate: f64) -> bool {
+    if sample_rate >= 1.0 {
+        return true;
+    }
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&hash.as_bytes()[..8]);
+    (u64::from_be_bytes(head) as f64 / u64::MAX as f64) < sample_rate
+}
+
+fn unix_millis() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+// A sampled transaction waiting to be included, replaced or dropped
+struct Observation {
+    record: Value,
+    sender: Option<Address>,
+    to: Option<Address>,
+    nonce: U256,
+    first_seen_ms: i64,
+    first_seen_block: u64,
+    gas_price_percentile: f64,
+}
+
+fn observe(tx: &Transaction, block: u64, gas_price_percentile: f64) -> Observation {
+    let first_seen_ms = unix_millis();
+    let record = json!({
+        "hash": tx.hash,
+        "from": tx.from,
+        "to": tx.to,
+        "nonce": tx.nonce.to_string(),
+        "value": tx.value.to_string(),
+        "gas": tx.gas.to_string(),
+        "gas_price": tx.gas_price.map(|price| price.to_string()),
+        "max_fee_per_gas": tx.max_fee_per_gas.map(|fee| fee.to_string()),
+        "max_priority_fee_per_gas": tx.max_priority_fee_per_gas.map(|fee| fee.to_string()),
+        "intent": decode_intent(&tx.input.0),
+        "first_seen_ms": first_seen_ms,
+        "first_seen_block": block,
+        "gas_price_percentile": gas_price_percentile,
+    });
+    Observation { record, sender: tx.from, to: tx.to, nonce: tx.nonce, first_seen_ms, first_seen_block: block, gas_price_percentile }
+}
+
+// Attach the inclusion outcome to an observation's record
+fn resolve(observation: Observation, outcome: Value) -> Value {
+    let mut record = observation.record;
+    record["outcome"] = outcome;
+    record["resolved_ms"] = json!(unix_millis());
+    record["pending_ms"] = json!(unix_millis() - observation.first_seen_ms);
+    record
+}
+
+// Hourly (by default) JSON-lines files, gzipped once they are rotated out
+struct ArchiveWriter {
+    dir: PathBuf,
+    rotate_after: Duration,
+    compress: bool,
+    current: Option<(PathBuf, File, Instant)>,
+}
+
+impl ArchiveWriter {
+    fn new(config: &Value) -> Self {
+        ArchiveWriter {
+            dir: PathBuf::from(config["output_dir"].as_str().unwrap_or("data/mempool")),
+            rotate_after: Duration::from_secs(config["rotate_minutes"].as_u64().unwrap_or(60) * 60),
+            compress: config["compress"].as_bool().unwrap_or(true),
+            current: None,
+        }
+    }
+
+    fn write(&mut self, record: &Value) -> io::Result<()> {
+        let expired = self.current.as_ref().map(|(_, _, opened)| opened.elapsed() >= self.rotate_after).unwrap_or(false);
+        if expired {
+            self.rotate();
+        }
+        if self.current.is_none() {
+            fs::create_dir_all(&self.dir)?;
+            let path = self.dir.join(format!("pending-{}.jsonl", Utc::now().format("%Y%m%dT%H%M%S")));
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            self.current = Some((path, file, Instant::now()));
+        }
+        let (_, file, _) = self.current.as_mut().unwrap();
+        writeln!(file, "{}", record)
+    }
+
+    // Close the current file and replace it with its gzipped copy
+    fn rotate(&mut self) {
+        let (path, file, _) = match self.current.take() {
+            Some(current) => current,
+            None => return,
+        };
+        drop(file);
+        if !self.compress {
+            return;
+        }
+        match compress_file(&path) {
+            Ok(compressed) => info!("Archived mempool sample to {}", compressed.display()),
+            Err(e) => error!("Failed to compress {}: {}", path.display(), e),
+        }
+    }
+}
+
+fn compress_file(path: &Path) -> io::Result<PathBuf> {
+    let compressed = path.with_extension("jsonl.gz");
+    let mut encoder = GzEncoder::new(File::create(&compressed)?, Compression::default());
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(path)?;
+    Ok(compressed)
+}
+
+// Read an archive file (plain or gzipped) back into records for research and backtests
+pub fn load_archive(path: &str) -> io::Result<Vec<Value>> {
+    let file = File::open(path)?;
+    let reader: Box<dyn BufRead> = if path.ends_with(".gz") {
+        Box::new(BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    Ok(reader.lines().map_while(Result::ok).filter_map(|line| serde_json::from_str(&line).ok()).collect())
+}
+
+// Next item from either subscription
+enum StreamEvent {
+    Pending(H256),
+    Head(Option<u64>),
+}
+
+// Resolve tracked transactions against a new block: included, replaced by the same sender/nonce, or dropped
+fn settle_block(
+    tracked: &mut HashMap<H256, Observation>,
+    block_number: u64,
+    transactions: &[Transaction],
+    drop_after_blocks: u64,
+) -> Vec<Value> {
+    let mut resolved = Vec::new();
+
+    for (position, tx) in transactions.iter().enumerate() {
+        if let Some(observation) = tracked.remove(&tx.hash) {
+            let blocks_pending = block_number.saturating_sub(observation.first_seen_block);
+            inclusion::record_inclusion_latency(observation.gas_price_percentile, blocks_pending);
+            inclusion::record_pending_time(observation.to, observation.gas_price_percentile, unix_millis() - observation.first_seen_ms);
+            cancellation_risk::record_outcome(observation.sender, observation.gas_price_percentile, false);
+            let outcome = json!({
+                "status": "included",
+                "block": block_number,
+                "position": position,
+                "blocks_pending": blocks_pending,
+            });
+            resolved.push(resolve(observation, outcome));
+            continue;
+        }
+        let replaced: Vec<H256> = tracked
+            .iter()
+            .filter(|(_, observation)| observation.sender.is_some() && observation.sender == tx.from && observation.nonce == tx.nonce)
+            .map(|(hash, _)| *hash)
+            .collect();
+        for hash in replaced {
+            if let Some(observation) = tracked.remove(&hash) {
+                cancellation_risk::record_outcome(observation.sender, observation.gas_price_percentile, true);
+                resolved.push(resolve(observation, json!({ "status": "replaced", "block": block_number, "replaced_by": tx.hash })));
+            }
+        }
+    }
+
+    let expired: Vec<H256> = tracked
+        .iter()
+        .filter(|(_, observation)| block_number.saturating_sub(observation.first_seen_block) > drop_after_blocks)
+        .map(|(hash, _)| *hash)
+        .collect();
+    for hash in expired {
+        if let Some(observation) = tracked.remove(&hash) {
+            resolved.push(resolve(observation, json!({ "status": "dropped", "last_checked_block": block_number })));
+        }
+    }
+
+    resolved
+}
+
+// Record sampled pending transactions and their inclusion outcomes until the process exits
+pub async fn run_mempool_recorder() {
+    let config = load_mempool_recorder_config();
+    let configured_url = config["websocket_url"].as_str().expect("WebSocket URL not found").to_string();
+    let sample_rate = config["sample_rate"].as_f64().unwrap_or(1.0);
+    let drop_after_blocks = config["drop_after_blocks"].as_u64().unwrap_or(25);
+    let max_tracked = config["max_tracked"].as_u64().unwrap_or(50_000) as usize;
+    let max_silence = Duration::from_secs(config["max_silence_secs"].as_u64().unwrap_or(30));
+    let stream_name = "mempool_recorder";
+
+    let mut writer = ArchiveWriter::new(&config);
+    let mut tracked: HashMap<H256, Observation> = HashMap::new();
+
+    loop {
+        let websocket_url = providers::websocket_url_for(stream_name, &configured_url);
+        let transport = match WebSocket::new(&websocket_url).await {
+            Ok(transport) => transport,
+            Err(e) => {
+                error!("Mempool recorder failed to connect to {}: {}", websocket_url, e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        let web3 = web3::Web3::new(transport);
+
+        let subscriptions = async {
+            let pending = web3.eth_subscribe().subscribe_new_pending_transactions().await?;
+            let heads = web3.eth_subscribe().subscribe_new_heads().await?;
+            Ok::<_, web3::Error>((pending, heads))
+        };
+        let (mut pending, mut heads) = match subscriptions.await {
+            Ok(streams) => streams,
+            Err(e) => {
+                error!("Mempool recorder failed to subscribe: {}", e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        let mut current_block = web3.eth().block_number().await.map(|block| block.as_u64()).unwrap_or(0);
+        info!("Mempool recorder subscribed (sample rate {})", sample_rate);
+
+        loop {
+            let event = timeout(max_silence, async {
+                tokio::select! {
+                    hash = pending.next() => hash.map(|hash| hash.map(StreamEvent::Pending)),
+                    header = heads.next() => header.map(|header| header.map(|header| StreamEvent::Head(header.number.map(|number| number.as_u64())))),
+                }
+            })
+            .await;
+
+            match event {
+                Ok(Some(Ok(StreamEvent::Pending(hash)))) => {
+                    ws_metrics::record_event(stream_name, None);
+                    if !sampled(hash, sample_rate) || tracked.contains_key(&hash) {
+                        continue;
+                    }
+                    if tracked.len() >= max_tracked {
+                        warn!("Mempool recorder is tracking {} transactions, skipping new ones", tracked.len());
+                        continue;
+                    }
+                    if let Ok(Some(tx)) = web3.eth().transaction(TransactionId::Hash(hash)).await {
+                        let percentile = inclusion::gas_price_percentile(&web3, &tx).await.unwrap_or(0.0);
+                        tracked.insert(hash, observe(&tx, current_block, percentile));
+                    }
+                }
+                Ok(Some(Ok(StreamEvent::Head(number)))) => {
+                    current_block = number.unwrap_or(current_block + 1);
+                    let block = match web3.eth().block_with_txs(BlockId::Number(BlockNumber::Number(current_block.into()))).await {
+                        Ok(Some(block)) => block,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            error!("Mempool recorder failed to fetch block {}: {}", current_block, e);
+                            continue;
+                        }
+                    };
+                    for record in settle_block(&mut tracked, current_block, &block.transactions, drop_after_blocks) {
+                        if let Err(e) = writer.write(&record) {
+                            error!("Failed to write mempool record: {}", e);
+                        }
+                    }
+                }
+                Ok(Some(Err(e))) => {
+                    error!("Mempool recorder subscription error: {}", e);
+                    break;
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    warn!("Mempool recorder streams silent for {:?}, reconnecting", max_silence);
+                    break;
+                }
+            }
+        }
+
+        ws_metrics::record_reconnect(stream_name);
+        sleep(Duration::from_secs(1)).await;
+    }
+}
@@ -1,17 +1,20 @@
 use serde_json::Value;
 use std::fs;
-use web3::types::{U256, Address, H160};
-use web3::contract::Options;
-use web3::contract::Contract;
-use web3::transports::Http;
-use log::{error, info};
+use web3::types::{Address, U256};
+use log::{error, info, warn};
 use lettre::{Message, SmtpTransport, Transport};
 use lettre::transport::smtp::authentication::Credentials;
 use thiserror::Error;
 use tokio::time::{sleep, Duration};
-use std::str::FromStr;
 use tokio_tungstenite::connect_async;
-use futures_util::{StreamExt, SinkExt};
+use futures::{StreamExt, SinkExt};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::ens;
+use crate::modules::execution::{ExecutionBackend, ExecutionError};
+use crate::modules::multicall::{self, MulticallError};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::storage::{self, StorageError};
 
 // Load the monitoring configuration
 fn load_monitoring_config() -> Value {
@@ -33,8 +36,8 @@ pub async fn monitor_websocket_for_events() -> Result<(), MonitoringError> {
     write.send("subscribe_to_events".into()).await.expect("Failed to send WebSocket message");
 
     while let Some(msg) = read.next().await {
-        let msg_text = msg.expect("Error reading WebSocket message").to_text().unwrap();
-        info!("Received WebSocket message: {}", msg_text);
+        let msg = msg.expect("Error reading WebSocket message");
+        info!("Received WebSocket message: {}", msg.to_text().unwrap_or_default());
         // Implement logic for handling real-time events
     }
 
@@ -62,85 +65,145 @@ pub fn send_email_notification(subject: &str, body: &str) -> Result<(), Monitori
         .credentials(creds)
         .build();
 
-    for _ in 0..3 {  // Retry logic
-        if mailer.send(&email).is_ok() {
-            info!("Email sent successfully.");
-            return Ok(());
+    let mut attempts = 0;
+    loop {  // Retry logic
+        match mailer.send(&email) {
+            Ok(_) => {
+                info!("Email sent successfully.");
+                return Ok(());
+            }
+            Err(e) if attempts < 2 => {
+                error!("Failed to send email ({}). Retrying...", e);
+                attempts += 1;
+                std::thread::sleep(Duration::from_secs(5));
+            }
+            Err(e) => return Err(MonitoringError::SmtpError(e)),
         }
-        error!("Failed to send email. Retrying...");
-        sleep(Duration::from_secs(5)).await;
     }
-
-    Err(MonitoringError::EmailError(lettre::error::Error::Client))
 }
 
-// Send SMS notification with retry logic (Twilio example)
-pub fn send_sms_notification(body: &str) -> Result<(), MonitoringError> {
+// Send SMS notification through Twilio's Messages API, with retry logic
+pub async fn send_sms_notification(body: &str) -> Result<(), MonitoringError> {
     let config = load_monitoring_config();
-    let twilio_sid = config["twilio_sid"].as_str().expect("Twilio SID not found");
-    let twilio_token = config["twilio_token"].as_str().expect("Twilio token not found");
-    let recipient_phone = config["recipient_phone"].as_str().expect("Recipient phone not found");
+    let twilio_sid = config["twilio_sid"].as_str().ok_or(MonitoringError::SmsNotConfigured)?;
+    let twilio_token = config["twilio_token"].as_str().ok_or(MonitoringError::SmsNotConfigured)?;
+    let from_phone = config["twilio_from_phone"].as_str().ok_or(MonitoringError::SmsNotConfigured)?;
+    let recipient_phone = config["recipient_phone"].as_str().ok_or(MonitoringError::SmsNotConfigured)?;
 
+    let mut status = 0;
     for _ in 0..3 {  // Retry logic
-        let result = twilio::OutboundMessage::new(twilio_sid, twilio_token)
-            .to(recipient_phone)
-            .body(body)
-            .send();
-
-        if result.is_ok() {
+        let response = reqwest::Client::new()
+            .post(format!("https://api.twilio.com/2010-04-01/Accounts/{}/Messages.json", twilio_sid))
+            .basic_auth(twilio_sid, Some(twilio_token))
+            .form(&[("To", recipient_phone), ("From", from_phone), ("Body", body)])
+            .send()
+            .await?;
+
+        if response.status().is_success() {
             info!("SMS sent successfully.");
             return Ok(());
         }
-        error!("Failed to send SMS. Retrying...");
+        status = response.status().as_u16();
+        error!("Failed to send SMS ({}). Retrying...", status);
         sleep(Duration::from_secs(5)).await;
     }
 
-    Err(MonitoringError::TwilioError(twilio::error::Error::Client))
+    Err(MonitoringError::SmsRejected(status))
 }
 
-// Calculate and monitor real-time profit for each module
-pub async fn monitor_real_time_profit(web3: &web3::Web3<Http>, modules: Vec<H160>) -> f64 {
-    let mut total_profit: f64 = 0.0;
+// Send a Telegram message through the Bot API
+pub async fn send_telegram_notification(text: &str) -> Result<(), MonitoringError> {
     let config = load_monitoring_config();
-
-    for module in modules {
-        let initial_balance = web3.eth().balance(module, None).await.expect("Failed to fetch initial balance");
-        let current_balance = web3.eth().balance(module, None).await.expect("Failed to fetch current balance");
-
-        let initial_balance_f64 = initial_balance.low_u64() as f64 / 1e18;
-        let current_balance_f64 = current_balance.low_u64() as f64 / 1e18;
-
-        let profit = current_balance_f64 - initial_balance_f64;
-        total_profit += profit;
+    let bot_token = config["alerts"]["telegram_bot_token"].as_str().ok_or(MonitoringError::TelegramNotConfigured)?;
+    let chat_id = config["alerts"]["telegram_chat_id"].as_str().ok_or(MonitoringError::TelegramNotConfigured)?;
+
+    let response = reqwest::Client::new()
+        .post(format!("https://api.telegram.org/bot{}/sendMessage", bot_token))
+        .json(&serde_json::json!({ "chat_id": chat_id, "text": text }))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(MonitoringError::TelegramRejected(response.status().as_u16()));
     }
+    info!("Telegram message sent successfully.");
+    Ok(())
+}
 
+// Realized profit across all strategies, in ETH, from the trade store
+pub async fn monitor_real_time_profit() -> Result<f64, MonitoringError> {
+    let total_profit = storage::realized_pnl_eth().await?;
     info!("Real-time profit: {}", total_profit);
-    total_profit
+    Ok(total_profit)
 }
 
-// Fetch the real-time gas usage based on recent transactions sent by the bot
-pub async fn get_real_time_gas_usage(web3: &web3::Web3<Http>, bot_address: H160) -> f64 {
-    let mut total_gas_used: f64 = 0.0;
+// Gas spent by the bot's settled transactions, in ETH, from the trade store
+pub async fn get_real_time_gas_usage() -> Result<f64, MonitoringError> {
+    let total_gas = storage::gas_spent_eth().await?;
+    info!("Real-time gas usage: {}", total_gas);
+    Ok(total_gas)
+}
 
-    // Example: query the last 100 transactions from the bot
-    let tx_count = web3
-        .eth()
-        .transaction_count(bot_address, None)
-        .await
-        .expect("Failed to get transaction count");
+fn min_balance(value: &Value) -> U256 {
+    value.as_str().and_then(|amount| U256::from_dec_str(amount).ok()).unwrap_or_default()
+}
 
-    let start_tx_count = tx_count.saturating_sub(U256::from(100));
+// Check the execution wallet's ETH and every configured token balance against its minimum, reading
+// the tokens in one multicall, and raise a warn alert for each one below. Returns how many are low
+pub async fn check_balances(web3: &web3::Web3<FailoverTransport>) -> Result<usize, MonitoringError> {
+    let config = load_monitoring_config()["balance_checks"].clone();
+    let holder = ExecutionBackend::from_config(web3)?.recipient();
+    let mut low = Vec::new();
+
+    let eth_balance = web3.eth().balance(holder, None).await?;
+    let min_eth = min_balance(&config["min_eth_wei"]);
+    if eth_balance < min_eth {
+        low.push(("ETH".to_string(), eth_balance, min_eth));
+    }
 
-    for nonce in start_tx_count.low_u64()..tx_count.low_u64() {
-        let tx_hash = web3.eth().transaction_by_hash(H160::from_low_u64_be(nonce)).await;
-        if let Ok(Some(receipt)) = web3.eth().transaction_receipt(tx_hash).await {
-            let gas_price: U256 = receipt.gas_used.unwrap_or(U256::zero());
-            total_gas_used += gas_price.low_u64() as f64;
+    let tokens: Vec<(Address, String, U256)> = config["tokens"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| {
+            let token = ens::parse_address(entry["token"].as_str()?)?;
+            Some((token, entry["symbol"].as_str().unwrap_or("?").to_string(), min_balance(&entry["min_balance"])))
+        })
+        .collect();
+    let queries: Vec<(Address, Address)> = tokens.iter().map(|(token, _, _)| (*token, holder)).collect();
+    let balances = multicall::token_balances(web3, &queries).await?;
+    for ((token, symbol, minimum), balance) in tokens.into_iter().zip(balances) {
+        match balance {
+            Some(balance) if balance < minimum => low.push((symbol, balance, minimum)),
+            Some(_) => {}
+            None => warn!("Unable to read {} ({:?}) balance of {:?}", symbol, token, holder),
         }
     }
 
-    info!("Real-time gas usage: {}", total_gas_used);
-    total_gas_used
+    for (symbol, balance, minimum) in &low {
+        alerts::raise(
+            &format!("low_balance:{}", symbol),
+            Severity::Warn,
+            &format!("Low {} balance", symbol),
+            &format!("{:?} holds {} {} (raw), below the {} minimum", holder, balance, symbol, minimum),
+        );
+    }
+    Ok(low.len())
+}
+
+// Check wallet balances on the configured interval
+pub async fn run_balance_checks(web3: web3::Web3<FailoverTransport>) {
+    loop {
+        let config = load_monitoring_config()["balance_checks"].clone();
+        if config["enabled"].as_bool().unwrap_or(false) {
+            match check_balances(&web3).await {
+                Ok(0) => {}
+                Ok(low) => info!("Balance check found {} balances below their minimum", low),
+                Err(e) => warn!("Balance check failed: {}", e),
+            }
+        }
+        sleep(Duration::from_secs(config["interval_secs"].as_u64().unwrap_or(300))).await;
+    }
 }
 
 // Custom error type for monitoring
@@ -148,10 +211,26 @@ pub async fn get_real_time_gas_usage(web3: &web3::Web3<Http>, bot_address: H160)
 pub enum MonitoringError {
     #[error("Email error: {0}")]
     EmailError(#[from] lettre::error::Error),
+    #[error("SMTP error: {0}")]
+    SmtpError(#[from] lettre::transport::smtp::Error),
     #[error("Web3 error: {0}")]
     Web3Error(#[from] web3::Error),
-    #[error("Twilio error: {0}")]
-    TwilioError(#[from] twilio::error::Error),
+    #[error("Twilio SID, token or phone numbers not configured")]
+    SmsNotConfigured,
+    #[error("Twilio rejected the message with status {0}")]
+    SmsRejected(u16),
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("Telegram bot token or chat id not configured")]
+    TelegramNotConfigured,
+    #[error("Telegram rejected the message with status {0}")]
+    TelegramRejected(u16),
+    #[error("Storage error: {0}")]
+    StorageError(#[from] StorageError),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Multicall error: {0}")]
+    MulticallError(#[from] MulticallError),
 }
 
 // Implement conversion for MonitoringError to Web3 error
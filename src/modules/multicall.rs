@@ -0,0 +1,164 @@
+use serde_json::Value;
+use web3::ethabi::{self, Function, ParamType, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, BlockId, BlockNumber, Bytes, CallRequest, U256};
+use web3::Transport;
+use log::debug;
+use thiserror::Error;
+
+use crate::modules::config;
+use crate::modules::ens;
+
+// Multicall3 is deployed at the same address on every chain it supports
+const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+// Load the multicall configuration; its fields carry comments, so it goes through the config loader
+fn load_multicall_config() -> Value {
+    config::load_value("config/multicall_config.json").expect("Unable to load multicall config file")
+}
+
+// Outputs of a batch of view calls and the block they were all read at. Calls that reverted are None
+#[derive(Clone, Debug, Default)]
+pub struct Aggregated {
+    pub block: u64,
+    pub outputs: Vec<Option<Vec<u8>>>,
+}
+
+// First four bytes of the keccak hash of a function signature, shared by everything that builds or
+// recognizes calldata by hand
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn multicall_address() -> Result<Address, MulticallError> {
+    load_multicall_config()["address"]
+        .as_str()
+        .and_then(ens::parse_address)
+        .or_else(|| ens::parse_address(MULTICALL3_ADDRESS))
+        .ok_or(MulticallError::NoMulticall)
+}
+
+// One tryBlockAndAggregate eth_call, at the given block or the head
+async fn try_block_and_aggregate<T: Transport>(
+    web3: &web3::Web3<T>,
+    multicall: Address,
+    calls: &[(Address, Vec<u8>)],
+    block: Option<u64>,
+) -> Result<Aggregated, MulticallError> {
+    let encoded = calls.iter().map(|(target, data)| Token::Tuple(vec![Token::Address(*target), Token::Bytes(data.clone())])).collect();
+    let mut data = selector("tryBlockAndAggregate(bool,(address,bytes)[])").to_vec();
+    data.extend(ethabi::encode(&[Token::Bool(false), Token::Array(encoded)]));
+    let request = CallRequest { to: Some(multicall), data: Some(Bytes(data)), ..Default::default() };
+    let output = web3.eth().call(request, block.map(|block| BlockId::Number(BlockNumber::Number(block.into())))).await?;
+
+    let result = ParamType::Tuple(vec![ParamType::Bool, ParamType::Bytes]);
+    let fields = ethabi::decode(&[ParamType::Uint(256), ParamType::FixedBytes(32), ParamType::Array(Box::new(result))], &output.0)?;
+    let outputs: Vec<Option<Vec<u8>>> = fields[2]
+        .clone()
+        .into_array()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| match entry.into_tuple().as_deref() {
+            Some([Token::Bool(true), Token::Bytes(output)]) => Some(output.clone()),
+            _ => None,
+        })
+        .collect();
+    if outputs.len() != calls.len() {
+        return Err(MulticallError::Malformed(calls.len(), outputs.len()));
+    }
+    Ok(Aggregated { block: fields[0].clone().into_uint().unwrap_or_default().as_u64(), outputs })
+}
+
+// Run any number of view calls through Multicall3, calls_per_batch per eth_call. Every batch after
+// the first is pinned to the first one's block, so the outputs are consistent with each other
+pub async fn aggregate<T: Transport>(web3: &web3::Web3<T>, calls: &[(Address, Vec<u8>)]) -> Result<Aggregated, MulticallError> {
+    if calls.is_empty() {
+        return Ok(Aggregated::default());
+    }
+    let multicall = multicall_address()?;
+    let per_batch = load_multicall_config()["calls_per_batch"].as_u64().unwrap_or(500).max(1) as usize;
+
+    let mut aggregated = Aggregated::default();
+    for batch in calls.chunks(per_batch) {
+        let pinned = (!aggregated.outputs.is_empty()).then_some(aggregated.block);
+        let result = try_block_and_aggregate(web3, multicall, batch, pinned).await?;
+        aggregated.block = result.block;
+        aggregated.outputs.extend(result.outputs);
+    }
+    debug!("Multicall read {} calls at block {} in {} batches", calls.len(), aggregated.block, calls.len().div_ceil(per_batch));
+    Ok(aggregated)
+}
+
+// Call one ABI function on many contracts, each with its own arguments, and decode the outputs
+pub async fn call_function<T: Transport>(
+    web3: &web3::Web3<T>,
+    function: &Function,
+    calls: &[(Address, Vec<Token>)],
+) -> Result<Vec<Option<Vec<Token>>>, MulticallError> {
+    let encoded = calls
+        .iter()
+        .map(|(target, args)| Ok((*target, function.encode_input(args)?)))
+        .collect::<Result<Vec<_>, MulticallError>>()?;
+    let aggregated = aggregate(web3, &encoded).await?;
+    Ok(aggregated.outputs.into_iter().map(|output| output.and_then(|output| function.decode_output(&output).ok())).collect())
+}
+
+fn erc20_function(name: &str) -> Result<Function, MulticallError> {
+    let erc20 = ethabi::Contract::load(&include_bytes!("abi/erc20_abi.json")[..])?;
+    Ok(erc20.function(name)?.clone())
+}
+
+fn first_uint(output: Option<Vec<Token>>) -> Option<U256> {
+    output?.into_iter().next()?.into_uint()
+}
+
+// ERC20 balances of (token, holder) pairs in one request; None where balanceOf reverted
+pub async fn token_balances<T: Transport>(web3: &web3::Web3<T>, queries: &[(Address, Address)]) -> Result<Vec<Option<U256>>, MulticallError> {
+    let calls: Vec<(Address, Vec<Token>)> = queries.iter().map(|(token, holder)| (*token, vec![Token::Address(*holder)])).collect();
+    Ok(call_function(web3, &erc20_function("balanceOf")?, &calls).await?.into_iter().map(first_uint).collect())
+}
+
+// ERC20 allowances of (token, owner, spender) triples in one request
+pub async fn allowances<T: Transport>(web3: &web3::Web3<T>, queries: &[(Address, Address, Address)]) -> Result<Vec<Option<U256>>, MulticallError> {
+    let calls: Vec<(Address, Vec<Token>)> = queries
+        .iter()
+        .map(|(token, owner, spender)| (*token, vec![Token::Address(*owner), Token::Address(*spender)]))
+        .collect();
+    Ok(call_function(web3, &erc20_function("allowance")?, &calls).await?.into_iter().map(first_uint).collect())
+}
+
+// Native balances in one request, through Multicall3's getEthBalance
+pub async fn eth_balances<T: Transport>(web3: &web3::Web3<T>, accounts: &[Address]) -> Result<Vec<Option<U256>>, MulticallError> {
+    let multicall = multicall_address()?;
+    let calls: Vec<(Address, Vec<u8>)> = accounts
+        .iter()
+        .map(|account| (multicall, [selector("getEthBalance(address)").to_vec(), ethabi::encode(&[Token::Address(*account)])].concat()))
+        .collect();
+    let aggregated = aggregate(web3, &calls).await?;
+    Ok(aggregated
+        .outputs
+        .into_iter()
+        .map(|output| output.filter(|output| output.len() >= 32).map(|output| U256::from_big_endian(&output[..32])))
+        .collect())
+}
+
+// Custom error type for multicall batching
+#[derive(Error, Debug)]
+pub enum MulticallError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("No Multicall3 address configured")]
+    NoMulticall,
+    #[error("Multicall returned {1} results for {0} calls")]
+    Malformed(usize, usize),
+}
+
+// Implement conversion for MulticallError to Web3 error
+impl From<MulticallError> for web3::Error {
+    fn from(error: MulticallError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,374 @@
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use web3::types::{Address, BlockNumber, Bytes, Transaction, TransactionId, TransactionRequest, H256, U256};
+use log::{error, info, warn};
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::chains::{self, ChainError};
+use crate::modules::coordination;
+use crate::modules::ens;
+use crate::modules::events;
+use crate::modules::gas_feed;
+use crate::modules::multicall;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::spend_caps::SpendCapError;
+use crate::modules::templates;
+
+// Transactions we sent, so pending ones can be found again after a restart
+const SUBMISSIONS_PATH: &str = "data/submissions.jsonl";
+// Every recovery action taken or withheld
+const RECOVERY_LEDGER_PATH: &str = "data/nonce_recovery.jsonl";
+
+// Load the stuck-nonce recovery policy
+fn load_nonce_recovery_config() -> Value {
+    let config_path = "config/nonce_recovery_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read nonce recovery config file");
+    serde_json::from_str(&config_data).expect("Unable to parse nonce recovery config file")
+}
+
+fn load_global_config() -> Value {
+    let config_path = "config/global_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read global config file");
+    serde_json::from_str(&config_data).expect("Unable to parse global config file")
+}
+
+// What to do with a transaction that has been pending too long
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecoveryPolicy {
+    // Resend the same transaction with higher fees
+    Bump,
+    // Replace it with a zero-value transfer to ourselves
+    Cancel,
+    // Only report it
+    Report,
+    // Cancel trades, which could land at a price the strategy no longer wants, and bump the rest
+    Auto,
+}
+
+impl RecoveryPolicy {
+    fn from_config(config: &Value) -> Self {
+        match config["policy"].as_str().unwrap_or("auto") {
+            "bump" => RecoveryPolicy::Bump,
+            "cancel" => RecoveryPolicy::Cancel,
+            "report" => RecoveryPolicy::Report,
+            _ => RecoveryPolicy::Auto,
+        }
+    }
+
+    // Bump or Cancel for one stuck transaction
+    fn for_transaction(self, tx: &Transaction) -> Self {
+        match self {
+            RecoveryPolicy::Auto if price_sensitive(tx) => RecoveryPolicy::Cancel,
+            RecoveryPolicy::Auto => RecoveryPolicy::Bump,
+            policy => policy,
+        }
+    }
+}
+
+// Plain transfers, ERC20 transfers and approvals mean the same whenever they land; any other call
+// (a swap, a liquidation, a bundle leg) is priced for the moment it was sent
+fn price_sensitive(tx: &Transaction) -> bool {
+    let data = &tx.input.0;
+    if data.is_empty() {
+        return false;
+    }
+    let neutral = [multicall::selector("transfer(address,uint256)"), multicall::selector("approve(address,uint256)")];
+    !neutral.iter().any(|selector| data.starts_with(selector))
+}
+
+// Our submissions not yet known to be mined or dropped: hash -> (wallet, first sent). After a
+// restart, submissions from the last day are picked up again from the log
+type Tracked = HashMap<H256, (Address, DateTime<Utc>)>;
+
+fn tracked() -> &'static Mutex<Tracked> {
+    static TRACKED: OnceLock<Mutex<Tracked>> = OnceLock::new();
+    TRACKED.get_or_init(|| {
+        let mut tracked = HashMap::new();
+        let cutoff = Utc::now() - chrono::Duration::days(1);
+        for line in fs::read_to_string(SUBMISSIONS_PATH).unwrap_or_default().lines() {
+            let record: Value = match serde_json::from_str(line) {
+                Ok(record) => record,
+                Err(_) => continue,
+            };
+            let hash = record["tx_hash"].as_str().and_then(|hash| hash.parse::<H256>().ok());
+            let wallet = record["wallet"].as_str().and_then(ens::parse_address);
+            let sent_at = record["timestamp"].as_str().and_then(|ts| DateTime::parse_from_rfc3339(ts).ok());
+            if let (Some(hash), Some(wallet), Some(sent_at)) = (hash, wallet, sent_at) {
+                if sent_at > cutoff {
+                    tracked.insert(hash, (wallet, sent_at.with_timezone(&Utc)));
+                }
+            }
+        }
+        Mutex::new(tracked)
+    })
+}
+
+// Replacements sent per (wallet, nonce)
+fn bumps() -> &'static Mutex<HashMap<(Address, U256), u32>> {
+    static BUMPS: OnceLock<Mutex<HashMap<(Address, U256), u32>>> = OnceLock::new();
+    BUMPS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn append(path: &str, record: &Value) {
+    let _ = fs::create_dir_all("data");
+    match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write to {}: {}", path, e);
+            }
+        }
+        Err(e) => error!("Failed to open {}: {}", path, e),
+    }
+}
+
+// Remember a transaction sent from one of our wallets
+pub fn track(wallet: Address, tx_hash: H256) {
+    let now = Utc::now();
+    tracked().lock().unwrap().insert(tx_hash, (wallet, now));
+    append(SUBMISSIONS_PATH, &json!({ "timestamp": now.to_rfc3339(), "wallet": wallet, "tx_hash": tx_hash }));
+}
+
+// The wallet from the global config plus any extra wallets in the recovery config
+fn wallets(config: &Value) -> Vec<Address> {
+    let mut wallets: Vec<Address> = load_global_config()["wallet_address"].as_str().and_then(ens::parse_address).into_iter().collect();
+    for wallet in config["extra_wallets"].as_array().cloned().unwrap_or_default() {
+        if let Some(wallet) = wallet.as_str().and_then(ens::parse_address) {
+            if !wallets.contains(&wallet) {
+                wallets.push(wallet);
+            }
+        }
+    }
+    wallets
+}
+
+fn bumped(fee: U256, bump_pct: f64) -> U256 {
+    U256::from((fee.as_u128() as f64 * (1.0 + bump_pct / 100.0)).ceil() as u128)
+}
+
+// Fees for a replacement: the original's bumped by at least bump_pct (nodes reject smaller bumps),
+// or today's consensus if that is higher
+async fn replacement_fees(web3: &web3::Web3<FailoverTransport>, original: Option<&Transaction>, bump_pct: f64) -> Result<(U256, U256), RecoveryError> {
+    let current = gas_feed::consensus_estimate(web3).await.ok();
+    let (current_max, current_tip) = match &current {
+        Some(estimate) => (estimate.max_fee, estimate.priority_fee),
+        None => {
+            let gas_price = web3.eth().gas_price().await?;
+            (gas_price, gas_price)
+        }
+    };
+    let (old_max, old_tip) = match original {
+        Some(tx) => (
+            tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default(),
+            tx.max_priority_fee_per_gas.or(tx.gas_price).unwrap_or_default(),
+        ),
+        None => (U256::zero(), U256::zero()),
+    };
+    Ok((bumped(old_max, bump_pct).max(current_max), bumped(old_tip, bump_pct).max(current_tip)))
+}
+
+// Send the replacement for one nonce: the same transaction with higher fees (bump) or a
+// zero-value self-transfer (cancel, and every gap fill)
+async fn replace(
+    web3: &web3::Web3<FailoverTransport>,
+    wallet: Address,
+    nonce: U256,
+    original: Option<&Transaction>,
+    policy: RecoveryPolicy,
+    config: &Value,
+) -> Result<H256, RecoveryError> {
+    let bump_pct = config["fee_bump_pct"].as_f64().unwrap_or(12.5);
+    let (max_fee, priority_fee) = replacement_fees(web3, original, bump_pct).await?;
+    let max_fee_cap = U256::from(config["max_fee_gwei"].as_u64().unwrap_or(300)) * U256::exp10(9);
+    if max_fee > max_fee_cap {
+        return Err(RecoveryError::FeeCapReached(max_fee));
+    }
+
+    let request = match (policy, original) {
+        (RecoveryPolicy::Bump, Some(tx)) => TransactionRequest {
+            from: wallet,
+            to: tx.to,
+            gas: Some(tx.gas),
+            value: Some(tx.value),
+            data: Some(tx.input.clone()),
+            nonce: Some(nonce),
+            max_fee_per_gas: Some(max_fee),
+            max_priority_fee_per_gas: Some(priority_fee.min(max_fee)),
+            ..Default::default()
+        },
+        _ => TransactionRequest {
+            from: wallet,
+            to: Some(wallet),
+            gas: Some(U256::from(21_000)),
+            value: Some(U256::zero()),
+            data: Some(Bytes(Vec::new())),
+            nonce: Some(nonce),
+            max_fee_per_gas: Some(max_fee),
+            max_priority_fee_per_gas: Some(priority_fee.min(max_fee)),
+            ..Default::default()
+        },
+    };
+    // The replacement's gas counts against the recovery routine's own spending cap when it is signed
+    let tx_hash = chains::submit(web3, "nonce_recovery", request).await?;
+    track(wallet, tx_hash);
+    Ok(tx_hash)
+}
+
+fn report(action: &str, wallet: Address, nonce: U256, original: Option<H256>, outcome: Result<H256, String>) -> Value {
+    let record = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "action": action,
+        "wallet": wallet,
+        "nonce": nonce.to_string(),
+        "original": original,
+        "replacement": outcome.as_ref().ok(),
+        "error": outcome.as_ref().err(),
+    });
+    append(RECOVERY_LEDGER_PATH, &record);
+    events::emit("nonce_recovery", record.clone());
+    let (subject, body) = templates::render("stuck_nonce", &[
+        ("wallet", format!("{:?}", wallet)),
+        ("nonce", nonce.to_string()),
+        ("action", action.to_string()),
+        ("original", original.map(|hash| format!("{:?}", hash)).unwrap_or_else(|| String::from("none (nonce gap)"))),
+        ("outcome", match &outcome {
+            Ok(hash) => format!("replacement {:?} sent", hash),
+            Err(e) => format!("not replaced: {}", e),
+        }),
+    ]);
+    alerts::raise(&format!("stuck_nonce:{:?}:{}", wallet, nonce), Severity::Warn, &subject, &body);
+    record
+}
+
+// Check every wallet once: fill nonce gaps below our highest pending transaction and apply the
+// policy to transactions pending longer than stale_after_secs. Returns the actions taken
+pub async fn recover_stuck_nonces(web3: &web3::Web3<FailoverTransport>) -> Result<Vec<Value>, RecoveryError> {
+    let config = load_nonce_recovery_config();
+    // Replacements are sends; only the instance holding the wallet lock may make them
+    if !coordination::is_writer() {
+        return Ok(Vec::new());
+    }
+    let policy = RecoveryPolicy::from_config(&config);
+    let stale_after = chrono::Duration::seconds(config["stale_after_secs"].as_i64().unwrap_or(180));
+    let max_bumps = config["max_bumps"].as_u64().unwrap_or(3) as u32;
+    let fill_gaps = config["fill_gaps"].as_bool().unwrap_or(true);
+    let mut actions = Vec::new();
+
+    for wallet in wallets(&config) {
+        let confirmed = web3.eth().transaction_count(wallet, Some(BlockNumber::Latest)).await?;
+
+        // Our pending transactions by nonce; mined and dropped ones stop being tracked
+        let candidates: Vec<(H256, DateTime<Utc>)> = tracked()
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, (owner, _))| *owner == wallet)
+            .map(|(hash, (_, sent_at))| (*hash, *sent_at))
+            .collect();
+        let mut pending: BTreeMap<U256, (Transaction, DateTime<Utc>)> = BTreeMap::new();
+        for (hash, sent_at) in candidates {
+            match web3.eth().transaction(TransactionId::Hash(hash)).await? {
+                Some(tx) if tx.block_number.is_none() && tx.nonce >= confirmed => {
+                    // Keep the latest replacement per nonce
+                    if pending.get(&tx.nonce).is_none_or(|(_, seen)| sent_at > *seen) {
+                        pending.insert(tx.nonce, (tx, sent_at));
+                    }
+                }
+                _ => {
+                    tracked().lock().unwrap().remove(&hash);
+                }
+            }
+        }
+
+        // A missing nonce below a pending one blocks it forever; only a transaction at that nonce helps
+        if fill_gaps {
+            if let Some(highest) = pending.keys().next_back().copied() {
+                let node_pending = web3.eth().transaction_count(wallet, Some(BlockNumber::Pending)).await?;
+                let mut nonce = confirmed;
+                while nonce < highest {
+                    if !pending.contains_key(&nonce) && nonce >= node_pending {
+                        let outcome = replace(web3, wallet, nonce, None, RecoveryPolicy::Cancel, &config).await.map_err(|e| e.to_string());
+                        warn!("Filled nonce gap {} for {:?}: {:?}", nonce, wallet, outcome);
+                        actions.push(report("fill_gap", wallet, nonce, None, outcome));
+                    }
+                    nonce += U256::one();
+                }
+            }
+        }
+
+        let now = Utc::now();
+        for (nonce, (tx, sent_at)) in pending {
+            if now - sent_at < stale_after {
+                continue;
+            }
+            let attempts = *bumps().lock().unwrap().get(&(wallet, nonce)).unwrap_or(&0);
+            let (action, outcome) = if policy == RecoveryPolicy::Report {
+                ("report", Err(String::from("policy is report-only")))
+            } else if attempts >= max_bumps {
+                ("report", Err(format!("already replaced {} times", attempts)))
+            } else {
+                let policy = policy.for_transaction(&tx);
+                let action = if policy == RecoveryPolicy::Cancel { "cancel" } else { "bump" };
+                let outcome = replace(web3, wallet, nonce, Some(&tx), policy, &config).await.map_err(|e| e.to_string());
+                if outcome.is_ok() {
+                    *bumps().lock().unwrap().entry((wallet, nonce)).or_default() += 1;
+                }
+                (action, outcome)
+            };
+            info!("Stuck transaction {:?} at nonce {} for {:?}: {} ({:?})", tx.hash, nonce, wallet, action, outcome);
+            actions.push(report(action, wallet, nonce, Some(tx.hash), outcome));
+        }
+    }
+    Ok(actions)
+}
+
+// Recover at startup, then on the configured interval
+pub async fn run_nonce_recovery(web3: web3::Web3<FailoverTransport>) {
+    loop {
+        let config = load_nonce_recovery_config();
+        match recover_stuck_nonces(&web3).await {
+            Ok(actions) if !actions.is_empty() => info!("Nonce recovery took {} actions", actions.len()),
+            Ok(_) => {}
+            Err(e) => warn!("Nonce recovery failed: {}", e),
+        }
+        sleep(Duration::from_secs(config["interval_secs"].as_u64().unwrap_or(60))).await;
+    }
+}
+
+// Latest recovery actions, newest first, for the dashboard
+pub fn recent_actions(limit: usize) -> Vec<Value> {
+    fs::read_to_string(RECOVERY_LEDGER_PATH)
+        .unwrap_or_default()
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
+}
+
+// Custom error type for stuck-nonce recovery
+#[derive(Error, Debug)]
+pub enum RecoveryError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Chain submission error: {0}")]
+    ChainError(#[from] ChainError),
+    #[error("Spending cap: {0}")]
+    SpendCapError(#[from] SpendCapError),
+    #[error("Replacement would need a max fee of {0} wei, above max_fee_gwei")]
+    FeeCapReached(U256),
+}
+
+// Implement conversion for RecoveryError to Web3 error
+impl From<RecoveryError> for web3::Error {
+    fn from(error: RecoveryError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use web3::types::{Address, U256};
+
+use crate::modules::ens;
+
+// How closely two tokens track each other, which decides the thresholds a spread between them has to clear
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PairClass {
+    Volatile,
+    // Pegged to each other through a redemption or a shared underlying (WETH/stETH, WBTC/cbBTC)
+    Correlated,
+    // Both tokens are dollar stablecoins
+    Stable,
+}
+
+impl PairClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PairClass::Volatile => "volatile",
+            PairClass::Correlated => "correlated",
+            PairClass::Stable => "stable",
+        }
+    }
+}
+
+// Spread threshold, sizing and slippage for one class of pair
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PairParams {
+    pub class: PairClass,
+    // Round-trip spread, before gas, the quotes must show
+    pub min_spread_bps: f64,
+    pub quote_amount: U256,
+    pub slippage_tolerance: f64,
+}
+
+fn addresses(value: &Value) -> Vec<Address> {
+    value.as_array().map(|list| list.iter().filter_map(|entry| entry.as_str().and_then(ens::parse_address)).collect()).unwrap_or_default()
+}
+
+// Stable when both tokens are listed stablecoins, correlated when both sit in the same correlated
+// group, volatile otherwise
+pub fn classify(config: &Value, token_a: Address, token_b: Address) -> PairClass {
+    let classes = &config["pair_classes"];
+    let stable = addresses(&classes["stable"]["tokens"]);
+    if stable.contains(&token_a) && stable.contains(&token_b) {
+        return PairClass::Stable;
+    }
+    let correlated = classes["correlated"]["groups"]
+        .as_array()
+        .map(|groups| groups.iter().map(addresses).any(|group| group.contains(&token_a) && group.contains(&token_b)))
+        .unwrap_or(false);
+    if correlated {
+        return PairClass::Correlated;
+    }
+    PairClass::Volatile
+}
+
+// Parameters for a pair from its class's section of the arbitrage config. Anything a class leaves out
+// falls back to the top-level quote_amount and slippage_tolerance, and to no spread threshold
+pub fn params_for(config: &Value, token_a: Address, token_b: Address) -> PairParams {
+    let class = classify(config, token_a, token_b);
+    let section = &config["pair_classes"][class.as_str()];
+    let amount = |value: &Value| value.as_str().and_then(|amount| U256::from_dec_str(amount).ok());
+    PairParams {
+        class,
+        min_spread_bps: section["min_spread_bps"].as_f64().unwrap_or(0.0),
+        quote_amount: amount(&section["quote_amount"]).or_else(|| amount(&config["quote_amount"])).unwrap_or_else(|| U256::exp10(18)),
+        slippage_tolerance: section["slippage_tolerance"].as_f64().or(config["slippage_tolerance"].as_f64()).unwrap_or(0.005),
+    }
+}
+
+// Round-trip spread of amount_back over amount_in in basis points; negative when it loses
+pub fn spread_bps(amount_in: U256, amount_back: U256) -> f64 {
+    if amount_in.is_zero() {
+        return 0.0;
+    }
+    let amount_in = amount_in.as_u128() as f64;
+    (amount_back.as_u128() as f64 - amount_in) / amount_in * 10_000.0
+}
+
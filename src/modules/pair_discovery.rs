@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::str::FromStr;
+use web3::types::Address;
+use log::{error, info, warn};
+use thiserror::Error;
+use chrono::Utc;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::config_audit::{self, ConfigChange};
+use crate::modules::explorer::ExplorerClient;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::templates;
+
+// Scanner pair set in use, and pairs waiting for approval in manual mode
+const ACTIVE_PAIRS_PATH: &str = "data/token_pairs.json";
+const PENDING_PAIRS_PATH: &str = "data/pending_token_pairs.json";
+
+// Load the pair discovery configuration
+fn load_pair_discovery_config() -> Value {
+    let config_path = "config/pair_discovery_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read pair discovery config file");
+    serde_json::from_str(&config_data).expect("Unable to parse pair discovery config file")
+}
+
+// A pair found on a DEX subgraph with its last-day stats
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DiscoveredPair {
+    pub dex: String,
+    pub token0: Address,
+    pub token1: Address,
+    pub symbol0: String,
+    pub symbol1: String,
+    pub volume_usd: f64,
+    pub reserve_usd: f64,
+    pub tx_count: u64,
+}
+
+impl DiscoveredPair {
+    // Order-independent key so the same pair on two DEXes is only scanned once
+    fn key(&self) -> (Address, Address) {
+        if self.token0 < self.token1 {
+            (self.token0, self.token1)
+        } else {
+            (self.token1, self.token0)
+        }
+    }
+}
+
+// Pull the top pairs by 24h volume from one subgraph
+pub async fn fetch_top_pairs(client: &reqwest::Client, subgraph: &Value, top_n: u64) -> Result<Vec<DiscoveredPair>, PairDiscoveryError> {
+    let dex = subgraph["name"].as_str().unwrap_or("unknown").to_string();
+    let url = subgraph["url"].as_str().ok_or(PairDiscoveryError::MissingConfig("subgraphs[].url"))?;
+    // Uniswap V2 and Sushi name their day-data fields differently
+    let volume_field = subgraph["volume_field"].as_str().unwrap_or("dailyVolumeUSD");
+    let tx_field = subgraph["tx_field"].as_str().unwrap_or("dailyTxns");
+
+    let since = Utc::now().timestamp() - 86_400;
+    let query = format!(
+        "{{ pairDayDatas(first: {}, orderBy: {}, orderDirection: desc, where: {{ date_gt: {} }}) {{ token0 {{ id symbol }} token1 {{ id symbol }} reserveUSD {} {} }} }}",
+        top_n, volume_field, since, volume_field, tx_field
+    );
+
+    let response: Value = client.post(url).json(&json!({ "query": query })).send().await?.json().await?;
+    if let Some(errors) = response.get("errors") {
+        return Err(PairDiscoveryError::SubgraphError(format!("{}: {}", dex, errors)));
+    }
+
+    let parse_f64 = |value: &Value| value.as_str().and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+    let pairs = response["data"]["pairDayDatas"]
+        .as_array()
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| {
+                    Some(DiscoveredPair {
+                        dex: dex.clone(),
+                        token0: Address::from_str(entry["token0"]["id"].as_str()?).ok()?,
+                        token1: Address::from_str(entry["token1"]["id"].as_str()?).ok()?,
+                        symbol0: entry["token0"]["symbol"].as_str().unwrap_or_default().to_string(),
+                        symbol1: entry["token1"]["symbol"].as_str().unwrap_or_default().to_string(),
+                        volume_usd: parse_f64(&entry[volume_field]),
+                        reserve_usd: parse_f64(&entry["reserveUSD"]),
+                        tx_count: entry[tx_field].as_str().and_then(|v| v.parse().ok()).unwrap_or(0),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(pairs)
+}
+
+// Token safety filter: denylist, thin liquidity, low activity, tokens without contract code and,
+// when require_verified_source is set, tokens whose source the block explorer has not verified
+pub async fn is_safe_pair(web3: &web3::Web3<FailoverTransport>, pair: &DiscoveredPair, config: &Value) -> bool {
+    let denylist: HashSet<Address> = config["token_denylist"]
+        .as_array()
+        .map(|tokens| tokens.iter().filter_map(|token| token.as_str()).filter_map(|token| Address::from_str(token).ok()).collect())
+        .unwrap_or_default();
+    if denylist.contains(&pair.token0) || denylist.contains(&pair.token1) {
+        return false;
+    }
+    if pair.symbol0.is_empty() || pair.symbol1.is_empty() {
+        return false;
+    }
+    if pair.reserve_usd < config["min_reserve_usd"].as_f64().unwrap_or(100_000.0) {
+        return false;
+    }
+    if pair.tx_count < config["min_daily_txns"].as_u64().unwrap_or(100) {
+        return false;
+    }
+
+    for token in [pair.token0, pair.token1] {
+        match web3.eth().code(token, None).await {
+            Ok(code) if !code.0.is_empty() => {}
+            _ => return false,
+        }
+    }
+
+    if config["require_verified_source"].as_bool().unwrap_or(false) {
+        let explorer = match ExplorerClient::from_config() {
+            Ok(explorer) => explorer,
+            Err(e) => {
+                warn!("Explorer unavailable, rejecting unverifiable pair: {}", e);
+                return false;
+            }
+        };
+        for token in [pair.token0, pair.token1] {
+            match explorer.source_verified(token).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    warn!("Token {:?} has no verified source", token);
+                    return false;
+                }
+                Err(e) => {
+                    warn!("Could not check source verification for {:?}: {}", token, e);
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+// Discover, filter and rank pairs across all configured subgraphs
+pub async fn discover_pairs(web3: &web3::Web3<FailoverTransport>) -> Result<Vec<DiscoveredPair>, PairDiscoveryError> {
+    let config = load_pair_discovery_config();
+    let top_n = config["top_n"].as_u64().unwrap_or(25);
+    let client = reqwest::Client::new();
+
+    let mut candidates = Vec::new();
+    for subgraph in config["subgraphs"].as_array().cloned().unwrap_or_default() {
+        match fetch_top_pairs(&client, &subgraph, top_n).await {
+            Ok(pairs) => candidates.extend(pairs),
+            Err(e) => error!("Pair discovery failed for subgraph {}: {}", subgraph["name"], e),
+        }
+    }
+    candidates.sort_by(|a, b| b.volume_usd.partial_cmp(&a.volume_usd).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut seen = HashSet::new();
+    let mut selected = Vec::new();
+    for pair in candidates {
+        if selected.len() as u64 >= top_n || !seen.insert(pair.key()) {
+            continue;
+        }
+        if is_safe_pair(web3, &pair, &config).await {
+            selected.push(pair);
+        } else {
+            warn!("Pair {}/{} on {} rejected by token safety filter", pair.symbol0, pair.symbol1, pair.dex);
+        }
+    }
+
+    Ok(selected)
+}
+
+fn read_pairs(path: &str) -> Vec<DiscoveredPair> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn write_pairs(path: &str, pairs: &[DiscoveredPair]) -> Result<(), PairDiscoveryError> {
+    fs::create_dir_all("data")?;
+    fs::write(path, serde_json::to_string_pretty(pairs)?)?;
+    Ok(())
+}
+
+// Pairs the scanner should use; empty until discovery has run (callers fall back to their config)
+pub fn active_pairs() -> Vec<(Address, Address)> {
+    read_pairs(ACTIVE_PAIRS_PATH).iter().map(|pair| (pair.token0, pair.token1)).collect()
+}
+
+// Active and pending pair sets for the dashboard
+pub fn pair_sets() -> Value {
+    json!({
+        "active": read_pairs(ACTIVE_PAIRS_PATH),
+        "pending": read_pairs(PENDING_PAIRS_PATH),
+    })
+}
+
+// Replace the active pair set and audit which pairs were added or removed
+fn activate_pairs(pairs: &[DiscoveredPair], operator: &str) -> Result<(), PairDiscoveryError> {
+    let label = |pair: &DiscoveredPair| format!("{}/{}", pair.symbol0, pair.symbol1);
+    let old: HashSet<String> = read_pairs(ACTIVE_PAIRS_PATH).iter().map(label).collect();
+    let new: HashSet<String> = pairs.iter().map(label).collect();
+
+    let mut changes: Vec<ConfigChange> = new
+        .difference(&old)
+        .map(|pair| ConfigChange { path: pair.clone(), old_value: None, new_value: Some(json!("active")) })
+        .collect();
+    changes.extend(
+        old.difference(&new)
+            .map(|pair| ConfigChange { path: pair.clone(), old_value: Some(json!("active")), new_value: None }),
+    );
+
+    write_pairs(ACTIVE_PAIRS_PATH, pairs)?;
+    config_audit::record_audit(ACTIVE_PAIRS_PATH, operator, "pair_discovery", &changes);
+    info!("Scanner pair set updated by {}: {} pairs ({} changes)", operator, pairs.len(), changes.len());
+    Ok(())
+}
+
+// Promote the pending pair set (manual mode) to the scanner
+pub fn approve_pending_pairs(operator: &str) -> Result<usize, PairDiscoveryError> {
+    let pending = read_pairs(PENDING_PAIRS_PATH);
+    if pending.is_empty() {
+        return Err(PairDiscoveryError::NothingPending);
+    }
+    activate_pairs(&pending, operator)?;
+    fs::remove_file(PENDING_PAIRS_PATH)?;
+    Ok(pending.len())
+}
+
+// Daily job: refresh the pair set automatically, or stage it for approval in manual mode
+pub async fn run_pair_discovery(web3: web3::Web3<FailoverTransport>) {
+    let config = load_pair_discovery_config();
+    let refresh_interval = config["refresh_interval_secs"].as_u64().unwrap_or(86_400);
+    let manual = config["mode"].as_str() == Some("manual");
+
+    loop {
+        match discover_pairs(&web3).await {
+            Ok(pairs) if pairs.is_empty() => warn!("Pair discovery returned no pairs, keeping current set"),
+            Ok(pairs) if manual => match write_pairs(PENDING_PAIRS_PATH, &pairs) {
+                Ok(()) => {
+                    info!("{} discovered pairs waiting for approval", pairs.len());
+                    let (subject, body) = templates::render("pairs_pending", &[("count", pairs.len().to_string())]);
+                    alerts::raise("pairs_pending", Severity::Warn, &subject, &body);
+                }
+                Err(e) => error!("Failed to stage discovered pairs: {}", e),
+            },
+            Ok(pairs) => {
+                if let Err(e) = activate_pairs(&pairs, "pair_discovery") {
+                    error!("Failed to update scanner pair set: {}", e);
+                }
+            }
+            Err(e) => error!("Pair discovery failed: {}", e),
+        }
+
+        sleep(Duration::from_secs(refresh_interval)).await;
+    }
+}
+
+// Custom error type for pair discovery
+#[derive(Error, Debug)]
+pub enum PairDiscoveryError {
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Subgraph error: {0}")]
+    SubgraphError(String),
+    #[error("Missing pair discovery config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("No discovered pairs are pending approval")]
+    NothingPending,
+}
@@ -0,0 +1,340 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use web3::contract::{Contract, Options};
+use web3::types::{Address, U256};
+use log::{debug, info};
+use thiserror::Error;
+
+use crate::modules::domain::TradeLeg;
+use crate::modules::ens;
+use crate::modules::gas::Urgency;
+use crate::modules::pool_state;
+use crate::modules::pricing::{self, PricingError};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::venues::{self, Venue};
+
+// Load the arbitrage configuration (the path finder section and the default token cycle live there)
+fn load_arbitrage_config() -> Value {
+    let config_path = "config/arbitrage_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read arbitrage config file");
+    serde_json::from_str(&config_data).expect("Unable to parse arbitrage config file")
+}
+
+pub fn enabled() -> bool {
+    load_arbitrage_config()["path_finder"]["enabled"].as_bool().unwrap_or(false)
+}
+
+// Configured input per start token (raw units); tokens without one start at the probe size
+pub fn start_amounts() -> HashMap<Address, U256> {
+    start_amounts_in(&load_arbitrage_config()["path_finder"])
+}
+
+fn start_amounts_in(settings: &Value) -> HashMap<Address, U256> {
+    settings["start_amounts"]
+        .as_object()
+        .map(|amounts| {
+            amounts
+                .iter()
+                .filter_map(|(token, amount)| Some((ens::parse_address(token)?, U256::from_dec_str(amount.as_str()?).ok()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// The best venue for one direction of a pair, and its rate at the probe size (out / in, raw units)
+struct Edge {
+    from: usize,
+    to: usize,
+    venue: Venue,
+    rate: f64,
+}
+
+impl Edge {
+    fn weight(&self) -> f64 {
+        -self.rate.ln()
+    }
+}
+
+struct Graph {
+    tokens: Vec<Address>,
+    edges: Vec<Edge>,
+}
+
+// A cycle quoted at its start amount, ranked by profit after gas
+#[derive(Clone, Debug, Serialize)]
+pub struct Route {
+    pub legs: Vec<TradeLeg>,
+    pub amount_in: U256,
+    pub amount_back: U256,
+    pub gas_fees: U256,
+    // Net of gas, in the start token and in wei for ranking across start tokens
+    pub profit: U256,
+    pub profit_wei: U256,
+    // Product of the legs' rates at the probe size
+    pub spot_return: f64,
+}
+
+impl Route {
+    pub fn start_token(&self) -> Address {
+        self.legs.first().map(|leg| leg.token_in).unwrap_or_default()
+    }
+
+    pub fn label(&self) -> String {
+        self.legs.iter().map(|leg| leg.venue.label()).collect::<Vec<_>>().join(" -> ")
+    }
+}
+
+// Routes from the last search, for the dashboard
+fn latest_routes() -> &'static Mutex<Vec<Route>> {
+    static ROUTES: OnceLock<Mutex<Vec<Route>>> = OnceLock::new();
+    ROUTES.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+fn decimals_cache() -> &'static Mutex<HashMap<Address, u8>> {
+    static DECIMALS: OnceLock<Mutex<HashMap<Address, u8>>> = OnceLock::new();
+    DECIMALS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+async fn token_decimals(web3: &web3::Web3<FailoverTransport>, token: Address) -> Option<u8> {
+    if let Some(decimals) = decimals_cache().lock().unwrap().get(&token) {
+        return Some(*decimals);
+    }
+    let erc20 = Contract::from_json(web3.eth(), token, include_bytes!("abi/erc20_abi.json")).ok()?;
+    let decimals: u8 = erc20.query("decimals", (), None, Options::default(), None).await.ok()?;
+    decimals_cache().lock().unwrap().insert(token, decimals);
+    Some(decimals)
+}
+
+fn to_f64(amount: U256) -> f64 {
+    amount.to_string().parse().unwrap_or(0.0)
+}
+
+fn addresses(value: &Value) -> Vec<Address> {
+    value.as_array().map(|list| list.iter().filter_map(|entry| entry.as_str().and_then(ens::parse_address)).collect()).unwrap_or_default()
+}
+
+// Token universe from path_finder.tokens, or the configured A/B/C cycle
+fn token_universe(config: &Value) -> Vec<Address> {
+    let configured = addresses(&config["path_finder"]["tokens"]);
+    let tokens = if configured.is_empty() {
+        addresses(&json!([config["arbitrage_token_a"], config["arbitrage_token_b"], config["arbitrage_token_c"]]))
+    } else {
+        configured
+    };
+    let mut seen = HashSet::new();
+    tokens.into_iter().filter(|token| seen.insert(*token)).collect()
+}
+
+// Output for amount_in on a venue, from cached pool state when there is some and over RPC otherwise
+async fn quote(web3: &web3::Web3<FailoverTransport>, venue: &Venue, token_in: Address, token_out: Address, amount_in: U256) -> Option<U256> {
+    let cached = pool_state::get(venue.pool).and_then(|state| venue.quote_from_state(&state, token_in, token_out, amount_in));
+    match cached {
+        Some(amount_out) => Some(amount_out),
+        None => venue.quote(web3, token_in, token_out, amount_in).await.ok(),
+    }
+}
+
+// One edge per direction of every pair in the universe with a venue, on the venue with the best
+// rate for probe_tokens whole tokens of the input
+async fn build_graph(web3: &web3::Web3<FailoverTransport>, tokens: Vec<Address>, probe_tokens: f64) -> Result<(Graph, Vec<U256>), PathFinderError> {
+    let mut probes = Vec::with_capacity(tokens.len());
+    for token in &tokens {
+        let decimals = token_decimals(web3, *token).await.ok_or(PathFinderError::NoDecimals(*token))?;
+        probes.push(U256::from((probe_tokens * 1e6) as u128).saturating_mul(U256::exp10(decimals as usize)) / U256::from(1_000_000u64));
+    }
+
+    let mut edges = Vec::new();
+    for from in 0..tokens.len() {
+        for to in (from + 1)..tokens.len() {
+            let pair_venues = match venues::venues_for(web3, tokens[from], tokens[to]).await {
+                Ok(pair_venues) => pair_venues,
+                Err(e) => {
+                    debug!("No venues for {:?}/{:?}: {}", tokens[from], tokens[to], e);
+                    continue;
+                }
+            };
+            for (source, target) in [(from, to), (to, from)] {
+                let mut best: Option<Edge> = None;
+                for venue in &pair_venues {
+                    let amount_out = match quote(web3, venue, tokens[source], tokens[target], probes[source]).await {
+                        Some(amount_out) if !amount_out.is_zero() => amount_out,
+                        _ => continue,
+                    };
+                    let rate = to_f64(amount_out) / to_f64(probes[source]);
+                    if best.as_ref().is_none_or(|edge| rate > edge.rate) {
+                        best = Some(Edge { from: source, to: target, venue: venue.clone(), rate });
+                    }
+                }
+                edges.extend(best);
+            }
+        }
+    }
+    debug!("Path finder graph: {} tokens, {} edges", tokens.len(), edges.len());
+    Ok((Graph { tokens, edges }, probes))
+}
+
+// Tokens a walk passes through, following the predecessor edges back from node at the given depth
+fn walk(graph: &Graph, pred: &[Vec<Option<usize>>], depth: usize, node: usize) -> Vec<usize> {
+    let mut edges = Vec::with_capacity(depth);
+    let mut node = node;
+    for level in (1..=depth).rev() {
+        match pred[level][node] {
+            Some(edge) => {
+                edges.push(edge);
+                node = graph.edges[edge].from;
+            }
+            None => break,
+        }
+    }
+    edges.reverse();
+    edges
+}
+
+// Profitable cycles through source of 2 to max_hops edges, as edge indices. A hop-bounded
+// Bellman-Ford on -ln(rate): after k rounds dist[k][v] is the lightest simple k-edge walk from the
+// source to v, so a negative weight back at the source is a cycle whose rates multiply past 1.
+// Walks never revisit a token, and the source only closes a cycle
+fn find_cycles(graph: &Graph, source: usize, max_hops: usize) -> Vec<Vec<usize>> {
+    let nodes = graph.tokens.len();
+    let mut dist = vec![vec![f64::INFINITY; nodes]; max_hops + 1];
+    let mut pred: Vec<Vec<Option<usize>>> = vec![vec![None; nodes]; max_hops + 1];
+    dist[0][source] = 0.0;
+    let mut cycles = Vec::new();
+
+    for hops in 1..=max_hops {
+        for (index, edge) in graph.edges.iter().enumerate() {
+            let start = dist[hops - 1][edge.from];
+            if !start.is_finite() || (edge.from == source && hops > 1) {
+                continue;
+            }
+            let visited = walk(graph, &pred, hops - 1, edge.from);
+            if edge.to != source && visited.iter().any(|step| graph.edges[*step].from == edge.to) {
+                continue;
+            }
+            if edge.to == source && hops < 2 {
+                continue;
+            }
+            if start + edge.weight() < dist[hops][edge.to] {
+                dist[hops][edge.to] = start + edge.weight();
+                pred[hops][edge.to] = Some(index);
+            }
+        }
+        if hops >= 2 && dist[hops][source] < 0.0 {
+            cycles.push(walk(graph, &pred, hops, source));
+        }
+    }
+    cycles
+}
+
+// Search the token universe for profitable cycles and quote each at its start amount through the
+// venues on its edges. Returns the routes that clear gas, best first by profit in wei
+pub async fn find_routes(web3: &web3::Web3<FailoverTransport>) -> Result<Vec<Route>, PathFinderError> {
+    let config = load_arbitrage_config();
+    let settings = &config["path_finder"];
+    let tokens = token_universe(&config);
+    if tokens.len() < 2 {
+        return Err(PathFinderError::NoUniverse);
+    }
+    let max_hops = settings["max_hops"].as_u64().unwrap_or(3).clamp(2, 6) as usize;
+    let (graph, probes) = build_graph(web3, tokens, settings["probe_tokens"].as_f64().unwrap_or(1.0)).await?;
+
+    // Cycles are searched from each start token; the same cycle reached from another start is skipped
+    let starts = addresses(&settings["start_tokens"]);
+    let mut seen: HashSet<Vec<usize>> = HashSet::new();
+    let mut candidates = Vec::new();
+    for source in 0..graph.tokens.len() {
+        if !starts.is_empty() && !starts.contains(&graph.tokens[source]) {
+            continue;
+        }
+        for cycle in find_cycles(&graph, source, max_hops) {
+            let mut key = cycle.clone();
+            key.sort();
+            if seen.insert(key) {
+                candidates.push((source, cycle));
+            }
+        }
+    }
+
+    let start_amounts = start_amounts_in(settings);
+    let base_gas = settings["base_gas_units"].as_u64().unwrap_or(100_000);
+    let hop_gas = settings["gas_units_per_hop"].as_u64().unwrap_or(120_000);
+    let mut routes = Vec::new();
+    for (source, cycle) in candidates {
+        let start_token = graph.tokens[source];
+        let amount_in = start_amounts.get(&start_token).copied().unwrap_or(probes[source]);
+
+        let mut legs = Vec::with_capacity(cycle.len());
+        let mut amount = amount_in;
+        for step in &cycle {
+            let edge = &graph.edges[*step];
+            let (token_in, token_out) = (graph.tokens[edge.from], graph.tokens[edge.to]);
+            let amount_out = quote(web3, &edge.venue, token_in, token_out, amount).await.unwrap_or_default();
+            legs.push(TradeLeg { venue: edge.venue.clone(), token_in, token_out, amount_in: amount, expected_out: amount_out });
+            amount = amount_out;
+        }
+        let gas_units = U256::from(base_gas + hop_gas * cycle.len() as u64);
+        let gas_fees = pricing::gas_cost_in_token(web3, start_token, gas_units, Urgency::for_strategy("arbitrage")).await?;
+        if amount <= amount_in.saturating_add(gas_fees) {
+            debug!("Cycle from {:?} returns {:?} for {:?} at size; not profitable after {:?} gas", start_token, amount, amount_in, gas_fees);
+            continue;
+        }
+        let profit = amount - amount_in - gas_fees;
+        routes.push(Route {
+            spot_return: cycle.iter().map(|step| graph.edges[*step].rate).product(),
+            profit_wei: pricing::token_to_wei(web3, start_token, profit).await?,
+            legs,
+            amount_in,
+            amount_back: amount,
+            gas_fees,
+            profit,
+        });
+    }
+    routes.sort_by_key(|route| std::cmp::Reverse(route.profit_wei));
+    routes.truncate(settings["max_routes"].as_u64().unwrap_or(10) as usize);
+
+    if let Some(best) = routes.first() {
+        info!("Path finder found {} profitable routes, best {} ({:?} profit)", routes.len(), best.label(), best.profit);
+    }
+    *latest_routes().lock().unwrap() = routes.clone();
+    Ok(routes)
+}
+
+// Routes from the last search, for the dashboard
+pub fn snapshot() -> Value {
+    let routes = latest_routes().lock().unwrap();
+    json!(routes
+        .iter()
+        .map(|route| json!({
+            "route": route.label(),
+            "tokens": route.legs.iter().map(|leg| leg.token_in).collect::<Vec<_>>(),
+            "amount_in": route.amount_in.to_string(),
+            "amount_back": route.amount_back.to_string(),
+            "gas_fees": route.gas_fees.to_string(),
+            "profit": route.profit.to_string(),
+            "profit_wei": route.profit_wei.to_string(),
+            "spot_return": route.spot_return,
+        }))
+        .collect::<Vec<_>>())
+}
+
+// Custom error type for the path finder
+#[derive(Error, Debug)]
+pub enum PathFinderError {
+    #[error("Pricing error: {0}")]
+    PricingError(#[from] PricingError),
+    #[error("Could not read the decimals of {0:?}")]
+    NoDecimals(Address),
+    #[error("The path finder needs at least two tokens in its universe")]
+    NoUniverse,
+}
+
+// Implement conversion for PathFinderError to Web3 error
+impl From<PathFinderError> for web3::Error {
+    fn from(error: PathFinderError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use web3::ethabi::{self, ParamType, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, CallRequest, H256, U256};
+use thiserror::Error;
+
+use crate::modules::execution::Call;
+use crate::modules::multicall;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::signer::{SignerError, TxSender};
+
+// EIP-2612 domain of a token, read once and cached; None when the token has no usable permit
+#[derive(Clone, Debug)]
+pub struct PermitDomain {
+    pub name: String,
+    pub version: String,
+    pub chain_id: U256,
+    pub token: Address,
+}
+
+fn domain_cache() -> &'static Mutex<HashMap<Address, Option<PermitDomain>>> {
+    static DOMAINS: OnceLock<Mutex<HashMap<Address, Option<PermitDomain>>>> = OnceLock::new();
+    DOMAINS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Raw eth_call of a view function, decoded into the given output types
+async fn view(web3: &web3::Web3<FailoverTransport>, to: Address, signature: &str, args: &[Token], outputs: &[ParamType]) -> Result<Vec<Token>, PermitError> {
+    let data = [multicall::selector(signature).to_vec(), ethabi::encode(args)].concat();
+    let request = CallRequest { to: Some(to), data: Some(Bytes(data)), ..Default::default() };
+    let output = web3.eth().call(request, None).await?;
+    Ok(ethabi::decode(outputs, &output.0)?)
+}
+
+impl PermitDomain {
+    pub fn separator(&self) -> H256 {
+        let type_hash = keccak256(b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)");
+        H256::from(keccak256(&ethabi::encode(&[
+            Token::FixedBytes(type_hash.to_vec()),
+            Token::FixedBytes(keccak256(self.name.as_bytes()).to_vec()),
+            Token::FixedBytes(keccak256(self.version.as_bytes()).to_vec()),
+            Token::Uint(self.chain_id),
+            Token::Address(self.token),
+        ])))
+    }
+}
+
+// Whether the token implements EIP-2612 permit: it must expose nonces() and a DOMAIN_SEPARATOR
+// matching the standard domain built from its name and version (DAI-style permits don't match)
+pub async fn permit_domain(web3: &web3::Web3<FailoverTransport>, token: Address) -> Option<PermitDomain> {
+    if let Some(cached) = domain_cache().lock().unwrap().get(&token) {
+        return cached.clone();
+    }
+
+    let domain = async {
+        view(web3, token, "nonces(address)", &[Token::Address(Address::zero())], &[ParamType::Uint(256)]).await.ok()?;
+        let on_chain = view(web3, token, "DOMAIN_SEPARATOR()", &[], &[ParamType::FixedBytes(32)]).await.ok()?;
+        let name = view(web3, token, "name()", &[], &[ParamType::String]).await.ok()?;
+        // Most permit tokens don't expose version(); OpenZeppelin's default is "1"
+        let version = match view(web3, token, "version()", &[], &[ParamType::String]).await {
+            Ok(tokens) => tokens[0].clone().into_string().unwrap_or_else(|| String::from("1")),
+            Err(_) => String::from("1"),
+        };
+        let domain = PermitDomain {
+            name: name[0].clone().into_string()?,
+            version,
+            chain_id: web3.eth().chain_id().await.ok()?,
+            token,
+        };
+        (on_chain[0].clone().into_fixed_bytes()? == domain.separator().as_bytes().to_vec()).then_some(domain)
+    }
+    .await;
+
+    domain_cache().lock().unwrap().insert(token, domain.clone());
+    domain
+}
+
+// EIP-712 digest of a permit: keccak256("\x19\x01" || domain separator || hash of the Permit struct)
+pub fn permit_digest(domain: &PermitDomain, owner: Address, spender: Address, value: U256, nonce: U256, deadline: U256) -> H256 {
+    let type_hash = keccak256(b"Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)");
+    let struct_hash = keccak256(&ethabi::encode(&[
+        Token::FixedBytes(type_hash.to_vec()),
+        Token::Address(owner),
+        Token::Address(spender),
+        Token::Uint(value),
+        Token::Uint(nonce),
+        Token::Uint(deadline),
+    ]));
+    H256::from(keccak256(&[&[0x19, 0x01], domain.separator().as_bytes(), &struct_hash[..]].concat()))
+}
+
+// Sign an EIP-2612 permit for the spender with the sender's key, returning (v, r, s)
+pub async fn sign_permit(
+    web3: &web3::Web3<FailoverTransport>,
+    sender: &TxSender,
+    domain: &PermitDomain,
+    spender: Address,
+    value: U256,
+    deadline: U256,
+) -> Result<(u8, H256, H256), PermitError> {
+    let owner = sender.address();
+    let nonce = view(web3, domain.token, "nonces(address)", &[Token::Address(owner)], &[ParamType::Uint(256)]).await?[0]
+        .clone()
+        .into_uint()
+        .unwrap_or_default();
+    Ok(sender.sign_digest(permit_digest(domain, owner, spender, value, nonce, deadline))?)
+}
+
+// A single router call that redeems the permit and swaps: multicall(selfPermit, swap). The router
+// calls permit with itself as spender, so no approval transaction precedes the swap
+pub fn permit_and_swap_call(
+    token: Address,
+    value: U256,
+    deadline: U256,
+    signature: (u8, H256, H256),
+    swap: Call,
+) -> Call {
+    let (v, r, s) = signature;
+    let self_permit = [
+        multicall::selector("selfPermit(address,uint256,uint256,uint8,bytes32,bytes32)").to_vec(),
+        ethabi::encode(&[
+            Token::Address(token),
+            Token::Uint(value),
+            Token::Uint(deadline),
+            Token::Uint(U256::from(v)),
+            Token::FixedBytes(r.as_bytes().to_vec()),
+            Token::FixedBytes(s.as_bytes().to_vec()),
+        ]),
+    ]
+    .concat();
+    let data = [
+        multicall::selector("multicall(bytes[])").to_vec(),
+        ethabi::encode(&[Token::Array(vec![Token::Bytes(self_permit), Token::Bytes(swap.data)])]),
+    ]
+    .concat();
+    Call { to: swap.to, value: swap.value, data }
+}
+
+// Custom error type for permit signing
+#[derive(Error, Debug)]
+pub enum PermitError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Signer error: {0}")]
+    SignerError(#[from] SignerError),
+}
+
+// Implement conversion for PermitError to Web3 error
+impl From<PermitError> for web3::Error {
+    fn from(error: PermitError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,164 @@
+use serde_json::{json, Value};
+use std::fs;
+use web3::types::{Address, H256};
+use thiserror::Error;
+
+use crate::modules::domain::{self, TradePlan};
+
+fn load_global_config() -> Value {
+    let config_path = "config/global_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read global config file");
+    serde_json::from_str(&config_data).expect("Unable to parse global config file")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GraphFormat {
+    Dot,
+    Json,
+}
+
+impl GraphFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "dot" => Some(GraphFormat::Dot),
+            "json" => Some(GraphFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+fn short(address: &Address) -> String {
+    let full = format!("{:?}", address);
+    format!("{}…{}", &full[..6], &full[full.len() - 4..])
+}
+
+// Tokens in the order the plan first touches them
+fn tokens(plan: &TradePlan) -> Vec<Address> {
+    let mut tokens = Vec::new();
+    for leg in &plan.legs {
+        for token in [leg.token_in, leg.token_out] {
+            if !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        }
+    }
+    tokens
+}
+
+// Tokens as nodes and legs as edges, each edge carrying its venue, amount in and expected out
+pub fn to_json(plan: &TradePlan) -> Value {
+    let nodes: Vec<Value> = tokens(plan).iter().map(|token| json!({ "id": token, "label": short(token) })).collect();
+    let edges: Vec<Value> = plan
+        .legs
+        .iter()
+        .enumerate()
+        .map(|(index, leg)| {
+            json!({
+                "leg": index,
+                "from": leg.token_in,
+                "to": leg.token_out,
+                "venue": leg.venue.label(),
+                "pool": leg.venue.pool,
+                "amount_in": leg.amount_in.to_string(),
+                "expected_out": leg.expected_out.to_string(),
+            })
+        })
+        .collect();
+    json!({
+        "strategy": plan.strategy,
+        "source": plan.source,
+        "expected_profit": plan.expected_profit.to_string(),
+        "nodes": nodes,
+        "edges": edges,
+    })
+}
+
+// The same graph in Graphviz DOT, e.g. for `dot -Tsvg`
+pub fn to_dot(plan: &TradePlan) -> String {
+    let mut dot = format!("digraph \"{} plan\" {{\n  rankdir=LR;\n", plan.strategy);
+    for token in tokens(plan) {
+        dot.push_str(&format!("  \"{:?}\" [label=\"{}\"];\n", token, short(&token)));
+    }
+    for (index, leg) in plan.legs.iter().enumerate() {
+        dot.push_str(&format!(
+            "  \"{:?}\" -> \"{:?}\" [label=\"{}: {}\\nin {}\\nexpected {}\"];\n",
+            leg.token_in,
+            leg.token_out,
+            index + 1,
+            leg.venue.label(),
+            leg.amount_in,
+            leg.expected_out
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+pub fn render(plan: &TradePlan, format: GraphFormat) -> Value {
+    match format {
+        GraphFormat::Dot => Value::String(to_dot(plan)),
+        GraphFormat::Json => to_json(plan),
+    }
+}
+
+// Graph stored with an execution record: multi-leg plans only, in the "plan_graph_format" set in
+// the global config ("json", "dot" or "off")
+pub fn for_record(plan: &TradePlan) -> Option<Value> {
+    if plan.legs.len() < 2 {
+        return None;
+    }
+    let format = GraphFormat::parse(load_global_config()["plan_graph_format"].as_str().unwrap_or("off"))?;
+    Some(render(plan, format))
+}
+
+// `taz plans graph [--format dot|json] [--limit N] [--tx <hash>]` prints the graphs of recent
+// multi-leg executions, or of the one execution submitted as <hash>
+pub fn run_plans_command(args: &[String]) -> Result<(), PlanGraphError> {
+    let value_of = |name: &str| args.iter().position(|arg| arg == name).and_then(|index| args.get(index + 1)).cloned();
+    if args.first().map(String::as_str) != Some("graph") {
+        return Err(PlanGraphError::Usage);
+    }
+    let format = match value_of("--format") {
+        Some(name) => GraphFormat::parse(&name).ok_or(PlanGraphError::Usage)?,
+        None => GraphFormat::Dot,
+    };
+    let limit = value_of("--limit").and_then(|limit| limit.parse::<usize>().ok()).unwrap_or(10);
+    let submission = match value_of("--tx") {
+        Some(hash) => Some(hash.parse::<H256>().map_err(|_| PlanGraphError::Usage)?),
+        None => None,
+    };
+
+    let executions: Vec<_> = domain::recent_executions(usize::MAX)
+        .into_iter()
+        .filter(|execution| execution.plan.legs.len() > 1)
+        .filter(|execution| submission.is_none_or(|hash| execution.submission == Some(hash)))
+        .take(limit)
+        .collect();
+    if executions.is_empty() {
+        return Err(PlanGraphError::NoPlans);
+    }
+    for execution in executions {
+        match render(&execution.plan, format) {
+            Value::String(dot) => println!("// {} {:?} at {}\n{}", execution.strategy, execution.submission, execution.timestamp, dot),
+            graph => println!("{}", graph),
+        }
+    }
+    Ok(())
+}
+
+// Custom error type for trade plan graphs
+#[derive(Error, Debug)]
+pub enum PlanGraphError {
+    #[error("No multi-leg executions recorded")]
+    NoPlans,
+    #[error("usage: taz plans graph [--format dot|json] [--limit <N>] [--tx <hash>]")]
+    Usage,
+}
+
+// Implement conversion for PlanGraphError to Web3 error
+impl From<PlanGraphError> for web3::Error {
+    fn from(error: PlanGraphError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,320 @@
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use web3::ethabi::{self, ParamType, Token};
+use web3::futures::StreamExt;
+use web3::signing::keccak256;
+use web3::transports::WebSocket;
+use web3::types::{Address, FilterBuilder, Log, H256, U256};
+use web3::Transport;
+use log::{debug, error, info, warn};
+use thiserror::Error;
+use tokio::time::{sleep, timeout, Duration};
+
+use crate::modules::multicall::{self, MulticallError};
+use crate::modules::providers;
+use crate::modules::venues::{self, Venue, VenueVersion};
+use crate::modules::ws_metrics;
+
+// Pool events that change the state we keep: V2 reserves after every trade and V3 price, liquidity and tick
+const V2_SYNC_EVENT: &str = "Sync(uint112,uint112)";
+const V3_SWAP_EVENT: &str = "Swap(address,address,int256,int256,uint160,uint128,int24)";
+
+// Load the pool state configuration
+fn load_pool_state_config() -> Value {
+    let config_path = "config/pool_state_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read pool state config file");
+    serde_json::from_str(&config_data).expect("Unable to parse pool state config file")
+}
+
+// Latest known state per pool, and the block it is current as of
+fn pool_states() -> &'static Mutex<HashMap<Address, Value>> {
+    static STATES: OnceLock<Mutex<HashMap<Address, Value>>> = OnceLock::new();
+    STATES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Where the event sync stands. Quotes only come from memory for pools in the live subscription
+#[derive(Default)]
+struct SyncStatus {
+    synced: bool,
+    quote_v3: bool,
+    subscribed: HashSet<Address>,
+    events: u64,
+    last_block: u64,
+}
+
+fn sync_status() -> &'static Mutex<SyncStatus> {
+    static STATUS: OnceLock<Mutex<SyncStatus>> = OnceLock::new();
+    STATUS.get_or_init(|| Mutex::new(SyncStatus::default()))
+}
+
+fn topic(signature: &str) -> H256 {
+    H256::from(keccak256(signature.as_bytes()))
+}
+
+// Sync and Swap topics, for log filters
+pub fn event_topics() -> Vec<H256> {
+    vec![topic(V2_SYNC_EVENT), topic(V3_SWAP_EVENT)]
+}
+
+pub fn get(pool: Address) -> Option<Value> {
+    pool_states().lock().unwrap().get(&pool).cloned()
+}
+
+// Store a pool's state unless what is stored is from a later block
+pub fn insert(pool: Address, state: Value) {
+    let mut states = pool_states().lock().unwrap();
+    let stored_block = states.get(&pool).and_then(|stored| stored["block"].as_u64()).unwrap_or(0);
+    if state["block"].as_u64().unwrap_or(0) >= stored_block {
+        states.insert(pool, state);
+    }
+}
+
+// Apply one Sync or Swap event to the stored state
+pub fn apply_log(log: &Log) {
+    let block = log.block_number.map(|block| block.as_u64()).unwrap_or_default();
+    let mut states = pool_states().lock().unwrap();
+    let state = states.entry(log.address).or_insert_with(|| json!({}));
+    if state["block"].as_u64().unwrap_or(0) > block {
+        return;
+    }
+    if log.topics.first() == Some(&topic(V2_SYNC_EVENT)) {
+        if let Ok(fields) = ethabi::decode(&[ParamType::Uint(112), ParamType::Uint(112)], &log.data.0) {
+            state["reserve0"] = json!(fields[0].clone().into_uint().unwrap_or_default().to_string());
+            state["reserve1"] = json!(fields[1].clone().into_uint().unwrap_or_default().to_string());
+            state["block"] = json!(block);
+        }
+    } else if log.topics.first() == Some(&topic(V3_SWAP_EVENT)) {
+        let params = [ParamType::Int(256), ParamType::Int(256), ParamType::Uint(160), ParamType::Uint(128), ParamType::Int(24)];
+        if let Ok(fields) = ethabi::decode(&params, &log.data.0) {
+            state["sqrt_price_x96"] = json!(fields[2].clone().into_uint().unwrap_or_default().to_string());
+            state["liquidity"] = json!(fields[3].clone().into_uint().unwrap_or_default().to_string());
+            state["tick"] = json!(fields[4].clone().into_int().map(|tick| tick.low_u32() as i32));
+            state["block"] = json!(block);
+        }
+    }
+}
+
+// Quote from memory when the pool is followed by a live subscription: V2 pools always, V3 pools
+// when quote_v3_from_state is set, since their state quote is the spot price and ignores impact
+pub fn quote(venue: &Venue, token_in: Address, token_out: Address, amount_in: U256) -> Option<U256> {
+    {
+        let status = sync_status().lock().unwrap();
+        let modelled = match venue.version {
+            VenueVersion::V2 => true,
+            VenueVersion::V3 { .. } => status.quote_v3,
+            VenueVersion::Curve { .. } => false,
+        };
+        if !status.synced || !modelled || !status.subscribed.contains(&venue.pool) {
+            return None;
+        }
+    }
+    venue.quote_from_state(&get(venue.pool)?, token_in, token_out, amount_in)
+}
+
+// View calls that read a pool's state
+fn state_calls(venue: &Venue) -> Vec<Vec<u8>> {
+    match venue.version {
+        VenueVersion::V2 => vec![multicall::selector("getReserves()").to_vec()],
+        VenueVersion::V3 { .. } => vec![multicall::selector("slot0()").to_vec(), multicall::selector("liquidity()").to_vec()],
+        VenueVersion::Curve { .. } => Vec::new(),
+    }
+}
+
+// State in the same shape as checkpoints and events keep it
+fn decode_state(venue: &Venue, outputs: &[Option<Vec<u8>>], block: u64) -> Option<Value> {
+    let uint = |token: &Token| token.clone().into_uint().unwrap_or_default().to_string();
+    match venue.version {
+        VenueVersion::V2 => {
+            let reserves = ethabi::decode(&[ParamType::Uint(112), ParamType::Uint(112), ParamType::Uint(32)], outputs.first()?.as_ref()?).ok()?;
+            Some(json!({ "block": block, "reserve0": uint(&reserves[0]), "reserve1": uint(&reserves[1]) }))
+        }
+        VenueVersion::V3 { .. } => {
+            let slot0 = outputs.first()?.as_ref()?;
+            let fields = ethabi::decode(&[ParamType::Uint(160), ParamType::Int(24)], &slot0[..64.min(slot0.len())]).ok()?;
+            let liquidity = ethabi::decode(&[ParamType::Uint(128)], outputs.get(1)?.as_ref()?).ok()?;
+            Some(json!({
+                "block": block,
+                "sqrt_price_x96": uint(&fields[0]),
+                "tick": fields[1].clone().into_int().map(|tick| tick.low_u32() as i32),
+                "liquidity": uint(&liquidity[0]),
+            }))
+        }
+        VenueVersion::Curve { .. } => None,
+    }
+}
+
+// Read every venue's state through Multicall3, all at one block, without storing it
+pub async fn read_states<T: Transport>(web3: &web3::Web3<T>, venues: &[Venue]) -> Result<HashMap<Address, Value>, PoolStateError> {
+    let mut calls = Vec::new();
+    let mut spans = Vec::with_capacity(venues.len());
+    for venue in venues {
+        let venue_calls = state_calls(venue);
+        spans.push((calls.len(), venue_calls.len()));
+        calls.extend(venue_calls.into_iter().map(|data| (venue.pool, data)));
+    }
+    let aggregated = multicall::aggregate(web3, &calls).await?;
+
+    let mut states = HashMap::new();
+    for (venue, (start, len)) in venues.iter().zip(spans) {
+        match aggregated.outputs.get(start..start + len).and_then(|outputs| decode_state(venue, outputs, aggregated.block)) {
+            Some(state) => {
+                states.insert(venue.pool, state);
+            }
+            None if len > 0 => debug!("Could not read state of {} through multicall", venue.label()),
+            None => {}
+        }
+    }
+    Ok(states)
+}
+
+// Read and store every venue's state. Returns how many pools were read
+pub async fn bootstrap<T: Transport>(web3: &web3::Web3<T>, venues: &[Venue]) -> Result<usize, PoolStateError> {
+    let states = read_states(web3, venues).await?;
+    let read = states.len();
+    for (pool, state) in states {
+        insert(pool, state);
+    }
+    Ok(read)
+}
+
+// V2 and V3 venues in the pool cache, one per pool
+fn tracked_venues() -> Vec<Venue> {
+    let mut seen = HashSet::new();
+    venues::cached_pairs()
+        .into_iter()
+        .flat_map(|(_, venues)| venues)
+        .filter(|venue| !matches!(venue.version, VenueVersion::Curve { .. }) && seen.insert(venue.pool))
+        .collect()
+}
+
+// Follow Sync and Swap events for every cached pool over a WebSocket subscription. Each connection
+// subscribes first and then reads every pool through multicall, so no event between the read and the
+// subscription is lost; events from later blocks overwrite the read. The subscription is renewed when
+// the pool cache gains pools, and a dropped connection stops in-memory quoting until it is back
+pub async fn run_pool_state_sync() {
+    let config = load_pool_state_config();
+    let configured_url = config["websocket_url"].as_str().expect("WebSocket URL not found").to_string();
+    let check_interval = Duration::from_secs(config["pool_check_interval_secs"].as_u64().unwrap_or(30));
+    let quote_v3 = config["quote_v3_from_state"].as_bool().unwrap_or(false);
+    let stream_name = "pool_state";
+
+    loop {
+        let venues = tracked_venues();
+        if venues.is_empty() {
+            sleep(check_interval).await;
+            continue;
+        }
+        let pools: HashSet<Address> = venues.iter().map(|venue| venue.pool).collect();
+
+        let websocket_url = providers::websocket_url_for(stream_name, &configured_url);
+        let transport = match WebSocket::new(&websocket_url).await {
+            Ok(transport) => transport,
+            Err(e) => {
+                error!("Pool state sync failed to connect to {}: {}", websocket_url, e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        let web3 = web3::Web3::new(transport);
+        let filter = FilterBuilder::default()
+            .address(pools.iter().copied().collect())
+            .topics(Some(event_topics()), None, None, None)
+            .build();
+        let mut logs = match web3.eth_subscribe().subscribe_logs(filter).await {
+            Ok(logs) => logs,
+            Err(e) => {
+                error!("Pool state sync failed to subscribe: {}", e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        match bootstrap(&web3, &venues).await {
+            Ok(read) => info!("Pool state synced for {} of {} pools, following Sync/Swap events", read, pools.len()),
+            Err(e) => {
+                error!("Pool state bootstrap failed: {}", e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        }
+        {
+            let mut status = sync_status().lock().unwrap();
+            status.synced = true;
+            status.quote_v3 = quote_v3;
+            status.subscribed = pools.clone();
+        }
+
+        loop {
+            match timeout(check_interval, logs.next()).await {
+                Ok(Some(Ok(log))) => {
+                    ws_metrics::record_event(stream_name, None);
+                    // A reorged event leaves the stored state unknown until the pool is read again
+                    if log.removed == Some(true) {
+                        if let Some(venue) = venues.iter().find(|venue| venue.pool == log.address) {
+                            pool_states().lock().unwrap().remove(&venue.pool);
+                            if let Err(e) = bootstrap(&web3, std::slice::from_ref(venue)).await {
+                                warn!("Could not re-read {} after a reorg: {}", venue.label(), e);
+                            }
+                        }
+                        continue;
+                    }
+                    apply_log(&log);
+                    let mut status = sync_status().lock().unwrap();
+                    status.events += 1;
+                    status.last_block = status.last_block.max(log.block_number.map(|block| block.as_u64()).unwrap_or_default());
+                }
+                Ok(Some(Err(e))) => {
+                    error!("Pool state subscription error: {}", e);
+                    break;
+                }
+                Ok(None) => break,
+                // Quiet interval: renew the subscription if pools were added to the cache
+                Err(_) => {
+                    if tracked_venues().iter().any(|venue| !pools.contains(&venue.pool)) {
+                        info!("Pool cache gained pools, renewing the pool state subscription");
+                        break;
+                    }
+                }
+            }
+        }
+
+        {
+            let mut status = sync_status().lock().unwrap();
+            status.synced = false;
+            status.subscribed.clear();
+        }
+        ws_metrics::record_reconnect(stream_name);
+        sleep(Duration::from_secs(1)).await;
+    }
+}
+
+// Sync status and pool count, for the dashboard
+pub fn snapshot() -> Value {
+    let status = sync_status().lock().unwrap();
+    json!({
+        "synced": status.synced,
+        "subscribed_pools": status.subscribed.len(),
+        "stored_pools": pool_states().lock().unwrap().len(),
+        "events_applied": status.events,
+        "last_event_block": status.last_block,
+        "quote_v3_from_state": status.quote_v3,
+    })
+}
+
+// Custom error type for the pool state cache
+#[derive(Error, Debug)]
+pub enum PoolStateError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Multicall error: {0}")]
+    MulticallError(#[from] MulticallError),
+}
+
+// Implement conversion for PoolStateError to Web3 error
+impl From<PoolStateError> for web3::Error {
+    fn from(error: PoolStateError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
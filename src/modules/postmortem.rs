@@ -0,0 +1,212 @@
+use serde_json::{json, Value};
+use std::fs;
+use web3::types::{Address, BlockId, BlockNumber, Bytes, CallRequest, H256, U256};
+use web3::Transport;
+use log::{error, info};
+use chrono::Utc;
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::execution::Call;
+use crate::modules::explorer;
+use crate::modules::multicall;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::templates;
+use crate::modules::ws_metrics;
+
+// Load the post-mortem configuration
+fn load_postmortem_config() -> Value {
+    let config_path = "config/postmortem_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read postmortem config file");
+    serde_json::from_str(&config_data).expect("Unable to parse postmortem config file")
+}
+
+async fn calls_to_json(calls: &[Call]) -> Vec<Value> {
+    let mut entries = Vec::with_capacity(calls.len());
+    for call in calls {
+        entries.push(json!({
+            "to": call.to,
+            "method": explorer::describe_call(call.to, &call.data).await,
+            "value": call.value.to_string(),
+            "data": Bytes(call.data.clone()),
+        }));
+    }
+    entries
+}
+
+// Call trace from the node; falls back to the plain eth_call revert reason when debug_* is unavailable
+async fn trace_calls(web3: &web3::Web3<FailoverTransport>, from: Address, calls: &[Call], tracer: &str) -> Vec<Value> {
+    let mut traces = Vec::with_capacity(calls.len());
+    for call in calls {
+        let request = CallRequest {
+            from: Some(from),
+            to: Some(call.to),
+            value: Some(call.value),
+            data: Some(Bytes(call.data.clone())),
+            ..Default::default()
+        };
+        let params = vec![json!(request), json!("latest"), json!({ "tracer": tracer })];
+        let trace = match web3.transport().execute("debug_traceCall", params).await {
+            Ok(trace) => trace,
+            Err(_) => match web3.eth().call(request, None).await {
+                Ok(output) => json!({ "eth_call": "success", "output": output }),
+                Err(e) => json!({ "eth_call": "reverted", "error": e.to_string() }),
+            },
+        };
+        traces.push(trace);
+    }
+    traces
+}
+
+// Receipt summary and call trace for a transaction that already landed
+async fn transaction_context(web3: &web3::Web3<FailoverTransport>, tx_hash: H256, tracer: &str) -> Value {
+    let receipt = match web3.eth().transaction_receipt(tx_hash).await {
+        Ok(Some(receipt)) => json!({
+            "status": receipt.status.map(|status| status.as_u64()),
+            "block_number": receipt.block_number.map(|block| block.as_u64()),
+            "gas_used": receipt.gas_used.map(|gas| gas.to_string()),
+            "effective_gas_price": receipt.effective_gas_price.map(|price| price.to_string()),
+            "logs": receipt.logs.len(),
+        }),
+        Ok(None) => json!("not included"),
+        Err(e) => json!({ "error": e.to_string() }),
+    };
+    let params = vec![json!(tx_hash), json!({ "tracer": tracer })];
+    let trace = web3.transport().execute("debug_traceTransaction", params).await.unwrap_or_else(|e| json!({ "error": e.to_string() }));
+
+    json!({ "hash": tx_hash, "receipt": receipt, "trace": trace })
+}
+
+// Pending pool depth from the node plus our own subscription health
+async fn mempool_context(web3: &web3::Web3<FailoverTransport>) -> Value {
+    let txpool = web3.transport().execute("txpool_status", vec![]).await.unwrap_or_else(|e| json!({ "error": e.to_string() }));
+    let pending_block_txs = web3
+        .eth()
+        .block_transaction_count(BlockId::Number(BlockNumber::Pending))
+        .await
+        .ok()
+        .flatten()
+        .map(|count| count.as_u64());
+
+    json!({
+        "txpool_status": txpool,
+        "pending_block_transactions": pending_block_txs,
+        "subscriptions": ws_metrics::snapshot(),
+    })
+}
+
+async fn eth_call_raw(web3: &web3::Web3<FailoverTransport>, to: Address, data: Vec<u8>) -> Option<Vec<u8>> {
+    let request = CallRequest { to: Some(to), data: Some(Bytes(data)), ..Default::default() };
+    web3.eth().call(request, None).await.ok().map(|output| output.0)
+}
+
+// Reserves for V2 pairs, price and liquidity for V3 pools
+async fn pool_state(web3: &web3::Web3<FailoverTransport>, pool: Address) -> Value {
+    if let Some(output) = eth_call_raw(web3, pool, multicall::selector("getReserves()").to_vec()).await.filter(|output| output.len() >= 64) {
+        return json!({
+            "pool": pool,
+            "kind": "v2",
+            "reserve0": U256::from_big_endian(&output[0..32]).to_string(),
+            "reserve1": U256::from_big_endian(&output[32..64]).to_string(),
+        });
+    }
+    if let Some(slot0) = eth_call_raw(web3, pool, multicall::selector("slot0()").to_vec()).await.filter(|output| output.len() >= 64) {
+        let liquidity = eth_call_raw(web3, pool, multicall::selector("liquidity()").to_vec())
+            .await
+            .filter(|output| output.len() >= 32)
+            .map(|output| U256::from_big_endian(&output[0..32]).to_string());
+        return json!({
+            "pool": pool,
+            "kind": "v3",
+            "sqrt_price_x96": U256::from_big_endian(&slot0[0..32]).to_string(),
+            "tick": U256::from_big_endian(&slot0[32..64]).low_u32() as i32,
+            "liquidity": liquidity,
+        });
+    }
+    json!({ "pool": pool, "error": "no reserves or slot0 readable" })
+}
+
+// Gas price, base fee and recent priority fee percentiles
+async fn gas_market(web3: &web3::Web3<FailoverTransport>, fee_history_blocks: u64) -> Value {
+    let gas_price = web3.eth().gas_price().await.ok().map(|price| price.to_string());
+    let latest = web3.eth().block(BlockId::Number(BlockNumber::Latest)).await.ok().flatten();
+    let fee_history = web3
+        .eth()
+        .fee_history(U256::from(fee_history_blocks), BlockNumber::Latest, Some(vec![10.0, 50.0, 90.0]))
+        .await
+        .ok();
+
+    json!({
+        "gas_price": gas_price,
+        "block_number": latest.as_ref().and_then(|block| block.number).map(|number| number.as_u64()),
+        "base_fee_per_gas": latest.as_ref().and_then(|block| block.base_fee_per_gas).map(|fee| fee.to_string()),
+        "gas_used": latest.as_ref().map(|block| block.gas_used.to_string()),
+        "gas_limit": latest.as_ref().map(|block| block.gas_limit.to_string()),
+        "fee_history": fee_history,
+    })
+}
+
+// Collect the diagnostic bundle for a failed trade, write it to disk and alert with its path
+pub async fn capture(
+    web3: &web3::Web3<FailoverTransport>,
+    strategy: &str,
+    reason: &str,
+    from: Address,
+    calls: &[Call],
+    tx_hash: Option<H256>,
+    pools: &[Address],
+) -> Option<String> {
+    let config = load_postmortem_config();
+    if !config["enabled"].as_bool().unwrap_or(true) {
+        return None;
+    }
+    let output_dir = config["output_dir"].as_str().unwrap_or("data/postmortems");
+    let tracer = config["tracer"].as_str().unwrap_or("callTracer");
+    let fee_history_blocks = config["fee_history_blocks"].as_u64().unwrap_or(5);
+
+    let transaction = match tx_hash {
+        Some(hash) => Some(transaction_context(web3, hash, tracer).await),
+        None => None,
+    };
+    let mut pool_states = Vec::with_capacity(pools.len());
+    for pool in pools {
+        pool_states.push(pool_state(web3, *pool).await);
+    }
+
+    let bundle = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "strategy": strategy,
+        "reason": reason,
+        "from": from,
+        "calls": calls_to_json(calls).await,
+        "simulation": trace_calls(web3, from, calls, tracer).await,
+        "transaction": transaction,
+        "mempool": mempool_context(web3).await,
+        "pools": pool_states,
+        "gas_market": gas_market(web3, fee_history_blocks).await,
+    });
+
+    if let Err(e) = fs::create_dir_all(output_dir) {
+        error!("Failed to create post-mortem directory {}: {}", output_dir, e);
+        return None;
+    }
+    let path = format!("{}/{}-{}.json", output_dir, Utc::now().format("%Y%m%dT%H%M%S%.3f"), strategy);
+    let contents = serde_json::to_string_pretty(&bundle).unwrap_or_else(|_| bundle.to_string());
+    if let Err(e) = fs::write(&path, contents) {
+        error!("Failed to write post-mortem bundle {}: {}", path, e);
+        return None;
+    }
+    info!("Post-mortem bundle for failed {} trade written to {}", strategy, path);
+
+    if config["alert"].as_bool().unwrap_or(true) {
+        let (subject, body) = templates::render("trade_failed", &[
+            ("strategy", strategy.to_string()),
+            ("reason", reason.to_string()),
+            ("tx", tx_hash.map(templates::tx_link).unwrap_or_else(|| String::from("not submitted"))),
+            ("from", templates::address_link(from)),
+            ("bundle", path.clone()),
+        ]);
+        alerts::raise(&format!("trade_failed:{}", strategy), Severity::Warn, &subject, &body);
+    }
+    Some(path)
+}
@@ -0,0 +1,102 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use web3::types::{Address, U256};
+use thiserror::Error;
+
+use crate::modules::ens;
+use crate::modules::gas::{self, GasError, Urgency};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::venues::{self, VenueError};
+
+// Load the pricing configuration
+fn load_pricing_config() -> Value {
+    let config_path = "config/pricing_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read pricing config file");
+    serde_json::from_str(&config_data).expect("Unable to parse pricing config file")
+}
+
+// Token units one ETH buys, per token, with when it was quoted
+fn rate_cache() -> &'static Mutex<HashMap<Address, (Instant, U256)>> {
+    static RATES: OnceLock<Mutex<HashMap<Address, (Instant, U256)>>> = OnceLock::new();
+    RATES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn weth(config: &Value) -> Result<Address, PricingError> {
+    config["weth_address"]
+        .as_str()
+        .and_then(ens::parse_address)
+        .ok_or(PricingError::MissingConfig("weth_address"))
+}
+
+// How many units of the token one ETH is worth, from the best venue quote for 1 WETH. Quotes are
+// reused for rate_ttl_secs so profitability checks don't each cost an extra round of RPC calls
+pub async fn eth_rate(web3: &web3::Web3<FailoverTransport>, token: Address) -> Result<U256, PricingError> {
+    let config = load_pricing_config();
+    if token == weth(&config)? {
+        return Ok(U256::exp10(18));
+    }
+    let ttl = Duration::from_secs(config["rate_ttl_secs"].as_u64().unwrap_or(12));
+    if let Some((quoted_at, rate)) = rate_cache().lock().unwrap().get(&token) {
+        if quoted_at.elapsed() < ttl {
+            return Ok(*rate);
+        }
+    }
+
+    let (_, rate) = venues::best_quote(web3, weth(&config)?, token, U256::exp10(18))
+        .await?
+        .ok_or(PricingError::NoRate(token))?;
+    if rate.is_zero() {
+        return Err(PricingError::NoRate(token));
+    }
+    rate_cache().lock().unwrap().insert(token, (Instant::now(), rate));
+    Ok(rate)
+}
+
+// Convert an amount of wei into the token at the current ETH rate
+pub async fn wei_to_token(web3: &web3::Web3<FailoverTransport>, token: Address, amount_wei: U256) -> Result<U256, PricingError> {
+    let rate = eth_rate(web3, token).await?;
+    Ok(amount_wei.full_mul(rate).checked_div(U256::exp10(18).into()).and_then(|value| U256::try_from(value).ok()).unwrap_or(U256::MAX))
+}
+
+// Convert an amount of the token into wei at the current ETH rate
+pub async fn token_to_wei(web3: &web3::Web3<FailoverTransport>, token: Address, amount: U256) -> Result<U256, PricingError> {
+    let rate = eth_rate(web3, token).await?;
+    Ok(amount.full_mul(U256::exp10(18)).checked_div(rate.into()).and_then(|value| U256::try_from(value).ok()).unwrap_or(U256::MAX))
+}
+
+// Expected cost in wei of a transaction using gas_units at the predicted base fee and the
+// priority fee for its urgency
+pub async fn gas_cost_wei(web3: &web3::Web3<FailoverTransport>, gas_units: U256, urgency: Urgency) -> Result<U256, PricingError> {
+    Ok(gas::suggest_fees(web3, urgency).await?.cost(gas_units))
+}
+
+// Gas cost of gas_units expressed in the token a strategy measures its profit in, so the two can be
+// compared directly by the is_profitable checks
+pub async fn gas_cost_in_token(web3: &web3::Web3<FailoverTransport>, token: Address, gas_units: U256, urgency: Urgency) -> Result<U256, PricingError> {
+    let cost_wei = gas_cost_wei(web3, gas_units, urgency).await?;
+    wei_to_token(web3, token, cost_wei).await
+}
+
+// Custom error type for pricing
+#[derive(Error, Debug)]
+pub enum PricingError {
+    #[error("Gas error: {0}")]
+    GasError(#[from] GasError),
+    #[error("Venue error: {0}")]
+    VenueError(#[from] VenueError),
+    #[error("No ETH rate for token {0:?}")]
+    NoRate(Address),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+}
+
+// Implement conversion for PricingError to Web3 error
+impl From<PricingError> for web3::Error {
+    fn from(error: PricingError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,519 @@
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+use web3::transports::{Http, WebSocket};
+use jsonrpc_core as rpc;
+use web3::{helpers, RequestId, Transport, Web3};
+use log::{info, warn};
+use thiserror::Error;
+use tokio::time::{sleep, timeout, Duration};
+
+// Name of the endpoint built from network/infura_project_id in the global config
+pub const DEFAULT_ENDPOINT: &str = "infura";
+
+// Latency samples kept per endpoint for percentiles
+const LATENCY_SAMPLES: usize = 100;
+
+// Load the RPC provider configuration
+fn load_providers_config() -> Value {
+    let config_path = "config/providers_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read providers config file");
+    serde_json::from_str(&config_data).expect("Unable to parse providers config file")
+}
+
+// Endpoints a strategy or background job may use, in preference order
+#[derive(Clone, Debug)]
+struct Pin {
+    endpoints: Vec<String>,
+    // Strict pins never fall back to the default endpoints
+    strict: bool,
+}
+
+// Last health check result for one endpoint
+#[derive(Clone, Debug, Default)]
+struct EndpointHealth {
+    healthy: bool,
+    checked: bool,
+    latency_ms: u64,
+    block: u64,
+    last_error: Option<String>,
+    // Result of the websocket probe, for endpoints with a ws_url
+    ws_healthy: Option<bool>,
+    // Smoothed round trip, what endpoint selection compares
+    latency_ewma_ms: Option<f64>,
+    recent_latencies: VecDeque<u64>,
+}
+
+fn health_registry() -> &'static Mutex<HashMap<String, EndpointHealth>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, EndpointHealth>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Lowest-latency healthy endpoint for sending transactions, re-chosen after every health check
+type SubmissionClient = Option<(String, Arc<Web3<FailoverTransport>>)>;
+
+fn submission_client() -> &'static Mutex<SubmissionClient> {
+    static CLIENT: OnceLock<Mutex<SubmissionClient>> = OnceLock::new();
+    CLIENT.get_or_init(|| Mutex::new(None))
+}
+
+// The process-wide pool, for modules that fan work out over several endpoints
+static SHARED_POOL: OnceLock<Arc<ProviderPool>> = OnceLock::new();
+
+pub fn install(pool: Arc<ProviderPool>) {
+    let _ = SHARED_POOL.set(pool);
+}
+
+pub fn shared() -> Option<Arc<ProviderPool>> {
+    SHARED_POOL.get().cloned()
+}
+
+// Rotates unpinned consumers across the read endpoints
+static READ_CURSOR: AtomicUsize = AtomicUsize::new(0);
+
+// Client to broadcast signed transactions through, when latency routing is enabled and has measured one
+pub fn fastest_submission_client() -> Option<Arc<Web3<FailoverTransport>>> {
+    submission_client().lock().unwrap().as_ref().map(|(_, client)| client.clone())
+}
+
+// Websocket URL for a subscribing consumer from the shared pool, or the URL from its own config when
+// none of its endpoints has one
+pub fn websocket_url_for(consumer: &str, configured: &str) -> String {
+    shared().and_then(|pool| pool.websocket_url(consumer)).unwrap_or_else(|| configured.to_string())
+}
+
+fn percentile(samples: &VecDeque<u64>, quantile: f64) -> Option<u64> {
+    let mut sorted: Vec<u64> = samples.iter().copied().collect();
+    sorted.sort_unstable();
+    let index = ((sorted.len() as f64 - 1.0) * quantile).round() as usize;
+    sorted.get(index).copied()
+}
+
+// Errors worth retrying on another endpoint: the endpoint is down, rate limiting us or answering
+// with something that isn't JSON-RPC. Errors about the request itself (reverts, bad params) are not
+fn should_fail_over(error: &web3::Error) -> bool {
+    match error {
+        web3::Error::Unreachable | web3::Error::Transport(_) | web3::Error::InvalidResponse(_) => true,
+        // -32005 is the limit-exceeded code hosted providers answer with when throttling
+        web3::Error::Rpc(rpc_error) => rpc_error.code.code() == -32005 || rpc_error.message.to_lowercase().contains("rate limit"),
+        _ => false,
+    }
+}
+
+// Where a FailoverTransport sends its requests
+#[derive(Clone, Debug)]
+enum Route {
+    // One fixed endpoint: the pool's per-endpoint clients and one-off URLs
+    Fixed(Http),
+    // The consumer's endpoints in the pool, failing over down them request by request
+    Pool(Arc<ProviderPool>, String),
+}
+
+// JSON-RPC transport every strategy's Web3 runs on. Pool-routed transports send each request to the
+// endpoint the pool currently picks for the consumer and retry it on the next usable one when that
+// endpoint is unreachable or throttling, marking it unhealthy until the next health check
+#[derive(Clone, Debug)]
+pub struct FailoverTransport {
+    route: Route,
+    ids: Arc<AtomicUsize>,
+}
+
+impl FailoverTransport {
+    // A transport fixed to one HTTP endpoint, without failover
+    pub fn http(url: &str) -> Result<Self, web3::Error> {
+        Ok(FailoverTransport { route: Route::Fixed(Http::new(url)?), ids: Arc::new(AtomicUsize::new(1)) })
+    }
+
+    fn pooled(pool: Arc<ProviderPool>, consumer: &str) -> Self {
+        FailoverTransport { route: Route::Pool(pool, consumer.to_string()), ids: Arc::new(AtomicUsize::new(1)) }
+    }
+}
+
+impl Transport for FailoverTransport {
+    type Out = BoxFuture<'static, web3::Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, rpc::Call) {
+        let id = self.ids.fetch_add(1, Ordering::Relaxed);
+        (id, helpers::build_request(id, method, params))
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        match &self.route {
+            Route::Fixed(http) => Box::pin(http.send(id, request)),
+            Route::Pool(pool, consumer) => {
+                let (pool, consumer) = (pool.clone(), consumer.clone());
+                Box::pin(async move { pool.send_with_failover(&consumer, id, request).await })
+            }
+        }
+    }
+}
+
+// Named RPC endpoints plus the per-consumer pins that decide which one each consumer gets
+#[derive(Debug)]
+pub struct ProviderPool {
+    endpoints: HashMap<String, Arc<Web3<FailoverTransport>>>,
+    // Websocket URLs of the endpoints that have one, for subscriptions
+    ws_urls: HashMap<String, String>,
+    default: Vec<String>,
+    pins: HashMap<String, Pin>,
+}
+
+impl ProviderPool {
+    // Build the pool from config/providers_config.json; the global node URL is always available as "infura"
+    pub fn from_config(default_url: &str) -> Result<Self, ProviderError> {
+        let config = load_providers_config();
+
+        let mut endpoints = HashMap::new();
+        let mut ws_urls = HashMap::new();
+        endpoints.insert(DEFAULT_ENDPOINT.to_string(), Arc::new(Web3::new(FailoverTransport::http(default_url)?)));
+        if let Some(configured) = config["endpoints"].as_object() {
+            for (name, endpoint) in configured {
+                let url = endpoint["url"].as_str().ok_or_else(|| ProviderError::MissingUrl(name.clone()))?;
+                endpoints.insert(name.clone(), Arc::new(Web3::new(FailoverTransport::http(url)?)));
+                if let Some(ws_url) = endpoint["ws_url"].as_str() {
+                    ws_urls.insert(name.clone(), ws_url.to_string());
+                }
+            }
+        }
+
+        let names = |value: &Value| -> Vec<String> {
+            value.as_array().map(|list| list.iter().filter_map(|name| name.as_str().map(String::from)).collect()).unwrap_or_default()
+        };
+        let mut default = names(&config["default"]);
+        if default.is_empty() {
+            default.push(DEFAULT_ENDPOINT.to_string());
+        }
+
+        let mut pins = HashMap::new();
+        if let Some(configured) = config["pins"].as_object() {
+            for (consumer, pin) in configured {
+                pins.insert(consumer.clone(), Pin { endpoints: names(&pin["endpoints"]), strict: pin["strict"].as_bool().unwrap_or(false) });
+            }
+        }
+
+        let unknown = default.iter().chain(pins.values().flat_map(|pin| pin.endpoints.iter())).find(|name| !endpoints.contains_key(*name));
+        if let Some(unknown) = unknown {
+            return Err(ProviderError::UnknownEndpoint(unknown.clone()));
+        }
+
+        Ok(ProviderPool { endpoints, ws_urls, default, pins })
+    }
+
+    // Candidate endpoints for a consumer, pinned ones first
+    fn candidates(&self, consumer: &str) -> Vec<String> {
+        match self.pins.get(consumer) {
+            Some(pin) if pin.strict => pin.endpoints.clone(),
+            Some(pin) => pin.endpoints.iter().chain(self.default.iter()).cloned().collect(),
+            None => self.default.clone(),
+        }
+    }
+
+    // Name of the endpoint a consumer should use right now: the first healthy (or not yet checked)
+    // candidate. With latency routing, unpinned consumers are spread over the healthy default
+    // endpoints other than the submission endpoint, so reads don't queue behind sends
+    pub fn endpoint_for(&self, consumer: &str) -> Result<String, ProviderError> {
+        let health = health_registry().lock().unwrap();
+        let usable = |name: &String| health.get(name).map(|status| !status.checked || status.healthy).unwrap_or(true);
+
+        if !self.pins.contains_key(consumer) && latency_routing_enabled() {
+            let submission = submission_client().lock().unwrap().as_ref().map(|(name, _)| name.clone());
+            let readers: Vec<&String> = self.default.iter().filter(|name| usable(name) && Some(*name) != submission.as_ref()).collect();
+            if !readers.is_empty() {
+                return Ok(readers[READ_CURSOR.fetch_add(1, Ordering::Relaxed) % readers.len()].clone());
+            }
+        }
+
+        self.candidates(consumer)
+            .into_iter()
+            .find(|name| usable(name))
+            .ok_or_else(|| ProviderError::NoHealthyEndpoint(consumer.to_string()))
+    }
+
+    // Endpoints to try for one request, in order: the one endpoint_for picks, then the consumer's
+    // other usable candidates
+    fn route(&self, consumer: &str) -> Result<Vec<String>, ProviderError> {
+        let first = self.endpoint_for(consumer)?;
+        let health = health_registry().lock().unwrap();
+        let usable = |name: &String| health.get(name).map(|status| !status.checked || status.healthy).unwrap_or(true);
+        let mut route = vec![first.clone()];
+        route.extend(self.candidates(consumer).into_iter().filter(|name| *name != first && usable(name)));
+        Ok(route)
+    }
+
+    // Take an endpoint out of rotation after a failed request; the next health check decides when it returns
+    fn mark_failed(&self, name: &str, error: &web3::Error) {
+        let mut health = health_registry().lock().unwrap();
+        let status = health.entry(name.to_string()).or_default();
+        if status.healthy || !status.checked {
+            warn!("RPC endpoint {} failed a request, failing over: {}", name, error);
+        }
+        status.checked = true;
+        status.healthy = false;
+        status.last_error = Some(error.to_string());
+    }
+
+    async fn send_with_failover(&self, consumer: &str, id: RequestId, request: rpc::Call) -> web3::Result<Value> {
+        let mut last_error = None;
+        for name in self.route(consumer)? {
+            match self.endpoints[&name].transport().send(id, request.clone()).await {
+                Err(e) if should_fail_over(&e) => {
+                    self.mark_failed(&name, &e);
+                    last_error = Some(e);
+                }
+                result => return result,
+            }
+        }
+        Err(last_error.unwrap_or_else(|| ProviderError::NoHealthyEndpoint(consumer.to_string()).into()))
+    }
+
+    // Websocket URL a subscribing consumer should connect to: the first of its endpoints with a ws_url
+    // whose last websocket probe didn't fail. Reconnect loops ask again on every attempt
+    pub fn websocket_url(&self, consumer: &str) -> Option<String> {
+        let health = health_registry().lock().unwrap();
+        let with_ws: Vec<String> = self.candidates(consumer).into_iter().filter(|name| self.ws_urls.contains_key(name)).collect();
+        with_ws
+            .iter()
+            .find(|name| health.get(*name).and_then(|status| status.ws_healthy).unwrap_or(true))
+            .or(with_ws.first())
+            .map(|name| self.ws_urls[name].clone())
+    }
+
+    // Healthy (or not yet checked) clients among the named endpoints, every endpoint when none are named
+    pub fn healthy_clients(&self, names: &[String]) -> Vec<(String, Arc<Web3<FailoverTransport>>)> {
+        let health = health_registry().lock().unwrap();
+        let mut clients: Vec<(String, Arc<Web3<FailoverTransport>>)> = self
+            .endpoints
+            .iter()
+            .filter(|(name, _)| names.is_empty() || names.contains(name))
+            .filter(|(name, _)| health.get(*name).map(|status| !status.checked || status.healthy).unwrap_or(true))
+            .map(|(name, client)| (name.clone(), client.clone()))
+            .collect();
+        clients.sort_by(|a, b| a.0.cmp(&b.0));
+        clients
+    }
+
+    // Re-pick the submission endpoint: the healthy candidate with the lowest smoothed latency
+    fn select_submission(&self) {
+        let config = load_providers_config();
+        let candidates: Vec<String> = match config["latency_routing"]["submission_endpoints"].as_array() {
+            Some(names) => names.iter().filter_map(|name| name.as_str().map(String::from)).collect(),
+            None => self.endpoints.keys().cloned().collect(),
+        };
+        let fastest = {
+            let health = health_registry().lock().unwrap();
+            candidates
+                .into_iter()
+                .filter(|name| self.endpoints.contains_key(name))
+                .filter_map(|name| {
+                    let latency = health.get(&name).filter(|status| status.healthy)?.latency_ewma_ms?;
+                    Some((name, latency))
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+        };
+
+        let mut current = submission_client().lock().unwrap();
+        let previous = current.as_ref().map(|(name, _)| name.clone());
+        if previous.as_ref() != fastest.as_ref().map(|(name, _)| name) {
+            match &fastest {
+                Some((name, latency)) => info!("Submission endpoint is now {} ({:.0}ms)", name, latency),
+                None => warn!("No healthy submission endpoint, sending through the wallet's node"),
+            }
+        }
+        *current = fastest.map(|(name, _)| (name.clone(), self.endpoints[&name].clone()));
+    }
+
+    // Client for a consumer, enforcing its pin. Its requests follow the pool as endpoints go down and
+    // come back, so the client stays usable for the consumer's lifetime
+    pub fn web3_for(self: &Arc<Self>, consumer: &str) -> Result<Arc<Web3<FailoverTransport>>, ProviderError> {
+        let name = self.endpoint_for(consumer)?;
+        info!("RPC provider for {}: {} (fails over within {:?})", consumer, name, self.candidates(consumer));
+        Ok(Arc::new(Web3::new(FailoverTransport::pooled(self.clone(), consumer))))
+    }
+
+    // Probe every endpoint with eth_blockNumber and record latency and head
+    pub async fn check_health(&self, probe_timeout: Duration, max_block_lag: u64) {
+        let mut results = HashMap::new();
+        for (name, web3) in &self.endpoints {
+            let started = Instant::now();
+            let status = match timeout(probe_timeout, web3.eth().block_number()).await {
+                Ok(Ok(block)) => EndpointHealth {
+                    healthy: true,
+                    checked: true,
+                    latency_ms: started.elapsed().as_millis() as u64,
+                    block: block.as_u64(),
+                    last_error: None,
+                    ..Default::default()
+                },
+                Ok(Err(e)) => EndpointHealth { checked: true, last_error: Some(e.to_string()), ..Default::default() },
+                Err(_) => EndpointHealth { checked: true, last_error: Some(String::from("timed out")), ..Default::default() },
+            };
+            results.insert(name.clone(), status);
+        }
+
+        // Websockets get a fresh connection each round, so a dead socket can't pass for a healthy one
+        for (name, ws_url) in &self.ws_urls {
+            let probe = async {
+                let web3 = Web3::new(WebSocket::new(ws_url).await?);
+                web3.eth().block_number().await
+            };
+            let ws_healthy = match timeout(probe_timeout, probe).await {
+                Ok(Ok(_)) => true,
+                Ok(Err(e)) => {
+                    warn!("Websocket for RPC endpoint {} failed its probe: {}", name, e);
+                    false
+                }
+                Err(_) => {
+                    warn!("Websocket for RPC endpoint {} timed out", name);
+                    false
+                }
+            };
+            if let Some(status) = results.get_mut(name) {
+                status.ws_healthy = Some(ws_healthy);
+            }
+        }
+
+        // Fold the new round trips into the running latency figures
+        let alpha = load_providers_config()["latency_routing"]["ewma_alpha"].as_f64().unwrap_or(0.3);
+        {
+            let previous = health_registry().lock().unwrap();
+            for (name, status) in results.iter_mut() {
+                let before = previous.get(name).cloned().unwrap_or_default();
+                status.recent_latencies = before.recent_latencies;
+                status.latency_ewma_ms = before.latency_ewma_ms;
+                if status.healthy {
+                    status.recent_latencies.push_back(status.latency_ms);
+                    if status.recent_latencies.len() > LATENCY_SAMPLES {
+                        status.recent_latencies.pop_front();
+                    }
+                    let sample = status.latency_ms as f64;
+                    status.latency_ewma_ms = Some(before.latency_ewma_ms.map_or(sample, |ewma| alpha * sample + (1.0 - alpha) * ewma));
+                }
+            }
+        }
+
+        // An endpoint that answers but trails the best head is as bad as one that doesn't answer
+        let best_block = results.values().map(|status| status.block).max().unwrap_or(0);
+        for (name, status) in results.iter_mut() {
+            if status.healthy && best_block.saturating_sub(status.block) > max_block_lag {
+                status.healthy = false;
+                status.last_error = Some(format!("{} blocks behind", best_block - status.block));
+            }
+            let was_healthy = health_registry().lock().unwrap().get(name).map(|previous| previous.healthy).unwrap_or(true);
+            if was_healthy && !status.healthy {
+                warn!("RPC endpoint {} unhealthy: {}", name, status.last_error.as_deref().unwrap_or("unknown"));
+            } else if !was_healthy && status.healthy {
+                info!("RPC endpoint {} healthy again ({}ms)", name, status.latency_ms);
+            }
+        }
+        health_registry().lock().unwrap().extend(results);
+
+        if latency_routing_enabled() {
+            self.select_submission();
+        }
+    }
+}
+
+fn latency_routing_enabled() -> bool {
+    load_providers_config()["latency_routing"]["enabled"].as_bool().unwrap_or(false)
+}
+
+// Round-trip latency per endpoint (last, smoothed, p50/p95/max over recent checks) and the current
+// submission endpoint
+pub fn latency_metrics() -> Value {
+    let health = health_registry().lock().unwrap();
+    let endpoints: HashMap<&String, Value> = health
+        .iter()
+        .map(|(name, status)| {
+            (name, json!({
+                "healthy": status.healthy,
+                "last_ms": status.latency_ms,
+                "ewma_ms": status.latency_ewma_ms,
+                "p50_ms": percentile(&status.recent_latencies, 0.5),
+                "p95_ms": percentile(&status.recent_latencies, 0.95),
+                "max_ms": status.recent_latencies.iter().max(),
+                "samples": status.recent_latencies.len(),
+            }))
+        })
+        .collect();
+    let submission = submission_client().lock().unwrap().as_ref().map(|(name, _)| name.clone());
+    json!({ "submission_endpoint": submission, "endpoints": endpoints })
+}
+
+// Endpoint health and per-consumer assignment for the dashboard
+pub fn snapshot(pool: &ProviderPool) -> Value {
+    let endpoints: HashMap<String, Value> = {
+        let health = health_registry().lock().unwrap();
+        pool.endpoints
+            .keys()
+            .map(|name| {
+                let status = health.get(name).cloned().unwrap_or_default();
+                (name.clone(), json!({
+                    "checked": status.checked,
+                    "healthy": status.healthy,
+                    "latency_ms": status.latency_ms,
+                    "latency_ewma_ms": status.latency_ewma_ms,
+                    "block": status.block,
+                    "last_error": status.last_error,
+                    "websocket": pool.ws_urls.contains_key(name),
+                    "ws_healthy": status.ws_healthy,
+                }))
+            })
+            .collect()
+    };
+    let assignments: HashMap<&String, Value> = pool
+        .pins
+        .iter()
+        .map(|(consumer, pin)| {
+            (consumer, json!({
+                "endpoints": pin.endpoints,
+                "strict": pin.strict,
+                "current": pool.endpoint_for(consumer).ok(),
+            }))
+        })
+        .collect();
+
+    json!({
+        "default": pool.default,
+        "endpoints": endpoints,
+        "pins": assignments,
+        "submission": submission_client().lock().unwrap().as_ref().map(|(name, _)| name.clone()),
+    })
+}
+
+// Periodic endpoint health checks
+pub async fn run_provider_health_checks(pool: Arc<ProviderPool>) {
+    let config = load_providers_config();
+    let interval = config["health_check_interval_secs"].as_u64().unwrap_or(15);
+    let probe_timeout = Duration::from_millis(config["probe_timeout_ms"].as_u64().unwrap_or(2000));
+    let max_block_lag = config["max_block_lag"].as_u64().unwrap_or(2);
+
+    loop {
+        pool.check_health(probe_timeout, max_block_lag).await;
+        sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+// Custom error type for the provider pool
+#[derive(Error, Debug)]
+pub enum ProviderError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Endpoint {0} has no url")]
+    MissingUrl(String),
+    #[error("Unknown RPC endpoint in pins or default: {0}")]
+    UnknownEndpoint(String),
+    #[error("No healthy RPC endpoint for {0}")]
+    NoHealthyEndpoint(String),
+}
+
+// Implement conversion for ProviderError to Web3 error
+impl From<ProviderError> for web3::Error {
+    fn from(error: ProviderError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
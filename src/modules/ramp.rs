@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use web3::types::U256;
+use log::{error, info, warn};
+use chrono::Utc;
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::events;
+use crate::modules::templates;
+
+// Stage and streak per ramped strategy, so graduation survives restarts
+const RAMP_STATE_PATH: &str = "data/ramp_state.json";
+
+// Load the ramp-up policy
+fn load_ramp_config() -> Value {
+    let config_path = "config/ramp_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read ramp config file");
+    serde_json::from_str(&config_data).expect("Unable to parse ramp config file")
+}
+
+// Where a strategy is on its ramp: the stage whose size limit applies and the run of consecutive
+// profitable, non-reverting trades at that stage
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RampState {
+    pub stage: usize,
+    pub streak: u32,
+    pub updated_at: Option<String>,
+}
+
+fn states() -> &'static Mutex<HashMap<String, RampState>> {
+    static STATES: OnceLock<Mutex<HashMap<String, RampState>>> = OnceLock::new();
+    STATES.get_or_init(|| {
+        let saved = fs::read_to_string(RAMP_STATE_PATH).ok().and_then(|data| serde_json::from_str(&data).ok());
+        Mutex::new(saved.unwrap_or_default())
+    })
+}
+
+fn save(states: &HashMap<String, RampState>) {
+    let _ = fs::create_dir_all("data");
+    if let Err(e) = fs::write(RAMP_STATE_PATH, json!(states).to_string()) {
+        error!("Failed to persist ramp state: {}", e);
+    }
+}
+
+// Size limit per stage in wei, smallest first
+fn stages(policy: &Value) -> Vec<U256> {
+    policy["stages_wei"]
+        .as_array()
+        .map(|stages| stages.iter().filter_map(|stage| stage.as_str().and_then(|amount| U256::from_dec_str(amount).ok())).collect())
+        .unwrap_or_default()
+}
+
+// The ramp policy of a strategy, if it is being ramped
+fn policy(config: &Value, strategy: &str) -> Option<Value> {
+    let policy = &config["strategies"][strategy];
+    (policy.is_object() && policy["enabled"].as_bool().unwrap_or(true)).then(|| policy.clone())
+}
+
+// Current size limit for a strategy; None once it has graduated past the last stage, or when it
+// isn't ramped at all
+pub fn max_size(strategy: &str) -> Option<U256> {
+    let policy = policy(&load_ramp_config(), strategy)?;
+    let stage = states().lock().unwrap().get(strategy).map(|state| state.stage).unwrap_or(0);
+    stages(&policy).get(stage).copied()
+}
+
+// A requested size capped at the strategy's current ramp limit
+pub fn cap_size(strategy: &str, size: U256) -> U256 {
+    match max_size(strategy) {
+        Some(limit) => size.min(limit),
+        None => size,
+    }
+}
+
+fn announce(strategy: &str, from: usize, to: usize, stages: &[U256], reason: &str) {
+    let limit = stages.get(to).map(|limit| limit.to_string()).unwrap_or_else(|| String::from("none (graduated)"));
+    let (subject, body) = templates::render("ramp_stage", &[
+        ("strategy", strategy.to_string()),
+        ("from", from.to_string()),
+        ("to", to.to_string()),
+        ("limit", limit.clone()),
+        ("reason", reason.to_string()),
+    ]);
+    alerts::raise(&format!("ramp:{}", strategy), Severity::Info, &subject, &body);
+    events::emit("ramp_stage", json!({ "strategy": strategy, "from": from, "to": to, "limit_wei": limit, "reason": reason }));
+}
+
+// Count a realized trade result. promote_after consecutive profitable trades move the strategy to
+// the next stage; a losing trade restarts the count
+pub fn record_result(strategy: &str, pnl_wei: i128) {
+    let config = load_ramp_config();
+    let policy = match policy(&config, strategy) {
+        Some(policy) => policy,
+        None => return,
+    };
+    let stages = stages(&policy);
+    let promote_after = policy["promote_after"].as_u64().or(config["default_promote_after"].as_u64()).unwrap_or(10) as u32;
+
+    let mut states = states().lock().unwrap();
+    let state = states.entry(strategy.to_string()).or_default();
+    if state.stage >= stages.len() {
+        return;
+    }
+    if pnl_wei <= 0 {
+        state.streak = 0;
+    } else {
+        state.streak += 1;
+    }
+    let promoted = state.streak >= promote_after;
+    let from = state.stage;
+    if promoted {
+        state.stage += 1;
+        state.streak = 0;
+    }
+    state.updated_at = Some(Utc::now().to_rfc3339());
+    let to = state.stage;
+    save(&states);
+    drop(states);
+
+    if promoted {
+        info!("Ramp: {} promoted to stage {} after {} profitable trades", strategy, to, promote_after);
+        announce(strategy, from, to, &stages, &format!("{} consecutive profitable trades", promote_after));
+    }
+}
+
+// A reverted trade restarts the count and, with demote_on_revert, drops the strategy back a stage
+pub fn record_revert(strategy: &str) {
+    let config = load_ramp_config();
+    let policy = match policy(&config, strategy) {
+        Some(policy) => policy,
+        None => return,
+    };
+    let stages = stages(&policy);
+
+    let mut states = states().lock().unwrap();
+    let state = states.entry(strategy.to_string()).or_default();
+    if state.stage >= stages.len() {
+        return;
+    }
+    let from = state.stage;
+    state.streak = 0;
+    if policy["demote_on_revert"].as_bool().unwrap_or(false) {
+        state.stage = state.stage.saturating_sub(1);
+    }
+    state.updated_at = Some(Utc::now().to_rfc3339());
+    let to = state.stage;
+    save(&states);
+    drop(states);
+
+    if to != from {
+        warn!("Ramp: {} demoted to stage {} after a revert", strategy, to);
+        announce(strategy, from, to, &stages, "reverted trade");
+    }
+}
+
+// Every ramped strategy with its stage, streak and current limit, for the dashboard
+pub fn snapshot() -> Value {
+    let config = load_ramp_config();
+    let states = states().lock().unwrap();
+    let strategies: HashMap<String, Value> = config["strategies"]
+        .as_object()
+        .map(|strategies| {
+            strategies
+                .iter()
+                .filter_map(|(strategy, _)| {
+                    let policy = policy(&config, strategy)?;
+                    let stages = stages(&policy);
+                    let state = states.get(strategy).cloned().unwrap_or_default();
+                    Some((strategy.clone(), json!({
+                        "stage": state.stage,
+                        "stages": stages.len(),
+                        "streak": state.streak,
+                        "graduated": state.stage >= stages.len(),
+                        "max_size_wei": stages.get(state.stage).map(|limit| limit.to_string()),
+                        "updated_at": state.updated_at,
+                    })))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    json!(strategies)
+}
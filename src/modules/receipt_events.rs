@@ -0,0 +1,240 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::time::Instant;
+use web3::ethabi::{self, ParamType, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, Log, TransactionReceipt, H256, U256};
+use log::{debug, warn};
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::providers::FailoverTransport;
+
+// Load the global configuration (receipt wait limits live there)
+fn load_global_config() -> Value {
+    let config_path = "config/global_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read global config file");
+    serde_json::from_str(&config_data).expect("Unable to parse global config file")
+}
+
+// Event signatures decoded from our receipts
+const TRANSFER: &str = "Transfer(address,address,uint256)";
+const SWAP_V2: &str = "Swap(address,uint256,uint256,uint256,uint256,address)";
+const SWAP_V3: &str = "Swap(address,address,int256,int256,uint160,uint128,int24)";
+const CURVE_EXCHANGE: &str = "TokenExchange(address,int128,uint256,int128,uint256)";
+const AAVE_LIQUIDATION: &str = "LiquidationCall(address,address,address,uint256,uint256,address,bool)";
+const COMPOUND_LIQUIDATION: &str = "LiquidateBorrow(address,address,uint256,address,uint256)";
+const AAVE_V3_FLASH_LOAN: &str = "FlashLoan(address,address,address,uint256,uint8,uint256,uint16)";
+const AAVE_V2_FLASH_LOAN: &str = "FlashLoan(address,address,address,uint256,uint256,uint16)";
+
+// One event from a receipt we care about. Swap indices are token0/token1 (0 and 1) on Uniswap
+// pools and coin indices on Curve
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum ReceiptEvent {
+    Transfer { token: Address, from: Address, to: Address, amount: U256 },
+    Swap { pool: Address, sender: Address, recipient: Address, in_index: u8, out_index: u8, amount_in: U256, amount_out: U256 },
+    LiquidationCall { market: Address, collateral_asset: Address, debt_asset: Address, user: Address, liquidator: Address, debt_covered: U256, collateral_seized: U256 },
+    FlashLoan { pool: Address, receiver: Address, initiator: Address, asset: Address, amount: U256, premium: U256 },
+}
+
+// A receipt reduced to what the ledgers need: outcome, gas paid and the decoded events
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DecodedReceipt {
+    pub tx_hash: H256,
+    pub block: Option<u64>,
+    pub success: bool,
+    pub gas_used: U256,
+    pub effective_gas_price: U256,
+    pub fee_wei: U256,
+    pub events: Vec<ReceiptEvent>,
+}
+
+impl DecodedReceipt {
+    pub fn decode(receipt: &TransactionReceipt) -> Self {
+        let gas_used = receipt.gas_used.unwrap_or_default();
+        let effective_gas_price = receipt.effective_gas_price.unwrap_or_default();
+        DecodedReceipt {
+            tx_hash: receipt.transaction_hash,
+            block: receipt.block_number.map(|block| block.as_u64()),
+            success: receipt.status.is_some_and(|status| status.as_u64() == 1),
+            gas_used,
+            effective_gas_price,
+            fee_wei: gas_used.saturating_mul(effective_gas_price),
+            events: receipt.logs.iter().filter_map(decode_log).collect(),
+        }
+    }
+
+    // Total of token moved from one address to another
+    pub fn received(&self, token: Address, from: Address, to: Address) -> U256 {
+        self.events.iter().fold(U256::zero(), |total, event| match event {
+            ReceiptEvent::Transfer { token: moved, from: sender, to: recipient, amount } if (*moved, *sender, *recipient) == (token, from, to) => total.saturating_add(*amount),
+            _ => total,
+        })
+    }
+
+    // Totals of token moved into and out of holder, from and to any address
+    pub fn flows(&self, token: Address, holder: Address) -> (U256, U256) {
+        self.events.iter().fold((U256::zero(), U256::zero()), |(received, sent), event| match event {
+            ReceiptEvent::Transfer { token: moved, to, amount, .. } if *moved == token && *to == holder => (received.saturating_add(*amount), sent),
+            ReceiptEvent::Transfer { token: moved, from, amount, .. } if *moved == token && *from == holder => (received, sent.saturating_add(*amount)),
+            _ => (received, sent),
+        })
+    }
+}
+
+fn topic(signature: &str) -> H256 {
+    H256::from(keccak256(signature.as_bytes()))
+}
+
+fn topic_address(log: &Log, index: usize) -> Option<Address> {
+    log.topics.get(index).map(|topic| Address::from(*topic))
+}
+
+// Two's complement int256 as a sign and magnitude
+fn signed(value: U256) -> (bool, U256) {
+    if value.bit(255) {
+        (true, (!value).overflowing_add(U256::one()).0)
+    } else {
+        (false, value)
+    }
+}
+
+// Decode one log, None for events we don't track or can't parse (ERC721 transfers index the token id
+// and are skipped by their topic count)
+pub fn decode_log(log: &Log) -> Option<ReceiptEvent> {
+    let event_topic = *log.topics.first()?;
+    let data = &log.data.0;
+    let uint = |token: &Token| token.clone().into_uint().unwrap_or_default();
+    let address = |token: &Token| token.clone().into_address().unwrap_or_default();
+
+    let event = if event_topic == topic(TRANSFER) && log.topics.len() == 3 {
+        ReceiptEvent::Transfer { token: log.address, from: topic_address(log, 1)?, to: topic_address(log, 2)?, amount: U256::from_big_endian(data.get(..32)?) }
+    } else if event_topic == topic(SWAP_V2) {
+        let fields = ethabi::decode(&[ParamType::Uint(256), ParamType::Uint(256), ParamType::Uint(256), ParamType::Uint(256)], data).ok()?;
+        let zero_for_one = !uint(&fields[0]).is_zero();
+        let (amount_in, amount_out) = if zero_for_one { (uint(&fields[0]), uint(&fields[3])) } else { (uint(&fields[1]), uint(&fields[2])) };
+        let (in_index, out_index) = if zero_for_one { (0, 1) } else { (1, 0) };
+        ReceiptEvent::Swap { pool: log.address, sender: topic_address(log, 1)?, recipient: topic_address(log, 2)?, in_index, out_index, amount_in, amount_out }
+    } else if event_topic == topic(SWAP_V3) {
+        let fields = ethabi::decode(&[ParamType::Int(256), ParamType::Int(256)], data.get(..64)?).ok()?;
+        let (amount0, amount1) = (signed(fields[0].clone().into_int()?), signed(fields[1].clone().into_int()?));
+        // Positive amounts flow into the pool
+        let (in_index, out_index, amount_in, amount_out) = if amount0.0 { (1, 0, amount1.1, amount0.1) } else { (0, 1, amount0.1, amount1.1) };
+        ReceiptEvent::Swap { pool: log.address, sender: topic_address(log, 1)?, recipient: topic_address(log, 2)?, in_index, out_index, amount_in, amount_out }
+    } else if event_topic == topic(CURVE_EXCHANGE) {
+        let fields = ethabi::decode(&[ParamType::Int(128), ParamType::Uint(256), ParamType::Int(128), ParamType::Uint(256)], data).ok()?;
+        let index = |token: &Token| token.clone().into_int().map_or(0, |index| index.low_u32() as u8);
+        let buyer = topic_address(log, 1)?;
+        ReceiptEvent::Swap {
+            pool: log.address,
+            sender: buyer,
+            recipient: buyer,
+            in_index: index(&fields[0]),
+            out_index: index(&fields[2]),
+            amount_in: uint(&fields[1]),
+            amount_out: uint(&fields[3]),
+        }
+    } else if event_topic == topic(AAVE_LIQUIDATION) {
+        let fields = ethabi::decode(&[ParamType::Uint(256), ParamType::Uint(256), ParamType::Address, ParamType::Bool], data).ok()?;
+        ReceiptEvent::LiquidationCall {
+            market: log.address,
+            collateral_asset: topic_address(log, 1)?,
+            debt_asset: topic_address(log, 2)?,
+            user: topic_address(log, 3)?,
+            liquidator: address(&fields[2]),
+            debt_covered: uint(&fields[0]),
+            collateral_seized: uint(&fields[1]),
+        }
+    } else if event_topic == topic(COMPOUND_LIQUIDATION) {
+        // Emitted by the borrowed cToken; the seized amount is in collateral cTokens
+        let fields = ethabi::decode(&[ParamType::Address, ParamType::Address, ParamType::Uint(256), ParamType::Address, ParamType::Uint(256)], data).ok()?;
+        ReceiptEvent::LiquidationCall {
+            market: log.address,
+            collateral_asset: address(&fields[3]),
+            debt_asset: log.address,
+            user: address(&fields[1]),
+            liquidator: address(&fields[0]),
+            debt_covered: uint(&fields[2]),
+            collateral_seized: uint(&fields[4]),
+        }
+    } else if event_topic == topic(AAVE_V3_FLASH_LOAN) {
+        let fields = ethabi::decode(&[ParamType::Address, ParamType::Uint(256), ParamType::Uint(8), ParamType::Uint(256)], data).ok()?;
+        ReceiptEvent::FlashLoan {
+            pool: log.address,
+            receiver: topic_address(log, 1)?,
+            initiator: address(&fields[0]),
+            asset: topic_address(log, 2)?,
+            amount: uint(&fields[1]),
+            premium: uint(&fields[3]),
+        }
+    } else if event_topic == topic(AAVE_V2_FLASH_LOAN) {
+        let fields = ethabi::decode(&[ParamType::Uint(256), ParamType::Uint(256), ParamType::Uint(16)], data).ok()?;
+        ReceiptEvent::FlashLoan {
+            pool: log.address,
+            receiver: topic_address(log, 1)?,
+            initiator: topic_address(log, 2)?,
+            asset: topic_address(log, 3)?,
+            amount: uint(&fields[0]),
+            premium: uint(&fields[1]),
+        }
+    } else {
+        return None;
+    };
+    Some(event)
+}
+
+// Fetch and decode a transaction's receipt; None while it is still pending
+pub async fn fetch(web3: &web3::Web3<FailoverTransport>, tx_hash: H256) -> Result<Option<DecodedReceipt>, ReceiptEventsError> {
+    let receipt = match web3.eth().transaction_receipt(tx_hash).await? {
+        Some(receipt) => DecodedReceipt::decode(&receipt),
+        None => return Ok(None),
+    };
+    debug!("Decoded {} events from {:?}", receipt.events.len(), tx_hash);
+    Ok(Some(receipt))
+}
+
+// Poll for a just-submitted transaction's receipt until it is mined; None if it isn't mined within
+// receipt_timeout_secs
+pub async fn wait_for_receipt(web3: &web3::Web3<FailoverTransport>, tx_hash: H256) -> Result<Option<TransactionReceipt>, ReceiptEventsError> {
+    let config = load_global_config();
+    let timeout = Duration::from_secs(config["receipt_timeout_secs"].as_u64().unwrap_or(120));
+    let poll_interval = Duration::from_millis(config["receipt_poll_ms"].as_u64().unwrap_or(1000));
+    let started = Instant::now();
+    loop {
+        if let Some(receipt) = web3.eth().transaction_receipt(tx_hash).await? {
+            return Ok(Some(receipt));
+        }
+        if started.elapsed() >= timeout {
+            warn!("{:?} not mined after {}s", tx_hash, timeout.as_secs());
+            return Ok(None);
+        }
+        sleep(poll_interval).await;
+    }
+}
+
+// Wait for a just-submitted transaction to be mined and decode its receipt
+pub async fn wait(web3: &web3::Web3<FailoverTransport>, tx_hash: H256) -> Result<Option<DecodedReceipt>, ReceiptEventsError> {
+    let receipt = match wait_for_receipt(web3, tx_hash).await? {
+        Some(receipt) => DecodedReceipt::decode(&receipt),
+        None => return Ok(None),
+    };
+    debug!("Decoded {} events from {:?}", receipt.events.len(), tx_hash);
+    Ok(Some(receipt))
+}
+
+// Custom error type for receipt decoding
+#[derive(Error, Debug)]
+pub enum ReceiptEventsError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+}
+
+// Implement conversion for ReceiptEventsError to Web3 error
+impl From<ReceiptEventsError> for web3::Error {
+    fn from(error: ReceiptEventsError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
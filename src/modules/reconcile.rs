@@ -0,0 +1,330 @@
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Arc;
+use web3::types::{Address, H256};
+use log::{error, info};
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, TimeZone, Timelike, Utc};
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::chains::{self, ChainError};
+use crate::modules::ens;
+use crate::modules::events;
+use crate::modules::explorer::{ExplorerClient, ExplorerError};
+use crate::modules::providers::{FailoverTransport, ProviderError, ProviderPool};
+use crate::modules::templates;
+
+// One summary line per reconciled day; full reports go to RECONCILIATION_DIR/<date>.json
+const RECONCILIATION_LOG_PATH: &str = "data/reconciliation.jsonl";
+const RECONCILIATION_DIR: &str = "data/reconciliation";
+
+// Load the reconciliation configuration
+fn load_reconcile_config() -> Value {
+    let config_path = "config/reconcile_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read reconcile config file");
+    serde_json::from_str(&config_data).expect("Unable to parse reconcile config file")
+}
+
+fn load_global_config() -> Value {
+    let config_path = "config/global_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read global config file");
+    serde_json::from_str(&config_data).expect("Unable to parse global config file")
+}
+
+fn ledger_lines(path: &str) -> Vec<Value> {
+    fs::read_to_string(path).unwrap_or_default().lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+}
+
+fn in_day(record: &Value, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+    record["timestamp"]
+        .as_str()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|ts| ts.with_timezone(&Utc) >= start && ts.with_timezone(&Utc) < end)
+        .unwrap_or(false)
+}
+
+// Every transaction hash anywhere in a ledger record
+fn collect_hashes(value: &Value, hashes: &mut HashSet<H256>) {
+    match value {
+        Value::String(text) if text.len() == 66 && text.starts_with("0x") => {
+            if let Ok(hash) = text.parse::<H256>() {
+                hashes.insert(hash);
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_hashes(item, hashes)),
+        Value::Object(fields) => fields.values().for_each(|field| collect_hashes(field, hashes)),
+        _ => {}
+    }
+}
+
+// Transactions the bot knows it sent, from every configured ledger
+fn known_hashes(config: &Value) -> HashSet<H256> {
+    let mut hashes = HashSet::new();
+    for path in config["ledgers"].as_array().cloned().unwrap_or_default() {
+        for record in ledger_lines(path.as_str().unwrap_or_default()) {
+            collect_hashes(&record, &mut hashes);
+        }
+    }
+    hashes
+}
+
+fn field_address(entry: &Value, key: &str) -> Option<Address> {
+    entry[key].as_str().and_then(ens::parse_address)
+}
+
+fn field_i128(entry: &Value, key: &str) -> i128 {
+    entry[key].as_str().and_then(|amount| amount.parse::<i128>().ok()).unwrap_or(0)
+}
+
+// Internal and on-chain figures disagree by more than the tolerance
+fn mismatch(kind: &str, internal: i128, onchain: i128, tolerance_wei: i128, tolerance_pct: f64) -> Option<Value> {
+    let allowed = tolerance_wei.max((internal.abs() as f64 * tolerance_pct) as i128);
+    ((internal - onchain).abs() > allowed).then(|| {
+        json!({
+            "kind": kind,
+            "internal_wei": internal.to_string(),
+            "onchain_wei": onchain.to_string(),
+            "difference_wei": (onchain - internal).to_string(),
+        })
+    })
+}
+
+// Diff one UTC day of the wallet's on-chain history against the internal ledgers: transactions
+// and transfers no ledger explains, submissions the chain never saw, and gas and PnL totals that
+// don't agree. Only blocks the active chain considers final are compared; a day whose last blocks
+// could still reorg is reconciled up to the final block and reported as partial
+pub async fn reconcile_day(web3: &web3::Web3<FailoverTransport>, date: NaiveDate) -> Result<Value, ReconcileError> {
+    let config = load_reconcile_config();
+    let wallet = load_global_config()["wallet_address"]
+        .as_str()
+        .and_then(ens::parse_address)
+        .ok_or(ReconcileError::MissingConfig("wallet_address"))?;
+    let start = date.and_hms_opt(0, 0, 0).map(|start| Utc.from_utc_datetime(&start)).ok_or(ReconcileError::MissingConfig("date"))?;
+    let end = start + ChronoDuration::days(1);
+
+    let explorer = ExplorerClient::from_config()?;
+    let start_block = explorer.block_at(start.timestamp()).await? + 1;
+    let day_end_block = explorer.block_at(end.timestamp() - 1).await?;
+    let end_block = day_end_block.min(chains::finality().final_block(web3).await?);
+    let partial = end_block < day_end_block;
+    let transactions = explorer.account_transactions(wallet, start_block, end_block).await?;
+    let transfers = explorer.token_transfers(wallet, start_block, end_block).await?;
+
+    let known = known_hashes(&config);
+    let external: Vec<Address> = config["external_addresses"]
+        .as_array()
+        .map(|addresses| addresses.iter().filter_map(|address| address.as_str().and_then(ens::parse_address)).collect())
+        .unwrap_or_default();
+    let base_token = field_address(&config, "base_token");
+    let hash_of = |entry: &Value| entry["hash"].as_str().and_then(|hash| hash.parse::<H256>().ok());
+
+    // Anything leaving the wallet must be ours; inbound transfers only need explaining when they
+    // don't come from an address we fund the bot from
+    let mut unexplained = Vec::new();
+    let mut onchain_hashes = HashSet::new();
+    let (mut onchain_gas, mut base_flow) = (0i128, 0i128);
+    for tx in &transactions {
+        let hash = match hash_of(tx) {
+            Some(hash) => hash,
+            None => continue,
+        };
+        onchain_hashes.insert(hash);
+        let (from, to) = (field_address(tx, "from"), field_address(tx, "to"));
+        let failed = tx["isError"].as_str() == Some("1");
+        let value = if failed { 0 } else { field_i128(tx, "value") };
+        if from == Some(wallet) {
+            onchain_gas += field_i128(tx, "gasUsed") * field_i128(tx, "gasPrice");
+            if !known.contains(&hash) {
+                unexplained.push(json!({ "kind": "outgoing_transaction", "hash": hash, "to": to, "value_wei": value.to_string() }));
+            }
+            if !to.is_some_and(|to| external.contains(&to)) {
+                base_flow -= value;
+            }
+        } else if !from.is_some_and(|from| external.contains(&from)) {
+            if !known.contains(&hash) && value > 0 {
+                unexplained.push(json!({ "kind": "incoming_transaction", "hash": hash, "from": from, "value_wei": value.to_string() }));
+            }
+            base_flow += value;
+        }
+    }
+    for transfer in &transfers {
+        let hash = match hash_of(transfer) {
+            Some(hash) => hash,
+            None => continue,
+        };
+        onchain_hashes.insert(hash);
+        let (from, to, token) = (field_address(transfer, "from"), field_address(transfer, "to"), field_address(transfer, "contractAddress"));
+        let amount = field_i128(transfer, "value");
+        let outgoing = from == Some(wallet);
+        let counterparty = if outgoing { to } else { from };
+        if outgoing && !known.contains(&hash) {
+            unexplained.push(json!({ "kind": "outgoing_transfer", "hash": hash, "token": token, "to": to, "amount": amount.to_string() }));
+        }
+        if token.is_some() && token == base_token && !counterparty.is_some_and(|counterparty| external.contains(&counterparty)) {
+            base_flow += if outgoing { -amount } else { amount };
+        }
+    }
+
+    // Submissions the ledger has for the day that the chain never included (dropped or replaced).
+    // Past the final block they may still land, so a partial day doesn't count them
+    let submissions_ledger = config["submissions_ledger"].as_str().unwrap_or("data/submissions.jsonl");
+    let not_on_chain: Vec<H256> = ledger_lines(submissions_ledger)
+        .iter()
+        .filter(|_| !partial)
+        .filter(|record| in_day(record, start, end))
+        .filter_map(|record| record["tx_hash"].as_str().and_then(|hash| hash.parse::<H256>().ok()))
+        .filter(|hash| !onchain_hashes.contains(hash))
+        .collect();
+
+    // Strategy PnL is recorded gross of gas, so gas goes back onto the on-chain flow before comparing
+    let gas_log_path = config["gas_log_path"].as_str().unwrap_or("data/gas_spend.jsonl");
+    let internal_gas: i128 = ledger_lines(gas_log_path).iter().filter(|record| in_day(record, start, end)).map(|record| field_i128(record, "cost_wei")).sum();
+    let pnl_ledger = config["pnl_ledger"].as_str().unwrap_or("data/strategy_pnl.jsonl");
+    let internal_pnl: i128 = ledger_lines(pnl_ledger).iter().filter(|record| in_day(record, start, end)).map(|record| field_i128(record, "pnl_wei")).sum();
+    let onchain_pnl = base_flow + onchain_gas;
+
+    let tolerance_wei = config["pnl_tolerance_wei"].as_str().and_then(|amount| amount.parse::<i128>().ok()).unwrap_or(0);
+    let mismatches: Vec<Value> = [
+        mismatch("gas", internal_gas, onchain_gas, tolerance_wei, config["gas_tolerance_pct"].as_f64().unwrap_or(0.05)),
+        mismatch("pnl", internal_pnl, onchain_pnl, tolerance_wei, config["pnl_tolerance_pct"].as_f64().unwrap_or(0.02)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let report = json!({
+        "date": date.to_string(),
+        "wallet": wallet,
+        "blocks": [start_block, end_block],
+        "partial": partial,
+        "transactions": transactions.len(),
+        "transfers": transfers.len(),
+        "unexplained": unexplained,
+        "not_on_chain": not_on_chain,
+        "mismatches": mismatches,
+        "gas": { "internal_wei": internal_gas.to_string(), "onchain_wei": onchain_gas.to_string() },
+        "pnl": { "internal_wei": internal_pnl.to_string(), "onchain_wei": onchain_pnl.to_string() },
+        "reconciled_at": Utc::now().to_rfc3339(),
+    });
+    record_report(&report);
+    Ok(report)
+}
+
+// Save the full report, log its summary and alert on anything that needs a look
+fn record_report(report: &Value) {
+    let date = report["date"].as_str().unwrap_or_default();
+    let _ = fs::create_dir_all(RECONCILIATION_DIR);
+    if let Err(e) = fs::write(format!("{}/{}.json", RECONCILIATION_DIR, date), report.to_string()) {
+        error!("Failed to write reconciliation report: {}", e);
+    }
+
+    let unexplained = report["unexplained"].as_array().map(Vec::len).unwrap_or(0);
+    let mismatches = report["mismatches"].as_array().map(Vec::len).unwrap_or(0);
+    let summary = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "date": date,
+        "unexplained": unexplained,
+        "not_on_chain": report["not_on_chain"].as_array().map(Vec::len).unwrap_or(0),
+        "partial": report["partial"],
+        "mismatches": report["mismatches"],
+    });
+    match OpenOptions::new().create(true).append(true).open(RECONCILIATION_LOG_PATH) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", summary) {
+                error!("Failed to write reconciliation summary: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open reconciliation log: {}", e),
+    }
+    events::emit("reconciliation", summary);
+
+    if unexplained == 0 && mismatches == 0 {
+        info!("Reconciliation for {}: ledger matches the chain", date);
+        return;
+    }
+    let (subject, body) = templates::render("reconciliation", &[
+        ("date", date.to_string()),
+        ("unexplained", unexplained.to_string()),
+        ("mismatches", mismatches.to_string()),
+        ("details", format!("{}\n{}", report["unexplained"], report["mismatches"])),
+    ]);
+    // Unexplained outgoing activity may mean a leaked key, a mismatch only a bookkeeping bug
+    let severity = if unexplained > 0 { Severity::Critical } else { Severity::Warn };
+    alerts::raise(&format!("reconciliation:{}", date), severity, &subject, &body);
+}
+
+// Recent daily summaries, newest first, for the dashboard
+pub fn recent_summaries(limit: usize) -> Vec<Value> {
+    let mut summaries = ledger_lines(RECONCILIATION_LOG_PATH);
+    summaries.reverse();
+    summaries.truncate(limit);
+    summaries
+}
+
+// Reconcile the previous UTC day once a day at run_at_utc_hour
+pub async fn run_reconciliation(web3: web3::Web3<FailoverTransport>) {
+    loop {
+        let hour = load_reconcile_config()["run_at_utc_hour"].as_u64().unwrap_or(0) as u32;
+        let now = Utc::now();
+        let today = now.date_naive().and_hms_opt(hour.min(23), 0, 0).map(|at| Utc.from_utc_datetime(&at)).unwrap_or(now);
+        let next = if today > now { today } else { today + ChronoDuration::days(1) };
+        sleep(Duration::from_secs((next - now).num_seconds().max(1) as u64)).await;
+
+        let yesterday = Utc::now().date_naive() - ChronoDuration::days(1);
+        if let Err(e) = reconcile_day(&web3, yesterday).await {
+            error!("Reconciliation for {} failed: {}", yesterday, e);
+        }
+    }
+}
+
+// `taz reconcile [--date YYYY-MM-DD]` reconciles one day (yesterday by default) and prints the report
+pub async fn run_reconcile_command(global_config: Value, args: Vec<String>) -> Result<(), ReconcileError> {
+    let date = match args.iter().position(|arg| arg == "--date").and_then(|index| args.get(index + 1)) {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| ReconcileError::Usage)?,
+        None if args.is_empty() => Utc::now().date_naive() - ChronoDuration::days(1),
+        None => return Err(ReconcileError::Usage),
+    };
+    let default_url = format!(
+        "https://{}.infura.io/v3/{}",
+        global_config["network"].as_str().unwrap_or("mainnet"),
+        global_config["infura_project_id"].as_str().unwrap_or_default()
+    );
+    let web3 = Arc::new(ProviderPool::from_config(&default_url)?).web3_for("reconcile")?;
+    let report = reconcile_day(&web3, date).await?;
+    println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+    if report["partial"].as_bool().unwrap_or(false) {
+        println!("Note: the last blocks of {} are not final yet, the report stops at block {}", date, report["blocks"][1]);
+    }
+    // A day that hasn't ended yet only has part of its activity
+    if (Utc::now().date_naive() - date).num_days() < 1 {
+        println!("Note: {} is not over yet ({}:00 UTC), the report is partial", date, Utc::now().hour());
+    }
+    Ok(())
+}
+
+// Custom error type for ledger reconciliation
+#[derive(Error, Debug)]
+pub enum ReconcileError {
+    #[error("Explorer error: {0}")]
+    ExplorerError(#[from] ExplorerError),
+    #[error("Chain error: {0}")]
+    ChainError(#[from] ChainError),
+    #[error("Provider error: {0}")]
+    ProviderError(#[from] ProviderError),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("usage: taz reconcile [--date YYYY-MM-DD]")]
+    Usage,
+}
+
+// Implement conversion for ReconcileError to Web3 error
+impl From<ReconcileError> for web3::Error {
+    fn from(error: ReconcileError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
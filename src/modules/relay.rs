@@ -0,0 +1,248 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::str::FromStr;
+use web3::signing::{keccak256, Key, SecretKey, SecretKeyRef};
+use web3::types::{Address, Bytes, TransactionRequest, H256, U256};
+use web3::Transport;
+use log::{info, warn};
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::events;
+use crate::modules::execution::Call;
+use crate::modules::postmortem;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::signer::{self, SignerError, TxSender};
+use crate::modules::storage;
+
+// Load the private relay configuration
+fn load_relay_config() -> Value {
+    let config_path = "config/relay_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read relay config file");
+    serde_json::from_str(&config_data).expect("Unable to parse relay config file")
+}
+
+// Whether strategies that trade around a pending transaction send bundles instead of public transactions
+pub fn enabled() -> bool {
+    load_relay_config()["private_relay"].as_bool().unwrap_or(false)
+}
+
+// How a submitted bundle ended
+#[derive(Clone, Debug, PartialEq)]
+pub enum BundleOutcome {
+    // Landed in this block; the hashes are our own transactions in the bundle
+    Included { block: u64, tx_hashes: Vec<H256> },
+    // Every target block passed without the bundle
+    NotIncluded { last_target_block: u64 },
+}
+
+// Signed transactions that must land together, in order
+pub struct Bundle {
+    pub transactions: Vec<Bytes>,
+    // Hashes of the transactions we signed, to follow inclusion by
+    pub own_hashes: Vec<H256>,
+    // Our legs as calls from the sender, replayed in the post-mortem of a bundle that didn't land
+    pub from: Address,
+    pub calls: Vec<Call>,
+}
+
+impl Bundle {
+    // Sign our transactions around a pending one: the legs before it, the pending transaction as it
+    // was broadcast, then the legs after it. Our legs take consecutive nonces from the pending nonce
+    pub async fn around(
+        web3: &web3::Web3<FailoverTransport>,
+        sender: &TxSender,
+        strategy: &str,
+        before: Vec<TransactionRequest>,
+        target: H256,
+        after: Vec<TransactionRequest>,
+    ) -> Result<Self, RelayError> {
+        let target_raw = raw_transaction(web3, target).await?;
+        let before_count = before.len();
+        let requests: Vec<TransactionRequest> = before.into_iter().chain(after).collect();
+        let calls = requests.iter().map(request_call).collect();
+        let signed = sign_in_sequence(sender, strategy, requests).await?;
+
+        let own_hashes = signed.iter().map(|raw| H256::from(keccak256(&raw.0))).collect();
+        let mut transactions = signed[..before_count].to_vec();
+        transactions.push(target_raw);
+        transactions.extend_from_slice(&signed[before_count..]);
+        Ok(Bundle { transactions, own_hashes, from: sender.address(), calls })
+    }
+
+    // Sign our own transactions as a bundle with nothing of anyone else's in it
+    pub async fn of(sender: &TxSender, strategy: &str, requests: Vec<TransactionRequest>) -> Result<Self, RelayError> {
+        let calls = requests.iter().map(request_call).collect();
+        let transactions = sign_in_sequence(sender, strategy, requests).await?;
+        let own_hashes = transactions.iter().map(|raw| H256::from(keccak256(&raw.0))).collect();
+        Ok(Bundle { transactions, own_hashes, from: sender.address(), calls })
+    }
+}
+
+fn request_call(request: &TransactionRequest) -> Call {
+    Call {
+        to: request.to.unwrap_or_default(),
+        value: request.value.unwrap_or_default(),
+        data: request.data.as_ref().map(|data| data.0.clone()).unwrap_or_default(),
+    }
+}
+
+// Sign requests with consecutive nonces from the sender's pending nonce
+async fn sign_in_sequence(sender: &TxSender, strategy: &str, requests: Vec<TransactionRequest>) -> Result<Vec<Bytes>, RelayError> {
+    let mut nonce = sender.next_nonce().await?;
+    let mut signed = Vec::with_capacity(requests.len());
+    for mut request in requests {
+        request.nonce = Some(nonce);
+        nonce += U256::one();
+        signed.push(sender.sign(strategy, request).await?);
+    }
+    Ok(signed)
+}
+
+// The pending transaction exactly as it was signed, so it can be placed inside our bundle
+async fn raw_transaction(web3: &web3::Web3<FailoverTransport>, hash: H256) -> Result<Bytes, RelayError> {
+    let raw = web3.transport().execute("eth_getRawTransactionByHash", vec![json!(hash)]).await?;
+    serde_json::from_value::<Option<Bytes>>(raw)
+        .ok()
+        .flatten()
+        .ok_or(RelayError::TargetUnavailable(hash))
+}
+
+// Relay client: requests are signed with the auth key, which only carries searcher reputation and
+// never holds funds
+pub struct RelayClient {
+    client: reqwest::Client,
+    relay_url: String,
+    auth_key: SecretKey,
+    target_blocks: u64,
+    poll_interval: Duration,
+}
+
+impl RelayClient {
+    pub fn from_config() -> Result<Self, RelayError> {
+        let config = load_relay_config();
+        let relay_url = config["relay_url"].as_str().ok_or(RelayError::MissingConfig("relay_url"))?.to_string();
+        let key_env = config["auth_key_env"].as_str().unwrap_or("TAZ_FLASHBOTS_AUTH_KEY");
+        let auth_key = std::env::var(key_env)
+            .ok()
+            .and_then(|key| SecretKey::from_str(key.trim().trim_start_matches("0x")).ok())
+            .ok_or(RelayError::MissingConfig("auth_key_env"))?;
+        Ok(RelayClient {
+            client: reqwest::Client::new(),
+            relay_url,
+            auth_key,
+            target_blocks: config["target_blocks"].as_u64().unwrap_or(3).max(1),
+            poll_interval: Duration::from_millis(config["poll_interval_ms"].as_u64().unwrap_or(1000)),
+        })
+    }
+
+    // X-Flashbots-Signature: the auth address and its personal_sign of the body's keccak hash as hex
+    fn auth_header(&self, body: &str) -> Result<String, RelayError> {
+        let message = format!("{:?}", H256::from(keccak256(body.as_bytes())));
+        let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
+        let key = SecretKeyRef::new(&self.auth_key);
+        let signature = key.sign(&keccak256(prefixed.as_bytes()), None).map_err(|_| RelayError::SigningFailed)?;
+        let mut bytes = Vec::with_capacity(65);
+        bytes.extend_from_slice(signature.r.as_bytes());
+        bytes.extend_from_slice(signature.s.as_bytes());
+        bytes.push(signature.v as u8);
+        let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        Ok(format!("{:?}:0x{}", key.address(), hex))
+    }
+
+    async fn rpc_request(&self, method: &str, params: Value) -> Result<Value, RelayError> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+        let response: Value = self
+            .client
+            .post(&self.relay_url)
+            .header("Content-Type", "application/json")
+            .header("X-Flashbots-Signature", self.auth_header(&body)?)
+            .body(body)
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(rpc_error) = response.get("error") {
+            return Err(RelayError::RelayRejected(rpc_error.to_string()));
+        }
+        Ok(response["result"].clone())
+    }
+
+    // Submit the bundle for each of the next target_blocks blocks, then follow it until it lands or
+    // the last target block has passed
+    pub async fn submit(&self, web3: &web3::Web3<FailoverTransport>, strategy: &str, bundle: &Bundle) -> Result<BundleOutcome, RelayError> {
+        // Our legs were checked when they were signed, but a bundle signed before a pause or a lost
+        // wallet lock must not go out either
+        signer::ensure_may_send(None)?;
+        let head = web3.eth().block_number().await?.as_u64();
+        let targets = (head + 1)..=(head + self.target_blocks);
+        let mut bundle_hashes = Vec::new();
+        for block in targets.clone() {
+            let params = json!([{ "txs": bundle.transactions, "blockNumber": format!("{:#x}", block) }]);
+            let result = self.rpc_request("eth_sendBundle", params).await?;
+            bundle_hashes.push(result["bundleHash"].as_str().unwrap_or_default().to_string());
+        }
+        info!("{} bundle of {} transactions sent for blocks {}-{}", strategy, bundle.transactions.len(), targets.start(), targets.end());
+        events::emit("bundle_submitted", json!({ "strategy": strategy, "bundle_hashes": bundle_hashes, "tx_hashes": bundle.own_hashes, "first_block": targets.start(), "last_block": targets.end() }));
+        for tx_hash in &bundle.own_hashes {
+            storage::record_transaction(strategy, *tx_hash, "bundle");
+        }
+
+        let outcome = self.wait_for_inclusion(web3, bundle, *targets.end()).await?;
+        match &outcome {
+            BundleOutcome::Included { block, .. } => info!("{} bundle included in block {}", strategy, block),
+            BundleOutcome::NotIncluded { last_target_block } => {
+                warn!("{} bundle not included by block {}", strategy, last_target_block);
+                let reason = format!("bundle not included by block {}", last_target_block);
+                postmortem::capture(web3, strategy, &reason, bundle.from, &bundle.calls, None, &[]).await;
+            }
+        }
+        events::emit("bundle_outcome", json!({ "strategy": strategy, "tx_hashes": bundle.own_hashes, "included": matches!(outcome, BundleOutcome::Included { .. }) }));
+        Ok(outcome)
+    }
+
+    // The bundle lands whole or not at all, so our first transaction's receipt settles it
+    async fn wait_for_inclusion(&self, web3: &web3::Web3<FailoverTransport>, bundle: &Bundle, last_target_block: u64) -> Result<BundleOutcome, RelayError> {
+        let first = match bundle.own_hashes.first() {
+            Some(first) => *first,
+            None => return Ok(BundleOutcome::NotIncluded { last_target_block }),
+        };
+        loop {
+            if let Some(receipt) = web3.eth().transaction_receipt(first).await? {
+                let block = receipt.block_number.map(|number| number.as_u64()).unwrap_or_default();
+                return Ok(BundleOutcome::Included { block, tx_hashes: bundle.own_hashes.clone() });
+            }
+            if web3.eth().block_number().await?.as_u64() > last_target_block {
+                return Ok(BundleOutcome::NotIncluded { last_target_block });
+            }
+            sleep(self.poll_interval).await;
+        }
+    }
+}
+
+// Custom error type for private relay submission
+#[derive(Error, Debug)]
+pub enum RelayError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("Signer error: {0}")]
+    SignerError(#[from] SignerError),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("Failed to sign the relay auth header")]
+    SigningFailed,
+    #[error("Raw transaction for {0:?} is not available from the node")]
+    TargetUnavailable(H256),
+    #[error("Relay rejected the bundle: {0}")]
+    RelayRejected(String),
+}
+
+// Implement conversion for RelayError to Web3 error
+impl From<RelayError> for web3::Error {
+    fn from(error: RelayError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
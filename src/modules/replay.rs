@@ -0,0 +1,163 @@
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use log::{error, info, warn};
+use chrono::Utc;
+
+use crate::modules::arbitrage::{self, ArbitrageInputs};
+use crate::modules::cancellation_risk::{self, RaceInputs};
+use crate::modules::sandwich::{self, VictimInputs};
+
+// One file of recorded decisions per block
+const DECISIONS_DIR: &str = "data/decisions";
+
+// Load global config (decision recording toggle lives there)
+fn load_global_config() -> Value {
+    let config_path = "config/global_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read global config file");
+    serde_json::from_str(&config_data).expect("Unable to parse global config file")
+}
+
+pub fn recording_enabled() -> bool {
+    load_global_config()["decision_recording_enabled"].as_bool().unwrap_or(false)
+}
+
+fn block_path(block: u64) -> String {
+    format!("{}/{}.jsonl", DECISIONS_DIR, block)
+}
+
+// Record the inputs a strategy decided on and what it decided, keyed by block
+pub fn record_decision(strategy: &str, block: u64, inputs: Value, decision: Value) {
+    let _ = fs::create_dir_all(DECISIONS_DIR);
+    let record = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "strategy": strategy,
+        "block": block,
+        "inputs": inputs,
+        "decision": decision,
+    });
+
+    match OpenOptions::new().create(true).append(true).open(block_path(block)) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write decision record: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open decision log for block {}: {}", block, e),
+    }
+}
+
+// Re-run the decision code for one recorded input set
+fn decide(strategy: &str, inputs: &Value) -> Result<Value, String> {
+    match strategy {
+        "arbitrage" => {
+            let inputs: ArbitrageInputs = serde_json::from_value(inputs.clone()).map_err(|e| e.to_string())?;
+            Ok(json!(arbitrage::decide(&inputs)))
+        }
+        "sandwich_victim" => {
+            let inputs: VictimInputs = serde_json::from_value(inputs.clone()).map_err(|e| e.to_string())?;
+            Ok(json!(sandwich::decide_victim(&inputs)))
+        }
+        "sandwich_race" => {
+            let inputs: RaceInputs = serde_json::from_value(inputs.clone()).map_err(|e| e.to_string())?;
+            Ok(json!(cancellation_risk::decide(&inputs)))
+        }
+        other => Err(format!("no replayable decision logic for {}", other)),
+    }
+}
+
+// Fields whose values differ between the recorded and replayed decision
+fn diff_fields(recorded: &Value, replayed: &Value) -> Vec<Value> {
+    let keys = recorded
+        .as_object()
+        .into_iter()
+        .chain(replayed.as_object())
+        .flat_map(|object| object.keys().cloned())
+        .collect::<std::collections::BTreeSet<_>>();
+    keys.into_iter()
+        .filter(|key| recorded[key] != replayed[key])
+        .map(|key| json!({ "field": key, "recorded": recorded[&key], "replayed": replayed[&key] }))
+        .collect()
+}
+
+// Re-run every decision recorded for the block and diff against what the live bot decided;
+// each decision runs twice so non-determinism shows up separately from regressions
+pub fn replay_block(block: u64) -> Value {
+    let contents = match fs::read_to_string(block_path(block)) {
+        Ok(contents) => contents,
+        Err(e) => return json!({ "block": block, "error": format!("no recorded decisions: {}", e) }),
+    };
+
+    let (mut total, mut matched) = (0, 0);
+    let mut mismatches = Vec::new();
+    let mut nondeterministic = Vec::new();
+    let mut errors = Vec::new();
+
+    for (line_number, line) in contents.lines().enumerate() {
+        let record: Value = match serde_json::from_str(line) {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(json!({ "line": line_number + 1, "error": e.to_string() }));
+                continue;
+            }
+        };
+        total += 1;
+        let strategy = record["strategy"].as_str().unwrap_or("unknown");
+
+        let (first, second) = match (decide(strategy, &record["inputs"]), decide(strategy, &record["inputs"])) {
+            (Ok(first), Ok(second)) => (first, second),
+            (Err(e), _) | (_, Err(e)) => {
+                errors.push(json!({ "line": line_number + 1, "strategy": strategy, "error": e }));
+                continue;
+            }
+        };
+
+        if first != second {
+            nondeterministic.push(json!({ "line": line_number + 1, "strategy": strategy, "diff": diff_fields(&first, &second) }));
+        }
+        let diff = diff_fields(&record["decision"], &first);
+        if diff.is_empty() {
+            matched += 1;
+        } else {
+            mismatches.push(json!({ "line": line_number + 1, "strategy": strategy, "inputs": record["inputs"], "diff": diff }));
+        }
+    }
+
+    json!({
+        "block": block,
+        "decisions": total,
+        "matched": matched,
+        "mismatches": mismatches,
+        "nondeterministic": nondeterministic,
+        "errors": errors,
+    })
+}
+
+// Replay the block named by "replay_block" in the global config and write the report next to its decisions
+pub fn run_replay() {
+    let block = match load_global_config()["replay_block"].as_u64() {
+        Some(block) => block,
+        None => {
+            error!("replay_block is not set in the global config");
+            return;
+        }
+    };
+
+    let report = replay_block(block);
+    let report_path = format!("{}/{}-replay.json", DECISIONS_DIR, block);
+    if let Err(e) = fs::write(&report_path, serde_json::to_string_pretty(&report).unwrap_or_default()) {
+        error!("Failed to write replay report {}: {}", report_path, e);
+    }
+
+    let mismatches = report["mismatches"].as_array().map(|list| list.len()).unwrap_or(0);
+    let nondeterministic = report["nondeterministic"].as_array().map(|list| list.len()).unwrap_or(0);
+    if mismatches > 0 || nondeterministic > 0 {
+        warn!(
+            "Replay of block {}: {} of {} decisions differ from live, {} non-deterministic (report: {})",
+            block, mismatches, report["decisions"], nondeterministic, report_path
+        );
+    } else {
+        info!("Replay of block {}: all {} decisions match (report: {})", block, report["decisions"], report_path);
+    }
+}
@@ -0,0 +1,236 @@
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use web3::ethabi::{self, ParamType, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, CallRequest, U256};
+use log::{error, info, warn};
+use chrono::Utc;
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::allocator;
+use crate::modules::domain::OpportunitySource;
+use crate::modules::ens;
+use crate::modules::gas::Urgency;
+use crate::modules::events;
+use crate::modules::execution::{Call, ExecutionBackend, ExecutionError};
+use crate::modules::pricing::{self, PricingError};
+use crate::modules::providers::FailoverTransport;
+
+// Every claim decision, claimed or skipped
+const REWARDS_LEDGER_PATH: &str = "data/rewards.jsonl";
+
+// Load the reward claiming configuration
+fn load_rewards_config() -> Value {
+    let config_path = "config/rewards_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read rewards config file");
+    serde_json::from_str(&config_data).expect("Unable to parse rewards config file")
+}
+
+// The incentive controllers we know how to read and claim from
+#[derive(Clone, Debug)]
+pub enum ControllerKind {
+    // Aave V3 RewardsController: rewards per reward token over a list of aTokens/debt tokens
+    AaveV3 { assets: Vec<Address> },
+    // Compound V2 Comptroller: COMP accrued to the holder
+    CompoundV2,
+    // Compound V3 CometRewards: rewards owed on one Comet market
+    Comet { comet: Address },
+}
+
+#[derive(Clone, Debug)]
+pub struct IncentiveController {
+    pub name: String,
+    pub kind: ControllerKind,
+    pub address: Address,
+    pub reward_token: Address,
+}
+
+fn parse_controller(entry: &Value) -> Result<IncentiveController, RewardsError> {
+    let name = entry["name"].as_str().unwrap_or("unnamed").to_string();
+    let address_field = |field: &'static str| entry[field].as_str().and_then(ens::parse_address).ok_or(RewardsError::MissingConfig(field));
+    let kind = match entry["kind"].as_str().unwrap_or_default() {
+        "aave_v3" => ControllerKind::AaveV3 {
+            assets: entry["assets"]
+                .as_array()
+                .map(|assets| assets.iter().filter_map(|asset| asset.as_str().and_then(ens::parse_address)).collect())
+                .unwrap_or_default(),
+        },
+        "compound_v2" => ControllerKind::CompoundV2,
+        "comet" => ControllerKind::Comet { comet: address_field("comet")? },
+        other => return Err(RewardsError::UnknownKind(other.to_string())),
+    };
+    Ok(IncentiveController { name, kind, address: address_field("address")?, reward_token: address_field("reward_token")? })
+}
+
+fn encode(signature: &str, args: &[Token]) -> Vec<u8> {
+    [keccak256(signature.as_bytes())[..4].to_vec(), ethabi::encode(args)].concat()
+}
+
+impl IncentiveController {
+    // Rewards the holder could claim right now, in reward token units
+    pub async fn claimable(&self, web3: &web3::Web3<FailoverTransport>, holder: Address) -> Result<U256, RewardsError> {
+        let (data, outputs) = match &self.kind {
+            ControllerKind::AaveV3 { assets } => (
+                encode("getUserRewards(address[],address,address)", &[
+                    Token::Array(assets.iter().map(|asset| Token::Address(*asset)).collect()),
+                    Token::Address(holder),
+                    Token::Address(self.reward_token),
+                ]),
+                vec![ParamType::Uint(256)],
+            ),
+            ControllerKind::CompoundV2 => (encode("compAccrued(address)", &[Token::Address(holder)]), vec![ParamType::Uint(256)]),
+            // getRewardOwed accrues as it reads, so it is only ever eth_call'ed
+            ControllerKind::Comet { comet } => (
+                encode("getRewardOwed(address,address)", &[Token::Address(*comet), Token::Address(holder)]),
+                vec![ParamType::Address, ParamType::Uint(256)],
+            ),
+        };
+        let request = CallRequest { to: Some(self.address), data: Some(Bytes(data)), ..Default::default() };
+        let output = web3.eth().call(request, None).await?;
+        let decoded = ethabi::decode(&outputs, &output.0)?;
+        Ok(decoded.last().and_then(|token| token.clone().into_uint()).unwrap_or_default())
+    }
+
+    // The claim call paying the rewards to the holder
+    pub fn claim_call(&self, holder: Address, amount: U256) -> Call {
+        let data = match &self.kind {
+            ControllerKind::AaveV3 { assets } => encode("claimRewards(address[],uint256,address,address)", &[
+                Token::Array(assets.iter().map(|asset| Token::Address(*asset)).collect()),
+                Token::Uint(amount),
+                Token::Address(holder),
+                Token::Address(self.reward_token),
+            ]),
+            ControllerKind::CompoundV2 => encode("claimComp(address)", &[Token::Address(holder)]),
+            ControllerKind::Comet { comet } => encode("claim(address,address,bool)", &[
+                Token::Address(*comet),
+                Token::Address(holder),
+                Token::Bool(true),
+            ]),
+        };
+        Call { to: self.address, value: U256::zero(), data }
+    }
+}
+
+fn append_ledger(record: &Value) {
+    let _ = fs::create_dir_all("data");
+    match OpenOptions::new().create(true).append(true).open(REWARDS_LEDGER_PATH) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write rewards record: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open rewards ledger: {}", e),
+    }
+}
+
+// Check every configured controller and claim where the rewards are worth more than the gas by
+// min_value_to_gas. Claimed value, net of gas, is booked as "rewards" PnL
+pub async fn claim_rewards(web3: &web3::Web3<FailoverTransport>) -> Result<usize, RewardsError> {
+    let config = load_rewards_config();
+    let backend = ExecutionBackend::from_config(web3)?;
+    let holder = backend.recipient();
+    let gas_units = U256::from(config["estimated_gas_units"].as_u64().unwrap_or(300_000));
+    let min_multiple = config["min_value_to_gas"].as_f64().unwrap_or(2.0);
+
+    let mut claimed = 0;
+    for entry in config["controllers"].as_array().cloned().unwrap_or_default() {
+        let controller = match parse_controller(&entry) {
+            Ok(controller) => controller,
+            Err(e) => {
+                warn!("Skipping incentive controller {}: {}", entry["name"], e);
+                continue;
+            }
+        };
+        let amount = controller.claimable(web3, holder).await?;
+        if amount.is_zero() {
+            continue;
+        }
+        let value_wei = pricing::token_to_wei(web3, controller.reward_token, amount).await?;
+        let gas_cost = pricing::gas_cost_wei(web3, gas_units, Urgency::Low).await?;
+
+        let mut record = json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "controller": controller.name,
+            "reward_token": controller.reward_token,
+            "amount": amount.to_string(),
+            "value_wei": value_wei.to_string(),
+            "gas_cost_wei": gas_cost.to_string(),
+        });
+        let as_f64 = |amount: U256| amount.min(U256::from(u128::MAX)).low_u128() as f64;
+        if as_f64(value_wei) < as_f64(gas_cost) * min_multiple {
+            record["kind"] = json!("skipped");
+            append_ledger(&record);
+            continue;
+        }
+
+        match backend.execute_calls(web3, "rewards", vec![controller.claim_call(holder, amount)]).await {
+            Ok(tx_hash) => {
+                record["kind"] = json!("claimed");
+                record["tx_hash"] = json!(tx_hash);
+                append_ledger(&record);
+                events::emit("rewards_claimed", record.clone());
+                allocator::record_trade_result("rewards", OpportunitySource::ScheduledScan, allocator::signed_difference(value_wei, gas_cost));
+                info!("Claimed {} of {:?} from {}: {:?}", amount, controller.reward_token, controller.name, tx_hash);
+                claimed += 1;
+            }
+            Err(e) => {
+                record["kind"] = json!("claim_failed");
+                record["error"] = json!(e.to_string());
+                append_ledger(&record);
+                warn!("Failed to claim rewards from {}: {}", controller.name, e);
+            }
+        }
+    }
+    Ok(claimed)
+}
+
+// Claim on the configured interval
+pub async fn run_reward_claimer(web3: web3::Web3<FailoverTransport>) {
+    loop {
+        let config = load_rewards_config();
+        match claim_rewards(&web3).await {
+            Ok(0) => {}
+            Ok(claimed) => info!("Reward claimer claimed from {} controllers", claimed),
+            Err(e) => warn!("Reward claiming failed: {}", e),
+        }
+        sleep(Duration::from_secs(config["interval_secs"].as_u64().unwrap_or(21600))).await;
+    }
+}
+
+// Latest claim decisions, newest first, for the dashboard
+pub fn recent_claims(limit: usize) -> Vec<Value> {
+    fs::read_to_string(REWARDS_LEDGER_PATH)
+        .unwrap_or_default()
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
+}
+
+// Custom error type for reward claiming
+#[derive(Error, Debug)]
+pub enum RewardsError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Pricing error: {0}")]
+    PricingError(#[from] PricingError),
+    #[error("Unknown incentive controller kind: {0}")]
+    UnknownKind(String),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+}
+
+// Implement conversion for RewardsError to Web3 error
+impl From<RewardsError> for web3::Error {
+    fn from(error: RewardsError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
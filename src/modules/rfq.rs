@@ -0,0 +1,341 @@
+use serde_json::{json, Value};
+use std::fs;
+use web3::ethabi::{self, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, TransactionReceipt, H256, U256};
+use log::{info, warn};
+use chrono::Utc;
+use thiserror::Error;
+use tokio::time::Duration;
+
+use crate::modules::domain;
+use crate::modules::ens;
+use crate::modules::execution::{self, Call, ExecutionBackend, ExecutionError};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::receipt_events::{self, ReceiptEventsError};
+use crate::modules::slippage;
+use crate::modules::venues::{self, Venue, VenueError};
+
+// Load the RFQ provider configuration
+fn load_rfq_config() -> Value {
+    let config_path = "config/rfq_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read RFQ config file");
+    serde_json::from_str(&config_data).expect("Unable to parse RFQ config file")
+}
+
+pub fn enabled() -> bool {
+    load_rfq_config()["enabled"].as_bool().unwrap_or(false)
+}
+
+// A firm quote from an RFQ maker. The maker may cover less than was asked for, in which case
+// amount_in is the part it will fill and the call settles exactly that
+#[derive(Clone, Debug)]
+pub struct RfqQuote {
+    pub provider: String,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    // Contract the taker approves token_in to
+    pub spender: Address,
+    pub call: Call,
+    pub expiry: i64,
+}
+
+fn parse_u256(value: &Value) -> Option<U256> {
+    match value {
+        Value::String(value) => U256::from_dec_str(value).ok(),
+        Value::Number(value) => value.as_u64().map(U256::from),
+        _ => None,
+    }
+}
+
+fn api_key(provider: &Value) -> Option<String> {
+    provider["api_key_env"].as_str().and_then(|name| std::env::var(name).ok())
+}
+
+// 0x RFQ: a quote restricted to RFQ liquidity comes back as a ready-to-send settlement transaction
+async fn zeroex_quote(provider: &Value, token_in: Address, token_out: Address, amount_in: U256, taker: Address, timeout: Duration) -> Result<RfqQuote, RfqError> {
+    let url = provider["url"].as_str().ok_or(RfqError::MissingConfig("url"))?;
+    let mut request = reqwest::Client::new()
+        .get(url)
+        .query(&[
+            ("sellToken", format!("{:?}", token_in)),
+            ("buyToken", format!("{:?}", token_out)),
+            ("sellAmount", amount_in.to_string()),
+            ("takerAddress", format!("{:?}", taker)),
+            ("includedSources", provider["included_sources"].as_str().unwrap_or("0x").to_string()),
+            ("intentOnFilling", String::from("true")),
+            ("skipValidation", String::from("true")),
+        ])
+        .timeout(timeout);
+    if let Some(key) = api_key(provider) {
+        request = request.header("0x-api-key", key);
+    }
+    let response: Value = request.send().await?.error_for_status()?.json().await?;
+
+    let to = response["to"].as_str().and_then(ens::parse_address).ok_or(RfqError::Malformed("to"))?;
+    let data: web3::types::Bytes = serde_json::from_value(response["data"].clone()).map_err(|_| RfqError::Malformed("data"))?;
+    Ok(RfqQuote {
+        provider: String::from("0x"),
+        token_in,
+        token_out,
+        amount_in: parse_u256(&response["sellAmount"]).ok_or(RfqError::Malformed("sellAmount"))?,
+        amount_out: parse_u256(&response["buyAmount"]).ok_or(RfqError::Malformed("buyAmount"))?,
+        spender: response["allowanceTarget"].as_str().and_then(ens::parse_address).unwrap_or(to),
+        call: Call { to, value: parse_u256(&response["value"]).unwrap_or_default(), data: data.0 },
+        expiry: Utc::now().timestamp() + provider["quote_ttl_secs"].as_i64().unwrap_or(30),
+    })
+}
+
+// Hashflow RFQ-T: the maker signs a quote that the taker settles through the Hashflow router with
+// tradeRFQT; baseTokenAmount may come back below the requested amount when the maker is short
+async fn hashflow_quote(provider: &Value, token_in: Address, token_out: Address, amount_in: U256, taker: Address, timeout: Duration) -> Result<RfqQuote, RfqError> {
+    let url = provider["url"].as_str().ok_or(RfqError::MissingConfig("url"))?;
+    let router = provider["router_address"].as_str().and_then(ens::parse_address).ok_or(RfqError::MissingConfig("router_address"))?;
+    let chain_id = provider["chain_id"].as_u64().unwrap_or(1);
+    let body = json!({
+        "source": provider["source"].as_str().unwrap_or("taz"),
+        "baseChain": { "chainType": "evm", "chainId": chain_id },
+        "quoteChain": { "chainType": "evm", "chainId": chain_id },
+        "rfqs": [{
+            "baseToken": token_in,
+            "quoteToken": token_out,
+            "baseTokenAmount": amount_in.to_string(),
+            "trader": taker,
+            "effectiveTrader": taker,
+        }],
+    });
+    let mut request = reqwest::Client::new().post(url).json(&body).timeout(timeout);
+    if let Some(key) = api_key(provider) {
+        request = request.header("Authorization", key);
+    }
+    let response: Value = request.send().await?.error_for_status()?.json().await?;
+
+    let quote = &response["quotes"][0];
+    let data = &quote["quoteData"];
+    let address = |key: &'static str| data[key].as_str().and_then(ens::parse_address).ok_or(RfqError::Malformed(key));
+    let amount = |key: &'static str| parse_u256(&data[key]).ok_or(RfqError::Malformed(key));
+    let base_amount = amount("baseTokenAmount")?;
+    let quote_amount = amount("quoteTokenAmount")?;
+    let expiry = data["quoteExpiry"].as_i64().ok_or(RfqError::Malformed("quoteExpiry"))?;
+    let txid: web3::types::Bytes = serde_json::from_value(data["txid"].clone()).map_err(|_| RfqError::Malformed("txid"))?;
+    let signature: web3::types::Bytes = serde_json::from_value(quote["signature"].clone()).map_err(|_| RfqError::Malformed("signature"))?;
+
+    // effectiveBaseTokenAmount equal to baseTokenAmount fills the whole quote
+    let trade = Token::Tuple(vec![
+        Token::Address(address("pool")?),
+        Token::Address(data["externalAccount"].as_str().and_then(ens::parse_address).unwrap_or_default()),
+        Token::Address(taker),
+        Token::Address(taker),
+        Token::Address(token_in),
+        Token::Address(token_out),
+        Token::Uint(base_amount),
+        Token::Uint(base_amount),
+        Token::Uint(quote_amount),
+        Token::Uint(U256::from(expiry)),
+        Token::Uint(amount("nonce")?),
+        Token::FixedBytes(txid.0),
+        Token::Bytes(signature.0),
+    ]);
+    let selector = &keccak256(b"tradeRFQT((address,address,address,address,address,address,uint256,uint256,uint256,uint256,uint256,bytes32,bytes))")[..4];
+    Ok(RfqQuote {
+        provider: String::from("hashflow"),
+        token_in,
+        token_out,
+        amount_in: base_amount,
+        amount_out: quote_amount,
+        spender: router,
+        call: Call { to: router, value: U256::zero(), data: [selector.to_vec(), ethabi::encode(&[trade])].concat() },
+        expiry,
+    })
+}
+
+// Firm quotes from every configured provider for selling amount_in; failures are logged and skipped
+pub async fn request_quotes(token_in: Address, token_out: Address, amount_in: U256, taker: Address) -> Vec<RfqQuote> {
+    let config = load_rfq_config();
+    let timeout = Duration::from_millis(config["quote_timeout_ms"].as_u64().unwrap_or(500));
+    let providers = config["providers"].as_array().cloned().unwrap_or_default();
+
+    let mut quotes = Vec::new();
+    for provider in providers.iter().filter(|provider| provider["enabled"].as_bool().unwrap_or(true)) {
+        let quote = match provider["kind"].as_str() {
+            Some("zeroex") => zeroex_quote(provider, token_in, token_out, amount_in, taker, timeout).await,
+            Some("hashflow") => hashflow_quote(provider, token_in, token_out, amount_in, taker, timeout).await,
+            other => Err(RfqError::UnknownProvider(other.unwrap_or_default().to_string())),
+        };
+        match quote {
+            Ok(quote) if !quote.amount_in.is_zero() && quote.amount_in <= amount_in => quotes.push(quote),
+            Ok(_) => {}
+            Err(e) => warn!("RFQ quote from {} failed: {}", provider["kind"], e),
+        }
+    }
+    quotes
+}
+
+// One arbitrage leg filled against an RFQ quote, with the part the maker doesn't cover routed to the
+// best AMM venue
+#[derive(Clone, Debug)]
+pub struct RfqLegPlan {
+    pub quote: RfqQuote,
+    pub amount_in: U256,
+    pub remainder_in: U256,
+    pub fallback: Option<(Venue, U256)>,
+}
+
+impl RfqLegPlan {
+    pub fn total_out(&self) -> U256 {
+        self.quote.amount_out + self.fallback.as_ref().map(|(_, out)| *out).unwrap_or_default()
+    }
+
+    pub fn label(&self) -> String {
+        match &self.fallback {
+            Some((venue, _)) => format!("rfq {} + {}", self.quote.provider, venue.label()),
+            None => format!("rfq {}", self.quote.provider),
+        }
+    }
+}
+
+// Best RFQ quote for the leg by rate, with the uncovered remainder quoted on the AMMs. Quotes filling
+// less than min_fill_ratio of the leg aren't worth the extra settlement
+pub async fn plan_leg(
+    web3: &web3::Web3<FailoverTransport>,
+    token_in: Address,
+    token_out: Address,
+    amount_in: U256,
+    taker: Address,
+) -> Result<Option<RfqLegPlan>, RfqError> {
+    let config = load_rfq_config();
+    let min_fill_ratio = config["min_fill_ratio"].as_f64().unwrap_or(0.25);
+    let now = Utc::now().timestamp();
+
+    let best = request_quotes(token_in, token_out, amount_in, taker)
+        .await
+        .into_iter()
+        .filter(|quote| quote.expiry > now)
+        .filter(|quote| quote.amount_in.as_u128() as f64 >= amount_in.as_u128() as f64 * min_fill_ratio)
+        .max_by(|a, b| a.amount_out.full_mul(b.amount_in).cmp(&b.amount_out.full_mul(a.amount_in)));
+    let quote = match best {
+        Some(quote) => quote,
+        None => return Ok(None),
+    };
+
+    let remainder_in = amount_in - quote.amount_in;
+    let fallback = if remainder_in.is_zero() { None } else { venues::best_quote(web3, token_in, token_out, remainder_in).await? };
+    // A remainder no venue can take leaves the leg short; only plan it if the quote covers everything
+    if !remainder_in.is_zero() && fallback.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(RfqLegPlan { quote, amount_in, remainder_in, fallback }))
+}
+
+// token amount transferred to the recipient by anyone in the transaction
+fn received(receipt: &TransactionReceipt, token: Address, recipient: Address) -> U256 {
+    let transfer_topic = H256::from(keccak256(b"Transfer(address,address,uint256)"));
+    receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == token && log.topics.len() == 3 && log.topics[0] == transfer_topic)
+        .filter(|log| Address::from(log.topics[2]) == recipient)
+        .fold(U256::zero(), |acc, log| acc + U256::from_big_endian(&log.data.0))
+}
+
+// What an executed RFQ leg actually delivered
+#[derive(Clone, Debug)]
+pub struct RfqLegResult {
+    pub rfq_submission: H256,
+    pub filled_in: U256,
+    pub fallback_in: U256,
+    pub received: U256,
+}
+
+// Fill the RFQ quote first, then read how much of it actually filled: makers can fill partially or
+// not at all. Everything not filled (the planned remainder plus any shortfall) is re-quoted and routed
+// to the best AMM venue at current state, so the leg always sells amount_in
+pub async fn execute_leg(
+    web3: &web3::Web3<FailoverTransport>,
+    backend: &ExecutionBackend,
+    strategy: &str,
+    plan: &RfqLegPlan,
+    slippage_tolerance: f64,
+) -> Result<RfqLegResult, RfqError> {
+    let recipient = backend.recipient();
+    let quote = &plan.quote;
+    if quote.expiry <= Utc::now().timestamp() {
+        return Err(RfqError::Expired(quote.provider.clone()));
+    }
+
+    let calls = vec![execution::approve_call(quote.token_in, quote.spender, quote.amount_in)?, quote.call.clone()];
+    let rfq_submission = backend.execute_calls(web3, strategy, calls).await?;
+    // The fill is read from the mined receipt; a Safe proposal has filled nothing yet
+    let rfq_receipt = if backend.is_proposal() { None } else { receipt_events::wait_for_receipt(web3, rfq_submission).await? };
+    let rfq_out = match rfq_receipt {
+        Some(receipt) if receipt.status != Some(0.into()) => received(&receipt, quote.token_out, recipient),
+        _ => U256::zero(),
+    };
+    // Fills are pro rata: the share of the quoted output received is the share of the input spent
+    let filled_in = quote
+        .amount_in
+        .full_mul(rfq_out)
+        .checked_div(quote.amount_out.into())
+        .and_then(|filled| U256::try_from(filled).ok())
+        .unwrap_or_default()
+        .min(quote.amount_in);
+    if filled_in < quote.amount_in {
+        warn!("RFQ {} filled {} of {} {:?}", quote.provider, filled_in, quote.amount_in, quote.token_in);
+    }
+
+    let fallback_in = plan.amount_in.saturating_sub(filled_in);
+    let mut fallback_out = U256::zero();
+    if !fallback_in.is_zero() {
+        // The unfilled remainder is held to the venue's quote for it less the slippage tolerance
+        let (venue, quoted) = venues::best_quote(web3, quote.token_in, quote.token_out, fallback_in)
+            .await?
+            .ok_or(RfqError::NoFallback(quote.token_in, quote.token_out))?;
+        let min_out = domain::min_out(slippage::adjust(venue.pool, quoted), Some(slippage_tolerance));
+        let deadline = U256::from(Utc::now().timestamp() + 600);
+        let calls = venue.swap_calls(quote.token_in, quote.token_out, fallback_in, min_out, recipient, deadline)?;
+        let submission = backend.execute_calls(web3, strategy, calls).await?;
+        if !backend.is_proposal() {
+            if let Some(receipt) = receipt_events::wait_for_receipt(web3, submission).await? {
+                fallback_out = received(&receipt, quote.token_out, recipient);
+            }
+        }
+        info!("Routed {} unfilled {:?} through {}: {:?}", fallback_in, quote.token_in, venue.label(), submission);
+    }
+
+    Ok(RfqLegResult { rfq_submission, filled_in, fallback_in, received: rfq_out + fallback_out })
+}
+
+// Custom error type for RFQ execution
+#[derive(Error, Debug)]
+pub enum RfqError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Venue error: {0}")]
+    VenueError(#[from] VenueError),
+    #[error("Receipt error: {0}")]
+    ReceiptEventsError(#[from] ReceiptEventsError),
+    #[error("Unknown RFQ provider kind: {0}")]
+    UnknownProvider(String),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("Malformed RFQ quote, field {0}")]
+    Malformed(&'static str),
+    #[error("RFQ quote from {0} expired before it was filled")]
+    Expired(String),
+    #[error("No venue to route the unfilled remainder {0:?} -> {1:?}")]
+    NoFallback(Address, Address),
+}
+
+// Implement conversion for RfqError to Web3 error
+impl From<RfqError> for web3::Error {
+    fn from(error: RfqError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
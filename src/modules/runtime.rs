@@ -0,0 +1,96 @@
+use serde_json::Value;
+use std::future::Future;
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+use log::{info, warn};
+
+// Worker stacks below this overflow on the bot's deeper futures; above the cap is a typo, not a tuning
+const MIN_THREAD_STACK_SIZE: u64 = 64 * 1024;
+const MAX_THREAD_STACK_SIZE: u64 = 1024 * 1024 * 1024;
+
+fn out_of_range(field: &str, min: u64, max: u64, value: u64) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("runtime.{} must be between {} and {}, got {}", field, min, max, value),
+    )
+}
+
+// A scheduler interval in ticks; Tokio panics on zero and the builder takes a u32
+fn interval(config: &Value, field: &str) -> std::io::Result<Option<u32>> {
+    match config[field].as_u64() {
+        Some(value) => match u32::try_from(value) {
+            Ok(interval) if interval > 0 => Ok(Some(interval)),
+            _ => Err(out_of_range(field, 1, u32::MAX as u64, value)),
+        },
+        None => Ok(None),
+    }
+}
+
+// Build the Tokio runtime from the "runtime" section of the global config
+pub fn build_runtime(config: &Value) -> std::io::Result<Runtime> {
+    let flavor = config["flavor"].as_str().unwrap_or("multi_thread");
+    let mut builder = match flavor {
+        "current_thread" => Builder::new_current_thread(),
+        _ => Builder::new_multi_thread(),
+    };
+
+    // Worker threads drive the async tasks (scanners, mempool streams, dashboard)
+    if let Some(worker_threads) = config["worker_threads"].as_u64() {
+        builder.worker_threads(worker_threads.max(1) as usize);
+    }
+
+    // Blocking pool is used by file I/O, SMTP and other sync calls
+    if let Some(max_blocking_threads) = config["max_blocking_threads"].as_u64() {
+        builder.max_blocking_threads(max_blocking_threads.max(1) as usize);
+    }
+
+    if let Some(keep_alive_ms) = config["thread_keep_alive_ms"].as_u64() {
+        builder.thread_keep_alive(Duration::from_millis(keep_alive_ms));
+    }
+
+    if let Some(stack_size) = config["thread_stack_size"].as_u64() {
+        if !(MIN_THREAD_STACK_SIZE..=MAX_THREAD_STACK_SIZE).contains(&stack_size) {
+            return Err(out_of_range("thread_stack_size", MIN_THREAD_STACK_SIZE, MAX_THREAD_STACK_SIZE, stack_size));
+        }
+        builder.thread_stack_size(stack_size as usize);
+    }
+
+    // Lower values make the scheduler poll for I/O more often (lower latency, more overhead)
+    if let Some(event_interval) = interval(config, "event_interval")? {
+        builder.event_interval(event_interval);
+    }
+
+    if let Some(global_queue_interval) = interval(config, "global_queue_interval")? {
+        builder.global_queue_interval(global_queue_interval);
+    }
+
+    builder
+        .thread_name(config["thread_name"].as_str().unwrap_or("taz-worker"))
+        .enable_all();
+
+    info!(
+        "Building {} runtime (worker_threads: {:?}, max_blocking_threads: {:?})",
+        flavor,
+        config["worker_threads"].as_u64(),
+        config["max_blocking_threads"].as_u64()
+    );
+
+    builder.build()
+}
+
+// Run the bot future on the configured runtime (io-uring when enabled and compiled in)
+pub fn run<F: Future>(config: &Value, future: F) -> std::io::Result<F::Output> {
+    if config["io_uring"].as_bool().unwrap_or(false) {
+        #[cfg(all(feature = "io-uring", target_os = "linux"))]
+        {
+            info!("Starting io-uring runtime");
+            return Ok(tokio_uring::start(future));
+        }
+
+        #[cfg(not(all(feature = "io-uring", target_os = "linux")))]
+        warn!("io_uring requested but the binary was built without the io-uring feature, using the standard runtime");
+    }
+
+    let runtime = build_runtime(config)?;
+    Ok(runtime.block_on(future))
+}
@@ -0,0 +1,196 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use web3::types::{TransactionReceipt, H256, U256};
+use log::{error, info, warn};
+use thiserror::Error;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use tokio::time::{sleep, Duration};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::ens;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::templates;
+
+// Load the runway configuration
+fn load_runway_config() -> Value {
+    let config_path = "config/runway_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read runway config file");
+    serde_json::from_str(&config_data).expect("Unable to parse runway config file")
+}
+
+// Projected runway for the bot wallet
+#[derive(Clone, Debug)]
+pub struct RunwayProjection {
+    pub balance_wei: U256,
+    pub daily_burn_wei: U256,
+    pub burn_by_strategy: HashMap<String, U256>,
+    pub runway_days: Option<f64>,
+}
+
+impl RunwayProjection {
+    pub fn to_json(&self) -> Value {
+        let burn_by_strategy: HashMap<&String, String> = self
+            .burn_by_strategy
+            .iter()
+            .map(|(strategy, burn)| (strategy, burn.to_string()))
+            .collect();
+
+        json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "balance_wei": self.balance_wei.to_string(),
+            "daily_burn_wei": self.daily_burn_wei.to_string(),
+            "burn_by_strategy": burn_by_strategy,
+            "runway_days": self.runway_days,
+        })
+    }
+}
+
+// Append a gas spend entry for a strategy transaction
+pub fn record_gas_spent(strategy: &str, tx_hash: H256, gas_used: U256, effective_gas_price: U256) {
+    let config = load_runway_config();
+    let log_path = config["gas_log_path"].as_str().unwrap_or("data/gas_spend.jsonl");
+    if let Some(parent) = std::path::Path::new(log_path).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    let record = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "strategy": strategy,
+        "tx_hash": tx_hash,
+        "gas_used": gas_used.to_string(),
+        "cost_wei": (gas_used * effective_gas_price).to_string(),
+    });
+
+    match OpenOptions::new().create(true).append(true).open(log_path) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write gas spend record: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open gas log {}: {}", log_path, e),
+    }
+}
+
+// Record the gas cost of a confirmed transaction from its receipt
+pub fn record_receipt_gas(strategy: &str, receipt: &TransactionReceipt) {
+    let gas_used = receipt.gas_used.unwrap_or_default();
+    let gas_price = receipt.effective_gas_price.unwrap_or_default();
+    record_gas_spent(strategy, receipt.transaction_hash, gas_used, gas_price);
+}
+
+// Sum gas spend per strategy over the trailing window
+pub fn burn_by_strategy(log_path: &str, window: ChronoDuration) -> HashMap<String, U256> {
+    let cutoff = Utc::now() - window;
+    let mut burn: HashMap<String, U256> = HashMap::new();
+
+    let contents = fs::read_to_string(log_path).unwrap_or_default();
+    for line in contents.lines() {
+        let record: Value = match serde_json::from_str(line) {
+            Ok(record) => record,
+            Err(_) => continue,
+        };
+        let timestamp = record["timestamp"]
+            .as_str()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc));
+        if timestamp.map(|ts| ts < cutoff).unwrap_or(true) {
+            continue;
+        }
+
+        let cost = U256::from_dec_str(record["cost_wei"].as_str().unwrap_or("0")).unwrap_or_default();
+        let strategy = record["strategy"].as_str().unwrap_or("unknown").to_string();
+        *burn.entry(strategy).or_insert_with(U256::zero) += cost;
+    }
+
+    burn
+}
+
+// Project days of runway from the wallet balance and recent burn rate
+pub async fn project_runway(web3: &web3::Web3<FailoverTransport>) -> Result<RunwayProjection, RunwayError> {
+    let config = load_runway_config();
+    let wallet = config["wallet_address"]
+        .as_str()
+        .and_then(ens::parse_address)
+        .ok_or(RunwayError::InvalidWallet)?;
+    let window_hours = config["burn_window_hours"].as_i64().unwrap_or(24).max(1);
+    let log_path = config["gas_log_path"].as_str().unwrap_or("data/gas_spend.jsonl");
+
+    let balance_wei = web3.eth().balance(wallet, None).await?;
+    let burn = burn_by_strategy(log_path, ChronoDuration::hours(window_hours));
+
+    // Normalize the window total to a daily rate
+    let window_total = burn.values().fold(U256::zero(), |acc, cost| acc + *cost);
+    let daily_burn_wei = window_total * U256::from(24) / U256::from(window_hours as u64);
+    let burn_by_strategy = burn
+        .into_iter()
+        .map(|(strategy, cost)| (strategy, cost * U256::from(24) / U256::from(window_hours as u64)))
+        .collect();
+
+    let runway_days = if daily_burn_wei.is_zero() {
+        None
+    } else {
+        Some(wei_to_eth(balance_wei) / wei_to_eth(daily_burn_wei))
+    };
+
+    Ok(RunwayProjection { balance_wei, daily_burn_wei, burn_by_strategy, runway_days })
+}
+
+// Periodically project runway, publish it for the dashboard and alert when it runs low
+pub async fn run_runway_monitor(web3: web3::Web3<FailoverTransport>) {
+    let config = load_runway_config();
+    let check_interval = config["check_interval_secs"].as_u64().unwrap_or(600);
+    let alert_below_days = config["alert_below_days"].as_f64().unwrap_or(3.0);
+    let projection_path = config["projection_path"].as_str().unwrap_or("data/runway.json").to_string();
+
+    loop {
+        match project_runway(&web3).await {
+            Ok(projection) => {
+                info!(
+                    "Runway: balance {:.4} ETH, burn {:.4} ETH/day, {:?} days",
+                    wei_to_eth(projection.balance_wei),
+                    wei_to_eth(projection.daily_burn_wei),
+                    projection.runway_days
+                );
+
+                if let Err(e) = fs::write(&projection_path, projection.to_json().to_string()) {
+                    error!("Failed to write runway projection: {}", e);
+                }
+
+                if let Some(days) = projection.runway_days {
+                    if days < alert_below_days {
+                        warn!("Runway below threshold: {:.2} days", days);
+                        let wallet = config["wallet_address"].as_str().and_then(ens::parse_address);
+                        let (subject, body) = templates::render("runway_low", &[
+                            ("days", format!("{:.2}", days)),
+                            ("threshold", format!("{:.2}", alert_below_days)),
+                            ("balance", templates::eth_amount(projection.balance_wei)),
+                            ("burn", templates::eth_amount(projection.daily_burn_wei)),
+                            ("wallet", wallet.map(templates::address_link).unwrap_or_default()),
+                        ]);
+                        alerts::raise("runway", Severity::Critical, &subject, &body);
+                    }
+                }
+            }
+            Err(e) => error!("Failed to project runway: {}", e),
+        }
+
+        sleep(Duration::from_secs(check_interval)).await;
+    }
+}
+
+// Convert wei to ETH as a float for display and ratios
+fn wei_to_eth(wei: U256) -> f64 {
+    wei.as_u128() as f64 / 1e18
+}
+
+// Custom error type for runway projection
+#[derive(Error, Debug)]
+pub enum RunwayError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Invalid wallet_address in runway config")]
+    InvalidWallet,
+}
@@ -0,0 +1,361 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::str::FromStr;
+use web3::contract::{Contract, Options};
+use web3::ethabi::{self, Token};
+use web3::signing::{keccak256, Key, SecretKey, SecretKeyRef};
+use web3::types::{Address, Bytes, CallRequest, TransactionParameters, H256, U256, U64};
+use log::{info, warn};
+use thiserror::Error;
+
+use crate::modules::ens;
+use crate::modules::gas::{self, GasError, Urgency};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::simulation::{self, SimulationError};
+use crate::modules::spend_caps::{self, SpendCapError};
+
+// Operation types understood by the Safe
+const OPERATION_CALL: u8 = 0;
+const OPERATION_DELEGATE_CALL: u8 = 1;
+
+// Load the Safe configuration
+fn load_safe_config() -> Value {
+    let config_path = "config/safe_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read safe config file");
+    serde_json::from_str(&config_data).expect("Unable to parse safe config file")
+}
+
+// Safe transaction as hashed by the Safe contract (EIP-712)
+#[derive(Clone, Debug)]
+pub struct SafeTransaction {
+    pub to: Address,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub operation: u8,
+    pub safe_tx_gas: U256,
+    pub base_gas: U256,
+    pub gas_price: U256,
+    pub gas_token: Address,
+    pub refund_receiver: Address,
+    pub nonce: U256,
+}
+
+impl SafeTransaction {
+    // EIP-712 hash signed by the Safe owners
+    pub fn hash(&self, safe: Address, chain_id: u64) -> H256 {
+        let domain_typehash = keccak256(b"EIP712Domain(uint256 chainId,address verifyingContract)");
+        let safe_tx_typehash = keccak256(
+            b"SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,uint256 baseGas,uint256 gasPrice,address gasToken,address refundReceiver,uint256 nonce)",
+        );
+
+        let domain_separator = keccak256(&ethabi::encode(&[
+            Token::FixedBytes(domain_typehash.to_vec()),
+            Token::Uint(U256::from(chain_id)),
+            Token::Address(safe),
+        ]));
+
+        let struct_hash = keccak256(&ethabi::encode(&[
+            Token::FixedBytes(safe_tx_typehash.to_vec()),
+            Token::Address(self.to),
+            Token::Uint(self.value),
+            Token::FixedBytes(keccak256(&self.data).to_vec()),
+            Token::Uint(U256::from(self.operation)),
+            Token::Uint(self.safe_tx_gas),
+            Token::Uint(self.base_gas),
+            Token::Uint(self.gas_price),
+            Token::Address(self.gas_token),
+            Token::Address(self.refund_receiver),
+            Token::Uint(self.nonce),
+        ]));
+
+        let mut payload = vec![0x19, 0x01];
+        payload.extend_from_slice(&domain_separator);
+        payload.extend_from_slice(&struct_hash);
+        H256::from(keccak256(&payload))
+    }
+}
+
+// Backend that routes calls through a Gnosis Safe, either proposing or executing them
+pub struct SafeBackend {
+    web3: web3::Web3<FailoverTransport>,
+    client: reqwest::Client,
+    safe_address: Address,
+    multisend_address: Address,
+    service_url: String,
+    chain_id: u64,
+    owner_keys: Vec<SecretKey>,
+    threshold: usize,
+    mode: String,
+}
+
+impl SafeBackend {
+    // Initialize the backend from config/safe_config.json
+    pub fn new(web3: web3::Web3<FailoverTransport>) -> Result<Self, SafeError> {
+        let config = load_safe_config();
+
+        let parse_address = |field: &'static str| {
+            config[field]
+                .as_str()
+                .and_then(ens::parse_address)
+                .ok_or(SafeError::MissingConfig(field))
+        };
+
+        // Owner keys are loaded from the env vars listed in the config, never from the file itself
+        let owner_keys = config["owner_key_envs"]
+            .as_array()
+            .map(|envs| {
+                envs.iter()
+                    .filter_map(|env| env.as_str())
+                    .filter_map(|env| std::env::var(env).ok())
+                    .filter_map(|key| SecretKey::from_str(key.trim_start_matches("0x")).ok())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        if owner_keys.is_empty() {
+            return Err(SafeError::MissingConfig("owner_key_envs"));
+        }
+
+        Ok(SafeBackend {
+            web3,
+            client: reqwest::Client::new(),
+            safe_address: parse_address("safe_address")?,
+            multisend_address: parse_address("multisend_call_only_address")?,
+            service_url: config["transaction_service_url"].as_str().unwrap_or("https://safe-transaction-mainnet.safe.global").trim_end_matches('/').to_string(),
+            chain_id: config["chain_id"].as_u64().ok_or(SafeError::MissingConfig("chain_id"))?,
+            owner_keys,
+            threshold: config["threshold"].as_u64().unwrap_or(1) as usize,
+            mode: config["mode"].as_str().unwrap_or("propose").to_string(),
+        })
+    }
+
+    // Address holding the funds (and receiving trade outputs)
+    pub fn safe_address(&self) -> Address {
+        self.safe_address
+    }
+
+    // Wrap one or more calls into a single Safe transaction (MultiSend for batches)
+    pub async fn build_transaction(&self, calls: &[(Address, U256, Vec<u8>)]) -> Result<SafeTransaction, SafeError> {
+        let (to, value, data, operation) = if calls.len() == 1 {
+            let (to, value, data) = calls[0].clone();
+            (to, value, data, OPERATION_CALL)
+        } else {
+            (self.multisend_address, U256::zero(), encode_multisend(calls)?, OPERATION_DELEGATE_CALL)
+        };
+
+        // safeTxGas and gasPrice of zero make execTransaction revert when the inner call fails
+        // (Safe 1.3+), and leave the inner call all the gas the outer transaction's limit allows
+        Ok(SafeTransaction {
+            to,
+            value,
+            data,
+            operation,
+            safe_tx_gas: U256::zero(),
+            base_gas: U256::zero(),
+            gas_price: U256::zero(),
+            gas_token: Address::zero(),
+            refund_receiver: Address::zero(),
+            nonce: self.get_nonce().await?,
+        })
+    }
+
+    // Current Safe nonce
+    async fn get_nonce(&self) -> Result<U256, SafeError> {
+        let safe = Contract::from_json(self.web3.eth(), self.safe_address, include_bytes!("abi/gnosis_safe_abi.json"))?;
+        let nonce: U256 = safe.query("nonce", (), None, Options::default(), None).await?;
+        Ok(nonce)
+    }
+
+    // Sign the Safe transaction hash with every configured owner key, sorted by owner address
+    fn collect_signatures(&self, safe_tx_hash: H256) -> Result<Vec<(Address, Vec<u8>)>, SafeError> {
+        let mut signatures = Vec::new();
+        for key in &self.owner_keys {
+            let key_ref = SecretKeyRef::new(key);
+            let signature = key_ref
+                .sign(safe_tx_hash.as_bytes(), None)
+                .map_err(|_| SafeError::SigningFailed)?;
+
+            let mut bytes = Vec::with_capacity(65);
+            bytes.extend_from_slice(signature.r.as_bytes());
+            bytes.extend_from_slice(signature.s.as_bytes());
+            bytes.push(signature.v as u8);
+            signatures.push((key_ref.address(), bytes));
+        }
+
+        // The Safe requires signatures ordered by ascending owner address
+        signatures.sort_by_key(|(owner, _)| *owner);
+        Ok(signatures)
+    }
+
+    // Propose the transaction to the Safe transaction service for the other owners to confirm
+    pub async fn propose(&self, safe_tx: &SafeTransaction) -> Result<H256, SafeError> {
+        let safe_tx_hash = safe_tx.hash(self.safe_address, self.chain_id);
+        let (sender, signature) = self.collect_signatures(safe_tx_hash)?.remove(0);
+
+        let body = json!({
+            "to": safe_tx.to,
+            "value": safe_tx.value.to_string(),
+            "data": Bytes(safe_tx.data.clone()),
+            "operation": safe_tx.operation,
+            "safeTxGas": safe_tx.safe_tx_gas.to_string(),
+            "baseGas": safe_tx.base_gas.to_string(),
+            "gasPrice": safe_tx.gas_price.to_string(),
+            "gasToken": safe_tx.gas_token,
+            "refundReceiver": safe_tx.refund_receiver,
+            "nonce": safe_tx.nonce.as_u64(),
+            "contractTransactionHash": safe_tx_hash,
+            "sender": sender,
+            "signature": Bytes(signature),
+            "origin": "taz-bot",
+        });
+
+        let url = format!("{}/api/v1/safes/{:?}/multisig-transactions/", self.service_url, self.safe_address);
+        let response = self.client.post(&url).json(&body).send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(SafeError::ServiceError(format!("{}: {}", status, text)));
+        }
+
+        info!("Proposed Safe transaction {:?} (nonce {})", safe_tx_hash, safe_tx.nonce);
+        Ok(safe_tx_hash)
+    }
+
+    // Execute the transaction directly when enough owner keys are configured to meet the threshold.
+    // value is what the Safe sends across the batch, charged to the strategy with the executor's gas
+    pub async fn execute(&self, strategy: &str, safe_tx: &SafeTransaction, value: U256) -> Result<H256, SafeError> {
+        if self.owner_keys.len() < self.threshold {
+            return Err(SafeError::ThresholdNotMet(self.owner_keys.len(), self.threshold));
+        }
+
+        let safe_tx_hash = safe_tx.hash(self.safe_address, self.chain_id);
+        let signatures: Vec<u8> = self
+            .collect_signatures(safe_tx_hash)?
+            .into_iter()
+            .take(self.threshold)
+            .flat_map(|(_, signature)| signature)
+            .collect();
+
+        let safe_abi = ethabi::Contract::load(&include_bytes!("abi/gnosis_safe_abi.json")[..])?;
+        let data = safe_abi.function("execTransaction")?.encode_input(&[
+            Token::Address(safe_tx.to),
+            Token::Uint(safe_tx.value),
+            Token::Bytes(safe_tx.data.clone()),
+            Token::Uint(U256::from(safe_tx.operation)),
+            Token::Uint(safe_tx.safe_tx_gas),
+            Token::Uint(safe_tx.base_gas),
+            Token::Uint(safe_tx.gas_price),
+            Token::Address(safe_tx.gas_token),
+            Token::Address(safe_tx.refund_receiver),
+            Token::Bytes(signatures),
+        ])?;
+
+        // The first owner key pays gas for the execTransaction call. Its limit comes from simulating
+        // the call against the pending block, so a batch that would revert is never sent
+        let executor = &self.owner_keys[0];
+        let estimate = CallRequest {
+            from: Some(SecretKeyRef::new(executor).address()),
+            to: Some(self.safe_address),
+            data: Some(Bytes(data.clone())),
+            ..Default::default()
+        };
+        let gas = simulation::gas_limit(&self.web3, estimate).await?;
+        let fees = gas::suggest_fees(&self.web3, Urgency::for_strategy(strategy)).await?;
+        spend_caps::authorize(strategy, value, gas.saturating_mul(fees.max_fee))?;
+
+        let tx = TransactionParameters {
+            to: Some(self.safe_address),
+            gas,
+            data: Bytes(data),
+            chain_id: Some(self.chain_id),
+            transaction_type: Some(U64::from(2)),
+            max_fee_per_gas: Some(fees.max_fee),
+            max_priority_fee_per_gas: Some(fees.priority_fee.min(fees.max_fee)),
+            ..Default::default()
+        };
+        let signed = self.web3.accounts().sign_transaction(tx, executor).await?;
+        let tx_hash = self.web3.eth().send_raw_transaction(signed.raw_transaction).await?;
+
+        info!("Executed Safe transaction {:?} in tx {:?}", safe_tx_hash, tx_hash);
+        Ok(tx_hash)
+    }
+
+    // Whether submitted batches are executed on-chain, rather than proposed for the owners to sign
+    pub fn executes(&self) -> bool {
+        self.mode == "execute"
+    }
+
+    // Submit calls according to the configured mode ("propose" or "execute"), charging the
+    // strategy's spending cap first. Owners pay the gas of a proposal when they execute it
+    pub async fn submit_calls(&self, strategy: &str, calls: &[(Address, U256, Vec<u8>)]) -> Result<H256, SafeError> {
+        let safe_tx = self.build_transaction(calls).await?;
+        let value = calls.iter().fold(U256::zero(), |total, (_, value, _)| total.saturating_add(*value));
+        match self.mode.as_str() {
+            "execute" => self.execute(strategy, &safe_tx, value).await,
+            "propose" => {
+                spend_caps::authorize(strategy, value, U256::zero())?;
+                self.propose(&safe_tx).await
+            }
+            other => {
+                warn!("Unknown Safe mode {}, proposing instead", other);
+                spend_caps::authorize(strategy, value, U256::zero())?;
+                self.propose(&safe_tx).await
+            }
+        }
+    }
+}
+
+// Pack calls for MultiSendCallOnly: operation(uint8) | to(address) | value(uint256) | dataLength(uint256) | data
+fn encode_multisend(calls: &[(Address, U256, Vec<u8>)]) -> Result<Vec<u8>, SafeError> {
+    let mut packed = Vec::new();
+    for (to, value, data) in calls {
+        packed.push(OPERATION_CALL);
+        packed.extend_from_slice(to.as_bytes());
+
+        let mut word = [0u8; 32];
+        value.to_big_endian(&mut word);
+        packed.extend_from_slice(&word);
+        U256::from(data.len()).to_big_endian(&mut word);
+        packed.extend_from_slice(&word);
+        packed.extend_from_slice(data);
+    }
+
+    let multisend_abi = ethabi::Contract::load(&include_bytes!("abi/multisend_abi.json")[..])?;
+    let encoded = multisend_abi.function("multiSend")?.encode_input(&[Token::Bytes(packed)])?;
+    Ok(encoded)
+}
+
+// Custom error type for the Safe backend
+#[derive(Error, Debug)]
+pub enum SafeError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Contract error: {0}")]
+    ContractError(#[from] web3::contract::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("Gas error: {0}")]
+    GasError(#[from] GasError),
+    #[error("Simulation error: {0}")]
+    SimulationError(#[from] SimulationError),
+    #[error("Spend cap error: {0}")]
+    SpendCapError(#[from] SpendCapError),
+    #[error("Safe transaction service error: {0}")]
+    ServiceError(String),
+    #[error("Missing or invalid safe config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("Only {0} owner keys configured, threshold is {1}")]
+    ThresholdNotMet(usize, usize),
+    #[error("Failed to sign Safe transaction hash")]
+    SigningFailed,
+}
+
+// Implement conversion for SafeError to Web3 error
+impl From<SafeError> for web3::Error {
+    fn from(error: SafeError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,173 @@
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use web3::types::Address;
+use log::{error, warn};
+use thiserror::Error;
+use chrono::Utc;
+
+// Every screening decision is appended here for compliance review
+const DECISION_LOG_PATH: &str = "data/sanctions_screening.jsonl";
+
+// Load the sanctions screening configuration
+fn load_sanctions_config() -> Value {
+    let config_path = "config/sanctions_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read sanctions config file");
+    serde_json::from_str(&config_data).expect("Unable to parse sanctions config file")
+}
+
+// Outcome of screening one counterparty
+#[derive(Clone, Debug)]
+pub struct Screening {
+    pub address: Address,
+    pub flagged: bool,
+    pub source: String,
+    pub detail: Option<String>,
+}
+
+// Local list, reloaded when the file changes, and API answers cached for cache_ttl_secs
+#[derive(Default)]
+struct ScreeningCache {
+    list_path: String,
+    list_modified: Option<std::time::SystemTime>,
+    listed: HashSet<Address>,
+    api_results: HashMap<Address, (Instant, bool)>,
+}
+
+fn cache() -> &'static Mutex<ScreeningCache> {
+    static CACHE: OnceLock<Mutex<ScreeningCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(ScreeningCache::default()))
+}
+
+pub fn screening_enabled() -> bool {
+    load_sanctions_config()["enabled"].as_bool().unwrap_or(false)
+}
+
+// JSON array of addresses, e.g. an export of the OFAC SDN digital currency addresses
+fn listed(path: &str, address: Address) -> Result<bool, SanctionsError> {
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    let mut cache = cache().lock().unwrap();
+    if cache.list_path != path || cache.list_modified != modified || modified.is_none() {
+        let contents = fs::read_to_string(path).map_err(|e| SanctionsError::ListUnavailable(format!("{}: {}", path, e)))?;
+        let entries: Vec<String> = serde_json::from_str(&contents).map_err(|e| SanctionsError::ListUnavailable(format!("{}: {}", path, e)))?;
+        cache.listed = entries
+            .iter()
+            .filter_map(|entry| match Address::from_str(entry.trim()) {
+                Ok(address) => Some(address),
+                Err(_) => {
+                    warn!("Ignoring invalid sanctions list entry: {}", entry);
+                    None
+                }
+            })
+            .collect();
+        cache.list_path = path.to_string();
+        cache.list_modified = modified;
+    }
+    Ok(cache.listed.contains(&address))
+}
+
+// Chainalysis sanctions screening API: any identification means the address is sanctioned
+async fn chainalysis(config: &Value, address: Address) -> Result<bool, SanctionsError> {
+    let ttl = Duration::from_secs(config["cache_ttl_secs"].as_u64().unwrap_or(3600));
+    if let Some((at, flagged)) = cache().lock().unwrap().api_results.get(&address) {
+        if at.elapsed() < ttl {
+            return Ok(*flagged);
+        }
+    }
+
+    let api_key = config["chainalysis_api_key"].as_str().ok_or(SanctionsError::MissingConfig("chainalysis_api_key"))?;
+    let url = config["chainalysis_url"].as_str().unwrap_or("https://public.chainalysis.com/api/v1/address");
+    let response: Value = reqwest::Client::new()
+        .get(format!("{}/{:?}", url.trim_end_matches('/'), address))
+        .header("X-API-Key", api_key)
+        .header("Accept", "application/json")
+        .timeout(Duration::from_millis(config["api_timeout_ms"].as_u64().unwrap_or(2000)))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let flagged = response["identifications"].as_array().map(|ids| !ids.is_empty()).unwrap_or(false);
+    cache().lock().unwrap().api_results.insert(address, (Instant::now(), flagged));
+    Ok(flagged)
+}
+
+fn log_decision(screening: &Screening, context: &str) {
+    let _ = fs::create_dir_all("data");
+    let entry = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "address": format!("{:?}", screening.address),
+        "context": context,
+        "flagged": screening.flagged,
+        "source": screening.source,
+        "detail": screening.detail,
+    });
+    match OpenOptions::new().create(true).append(true).open(DECISION_LOG_PATH) {
+        Ok(mut decision_log) => {
+            if let Err(e) = writeln!(decision_log, "{}", entry) {
+                error!("Failed to write sanctions screening entry: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open sanctions screening log: {}", e),
+    }
+}
+
+// Screen one counterparty against the configured source and log the decision. When the source
+// cannot answer, fail_closed decides whether the counterparty is treated as flagged
+pub async fn screen(address: Address, context: &str) -> Screening {
+    let config = load_sanctions_config();
+    let source = config["source"].as_str().unwrap_or("file").to_string();
+
+    let result = match source.as_str() {
+        "file" => listed(config["list_path"].as_str().unwrap_or("config/sanctions_list.json"), address),
+        "chainalysis" => chainalysis(&config, address).await,
+        other => Err(SanctionsError::UnknownSource(other.to_string())),
+    };
+
+    let screening = match result {
+        Ok(flagged) => Screening { address, flagged, source, detail: None },
+        Err(e) => {
+            let fail_closed = config["fail_closed"].as_bool().unwrap_or(true);
+            warn!("Sanctions screening of {:?} failed ({}), treating as {}", address, e, if fail_closed { "flagged" } else { "clear" });
+            Screening { address, flagged: fail_closed, source, detail: Some(e.to_string()) }
+        }
+    };
+    log_decision(&screening, context);
+    screening
+}
+
+// Screen several counterparties of one opportunity; returns the first flagged one. Always clear when screening is disabled
+pub async fn first_flagged(addresses: &[Address], context: &str) -> Option<Screening> {
+    if !screening_enabled() {
+        return None;
+    }
+    for address in addresses {
+        if address.is_zero() {
+            continue;
+        }
+        let screening = screen(*address, context).await;
+        if screening.flagged {
+            return Some(screening);
+        }
+    }
+    None
+}
+
+// Custom error type for sanctions screening
+#[derive(Error, Debug)]
+pub enum SanctionsError {
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+    #[error("Sanctions list unavailable: {0}")]
+    ListUnavailable(String),
+    #[error("Unknown sanctions source: {0}")]
+    UnknownSource(String),
+}
@@ -1,14 +1,43 @@
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::fs;
-use web3::types::{U256, Address, TransactionRequest, H160};
-use web3::contract::{Contract, Options};
-use log::{error, info};
-use tokio::task;
+use web3::types::{U256, Address, Bytes, Transaction, TransactionId, TransactionRequest, H256};
+use web3::Transport;
+use log::{error, info, warn};
 use thiserror::Error;
 use tokio::time::{sleep, Duration};
 use chrono::Utc;
+use async_trait::async_trait;
 use web3::transports::WebSocket;
 use web3::futures::StreamExt;
+use tokio::time::timeout;
+use web3::ethabi::{self, ParamType, Token};
+use web3::signing::keccak256;
+use crate::modules::allocator;
+use crate::modules::cancellation_risk::{self, RaceInputs};
+use crate::modules::clustering;
+use crate::modules::config::{SandwichConfig, StrategyConfig};
+use crate::modules::deadlines::DecisionBudget;
+use crate::modules::execution::{Call, ExecutionError};
+use crate::modules::domain::OpportunitySource;
+use crate::modules::feed::{self, FeedConsumer, FeedError, Opportunity};
+use crate::modules::gas::{self, Urgency};
+use crate::modules::pricing::{self, PricingError};
+use crate::modules::inclusion::{self, InclusionFeatures, InclusionThresholds};
+use crate::modules::jit;
+use crate::modules::providers::{self, FailoverTransport};
+use crate::modules::relay::{self, Bundle, BundleOutcome, RelayClient, RelayError};
+use crate::modules::replay;
+use crate::modules::sanctions;
+use crate::modules::signer::{SignerError, TxSender};
+use crate::modules::size_buckets::{self, SizeBucket};
+use crate::modules::sizing;
+use crate::modules::storage;
+use crate::modules::strategy::{self, Strategy, StrategyError};
+use crate::modules::testnet;
+use crate::modules::venues::{self, VenueError};
+use crate::modules::victim_blacklist;
+use crate::modules::ws_metrics;
 
 // Load the sandwich configuration
 fn load_sandwich_config() -> Value {
@@ -17,7 +46,15 @@ fn load_sandwich_config() -> Value {
         .expect("Unable to read sandwich config file");
     let config: Value = serde_json::from_str(&config_data)
         .expect("Unable to parse sandwich config file");
-    config
+    testnet::apply(config_path, config)
+}
+
+// Pending transactions are watched over the monitoring websocket
+fn load_monitoring_config() -> Value {
+    let config_path = "config/monitoring_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read monitoring config file");
+    serde_json::from_str(&config_data).expect("Unable to parse monitoring config file")
 }
 
 // Dynamic flash loan calculation for sandwich attacks
@@ -28,35 +65,286 @@ pub fn calculate_dynamic_loan_amount(amount_in: U256, gas_fee: U256, slippage: f
     U256::from(max_loan_amount).max(min_profit)
 }
 
+// Token pair a victim swaps on, decoded from common V2 and V3 router calls
+pub fn victim_pair(input: &[u8]) -> Option<(Address, Address)> {
+    victim_swap(input, U256::zero()).map(|swap| (swap.token_in, swap.token_out))
+}
+
+// A decoded victim swap: the pair, how much of token_in it sells and the least it accepts back
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VictimSwap {
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: U256,
+    pub amount_out_min: U256,
+}
+
+// Decode a victim swap from common V2 and V3 router calls; ETH-in swaps sell the transaction value
+pub fn victim_swap(input: &[u8], value: U256) -> Option<VictimSwap> {
+    if input.len() < 4 {
+        return None;
+    }
+    let (selector, args) = input.split_at(4);
+    let matches = |signature: &str| selector == &keccak256(signature.as_bytes())[..4];
+    let path_ends = |path: &Token, amount_in: U256, amount_out_min: U256| match path {
+        Token::Array(hops) if hops.len() >= 2 => Some(VictimSwap {
+            token_in: hops[0].clone().into_address()?,
+            token_out: hops[1].clone().into_address()?,
+            amount_in,
+            // The minimum covers the whole path; on a multi-hop swap it doesn't bound the first hop
+            amount_out_min: if hops.len() == 2 { amount_out_min } else { U256::zero() },
+        }),
+        _ => None,
+    };
+    let path = ParamType::Array(Box::new(ParamType::Address));
+
+    if matches("swapExactETHForTokens(uint256,address[],address,uint256)") {
+        let tokens = ethabi::decode(&[ParamType::Uint(256), path, ParamType::Address, ParamType::Uint(256)], args).ok()?;
+        path_ends(&tokens[1], value, tokens[0].clone().into_uint()?)
+    } else if matches("swapExactTokensForTokens(uint256,uint256,address[],address,uint256)")
+        || matches("swapExactTokensForETH(uint256,uint256,address[],address,uint256)")
+    {
+        let tokens = ethabi::decode(&[ParamType::Uint(256), ParamType::Uint(256), path, ParamType::Address, ParamType::Uint(256)], args).ok()?;
+        path_ends(&tokens[2], tokens[0].clone().into_uint()?, tokens[1].clone().into_uint()?)
+    } else if matches("exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))") {
+        let params = ParamType::Tuple(vec![
+            ParamType::Address,
+            ParamType::Address,
+            ParamType::Uint(24),
+            ParamType::Address,
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Uint(160),
+        ]);
+        match ethabi::decode(&[params], args).ok()?.pop()? {
+            Token::Tuple(fields) => Some(VictimSwap {
+                token_in: fields[0].clone().into_address()?,
+                token_out: fields[1].clone().into_address()?,
+                amount_in: fields[5].clone().into_uint()?,
+                amount_out_min: fields[6].clone().into_uint()?,
+            }),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+// Everything the victim selection depends on, recorded so the decision can be replayed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VictimInputs {
+    pub hash: H256,
+    // Aggregate ETH-equivalent value when the transaction is part of a split-order cluster
+    pub value: U256,
+    #[serde(default = "single_transaction")]
+    pub cluster_size: usize,
+    pub min_tx_value: U256,
+    pub jit_value_multiplier: u64,
+    pub on_jit_pool: bool,
+    // Only fetched for victims that pass the value checks
+    pub features: Option<InclusionFeatures>,
+    pub thresholds: InclusionThresholds,
+    // Size bucket whose tip, front-run cap and profit threshold apply to this victim
+    #[serde(default)]
+    pub bucket: Option<SizeBucket>,
+}
+
+// Decisions recorded before clustering were always about a single transaction
+fn single_transaction() -> usize {
+    1
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VictimDecision {
+    pub target: bool,
+    pub reason: String,
+}
+
+// Pure victim selection: large enough, worth it on JIT-heavy pools, and likely to land next block
+pub fn decide_victim(inputs: &VictimInputs) -> VictimDecision {
+    let reject = |reason: &str| VictimDecision { target: false, reason: reason.to_string() };
+    if inputs.value < inputs.min_tx_value {
+        return reject("below_min_value");
+    }
+    // Victims trading on JIT-heavy pools rarely pay off, so they must be this many times larger
+    if inputs.on_jit_pool && inputs.value < inputs.min_tx_value * U256::from(inputs.jit_value_multiplier) {
+        return reject("jit_heavy_pool");
+    }
+    // Don't build around a victim that won't land soon
+    match &inputs.features {
+        None => reject("no_inclusion_features"),
+        Some(features) if !inclusion::likely_next_block_with(features, &inputs.thresholds) => reject("unlikely_next_block"),
+        Some(_) => VictimDecision { target: true, reason: String::from("target") },
+    }
+}
+
+// Value, JIT, inclusion and sanctions checks for one pending transaction; selected victims are
+// published to the shared feed for execution machines
+async fn select_victim<T: Transport>(
+    web3: &web3::Web3<T>,
+    transaction: &Transaction,
+    min_tx_value: U256,
+    jit_value_multiplier: u64,
+    thresholds: &InclusionThresholds,
+    buckets: &[SizeBucket],
+) -> bool {
+    // Victims matching a pattern whose sandwiches keep reverting aren't worth any of the checks below
+    if let Some(pattern) = victim_blacklist::is_blacklisted(transaction) {
+        info!("Skipping victim {:?}: matches blacklisted pattern {}", transaction.hash, pattern);
+        return false;
+    }
+    // A small swap may be one slice of a split order: judge it by its sender's cluster on the pair
+    let cluster = clustering::observe(transaction).filter(|cluster| cluster.len() > 1);
+    let value = cluster.as_ref().map(|cluster| cluster.total_value.max(transaction.value)).unwrap_or(transaction.value);
+
+    // Check the transaction (or cluster) value
+    if value < min_tx_value {
+        return false;
+    }
+    // With size buckets configured, only victims inside one are targeted
+    let bucket = size_buckets::bucket_for(buckets, value).cloned();
+    if !buckets.is_empty() && bucket.is_none() {
+        return false;
+    }
+    // From here on the victim is worth deciding on, within the strategy's per-block budget
+    let budget = DecisionBudget::start("sandwich", Some(transaction.hash));
+    let mut inputs = VictimInputs {
+        hash: transaction.hash,
+        value,
+        cluster_size: cluster.as_ref().map(|cluster| cluster.len()).unwrap_or(1),
+        min_tx_value,
+        jit_value_multiplier,
+        on_jit_pool: victim_pair(&transaction.input.0)
+            .map(|(token_in, token_out)| jit::is_jit_pair(token_in, token_out))
+            .unwrap_or(false),
+        features: None,
+        thresholds: thresholds.clone(),
+        bucket,
+    };
+    // Inclusion features cost RPC calls, so only fetch them once the value checks pass
+    if decide_victim(&inputs).reason == "no_inclusion_features" {
+        match budget.stage("inclusion_features", inclusion::extract_features(web3, transaction)).await {
+            Ok(Ok(features)) => inputs.features = Some(features),
+            Ok(Err(e)) => error!("Failed to extract inclusion features for {:?}: {}", transaction.hash, e),
+            Err(_) => return false,
+        }
+    }
+
+    let decision = decide_victim(&inputs);
+    if replay::recording_enabled() {
+        if let Ok(block) = web3.eth().block_number().await {
+            replay::record_decision("sandwich_victim", block.as_u64(), json!(inputs), json!(decision));
+        }
+    }
+    if !decision.target {
+        budget.finish();
+        info!("Skipping victim {:?} ({}), Value: {:?}", transaction.hash, decision.reason, transaction.value);
+        return false;
+    }
+    // Don't build a bundle around a sanctioned sender or contract
+    let counterparties = [transaction.from, transaction.to].into_iter().flatten().collect::<Vec<_>>();
+    match budget.stage("sanctions", sanctions::first_flagged(&counterparties, "sandwich_victim")).await {
+        Ok(Some(screening)) => {
+            budget.finish();
+            info!("Skipping victim {:?}: counterparty {:?} is flagged ({})", transaction.hash, screening.address, screening.source);
+            return false;
+        }
+        Ok(None) => budget.finish(),
+        Err(_) => return false,
+    }
+    info!(
+        "Detected large transaction: {:?}, Value: {:?}",
+        transaction.from, transaction.value
+    );
+    if let Some(bucket) = &inputs.bucket {
+        info!("Victim {:?} is in the {} bucket: tip {}%, front-run up to {:?}", transaction.hash, bucket.name, bucket.tip_pct * 100.0, bucket.max_front_run);
+    }
+    // A cluster is targeted as a whole: the backrun follows its latest slice
+    let details = match &cluster {
+        Some(cluster) => {
+            info!("Targeting split order of {} swaps from {:?}, aggregate value {:?}", cluster.len(), cluster.sender, cluster.total_value);
+            json!({ "from": transaction.from, "to": transaction.to, "value": transaction.value, "inputs": inputs, "cluster": cluster.to_json() })
+        }
+        None => json!({ "from": transaction.from, "to": transaction.to, "value": transaction.value, "inputs": inputs }),
+    };
+    feed::publish_opportunity(Opportunity {
+        strategy: String::from("sandwich"),
+        target_hash: Some(cluster.as_ref().and_then(|cluster| cluster.latest()).unwrap_or(transaction.hash)),
+        block: None,
+        details,
+        detected_at_ms: Utc::now().timestamp_millis(),
+        legs: Vec::new(),
+        expected_profit: None,
+        source: OpportunitySource::PublicMempool,
+    })
+    .await;
+    true
+}
+
+// Same selection over pending transactions published by a detection machine
+async fn monitor_feed_for_large_transactions<T: Transport>(
+    web3: &web3::Web3<T>,
+    min_tx_value: U256,
+    jit_value_multiplier: u64,
+    thresholds: &InclusionThresholds,
+    buckets: &[SizeBucket],
+) -> Result<Transaction, SandwichError> {
+    let mut consumer = FeedConsumer::pending("sandwich").await?;
+    loop {
+        for pending in consumer.next_pending().await? {
+            ws_metrics::record_event("pending_transactions", None);
+            let transaction = pending.to_transaction();
+            if select_victim(web3, &transaction, min_tx_value, jit_value_multiplier, thresholds, buckets).await {
+                return Ok(transaction);
+            }
+        }
+    }
+}
+
 // Real-time monitoring of the mempool for large trades
 pub async fn monitor_mempool_for_large_transactions(
     websocket_url: &str,
     min_tx_value: U256
-) -> Result<H160, SandwichError> {
+) -> Result<Transaction, SandwichError> {
     info!("Monitoring mempool for large transactions...");
+    let config = load_sandwich_config();
+    let jit_value_multiplier = config["jit_value_multiplier"].as_u64().unwrap_or(3);
+    let thresholds = InclusionThresholds::from_config();
+    let buckets = size_buckets::from_config(&config);
 
     // Initialize a WebSocket connection to listen to pending transactions
     let websocket = WebSocket::new(websocket_url).await?;
     let web3 = web3::Web3::new(websocket);
 
+    // Pending transactions come from the shared feed when detection runs on another machine
+    if feed::consume_pending_enabled() {
+        return monitor_feed_for_large_transactions(&web3, min_tx_value, jit_value_multiplier, &thresholds, &buckets).await;
+    }
+
     // Subscribe to pending transactions
     let mut pending_tx_stream = web3.eth_subscribe().subscribe_new_pending_transactions().await?;
 
-    // Loop over the pending transactions
-    while let Some(pending_tx) = pending_tx_stream.next().await {
+    // Loop over the pending transactions, treating a silent stream as stalled so the caller reconnects
+    let max_silence = Duration::from_secs(config["max_silence_secs"].as_u64().unwrap_or(30));
+    loop {
+        let pending_tx = match timeout(max_silence, pending_tx_stream.next()).await {
+            Ok(Some(pending_tx)) => pending_tx,
+            Ok(None) => break,
+            Err(_) => {
+                error!("No pending transactions for {}s, subscription looks stalled", max_silence.as_secs());
+                ws_metrics::record_reconnect("pending_transactions");
+                return Err(SandwichError::StreamStalled);
+            }
+        };
+
         match pending_tx {
             Ok(tx_hash) => {
+                ws_metrics::record_event("pending_transactions", None);
                 // Fetch the transaction details
-                if let Ok(tx) = web3.eth().transaction(TransactionRequest::new().hash(tx_hash)).await {
-                    if let Some(transaction) = tx {
-                        // Check the transaction value
-                        if transaction.value >= min_tx_value {
-                            info!(
-                                "Detected large transaction: {:?}, Value: {:?}",
-                                transaction.from, transaction.value
-                            );
-                            return Ok(transaction.from); // Return the sender address of the large transaction
-                        }
+                if let Ok(Some(transaction)) = web3.eth().transaction(TransactionId::Hash(tx_hash)).await {
+                    if select_victim(&web3, &transaction, min_tx_value, jit_value_multiplier, &thresholds, &buckets).await {
+                        return Ok(transaction); // Return the large transaction itself
                     }
                 }
             }
@@ -76,85 +364,169 @@ pub fn is_profitable(flashloan_amount: U256, gas_fee: U256, expected_profit: U25
     expected_profit > (flashloan_amount + gas_fee)
 }
 
-// Request a flash loan
-pub async fn request_flash_loan(
-    web3: web3::Web3<web3::transports::Http>,
-    amount: U256
-) -> Result<(), SandwichError> {
-    let aave_flashloan_contract = Contract::from_json(
-        web3.eth(),
-        "AAVE_FLASHLOAN_CONTRACT_ADDRESS".parse().unwrap(),
-        include_bytes!("abi/aave_flashloan_abi.json"),
-    )?;
-
-    let params = (
-        vec!["TOKEN_ADDRESS".parse().unwrap()],
-        vec![amount],
-        vec![0],
-        "SENDER_ADDRESS".parse().unwrap(),
-        vec![0u8],
-    );
-
-    aave_flashloan_contract
-        .call("flashLoan", params, Address::zero(), Options::default())
-        .await?;
-
-    Ok(())
-}
-
-// Execute sandwich attack across multiple DEXs
+// Execute a sandwich around a victim swap: buy the victim's output token ahead of it on the venue it
+// trades through, sell it back after it on whichever configured DEX quotes best. With private_relay on, the legs and the victim go
+// to the relay as one bundle so the legs can't be sandwiched in turn; otherwise they are sent as
+// public transactions
 pub async fn execute_sandwich_attack(
-    web3: web3::Web3<web3::transports::Http>,
-    flashloan_amount: U256
+    web3: web3::Web3<FailoverTransport>,
+    victim: Transaction,
+    front_run_amount: U256
 ) -> Result<(), SandwichError> {
     let config = load_sandwich_config();
-    let uniswap_router_address: Address = config["uniswap_router_address"].as_str().unwrap().parse().expect("Invalid address");
-    let sushiswap_router_address: Address = config["sushiswap_router_address"].as_str().unwrap().parse().expect("Invalid address");
-
-    let uniswap_router_contract = Contract::from_json(
-        web3.eth(),
-        uniswap_router_address,
-        include_bytes!("abi/uniswap_router_abi.json")
-    )?;
-
-    let sushiswap_router_contract = Contract::from_json(
-        web3.eth(),
-        sushiswap_router_address,
-        include_bytes!("abi/sushiswap_router_abi.json")
-    )?;
-
-    let path = vec!["TOKEN_IN_ADDRESS".parse().unwrap(), "TOKEN_OUT_ADDRESS".parse().unwrap()];
-    let recipient = "SENDER_ADDRESS".parse().unwrap();
+    let swap = victim_swap(&victim.input.0, victim.value).ok_or(SandwichError::UndecodableVictim(victim.hash))?;
+    let front_run_amount = allocator::cap_to_allocation(&web3, "sandwich", swap.token_in, front_run_amount).await;
+
+    // The front-run has to move the pool the victim trades on, so prefer the venue behind the victim's
+    // router; the best quote otherwise. Testnet presets drop DEXes that aren't deployed there, and
+    // without a venue for both legs there is no sandwich
+    let quotes = venues::quote_all_venues(&web3, swap.token_in, swap.token_out, front_run_amount).await?;
+    let victim_venue = quotes.iter().find(|(venue, _)| Some(venue.router) == victim.to).cloned();
+    let (front_venue, mut bought) = match victim_venue.or_else(|| quotes.into_iter().max_by_key(|(_, amount_out)| *amount_out)) {
+        Some(quote) => quote,
+        None => {
+            warn!("No configured venue trades {:?} -> {:?}, skipping sandwich", swap.token_in, swap.token_out);
+            return Ok(());
+        }
+    };
+    // The back-run sells what the front-run is quoted to buy
+    let (back_venue, mut expected_back) = match venues::best_quote(&web3, swap.token_out, swap.token_in, bought).await? {
+        Some(quote) => quote,
+        None => {
+            warn!("No configured venue trades {:?} -> {:?}, skipping sandwich", swap.token_out, swap.token_in);
+            return Ok(());
+        }
+    };
+    // The back-run can't be estimated before the front-run has landed, so every leg gets a fixed limit
+    let leg_gas = U256::from(config["leg_gas_limit"].as_u64().unwrap_or(300_000));
+    // Size the front-run from both pools' reserves, up to the requested amount and no further than
+    // the victim's minimum output allows; Curve pools and failed reads keep the requested amount
+    let mut front_run_amount = front_run_amount;
+    if sizing::enabled() {
+        let gas_cost = pricing::gas_cost_in_token(&web3, swap.token_in, leg_gas * 2, Urgency::for_strategy("sandwich")).await?;
+        match sizing::size_front_run(&web3, &front_venue, &back_venue, &swap, front_run_amount, gas_cost).await {
+            Ok(Some(sized)) => {
+                front_run_amount = sized.amount_in;
+                bought = front_venue.quote(&web3, swap.token_in, swap.token_out, front_run_amount).await?;
+                expected_back = sized.amount_back;
+                info!("Sized front-run around {:?} at {:?}, modelled profit {:?}", victim.hash, front_run_amount, sized.profit);
+            }
+            Ok(None) => {
+                info!("No front-run size around {:?} clears the premium and gas, skipping sandwich", victim.hash);
+                return Ok(());
+            }
+            Err(e) => warn!("Could not size the front-run around {:?}, using {:?}: {}", victim.hash, front_run_amount, e),
+        }
+    }
+    info!("Sandwiching {:?}: front-run on {}, back-run on {}", victim.hash, front_venue.label(), back_venue.label());
+
+    let sender = TxSender::from_config(&web3).await?;
+    let recipient = sender.address();
     let deadline = U256::from(Utc::now().timestamp() + 600);
 
-    // **Front-running transaction**
-    let front_run_tx = uniswap_router_contract
-        .call("swapExactTokensForTokens", (flashloan_amount, U256::from(1), path.clone(), recipient, deadline), Options::default(), None)
-        .await?;
+    // Both legs are priced at the strategy's urgency; without fee history the signer fills them in
+    let fees = gas::suggest_fees(&web3, Urgency::for_strategy("sandwich")).await.ok();
+    let front_calls = front_venue.swap_calls(swap.token_in, swap.token_out, front_run_amount, U256::from(1), recipient, deadline)?;
+    let back_calls = back_venue.swap_calls(swap.token_out, swap.token_in, bought, U256::from(1), recipient, deadline)?;
+
+    // A bundle pays the victim's size bucket tip as extra priority fee on every leg. A victim that is
+    // replaced before the bundle lands invalidates it, so the tip is scaled by the chance it survives
+    // and bundles that are marginal once that risk is priced in aren't sent
+    let mut tip_per_gas = U256::zero();
+    if relay::enabled() {
+        let gas_units = leg_gas * U256::from(front_calls.len() + back_calls.len());
+        let expected_profit = pricing::token_to_wei(&web3, swap.token_in, expected_back.saturating_sub(front_run_amount)).await?;
+        let gas_cost = gas_units.saturating_mul(fees.map(|fees| fees.max_fee).unwrap_or_default());
+        let buckets = size_buckets::from_config(&config);
+        let tip = size_buckets::bucket_for(&buckets, victim.value).map_or(U256::zero(), |bucket| bucket.tip(expected_profit));
+        let inputs = RaceInputs::new(cancellation_risk::estimate(&web3, &victim).await?, expected_profit, gas_cost, tip);
+        let decision = cancellation_risk::decide(&inputs);
+        if replay::recording_enabled() {
+            if let Ok(block) = web3.eth().block_number().await {
+                replay::record_decision("sandwich_race", block.as_u64(), json!(inputs), json!(decision));
+            }
+        }
+        if !decision.submit {
+            info!("Skipping bundle around {:?} ({}), replacement probability {:.2}", victim.hash, decision.reason, inputs.risk.probability);
+            return Ok(());
+        }
+        tip_per_gas = decision.tip / gas_units.max(U256::one());
+    }
 
-    info!("Front-running transaction executed: {:?}", front_run_tx);
+    let to_request = |call: Call| TransactionRequest {
+        from: recipient,
+        to: Some(call.to),
+        gas: Some(leg_gas),
+        value: Some(call.value),
+        data: Some(Bytes(call.data)),
+        max_fee_per_gas: fees.map(|fees| fees.max_fee + tip_per_gas),
+        max_priority_fee_per_gas: fees.map(|fees| fees.priority_fee + tip_per_gas),
+        ..Default::default()
+    };
+    let front_run: Vec<TransactionRequest> = front_calls.into_iter().map(to_request).collect();
+    let back_run: Vec<TransactionRequest> = back_calls.into_iter().map(to_request).collect();
+
+    if relay::enabled() {
+        // A victim the node can't hand over was sent privately; the relay rejecting a bundle or
+        // leaving it out means it reverted. Both count against the victim's patterns
+        let bundle = match Bundle::around(&web3, &sender, "sandwich", front_run, victim.hash, back_run).await {
+            Ok(bundle) => bundle,
+            Err(e @ RelayError::TargetUnavailable(_)) => {
+                victim_blacklist::record_attempt(&victim, true);
+                return Err(e.into());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let outcome = RelayClient::from_config()?.submit(&web3, "sandwich", &bundle).await;
+        match &outcome {
+            Ok(BundleOutcome::Included { .. }) => victim_blacklist::record_attempt(&victim, false),
+            Ok(BundleOutcome::NotIncluded { .. }) | Err(RelayError::RelayRejected(_)) => victim_blacklist::record_attempt(&victim, true),
+            Err(_) => {}
+        }
+        return match outcome? {
+            BundleOutcome::Included { block, tx_hashes } => {
+                info!("Sandwich bundle around {:?} included in block {}", victim.hash, block);
+                allocator::record_realized(&web3, "sandwich", OpportunitySource::PublicMempool, recipient, &[swap.token_in, swap.token_out], &tx_hashes).await;
+                Ok(())
+            }
+            BundleOutcome::NotIncluded { .. } => Err(SandwichError::BundleNotIncluded(victim.hash)),
+        };
+    }
 
-    // **Back-running transaction**
-    let back_run_tx = sushiswap_router_contract
-        .call("swapExactTokensForTokens", (flashloan_amount, U256::from(1), path, recipient, deadline), Options::default(), None)
-        .await?;
+    let mut sent = Vec::new();
 
-    info!("Back-running transaction executed: {:?}", back_run_tx);
+    // **Front-running transaction**
+    for request in front_run {
+        let front_run_tx = sender.send("sandwich", request).await?;
+        storage::record_transaction("sandwich", front_run_tx, "signed");
+        info!("Front-running transaction executed: {:?}", front_run_tx);
+        sent.push(front_run_tx);
+    }
 
+    // **Back-running transaction**
+    for request in back_run {
+        let back_run_tx = sender.send("sandwich", request).await?;
+        storage::record_transaction("sandwich", back_run_tx, "signed");
+        info!("Back-running transaction executed: {:?}", back_run_tx);
+        sent.push(back_run_tx);
+    }
+
+    allocator::record_realized(&web3, "sandwich", OpportunitySource::PublicMempool, recipient, &[swap.token_in, swap.token_out], &sent).await;
     Ok(())
 }
 
 // Retry logic for sandwich attacks
 pub async fn execute_sandwich_attack_with_retry(
-    web3: web3::Web3<web3::transports::Http>,
-    flashloan_amount: U256,
+    web3: web3::Web3<FailoverTransport>,
+    victim: Transaction,
+    front_run_amount: U256,
     max_retries: u8
 ) -> Result<(), SandwichError> {
     let mut attempts = 0;
     let mut delay = 1;
 
     while attempts < max_retries {
-        let result = execute_sandwich_attack(web3.clone(), flashloan_amount).await;
+        let result = execute_sandwich_attack(web3.clone(), victim.clone(), front_run_amount).await;
         match result {
             Ok(_) => return Ok(()),
             Err(e) => {
@@ -169,25 +541,49 @@ pub async fn execute_sandwich_attack_with_retry(
     Err(SandwichError::RetriesExceeded)
 }
 
-// Repay flash loan
-pub async fn repay_flash_loan(
-    web3: web3::Web3<web3::transports::Http>,
-    flashloan_amount: U256
-) -> Result<(), SandwichError> {
-    let aave_flashloan_contract = Contract::from_json(
-        web3.eth(),
-        "AAVE_FLASHLOAN_CONTRACT_ADDRESS".parse().unwrap(),
-        include_bytes!("abi/aave_flashloan_abi.json"),
-    )?;
+// Sandwiching large pending swaps under the orchestrator
+pub struct SandwichStrategy {
+    settings: SandwichConfig,
+}
 
-    let repay_amount = flashloan_amount + (flashloan_amount / U256::from(1000)); 
+impl SandwichStrategy {
+    pub fn new(settings: SandwichConfig) -> Self {
+        SandwichStrategy { settings }
+    }
+}
 
-    aave_flashloan_contract
-        .call("repay", ("TOKEN_ADDRESS".parse().unwrap(), repay_amount, "SENDER_ADDRESS".parse().unwrap()), Options::default(), None)
-        .await?;
+#[async_trait]
+impl Strategy for SandwichStrategy {
+    fn name(&self) -> &'static str {
+        "sandwich"
+    }
 
-    info!("Flash loan repaid: {:?}", repay_amount);
-    Ok(())
+    fn config(&self) -> Value {
+        self.settings.to_value()
+    }
+
+    // Waits on the pending transaction stream until a victim qualifies; a stalled stream or one that
+    // ends without a victim just ends the scan so the next one reconnects
+    async fn scan(&self, _web3: &web3::Web3<FailoverTransport>) -> Result<Vec<strategy::Opportunity>, StrategyError> {
+        let monitoring_config = load_monitoring_config();
+        let configured_url = monitoring_config["websocket_url"].as_str().expect("WebSocket URL not found");
+        let websocket_url = providers::websocket_url_for("sandwich", configured_url);
+        match monitor_mempool_for_large_transactions(&websocket_url, self.settings.min_victim_value).await {
+            Ok(victim) => Ok(vec![strategy::Opportunity {
+                amount: self.settings.min_victim_value,
+                target: victim.from,
+                transaction: Some(victim),
+                ..Default::default()
+            }]),
+            Err(SandwichError::NoLargeTrades) | Err(SandwichError::StreamStalled) => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn execute(&self, web3: &web3::Web3<FailoverTransport>, opportunity: strategy::Opportunity) -> Result<(), StrategyError> {
+        let victim = opportunity.transaction.ok_or(StrategyError::IncompleteOpportunity("victim transaction"))?;
+        Ok(execute_sandwich_attack_with_retry(web3.clone(), victim, opportunity.amount, 3).await?)
+    }
 }
 
 // Define errors for the sandwich attack process
@@ -203,8 +599,26 @@ pub enum SandwichError {
     RetriesExceeded,
     #[error("No large trades detected")]
     NoLargeTrades,
+    #[error("Pending transaction subscription stalled")]
+    StreamStalled,
     #[error("Join error: {0}")]
     JoinError(#[from] tokio::task::JoinError),
+    #[error("Feed error: {0}")]
+    FeedError(#[from] FeedError),
+    #[error("Execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("Venue error: {0}")]
+    VenueError(#[from] VenueError),
+    #[error("Signer error: {0}")]
+    SignerError(#[from] SignerError),
+    #[error("Pricing error: {0}")]
+    PricingError(#[from] PricingError),
+    #[error("Relay error: {0}")]
+    RelayError(#[from] RelayError),
+    #[error("Victim {0:?} is not a swap we can decode")]
+    UndecodableVictim(H256),
+    #[error("Bundle around {0:?} was not included")]
+    BundleNotIncluded(H256),
 }
 
 // Convert SandwichError to Web3 error
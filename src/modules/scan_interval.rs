@@ -0,0 +1,75 @@
+use serde_json::Value;
+use std::collections::VecDeque;
+use tokio::time::Duration;
+use log::info;
+
+// Scan interval that tightens while opportunities are being found and backs off when quiet
+pub struct AdaptiveInterval {
+    name: String,
+    current: Duration,
+    min: Duration,
+    max: Duration,
+    window: usize,
+    tighten_above: f64,
+    recent: VecDeque<bool>,
+}
+
+impl AdaptiveInterval {
+    // Bounds come from the strategy config, falling back to the fixed check_interval
+    pub fn from_config(name: &str, config: &Value, check_interval: u64) -> AdaptiveInterval {
+        let base_ms = check_interval.max(1) * 1000;
+        let min_ms = config["min_scan_interval_ms"].as_u64().unwrap_or(base_ms / 4).max(1);
+        let max_ms = config["max_scan_interval_ms"].as_u64().unwrap_or(base_ms * 4).max(min_ms);
+
+        AdaptiveInterval {
+            name: name.to_string(),
+            current: Duration::from_millis(base_ms.clamp(min_ms, max_ms)),
+            min: Duration::from_millis(min_ms),
+            max: Duration::from_millis(max_ms),
+            window: config["hit_rate_window"].as_u64().unwrap_or(20).max(1) as usize,
+            tighten_above: config["tighten_above_hit_rate"].as_f64().unwrap_or(0.1),
+            recent: VecDeque::new(),
+        }
+    }
+
+    // Record whether the last scan found an opportunity and return the delay before the next one
+    pub fn record(&mut self, hit: bool) -> Duration {
+        self.recent.push_back(hit);
+        while self.recent.len() > self.window {
+            self.recent.pop_front();
+        }
+
+        let previous = self.current;
+        let hit_rate = self.hit_rate();
+        if hit || hit_rate >= self.tighten_above {
+            // Volatile period: halve the interval towards the floor
+            self.current = (self.current / 2).max(self.min);
+        } else if hit_rate == 0.0 && self.recent.len() == self.window {
+            // A full window without hits: back off by half again towards the ceiling
+            self.current = (self.current * 3 / 2).min(self.max);
+        }
+
+        if self.current != previous {
+            info!(
+                "{} scan interval {}ms -> {}ms (hit rate {:.2} over last {} scans)",
+                self.name,
+                previous.as_millis(),
+                self.current.as_millis(),
+                hit_rate,
+                self.recent.len()
+            );
+        }
+        self.current
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        if self.recent.is_empty() {
+            return 0.0;
+        }
+        self.recent.iter().filter(|hit| **hit).count() as f64 / self.recent.len() as f64
+    }
+
+    pub fn current(&self) -> Duration {
+        self.current
+    }
+}
@@ -0,0 +1,206 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use web3::types::{Address, BlockNumber, Bytes, CallRequest};
+use log::{error, info, warn};
+use thiserror::Error;
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::code_watch::{self, CodeWatchError};
+use crate::modules::control;
+use crate::modules::ens;
+use crate::modules::multicall;
+use crate::modules::providers::FailoverTransport;
+
+// PUSH1 opcode; PUSHn is PUSH1 + n - 1
+const PUSH1: u8 = 0x60;
+
+// Load the selector check configuration
+fn load_selector_check_config() -> Value {
+    let config_path = "config/selector_check_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read selector check config file");
+    serde_json::from_str(&config_data).expect("Unable to parse selector check config file")
+}
+
+// A function we intend to call on a contract, and how to confirm it exists
+struct ExpectedFunction {
+    signature: String,
+    // Confirm with an argument-less eth_call instead of the bytecode, for views behind
+    // proxies whose dispatch the bytecode doesn't show
+    call: bool,
+}
+
+struct CheckedContract {
+    name: String,
+    address: Address,
+    strategies: Vec<String>,
+    functions: Vec<ExpectedFunction>,
+}
+
+// Outcome for one contract, kept for the dashboard
+#[derive(Clone, Debug, Serialize)]
+pub struct SelectorReport {
+    pub name: String,
+    pub address: Address,
+    pub missing: Vec<String>,
+    // Set when the contract couldn't be checked at all (no code, beacon proxy, RPC failure)
+    pub unverified: Option<String>,
+}
+
+fn reports() -> &'static Mutex<Vec<SelectorReport>> {
+    static REPORTS: OnceLock<Mutex<Vec<SelectorReport>>> = OnceLock::new();
+    REPORTS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+// The contract address, given directly or as a field of the strategy config that holds it
+fn contract_address(entry: &Value) -> Option<Address> {
+    if let Some(address) = entry["address"].as_str() {
+        return ens::parse_address(address);
+    }
+    let path = entry["config"].as_str()?;
+    let config: Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    config[entry["field"].as_str()?].as_str().and_then(ens::parse_address)
+}
+
+fn checked_contracts(config: &Value) -> Vec<CheckedContract> {
+    let names = |value: &Value| -> Vec<String> {
+        value.as_array().map(|list| list.iter().filter_map(|name| name.as_str().map(String::from)).collect()).unwrap_or_default()
+    };
+    config["contracts"]
+        .as_array()
+        .map(|contracts| {
+            contracts
+                .iter()
+                .filter_map(|entry| {
+                    let name = entry["name"].as_str().unwrap_or("unnamed").to_string();
+                    let address = contract_address(entry);
+                    if address.is_none() {
+                        warn!("Skipping selector check for {}: no valid address", name);
+                    }
+                    let functions = entry["functions"]
+                        .as_array()
+                        .map(|list| {
+                            list.iter()
+                                .filter_map(|function| match function {
+                                    Value::String(signature) => Some(ExpectedFunction { signature: signature.clone(), call: false }),
+                                    _ => Some(ExpectedFunction {
+                                        signature: function["signature"].as_str()?.to_string(),
+                                        call: function["call"].as_bool().unwrap_or(false),
+                                    }),
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    Some(CheckedContract { name, address: address?, strategies: names(&entry["strategies"]), functions })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Whether the dispatcher pushes the selector. Solidity drops leading zero bytes, so a selector like
+// 0x00fdd58e is pushed with PUSH3
+fn code_has_selector(code: &[u8], selector: [u8; 4]) -> bool {
+    let significant = selector.iter().position(|byte| *byte != 0).unwrap_or(3);
+    let pushed = &selector[significant..];
+    let opcode = PUSH1 + pushed.len() as u8 - 1;
+    code.windows(pushed.len() + 1).any(|window| window[0] == opcode && &window[1..] == pushed)
+}
+
+// Whether an argument-less call to the function returns without reverting
+async fn call_succeeds(web3: &web3::Web3<FailoverTransport>, address: Address, selector: [u8; 4]) -> bool {
+    let request = CallRequest { to: Some(address), data: Some(Bytes(selector.to_vec())), ..Default::default() };
+    web3.eth().call(request, Some(BlockNumber::Latest.into())).await.is_ok()
+}
+
+// Check one contract's expected functions against its code, or its EIP-1967 implementation's code
+async fn check_contract(web3: &web3::Web3<FailoverTransport>, contract: &CheckedContract) -> Result<Vec<String>, SelectorCheckError> {
+    let fingerprint = code_watch::fingerprint(web3, contract.address).await?;
+    let code_address = if fingerprint.implementation.is_zero() { contract.address } else { fingerprint.implementation };
+    let code = web3.eth().code(code_address, None).await?;
+
+    let mut missing = Vec::new();
+    for function in &contract.functions {
+        let selector = multicall::selector(&function.signature);
+        let found = if function.call {
+            call_succeeds(web3, contract.address, selector).await
+        } else {
+            code_has_selector(&code.0, selector)
+        };
+        if !found {
+            missing.push(function.signature.clone());
+        }
+    }
+    // A beacon proxy's code is elsewhere; don't call its selectors missing on the proxy's own bytecode
+    if !missing.is_empty() && !fingerprint.beacon.is_zero() && contract.functions.iter().any(|function| !function.call) {
+        return Err(SelectorCheckError::BeaconProxy(fingerprint.beacon));
+    }
+    Ok(missing)
+}
+
+// Confirm every configured contract exposes the functions the bot calls on it. A contract missing
+// any of them has its strategies paused, so the mismatch surfaces at startup instead of as reverts
+pub async fn verify_selectors(web3: &web3::Web3<FailoverTransport>) -> Vec<SelectorReport> {
+    let config = load_selector_check_config();
+    let pause = config["pause_on_missing"].as_bool().unwrap_or(true);
+    let mut results = Vec::new();
+
+    for contract in checked_contracts(&config) {
+        let report = match check_contract(web3, &contract).await {
+            Ok(missing) => SelectorReport { name: contract.name.clone(), address: contract.address, missing, unverified: None },
+            Err(e) => {
+                warn!("Could not verify selectors on {} ({:?}): {}", contract.name, contract.address, e);
+                SelectorReport { name: contract.name.clone(), address: contract.address, missing: Vec::new(), unverified: Some(e.to_string()) }
+            }
+        };
+        if !report.missing.is_empty() {
+            error!("{} ({:?}) does not expose {}", contract.name, contract.address, report.missing.join(", "));
+            if pause {
+                let reason = format!("{} is missing {}", contract.name, report.missing.join(", "));
+                for strategy in &contract.strategies {
+                    control::pause_strategy(strategy, &reason);
+                }
+            }
+        }
+        results.push(report);
+    }
+
+    let failed: Vec<&SelectorReport> = results.iter().filter(|report| !report.missing.is_empty()).collect();
+    if failed.is_empty() {
+        info!("Selector check passed for {} contracts", results.len());
+    } else if config["alert"].as_bool().unwrap_or(true) {
+        let body = failed
+            .iter()
+            .map(|report| format!("{} ({:?}): {}", report.name, report.address, report.missing.join(", ")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        alerts::raise("selector_check", Severity::Critical, &format!("{} contracts are missing expected functions", failed.len()), &body);
+    }
+    *reports().lock().unwrap() = results.clone();
+    results
+}
+
+// Last startup check, for the dashboard
+pub fn snapshot() -> Value {
+    json!({ "contracts": reports().lock().unwrap().clone() })
+}
+
+// Custom error type for the selector check
+#[derive(Error, Debug)]
+pub enum SelectorCheckError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Code watch error: {0}")]
+    CodeWatchError(#[from] CodeWatchError),
+    #[error("Beacon proxy (beacon {0:?}); its implementation isn't read from a slot")]
+    BeaconProxy(Address),
+}
+
+// Implement conversion for SelectorCheckError to Web3 error
+impl From<SelectorCheckError> for web3::Error {
+    fn from(error: SelectorCheckError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,299 @@
+use base64::Engine;
+use serde_json::Value;
+use std::sync::OnceLock;
+use web3::signing::{keccak256, recover};
+use web3::types::{Address, Bytes, H256, U256};
+use rlp::{Rlp, RlpStream};
+use futures::StreamExt;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, timeout, Duration};
+use tokio_tungstenite::tungstenite::Message;
+use log::{debug, error, info, warn};
+use chrono::Utc;
+use thiserror::Error;
+
+use crate::modules::chains;
+use crate::modules::feed::{self, FeedPublisher, PendingTx};
+use crate::modules::ws_metrics;
+
+// Arbitrum L1 message kind carrying an L2 message, and the L2 message kinds inside it
+const L1_KIND_L2_MESSAGE: u64 = 3;
+const L2_KIND_BATCH: u8 = 3;
+const L2_KIND_SIGNED_TX: u8 = 4;
+
+// Batches nest; anything deeper than this is malformed
+const MAX_BATCH_DEPTH: usize = 16;
+
+const SOURCE_NAME: &str = "arbitrum_sequencer";
+
+fn bus() -> &'static broadcast::Sender<PendingTx> {
+    static BUS: OnceLock<broadcast::Sender<PendingTx>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(4096).0)
+}
+
+// Sequenced transactions as they come off the feed, before they are in a block
+pub fn subscribe() -> broadcast::Receiver<PendingTx> {
+    bus().subscribe()
+}
+
+fn field(item: &Rlp, index: usize) -> Result<Vec<u8>, SequencerFeedError> {
+    Ok(item.at(index)?.data()?.to_vec())
+}
+
+fn uint(item: &Rlp, index: usize) -> Result<U256, SequencerFeedError> {
+    let bytes = field(item, index)?;
+    if bytes.len() > 32 {
+        return Err(SequencerFeedError::Malformed("integer wider than 256 bits"));
+    }
+    Ok(U256::from_big_endian(&bytes))
+}
+
+fn to_address(item: &Rlp, index: usize) -> Result<Option<Address>, SequencerFeedError> {
+    let bytes = field(item, index)?;
+    match bytes.len() {
+        0 => Ok(None),
+        20 => Ok(Some(Address::from_slice(&bytes))),
+        _ => Err(SequencerFeedError::Malformed("recipient is not 20 bytes")),
+    }
+}
+
+// RLP list of the first `count` fields, re-encoded for the signing hash
+fn unsigned_fields(item: &Rlp, count: usize) -> Result<Vec<u8>, SequencerFeedError> {
+    let mut stream = RlpStream::new_list(count);
+    for index in 0..count {
+        stream.append_raw(item.at(index)?.as_raw(), 1);
+    }
+    Ok(stream.out().to_vec())
+}
+
+fn recover_sender(signing_hash: [u8; 32], r: U256, s: U256, recovery_id: u64) -> Option<Address> {
+    let mut signature = [0u8; 64];
+    r.to_big_endian(&mut signature[..32]);
+    s.to_big_endian(&mut signature[32..]);
+    recover(&signing_hash, &signature, recovery_id as i32).ok()
+}
+
+// Decode a signed legacy, EIP-2930 or EIP-1559 transaction into the mempool feed's format,
+// recovering the sender from its signature
+pub fn decode_signed_tx(raw: &[u8]) -> Result<PendingTx, SequencerFeedError> {
+    let first = *raw.first().ok_or(SequencerFeedError::Malformed("empty transaction"))?;
+    let mut pending = PendingTx {
+        hash: H256::from(keccak256(raw)),
+        from: None,
+        to: None,
+        nonce: U256::zero(),
+        value: U256::zero(),
+        gas: U256::zero(),
+        gas_price: None,
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+        input: Bytes::default(),
+        first_seen_ms: Utc::now().timestamp_millis(),
+        source: SOURCE_NAME.to_string(),
+    };
+
+    match first {
+        // EIP-1559: [chainId, nonce, maxPriorityFee, maxFee, gas, to, value, data, accessList, yParity, r, s]
+        0x02 => {
+            let item = Rlp::new(&raw[1..]);
+            pending.nonce = uint(&item, 1)?;
+            pending.max_priority_fee_per_gas = Some(uint(&item, 2)?);
+            pending.max_fee_per_gas = Some(uint(&item, 3)?);
+            pending.gas = uint(&item, 4)?;
+            pending.to = to_address(&item, 5)?;
+            pending.value = uint(&item, 6)?;
+            pending.input = Bytes(field(&item, 7)?);
+            let signing_hash = keccak256(&[vec![0x02], unsigned_fields(&item, 9)?].concat());
+            pending.from = recover_sender(signing_hash, uint(&item, 10)?, uint(&item, 11)?, uint(&item, 9)?.low_u64());
+        }
+        // EIP-2930: [chainId, nonce, gasPrice, gas, to, value, data, accessList, yParity, r, s]
+        0x01 => {
+            let item = Rlp::new(&raw[1..]);
+            pending.nonce = uint(&item, 1)?;
+            pending.gas_price = Some(uint(&item, 2)?);
+            pending.gas = uint(&item, 3)?;
+            pending.to = to_address(&item, 4)?;
+            pending.value = uint(&item, 5)?;
+            pending.input = Bytes(field(&item, 6)?);
+            let signing_hash = keccak256(&[vec![0x01], unsigned_fields(&item, 8)?].concat());
+            pending.from = recover_sender(signing_hash, uint(&item, 9)?, uint(&item, 10)?, uint(&item, 8)?.low_u64());
+        }
+        // Legacy: [nonce, gasPrice, gas, to, value, data, v, r, s], EIP-155 replay protected or not
+        first if first >= 0xc0 => {
+            let item = Rlp::new(raw);
+            pending.nonce = uint(&item, 0)?;
+            pending.gas_price = Some(uint(&item, 1)?);
+            pending.gas = uint(&item, 2)?;
+            pending.to = to_address(&item, 3)?;
+            pending.value = uint(&item, 4)?;
+            pending.input = Bytes(field(&item, 5)?);
+            let v = uint(&item, 6)?.low_u64();
+            let (signing_hash, recovery_id) = if v >= 35 {
+                let chain_id = (v - 35) / 2;
+                let mut stream = RlpStream::new_list(9);
+                for index in 0..6 {
+                    stream.append_raw(item.at(index)?.as_raw(), 1);
+                }
+                stream.append(&chain_id).append_empty_data().append_empty_data();
+                (keccak256(&stream.out()), (v - 35) % 2)
+            } else {
+                (keccak256(&unsigned_fields(&item, 6)?), v.saturating_sub(27))
+            };
+            pending.from = recover_sender(signing_hash, uint(&item, 7)?, uint(&item, 8)?, recovery_id);
+        }
+        _ => return Err(SequencerFeedError::Malformed("unsupported transaction type")),
+    }
+    Ok(pending)
+}
+
+// Signed transactions inside one L2 message, descending into batches (each entry is a big-endian
+// u64 length followed by a nested L2 message)
+fn signed_transactions(message: &[u8], depth: usize, out: &mut Vec<Vec<u8>>) {
+    let (kind, body) = match message.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    match *kind {
+        L2_KIND_SIGNED_TX => out.push(body.to_vec()),
+        L2_KIND_BATCH if depth < MAX_BATCH_DEPTH => {
+            let mut rest = body;
+            while rest.len() >= 8 {
+                let mut length = [0u8; 8];
+                length.copy_from_slice(&rest[..8]);
+                let length = u64::from_be_bytes(length) as usize;
+                if rest.len() < 8 + length {
+                    break;
+                }
+                signed_transactions(&rest[8..8 + length], depth + 1, out);
+                rest = &rest[8 + length..];
+            }
+        }
+        // Heartbeats, unsigned and contract transactions carry nothing to backrun
+        _ => {}
+    }
+}
+
+// Normalized transactions from one feed broadcast
+pub fn decode_broadcast(broadcast: &Value) -> Vec<PendingTx> {
+    let mut transactions = Vec::new();
+    for entry in broadcast["messages"].as_array().into_iter().flatten() {
+        let message = &entry["message"]["message"];
+        if message["header"]["kind"].as_u64() != Some(L1_KIND_L2_MESSAGE) {
+            continue;
+        }
+        let l2_message = match message["l2Msg"].as_str().and_then(|encoded| base64::engine::general_purpose::STANDARD.decode(encoded).ok()) {
+            Some(l2_message) => l2_message,
+            None => continue,
+        };
+
+        let mut raw_transactions = Vec::new();
+        signed_transactions(&l2_message, 0, &mut raw_transactions);
+        for raw in raw_transactions {
+            match decode_signed_tx(&raw) {
+                Ok(pending) => transactions.push(pending),
+                Err(e) => debug!("Skipping undecodable sequencer transaction at {}: {}", entry["sequenceNumber"], e),
+            }
+        }
+    }
+    transactions
+}
+
+// Stream the active chain's sequencer feed, handing every transaction to in-process subscribers and,
+// when the shared feed is publishing, to the Redis pending stream in the usual format
+pub async fn run_sequencer_feed() {
+    let stream_name = "sequencer_feed";
+    let max_silence = Duration::from_secs(30);
+
+    loop {
+        let feed_url = match chains::active_chain() {
+            Ok(chain) => match chain.feed_url {
+                Some(feed_url) => feed_url,
+                None => {
+                    warn!("Chain {} has no sequencer feed_url; sequencer feed reader stopped", chain.name);
+                    return;
+                }
+            },
+            Err(e) => {
+                error!("Sequencer feed reader has no chain profile: {}", e);
+                return;
+            }
+        };
+
+        let mut publisher = if feed::publish_pending_enabled() {
+            match FeedPublisher::connect().await {
+                Ok(publisher) => Some(publisher),
+                Err(e) => {
+                    warn!("Sequencer feed not publishing to Redis: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let (mut socket, _) = match tokio_tungstenite::connect_async(feed_url.as_str()).await {
+            Ok(connection) => connection,
+            Err(e) => {
+                error!("Failed to connect to sequencer feed {}: {}", feed_url, e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        info!("Reading sequencer feed from {}", feed_url);
+
+        loop {
+            let message = match timeout(max_silence, socket.next()).await {
+                Ok(Some(Ok(message))) => message,
+                Ok(Some(Err(e))) => {
+                    error!("Sequencer feed error: {}", e);
+                    ws_metrics::record_reconnect(stream_name);
+                    break;
+                }
+                Ok(None) | Err(_) => {
+                    ws_metrics::record_reconnect(stream_name);
+                    break;
+                }
+            };
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Binary(data) => String::from_utf8_lossy(&data).to_string(),
+                _ => continue,
+            };
+            let broadcast: Value = match serde_json::from_str(&text) {
+                Ok(broadcast) => broadcast,
+                Err(e) => {
+                    warn!("Unparseable sequencer feed message: {}", e);
+                    continue;
+                }
+            };
+
+            for pending in decode_broadcast(&broadcast) {
+                ws_metrics::record_event(stream_name, None);
+                let _ = bus().send(pending.clone());
+                if let Some(active) = publisher.as_mut() {
+                    if let Err(e) = active.publish_pending(&pending).await {
+                        error!("Failed to publish sequencer transaction {:?}: {}", pending.hash, e);
+                        publisher = None;
+                    }
+                }
+            }
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+}
+
+// Custom error type for the sequencer feed
+#[derive(Error, Debug)]
+pub enum SequencerFeedError {
+    #[error("RLP error: {0}")]
+    RlpError(#[from] rlp::DecoderError),
+    #[error("Malformed transaction: {0}")]
+    Malformed(&'static str),
+}
+
+// Implement conversion for SequencerFeedError to Web3 error
+impl From<SequencerFeedError> for web3::Error {
+    fn from(error: SequencerFeedError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
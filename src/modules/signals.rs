@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::fs;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::broadcast;
+use warp::hyper::StatusCode;
+use warp::ws::{Message, WebSocket};
+use futures::{SinkExt, StreamExt};
+use web3::types::Address;
+use log::{info, warn};
+use chrono::Utc;
+
+use crate::modules::auth::{AuthConfig, OperatorIdentity, Role};
+use crate::modules::ens;
+use crate::modules::events;
+
+// Signals not yet picked up are dropped once this many newer ones are queued
+const SIGNAL_BUFFER: usize = 256;
+
+// Load the external signal configuration
+fn load_signals_config() -> Value {
+    let config_path = "config/signals_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read signals config file");
+    serde_json::from_str(&config_data).expect("Unable to parse signals config file")
+}
+
+pub fn enabled() -> bool {
+    load_signals_config()["enabled"].as_bool().unwrap_or(false)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalAction {
+    Buy,
+    Sell,
+    // Something worth checking now, without a direction
+    Opportunity,
+}
+
+// A validated signal from an external system (research model, TradingView alert, ...)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignalEvent {
+    pub source: String,
+    pub action: SignalAction,
+    pub asset: Option<Address>,
+    // Confidence in [0, 1]; consumers may ignore weak signals
+    pub strength: f64,
+    pub details: Value,
+    pub received_at_ms: i64,
+    pub expires_at_ms: i64,
+}
+
+impl SignalEvent {
+    pub fn is_expired(&self) -> bool {
+        Utc::now().timestamp_millis() > self.expires_at_ms
+    }
+}
+
+fn bus() -> &'static broadcast::Sender<SignalEvent> {
+    static BUS: OnceLock<broadcast::Sender<SignalEvent>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(SIGNAL_BUFFER).0)
+}
+
+pub fn subscribe() -> broadcast::Receiver<SignalEvent> {
+    bus().subscribe()
+}
+
+// Check a pushed payload against the signal schema, collecting every problem so the sender sees
+// them all at once:
+//   action    "buy" | "sell" | "opportunity"                     (required)
+//   asset     token address or ENS name                         (required for buy/sell)
+//   strength  number in [0, 1]                                   (default 1)
+//   ttl_ms    how long the signal stays actionable               (default from config, capped)
+//   details   object passed through to consumers                 (optional)
+pub fn validate(payload: &Value, source: &str) -> Result<SignalEvent, Vec<String>> {
+    let config = load_signals_config();
+    let mut errors = Vec::new();
+    if !payload.is_object() {
+        return Err(vec![String::from("payload must be a JSON object")]);
+    }
+
+    let action = match payload["action"].as_str().map(|action| action.to_lowercase()) {
+        Some(action) if action == "buy" => Some(SignalAction::Buy),
+        Some(action) if action == "sell" => Some(SignalAction::Sell),
+        Some(action) if action == "opportunity" => Some(SignalAction::Opportunity),
+        Some(other) => {
+            errors.push(format!("action must be buy, sell or opportunity, got {:?}", other));
+            None
+        }
+        None => {
+            errors.push(String::from("action is required"));
+            None
+        }
+    };
+
+    let asset = match &payload["asset"] {
+        Value::Null => None,
+        Value::String(asset) => match ens::parse_address(asset) {
+            Some(address) => Some(address),
+            None => {
+                errors.push(format!("asset {:?} is not an address or known ENS name", asset));
+                None
+            }
+        },
+        _ => {
+            errors.push(String::from("asset must be a string"));
+            None
+        }
+    };
+    if asset.is_none() && matches!(action, Some(SignalAction::Buy) | Some(SignalAction::Sell)) && payload["asset"].is_null() {
+        errors.push(String::from("asset is required for buy and sell signals"));
+    }
+
+    let strength = match &payload["strength"] {
+        Value::Null => 1.0,
+        value => match value.as_f64() {
+            Some(strength) if (0.0..=1.0).contains(&strength) => strength,
+            _ => {
+                errors.push(String::from("strength must be a number between 0 and 1"));
+                0.0
+            }
+        },
+    };
+
+    let max_ttl = config["max_ttl_ms"].as_i64().unwrap_or(60_000);
+    let ttl = match &payload["ttl_ms"] {
+        Value::Null => config["default_ttl_ms"].as_i64().unwrap_or(5_000),
+        value => match value.as_i64() {
+            Some(ttl) if ttl > 0 => ttl.min(max_ttl),
+            _ => {
+                errors.push(String::from("ttl_ms must be a positive integer"));
+                0
+            }
+        },
+    };
+
+    if !payload["details"].is_null() && !payload["details"].is_object() {
+        errors.push(String::from("details must be an object"));
+    }
+
+    match action {
+        Some(action) if errors.is_empty() => {
+            let now = Utc::now().timestamp_millis();
+            Ok(SignalEvent {
+                source: source.to_string(),
+                action,
+                asset,
+                strength,
+                details: payload["details"].clone(),
+                received_at_ms: now,
+                expires_at_ms: now + ttl,
+            })
+        }
+        _ => Err(errors),
+    }
+}
+
+// Validate and publish a payload, returning the response for the sender
+fn accept(payload: &Value, source: &str) -> Value {
+    if !enabled() {
+        return json!({ "accepted": false, "errors": ["external signals are disabled"] });
+    }
+    match validate(payload, source) {
+        Ok(signal) => {
+            info!("External signal from {}: {:?} {:?} (strength {})", source, signal.action, signal.asset, signal.strength);
+            events::emit("external_signal", json!(signal));
+            let _ = bus().send(signal);
+            json!({ "accepted": true })
+        }
+        Err(errors) => {
+            warn!("Rejected external signal from {}: {}", source, errors.join("; "));
+            json!({ "accepted": false, "errors": errors })
+        }
+    }
+}
+
+// Persistent signal stream: every text frame is one signal, answered with its validation result
+pub async fn handle_signal_socket(ws: WebSocket, operator: OperatorIdentity) {
+    let (mut tx, mut rx) = ws.split();
+    info!("Signal stream opened by {}", operator.name);
+
+    while let Some(Ok(message)) = rx.next().await {
+        if message.is_close() {
+            break;
+        }
+        let reply = match message.to_str().ok().map(serde_json::from_str::<Value>) {
+            Some(Ok(payload)) => accept(&payload, &operator.name),
+            Some(Err(e)) => json!({ "accepted": false, "errors": [format!("invalid JSON: {}", e)] }),
+            None => continue,
+        };
+        if tx.send(Message::text(reply.to_string())).await.is_err() {
+            break;
+        }
+    }
+    info!("Signal stream closed by {}", operator.name);
+}
+
+// One-shot webhook for senders that can't hold a socket or set headers (TradingView alerts): the
+// token may be given as a bearer header or as a "token" field in the body
+pub async fn handle_signal_webhook(auth: Arc<AuthConfig>, header: Option<String>, payload: Value) -> Result<impl warp::Reply, Infallible> {
+    let token = header
+        .as_deref()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or_else(|| payload["token"].as_str().map(str::to_string));
+    let identity = token.as_deref().and_then(|token| auth.authenticate(token));
+
+    match identity {
+        Some(identity) if identity.role >= Role::Operator => {
+            let reply = accept(&payload, &identity.name);
+            let status = if reply["accepted"].as_bool().unwrap_or(false) { StatusCode::ACCEPTED } else { StatusCode::UNPROCESSABLE_ENTITY };
+            Ok(warp::reply::with_status(warp::reply::json(&reply), status))
+        }
+        Some(identity) => Ok(warp::reply::with_status(
+            warp::reply::json(&format!("{} may not push signals", identity.name)),
+            StatusCode::FORBIDDEN,
+        )),
+        None => Ok(warp::reply::with_status(warp::reply::json(&"Missing or invalid API token"), StatusCode::UNAUTHORIZED)),
+    }
+}
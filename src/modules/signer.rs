@@ -0,0 +1,314 @@
+use serde_json::Value;
+use std::fs;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use web3::contract::tokens::Tokenize;
+use web3::contract::{Contract, Options};
+use web3::signing::{Key, SecretKey, SecretKeyRef};
+use web3::types::{Address, BlockNumber, Bytes, CallRequest, TransactionParameters, TransactionRequest, H256, U256, U64};
+use web3::Web3;
+use log::info;
+use thiserror::Error;
+
+use crate::modules::code_watch;
+use crate::modules::control;
+use crate::modules::coordination;
+use crate::modules::ens;
+use crate::modules::gas::{self, GasError, Urgency};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::simulation::{self, SimulationError};
+use crate::modules::spend_caps::{self, SpendCapError};
+
+// Load global config (the signer section and wallet_address live there)
+fn load_global_config() -> Value {
+    let config_path = "config/global_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read global config file");
+    serde_json::from_str(&config_data).expect("Unable to parse global config file")
+}
+
+// Loaded once per process: decrypting a keystore is deliberately slow
+static SIGNING_KEY: OnceLock<SecretKey> = OnceLock::new();
+
+// Private key from the env var named by signer.private_key_env, or else the keystore at
+// signer.keystore_path unlocked with the password in signer.keystore_password_env
+fn load_key(signer: &Value) -> Result<SecretKey, SignerError> {
+    let key_env = signer["private_key_env"].as_str().unwrap_or("TAZ_PRIVATE_KEY");
+    if let Ok(key) = std::env::var(key_env) {
+        return SecretKey::from_str(key.trim().trim_start_matches("0x")).map_err(|_| SignerError::InvalidKey);
+    }
+    if let Some(path) = signer["keystore_path"].as_str() {
+        let password_env = signer["keystore_password_env"].as_str().unwrap_or("TAZ_KEYSTORE_PASSWORD");
+        let password = std::env::var(password_env).map_err(|_| SignerError::MissingPassword(password_env.to_string()))?;
+        let secret = eth_keystore::decrypt_key(path, password).map_err(|e| SignerError::KeystoreError(e.to_string()))?;
+        return SecretKey::from_slice(&secret).map_err(|_| SignerError::InvalidKey);
+    }
+    Err(SignerError::NoKey)
+}
+
+// Analyst deployments set read_only: detection, simulation and decisions run as usual, but no key
+// is ever loaded, so nothing can be signed or sent
+pub fn read_only() -> bool {
+    load_global_config()["read_only"].as_bool().unwrap_or(false)
+}
+
+fn signing_key(config: &Value) -> Result<&'static SecretKey, SignerError> {
+    if config["read_only"].as_bool().unwrap_or(false) {
+        return Err(SignerError::ReadOnly);
+    }
+    if let Some(key) = SIGNING_KEY.get() {
+        return Ok(key);
+    }
+    let key = load_key(&config["signer"])?;
+    Ok(SIGNING_KEY.get_or_init(|| key))
+}
+
+// Checked before anything is signed or a bundle is sent, so no send path can skip it: nothing goes
+// out while paused, from an observer instance (two instances would race on nonces), or to a
+// contract whose code changed until an operator has reviewed it
+pub fn ensure_may_send(to: Option<Address>) -> Result<(), SignerError> {
+    if control::is_paused() {
+        return Err(SignerError::Paused);
+    }
+    if !coordination::is_writer() {
+        return Err(SignerError::ObserverMode);
+    }
+    if let Some(target) = code_watch::unacknowledged_target(to) {
+        return Err(SignerError::UnacknowledgedUpgrade(target));
+    }
+    Ok(())
+}
+
+// Envelope of the transactions we build
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TxKind {
+    // Type 0, a single gas price
+    Legacy,
+    // Type 2, max fee plus priority fee
+    Eip1559,
+}
+
+// Signs transactions locally with the configured key and submits them with eth_sendRawTransaction.
+// Shared by every strategy so nothing depends on the node holding an unlocked account
+#[derive(Clone)]
+pub struct TxSender {
+    web3: Web3<FailoverTransport>,
+    key: &'static SecretKey,
+    address: Address,
+    chain_id: u64,
+    kind: TxKind,
+}
+
+impl TxSender {
+    // Sender for the configured key; refuses a key that isn't the configured wallet_address
+    pub async fn from_config(web3: &Web3<FailoverTransport>) -> Result<Self, SignerError> {
+        let config = load_global_config();
+        let key = signing_key(&config)?;
+        let address = SecretKeyRef::new(key).address();
+        if let Some(wallet) = config["wallet_address"].as_str().and_then(ens::parse_address) {
+            if wallet != address {
+                return Err(SignerError::WalletMismatch { key: address, wallet });
+            }
+        }
+        let kind = match config["signer"]["transaction_type"].as_str().unwrap_or("eip1559") {
+            "legacy" => TxKind::Legacy,
+            "eip1559" => TxKind::Eip1559,
+            other => return Err(SignerError::UnknownTransactionType(other.to_string())),
+        };
+        let chain_id = web3.eth().chain_id().await?.as_u64();
+        Ok(TxSender { web3: web3.clone(), key, address, chain_id, kind })
+    }
+
+    // Address of the signing key, which sends and receives trades
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn kind(&self) -> TxKind {
+        self.kind
+    }
+
+    // Next nonce counting transactions still pending
+    pub async fn next_nonce(&self) -> Result<U256, SignerError> {
+        Ok(self.web3.eth().transaction_count(self.address, Some(BlockNumber::Pending)).await?)
+    }
+
+    // Complete a request as a transaction of the configured type. Nonce, gas and fees the request
+    // leaves out are filled in: the pending nonce, a simulation-backed gas limit, and normal-urgency
+    // fees (the node's gas price for legacy transactions)
+    pub async fn build(&self, request: TransactionRequest) -> Result<TransactionParameters, SignerError> {
+        let nonce = match request.nonce {
+            Some(nonce) => nonce,
+            None => self.next_nonce().await?,
+        };
+        let gas = match request.gas {
+            Some(gas) => gas,
+            None => {
+                let estimate = CallRequest {
+                    from: Some(self.address),
+                    to: request.to,
+                    value: request.value,
+                    data: request.data.clone(),
+                    ..Default::default()
+                };
+                simulation::gas_limit(&self.web3, estimate).await?
+            }
+        };
+        let mut tx = TransactionParameters {
+            nonce: Some(nonce),
+            to: request.to,
+            gas,
+            value: request.value.unwrap_or_default(),
+            data: request.data.unwrap_or_default(),
+            chain_id: Some(self.chain_id),
+            ..Default::default()
+        };
+
+        match self.kind {
+            TxKind::Legacy => {
+                tx.gas_price = Some(match request.gas_price.or(request.max_fee_per_gas) {
+                    Some(gas_price) => gas_price,
+                    None => self.web3.eth().gas_price().await?,
+                });
+            }
+            TxKind::Eip1559 => {
+                let (max_fee, priority_fee) = match (request.max_fee_per_gas.or(request.gas_price), request.max_priority_fee_per_gas) {
+                    (Some(max_fee), Some(priority_fee)) => (max_fee, priority_fee),
+                    (max_fee, priority_fee) => {
+                        let fees = gas::suggest_fees(&self.web3, Urgency::Normal).await?;
+                        (max_fee.unwrap_or(fees.max_fee), priority_fee.unwrap_or(fees.priority_fee))
+                    }
+                };
+                tx.transaction_type = Some(U64::from(2));
+                tx.max_fee_per_gas = Some(max_fee);
+                tx.max_priority_fee_per_gas = Some(priority_fee.min(max_fee));
+                tx.access_list = request.access_list;
+            }
+        }
+        Ok(tx)
+    }
+
+    // Build and sign a request, returning the raw transaction. The strategy's spending cap is
+    // charged here for the value and the gas limit at the max fee, whichever path built the request
+    pub async fn sign(&self, strategy: &str, request: TransactionRequest) -> Result<Bytes, SignerError> {
+        ensure_may_send(request.to)?;
+        let tx = self.build(request).await?;
+        let max_fee = tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default();
+        spend_caps::authorize(strategy, tx.value, tx.gas.saturating_mul(max_fee))?;
+        let signed = self.web3.accounts().sign_transaction(tx, SecretKeyRef::new(self.key)).await?;
+        Ok(signed.raw_transaction)
+    }
+
+    // Sign a request and submit it with eth_sendRawTransaction, unless it reverts against the
+    // pending block. Bundle legs go through sign instead, since they depend on the target transaction
+    pub async fn send(&self, strategy: &str, request: TransactionRequest) -> Result<H256, SignerError> {
+        let call = CallRequest {
+            from: Some(self.address),
+            to: request.to,
+            value: request.value,
+            data: request.data.clone(),
+            ..Default::default()
+        };
+        simulation::presend_check(&self.web3, strategy, call).await?;
+        let raw = self.sign(strategy, request).await?;
+        let tx_hash = self.web3.eth().send_raw_transaction(raw).await?;
+        info!("Sent signed transaction {:?} from {:?}", tx_hash, self.address);
+        Ok(tx_hash)
+    }
+
+    // Sign a 32-byte digest (an EIP-712 hash) with the sending key, returning (v, r, s)
+    pub fn sign_digest(&self, digest: H256) -> Result<(u8, H256, H256), SignerError> {
+        let signature = SecretKeyRef::new(self.key)
+            .sign(digest.as_bytes(), None)
+            .map_err(|e| SignerError::SigningFailed(e.to_string()))?;
+        Ok((signature.v as u8, signature.r, signature.s))
+    }
+
+    // Request calling a contract function from this sender, for send or for a bundle
+    pub fn contract_request<P: Tokenize>(&self, contract: &Contract<FailoverTransport>, function: &str, params: P, options: Options) -> Result<TransactionRequest, SignerError> {
+        let data = contract.abi().function(function)?.encode_input(&params.into_tokens())?;
+        Ok(TransactionRequest {
+            from: self.address,
+            to: Some(contract.address()),
+            gas: options.gas,
+            gas_price: options.gas_price,
+            value: options.value,
+            data: Some(Bytes(data)),
+            nonce: options.nonce,
+            access_list: options.access_list,
+            max_fee_per_gas: options.max_fee_per_gas,
+            max_priority_fee_per_gas: options.max_priority_fee_per_gas,
+            ..Default::default()
+        })
+    }
+
+    // Call a contract function in a signed transaction; the contract-call counterpart of send
+    pub async fn call<P: Tokenize>(&self, strategy: &str, contract: &Contract<FailoverTransport>, function: &str, params: P, options: Options) -> Result<H256, SignerError> {
+        let request = self.contract_request(contract, function, params, options)?;
+        self.send(strategy, request).await
+    }
+}
+
+// Raw transaction for a request: signed locally when a key is configured, by the node's
+// wallet otherwise
+pub async fn sign_request(web3: &Web3<FailoverTransport>, strategy: &str, request: TransactionRequest) -> Result<Bytes, SignerError> {
+    match TxSender::from_config(web3).await {
+        Ok(sender) => sender.sign(strategy, request).await,
+        Err(SignerError::NoKey) => {
+            ensure_may_send(request.to)?;
+            let max_fee = match request.max_fee_per_gas.or(request.gas_price) {
+                Some(max_fee) => max_fee,
+                None => web3.eth().gas_price().await?,
+            };
+            spend_caps::authorize(strategy, request.value.unwrap_or_default(), request.gas.unwrap_or_default().saturating_mul(max_fee))?;
+            // The node returns { raw, tx }
+            let signed = web3::Transport::execute(web3.transport(), "eth_signTransaction", vec![web3::helpers::serialize(&request)]).await?;
+            serde_json::from_value(signed["raw"].clone()).map_err(|e| SignerError::from(web3::Error::Decoder(e.to_string())))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// Custom error type for local signing
+#[derive(Error, Debug)]
+pub enum SignerError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("Gas error: {0}")]
+    GasError(#[from] GasError),
+    #[error("Simulation error: {0}")]
+    SimulationError(#[from] SimulationError),
+    #[error("Spend cap error: {0}")]
+    SpendCapError(#[from] SpendCapError),
+    #[error("No signing key: set the signer.private_key_env variable or signer.keystore_path")]
+    NoKey,
+    #[error("Signing key is not a valid secp256k1 private key")]
+    InvalidKey,
+    #[error("Signing failed: {0}")]
+    SigningFailed(String),
+    #[error("Keystore password variable {0} is not set")]
+    MissingPassword(String),
+    #[error("Failed to decrypt keystore: {0}")]
+    KeystoreError(String),
+    #[error("Signing key address {key:?} does not match wallet_address {wallet:?}")]
+    WalletMismatch { key: Address, wallet: Address },
+    #[error("Unknown transaction type: {0}")]
+    UnknownTransactionType(String),
+    #[error("Read-only mode: no signing key is loaded")]
+    ReadOnly,
+    #[error("Execution is paused or the kill switch is engaged")]
+    Paused,
+    #[error("This instance is an observer; another instance holds the wallet lock")]
+    ObserverMode,
+    #[error("Call targets {0:?}, whose code changed and has not been acknowledged")]
+    UnacknowledgedUpgrade(Address),
+}
+
+// Implement conversion for SignerError to Web3 error
+impl From<SignerError> for web3::Error {
+    fn from(error: SignerError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
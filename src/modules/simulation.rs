@@ -0,0 +1,411 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use web3::ethabi::{self, AbiError, ParamType};
+use web3::types::{BlockId, BlockNumber, Bytes, CallRequest, U256};
+use web3::{Transport, Web3};
+use log::warn;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio::time::{timeout_at, Duration, Instant};
+use futures::future::join_all;
+
+use crate::modules::config::GlobalConfig;
+use crate::modules::providers::{self, FailoverTransport};
+
+// Load the simulation pool configuration
+fn load_simulation_config() -> Value {
+    let config_path = "config/simulation_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read simulation config file");
+    serde_json::from_str(&config_data).expect("Unable to parse simulation config file")
+}
+
+// What to simulate: a single eth_call, the gas a call uses (eth_estimateGas), or a signed bundle
+// through eth_callBundle
+#[derive(Clone, Debug)]
+pub enum SimulationRequest {
+    Call(CallRequest),
+    GasUsed(CallRequest),
+    Bundle { transactions: Vec<Bytes>, block: u64 },
+}
+
+// A simulation that finished inside its deadline
+#[derive(Clone, Debug)]
+pub struct SimulationOutcome {
+    pub endpoint: String,
+    pub output: Value,
+    pub elapsed_ms: u64,
+}
+
+// Counters for the dashboard
+static COMPLETED: AtomicU64 = AtomicU64::new(0);
+static REVERTED: AtomicU64 = AtomicU64::new(0);
+static DISCARDED: AtomicU64 = AtomicU64::new(0);
+static NEXT_ENDPOINT: AtomicUsize = AtomicUsize::new(0);
+static GAS_LIMITS: AtomicU64 = AtomicU64::new(0);
+static GAS_BELOW_CEILING: AtomicU64 = AtomicU64::new(0);
+static PRESEND_CHECKED: AtomicU64 = AtomicU64::new(0);
+static PRESEND_BLOCKED: AtomicU64 = AtomicU64::new(0);
+
+// Selectors of the revert payloads the compiler emits for require/revert strings and for panics
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+// ABIs the bot loads, searched for custom errors when revert data matches neither selector
+const ABIS: &[&[u8]] = &[
+    include_bytes!("abi/aave_flashloan_abi.json"),
+    include_bytes!("abi/arbitrage_abi.json"),
+    include_bytes!("abi/compound_comptroller_abi.json"),
+    include_bytes!("abi/ctoken_abi.json"),
+    include_bytes!("abi/entry_point_abi.json"),
+    include_bytes!("abi/erc20_abi.json"),
+    include_bytes!("abi/flashloan_abi.json"),
+    include_bytes!("abi/frontrunning_abi.json"),
+    include_bytes!("abi/gnosis_safe_abi.json"),
+    include_bytes!("abi/hft_abi.json"),
+    include_bytes!("abi/liquidation_abi.json"),
+    include_bytes!("abi/multisend_abi.json"),
+    include_bytes!("abi/sandwich_abi.json"),
+    include_bytes!("abi/simple_account_abi.json"),
+    include_bytes!("abi/sushiswap_router_abi.json"),
+    include_bytes!("abi/token_approval_abi.json"),
+    include_bytes!("abi/uniswap_router_abi.json"),
+    include_bytes!("abi/uniswap_v2_factory_abi.json"),
+    include_bytes!("abi/uniswap_v2_router_abi.json"),
+    include_bytes!("abi/uniswap_v3_factory_abi.json"),
+    include_bytes!("abi/uniswap_v3_quoter_abi.json"),
+    include_bytes!("abi/uniswap_v3_router_abi.json"),
+];
+
+// Global cap on simulations in flight, across every strategy
+fn permits() -> &'static Arc<Semaphore> {
+    static PERMITS: OnceLock<Arc<Semaphore>> = OnceLock::new();
+    PERMITS.get_or_init(|| Arc::new(Semaphore::new(load_simulation_config()["max_concurrency"].as_u64().unwrap_or(8) as usize)))
+}
+
+fn names(value: &Value) -> Vec<String> {
+    value.as_array().map(|list| list.iter().filter_map(|name| name.as_str().map(String::from)).collect()).unwrap_or_default()
+}
+
+// Next endpoint in rotation for the request kind; falls back to the caller's client without a pool
+fn pick_endpoint(request: &SimulationRequest, fallback: &Web3<FailoverTransport>) -> (String, Web3<FailoverTransport>) {
+    let config = load_simulation_config();
+    let allowed = match request {
+        SimulationRequest::Call(_) | SimulationRequest::GasUsed(_) => names(&config["call_endpoints"]),
+        SimulationRequest::Bundle { .. } => names(&config["bundle_endpoints"]),
+    };
+    let clients = providers::shared().map(|pool| pool.healthy_clients(&allowed)).unwrap_or_default();
+    if clients.is_empty() {
+        return (String::from("caller"), fallback.clone());
+    }
+    let (name, client) = &clients[NEXT_ENDPOINT.fetch_add(1, Ordering::Relaxed) % clients.len()];
+    (name.clone(), (**client).clone())
+}
+
+// Custom errors declared in the loaded ABIs, keyed by selector
+fn custom_errors() -> &'static HashMap<[u8; 4], AbiError> {
+    static ERRORS: OnceLock<HashMap<[u8; 4], AbiError>> = OnceLock::new();
+    ERRORS.get_or_init(|| {
+        let mut errors = HashMap::new();
+        for abi in ABIS {
+            if let Ok(contract) = ethabi::Contract::load(*abi) {
+                for error in contract.errors() {
+                    let mut selector = [0u8; 4];
+                    selector.copy_from_slice(&error.signature().as_bytes()[..4]);
+                    errors.insert(selector, error.clone());
+                }
+            }
+        }
+        errors
+    })
+}
+
+// Meaning of the code in a Panic(uint256) revert
+fn panic_description(code: U256) -> &'static str {
+    match code.low_u64() {
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "invalid storage byte array",
+        0x31 => "pop on empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to uninitialized function",
+        _ => "unknown panic",
+    }
+}
+
+// Human-readable reason for revert data: Error(string), Panic(uint256), a custom error from the
+// loaded ABIs, or the raw selector when nothing matches
+pub fn decode_revert(data: &[u8]) -> String {
+    if data.is_empty() {
+        return String::from("reverted without data");
+    }
+    if data.len() < 4 {
+        return format!("malformed revert data {}", to_hex(data));
+    }
+    let (selector, args) = data.split_at(4);
+    if selector == ERROR_STRING_SELECTOR {
+        if let Ok(tokens) = ethabi::decode(&[ParamType::String], args) {
+            if let Some(message) = tokens.into_iter().next().and_then(|token| token.into_string()) {
+                return message;
+            }
+        }
+    }
+    if selector == PANIC_SELECTOR {
+        if let Ok(tokens) = ethabi::decode(&[ParamType::Uint(256)], args) {
+            if let Some(code) = tokens.into_iter().next().and_then(|token| token.into_uint()) {
+                return format!("Panic(0x{:02x}): {}", code.low_u64(), panic_description(code));
+            }
+        }
+    }
+    let mut key = [0u8; 4];
+    key.copy_from_slice(selector);
+    if let Some(error) = custom_errors().get(&key) {
+        if let Ok(tokens) = error.decode(args) {
+            let args: Vec<String> = tokens.iter().map(|token| token.to_string()).collect();
+            return format!("{}({})", error.name, args.join(", "));
+        }
+    }
+    format!("unknown error {}", to_hex(selector))
+}
+
+// Revert data in an RPC error: most nodes put the hex string in data, some nest it one level down
+fn revert_data(data: Option<&Value>) -> Option<Vec<u8>> {
+    let data = data?;
+    let encoded = data.as_str().or_else(|| data["data"].as_str())?;
+    parse_hex(encoded)
+}
+
+fn parse_hex(encoded: &str) -> Option<Vec<u8>> {
+    serde_json::from_value::<Bytes>(json!(encoded)).ok().map(|bytes| bytes.0)
+}
+
+fn to_hex(data: &[u8]) -> String {
+    format!("0x{}", data.iter().map(|byte| format!("{:02x}", byte)).collect::<String>())
+}
+
+// Deepest failing frame of a callTracer trace: the contract whose revert bubbled up
+fn failing_frame(frame: &Value) -> Option<&Value> {
+    let deeper = frame["calls"].as_array().and_then(|calls| calls.iter().filter(|call| !call["error"].is_null()).find_map(failing_frame));
+    match deeper {
+        Some(deeper) => Some(deeper),
+        None if !frame["error"].is_null() => Some(frame),
+        None => None,
+    }
+}
+
+// Re-run a reverted call through debug_traceCall to name the contract that reverted first
+async fn trace_revert(client: &Web3<FailoverTransport>, call: &CallRequest) -> Option<String> {
+    let params = vec![json!(call), json!("pending"), json!({ "tracer": "callTracer" })];
+    let trace = match client.transport().execute("debug_traceCall", params).await {
+        Ok(trace) => trace,
+        Err(e) => {
+            warn!("debug_traceCall unavailable for revert tracing: {}", e);
+            return None;
+        }
+    };
+    let frame = failing_frame(&trace)?;
+    let reason = match frame["output"].as_str().and_then(parse_hex) {
+        Some(output) if !output.is_empty() => decode_revert(&output),
+        _ => frame["error"].as_str().unwrap_or("reverted").to_string(),
+    };
+    Some(format!("{} (in {})", reason, frame["to"].as_str().unwrap_or("unknown contract")))
+}
+
+// Decoded reason for a call the node rejected. With trace_reverts the trace names the reverting
+// contract; otherwise the revert data in the error is decoded, falling back to the node's message
+async fn revert_reason(client: &Web3<FailoverTransport>, call: &CallRequest, message: String, data: Option<&Value>) -> String {
+    if load_simulation_config()["trace_reverts"].as_bool().unwrap_or(false) {
+        if let Some(traced) = trace_revert(client, call).await {
+            return traced;
+        }
+    }
+    match revert_data(data) {
+        Some(data) => decode_revert(&data),
+        None => message,
+    }
+}
+
+// Calls run against the pending block, so they see the transactions already queued ahead of ours
+async fn run(client: &Web3<FailoverTransport>, request: SimulationRequest) -> Result<Value, SimulationError> {
+    match request {
+        SimulationRequest::Call(call) => match client.eth().call(call.clone(), Some(BlockId::Number(BlockNumber::Pending))).await {
+            Ok(output) => Ok(json!(output)),
+            Err(web3::Error::Rpc(e)) => Err(SimulationError::Reverted(revert_reason(client, &call, e.message, e.data.as_ref()).await)),
+            Err(e) => Err(SimulationError::Web3Error(e)),
+        },
+        SimulationRequest::GasUsed(call) => match client.eth().estimate_gas(call.clone(), Some(BlockNumber::Pending)).await {
+            Ok(gas) => Ok(json!(gas)),
+            Err(web3::Error::Rpc(e)) => Err(SimulationError::Reverted(revert_reason(client, &call, e.message, e.data.as_ref()).await)),
+            Err(e) => Err(SimulationError::Web3Error(e)),
+        },
+        SimulationRequest::Bundle { transactions, block } => {
+            let params = json!({
+                "txs": transactions,
+                "blockNumber": format!("0x{:x}", block),
+                "stateBlockNumber": "latest",
+            });
+            let result = client.transport().execute("eth_callBundle", vec![params]).await?;
+            // A bundle "succeeds" as an RPC call even when a transaction in it reverts
+            let reverted = result["results"]
+                .as_array()
+                .and_then(|results| results.iter().find(|tx| !tx["revert"].is_null() || !tx["error"].is_null()))
+                .map(|tx| tx["revert"].as_str().or(tx["error"].as_str()).unwrap_or("reverted").to_string());
+            match reverted {
+                Some(reason) => Err(SimulationError::Reverted(reason)),
+                None => Ok(result),
+            }
+        }
+    }
+}
+
+// Run one simulation through the pool. It waits for a concurrency slot and runs on the next
+// endpoint in rotation, but only until the deadline: a result arriving after the decision window
+// is discarded, since the opportunity it would decide on is gone
+pub async fn simulate(web3: &Web3<FailoverTransport>, request: SimulationRequest, deadline: Instant) -> Result<SimulationOutcome, SimulationError> {
+    let started = Instant::now();
+    let outcome = timeout_at(deadline, async {
+        let _permit = permits().acquire().await.map_err(|_| SimulationError::PoolClosed)?;
+        let (endpoint, client) = pick_endpoint(&request, web3);
+        let output = run(&client, request).await?;
+        Ok::<_, SimulationError>(SimulationOutcome { endpoint, output, elapsed_ms: started.elapsed().as_millis() as u64 })
+    })
+    .await;
+
+    match outcome {
+        Ok(Ok(outcome)) => {
+            COMPLETED.fetch_add(1, Ordering::Relaxed);
+            Ok(outcome)
+        }
+        Ok(Err(SimulationError::Reverted(reason))) => {
+            REVERTED.fetch_add(1, Ordering::Relaxed);
+            Err(SimulationError::Reverted(reason))
+        }
+        Ok(Err(e)) => Err(e),
+        Err(_) => {
+            DISCARDED.fetch_add(1, Ordering::Relaxed);
+            warn!("Simulation discarded after {}ms: past its decision deadline", started.elapsed().as_millis());
+            Err(SimulationError::DeadlineExceeded)
+        }
+    }
+}
+
+// Several simulations for one opportunity, concurrently and under one deadline
+pub async fn simulate_all(web3: &Web3<FailoverTransport>, requests: Vec<SimulationRequest>, deadline: Instant) -> Vec<Result<SimulationOutcome, SimulationError>> {
+    join_all(requests.into_iter().map(|request| simulate(web3, request, deadline))).await
+}
+
+// Deadline for a simulation started now, from the configured per-opportunity budget
+pub fn default_deadline() -> Instant {
+    Instant::now() + Duration::from_millis(load_simulation_config()["default_deadline_ms"].as_u64().unwrap_or(500))
+}
+
+// Pre-send check every candidate transaction goes through: an eth_call at the pending block. A
+// revert blocks the submission and its decoded reason is logged; presend_simulation false skips it
+pub async fn presend_check(web3: &Web3<FailoverTransport>, strategy: &str, call: CallRequest) -> Result<(), SimulationError> {
+    if !load_simulation_config()["presend_simulation"].as_bool().unwrap_or(true) {
+        return Ok(());
+    }
+    PRESEND_CHECKED.fetch_add(1, Ordering::Relaxed);
+    match simulate(web3, SimulationRequest::Call(call.clone()), default_deadline()).await {
+        Ok(_) => Ok(()),
+        Err(SimulationError::Reverted(reason)) => {
+            PRESEND_BLOCKED.fetch_add(1, Ordering::Relaxed);
+            warn!("Blocked {} transaction to {:?}: simulation reverted with {}", strategy, call.to, reason);
+            record_revert_reason(&reason);
+            Err(SimulationError::Reverted(reason))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// How often each decoded reason blocked a submission, for the dashboard
+fn revert_reasons() -> &'static Mutex<HashMap<String, u64>> {
+    static REASONS: OnceLock<Mutex<HashMap<String, u64>>> = OnceLock::new();
+    REASONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn record_revert_reason(reason: &str) {
+    *revert_reasons().lock().unwrap().entry(reason.to_string()).or_insert(0) += 1;
+}
+
+// Gas limit for one transaction: the gas a simulation of it uses plus gas_margin_pct, capped at
+// default_gas_limit. A tight limit locks up less capital while the transaction is pending and burns
+// less when it reverts on-chain; a call that reverts in simulation is never given a limit at all
+pub async fn gas_limit(web3: &Web3<FailoverTransport>, call: CallRequest) -> Result<U256, SimulationError> {
+    let config = load_simulation_config();
+    let ceiling = U256::from(GlobalConfig::load().map(|global| global.default_gas_limit).unwrap_or(5_000_000));
+    let outcome = simulate(web3, SimulationRequest::GasUsed(call), default_deadline()).await?;
+    let used: U256 = serde_json::from_value(outcome.output).map_err(|e| SimulationError::Unexpected(e.to_string()))?;
+
+    let margin_pct = config["gas_margin_pct"].as_u64().unwrap_or(15);
+    let limit = used.saturating_add(used * U256::from(margin_pct) / U256::from(100)).max(U256::from(21_000));
+    if limit > ceiling {
+        warn!("Simulated gas {} plus margin exceeds default_gas_limit {}, capping", used, ceiling);
+    }
+    let limit = limit.min(ceiling);
+    GAS_LIMITS.fetch_add(1, Ordering::Relaxed);
+    GAS_BELOW_CEILING.fetch_add((ceiling - limit).low_u64(), Ordering::Relaxed);
+    Ok(limit)
+}
+
+// Pool counters and free slots for the dashboard
+pub fn snapshot() -> Value {
+    json!({
+        "max_concurrency": load_simulation_config()["max_concurrency"].as_u64().unwrap_or(8),
+        "available": permits().available_permits(),
+        "completed": COMPLETED.load(Ordering::Relaxed),
+        "reverted": REVERTED.load(Ordering::Relaxed),
+        "discarded_late": DISCARDED.load(Ordering::Relaxed),
+        "gas_limits_set": GAS_LIMITS.load(Ordering::Relaxed),
+        "gas_below_ceiling": GAS_BELOW_CEILING.load(Ordering::Relaxed),
+        "presend_checked": PRESEND_CHECKED.load(Ordering::Relaxed),
+        "presend_blocked": PRESEND_BLOCKED.load(Ordering::Relaxed),
+        "revert_reasons": revert_reasons().lock().unwrap().clone(),
+    })
+}
+
+// Custom error type for the simulation pool
+#[derive(Error, Debug)]
+pub enum SimulationError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Simulation reverted: {0}")]
+    Reverted(String),
+    #[error("Simulation missed its decision deadline")]
+    DeadlineExceeded,
+    #[error("Simulation pool is closed")]
+    PoolClosed,
+    #[error("Unexpected simulation output: {0}")]
+    Unexpected(String),
+}
+
+impl SimulationError {
+    pub fn is_revert(&self) -> bool {
+        match self {
+            SimulationError::Reverted(_) => true,
+            SimulationError::Web3Error(e) => is_revert_error(e),
+            _ => false,
+        }
+    }
+}
+
+// A node error that reports an execution revert: JSON-RPC code 3 or an error carrying revert data
+pub fn is_revert_error(error: &web3::Error) -> bool {
+    match error {
+        web3::Error::Rpc(e) => e.code.code() == 3 || e.data.is_some(),
+        _ => false,
+    }
+}
+
+// Implement conversion for SimulationError to Web3 error
+impl From<SimulationError> for web3::Error {
+    fn from(error: SimulationError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
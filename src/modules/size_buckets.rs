@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use web3::types::U256;
+
+// Strategy parameters for victims in one size range. How aggressive to be depends on the target:
+// small victims only pay for a small, cheap front-run, large ones justify a bigger position and tip
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SizeBucket {
+    pub name: String,
+    // Victim value range in wei, upper bound exclusive; no upper bound for the top bucket
+    pub min_value: U256,
+    pub max_value: Option<U256>,
+    // Share of the expected profit paid to the block builder as the tip
+    pub tip_pct: f64,
+    pub max_front_run: U256,
+    // Minimum profit after gas and tip, as a fraction of the front-run size
+    pub min_profit_margin: f64,
+}
+
+fn eth_to_wei(eth: f64) -> U256 {
+    U256::from((eth * 1e18) as u128)
+}
+
+fn parse_bucket(entry: &Value) -> Option<SizeBucket> {
+    Some(SizeBucket {
+        name: entry["name"].as_str()?.to_string(),
+        min_value: eth_to_wei(entry["min_value_eth"].as_f64()?),
+        max_value: entry["max_value_eth"].as_f64().map(eth_to_wei),
+        tip_pct: entry["tip_pct"].as_f64().unwrap_or(0.0),
+        max_front_run: eth_to_wei(entry["max_front_run_eth"].as_f64()?),
+        min_profit_margin: entry["min_profit_margin"].as_f64().unwrap_or(0.0),
+    })
+}
+
+// Buckets from a strategy config's "size_buckets"; malformed entries are left out
+pub fn from_config(config: &Value) -> Vec<SizeBucket> {
+    config["size_buckets"]
+        .as_array()
+        .map(|buckets| buckets.iter().filter_map(parse_bucket).collect())
+        .unwrap_or_default()
+}
+
+// The bucket a victim of this value falls in, if any
+pub fn bucket_for(buckets: &[SizeBucket], value: U256) -> Option<&SizeBucket> {
+    buckets
+        .iter()
+        .find(|bucket| value >= bucket.min_value && bucket.max_value.is_none_or(|max| value < max))
+}
+
+impl SizeBucket {
+    // Front-run size for a victim: the requested size capped at the bucket's maximum
+    pub fn front_run_size(&self, requested: U256) -> U256 {
+        requested.min(self.max_front_run)
+    }
+
+    // Builder tip out of an expected profit
+    pub fn tip(&self, expected_profit: U256) -> U256 {
+        U256::from((expected_profit.as_u128() as f64 * self.tip_pct.clamp(0.0, 1.0)) as u128)
+    }
+
+    // Worth doing once gas and the tip are paid
+    pub fn is_profitable(&self, expected_profit: U256, gas_cost: U256, front_run_size: U256) -> bool {
+        let net = expected_profit.saturating_sub(gas_cost).saturating_sub(self.tip(expected_profit));
+        net > U256::zero() && net.as_u128() as f64 >= front_run_size.as_u128() as f64 * self.min_profit_margin
+    }
+}
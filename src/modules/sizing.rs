@@ -0,0 +1,252 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use web3::ethabi::{self, ParamType};
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, CallRequest, U256};
+use log::debug;
+use thiserror::Error;
+
+use crate::modules::providers::FailoverTransport;
+use crate::modules::sandwich::VictimSwap;
+use crate::modules::venues::{self, Venue, VenueVersion};
+
+// Load the input sizing configuration
+fn load_sizing_config() -> Value {
+    let config_path = "config/sizing_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read sizing config file");
+    serde_json::from_str(&config_data).expect("Unable to parse sizing config file")
+}
+
+pub fn enabled() -> bool {
+    load_sizing_config()["enabled"].as_bool().unwrap_or(false)
+}
+
+// A pool as constant-product reserves in the direction of a trade. V3 pools are modelled by their
+// virtual reserves in the current tick range (x = L / sqrtP, y = L * sqrtP), which holds until the
+// trade crosses a tick
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Reserves {
+    pub reserve_in: U256,
+    pub reserve_out: U256,
+    // Pool and router fee together, in hundredths of a bip
+    pub fee: u32,
+}
+
+impl Reserves {
+    pub fn amount_out(&self, amount_in: U256) -> U256 {
+        venues::v2_amount_out(amount_in, self.reserve_in, self.reserve_out, self.fee)
+    }
+
+    // Output of a swap and the pool it leaves behind; the whole input stays in the pool
+    pub fn after_swap(&self, amount_in: U256) -> (U256, Reserves) {
+        let amount_out = self.amount_out(amount_in);
+        let after = Reserves {
+            reserve_in: self.reserve_in.saturating_add(amount_in),
+            reserve_out: self.reserve_out.saturating_sub(amount_out),
+            fee: self.fee,
+        };
+        (amount_out, after)
+    }
+
+    // The same pool traded the other way
+    pub fn reversed(&self) -> Reserves {
+        Reserves { reserve_in: self.reserve_out, reserve_out: self.reserve_in, fee: self.fee }
+    }
+}
+
+// Input size picked for a trade and what it is modelled to return
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Sizing {
+    pub amount_in: U256,
+    // Bought on the first leg
+    pub amount_out: U256,
+    // Back in the input token after the last leg
+    pub amount_back: U256,
+    // Net of the flash loan premium and gas
+    pub profit: U256,
+}
+
+async fn call(web3: &web3::Web3<FailoverTransport>, to: Address, signature: &str) -> Result<Vec<u8>, SizingError> {
+    let request = CallRequest { to: Some(to), data: Some(Bytes(keccak256(signature.as_bytes())[..4].to_vec())), ..Default::default() };
+    Ok(web3.eth().call(request, None).await?.0)
+}
+
+// Current reserves of the venue's pool for a token_in -> token_out trade
+pub async fn reserves(web3: &web3::Web3<FailoverTransport>, venue: &Venue, token_in: Address, token_out: Address) -> Result<Reserves, SizingError> {
+    let (reserve0, reserve1) = match venue.version {
+        VenueVersion::V2 => {
+            let output = call(web3, venue.pool, "getReserves()").await?;
+            let fields = ethabi::decode(&[ParamType::Uint(112), ParamType::Uint(112), ParamType::Uint(32)], &output)?;
+            (fields[0].clone().into_uint().unwrap_or_default(), fields[1].clone().into_uint().unwrap_or_default())
+        }
+        VenueVersion::V3 { .. } => {
+            let slot0 = call(web3, venue.pool, "slot0()").await?;
+            let sqrt_price_x96 = ethabi::decode(&[ParamType::Uint(160)], &slot0[..32.min(slot0.len())])?[0].clone().into_uint().unwrap_or_default();
+            let liquidity = call(web3, venue.pool, "liquidity()").await?;
+            let liquidity = ethabi::decode(&[ParamType::Uint(128)], &liquidity)?[0].clone().into_uint().unwrap_or_default();
+            if sqrt_price_x96.is_zero() {
+                return Err(SizingError::EmptyPool(venue.label()));
+            }
+            let q96 = U256::one() << 96;
+            (liquidity.saturating_mul(q96) / sqrt_price_x96, liquidity.saturating_mul(sqrt_price_x96) / q96)
+        }
+        VenueVersion::Curve { .. } => return Err(SizingError::Unmodelled(venue.label())),
+    };
+    if reserve0.is_zero() || reserve1.is_zero() {
+        return Err(SizingError::EmptyPool(venue.label()));
+    }
+    // Pools order their tokens by address
+    let (reserve_in, reserve_out) = if token_in < token_out { (reserve0, reserve1) } else { (reserve1, reserve0) };
+    Ok(Reserves { reserve_in, reserve_out, fee: venue.total_fee() })
+}
+
+// Input plus the flash loan premium on it
+fn with_premium(amount: U256, premium_bps: u64) -> U256 {
+    amount.saturating_add(amount.saturating_mul(U256::from(premium_bps)) / U256::from(10_000))
+}
+
+// Limits a search runs under: the largest input, the flash loan premium on it, the gas the trade
+// costs in the input token and the precision the search stops at
+#[derive(Clone, Copy, Debug)]
+pub struct Bounds {
+    pub cap: U256,
+    pub premium_bps: u64,
+    pub gas_cost: U256,
+    pub step: U256,
+}
+
+impl Bounds {
+    fn from_config(config: &Value, cap: U256, gas_cost: U256) -> Self {
+        Bounds {
+            cap,
+            premium_bps: config["flash_loan_premium_bps"].as_u64().unwrap_or(0),
+            gas_cost,
+            // Stop once the bracket is within tolerance_bps of the cap
+            step: cap.saturating_mul(U256::from(config["tolerance_bps"].as_u64().unwrap_or(10))) / U256::from(10_000),
+        }
+    }
+
+    // Profit at a size, None when the size doesn't clear the premium and gas
+    fn sizing(&self, amount_in: U256, amount_out: U256, amount_back: U256) -> Option<Sizing> {
+        let cost = with_premium(amount_in, self.premium_bps).saturating_add(self.gas_cost);
+        (!amount_in.is_zero() && amount_back > cost).then(|| Sizing { amount_in, amount_out, amount_back, profit: amount_back - cost })
+    }
+}
+
+// Binary search for the input in [0, cap] where the marginal return stops covering the marginal
+// cost. Returns are concave in the input under constant-product impact, so the first size whose
+// next step earns less than it costs is the maximum. Stops once the bracket is within step
+fn maximize(cap: U256, step: U256, value: impl Fn(U256) -> U256, cost: impl Fn(U256) -> U256) -> U256 {
+    let step = step.max(U256::one());
+    let (mut low, mut high) = (U256::zero(), cap);
+    while high.saturating_sub(low) > step {
+        let mid = low + (high - low) / 2;
+        let next = mid.saturating_add(step).min(high);
+        if value(next).saturating_sub(value(mid)) > cost(next).saturating_sub(cost(mid)) {
+            low = next;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+// Most profitable input for buying on one pool and selling back on another, at most cap. None when
+// no size clears the premium and gas
+pub fn size_round_trip(buy: &Reserves, sell: &Reserves, bounds: &Bounds) -> Option<Sizing> {
+    let back = |amount_in: U256| sell.amount_out(buy.amount_out(amount_in));
+    let amount_in = maximize(bounds.cap, bounds.step, back, |amount_in| with_premium(amount_in, bounds.premium_bps));
+    let amount_out = buy.amount_out(amount_in);
+    bounds.sizing(amount_in, amount_out, sell.amount_out(amount_out))
+}
+
+// Most profitable front-run around a victim selling victim_in with victim_min_out, at most cap. The
+// front-run is capped so the victim still gets its minimum, since a victim that reverts leaves
+// nothing to back-run. When both legs trade the same pool the back-run sees the pool after the
+// front-run and the victim
+pub fn size_sandwich(front: &Reserves, back: &Reserves, same_pool: bool, victim_in: U256, victim_min_out: U256, bounds: &Bounds) -> Option<Sizing> {
+    let victim_out = |front_run: U256| front.after_swap(front_run).1.amount_out(victim_in);
+    if victim_out(U256::zero()) < victim_min_out {
+        return None;
+    }
+    // Largest front-run the victim's slippage limit allows; victim output falls as the front-run grows
+    let (mut low, mut high) = (U256::zero(), bounds.cap);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if victim_out(mid) >= victim_min_out {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    let limit = low;
+
+    let back_run = |front_run: U256| {
+        let (bought, after_front) = front.after_swap(front_run);
+        if same_pool {
+            after_front.after_swap(victim_in).1.reversed().amount_out(bought)
+        } else {
+            back.amount_out(bought)
+        }
+    };
+    let amount_in = maximize(limit, bounds.step, back_run, |amount_in| with_premium(amount_in, bounds.premium_bps));
+    bounds.sizing(amount_in, front.amount_out(amount_in), back_run(amount_in))
+}
+
+// Size an arbitrage between two venues from their current reserves, gas priced in token_in
+pub async fn size_arbitrage(
+    web3: &web3::Web3<FailoverTransport>,
+    buy: &Venue,
+    sell: &Venue,
+    token_in: Address,
+    token_out: Address,
+    cap: U256,
+    gas_cost: U256,
+) -> Result<Option<Sizing>, SizingError> {
+    let bounds = Bounds::from_config(&load_sizing_config(), cap, gas_cost);
+    let buy_reserves = reserves(web3, buy, token_in, token_out).await?;
+    let sell_reserves = reserves(web3, sell, token_out, token_in).await?;
+    let sizing = size_round_trip(&buy_reserves, &sell_reserves, &bounds);
+    debug!("Sized {} -> {} arbitrage (cap {:?}): {:?}", buy.label(), sell.label(), cap, sizing);
+    Ok(sizing)
+}
+
+// Size a sandwich front-run around a victim swap from the venues' current reserves
+pub async fn size_front_run(
+    web3: &web3::Web3<FailoverTransport>,
+    front: &Venue,
+    back: &Venue,
+    victim: &VictimSwap,
+    cap: U256,
+    gas_cost: U256,
+) -> Result<Option<Sizing>, SizingError> {
+    let bounds = Bounds::from_config(&load_sizing_config(), cap, gas_cost);
+    let front_reserves = reserves(web3, front, victim.token_in, victim.token_out).await?;
+    let back_reserves = reserves(web3, back, victim.token_out, victim.token_in).await?;
+    let same_pool = front.pool == back.pool;
+    let sizing = size_sandwich(&front_reserves, &back_reserves, same_pool, victim.amount_in, victim.amount_out_min, &bounds);
+    debug!("Sized front-run on {} around a {:?} victim (cap {:?}): {:?}", front.label(), victim.amount_in, cap, sizing);
+    Ok(sizing)
+}
+
+// Custom error type for input sizing
+#[derive(Error, Debug)]
+pub enum SizingError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("{0} has no liquidity to size against")]
+    EmptyPool(String),
+    #[error("{0} isn't a constant-product pool")]
+    Unmodelled(String),
+}
+
+// Implement conversion for SizingError to Web3 error
+impl From<SizingError> for web3::Error {
+    fn from(error: SizingError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,163 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use web3::ethabi::ethereum_types::U512;
+use web3::types::{Address, H256, U256};
+use log::{error, info};
+use chrono::Utc;
+use tokio::time::{sleep, Duration};
+use crate::modules::providers::FailoverTransport;
+use crate::modules::receipt_events;
+
+// Expected vs realized output per executed leg, and the fitted factors derived from it
+const EXECUTIONS_LOG_PATH: &str = "data/executions.jsonl";
+const FACTORS_PATH: &str = "data/slippage_factors.json";
+
+// Load the slippage model configuration
+fn load_slippage_config() -> Value {
+    let config_path = "config/slippage_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read slippage config file");
+    serde_json::from_str(&config_data).expect("Unable to parse slippage config file")
+}
+
+// Fitted realized/expected ratio per pool
+fn factors() -> &'static Mutex<HashMap<Address, f64>> {
+    static FACTORS: OnceLock<Mutex<HashMap<Address, f64>>> = OnceLock::new();
+    FACTORS.get_or_init(|| {
+        let saved = fs::read_to_string(FACTORS_PATH)
+            .ok()
+            .and_then(|data| serde_json::from_str::<HashMap<String, f64>>(&data).ok())
+            .unwrap_or_default();
+        Mutex::new(
+            saved
+                .into_iter()
+                .filter_map(|(pool, factor)| Address::from_str(&pool).ok().map(|pool| (pool, factor)))
+                .collect(),
+        )
+    })
+}
+
+// Append one executed leg: what the AMM math promised and what actually arrived
+pub fn record_execution(pool: Address, venue: &str, expected_out: U256, realized_out: U256) {
+    let _ = fs::create_dir_all("data");
+    let record = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "pool": pool,
+        "venue": venue,
+        "expected_out": expected_out.to_string(),
+        "realized_out": realized_out.to_string(),
+    });
+
+    match OpenOptions::new().create(true).append(true).open(EXECUTIONS_LOG_PATH) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write execution record: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open execution log: {}", e),
+    }
+}
+
+// Sum the token transfers a pool sent to the recipient in a just-submitted transaction, once it is mined
+pub async fn realized_output(
+    web3: &web3::Web3<FailoverTransport>,
+    tx_hash: H256,
+    token: Address,
+    pool: Address,
+    recipient: Address,
+) -> Option<U256> {
+    let receipt = receipt_events::wait(web3, tx_hash).await.ok()??;
+    Some(receipt.received(token, pool, recipient))
+}
+
+// Fit a factor per pool as the median realized/expected ratio over the most recent samples
+pub fn fit_factors() -> HashMap<Address, f64> {
+    let config = load_slippage_config();
+    let min_samples = config["min_samples"].as_u64().unwrap_or(5) as usize;
+    let max_samples = config["max_samples"].as_u64().unwrap_or(100) as usize;
+    let floor = config["min_factor"].as_f64().unwrap_or(0.5);
+    let ceiling = config["max_factor"].as_f64().unwrap_or(1.02);
+
+    let mut ratios: HashMap<Address, Vec<f64>> = HashMap::new();
+    let contents = fs::read_to_string(EXECUTIONS_LOG_PATH).unwrap_or_default();
+    for line in contents.lines() {
+        let record: Value = match serde_json::from_str(line) {
+            Ok(record) => record,
+            Err(_) => continue,
+        };
+        let pool = match record["pool"].as_str().and_then(|pool| Address::from_str(pool).ok()) {
+            Some(pool) => pool,
+            None => continue,
+        };
+        let expected = record["expected_out"].as_str().and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+        let realized = record["realized_out"].as_str().and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+        if expected > 0.0 {
+            ratios.entry(pool).or_default().push(realized / expected);
+        }
+    }
+
+    ratios
+        .into_iter()
+        .filter(|(_, samples)| samples.len() >= min_samples)
+        .map(|(pool, samples)| {
+            let mut recent: Vec<f64> = samples.iter().rev().take(max_samples).copied().collect();
+            recent.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            (pool, recent[recent.len() / 2].clamp(floor, ceiling))
+        })
+        .collect()
+}
+
+// The pool's fitted factor, if it has enough data
+pub fn factor(pool: Address) -> Option<f64> {
+    factors().lock().unwrap().get(&pool).copied()
+}
+
+// Apply a factor to a theoretical AMM output (no factor leaves it as-is). The factor is rounded to
+// basis points and applied in integer math, so outputs past u128 keep their precision
+pub fn apply_factor(theoretical_out: U256, factor: Option<f64>) -> U256 {
+    match factor {
+        Some(factor) => {
+            let factor_bps = U512::from((factor.max(0.0) * 10_000.0).round() as u64);
+            U256::try_from(U512::from(theoretical_out) * factor_bps / U512::from(10_000u64)).unwrap_or(U256::MAX)
+        }
+        None => theoretical_out,
+    }
+}
+
+// Apply the pool's fitted factor to a theoretical AMM output (pools without enough data are left as-is)
+pub fn adjust(pool: Address, theoretical_out: U256) -> U256 {
+    apply_factor(theoretical_out, factor(pool))
+}
+
+// Fitted factors for the dashboard
+pub fn factors_snapshot() -> Value {
+    let snapshot: HashMap<String, f64> = factors()
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(pool, factor)| (format!("{:?}", pool), *factor))
+        .collect();
+    json!(snapshot)
+}
+
+// Periodically refit the factors from the execution log and persist them
+pub async fn run_slippage_calibration() {
+    let refit_interval = load_slippage_config()["refit_interval_secs"].as_u64().unwrap_or(3600);
+
+    loop {
+        let fitted = fit_factors();
+        info!("Slippage model refit: {} pools calibrated", fitted.len());
+
+        let saved: HashMap<String, f64> = fitted.iter().map(|(pool, factor)| (format!("{:?}", pool), *factor)).collect();
+        if let Err(e) = fs::write(FACTORS_PATH, json!(saved).to_string()) {
+            error!("Failed to persist slippage factors: {}", e);
+        }
+        *factors().lock().unwrap() = fitted;
+
+        sleep(Duration::from_secs(refit_interval)).await;
+    }
+}
@@ -0,0 +1,273 @@
+use serde_json::Value;
+use std::fs;
+use std::time::Instant;
+use web3::types::{Address, Bytes, Transaction, H256, U256};
+use log::info;
+use tokio::sync::mpsc;
+use tokio::time::{interval, Duration};
+
+use crate::modules::{arbitrage, frontrunning, sandwich};
+
+// Load the soak test configuration
+fn load_soak_config() -> Value {
+    let config_path = "config/soak_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read soak config file");
+    serde_json::from_str(&config_data).expect("Unable to parse soak config file")
+}
+
+// Synthetic events fed into the detection pipeline; transactions are boxed to keep the channel slots small
+enum SoakEvent {
+    PendingTx(Box<Transaction>, Instant),
+    PoolUpdate(PoolUpdate, Instant),
+}
+
+// Reserve update for a synthetic pair quoted on two DEXs
+struct PoolUpdate {
+    pair_id: u64,
+    uniswap_price: U256,
+    sushiswap_price: U256,
+}
+
+// Small deterministic PRNG so runs are reproducible for a given seed
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next() % (high - low).max(1)
+    }
+}
+
+// Latency samples kept per stage; percentiles come from a uniform sample of this size
+const RESERVOIR_SIZE: usize = 10_000;
+
+// Per-stage latency in microseconds. A long soak at a high rate sees far more events than are worth
+// keeping, so only a reservoir sample is stored; the count and max stay exact
+struct StageStats {
+    samples: Vec<u64>,
+    count: u64,
+    max: u64,
+    rng: XorShift,
+}
+
+impl Default for StageStats {
+    fn default() -> Self {
+        StageStats { samples: Vec::with_capacity(RESERVOIR_SIZE), count: 0, max: 0, rng: XorShift(0x9e37_79b9_7f4a_7c15) }
+    }
+}
+
+impl StageStats {
+    fn record(&mut self, started: Instant) {
+        let micros = started.elapsed().as_micros() as u64;
+        self.count += 1;
+        self.max = self.max.max(micros);
+        if self.samples.len() < RESERVOIR_SIZE {
+            self.samples.push(micros);
+        } else {
+            // Keep the new sample with probability RESERVOIR_SIZE / count
+            let slot = self.rng.next() % self.count;
+            if slot < RESERVOIR_SIZE as u64 {
+                self.samples[slot as usize] = micros;
+            }
+        }
+    }
+
+    fn percentile(&self, pct: f64) -> u64 {
+        if self.samples.is_empty() {
+            return 0;
+        }
+        if pct >= 1.0 {
+            return self.max;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        let index = ((sorted.len() - 1) as f64 * pct).round() as usize;
+        sorted[index]
+    }
+}
+
+// Aggregated soak results
+#[derive(Default)]
+pub struct SoakReport {
+    pub pending_txs: u64,
+    pub pool_updates: u64,
+    pub opportunities: u64,
+    queue_wait: StageStats,
+    frontrunning_eval: StageStats,
+    sandwich_eval: StageStats,
+    arbitrage_eval: StageStats,
+}
+
+impl SoakReport {
+    fn log(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64().max(0.001);
+        info!(
+            "Soak: {} pending txs ({:.0}/s), {} pool updates ({:.0}/s), {} opportunities",
+            self.pending_txs,
+            self.pending_txs as f64 / secs,
+            self.pool_updates,
+            self.pool_updates as f64 / secs,
+            self.opportunities
+        );
+        for (stage, stats) in [
+            ("queue_wait", &self.queue_wait),
+            ("frontrunning_eval", &self.frontrunning_eval),
+            ("sandwich_eval", &self.sandwich_eval),
+            ("arbitrage_eval", &self.arbitrage_eval),
+        ] {
+            info!(
+                "Soak stage {}: p50 {}us, p99 {}us, max {}us ({} samples)",
+                stage,
+                stats.percentile(0.50),
+                stats.percentile(0.99),
+                stats.percentile(1.0),
+                stats.count
+            );
+        }
+    }
+}
+
+// Build a synthetic pending swap transaction
+fn synthetic_transaction(rng: &mut XorShift, max_value_eth: u64) -> Transaction {
+    Transaction {
+        hash: H256::from_low_u64_be(rng.next()),
+        from: Some(Address::from_low_u64_be(rng.next())),
+        to: Some(Address::from_low_u64_be(rng.range(1, 16))),
+        value: U256::from(rng.range(1, max_value_eth * 1000)) * U256::exp10(15),
+        gas_price: Some(U256::from(rng.range(5, 200)) * U256::exp10(9)),
+        gas: U256::from(rng.range(100_000, 400_000)),
+        input: Bytes(vec![0x38, 0xed, 0x17, 0x39]), // swapExactTokensForTokens selector
+        ..Transaction::default()
+    }
+}
+
+// Build a synthetic pool update with a small random spread between DEXs
+fn synthetic_pool_update(rng: &mut XorShift, pairs: u64) -> PoolUpdate {
+    let base_price = rng.range(1_000, 4_000);
+    let spread = rng.range(0, 20);
+    PoolUpdate {
+        pair_id: rng.range(0, pairs),
+        uniswap_price: U256::from(base_price + spread) * U256::exp10(15),
+        sushiswap_price: U256::from(base_price) * U256::exp10(15),
+    }
+}
+
+// Generate events at a fixed rate until the duration elapses
+async fn produce_events(
+    sender: mpsc::Sender<SoakEvent>,
+    rate_per_sec: u64,
+    duration: Duration,
+    seed: u64,
+    config: Value,
+    pool_updates: bool,
+) {
+    if rate_per_sec == 0 {
+        return;
+    }
+
+    let mut rng = XorShift(seed.max(1));
+    let max_value_eth = config["max_tx_value_eth"].as_u64().unwrap_or(100);
+    let pairs = config["pairs"].as_u64().unwrap_or(50);
+    // interval panics on a zero period; rates above 1M/s are produced as fast as 1us ticks allow
+    let mut ticker = interval(Duration::from_micros((1_000_000 / rate_per_sec).max(1)));
+    let started = Instant::now();
+
+    while started.elapsed() < duration {
+        ticker.tick().await;
+        let event = if pool_updates {
+            SoakEvent::PoolUpdate(synthetic_pool_update(&mut rng, pairs), Instant::now())
+        } else {
+            SoakEvent::PendingTx(Box::new(synthetic_transaction(&mut rng, max_value_eth)), Instant::now())
+        };
+        if sender.send(event).await.is_err() {
+            break;
+        }
+    }
+}
+
+// Run the detection pipeline against synthetic traffic without touching the network
+pub async fn run_soak_test() -> SoakReport {
+    let config = load_soak_config();
+    let duration = Duration::from_secs(config["duration_secs"].as_u64().unwrap_or(60));
+    let report_interval = Duration::from_secs(config["report_interval_secs"].as_u64().unwrap_or(10));
+    let tx_rate = config["pending_tx_rate"].as_u64().unwrap_or(500);
+    let pool_rate = config["pool_update_rate"].as_u64().unwrap_or(200);
+    let seed = config["seed"].as_u64().unwrap_or(42);
+
+    let threshold = U256::from(config["frontrunning_threshold_wei"].as_u64().unwrap_or(1_000_000_000_000_000_000));
+    let gas_fee = U256::from(config["gas_fee_wei"].as_u64().unwrap_or(300_000));
+    let slippage = config["slippage_tolerance"].as_f64().unwrap_or(0.005);
+
+    info!(
+        "Starting soak test for {:?}: {} pending txs/s, {} pool updates/s",
+        duration, tx_rate, pool_rate
+    );
+
+    let (sender, mut receiver) = mpsc::channel(config["queue_capacity"].as_u64().unwrap_or(10_000) as usize);
+    tokio::spawn(produce_events(sender.clone(), tx_rate, duration, seed, config.clone(), false));
+    tokio::spawn(produce_events(sender, pool_rate, duration, seed.wrapping_mul(31), config.clone(), true));
+
+    let mut report = SoakReport::default();
+    let started = Instant::now();
+    let mut last_report = Instant::now();
+
+    while let Some(event) = receiver.recv().await {
+        match event {
+            SoakEvent::PendingTx(transaction, created) => {
+                report.queue_wait.record(created);
+                report.pending_txs += 1;
+
+                let stage = Instant::now();
+                let mut candidate = false;
+                if transaction.value > threshold {
+                    let profit = frontrunning::calculate_potential_profit(transaction.value, gas_fee);
+                    candidate = frontrunning::is_profitable(profit, gas_fee);
+                }
+                report.frontrunning_eval.record(stage);
+
+                let stage = Instant::now();
+                let loan = sandwich::calculate_dynamic_loan_amount(transaction.value, gas_fee, slippage, U256::zero());
+                if sandwich::is_profitable(loan, gas_fee, transaction.value) {
+                    candidate = true;
+                }
+                report.sandwich_eval.record(stage);
+
+                if candidate {
+                    report.opportunities += 1;
+                }
+            }
+            SoakEvent::PoolUpdate(update, created) => {
+                report.queue_wait.record(created);
+                report.pool_updates += 1;
+
+                let stage = Instant::now();
+                if update.uniswap_price > update.sushiswap_price {
+                    let profit = update.uniswap_price - update.sushiswap_price;
+                    if arbitrage::is_profitable(profit, gas_fee) {
+                        report.opportunities += 1;
+                        log::debug!("Soak arbitrage candidate on pair {}", update.pair_id);
+                    }
+                }
+                report.arbitrage_eval.record(stage);
+            }
+        }
+
+        if last_report.elapsed() >= report_interval {
+            report.log(started.elapsed());
+            last_report = Instant::now();
+        }
+    }
+
+    info!("Soak test finished");
+    report.log(started.elapsed());
+    report
+}
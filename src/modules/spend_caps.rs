@@ -0,0 +1,158 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use web3::types::U256;
+use log::error;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use thiserror::Error;
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::events;
+use crate::modules::templates;
+
+// Every spend authorized at the signer, so the rolling window survives restarts
+const SPEND_LEDGER_PATH: &str = "data/spend_ledger.jsonl";
+
+// Load the per-strategy spending caps
+fn load_spend_caps_config() -> Value {
+    let config_path = "config/spend_caps_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read spend caps config file");
+    serde_json::from_str(&config_data).expect("Unable to parse spend caps config file")
+}
+
+// (when, value, gas) per strategy inside the window
+type Spends = HashMap<String, Vec<(DateTime<Utc>, U256, U256)>>;
+
+fn spends() -> &'static Mutex<Spends> {
+    static SPENDS: OnceLock<Mutex<Spends>> = OnceLock::new();
+    SPENDS.get_or_init(|| {
+        let mut spends: Spends = HashMap::new();
+        for line in fs::read_to_string(SPEND_LEDGER_PATH).unwrap_or_default().lines() {
+            let record: Value = match serde_json::from_str(line) {
+                Ok(record) => record,
+                Err(_) => continue,
+            };
+            let amount = |field: &str| record[field].as_str().and_then(|amount| U256::from_dec_str(amount).ok()).unwrap_or_default();
+            if let (Some(strategy), Some(timestamp)) = (record["strategy"].as_str(), record["timestamp"].as_str().and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())) {
+                spends.entry(strategy.to_string()).or_default().push((timestamp.with_timezone(&Utc), amount("value_wei"), amount("gas_wei")));
+            }
+        }
+        Mutex::new(spends)
+    })
+}
+
+fn parse_wei(value: &Value) -> Option<U256> {
+    value.as_str().and_then(|amount| U256::from_dec_str(amount).ok())
+}
+
+// (value cap, gas cap) for a strategy, falling back to "default"; a missing cap is unlimited
+fn caps_for(config: &Value, strategy: &str) -> (Option<U256>, Option<U256>) {
+    let caps = if config["strategies"][strategy].is_object() { &config["strategies"][strategy] } else { &config["default"] };
+    (parse_wei(&caps["max_value_wei"]), parse_wei(&caps["max_gas_wei"]))
+}
+
+fn window(config: &Value) -> ChronoDuration {
+    ChronoDuration::seconds(config["window_secs"].as_i64().unwrap_or(86400))
+}
+
+// Value and gas a strategy signed for inside the window
+fn used(spends: &mut Spends, strategy: &str, cutoff: DateTime<Utc>) -> (U256, U256) {
+    let entries = spends.entry(strategy.to_string()).or_default();
+    entries.retain(|(at, _, _)| *at >= cutoff);
+    entries.iter().fold((U256::zero(), U256::zero()), |(value, gas), (_, v, g)| (value.saturating_add(*v), gas.saturating_add(*g)))
+}
+
+// Called by the signer, and by the smart-account and Safe backends, right before anything is signed. The spend is booked as soon
+// as it is authorized, sent or not: a hard cap should err on the side of counting too much.
+// This is deliberately independent of strategy-level risk checks, so a strategy bug that gets
+// past them still can't sign for more than its cap in the window
+pub fn authorize(strategy: &str, value: U256, gas: U256) -> Result<(), SpendCapError> {
+    let config = load_spend_caps_config();
+    let (max_value, max_gas) = caps_for(&config, strategy);
+    let now = Utc::now();
+
+    let mut spends = spends().lock().unwrap();
+    let (used_value, used_gas) = used(&mut spends, strategy, now - window(&config));
+    let breach = match (max_value, max_gas) {
+        (Some(cap), _) if used_value.saturating_add(value) > cap => Some(("value", used_value, value, cap)),
+        (_, Some(cap)) if used_gas.saturating_add(gas) > cap => Some(("gas", used_gas, gas, cap)),
+        _ => None,
+    };
+    if let Some((kind, used, requested, cap)) = breach {
+        drop(spends);
+        let (subject, body) = templates::render("spend_cap", &[
+            ("strategy", strategy.to_string()),
+            ("kind", kind.to_string()),
+            ("used", used.to_string()),
+            ("requested", requested.to_string()),
+            ("cap", cap.to_string()),
+        ]);
+        alerts::raise(&format!("spend_cap:{}:{}", strategy, kind), Severity::Critical, &subject, &body);
+        events::emit("spend_cap_exceeded", json!({ "strategy": strategy, "kind": kind, "used": used.to_string(), "requested": requested.to_string(), "cap": cap.to_string() }));
+        return Err(SpendCapError::Exceeded(Box::new(CapBreach { strategy: strategy.to_string(), kind, used, requested, cap })));
+    }
+
+    spends.entry(strategy.to_string()).or_default().push((now, value, gas));
+    drop(spends);
+    let record = json!({
+        "timestamp": now.to_rfc3339(),
+        "strategy": strategy,
+        "value_wei": value.to_string(),
+        "gas_wei": gas.to_string(),
+    });
+    let _ = fs::create_dir_all("data");
+    match OpenOptions::new().create(true).append(true).open(SPEND_LEDGER_PATH) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write spend record: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open spend ledger: {}", e),
+    }
+    Ok(())
+}
+
+// Usage against the caps per strategy for the dashboard
+pub fn snapshot() -> Value {
+    let config = load_spend_caps_config();
+    let cutoff = Utc::now() - window(&config);
+    let mut spends = spends().lock().unwrap();
+    let mut strategies: Vec<String> = spends.keys().cloned().collect();
+    if let Some(configured) = config["strategies"].as_object() {
+        strategies.extend(configured.keys().filter(|name| !spends.contains_key(*name)).cloned());
+    }
+    let report: HashMap<String, Value> = strategies
+        .into_iter()
+        .map(|strategy| {
+            let (used_value, used_gas) = used(&mut spends, &strategy, cutoff);
+            let (max_value, max_gas) = caps_for(&config, &strategy);
+            let report = json!({
+                "value_used_wei": used_value.to_string(),
+                "value_cap_wei": max_value.map(|cap| cap.to_string()),
+                "gas_used_wei": used_gas.to_string(),
+                "gas_cap_wei": max_gas.map(|cap| cap.to_string()),
+            });
+            (strategy, report)
+        })
+        .collect();
+    json!({ "window_secs": window(&config).num_seconds(), "strategies": report })
+}
+
+// Boxed in the error so everything that wraps a SpendCapError stays small
+#[derive(Debug)]
+pub struct CapBreach {
+    pub strategy: String,
+    pub kind: &'static str,
+    pub used: U256,
+    pub requested: U256,
+    pub cap: U256,
+}
+
+#[derive(Error, Debug)]
+pub enum SpendCapError {
+    #[error("{} would exceed its {} cap: {} used + {} requested > {}", .0.strategy, .0.kind, .0.used, .0.requested, .0.cap)]
+    Exceeded(Box<CapBreach>),
+}
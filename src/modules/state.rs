@@ -0,0 +1,175 @@
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{info, warn};
+use thiserror::Error;
+use chrono::Utc;
+
+// Everything the bot persists lives under data/; configs travel only on request
+const DATA_DIR: &str = "data";
+const CONFIG_DIR: &str = "config";
+const MANIFEST_NAME: &str = "MANIFEST.json";
+const ARCHIVE_VERSION: u64 = 1;
+
+// Wallet locks belong to the machine holding them and must never be migrated
+fn excluded(relative: &Path) -> bool {
+    relative.starts_with("data/locks")
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if excluded(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Write ledgers, caches and strategy state (and optionally configs) to a gzipped tar archive
+// with a manifest listing every file and its size
+pub fn export_state(archive_path: &str, include_config: bool) -> Result<Value, StateError> {
+    let mut files = Vec::new();
+    collect_files(Path::new(DATA_DIR), &mut files)?;
+    if include_config {
+        collect_files(Path::new(CONFIG_DIR), &mut files)?;
+    }
+    files.sort();
+
+    let entries: Vec<Value> = files
+        .iter()
+        .map(|path| json!({ "path": path.to_string_lossy(), "bytes": fs::metadata(path).map(|m| m.len()).unwrap_or(0) }))
+        .collect();
+    let manifest = json!({
+        "version": ARCHIVE_VERSION,
+        "created_at": Utc::now().to_rfc3339(),
+        "host": std::env::var("HOSTNAME").unwrap_or_default(),
+        "includes_config": include_config,
+        "files": entries,
+    });
+
+    let mut builder = tar::Builder::new(GzEncoder::new(File::create(archive_path)?, Compression::default()));
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp() as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST_NAME, manifest_bytes.as_slice())?;
+    for path in &files {
+        builder.append_path(path)?;
+    }
+    builder.into_inner()?.finish()?;
+
+    info!("Exported {} files to {}", files.len(), archive_path);
+    Ok(manifest)
+}
+
+fn read_manifest(archive_path: &str) -> Result<Value, StateError> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(archive_path)?));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.as_ref() == Path::new(MANIFEST_NAME) {
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents)?;
+            return Ok(serde_json::from_str(&contents)?);
+        }
+    }
+    Err(StateError::MissingManifest)
+}
+
+// Restore an exported archive into data/ (and config/ if it was included). Refuses to overwrite
+// existing state unless forced, and only unpacks paths under data/ or config/
+pub fn import_state(archive_path: &str, force: bool) -> Result<Value, StateError> {
+    let manifest = read_manifest(archive_path)?;
+    let version = manifest["version"].as_u64().unwrap_or(0);
+    if version != ARCHIVE_VERSION {
+        return Err(StateError::UnsupportedVersion(version));
+    }
+
+    let has_state = fs::read_dir(DATA_DIR).map(|mut entries| entries.next().is_some()).unwrap_or(false);
+    if has_state && !force {
+        return Err(StateError::StateExists);
+    }
+
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(archive_path)?));
+    let mut restored = 0;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        if path == Path::new(MANIFEST_NAME) {
+            continue;
+        }
+        let allowed = (path.starts_with(DATA_DIR) || path.starts_with(CONFIG_DIR)) && !excluded(&path);
+        if !allowed {
+            warn!("Skipping unexpected archive entry {}", path.display());
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // unpack_in rejects entries that would escape the working directory
+        entry.unpack_in(".")?;
+        restored += 1;
+    }
+
+    info!(
+        "Imported {} files from {} (exported {} on {})",
+        restored, archive_path, manifest["created_at"], manifest["host"]
+    );
+    Ok(manifest)
+}
+
+// `taz state export <archive> [--with-config]` and `taz state import <archive> [--force]`
+pub fn run_state_command(args: &[String]) -> Result<(), StateError> {
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("export"), Some(archive_path)) => {
+            let manifest = export_state(archive_path, flag("--with-config"))?;
+            println!("Exported {} files to {}", manifest["files"].as_array().map(|files| files.len()).unwrap_or(0), archive_path);
+            Ok(())
+        }
+        (Some("import"), Some(archive_path)) => {
+            import_state(archive_path, flag("--force"))?;
+            println!("Imported state from {}", archive_path);
+            Ok(())
+        }
+        _ => Err(StateError::Usage),
+    }
+}
+
+// Custom error type for state export/import
+#[derive(Error, Debug)]
+pub enum StateError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Archive has no manifest")]
+    MissingManifest,
+    #[error("Unsupported archive version {0}")]
+    UnsupportedVersion(u64),
+    #[error("data/ already holds state; pass --force to overwrite it")]
+    StateExists,
+    #[error("usage: taz state export <archive> [--with-config] | taz state import <archive> [--force]")]
+    Usage,
+}
+
+// Implement conversion for StateError to Web3 error
+impl From<StateError> for web3::Error {
+    fn from(error: StateError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
@@ -0,0 +1,400 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use web3::types::H256;
+use log::{error, info, warn};
+use thiserror::Error;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::{interval, Duration};
+
+use crate::modules::chains::{self, ChainError};
+use crate::modules::domain::OpportunitySource;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::receipt_events::DecodedReceipt;
+use crate::modules::runway;
+use crate::modules::strategy::Opportunity;
+
+// Tables are created on startup; amounts are kept exactly as wei strings next to an ETH float that
+// SQL can aggregate
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS opportunities (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp TEXT NOT NULL,
+        strategy TEXT NOT NULL,
+        target TEXT,
+        asset TEXT,
+        amount_wei TEXT NOT NULL,
+        outcome TEXT NOT NULL,
+        error TEXT
+    )",
+    "CREATE TABLE IF NOT EXISTS transactions (
+        tx_hash TEXT PRIMARY KEY,
+        timestamp TEXT NOT NULL,
+        strategy TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        status TEXT NOT NULL,
+        block INTEGER,
+        resolved_at TEXT
+    )",
+    "CREATE TABLE IF NOT EXISTS pnl (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp TEXT NOT NULL,
+        strategy TEXT NOT NULL,
+        source TEXT NOT NULL,
+        pnl_wei TEXT NOT NULL,
+        pnl_eth REAL NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS gas_spent (
+        tx_hash TEXT PRIMARY KEY,
+        timestamp TEXT NOT NULL,
+        strategy TEXT NOT NULL,
+        gas_used INTEGER NOT NULL,
+        effective_gas_price TEXT NOT NULL,
+        cost_wei TEXT NOT NULL,
+        cost_eth REAL NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS receipt_events (
+        tx_hash TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        strategy TEXT NOT NULL,
+        event TEXT NOT NULL,
+        details TEXT NOT NULL,
+        PRIMARY KEY (tx_hash, position)
+    )",
+    "CREATE INDEX IF NOT EXISTS transactions_status ON transactions (status)",
+    "CREATE INDEX IF NOT EXISTS pnl_strategy ON pnl (strategy, timestamp)",
+];
+
+// Load the storage configuration
+fn load_storage_config() -> Value {
+    let config_path = "config/storage_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read storage config file");
+    serde_json::from_str(&config_data).expect("Unable to parse storage config file")
+}
+
+// A row for the writer task; strategies hand records over without waiting on the database
+enum StorageRecord {
+    Opportunity { strategy: String, target: Option<String>, asset: Option<String>, amount_wei: String, outcome: String, error: Option<String> },
+    Transaction { strategy: String, tx_hash: H256, kind: &'static str },
+    Pnl { strategy: String, source: String, pnl_wei: i128 },
+}
+
+// Set once the writer is running; records made before that (or with storage disabled) are dropped
+static SENDER: OnceLock<UnboundedSender<StorageRecord>> = OnceLock::new();
+static POOL: OnceLock<SqlitePool> = OnceLock::new();
+
+fn send(record: StorageRecord) {
+    if let Some(sender) = SENDER.get() {
+        let _ = sender.send(record);
+    }
+}
+
+fn to_eth(wei: i128) -> f64 {
+    wei as f64 / 1e18
+}
+
+// A scanned opportunity and what executing it came to: "executed", "failed" or "executed_in_scan"
+pub fn record_opportunity(strategy: &str, opportunity: &Opportunity, outcome: &str, error: Option<String>) {
+    send(StorageRecord::Opportunity {
+        strategy: strategy.to_string(),
+        target: opportunity.target.map(|target| format!("{:?}", target)),
+        asset: opportunity.asset.map(|asset| format!("{:?}", asset)),
+        amount_wei: opportunity.amount.to_string(),
+        outcome: outcome.to_string(),
+        error,
+    });
+}
+
+// A transaction we sent ("direct", "signed" or "bundle"); its receipt is picked up later to settle
+// its status and the gas it cost
+pub fn record_transaction(strategy: &str, tx_hash: H256, kind: &'static str) {
+    send(StorageRecord::Transaction { strategy: strategy.to_string(), tx_hash, kind });
+}
+
+// A realized trade result, signed, in wei of the strategy's base asset
+pub fn record_pnl(strategy: &str, source: OpportunitySource, pnl_wei: i128) {
+    send(StorageRecord::Pnl { strategy: strategy.to_string(), source: source.as_str().to_string(), pnl_wei });
+}
+
+async fn connect(config: &Value) -> Result<SqlitePool, StorageError> {
+    let url = config["database_url"].as_str().unwrap_or("sqlite://data/taz.db");
+    if let Some(path) = url.strip_prefix("sqlite://") {
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+    }
+    let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(config["max_connections"].as_u64().unwrap_or(4) as u32)
+        .connect_with(options)
+        .await?;
+    for statement in SCHEMA {
+        sqlx::query(statement).execute(&pool).await?;
+    }
+    Ok(pool)
+}
+
+async fn insert(pool: &SqlitePool, record: StorageRecord) -> Result<(), StorageError> {
+    let timestamp = Utc::now().to_rfc3339();
+    match record {
+        StorageRecord::Opportunity { strategy, target, asset, amount_wei, outcome, error } => {
+            sqlx::query("INSERT INTO opportunities (timestamp, strategy, target, asset, amount_wei, outcome, error) VALUES (?, ?, ?, ?, ?, ?, ?)")
+                .bind(timestamp)
+                .bind(strategy)
+                .bind(target)
+                .bind(asset)
+                .bind(amount_wei)
+                .bind(outcome)
+                .bind(error)
+                .execute(pool)
+                .await?;
+        }
+        StorageRecord::Transaction { strategy, tx_hash, kind } => {
+            sqlx::query("INSERT OR IGNORE INTO transactions (tx_hash, timestamp, strategy, kind, status) VALUES (?, ?, ?, ?, 'pending')")
+                .bind(format!("{:?}", tx_hash))
+                .bind(timestamp)
+                .bind(strategy)
+                .bind(kind)
+                .execute(pool)
+                .await?;
+        }
+        StorageRecord::Pnl { strategy, source, pnl_wei } => {
+            sqlx::query("INSERT INTO pnl (timestamp, strategy, source, pnl_wei, pnl_eth) VALUES (?, ?, ?, ?, ?)")
+                .bind(timestamp)
+                .bind(strategy)
+                .bind(source)
+                .bind(pnl_wei.to_string())
+                .bind(to_eth(pnl_wei))
+                .execute(pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+// Settle pending transactions from their receipts under the active chain's finality rules. A
+// receipt with its soft confirmations marks the transaction included; once its block is final it
+// is settled as success or reverted, with the gas it cost and the swaps, transfers, liquidations and
+// flash loans decoded from its logs. An included transaction whose receipt disappears was reorged
+// out and goes back to pending. Bundle transactions that never land, and anything without a
+// receipt after drop_after_secs, are marked dropped
+async fn settle_pending(web3: &web3::Web3<FailoverTransport>, pool: &SqlitePool, drop_after: ChronoDuration) -> Result<(), StorageError> {
+    let pending = sqlx::query("SELECT tx_hash, timestamp, strategy, status FROM transactions WHERE status IN ('pending', 'included')")
+        .fetch_all(pool)
+        .await?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let now = Utc::now();
+    let finality = chains::finality();
+    let head = web3.eth().block_number().await?.as_u64();
+    let final_block = finality.final_block(web3).await?;
+
+    for row in pending {
+        let tx_hash: String = row.try_get("tx_hash")?;
+        let strategy: String = row.try_get("strategy")?;
+        let sent: String = row.try_get("timestamp")?;
+        let included = row.try_get::<String, _>("status")? == "included";
+        let hash = match tx_hash.parse::<H256>() {
+            Ok(hash) => hash,
+            Err(_) => continue,
+        };
+
+        match web3.eth().transaction_receipt(hash).await? {
+            Some(receipt) => {
+                let block = match receipt.block_number {
+                    Some(block) => block.as_u64(),
+                    None => continue,
+                };
+                if !finality.is_confirmed(block, head) {
+                    continue;
+                }
+                if block > final_block {
+                    if !included {
+                        sqlx::query("UPDATE transactions SET status = 'included', block = ? WHERE tx_hash = ?")
+                            .bind(block as i64)
+                            .bind(&tx_hash)
+                            .execute(pool)
+                            .await?;
+                    }
+                    continue;
+                }
+                let status = if receipt.status.is_some_and(|status| status.as_u64() == 1) { "success" } else { "reverted" };
+                let gas_used = receipt.gas_used.unwrap_or_default();
+                let gas_price = receipt.effective_gas_price.unwrap_or_default();
+                let cost = gas_used.saturating_mul(gas_price);
+                sqlx::query("UPDATE transactions SET status = ?, block = ?, resolved_at = ? WHERE tx_hash = ?")
+                    .bind(status)
+                    .bind(block as i64)
+                    .bind(now.to_rfc3339())
+                    .bind(&tx_hash)
+                    .execute(pool)
+                    .await?;
+                sqlx::query("INSERT OR IGNORE INTO gas_spent (tx_hash, timestamp, strategy, gas_used, effective_gas_price, cost_wei, cost_eth) VALUES (?, ?, ?, ?, ?, ?, ?)")
+                    .bind(&tx_hash)
+                    .bind(now.to_rfc3339())
+                    .bind(&strategy)
+                    .bind(gas_used.low_u64() as i64)
+                    .bind(gas_price.to_string())
+                    .bind(cost.to_string())
+                    .bind(cost.low_u128() as f64 / 1e18)
+                    .execute(pool)
+                    .await?;
+                // Per-strategy burn for the runway projection and the allocator
+                runway::record_receipt_gas(&strategy, &receipt);
+                for (position, event) in DecodedReceipt::decode(&receipt).events.iter().enumerate() {
+                    let details = json!(event);
+                    sqlx::query("INSERT OR IGNORE INTO receipt_events (tx_hash, position, strategy, event, details) VALUES (?, ?, ?, ?, ?)")
+                        .bind(&tx_hash)
+                        .bind(position as i64)
+                        .bind(&strategy)
+                        .bind(details["event"].as_str().unwrap_or("unknown").to_string())
+                        .bind(details.to_string())
+                        .execute(pool)
+                        .await?;
+                }
+            }
+            None if included => {
+                warn!("Transaction {} was reorged out before it was final", tx_hash);
+                sqlx::query("UPDATE transactions SET status = 'pending', block = NULL WHERE tx_hash = ?")
+                    .bind(&tx_hash)
+                    .execute(pool)
+                    .await?;
+            }
+            None => {
+                let expired = DateTime::parse_from_rfc3339(&sent).map_or(true, |sent| now - sent.with_timezone(&Utc) > drop_after);
+                if expired {
+                    sqlx::query("UPDATE transactions SET status = 'dropped', resolved_at = ? WHERE tx_hash = ?")
+                        .bind(now.to_rfc3339())
+                        .bind(&tx_hash)
+                        .execute(pool)
+                        .await?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Write records as strategies hand them over and settle sent transactions every receipt_poll_secs,
+// until the process exits
+pub async fn run_storage(web3: web3::Web3<FailoverTransport>) {
+    let config = load_storage_config();
+    let pool = match connect(&config).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            error!("Storage disabled, could not open the database: {}", e);
+            return;
+        }
+    };
+    let (sender, mut records): (UnboundedSender<StorageRecord>, UnboundedReceiver<StorageRecord>) = mpsc::unbounded_channel();
+    let _ = POOL.set(pool.clone());
+    let _ = SENDER.set(sender);
+    info!("Recording trades and PnL to {}", config["database_url"].as_str().unwrap_or("sqlite://data/taz.db"));
+
+    let drop_after = ChronoDuration::seconds(config["drop_after_secs"].as_i64().unwrap_or(600));
+    let mut receipts = interval(Duration::from_secs(config["receipt_poll_secs"].as_u64().unwrap_or(12)));
+    loop {
+        tokio::select! {
+            record = records.recv() => match record {
+                Some(record) => {
+                    if let Err(e) = insert(&pool, record).await {
+                        error!("Failed to store record: {}", e);
+                    }
+                }
+                None => break,
+            },
+            _ = receipts.tick() => {
+                if let Err(e) = settle_pending(&web3, &pool, drop_after).await {
+                    warn!("Failed to settle pending transactions: {}", e);
+                }
+            }
+        }
+    }
+}
+
+fn pool() -> Result<&'static SqlitePool, StorageError> {
+    POOL.get().ok_or(StorageError::NotRunning)
+}
+
+// Realized PnL across every strategy, in ETH
+pub async fn realized_pnl_eth() -> Result<f64, StorageError> {
+    let total: Option<f64> = sqlx::query_scalar("SELECT SUM(pnl_eth) FROM pnl").fetch_one(pool()?).await?;
+    Ok(total.unwrap_or(0.0))
+}
+
+// Gas spent by every transaction we sent, in ETH
+pub async fn gas_spent_eth() -> Result<f64, StorageError> {
+    let total: Option<f64> = sqlx::query_scalar("SELECT SUM(cost_eth) FROM gas_spent").fetch_one(pool()?).await?;
+    Ok(total.unwrap_or(0.0))
+}
+
+// Events decoded from a settled transaction's receipt, in log order
+pub async fn receipt_events(tx_hash: &str) -> Result<Value, StorageError> {
+    let rows = sqlx::query("SELECT details FROM receipt_events WHERE tx_hash = ? ORDER BY position")
+        .bind(tx_hash.to_lowercase())
+        .fetch_all(pool()?)
+        .await?;
+    let mut events = Vec::new();
+    for row in rows {
+        events.push(serde_json::from_str::<Value>(&row.try_get::<String, _>("details")?).unwrap_or(Value::Null));
+    }
+    Ok(json!({ "tx_hash": tx_hash, "events": events }))
+}
+
+// Per strategy: opportunities by outcome, transactions by status, realized PnL and gas spent
+pub async fn summary() -> Result<Value, StorageError> {
+    let pool = pool()?;
+    let mut strategies: HashMap<String, Value> = HashMap::new();
+
+    for row in sqlx::query("SELECT strategy, outcome, COUNT(*) AS count FROM opportunities GROUP BY strategy, outcome").fetch_all(pool).await? {
+        let entry = strategies.entry(row.try_get("strategy")?).or_insert_with(|| json!({}));
+        entry["opportunities"][row.try_get::<String, _>("outcome")?] = json!(row.try_get::<i64, _>("count")?);
+    }
+    for row in sqlx::query("SELECT strategy, status, COUNT(*) AS count FROM transactions GROUP BY strategy, status").fetch_all(pool).await? {
+        let entry = strategies.entry(row.try_get("strategy")?).or_insert_with(|| json!({}));
+        entry["transactions"][row.try_get::<String, _>("status")?] = json!(row.try_get::<i64, _>("count")?);
+    }
+    for row in sqlx::query("SELECT strategy, COUNT(*) AS trades, SUM(pnl_eth) AS pnl_eth FROM pnl GROUP BY strategy").fetch_all(pool).await? {
+        let entry = strategies.entry(row.try_get("strategy")?).or_insert_with(|| json!({}));
+        entry["trades"] = json!(row.try_get::<i64, _>("trades")?);
+        entry["pnl_eth"] = json!(row.try_get::<f64, _>("pnl_eth")?);
+    }
+    for row in sqlx::query("SELECT strategy, SUM(gas_used) AS gas_used, SUM(cost_eth) AS gas_eth FROM gas_spent GROUP BY strategy").fetch_all(pool).await? {
+        let entry = strategies.entry(row.try_get("strategy")?).or_insert_with(|| json!({}));
+        entry["gas_used"] = json!(row.try_get::<i64, _>("gas_used")?);
+        entry["gas_eth"] = json!(row.try_get::<f64, _>("gas_eth")?);
+    }
+
+    Ok(json!({
+        "strategies": strategies,
+        "realized_pnl_eth": realized_pnl_eth().await?,
+        "gas_spent_eth": gas_spent_eth().await?,
+    }))
+}
+
+// Custom error type for storage
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Chain error: {0}")]
+    ChainError(#[from] ChainError),
+    #[error("Storage is not running")]
+    NotRunning,
+}
+
+// Implement conversion for StorageError to Web3 error
+impl From<StorageError> for web3::Error {
+    fn from(error: StorageError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
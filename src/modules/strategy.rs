@@ -0,0 +1,262 @@
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde_json::{json, Value};
+use std::any::Any;
+use std::fs;
+use std::sync::Arc;
+use web3::types::{Address, Transaction, H160, U256};
+use web3::Web3;
+use log::{error, info, warn};
+use thiserror::Error;
+use tokio::task;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::arbitrage::{ArbitrageError, ArbitrageStrategy};
+use crate::modules::config::{ConfigError, GlobalConfig};
+use crate::modules::control;
+use crate::modules::events;
+use crate::modules::flashloan::{FlashloanError, FlashloanStrategy};
+use crate::modules::frontrunning::{FrontrunningError, FrontrunningStrategy};
+use crate::modules::hft::{HFTError, HftStrategy};
+use crate::modules::liquidation::{LiquidationError, LiquidationStrategy};
+use crate::modules::mempool_mode;
+use crate::modules::providers::{FailoverTransport, ProviderError, ProviderPool};
+use crate::modules::sandwich::{SandwichError, SandwichStrategy};
+use crate::modules::scan_interval::AdaptiveInterval;
+use crate::modules::signer;
+use crate::modules::storage;
+use crate::modules::templates;
+
+// Load the orchestrator settings
+fn load_orchestrator_config() -> Value {
+    let config_path = "config/orchestrator_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read orchestrator config file");
+    serde_json::from_str(&config_data).expect("Unable to parse orchestrator config file")
+}
+
+// Something a scan found, with whatever execute needs to act on it
+#[derive(Clone, Debug, Default)]
+pub struct Opportunity {
+    pub amount: U256,
+    // Borrower or victim sender the opportunity is about
+    pub target: Option<H160>,
+    pub asset: Option<Address>,
+    // Pending transaction being front-run
+    pub transaction: Option<Transaction>,
+    pub tip_per_gas: U256,
+    // Already traded during the scan; counts as a hit but there is nothing left to execute
+    pub executed: bool,
+}
+
+impl Opportunity {
+    pub fn sized(amount: U256) -> Self {
+        Opportunity { amount, ..Default::default() }
+    }
+}
+
+// A strategy the orchestrator can run: scan for opportunities, execute each one found
+#[async_trait]
+pub trait Strategy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Enabled in the global config, and able to see pending transactions if it needs them
+    fn is_enabled(&self, config: &GlobalConfig) -> bool {
+        let switched_on = config.strategies.get(self.name()).is_some_and(|entry| entry.enabled);
+        switched_on && (!mempool_mode::requires_mempool(self.name()) || mempool_mode::mempool_available())
+    }
+
+    // The strategy's config, for scan interval bounds
+    fn config(&self) -> Value;
+
+    async fn scan(&self, web3: &Web3<FailoverTransport>) -> Result<Vec<Opportunity>, StrategyError>;
+
+    async fn execute(&self, web3: &Web3<FailoverTransport>, opportunity: Opportunity) -> Result<(), StrategyError>;
+}
+
+// Build a strategy from its typed config; None for names that aren't strategies
+pub fn build(name: &str, config: &GlobalConfig) -> Result<Option<Arc<dyn Strategy>>, ConfigError> {
+    let strategy: Arc<dyn Strategy> = match name {
+        "arbitrage" => Arc::new(ArbitrageStrategy::new(config.strategy()?)),
+        "flashloan" => Arc::new(FlashloanStrategy::new(config.strategy()?)),
+        "frontrunning" => Arc::new(FrontrunningStrategy::new(config.strategy()?)),
+        "liquidation" => Arc::new(LiquidationStrategy::new(config.strategy()?)),
+        "sandwich" => Arc::new(SandwichStrategy::new(config.strategy()?)),
+        "hft" => Arc::new(HftStrategy::new(config.strategy()?)),
+        _ => return Ok(None),
+    };
+    Ok(Some(strategy))
+}
+
+// Every enabled strategy; one with an invalid config is reported and left out
+pub fn enabled(config: &GlobalConfig) -> Vec<Arc<dyn Strategy>> {
+    config
+        .enabled_strategies()
+        .iter()
+        .filter_map(|name| match build(name, config) {
+            Ok(Some(strategy)) if strategy.is_enabled(config) => Some(strategy),
+            Ok(Some(_)) => {
+                info!("Skipping {}: no pending transaction visibility", name);
+                None
+            }
+            Ok(None) => {
+                error!("Unknown strategy: {}", name);
+                None
+            }
+            Err(e) => {
+                error!("Skipping {}: {}", name, e);
+                None
+            }
+        })
+        .collect()
+}
+
+// Run strategies side by side, each under its own supervisor; returns once every one has stopped
+pub async fn run_orchestrator(providers: Arc<ProviderPool>, strategies: Vec<Arc<dyn Strategy>>) {
+    let config = load_orchestrator_config();
+    let supervisors = strategies
+        .into_iter()
+        .map(|strategy| {
+            info!("Starting {} strategy", strategy.name());
+            task::spawn(supervise(providers.clone(), strategy, config.clone()))
+        })
+        .collect::<Vec<_>>();
+    join_all(supervisors).await;
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => panic.downcast_ref::<&str>().map(|message| message.to_string()).unwrap_or_else(|| String::from("unknown panic")),
+    }
+}
+
+// Restart a strategy that panics, backing off exponentially, until max_restarts is reached
+async fn supervise(providers: Arc<ProviderPool>, strategy: Arc<dyn Strategy>, config: Value) {
+    let name = strategy.name();
+    let check_interval = config["scan_interval_secs"].as_u64().unwrap_or(1);
+    let base_backoff = config["restart_backoff_secs"].as_u64().unwrap_or(5).max(1);
+    let max_backoff = config["max_restart_backoff_secs"].as_u64().unwrap_or(300);
+    let max_restarts = config["max_restarts"].as_u64().unwrap_or(0);
+    let mut restarts = 0u64;
+
+    loop {
+        let web3 = match providers.web3_for(name) {
+            Ok(web3) => web3,
+            Err(e) => {
+                error!("No RPC provider for {}, not starting it: {}", name, e);
+                return;
+            }
+        };
+        let run = task::spawn(run_strategy(strategy.clone(), web3, check_interval));
+        let panic = match run.await {
+            Ok(()) => {
+                info!("{} strategy stopped", name);
+                return;
+            }
+            Err(e) if e.is_panic() => panic_message(e.into_panic()),
+            Err(e) => {
+                warn!("{} strategy task was cancelled: {}", name, e);
+                return;
+            }
+        };
+
+        restarts += 1;
+        let giving_up = max_restarts > 0 && restarts > max_restarts;
+        error!("{} strategy panicked (restart {}): {}", name, restarts, panic);
+        events::emit("strategy_panicked", json!({ "strategy": name, "restarts": restarts, "panic": panic, "giving_up": giving_up }));
+        let (subject, body) = templates::render("strategy_restart", &[
+            ("strategy", name.to_string()),
+            ("restarts", restarts.to_string()),
+            ("panic", panic.clone()),
+            ("action", String::from(if giving_up { "not restarted, max_restarts reached" } else { "restarting" })),
+        ]);
+        let severity = if giving_up { Severity::Critical } else { Severity::Warn };
+        alerts::raise(&format!("strategy_restart:{}", name), severity, &subject, &body);
+        if giving_up {
+            return;
+        }
+
+        let backoff = base_backoff.saturating_mul(1 << (restarts - 1).min(16)).min(max_backoff);
+        sleep(Duration::from_secs(backoff)).await;
+    }
+}
+
+// One scan, executing whatever it finds; returns how many opportunities were found. Used by the
+// orchestrator loop and by manual triggers
+pub async fn run_once(strategy: &dyn Strategy, web3: &Web3<FailoverTransport>) -> Result<usize, StrategyError> {
+    let name = strategy.name();
+    let opportunities = strategy.scan(web3).await?;
+    let found = opportunities.len();
+    for opportunity in opportunities {
+        // Read-only instances record what they would have executed
+        if signer::read_only() {
+            storage::record_opportunity(name, &opportunity, "observed", None);
+            continue;
+        }
+        if opportunity.executed {
+            storage::record_opportunity(name, &opportunity, "executed_in_scan", None);
+            continue;
+        }
+        match strategy.execute(web3, opportunity.clone()).await {
+            Ok(()) => storage::record_opportunity(name, &opportunity, "executed", None),
+            Err(e) => {
+                error!("{} execution failed: {}", name, e);
+                storage::record_opportunity(name, &opportunity, "failed", Some(e.to_string()));
+            }
+        }
+    }
+    Ok(found)
+}
+
+// Scan and execute on the strategy's adaptive interval, idling while it is paused
+async fn run_strategy(strategy: Arc<dyn Strategy>, web3: Arc<Web3<FailoverTransport>>, check_interval: u64) {
+    let name = strategy.name();
+    let mut interval = AdaptiveInterval::from_config(name, &strategy.config(), check_interval);
+
+    loop {
+        if control::is_strategy_paused(name) {
+            sleep(interval.current()).await;
+            continue;
+        }
+        let found = match run_once(strategy.as_ref(), &web3).await {
+            Ok(found) => found > 0,
+            Err(e) => {
+                error!("{} scan failed: {}", name, e);
+                false
+            }
+        };
+        sleep(interval.record(found)).await;
+    }
+}
+
+// Custom error type for strategies run by the orchestrator
+#[derive(Error, Debug)]
+pub enum StrategyError {
+    #[error("Arbitrage error: {0}")]
+    ArbitrageError(#[from] ArbitrageError),
+    #[error("Flashloan error: {0}")]
+    FlashloanError(#[from] FlashloanError),
+    #[error("Frontrunning error: {0}")]
+    FrontrunningError(#[from] FrontrunningError),
+    #[error("Liquidation error: {0}")]
+    LiquidationError(#[from] LiquidationError),
+    #[error("Sandwich error: {0}")]
+    SandwichError(#[from] SandwichError),
+    #[error("HFT error: {0}")]
+    HFTError(#[from] HFTError),
+    #[error("Provider error: {0}")]
+    ProviderError(#[from] ProviderError),
+    #[error("Config error: {0}")]
+    ConfigError(#[from] ConfigError),
+    #[error("Opportunity is missing its {0}")]
+    IncompleteOpportunity(&'static str),
+}
+
+// Implement conversion for StrategyError to Web3 error
+impl From<StrategyError> for web3::Error {
+    fn from(error: StrategyError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
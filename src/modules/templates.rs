@@ -0,0 +1,123 @@
+use serde_json::Value;
+use std::fs;
+use std::str::FromStr;
+use web3::types::{Address, H256, U256};
+use log::warn;
+
+use crate::modules::equity;
+
+// Load the notification template configuration
+fn load_templates_config() -> Value {
+    let config_path = "config/templates_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read templates config file");
+    serde_json::from_str(&config_data).expect("Unable to parse templates config file")
+}
+
+// Render a named template, substituting each {placeholder} with its value. Unknown templates fall
+// back to listing the values so the alert still goes out
+pub fn render(name: &str, vars: &[(&str, String)]) -> (String, String) {
+    let config = load_templates_config();
+    let template = &config["templates"][name];
+    let (subject, body) = match (template["subject"].as_str(), template["body"].as_str()) {
+        (Some(subject), Some(body)) => (subject.to_string(), body.to_string()),
+        _ => {
+            warn!("No notification template named {}", name);
+            let body = vars.iter().map(|(key, value)| format!("{}: {}", key, value)).collect::<Vec<_>>().join("\n");
+            return (name.to_string(), body);
+        }
+    };
+
+    let fill = |text: String| vars.iter().fold(text, |text, (key, value)| text.replace(&format!("{{{}}}", key), value));
+    (fill(subject), fill(body))
+}
+
+fn explorer_url(config: &Value) -> String {
+    config["explorer_url"].as_str().unwrap_or("https://etherscan.io").trim_end_matches('/').to_string()
+}
+
+pub fn tx_link(hash: H256) -> String {
+    format!("{}/tx/{:?}", explorer_url(&load_templates_config()), hash)
+}
+
+pub fn address_link(address: Address) -> String {
+    format!("{}/address/{:?}", explorer_url(&load_templates_config()), address)
+}
+
+// Exact decimal rendering of a base-unit amount, truncated to `precision` fractional digits
+pub fn format_units(amount: U256, decimals: u32, precision: usize) -> String {
+    let digits = format!("{:0>width$}", amount.to_string(), width = decimals as usize + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals as usize);
+    let fraction = fraction[..fraction.len().min(precision)].trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+// USD with thousands separators and cents
+pub fn format_usd(value: f64) -> String {
+    let cents = (value.abs() * 100.0).round() as u128;
+    let dollars = (cents / 100).to_string();
+    let mut grouped = String::new();
+    for (i, digit) in dollars.chars().enumerate() {
+        if i > 0 && (dollars.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("{}${}.{:02}", if value < 0.0 { "-" } else { "" }, grouped, cents % 100)
+}
+
+fn with_usd(amount: String, symbol: &str, usd: Option<f64>) -> String {
+    match usd {
+        Some(usd) => format!("{} {} ({})", amount, symbol, format_usd(usd)),
+        None => format!("{} {}", amount, symbol),
+    }
+}
+
+fn to_f64(amount: U256, decimals: u32) -> f64 {
+    amount.to_string().parse::<f64>().unwrap_or(0.0) / 10f64.powi(decimals as i32)
+}
+
+// "1.2345 ETH ($3,210.55)", marked with the latest ETH/USD from the equity tracker
+pub fn eth_amount(wei: U256) -> String {
+    let precision = load_templates_config()["display_decimals"].as_u64().unwrap_or(4) as usize;
+    let usd = equity::latest_eth_usd().map(|eth_usd| to_f64(wei, 18) * eth_usd);
+    with_usd(format_units(wei, 18, precision), "ETH", usd)
+}
+
+fn token_entry(config: &Value, token: Address) -> Option<Value> {
+    config["tokens"]
+        .as_array()?
+        .iter()
+        .find(|entry| entry["address"].as_str().and_then(|address| Address::from_str(address).ok()) == Some(token))
+        .cloned()
+}
+
+// Configured symbol for a token, if it is one we know
+pub fn token_symbol(token: Address) -> Option<String> {
+    token_entry(&load_templates_config(), token).and_then(|entry| entry["symbol"].as_str().map(String::from))
+}
+
+// Token amount with the configured symbol, decimals and USD value ("usd" is a fixed price or "eth"
+// to mark at ETH/USD). Unknown tokens show the raw amount and an explorer link
+pub fn token_amount(token: Address, amount: U256) -> String {
+    let config = load_templates_config();
+    let precision = config["display_decimals"].as_u64().unwrap_or(4) as usize;
+    let entry = token_entry(&config, token);
+
+    match entry {
+        Some(entry) => {
+            let decimals = entry["decimals"].as_u64().unwrap_or(18) as u32;
+            let price = match &entry["usd"] {
+                Value::String(source) if source == "eth" => equity::latest_eth_usd(),
+                other => other.as_f64(),
+            };
+            let usd = price.map(|price| to_f64(amount, decimals) * price);
+            with_usd(format_units(amount, decimals, precision), entry["symbol"].as_str().unwrap_or("?"), usd)
+        }
+        None => format!("{} units of {}", amount, address_link(token)),
+    }
+}
@@ -0,0 +1,114 @@
+use serde_json::Value;
+use web3::types::U256;
+use log::info;
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+
+use crate::modules::config;
+use crate::modules::ens;
+use crate::modules::providers::FailoverTransport;
+
+// Load the testnet presets. Both files are read without presets applied (the config loader applies
+// them through here) and with their comments removed
+fn load_testnet_config() -> Value {
+    config::read_value("config/testnet_config.json").expect("Unable to load testnet config file")
+}
+
+fn load_global_config() -> Value {
+    config::read_value(config::GLOBAL_CONFIG_PATH).expect("Unable to load global config file")
+}
+
+// The preset for the configured network, when it is a testnet we ship one for
+pub fn active_preset() -> Option<(String, Value)> {
+    let network = load_global_config()["network"].as_str().unwrap_or("mainnet").to_string();
+    let preset = load_testnet_config()["networks"][&network].clone();
+    preset.is_object().then_some((network, preset))
+}
+
+pub fn is_testnet() -> bool {
+    active_preset().is_some()
+}
+
+// Overlay the active preset's overrides for a config file, keyed by file name (e.g.
+// "venues_config.json"), on the loaded config. Top-level keys are replaced; a null override removes
+// the key, which is how a preset marks a contract as not deployed on the testnet. Mainnet configs
+// pass through untouched
+pub fn apply(config_path: &str, mut config: Value) -> Value {
+    let file = config_path.rsplit('/').next().unwrap_or(config_path);
+    let overrides = match active_preset() {
+        Some((_, preset)) => preset["overrides"][file].clone(),
+        None => return config,
+    };
+    if let (Some(config), Some(overrides)) = (config.as_object_mut(), overrides.as_object()) {
+        for (key, value) in overrides {
+            if value.is_null() {
+                config.remove(key);
+            } else {
+                config.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    config
+}
+
+// `taz faucet [--wait]`: show the wallet's testnet balance and where to get test ETH, and with
+// --wait keep polling until the balance reaches the preset's min_balance_wei
+pub async fn run_faucet_command(global_config: Value, args: Vec<String>) -> Result<(), TestnetError> {
+    let (network, preset) = active_preset().ok_or_else(|| TestnetError::NotTestnet(global_config["network"].as_str().unwrap_or("mainnet").to_string()))?;
+    let wallet = global_config["wallet_address"]
+        .as_str()
+        .and_then(ens::parse_address)
+        .ok_or(TestnetError::MissingConfig("wallet_address"))?;
+    let min_balance = preset["min_balance_wei"]
+        .as_str()
+        .and_then(|amount| U256::from_dec_str(amount).ok())
+        .unwrap_or_else(|| U256::exp10(17));
+
+    let url = format!("https://{}.infura.io/v3/{}", network, global_config["infura_project_id"].as_str().unwrap_or_default());
+    let web3 = web3::Web3::new(FailoverTransport::http(&url)?);
+    let chain_id = web3.eth().chain_id().await?;
+    if preset["chain_id"].as_u64().is_some_and(|expected| U256::from(expected) != chain_id) {
+        return Err(TestnetError::WrongChain { network, chain_id: chain_id.as_u64() });
+    }
+
+    let balance = web3.eth().balance(wallet, None).await?;
+    println!("Wallet {:?} on {} (chain {}): {} ETH", wallet, network, chain_id, balance.as_u128() as f64 / 1e18);
+    println!("Faucets:");
+    for faucet in preset["faucets"].as_array().cloned().unwrap_or_default() {
+        println!("  {}", faucet.as_str().unwrap_or_default());
+    }
+    if balance >= min_balance || !args.iter().any(|arg| arg == "--wait") {
+        return Ok(());
+    }
+
+    println!("Waiting for at least {} ETH to arrive...", min_balance.as_u128() as f64 / 1e18);
+    loop {
+        sleep(Duration::from_secs(15)).await;
+        let balance = web3.eth().balance(wallet, None).await?;
+        if balance >= min_balance {
+            info!("Faucet funds arrived on {}", network);
+            println!("Funded: {} ETH", balance.as_u128() as f64 / 1e18);
+            return Ok(());
+        }
+    }
+}
+
+// Custom error type for testnet presets
+#[derive(Error, Debug)]
+pub enum TestnetError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Network {0} has no testnet preset (see config/testnet_config.json)")]
+    NotTestnet(String),
+    #[error("Endpoint for {network} reports chain id {chain_id}")]
+    WrongChain { network: String, chain_id: u64 },
+    #[error("Missing or invalid config field: {0}")]
+    MissingConfig(&'static str),
+}
+
+// Implement conversion for TestnetError to Web3 error
+impl From<TestnetError> for web3::Error {
+    fn from(error: TestnetError) -> Self {
+        web3::Error::Decoder(format!("{:?}", error))
+    }
+}
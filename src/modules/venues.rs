@@ -0,0 +1,346 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use web3::types::{Address, U256};
+use log::{info, warn};
+use thiserror::Error;
+
+use crate::modules::dex;
+use crate::modules::ens;
+use crate::modules::execution::{Call, ExecutionError, SwapOrder};
+use crate::modules::pool_state;
+use crate::modules::providers::FailoverTransport;
+use crate::modules::testnet;
+
+// Swap fee of the canonical Uniswap V2 pair, in hundredths of a bip like V3 fee tiers
+pub const DEFAULT_V2_FEE: u32 = 3000;
+
+// Load the venue configuration (factories, routers and fee tiers per DEX)
+fn load_venues_config() -> Value {
+    let config_path = "config/venues_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read venues config file");
+    testnet::apply(config_path, serde_json::from_str(&config_data).expect("Unable to parse venues config file"))
+}
+
+// Every configured DEX entry
+pub fn dex_configs() -> Vec<Value> {
+    load_venues_config()["dexes"].as_array().cloned().unwrap_or_default()
+}
+
+// Router version (and fee tier for V3) a pool trades on. Curve pools keep their fee and the coin
+// indices of the lower and higher token address
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VenueVersion {
+    V2,
+    V3 { fee: u32 },
+    Curve { fee: u32, low_index: u8, high_index: u8 },
+}
+
+// A pool that exists for a pair, with what is needed to quote and trade it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Venue {
+    pub dex: String,
+    pub version: VenueVersion,
+    pub pool: Address,
+    pub router: Address,
+    // V3 quoter; V2 quotes go through the router itself
+    pub quoter: Option<Address>,
+    // The quoter is QuoterV2, which takes a params struct and also reports ticks crossed
+    #[serde(default)]
+    pub quoter_v2: bool,
+    // V2 pair fee in hundredths of a bip (3000 = 0.3%), from the DEX config or detected from the
+    // pair; V3 pools use their fee tier instead
+    #[serde(default = "default_v2_fee")]
+    pub v2_fee: u32,
+    // Fee the router takes out of the output on top of the pool fee, same units
+    #[serde(default)]
+    pub router_fee: u32,
+}
+
+// Venues recorded before fees were part of the metadata were all priced at 0.3%
+fn default_v2_fee() -> u32 {
+    DEFAULT_V2_FEE
+}
+
+impl Venue {
+    pub fn label(&self) -> String {
+        match self.version {
+            VenueVersion::V2 if self.v2_fee != DEFAULT_V2_FEE => format!("{} v2 {}bps", self.dex, self.v2_fee as f64 / 100.0),
+            VenueVersion::V2 => format!("{} v2", self.dex),
+            VenueVersion::V3 { fee } => format!("{} v3 {}bps", self.dex, fee as f64 / 100.0),
+            VenueVersion::Curve { fee, .. } => format!("{} curve {}bps", self.dex, fee as f64 / 100.0),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "venue": self.label(),
+            "pool": self.pool,
+            "router": self.router,
+            "pool_fee": self.pool_fee(),
+            "router_fee": self.router_fee,
+        })
+    }
+
+    // Swap fee charged by the pool in hundredths of a bip
+    pub fn pool_fee(&self) -> u32 {
+        match self.version {
+            VenueVersion::V2 => self.v2_fee,
+            VenueVersion::V3 { fee } | VenueVersion::Curve { fee, .. } => fee,
+        }
+    }
+
+    // Everything taken out of a swap on this venue, pool and router fee together
+    pub fn total_fee(&self) -> u32 {
+        self.pool_fee().saturating_add(self.router_fee)
+    }
+
+    // Amount of token_out received for amount_in on this venue, through its DEX adapter
+    pub async fn quote(&self, web3: &web3::Web3<FailoverTransport>, token_in: Address, token_out: Address, amount_in: U256) -> Result<U256, VenueError> {
+        // Pools followed by the pool state sync quote from memory
+        if let Some(amount_out) = pool_state::quote(self, token_in, token_out, amount_in) {
+            return Ok(amount_out);
+        }
+        dex::for_venue(self).quote(web3, self, token_in, token_out, amount_in).await
+    }
+
+    // Quote from cached pool state (checkpointed or synced from events) without an RPC round trip:
+    // exact constant-product math on V2 reserves, spot price less the fee tier on V3, both net of
+    // the router fee. Curve's invariant isn't modelled, so its pools always quote over RPC
+    pub fn quote_from_state(&self, state: &Value, token_in: Address, token_out: Address, amount_in: U256) -> Option<U256> {
+        let field = |key: &str| state[key].as_str().and_then(|value| U256::from_dec_str(value).ok());
+        // Pools order their tokens by address
+        let zero_for_one = token_in < token_out;
+        match self.version {
+            VenueVersion::V2 => {
+                let (reserve0, reserve1) = (field("reserve0")?, field("reserve1")?);
+                let (reserve_in, reserve_out) = if zero_for_one { (reserve0, reserve1) } else { (reserve1, reserve0) };
+                Some(less_fee(v2_amount_out(amount_in, reserve_in, reserve_out, self.v2_fee), self.router_fee))
+            }
+            VenueVersion::V3 { fee } => Some(less_fee(v3_spot_amount_out(amount_in, field("sqrt_price_x96")?, zero_for_one, fee), self.router_fee)),
+            VenueVersion::Curve { .. } => None,
+        }
+    }
+
+    // Approve + swap calls for this venue, through its DEX adapter
+    pub fn swap_calls(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        amount_out_min: U256,
+        recipient: Address,
+        deadline: U256,
+    ) -> Result<Vec<Call>, ExecutionError> {
+        let order = SwapOrder { token_in, token_out, amount_in, amount_out_min, recipient, deadline };
+        dex::for_venue(self).build_swap_tx(self, &order)
+    }
+}
+
+// An amount less a fee in hundredths of a bip
+pub fn less_fee(amount: U256, fee: u32) -> U256 {
+    amount.saturating_mul(U256::from(1_000_000 - fee.min(1_000_000))) / U256::from(1_000_000)
+}
+
+// Uniswap V2 getAmountOut generalized to the pair's fee in hundredths of a bip (3000 for
+// Uniswap's 0.3%), constant product
+pub fn v2_amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256, fee: u32) -> U256 {
+    if amount_in.is_zero() || reserve_in.is_zero() || reserve_out.is_zero() {
+        return U256::zero();
+    }
+    let amount_in_with_fee = amount_in.saturating_mul(U256::from(1_000_000 - fee.min(1_000_000)));
+    let numerator = amount_in_with_fee.saturating_mul(reserve_out);
+    let denominator = reserve_in.saturating_mul(U256::from(1_000_000)).saturating_add(amount_in_with_fee);
+    numerator / denominator
+}
+
+// Output at the V3 pool's current price (no tick crossing), net of the fee tier in hundredths of a bip
+pub fn v3_spot_amount_out(amount_in: U256, sqrt_price_x96: U256, zero_for_one: bool, fee: u32) -> U256 {
+    if sqrt_price_x96.is_zero() {
+        return U256::zero();
+    }
+    let amount_in = less_fee(amount_in, fee);
+    let q96 = U256::one() << 96;
+    // price = (sqrtPriceX96 / 2^96)^2 token1 per token0, applied in two steps to stay inside 256 bits
+    if zero_for_one {
+        amount_in.saturating_mul(sqrt_price_x96) / q96 * sqrt_price_x96 / q96
+    } else {
+        amount_in.saturating_mul(q96) / sqrt_price_x96 * q96 / sqrt_price_x96
+    }
+}
+
+// Pool cache: venues found per (ordered) pair and when they were looked up
+type PoolCache = HashMap<(Address, Address), (Instant, Vec<Venue>)>;
+
+fn pool_cache() -> &'static Mutex<PoolCache> {
+    static POOL_CACHE: OnceLock<Mutex<PoolCache>> = OnceLock::new();
+    POOL_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn pair_key(token_a: Address, token_b: Address) -> (Address, Address) {
+    if token_a < token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    }
+}
+
+fn config_address(entry: &Value, key: &str) -> Option<Address> {
+    entry[key].as_str().and_then(ens::parse_address)
+}
+
+// Look up the pair's pools on every configured DEX adapter. An adapter whose lookups fail
+// (typically a factory that isn't deployed on a testnet) is skipped rather than failing the pair
+pub async fn detect_venues(web3: &web3::Web3<FailoverTransport>, token_a: Address, token_b: Address) -> Result<Vec<Venue>, VenueError> {
+    let mut venues = Vec::new();
+
+    for adapter in dex::configured() {
+        match adapter.pools(web3, token_a, token_b).await {
+            Ok(found) => venues.extend(found),
+            Err(e) => warn!("Skipping {} for {:?}/{:?}: {}", adapter.name(), token_a, token_b, e),
+        }
+    }
+
+    info!("Detected {} venues for {:?}/{:?}", venues.len(), token_a, token_b);
+    Ok(venues)
+}
+
+// DEX and router version a configured router address belongs to, such as "uniswap v3"
+pub fn router_label(router: Address) -> Option<String> {
+    let config = load_venues_config();
+    config["dexes"].as_array()?.iter().find_map(|dex| {
+        let name = dex["name"].as_str().unwrap_or("unknown");
+        [("v2_router", "v2"), ("v3_router", "v3")]
+            .iter()
+            .find(|(key, _)| config_address(dex, key) == Some(router))
+            .map(|(_, version)| format!("{} {}", name, version))
+    })
+}
+
+// Venues for a pair, from the pool cache when fresh
+pub async fn venues_for(web3: &web3::Web3<FailoverTransport>, token_a: Address, token_b: Address) -> Result<Vec<Venue>, VenueError> {
+    let ttl = load_venues_config()["cache_ttl_secs"].as_u64().unwrap_or(3600);
+    let key = pair_key(token_a, token_b);
+
+    if let Some((looked_up, venues)) = pool_cache().lock().unwrap().get(&key) {
+        if looked_up.elapsed().as_secs() < ttl {
+            return Ok(venues.clone());
+        }
+    }
+
+    let venues = detect_venues(web3, key.0, key.1).await?;
+    pool_cache().lock().unwrap().insert(key, (Instant::now(), venues.clone()));
+    Ok(venues)
+}
+
+// Quote every venue for the pair; venues that fail to quote are skipped. V2 pools not followed in
+// memory have their reserves read in one multicall and are quoted with the pair math, instead of a
+// getAmountsOut per router
+pub async fn quote_all_venues(
+    web3: &web3::Web3<FailoverTransport>,
+    token_in: Address,
+    token_out: Address,
+    amount_in: U256,
+) -> Result<Vec<(Venue, U256)>, VenueError> {
+    let venues = venues_for(web3, token_in, token_out).await?;
+    let batched: Vec<Venue> = venues
+        .iter()
+        .filter(|venue| venue.version == VenueVersion::V2 && pool_state::quote(venue, token_in, token_out, amount_in).is_none())
+        .cloned()
+        .collect();
+    let states = match pool_state::read_states(web3, &batched).await {
+        Ok(states) => states,
+        Err(e) => {
+            warn!("Multicall reserve read failed, quoting V2 pools one by one: {}", e);
+            HashMap::new()
+        }
+    };
+
+    let mut quotes = Vec::new();
+    for venue in venues {
+        let batched_quote = states.get(&venue.pool).and_then(|state| venue.quote_from_state(state, token_in, token_out, amount_in));
+        let quote = match batched_quote {
+            Some(amount_out) => Ok(amount_out),
+            None => venue.quote(web3, token_in, token_out, amount_in).await,
+        };
+        match quote {
+            Ok(amount_out) if !amount_out.is_zero() => quotes.push((venue, amount_out)),
+            Ok(_) => {}
+            Err(e) => warn!("Quote failed on {}: {}", venue.label(), e),
+        }
+    }
+    Ok(quotes)
+}
+
+// Venue returning the most token_out for amount_in
+pub async fn best_quote(
+    web3: &web3::Web3<FailoverTransport>,
+    token_in: Address,
+    token_out: Address,
+    amount_in: U256,
+) -> Result<Option<(Venue, U256)>, VenueError> {
+    Ok(quote_all_venues(web3, token_in, token_out, amount_in)
+        .await?
+        .into_iter()
+        .max_by_key(|(_, amount_out)| *amount_out))
+}
+
+// Every cached pair with its venues, for checkpointing
+pub fn cached_pairs() -> Vec<((Address, Address), Vec<Venue>)> {
+    let cache = pool_cache().lock().unwrap();
+    cache.iter().map(|(pair, (_, venues))| (*pair, venues.clone())).collect()
+}
+
+// Seed the cache with venues restored from a checkpoint, as if they had just been looked up
+pub fn seed_cache(token_a: Address, token_b: Address, venues: Vec<Venue>) {
+    pool_cache().lock().unwrap().insert(pair_key(token_a, token_b), (Instant::now(), venues));
+}
+
+// V3 pools in the cache with the pair they trade
+pub fn cached_v3_pools() -> Vec<(Address, Address, Address)> {
+    let cache = pool_cache().lock().unwrap();
+    cache
+        .iter()
+        .flat_map(|((token_a, token_b), (_, venues))| {
+            venues
+                .iter()
+                .filter(|venue| matches!(venue.version, VenueVersion::V3 { .. }))
+                .map(move |venue| (venue.pool, *token_a, *token_b))
+        })
+        .collect()
+}
+
+// Cached venues for the dashboard
+pub fn cached_venues() -> Value {
+    let cache = pool_cache().lock().unwrap();
+    let pairs: Vec<Value> = cache
+        .iter()
+        .map(|((token_a, token_b), (_, venues))| {
+            json!({
+                "token_a": token_a,
+                "token_b": token_b,
+                "venues": venues.iter().map(|venue| venue.to_json()).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    json!(pairs)
+}
+
+// Custom error type for venue detection and quoting
+#[derive(Error, Debug)]
+pub enum VenueError {
+    #[error("Web3 error: {0}")]
+    Web3Error(#[from] web3::Error),
+    #[error("Contract error: {0}")]
+    ContractError(#[from] web3::contract::Error),
+    #[error("ABI error: {0}")]
+    ABIError(#[from] web3::ethabi::Error),
+    #[error("No v3_quoter configured for {0}")]
+    MissingQuoter(String),
+    #[error("Venue {0} can't be quoted by this adapter")]
+    WrongAdapter(String),
+}
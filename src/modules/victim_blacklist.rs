@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use web3::types::Transaction;
+use log::{error, info};
+use chrono::Utc;
+
+// Load the sandwich configuration (blacklist thresholds live there)
+fn load_sandwich_config() -> Value {
+    let config_path = "config/sandwich_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read sandwich config file");
+    serde_json::from_str(&config_data).expect("Unable to parse sandwich config file")
+}
+
+// Sandwich attempts against every victim matching one pattern
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct PatternStats {
+    attempts: u64,
+    reverts: u64,
+    last_attempt: i64,
+    // Set once the revert rate crosses the threshold; cleared when the entry expires
+    blacklisted_at: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BlacklistState {
+    patterns: HashMap<String, PatternStats>,
+    // Pending transactions skipped because they matched, since startup
+    #[serde(skip)]
+    skipped: u64,
+}
+
+fn blacklist_path(config: &Value) -> String {
+    config["blacklist_path"].as_str().unwrap_or("data/victim_blacklist.json").to_string()
+}
+
+fn state() -> &'static Mutex<BlacklistState> {
+    static STATE: OnceLock<Mutex<BlacklistState>> = OnceLock::new();
+    STATE.get_or_init(|| {
+        let state = fs::read_to_string(blacklist_path(&load_sandwich_config()))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Mutex::new(state)
+    })
+}
+
+fn save_state(path: &str, state: &BlacklistState) {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match serde_json::to_string_pretty(state) {
+        Ok(contents) => {
+            if let Err(e) = fs::write(path, contents) {
+                error!("Failed to write victim blacklist {}: {}", path, e);
+            }
+        }
+        Err(e) => error!("Failed to serialize victim blacklist: {}", e),
+    }
+}
+
+// Patterns a victim matches: the contract it calls (MEV-protected routers), the function it calls
+// there, and its sender (private RPC users whose transactions never reach the bundle)
+fn pattern_keys(victim: &Transaction) -> Vec<String> {
+    let mut keys = Vec::new();
+    if let Some(to) = victim.to {
+        keys.push(format!("contract:{:?}", to));
+        if victim.input.0.len() >= 4 {
+            let selector: String = victim.input.0[..4].iter().map(|byte| format!("{:02x}", byte)).collect();
+            keys.push(format!("call:{:?}:0x{}", to, selector));
+        }
+    }
+    if let Some(from) = victim.from {
+        keys.push(format!("sender:{:?}", from));
+    }
+    keys
+}
+
+// The blacklisted pattern a pending transaction matches, if any. Entries older than
+// blacklist_ttl_hours are dropped so a router that changed gets another chance
+pub fn is_blacklisted(victim: &Transaction) -> Option<String> {
+    let config = load_sandwich_config();
+    if !config["blacklist_enabled"].as_bool().unwrap_or(true) {
+        return None;
+    }
+    let ttl_secs = config["blacklist_ttl_hours"].as_i64().unwrap_or(168) * 3600;
+    let now = Utc::now().timestamp();
+
+    let mut state = state().lock().unwrap();
+    let before = state.patterns.len();
+    state.patterns.retain(|_, stats| stats.blacklisted_at.is_none_or(|at| now - at < ttl_secs));
+    if state.patterns.len() != before {
+        save_state(&blacklist_path(&config), &state);
+    }
+
+    let matched = pattern_keys(victim)
+        .into_iter()
+        .find(|key| state.patterns.get(key).is_some_and(|stats| stats.blacklisted_at.is_some()));
+    if matched.is_some() {
+        state.skipped += 1;
+    }
+    matched
+}
+
+// Record how a sandwich attempt against the victim ended. A pattern is blacklisted once it has
+// blacklist_min_attempts attempts and at least blacklist_revert_rate of them reverted or never landed
+pub fn record_attempt(victim: &Transaction, reverted: bool) {
+    let config = load_sandwich_config();
+    if !config["blacklist_enabled"].as_bool().unwrap_or(true) {
+        return;
+    }
+    let min_attempts = config["blacklist_min_attempts"].as_u64().unwrap_or(3);
+    let revert_rate = config["blacklist_revert_rate"].as_f64().unwrap_or(0.8);
+    let now = Utc::now().timestamp();
+
+    let mut state = state().lock().unwrap();
+    for key in pattern_keys(victim) {
+        let stats = state.patterns.entry(key.clone()).or_default();
+        stats.attempts += 1;
+        if reverted {
+            stats.reverts += 1;
+        }
+        stats.last_attempt = now;
+        let blacklist = stats.attempts >= min_attempts && stats.reverts as f64 / stats.attempts as f64 >= revert_rate;
+        if blacklist && stats.blacklisted_at.is_none() {
+            stats.blacklisted_at = Some(now);
+            info!("Blacklisted victim pattern {}: {} of {} sandwich attempts reverted", key, stats.reverts, stats.attempts);
+        }
+    }
+    save_state(&blacklist_path(&config), &state);
+}
+
+// Blacklisted patterns with their attempt history, for the dashboard
+pub fn snapshot() -> Value {
+    let state = state().lock().unwrap();
+    let blacklisted: HashMap<&String, &PatternStats> = state.patterns.iter().filter(|(_, stats)| stats.blacklisted_at.is_some()).collect();
+    json!({
+        "blacklisted": blacklisted,
+        "tracked_patterns": state.patterns.len(),
+        "skipped_since_start": state.skipped,
+    })
+}
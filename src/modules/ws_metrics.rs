@@ -0,0 +1,164 @@
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use web3::futures::StreamExt;
+use web3::transports::WebSocket;
+use log::{error, info, warn};
+use tokio::time::{sleep, timeout, Duration};
+
+use crate::modules::alerts::{self, Severity};
+use crate::modules::providers;
+use crate::modules::templates;
+
+// Window used for the events/sec rate
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+// Load the monitoring configuration (websocket thresholds live there)
+fn load_monitoring_config() -> Value {
+    let config_path = "config/monitoring_config.json";
+    let config_data = fs::read_to_string(config_path)
+        .expect("Unable to read monitoring config file");
+    serde_json::from_str(&config_data).expect("Unable to parse monitoring config file")
+}
+
+// Live metrics for one eth_subscribe stream
+#[derive(Default)]
+struct SubscriptionMetrics {
+    total_events: u64,
+    recent_events: VecDeque<Instant>,
+    last_event: Option<Instant>,
+    last_lag_ms: Option<i64>,
+    max_lag_ms: i64,
+    reconnects: u64,
+}
+
+fn registry() -> &'static Mutex<HashMap<String, SubscriptionMetrics>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, SubscriptionMetrics>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Record an event on a stream, with the event's chain timestamp when it has one
+pub fn record_event(stream: &str, chain_timestamp: Option<u64>) {
+    let now = Instant::now();
+    let mut metrics = registry().lock().unwrap();
+    let entry = metrics.entry(stream.to_string()).or_default();
+
+    entry.total_events += 1;
+    entry.last_event = Some(now);
+    entry.recent_events.push_back(now);
+    while entry.recent_events.front().map(|seen| now.duration_since(*seen) > RATE_WINDOW).unwrap_or(false) {
+        entry.recent_events.pop_front();
+    }
+
+    if let Some(chain_timestamp) = chain_timestamp {
+        let lag_ms = unix_millis() - (chain_timestamp as i64 * 1000);
+        entry.last_lag_ms = Some(lag_ms);
+        entry.max_lag_ms = entry.max_lag_ms.max(lag_ms);
+    }
+}
+
+// Count a forced or unexpected reconnect
+pub fn record_reconnect(stream: &str) {
+    let mut metrics = registry().lock().unwrap();
+    metrics.entry(stream.to_string()).or_default().reconnects += 1;
+}
+
+// Snapshot of all stream metrics for the dashboard
+pub fn snapshot() -> Value {
+    let metrics = registry().lock().unwrap();
+    let streams: HashMap<&String, Value> = metrics
+        .iter()
+        .map(|(stream, entry)| {
+            let events_per_sec = entry.recent_events.len() as f64 / RATE_WINDOW.as_secs_f64();
+            (stream, json!({
+                "total_events": entry.total_events,
+                "events_per_sec": events_per_sec,
+                "secs_since_last_event": entry.last_event.map(|seen| seen.elapsed().as_secs_f64()),
+                "last_lag_ms": entry.last_lag_ms,
+                "max_lag_ms": entry.max_lag_ms,
+                "reconnects": entry.reconnects,
+            }))
+        })
+        .collect();
+    json!(streams)
+}
+
+// Seconds since the stream last produced an event
+pub fn silence_secs(stream: &str) -> Option<f64> {
+    let metrics = registry().lock().unwrap();
+    metrics.get(stream).and_then(|entry| entry.last_event).map(|seen| seen.elapsed().as_secs_f64())
+}
+
+fn unix_millis() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+// Alert operators that a stream is lagging and is being reconnected
+fn alert_lag(stream: &str, reason: &str) {
+    warn!("WebSocket stream {} unhealthy: {}, forcing reconnect", stream, reason);
+    let (subject, body) = templates::render("ws_lag", &[("stream", stream.to_string()), ("reason", reason.to_string())]);
+    // Keyed per stream so a reconnect loop sends one notification per dedup window
+    alerts::raise(&format!("ws_lag:{}", stream), Severity::Warn, &subject, &body);
+}
+
+// Watch newHeads: measure block-timestamp lag and force a reconnect when it exceeds the threshold
+pub async fn run_new_heads_watchdog() {
+    let config = load_monitoring_config();
+    let configured_url = config["websocket_url"].as_str().expect("WebSocket URL not found").to_string();
+    let max_lag_ms = config["ws_max_lag_secs"].as_u64().unwrap_or(6) as i64 * 1000;
+    let max_silence = Duration::from_secs(config["ws_max_silence_secs"].as_u64().unwrap_or(30));
+    let stream_name = "new_heads";
+
+    loop {
+        let websocket_url = providers::websocket_url_for(stream_name, &configured_url);
+        let transport = match WebSocket::new(&websocket_url).await {
+            Ok(transport) => transport,
+            Err(e) => {
+                error!("Failed to connect to {}: {}", websocket_url, e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        let web3 = web3::Web3::new(transport);
+
+        let mut heads = match web3.eth_subscribe().subscribe_new_heads().await {
+            Ok(heads) => heads,
+            Err(e) => {
+                error!("Failed to subscribe to newHeads: {}", e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        info!("Subscribed to newHeads for lag monitoring");
+
+        loop {
+            match timeout(max_silence, heads.next()).await {
+                Ok(Some(Ok(header))) => {
+                    record_event(stream_name, Some(header.timestamp.as_u64()));
+                    let lag_ms = unix_millis() - header.timestamp.as_u64() as i64 * 1000;
+                    if lag_ms > max_lag_ms {
+                        alert_lag(stream_name, &format!("block {:?} arrived {}ms after its timestamp", header.number, lag_ms));
+                        break;
+                    }
+                }
+                Ok(Some(Err(e))) => {
+                    error!("newHeads subscription error: {}", e);
+                    break;
+                }
+                Ok(None) => {
+                    warn!("newHeads subscription closed by the node");
+                    break;
+                }
+                Err(_) => {
+                    alert_lag(stream_name, &format!("no new head for {}s", max_silence.as_secs()));
+                    break;
+                }
+            }
+        }
+
+        record_reconnect(stream_name);
+        sleep(Duration::from_secs(1)).await;
+    }
+}